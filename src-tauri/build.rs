@@ -1,3 +1,14 @@
 fn main() {
+    // Build date for the About box; reproducible builds set SOURCE_DATE_EPOCH
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH").unwrap_or_else(|_| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs().to_string())
+            .unwrap_or_default()
+    });
+    println!("cargo:rustc-env=GPTBAR_BUILD_TIMESTAMP={}", timestamp);
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    #[cfg(feature = "desktop")]
     tauri_build::build()
 }
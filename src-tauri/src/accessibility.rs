@@ -0,0 +1,256 @@
+//! Accessibility preferences read from the OS
+//!
+//! The webview can't always see the system's reduce motion, high contrast
+//! and text size settings (`prefers-reduced-motion` is missing on some
+//! Linux webviews, and Windows text scaling never reaches CSS), so they are
+//! read here and handed to the frontend as [`UiPreferences`].
+//!
+//! Each setting falls back to its default when it can't be read.
+
+use serde::Serialize;
+
+/// Accessibility settings the UI should follow
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct UiPreferences {
+    /// Animations should be turned off
+    pub reduce_motion: bool,
+    /// Colors should be replaced by a higher contrast palette
+    pub high_contrast: bool,
+    /// Text size relative to the default, `1.0` when unchanged
+    pub font_scale: f64,
+}
+
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self {
+            reduce_motion: false,
+            high_contrast: false,
+            font_scale: 1.0,
+        }
+    }
+}
+
+impl UiPreferences {
+    /// Reads the preferences of the current user
+    ///
+    /// Runs helper programs on Linux and macOS, so call it off the async
+    /// runtime.
+    pub fn detect() -> Self {
+        let defaults = Self::default();
+        Self {
+            reduce_motion: detect_reduce_motion().unwrap_or(defaults.reduce_motion),
+            high_contrast: detect_high_contrast().unwrap_or(defaults.high_contrast),
+            font_scale: detect_font_scale().unwrap_or(defaults.font_scale),
+        }
+    }
+}
+
+/// Parses a boolean as printed by `gsettings` (`true`) or `defaults` (`1`)
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "macos", target_os = "windows")),
+    allow(dead_code)
+)]
+fn parse_bool(output: &str) -> Option<bool> {
+    match output.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses a text scale, ignoring values no UI could be laid out at
+#[cfg_attr(not(any(target_os = "linux", target_os = "windows")), allow(dead_code))]
+fn parse_scale(value: f64) -> Option<f64> {
+    (0.5..=4.0).contains(&value).then_some(value)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn read_command(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+fn gsettings(schema: &str, key: &str) -> Option<String> {
+    read_command("gsettings", &["get", schema, key])
+}
+
+#[cfg(target_os = "linux")]
+fn detect_reduce_motion() -> Option<bool> {
+    parse_bool(&gsettings(
+        "org.gnome.desktop.interface",
+        "enable-animations",
+    )?)
+    .map(|on| !on)
+}
+
+#[cfg(target_os = "linux")]
+fn detect_high_contrast() -> Option<bool> {
+    parse_bool(&gsettings(
+        "org.gnome.desktop.a11y.interface",
+        "high-contrast",
+    )?)
+}
+
+#[cfg(target_os = "linux")]
+fn detect_font_scale() -> Option<f64> {
+    gsettings("org.gnome.desktop.interface", "text-scaling-factor")?
+        .trim()
+        .parse()
+        .ok()
+        .and_then(parse_scale)
+}
+
+#[cfg(target_os = "macos")]
+fn universal_access(key: &str) -> Option<bool> {
+    parse_bool(&read_command(
+        "defaults",
+        &["read", "com.apple.universalaccess", key],
+    )?)
+}
+
+#[cfg(target_os = "macos")]
+fn detect_reduce_motion() -> Option<bool> {
+    universal_access("reduceMotion")
+}
+
+#[cfg(target_os = "macos")]
+fn detect_high_contrast() -> Option<bool> {
+    universal_access("increaseContrast")
+}
+
+/// macOS has no system-wide text size the webview doesn't already follow
+#[cfg(target_os = "macos")]
+fn detect_font_scale() -> Option<f64> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn registry_string(key: windows::core::PCWSTR, value: windows::core::PCWSTR) -> Option<String> {
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ};
+
+    let mut buffer = [0u16; 64];
+    let mut size = std::mem::size_of_val(&buffer) as u32;
+
+    // SAFETY: buffer and size describe a valid, correctly sized UTF-16 buffer
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr() as *mut _),
+            Some(&mut size),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+#[cfg(target_os = "windows")]
+fn registry_dword(key: windows::core::PCWSTR, value: windows::core::PCWSTR) -> Option<u32> {
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let mut data: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+
+    // SAFETY: data and size point to a valid, correctly sized DWORD buffer
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key,
+            value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut data as *mut u32 as *mut _),
+            Some(&mut size),
+        )
+    };
+    (status == ERROR_SUCCESS).then_some(data)
+}
+
+/// Window animations off in the Performance Options
+#[cfg(target_os = "windows")]
+fn detect_reduce_motion() -> Option<bool> {
+    use windows::core::w;
+
+    parse_bool(&registry_string(
+        w!("Control Panel\\Desktop\\WindowMetrics"),
+        w!("MinAnimate"),
+    )?)
+    .map(|on| !on)
+}
+
+/// The `HCF_HIGHCONTRASTON` bit of the high contrast flags
+#[cfg(target_os = "windows")]
+fn detect_high_contrast() -> Option<bool> {
+    use windows::core::w;
+
+    let flags: u32 = registry_string(
+        w!("Control Panel\\Accessibility\\HighContrast"),
+        w!("Flags"),
+    )?
+    .trim()
+    .parse()
+    .ok()?;
+    Some(flags & 1 != 0)
+}
+
+/// "Make text bigger", saved as a percentage
+#[cfg(target_os = "windows")]
+fn detect_font_scale() -> Option<f64> {
+    use windows::core::w;
+
+    let percent = registry_dword(
+        w!("Software\\Microsoft\\Accessibility"),
+        w!("TextScaleFactor"),
+    )?;
+    parse_scale(f64::from(percent) / 100.0)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn detect_reduce_motion() -> Option<bool> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn detect_high_contrast() -> Option<bool> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn detect_font_scale() -> Option<f64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("true\n"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("The domain/default pair does not exist"), None);
+    }
+
+    #[test]
+    fn test_parse_scale() {
+        assert_eq!(parse_scale(1.25), Some(1.25));
+        assert_eq!(parse_scale(0.0), None);
+        assert_eq!(parse_scale(100.0), None);
+    }
+}
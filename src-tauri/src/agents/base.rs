@@ -2,8 +2,12 @@
 //!
 //! Defines the Agent trait that all background agents must implement.
 
+use std::sync::Mutex;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 
 /// Errors that can occur in agents
 #[derive(Debug, Error)]
@@ -27,6 +31,10 @@ pub enum AgentError {
     /// Internal error
     #[error("Internal error: {0}")]
     Internal(String),
+
+    /// Agent settings were rejected
+    #[error("Invalid agent config: {0}")]
+    InvalidConfig(String),
 }
 
 /// Status of an agent
@@ -59,6 +67,37 @@ impl AgentStatus {
     }
 }
 
+/// Cancellation signal that can be renewed, so a stopped agent can be
+/// started again
+#[derive(Debug, Default)]
+pub struct StopSignal {
+    token: Mutex<CancellationToken>,
+}
+
+impl StopSignal {
+    /// Creates a signal that hasn't been raised
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token for a new run, replacing one cancelled by a previous stop
+    pub fn renew(&self) -> CancellationToken {
+        let mut token = self.token.lock().unwrap_or_else(|e| e.into_inner());
+        if token.is_cancelled() {
+            *token = CancellationToken::new();
+        }
+        token.clone()
+    }
+
+    /// Cancels the current run
+    pub fn cancel(&self) {
+        self.token
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cancel();
+    }
+}
+
 /// Trait for background agents
 ///
 /// Agents are long-running background tasks that can be started and stopped.
@@ -75,6 +114,16 @@ pub trait Agent: Send + Sync {
     /// Returns the current status of the agent
     fn status(&self) -> AgentStatus;
 
+    /// IDs of the agents this one needs; `AgentManager::start_all` starts it
+    /// once they are ready
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Resolves once the agent has started and agents depending on it can
+    /// start too
+    async fn ready(&self) {}
+
     /// Starts the agent
     ///
     /// This should run the agent's main loop until cancelled.
@@ -92,6 +141,30 @@ pub trait Agent: Send + Sync {
     async fn trigger(&self) -> Result<(), AgentError> {
         Ok(()) // Default: no-op
     }
+
+    /// Returns when the agent next does its periodic work, if it has a schedule
+    fn next_run(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    /// Current settings, in the form [`Agent::configure`] takes, if the agent
+    /// has any
+    fn settings(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Changes the agent's settings while it runs
+    ///
+    /// `config` is a JSON object with the fields to change; fields left out
+    /// keep their value. Each agent parses it into its own settings type and
+    /// rejects unknown fields.
+    async fn configure(&self, config: serde_json::Value) -> Result<(), AgentError> {
+        let _ = config;
+        Err(AgentError::InvalidConfig(format!(
+            "{} has no settings",
+            self.name()
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -122,6 +195,19 @@ mod tests {
         assert!(!AgentStatus::Stopped.has_error());
     }
 
+    #[test]
+    fn test_stop_signal_renews_after_cancel() {
+        let signal = StopSignal::new();
+        let first = signal.renew();
+        signal.cancel();
+        assert!(first.is_cancelled());
+
+        let second = signal.renew();
+        assert!(!second.is_cancelled());
+        signal.cancel();
+        assert!(second.is_cancelled());
+    }
+
     #[test]
     fn test_agent_status_clone() {
         let status = AgentStatus::Error("error message".into());
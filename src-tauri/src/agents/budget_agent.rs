@@ -0,0 +1,183 @@
+//! Budget agent - Announces new billing periods
+//!
+//! Checks every minute whether a provider's budget period rolled over. On a
+//! rollover it publishes [`AppEvent::BudgetPeriodStarted`] and refreshes,
+//! so spend gauges start again from the new period.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use super::refresh_agent::RefreshAgent;
+use crate::budget::{BudgetPeriod, BudgetRollover, BudgetTracker};
+use crate::events::{self, AppEvent};
+
+/// How often the current periods are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Agent that detects budget period rollovers
+pub struct BudgetAgent {
+    refresh: Arc<RefreshAgent>,
+    periods: HashMap<String, BudgetPeriod>,
+    tracker: BudgetTracker,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl BudgetAgent {
+    /// Creates a budget agent that refreshes through `refresh` on rollovers
+    pub fn new(refresh: Arc<RefreshAgent>) -> Self {
+        Self {
+            refresh,
+            periods: HashMap::new(),
+            tracker: BudgetTracker::new(),
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    /// Sets the providers to watch and their budget periods
+    pub fn with_periods(mut self, periods: HashMap<String, BudgetPeriod>) -> Self {
+        self.periods = periods;
+        self
+    }
+
+    /// Providers whose period rolled over since the last check
+    ///
+    /// The first check only records the current periods.
+    fn check(&self, now: DateTime<Utc>) -> Vec<BudgetRollover> {
+        let mut rollovers: Vec<_> = self
+            .periods
+            .iter()
+            .filter_map(|(id, period)| self.tracker.observe(id, *period, now))
+            .collect();
+        rollovers.sort_by(|a, b| a.provider_id.cmp(&b.provider_id));
+        rollovers
+    }
+}
+
+#[async_trait]
+impl Agent for BudgetAgent {
+    fn id(&self) -> &'static str {
+        "budget"
+    }
+
+    fn name(&self) -> &'static str {
+        "Budget Periods"
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["refresh"]
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        let cancel_token = self.cancel_token.renew();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let rollovers = self.check(Utc::now());
+                    if rollovers.is_empty() {
+                        continue;
+                    }
+                    for rollover in rollovers {
+                        tracing::info!(
+                            "New budget period for {} until {}",
+                            rollover.provider_id,
+                            rollover.end
+                        );
+                        events::publish(AppEvent::BudgetPeriodStarted(rollover));
+                    }
+                    if let Err(e) = self.refresh.trigger().await {
+                        tracing::warn!("Refresh after budget rollover failed: {}", e);
+                    }
+                }
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn agent() -> BudgetAgent {
+        BudgetAgent::new(Arc::new(RefreshAgent::new())).with_periods(HashMap::from([
+            (
+                "claude".to_string(),
+                BudgetPeriod::BillingAnchor { day: 10 },
+            ),
+            ("openai".to_string(), BudgetPeriod::CalendarMonth),
+        ]))
+    }
+
+    #[test]
+    fn test_check_reports_rollovers() {
+        let agent = agent();
+        let at = |month, day| Utc.with_ymd_and_hms(2026, month, day, 12, 0, 0).unwrap();
+
+        assert!(agent.check(at(3, 5)).is_empty());
+        assert!(agent.check(at(3, 9)).is_empty());
+
+        let rollovers = agent.check(at(3, 10));
+        assert_eq!(rollovers.len(), 1);
+        assert_eq!(rollovers[0].provider_id, "claude");
+
+        let ids: Vec<_> = agent
+            .check(at(4, 10))
+            .into_iter()
+            .map(|r| r.provider_id)
+            .collect();
+        assert_eq!(ids, vec!["claude", "openai"]);
+    }
+
+    #[tokio::test]
+    async fn test_start_and_stop() {
+        let agent = Arc::new(agent());
+        let runner = agent.clone();
+        let task = tokio::spawn(async move { runner.start().await });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(agent.status().is_running());
+        assert!(matches!(
+            agent.start().await,
+            Err(AgentError::AlreadyRunning)
+        ));
+
+        agent.stop().await.unwrap();
+        task.await.unwrap().unwrap();
+        assert!(agent.status().is_stopped());
+    }
+}
@@ -0,0 +1,193 @@
+//! Currency agent - Keeps the display currency's exchange rate current
+//!
+//! Costs are reported in USD. When a display currency is configured without
+//! a fixed rate, this agent fetches the European Central Bank's daily
+//! reference rates and sets the rate used by [`format::format_cost`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use tokio::sync::RwLock;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use super::refresh_agent::RefreshAgent;
+use crate::format::{self, Currency};
+
+/// ECB euro foreign exchange reference rates, updated once a working day
+const ECB_RATES_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+/// Delay between fetches after a successful one
+const FETCH_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Delay before trying again after a failed fetch
+const RETRY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Agent that fetches the exchange rate of the display currency
+pub struct CurrencyAgent {
+    code: String,
+    client: Client,
+    refresh: Arc<RefreshAgent>,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl CurrencyAgent {
+    /// Creates an agent for a display currency, refreshing through
+    /// `refresh` once costs can be shown in it
+    pub fn new(code: impl Into<String>, refresh: Arc<RefreshAgent>) -> Self {
+        Self {
+            code: code.into().to_uppercase(),
+            client: Client::new(),
+            refresh,
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    /// Fetches the current units of the display currency per US dollar
+    async fn fetch_rate(&self) -> Result<f64, String> {
+        let response = self
+            .client
+            .get(ECB_RATES_URL)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let xml = response.text().await.map_err(|e| e.to_string())?;
+        rate_per_usd(&parse_ecb_rates(&xml), &self.code)
+            .ok_or_else(|| format!("No ECB rate for {}", self.code))
+    }
+}
+
+/// Euro rates by currency code from the ECB's daily XML
+fn parse_ecb_rates(xml: &str) -> HashMap<String, f64> {
+    static CUBE: OnceLock<Regex> = OnceLock::new();
+    let cube = CUBE.get_or_init(|| {
+        Regex::new(r#"currency=['"]([A-Z]{3})['"]\s+rate=['"]([0-9.]+)['"]"#)
+            .expect("valid ECB rate pattern")
+    });
+    cube.captures_iter(xml)
+        .filter_map(|c| Some((c[1].to_string(), c[2].parse().ok()?)))
+        .collect()
+}
+
+/// Units of `code` per US dollar, from rates quoted per euro
+fn rate_per_usd(euro_rates: &HashMap<String, f64>, code: &str) -> Option<f64> {
+    let usd = euro_rates.get("USD").copied().filter(|rate| *rate > 0.0)?;
+    let per_euro = if code == "EUR" {
+        1.0
+    } else {
+        *euro_rates.get(code)?
+    };
+    Some(per_euro / usd)
+}
+
+#[async_trait]
+impl Agent for CurrencyAgent {
+    fn id(&self) -> &'static str {
+        "currency"
+    }
+
+    fn name(&self) -> &'static str {
+        "Exchange Rates"
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["refresh"]
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        let cancel_token = self.cancel_token.renew();
+        let mut delay = Duration::ZERO;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {
+                    match self.fetch_rate().await {
+                        Ok(rate) => {
+                            let first = format::currency().code != self.code;
+                            tracing::info!("1 USD = {:.4} {}", rate, self.code);
+                            format::set_currency(Currency::new(self.code.as_str(), rate));
+                            // Spend shown so far was formatted in USD
+                            if first {
+                                if let Err(e) = self.refresh.trigger().await {
+                                    tracing::warn!("Refresh after rate update failed: {}", e);
+                                }
+                            }
+                            delay = FETCH_INTERVAL;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to fetch {} exchange rate: {}", self.code, e);
+                            delay = RETRY_INTERVAL;
+                        }
+                    }
+                }
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01">
+    <Cube>
+        <Cube time='2026-10-15'>
+            <Cube currency='USD' rate='1.2500'/>
+            <Cube currency='JPY' rate='162.50'/>
+            <Cube currency='GBP' rate='0.8750'/>
+        </Cube>
+    </Cube>
+</gesmes:Envelope>"#;
+
+    #[test]
+    fn test_parse_ecb_rates() {
+        let rates = parse_ecb_rates(RATES);
+        assert_eq!(rates.len(), 3);
+        assert_eq!(rates["USD"], 1.25);
+        assert_eq!(rates["JPY"], 162.5);
+    }
+
+    #[test]
+    fn test_rate_per_usd() {
+        let rates = parse_ecb_rates(RATES);
+        assert_eq!(rate_per_usd(&rates, "EUR"), Some(0.8));
+        assert_eq!(rate_per_usd(&rates, "JPY"), Some(130.0));
+        assert_eq!(rate_per_usd(&rates, "GBP"), Some(0.7));
+        assert_eq!(rate_per_usd(&rates, "CHF"), None);
+        assert_eq!(rate_per_usd(&HashMap::new(), "EUR"), None);
+    }
+}
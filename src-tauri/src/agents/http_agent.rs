@@ -0,0 +1,464 @@
+//! HTTP agent - Opt-in REST API for dashboards
+//!
+//! Serves the same data as the Tauri commands over HTTP so browser or
+//! remote dashboards can read usage. Binds to localhost by default and every
+//! request must carry `Authorization: Bearer <token>`; the token is
+//! generated on first start and kept in the `SecureStore`.
+//!
+//! - `GET /providers` lists provider metadata
+//! - `GET /usage` returns `{ "providers": { id: snapshot }, "aggregate": snapshot | null }`
+//! - `GET /usage/{id}` returns one provider's snapshot (or `aggregate`)
+//! - `GET /history?provider=<id>&hours=<n>` returns recorded snapshots (default 24h,
+//!   at most the 90-day retention)
+//! - `POST /refresh` refreshes all providers now
+//! - `GET /metrics` returns refresh metrics in the Prometheus text format
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use super::ipc_agent::UsageReport;
+use super::maintenance_agent::DEFAULT_RETENTION_DAYS;
+use super::refresh_agent::{FetchMetrics, RefreshAgent};
+use crate::auth::SecureStore;
+use crate::config::ApiServerSettings;
+use crate::history::{HistoryError, HistoryStore};
+use crate::providers::ProviderMetadata;
+
+/// SecureStore key for the API bearer token
+pub const API_TOKEN_KEY: &str = "api-token";
+
+/// History window when `hours` is not given
+const DEFAULT_HISTORY_HOURS: i64 = 24;
+
+/// Longest history window a request may ask for, matching the default
+/// retention
+const MAX_HISTORY_HOURS: i64 = DEFAULT_RETENTION_DAYS as i64 * 24;
+
+/// Shared state for request handlers
+#[derive(Clone)]
+pub struct ApiState {
+    refresh: Arc<RefreshAgent>,
+    history: Arc<HistoryStore>,
+    providers: Arc<Vec<ProviderMetadata>>,
+    token: Arc<String>,
+}
+
+impl ApiState {
+    /// Creates handler state that accepts the given bearer token
+    pub fn new(
+        refresh: Arc<RefreshAgent>,
+        history: Arc<HistoryStore>,
+        providers: Vec<ProviderMetadata>,
+        token: String,
+    ) -> Self {
+        Self {
+            refresh,
+            history,
+            providers: Arc::new(providers),
+            token: Arc::new(token),
+        }
+    }
+}
+
+/// Returns the stored API token, generating and saving one if none exists
+pub fn api_token(store: &SecureStore) -> Result<String, String> {
+    if let Some(token) = store.get_token(API_TOKEN_KEY).map_err(|e| e.to_string())? {
+        return Ok(token);
+    }
+
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    store
+        .set_token(API_TOKEN_KEY, &token)
+        .map_err(|e| e.to_string())?;
+    Ok(token)
+}
+
+/// Builds the API router
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/providers", get(providers))
+        .route("/usage", get(usage))
+        .route("/usage/{id}", get(provider_usage))
+        .route("/history", get(history))
+        .route("/refresh", post(refresh))
+        .route("/metrics", get(metrics))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// Rejects requests without the bearer token
+async fn authorize(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), state.token.as_bytes()));
+
+    if authorized {
+        next.run(request).await
+    } else {
+        error(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token")
+    }
+}
+
+/// Compares without short-circuiting so timing doesn't leak the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+async fn providers(State(state): State<ApiState>) -> Json<Vec<ProviderMetadata>> {
+    Json(state.providers.as_ref().clone())
+}
+
+async fn usage(State(state): State<ApiState>) -> Json<UsageReport> {
+    Json(UsageReport::collect(&state.refresh).await)
+}
+
+async fn provider_usage(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    match UsageReport::collect(&state.refresh).await.get(&id) {
+        Some(snapshot) => Json(snapshot).into_response(),
+        None => error(
+            StatusCode::NOT_FOUND,
+            &format!("No usage data for {}", id),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    provider: Option<String>,
+    hours: Option<i64>,
+}
+
+async fn history(State(state): State<ApiState>, Query(query): Query<HistoryQuery>) -> Response {
+    let hours = query
+        .hours
+        .unwrap_or(DEFAULT_HISTORY_HOURS)
+        .clamp(0, MAX_HISTORY_HOURS);
+    match state.history.recent(query.provider.as_deref(), hours) {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e @ HistoryError::InvalidRange) => error(StatusCode::BAD_REQUEST, &e.to_string()),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+async fn refresh(State(state): State<ApiState>) -> Response {
+    match state.refresh.trigger().await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+async fn metrics(State(state): State<ApiState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        prometheus_metrics(&state.refresh.metrics().await),
+    )
+        .into_response()
+}
+
+/// Renders refresh metrics in the Prometheus text exposition format
+fn prometheus_metrics(metrics: &HashMap<String, FetchMetrics>) -> String {
+    let mut ids: Vec<&String> = metrics.keys().collect();
+    ids.sort();
+
+    type Value = fn(&FetchMetrics) -> Option<f64>;
+    let families: [(&str, &str, &str, Value); 5] = [
+        (
+            "gptbar_fetch_success_total",
+            "counter",
+            "Successful usage fetches",
+            |m| Some(m.successes as f64),
+        ),
+        (
+            "gptbar_fetch_errors_total",
+            "counter",
+            "Failed usage fetches",
+            |m| Some(m.errors as f64),
+        ),
+        (
+            "gptbar_fetch_duration_seconds_total",
+            "counter",
+            "Time spent fetching usage",
+            |m| Some(m.total_duration_ms as f64 / 1000.0),
+        ),
+        (
+            "gptbar_fetch_last_duration_seconds",
+            "gauge",
+            "Duration of the last usage fetch",
+            |m| m.last_duration_ms.map(|ms| ms as f64 / 1000.0),
+        ),
+        (
+            "gptbar_fetch_last_http_status",
+            "gauge",
+            "HTTP status of the last failed fetch",
+            |m| m.last_http_status.map(f64::from),
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, kind, help, value) in families {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for id in &ids {
+            if let Some(value) = value(&metrics[*id]) {
+                let _ = writeln!(out, "{}{{provider=\"{}\"}} {}", name, id, value);
+            }
+        }
+    }
+    out
+}
+
+/// Agent that runs the REST API server
+pub struct HttpAgent {
+    settings: ApiServerSettings,
+    state: ApiState,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl HttpAgent {
+    /// Creates an HTTP agent with the given listen settings and handler state
+    pub fn new(settings: ApiServerSettings, state: ApiState) -> Self {
+        Self {
+            settings,
+            state,
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    async fn serve(&self) -> std::io::Result<()> {
+        let addr = SocketAddr::new(self.settings.bind_address, self.settings.port);
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        tracing::info!("REST API listening on http://{}", listener.local_addr()?);
+
+        let cancel_token = self.cancel_token.renew();
+        axum::serve(listener, router(self.state.clone()))
+            .with_graceful_shutdown(async move { cancel_token.cancelled().await })
+            .await
+    }
+}
+
+#[async_trait]
+impl Agent for HttpAgent {
+    fn id(&self) -> &'static str {
+        "http"
+    }
+
+    fn name(&self) -> &'static str {
+        "REST API Server"
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["refresh"]
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        match self.serve().await {
+            Ok(()) => {
+                *self.status.write().await = AgentStatus::Stopped;
+                Ok(())
+            }
+            Err(e) => {
+                *self.status.write().await = AgentStatus::Error(e.to_string());
+                Err(AgentError::OperationFailed(e.to_string()))
+            }
+        }
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::UsageSnapshot;
+
+    const TOKEN: &str = "test-token";
+
+    async fn spawn_server() -> (String, Arc<HistoryStore>) {
+        let history = Arc::new(HistoryStore::open_in_memory().unwrap());
+        let state = ApiState::new(
+            Arc::new(RefreshAgent::new()),
+            history.clone(),
+            Vec::new(),
+            TOKEN.to_string(),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        (url, history)
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret!"));
+    }
+
+    #[tokio::test]
+    async fn test_requires_bearer_token() {
+        let (url, _) = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/usage", url)).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+
+        let response = client
+            .get(format!("{}/usage", url))
+            .bearer_auth("wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn test_usage_endpoints() {
+        let (url, _) = spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("{}/usage", url))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let report: UsageReport = response.json().await.unwrap();
+        assert!(report.providers.is_empty());
+
+        let response = client
+            .get(format!("{}/usage/claude", url))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+
+        let response = client
+            .post(format!("{}/refresh", url))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 204);
+    }
+
+    #[test]
+    fn test_prometheus_metrics() {
+        let metrics = HashMap::from([(
+            "claude".to_string(),
+            FetchMetrics {
+                successes: 3,
+                errors: 1,
+                last_duration_ms: Some(250),
+                total_duration_ms: 1_000,
+                last_http_status: Some(429),
+                ..Default::default()
+            },
+        )]);
+
+        let text = prometheus_metrics(&metrics);
+        assert!(text.contains("# TYPE gptbar_fetch_success_total counter\n"));
+        assert!(text.contains("gptbar_fetch_success_total{provider=\"claude\"} 3\n"));
+        assert!(text.contains("gptbar_fetch_errors_total{provider=\"claude\"} 1\n"));
+        assert!(text.contains("gptbar_fetch_duration_seconds_total{provider=\"claude\"} 1\n"));
+        assert!(text.contains("gptbar_fetch_last_duration_seconds{provider=\"claude\"} 0.25\n"));
+        assert!(text.contains("gptbar_fetch_last_http_status{provider=\"claude\"} 429\n"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let (url, _) = spawn_server().await;
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/metrics", url))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response
+            .text()
+            .await
+            .unwrap()
+            .contains("# TYPE gptbar_fetch_errors_total counter"));
+    }
+
+    #[tokio::test]
+    async fn test_history_endpoint() {
+        let (url, history) = spawn_server().await;
+        history.record("claude", &UsageSnapshot::new()).unwrap();
+        history.record("openai", &UsageSnapshot::new()).unwrap();
+
+        let entries: Vec<serde_json::Value> = reqwest::Client::new()
+            .get(format!("{}/history?provider=claude", url))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["provider_id"], "claude");
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/history?hours={}", url, i64::MAX))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+}
@@ -0,0 +1,392 @@
+//! IPC agent - Serves current usage to external tools over a local socket
+//!
+//! Listens on a unix socket (named pipe on Windows) so shell prompts, status
+//! bars and editor statuslines can read quota without running their own
+//! pollers. The protocol is line based: each request line gets exactly one
+//! JSON response line.
+//!
+//! - `GET usage` returns `{ "providers": { id: snapshot }, "aggregate": snapshot | null }`
+//! - `GET usage/<id>` returns the snapshot for one provider (or `aggregate`)
+//! - `OPEN <url>` opens a [`gptbar://` link](crate::deep_link) in the app and
+//!   returns `{ "ok": true }`
+//! - Anything else returns `{ "error": message }`
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use super::refresh_agent::RefreshAgent;
+use crate::config::AppConfig;
+use crate::deep_link::DeepLink;
+use crate::events::{self, AppEvent};
+use crate::providers::{UsageSnapshot, AGGREGATE_ID};
+
+/// Response to `GET usage`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UsageReport {
+    /// Latest snapshot per provider
+    pub providers: HashMap<String, UsageSnapshot>,
+    /// Aggregate across enabled providers
+    pub aggregate: Option<UsageSnapshot>,
+}
+
+impl UsageReport {
+    /// Collects the latest snapshots and their aggregate from the refresh agent
+    pub async fn collect(refresh: &RefreshAgent) -> Self {
+        let providers = refresh.get_all_snapshots().await;
+        let config = AppConfig::load();
+        let aggregate = config
+            .usage_aggregate()
+            .snapshot(&providers, &config.active_providers());
+        Self {
+            providers,
+            aggregate,
+        }
+    }
+
+    /// Returns the snapshot for a provider ID, or the aggregate for `aggregate`
+    pub fn get(&self, id: &str) -> Option<UsageSnapshot> {
+        if id == AGGREGATE_ID {
+            self.aggregate.clone()
+        } else {
+            self.providers.get(id).cloned()
+        }
+    }
+}
+
+/// Returns the default socket path (pipe name on Windows)
+///
+/// The socket is in a directory only the user can use: the runtime dir, the
+/// per-user temp dir on macOS, or else the config folder, since `/tmp` is
+/// shared between users on Linux.
+pub fn default_socket_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        PathBuf::from(r"\\.\pipe\gptbar")
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::env::var("XDG_RUNTIME_DIR")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                if cfg!(target_os = "macos") {
+                    Some(std::env::temp_dir())
+                } else {
+                    AppConfig::config_dir()
+                }
+            })
+            .unwrap_or_else(std::env::temp_dir)
+            .join("gptbar.sock")
+    }
+}
+
+/// Agent that answers usage queries from other local processes
+pub struct IpcAgent {
+    refresh: Arc<RefreshAgent>,
+    path: PathBuf,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl IpcAgent {
+    /// Creates an IPC agent serving snapshots from the refresh agent
+    pub fn new(refresh: Arc<RefreshAgent>) -> Self {
+        Self::with_path(refresh, default_socket_path())
+    }
+
+    /// Creates an IPC agent listening on a custom path
+    pub fn with_path(refresh: Arc<RefreshAgent>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            refresh,
+            path: path.into(),
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    /// Returns the path the agent listens on
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Sends a single request to a running IPC server and returns the response
+    pub async fn query(path: &std::path::Path, request: &str) -> std::io::Result<String> {
+        #[cfg(target_os = "windows")]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(path)?;
+
+        #[cfg(not(target_os = "windows"))]
+        let stream = tokio::net::UnixStream::connect(path).await?;
+
+        let (reader, mut writer) = tokio::io::split(stream);
+        writer
+            .write_all(format!("{}\n", request.trim()).as_bytes())
+            .await?;
+
+        let mut response = String::new();
+        BufReader::new(reader).read_line(&mut response).await?;
+        Ok(response.trim_end().to_string())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    async fn listen(&self) -> std::io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A socket that still answers belongs to another running instance;
+        // one left by a previous run would only make bind fail
+        if tokio::net::UnixStream::connect(&self.path).await.is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is served by another instance", self.path.display()),
+            ));
+        }
+        let _ = std::fs::remove_file(&self.path);
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let listener = tokio::net::UnixListener::bind(&self.path)?;
+        std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))?;
+        tracing::info!("IPC server listening on {}", self.path.display());
+
+        let cancel_token = self.cancel_token.renew();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, _) = accepted?;
+                    tokio::spawn(serve_connection(stream, self.refresh.clone()));
+                }
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        let _ = std::fs::remove_file(&self.path);
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    async fn listen(&self) -> std::io::Result<()> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&self.path)?;
+        tracing::info!("IPC server listening on {}", self.path.display());
+
+        let cancel_token = self.cancel_token.renew();
+        loop {
+            tokio::select! {
+                connected = server.connect() => {
+                    connected?;
+                    let client = std::mem::replace(
+                        &mut server,
+                        ServerOptions::new().create(&self.path)?,
+                    );
+                    tokio::spawn(serve_connection(client, self.refresh.clone()));
+                }
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Answers request lines on one connection until the client disconnects
+async fn serve_connection<S>(stream: S, refresh: Arc<RefreshAgent>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = handle_request(&refresh, &line).await;
+        if writer
+            .write_all(format!("{}\n", response).as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Builds the JSON response for a single request line
+pub async fn handle_request(refresh: &RefreshAgent, request: &str) -> String {
+    if let Some(url) = request.trim().strip_prefix("OPEN ") {
+        return match DeepLink::parse(url) {
+            Some(link) => {
+                events::publish(AppEvent::DeepLinkOpened(link));
+                json!({ "ok": true }).to_string()
+            }
+            None => error_response(&format!("Unknown link: {}", url.trim())),
+        };
+    }
+
+    let path = match request.trim().strip_prefix("GET ") {
+        Some(path) => path.trim(),
+        None => return error_response("Unsupported request"),
+    };
+
+    let report = UsageReport::collect(refresh).await;
+
+    let response = match path.strip_prefix("usage") {
+        Some("") => json!(report),
+        Some(rest) if rest.starts_with('/') => {
+            let id = &rest[1..];
+            match report.get(id) {
+                Some(snapshot) => json!(snapshot),
+                None => return error_response(&format!("No usage data for {}", id)),
+            }
+        }
+        _ => return error_response(&format!("Unknown path: {}", path)),
+    };
+
+    response.to_string()
+}
+
+fn error_response(message: &str) -> String {
+    json!({ "error": message }).to_string()
+}
+
+#[async_trait]
+impl Agent for IpcAgent {
+    fn id(&self) -> &'static str {
+        "ipc"
+    }
+
+    fn name(&self) -> &'static str {
+        "IPC Server"
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["refresh"]
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        match self.listen().await {
+            Ok(()) => {
+                *self.status.write().await = AgentStatus::Stopped;
+                Ok(())
+            }
+            Err(e) => {
+                *self.status.write().await = AgentStatus::Error(e.to_string());
+                Err(AgentError::OperationFailed(e.to_string()))
+            }
+        }
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handle_unknown_request() {
+        let refresh = RefreshAgent::new();
+
+        let response = handle_request(&refresh, "POST usage").await;
+        assert!(response.contains("\"error\""));
+
+        let response = handle_request(&refresh, "GET quota").await;
+        assert!(response.contains("Unknown path"));
+
+        let response = handle_request(&refresh, "GET usageclaude").await;
+        assert!(response.contains("Unknown path"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_missing_provider() {
+        let refresh = RefreshAgent::new();
+        let response = handle_request(&refresh, "GET usage/claude").await;
+        assert_eq!(response, r#"{"error":"No usage data for claude"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_handle_open_link() {
+        let refresh = RefreshAgent::new();
+        let mut events = events::subscribe();
+
+        let response = handle_request(&refresh, "OPEN gptbar://provider/ipc-test\n").await;
+        assert_eq!(response, r#"{"ok":true}"#);
+        let response = handle_request(&refresh, "OPEN gptbar://quit").await;
+        assert_eq!(response, r#"{"error":"Unknown link: gptbar://quit"}"#);
+
+        // Other tests publish on the same bus
+        let opened = std::iter::from_fn(|| events.try_recv().ok()).any(|event| {
+            matches!(event, AppEvent::DeepLinkOpened(DeepLink::Provider { provider_id })
+                if provider_id == "ipc-test")
+        });
+        assert!(opened);
+    }
+
+    #[tokio::test]
+    async fn test_handle_all_usage() {
+        let refresh = RefreshAgent::new();
+        let response: serde_json::Value =
+            serde_json::from_str(&handle_request(&refresh, "GET usage\n").await).unwrap();
+        assert!(response["providers"].as_object().unwrap().is_empty());
+        assert!(response["aggregate"].is_null());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_query_over_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gptbar.sock");
+        let agent = Arc::new(IpcAgent::with_path(Arc::new(RefreshAgent::new()), &path));
+
+        let server = agent.clone();
+        let handle = tokio::spawn(async move { server.start().await });
+
+        // Wait for the socket to appear
+        for _ in 0..50 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let response = IpcAgent::query(&path, "GET usage/gemini").await.unwrap();
+        assert!(response.contains("No usage data for gemini"));
+
+        // A second instance leaves the live socket alone
+        let second = IpcAgent::with_path(Arc::new(RefreshAgent::new()), &path);
+        assert!(second.start().await.is_err());
+        assert!(IpcAgent::query(&path, "GET usage").await.is_ok());
+
+        agent.stop().await.unwrap();
+        handle.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,312 @@
+//! Maintenance agent - Keeps stored data from growing without bound
+//!
+//! Runs every night at [`RUN_AT_HOUR`] local time. It deletes history older
+//! than the retention period and vacuums the database so the file shrinks.
+//! It also deletes cookie database copies that a crashed run left behind. A
+//! trigger runs it right away.
+//!
+//! Logs need no rotation: they only go to stdout and the bounded buffer in
+//! [`crate::diagnostics`], never to a file.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use crate::auth::CookieExtractor;
+use crate::history::{HistoryError, HistoryStore};
+
+/// Local hour the nightly run starts at
+pub const RUN_AT_HOUR: u32 = 3;
+
+/// Days of history kept unless configured otherwise
+pub const DEFAULT_RETENTION_DAYS: u32 = 90;
+
+/// Longest retention [`Agent::configure`] accepts, ten years
+const MAX_RETENTION_DAYS: u32 = 3650;
+
+/// How often the agent checks whether the nightly run is due
+///
+/// Checking instead of sleeping until the run keeps it on time after the
+/// machine wakes from sleep.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Age after which a cookie database copy is left over from a crashed run
+const STALE_COPY_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Settings of the maintenance agent, as passed to [`Agent::configure`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceSettings {
+    /// Days of history kept; older snapshots and notifications are deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<u32>,
+}
+
+/// What one maintenance run did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// History rows deleted
+    pub pruned: usize,
+    /// Cookie database copies deleted
+    pub stale_copies: usize,
+}
+
+/// Agent that prunes and vacuums the history and cleans the temp directory
+pub struct MaintenanceAgent {
+    history: Arc<HistoryStore>,
+    retention_days: RwLock<u32>,
+    next_run_at: Mutex<Option<DateTime<Utc>>>,
+    status: tokio::sync::RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl MaintenanceAgent {
+    /// Creates an agent maintaining `history`
+    pub fn new(history: Arc<HistoryStore>) -> Self {
+        Self {
+            history,
+            retention_days: RwLock::new(DEFAULT_RETENTION_DAYS),
+            next_run_at: Mutex::new(None),
+            status: tokio::sync::RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    fn retention_days(&self) -> u32 {
+        *self
+            .retention_days
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_next_run(&self, next: Option<DateTime<Utc>>) {
+        *self.next_run_at.lock().unwrap_or_else(|e| e.into_inner()) = next;
+    }
+
+    /// Runs the maintenance off the async runtime, since a vacuum rewrites
+    /// the whole file
+    async fn run(&self) -> Result<MaintenanceReport, AgentError> {
+        let history = self.history.clone();
+        let retention_days = self.retention_days();
+        // Older versions copied cookie databases into the temp directory
+        let copy_dirs = [CookieExtractor::copy_dir(), std::env::temp_dir()];
+        let report = tokio::task::spawn_blocking(move || {
+            maintain(&history, retention_days, Utc::now(), &copy_dirs)
+        })
+        .await
+        .map_err(|e| AgentError::Internal(e.to_string()))?
+        .map_err(|e| AgentError::OperationFailed(e.to_string()))?;
+
+        tracing::info!(
+            "Maintenance deleted {} history rows and {} cookie copies",
+            report.pruned,
+            report.stale_copies
+        );
+        Ok(report)
+    }
+}
+
+/// Prunes history older than `retention_days` before `now`, vacuums the
+/// database and deletes stale cookie copies in `copy_dirs`
+fn maintain(
+    history: &HistoryStore,
+    retention_days: u32,
+    now: DateTime<Utc>,
+    copy_dirs: &[PathBuf],
+) -> Result<MaintenanceReport, HistoryError> {
+    let pruned = history.prune(now - chrono::Duration::days(retention_days.into()))?;
+    history.vacuum()?;
+    Ok(MaintenanceReport {
+        pruned,
+        stale_copies: copy_dirs
+            .iter()
+            .map(|dir| CookieExtractor::remove_stale_copies(dir, STALE_COPY_AGE))
+            .sum(),
+    })
+}
+
+/// The first [`RUN_AT_HOUR`] in `timezone` after `now`
+///
+/// A day where that hour is skipped by a daylight saving change is skipped
+/// too.
+fn next_run_after<Tz: TimeZone>(now: DateTime<Utc>, timezone: &Tz) -> DateTime<Utc> {
+    let today = now.with_timezone(timezone).date_naive();
+    (0..3)
+        .filter_map(|days| {
+            let time = (today + chrono::Days::new(days)).and_hms_opt(RUN_AT_HOUR, 0, 0)?;
+            timezone.from_local_datetime(&time).earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+        .find(|at| *at > now)
+        .unwrap_or_else(|| now + chrono::Duration::days(1))
+}
+
+#[async_trait]
+impl Agent for MaintenanceAgent {
+    fn id(&self) -> &'static str {
+        "maintenance"
+    }
+
+    fn name(&self) -> &'static str {
+        "Maintenance"
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        let cancel_token = self.cancel_token.renew();
+        let mut next = next_run_after(Utc::now(), &Local);
+        self.set_next_run(Some(next));
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let now = Utc::now();
+                    if now < next {
+                        continue;
+                    }
+                    if let Err(e) = self.run().await {
+                        tracing::warn!("Maintenance failed: {}", e);
+                    }
+                    next = next_run_after(now, &Local);
+                    self.set_next_run(Some(next));
+                }
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        self.set_next_run(None);
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        self.set_next_run(None);
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn trigger(&self) -> Result<(), AgentError> {
+        self.run().await.map(|_| ())
+    }
+
+    fn next_run(&self) -> Option<DateTime<Utc>> {
+        *self.next_run_at.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn settings(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(MaintenanceSettings {
+            retention_days: Some(self.retention_days()),
+        })
+        .ok()
+    }
+
+    async fn configure(&self, config: serde_json::Value) -> Result<(), AgentError> {
+        let settings: MaintenanceSettings =
+            serde_json::from_value(config).map_err(|e| AgentError::InvalidConfig(e.to_string()))?;
+        if let Some(days) = settings.retention_days {
+            if !(1..=MAX_RETENTION_DAYS).contains(&days) {
+                return Err(AgentError::InvalidConfig(format!(
+                    "retention must be between 1 and {} days",
+                    MAX_RETENTION_DAYS
+                )));
+            }
+            *self
+                .retention_days
+                .write()
+                .unwrap_or_else(|e| e.into_inner()) = days;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::UsageSnapshot;
+    use chrono::FixedOffset;
+
+    fn snapshot_at(at: DateTime<Utc>) -> UsageSnapshot {
+        let mut snapshot = UsageSnapshot::new();
+        snapshot.updated_at = at;
+        snapshot
+    }
+
+    #[test]
+    fn test_maintain_prunes_old_history() {
+        let now = Utc::now();
+        let history = HistoryStore::open_in_memory().unwrap();
+        history
+            .record("claude", &snapshot_at(now - chrono::Duration::days(40)))
+            .unwrap();
+        history
+            .record("claude", &snapshot_at(now - chrono::Duration::days(2)))
+            .unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let report = maintain(&history, 30, now, &[temp_dir.path().to_path_buf()]).unwrap();
+        assert_eq!(report.pruned, 1);
+        assert_eq!(history.recent(None, 24 * 60).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_next_run_after() {
+        let madrid = FixedOffset::east_opt(2 * 3600).unwrap();
+        let at = |day, hour| {
+            madrid
+                .with_ymd_and_hms(2026, 10, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        assert_eq!(next_run_after(at(16, 1), &madrid), at(16, 3));
+        assert_eq!(next_run_after(at(16, 3), &madrid), at(17, 3));
+        assert_eq!(next_run_after(at(16, 22), &madrid), at(17, 3));
+    }
+
+    #[tokio::test]
+    async fn test_configure_retention() {
+        let agent = MaintenanceAgent::new(Arc::new(HistoryStore::open_in_memory().unwrap()));
+        agent
+            .configure(serde_json::json!({ "retention_days": 30 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            agent.settings(),
+            Some(serde_json::json!({ "retention_days": 30 }))
+        );
+
+        assert!(agent
+            .configure(serde_json::json!({ "retention_days": 0 }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "retention_days": u32::MAX }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "keep": 30 }))
+            .await
+            .is_err());
+    }
+}
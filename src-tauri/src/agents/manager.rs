@@ -1,15 +1,41 @@
 //! Agent manager - Orchestrates multiple agents
 //!
 //! Provides lifecycle management for all agents, including starting,
-//! stopping, and monitoring their status.
+//! stopping, and monitoring their status. Agents start in dependency order,
+//! each after the agents it depends on are ready. Starts and stops are
+//! published on the event bus.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
+use tracing::Instrument;
 
 use super::base::{Agent, AgentError, AgentStatus};
+use crate::events::{self, AppEvent};
+
+/// How long `start_all` waits for an agent's dependencies to become ready
+/// before starting it anyway
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Status report of one agent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentInfo {
+    /// Agent ID
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Status, e.g. "Running" or "Error(...)"
+    pub status: String,
+    /// When the agent next does its periodic work, if it has a schedule
+    pub next_refresh_at: Option<DateTime<Utc>>,
+    /// Current settings, if the agent has any
+    pub settings: Option<serde_json::Value>,
+}
 
 /// Manages the lifecycle of multiple agents
 pub struct AgentManager {
@@ -48,14 +74,33 @@ impl AgentManager {
         self.agents.read().await.get(id).cloned()
     }
 
-    /// Starts all registered agents
+    /// Starts all registered agents, each once its dependencies are ready
     pub async fn start_all(&self) -> Result<(), AgentError> {
         let agents = self.agents.read().await.clone();
 
-        for (id, agent) in agents {
-            if agent.status().is_stopped() {
-                self.start_agent_internal(id, agent).await?;
+        for id in start_order(&agents)? {
+            let agent = Arc::clone(&agents[id]);
+            if !agent.status().is_stopped() {
+                continue;
+            }
+
+            for dependency in agent.depends_on() {
+                let Some(dependency) = agents.get(dependency) else {
+                    continue;
+                };
+                if tokio::time::timeout(READY_TIMEOUT, dependency.ready())
+                    .await
+                    .is_err()
+                {
+                    tracing::warn!(
+                        "Starting agent '{}' before '{}' is ready",
+                        id,
+                        dependency.id()
+                    );
+                }
             }
+
+            self.start_agent_internal(id, agent).await?;
         }
 
         Ok(())
@@ -71,6 +116,9 @@ impl AgentManager {
             .ok_or_else(|| AgentError::OperationFailed(format!("Agent '{}' not found", id)))?;
         drop(agents);
 
+        if agent.status().is_running() {
+            return Err(AgentError::AlreadyRunning);
+        }
         self.start_agent_internal(static_id, agent).await
     }
 
@@ -82,15 +130,24 @@ impl AgentManager {
     ) -> Result<(), AgentError> {
         let agent_clone = Arc::clone(&agent);
 
-        let handle = tokio::spawn(async move {
-            if let Err(e) = agent_clone.start().await {
-                tracing::error!("Agent '{}' error: {}", agent_clone.id(), e);
+        // Everything the agent logs carries its ID
+        let span = tracing::info_span!("agent", agent_id = id);
+        let handle = tokio::spawn(
+            async move {
+                if let Err(e) = agent_clone.start().await {
+                    tracing::error!("Agent '{}' error: {}", agent_clone.id(), e);
+                }
             }
-        });
+            .instrument(span),
+        );
 
         self.handles.write().await.insert(id, handle);
 
         tracing::info!("Started agent: {}", id);
+        events::publish(AppEvent::AgentStateChanged {
+            agent_id: id.to_string(),
+            running: true,
+        });
         Ok(())
     }
 
@@ -138,6 +195,10 @@ impl AgentManager {
         }
 
         tracing::info!("Stopped agent: {}", id);
+        events::publish(AppEvent::AgentStateChanged {
+            agent_id: id.to_string(),
+            running: false,
+        });
         Ok(())
     }
 
@@ -150,6 +211,25 @@ impl AgentManager {
             .collect()
     }
 
+    /// Gets the status and next run of every agent, sorted by ID
+    pub async fn info(&self) -> Vec<AgentInfo> {
+        let mut info: Vec<AgentInfo> = self
+            .agents
+            .read()
+            .await
+            .values()
+            .map(|agent| AgentInfo {
+                id: agent.id().to_string(),
+                name: agent.name().to_string(),
+                status: format!("{:?}", agent.status()),
+                next_refresh_at: agent.next_run(),
+                settings: agent.settings(),
+            })
+            .collect();
+        info.sort_by(|a, b| a.id.cmp(&b.id));
+        info
+    }
+
     /// Gets the status of a specific agent
     pub async fn agent_status(&self, id: &str) -> Option<AgentStatus> {
         self.agents.read().await.get(id).map(|a| a.status())
@@ -183,6 +263,23 @@ impl AgentManager {
         Ok(())
     }
 
+    /// Changes the settings of a specific agent
+    pub async fn configure_agent(
+        &self,
+        id: &str,
+        config: serde_json::Value,
+    ) -> Result<(), AgentError> {
+        let agent = self.agents.read().await.get(id).cloned();
+
+        match agent {
+            Some(agent) => agent.configure(config).await,
+            None => Err(AgentError::OperationFailed(format!(
+                "Agent '{}' not found",
+                id
+            ))),
+        }
+    }
+
     /// Triggers a specific agent
     pub async fn trigger_agent(&self, id: &str) -> Result<(), AgentError> {
         let agent = self.agents.read().await.get(id).cloned();
@@ -198,6 +295,47 @@ impl AgentManager {
     }
 }
 
+/// Orders agents so each comes after the agents it depends on, ties broken by
+/// ID; dependencies that aren't registered are ignored
+fn start_order(
+    agents: &HashMap<&'static str, Arc<dyn Agent>>,
+) -> Result<Vec<&'static str>, AgentError> {
+    let mut pending: BTreeMap<&'static str, Vec<&'static str>> = agents
+        .iter()
+        .map(|(id, agent)| {
+            let depends_on = agent
+                .depends_on()
+                .iter()
+                .copied()
+                .filter(|dependency| agents.contains_key(dependency))
+                .collect();
+            (*id, depends_on)
+        })
+        .collect();
+
+    let mut order = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let startable: Vec<_> = pending
+            .iter()
+            .filter(|(_, depends_on)| depends_on.iter().all(|d| order.contains(d)))
+            .map(|(id, _)| *id)
+            .collect();
+        if startable.is_empty() {
+            let cycle: Vec<_> = pending.keys().copied().collect();
+            return Err(AgentError::Internal(format!(
+                "Agents depend on each other: {}",
+                cycle.join(", ")
+            )));
+        }
+        for id in startable {
+            pending.remove(id);
+            order.push(id);
+        }
+    }
+
+    Ok(order)
+}
+
 impl Default for AgentManager {
     fn default() -> Self {
         Self::new()
@@ -212,13 +350,19 @@ mod tests {
     struct TestAgent {
         id: &'static str,
         status: RwLock<AgentStatus>,
+        depends_on: &'static [&'static str],
     }
 
     impl TestAgent {
         fn new(id: &'static str) -> Self {
+            Self::with_dependencies(id, &[])
+        }
+
+        fn with_dependencies(id: &'static str, depends_on: &'static [&'static str]) -> Self {
             Self {
                 id,
                 status: RwLock::new(AgentStatus::Idle),
+                depends_on,
             }
         }
     }
@@ -240,6 +384,10 @@ mod tests {
                 .unwrap_or(AgentStatus::Idle)
         }
 
+        fn depends_on(&self) -> &'static [&'static str] {
+            self.depends_on
+        }
+
         async fn start(&self) -> Result<(), AgentError> {
             *self.status.write().await = AgentStatus::Running;
             // Simulate running
@@ -299,6 +447,21 @@ mod tests {
         assert_eq!(status.get("test-1"), Some(&AgentStatus::Idle));
     }
 
+    #[tokio::test]
+    async fn test_agent_manager_info() {
+        let manager = AgentManager::new();
+        manager.register(Arc::new(TestAgent::new("test-2"))).await;
+        manager.register(Arc::new(TestAgent::new("test-1"))).await;
+
+        let info = manager.info().await;
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0].id, "test-1");
+        assert_eq!(info[0].name, "Test Agent");
+        assert_eq!(info[0].status, "Idle");
+        assert_eq!(info[0].next_refresh_at, None);
+        assert_eq!(info[0].settings, None);
+    }
+
     #[tokio::test]
     async fn test_agent_manager_agent_status() {
         let manager = AgentManager::new();
@@ -331,6 +494,82 @@ mod tests {
         assert!(manager.get("nonexistent").await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_agent_manager_start_stop_agent() {
+        let manager = AgentManager::new();
+        manager.register(Arc::new(TestAgent::new("test-1"))).await;
+
+        manager.start_agent("test-1").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(manager.running_count().await, 1);
+        assert!(matches!(
+            manager.start_agent("test-1").await,
+            Err(AgentError::AlreadyRunning)
+        ));
+
+        manager.stop_agent("test-1").await.unwrap();
+        assert_eq!(
+            manager.agent_status("test-1").await,
+            Some(AgentStatus::Stopped)
+        );
+        assert!(manager.start_agent("nonexistent").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_agent_manager_configure_agent() {
+        let manager = AgentManager::new();
+        manager.register(Arc::new(TestAgent::new("test-1"))).await;
+
+        // TestAgent keeps the default, which has no settings
+        assert!(matches!(
+            manager
+                .configure_agent("test-1", serde_json::json!({}))
+                .await,
+            Err(AgentError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            manager
+                .configure_agent("nonexistent", serde_json::json!({}))
+                .await,
+            Err(AgentError::OperationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_start_order_follows_dependencies() {
+        let agents: HashMap<&'static str, Arc<dyn Agent>> = [
+            Arc::new(TestAgent::with_dependencies("notification", &["refresh"])) as Arc<dyn Agent>,
+            Arc::new(TestAgent::with_dependencies(
+                "http",
+                &["refresh", "missing"],
+            )),
+            Arc::new(TestAgent::new("refresh")),
+            Arc::new(TestAgent::new("mqtt")),
+        ]
+        .into_iter()
+        .map(|agent| (agent.id(), agent))
+        .collect();
+
+        assert_eq!(
+            start_order(&agents).unwrap(),
+            vec!["mqtt", "refresh", "http", "notification"]
+        );
+    }
+
+    #[test]
+    fn test_start_order_rejects_cycles() {
+        let agents: HashMap<&'static str, Arc<dyn Agent>> = [
+            Arc::new(TestAgent::with_dependencies("a", &["b"])) as Arc<dyn Agent>,
+            Arc::new(TestAgent::with_dependencies("b", &["a"])),
+            Arc::new(TestAgent::new("c")),
+        ]
+        .into_iter()
+        .map(|agent| (agent.id(), agent))
+        .collect();
+
+        assert!(matches!(start_order(&agents), Err(AgentError::Internal(_))));
+    }
+
     #[tokio::test]
     async fn test_agent_manager_stop_nonexistent() {
         let manager = AgentManager::new();
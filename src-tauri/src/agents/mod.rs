@@ -4,13 +4,44 @@
 //! - Periodic refresh of usage data
 //! - Usage threshold notifications
 //! - Cookie change monitoring
+//! - Serving usage to external tools over local IPC
+//! - Publishing usage updates to an MQTT broker
+//! - Serving a REST API for dashboards
+//! - Announcing new budget periods
+//! - Fetching the exchange rate of the display currency
+//! - Sending opt-in anonymous telemetry
+//! - Sharing usage with a team through a self-hosted endpoint
+//! - Pruning and vacuuming the history and cleaning temp files nightly
 
 mod base;
+mod budget_agent;
+mod currency_agent;
+mod http_agent;
+mod ipc_agent;
+mod maintenance_agent;
 mod manager;
+mod mqtt_agent;
 mod refresh_agent;
 mod notification_agent;
+mod team_sync_agent;
+mod telemetry_agent;
 
 pub use base::{Agent, AgentError, AgentStatus};
-pub use manager::AgentManager;
-pub use refresh_agent::RefreshAgent;
-pub use notification_agent::{NotificationAgent, NotificationThresholds};
+pub use budget_agent::BudgetAgent;
+pub use currency_agent::CurrencyAgent;
+pub use http_agent::{api_token, ApiState, HttpAgent, API_TOKEN_KEY};
+pub use ipc_agent::{default_socket_path, IpcAgent, UsageReport};
+pub use maintenance_agent::{
+    MaintenanceAgent, MaintenanceReport, MaintenanceSettings, DEFAULT_RETENTION_DAYS,
+};
+pub use manager::{AgentInfo, AgentManager};
+pub use mqtt_agent::{MqttAgent, MQTT_PASSWORD_KEY, MQTT_USERNAME_KEY};
+pub use refresh_agent::{
+    FetchMetrics, RefreshAgent, RefreshConfig, RefreshSettings, UsageUpdate, USAGE_UPDATED_EVENT,
+};
+pub use notification_agent::{
+    NotificationAgent, NotificationEvent, NotificationLevel, NotificationSettings,
+    NotificationThresholds,
+};
+pub use team_sync_agent::TeamSyncAgent;
+pub use telemetry_agent::TelemetryAgent;
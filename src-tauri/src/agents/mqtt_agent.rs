@@ -0,0 +1,231 @@
+//! MQTT agent - Publishes usage updates to an MQTT broker
+//!
+//! Lets home-automation dashboards and Stream Deck plugins display usage.
+//! Each refresh publishes the provider's snapshot as retained JSON on
+//! `<topic_prefix>/<provider_id>`. Broker credentials live in the
+//! `SecureStore`, never in the config file.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rumqttc::{AsyncClient, MqttOptions, QoS, Transport};
+use tokio::sync::{mpsc, Mutex, RwLock};
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use crate::auth::SecureStore;
+use crate::config::MqttSettings;
+use crate::providers::UsageSnapshot;
+
+/// SecureStore key for the broker username
+pub const MQTT_USERNAME_KEY: &str = "mqtt-username";
+/// SecureStore key for the broker password
+pub const MQTT_PASSWORD_KEY: &str = "mqtt-password";
+
+/// Delay before polling again after a connection error
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+type Update = (String, UsageSnapshot);
+
+/// Agent that forwards usage updates to an MQTT broker
+pub struct MqttAgent {
+    settings: MqttSettings,
+    credentials: Option<(String, String)>,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+    updates: mpsc::UnboundedSender<Update>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<Update>>>,
+}
+
+impl MqttAgent {
+    /// Creates an MQTT agent for the given broker settings
+    pub fn new(settings: MqttSettings) -> Self {
+        let (updates, receiver) = mpsc::unbounded_channel();
+        Self {
+            settings,
+            credentials: None,
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+            updates,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    /// Sets the broker username and password
+    pub fn with_credentials(mut self, username: String, password: String) -> Self {
+        self.credentials = Some((username, password));
+        self
+    }
+
+    /// Loads broker credentials from the secure store, if any were saved
+    pub fn with_stored_credentials(self, store: &SecureStore) -> Self {
+        match (
+            store.get_token(MQTT_USERNAME_KEY),
+            store.get_token(MQTT_PASSWORD_KEY),
+        ) {
+            (Ok(Some(username)), Ok(Some(password))) => self.with_credentials(username, password),
+            _ => self,
+        }
+    }
+
+    /// Queues a snapshot for publishing
+    ///
+    /// Never blocks, so it can be called from the refresh agent's update
+    /// subscriber.
+    pub fn publish(&self, provider_id: &str, snapshot: &UsageSnapshot) {
+        let _ = self
+            .updates
+            .send((provider_id.to_string(), snapshot.clone()));
+    }
+
+    /// Returns the topic a provider's usage is published on
+    pub fn topic(&self, provider_id: &str) -> String {
+        format!(
+            "{}/{}",
+            self.settings.topic_prefix.trim_end_matches('/'),
+            provider_id
+        )
+    }
+
+    /// Builds the client options from the settings and credentials
+    fn options(&self) -> MqttOptions {
+        let mut options = MqttOptions::new(
+            "gptbar",
+            self.settings.host.clone(),
+            self.settings.port,
+        );
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let Some((username, password)) = &self.credentials {
+            options.set_credentials(username.clone(), password.clone());
+        }
+
+        if self.settings.use_tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+
+        options
+    }
+}
+
+#[async_trait]
+impl Agent for MqttAgent {
+    fn id(&self) -> &'static str {
+        "mqtt"
+    }
+
+    fn name(&self) -> &'static str {
+        "MQTT Publisher"
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        let mut receiver = self
+            .receiver
+            .lock()
+            .await
+            .take()
+            .ok_or(AgentError::AlreadyRunning)?;
+
+        *self.status.write().await = AgentStatus::Running;
+
+        let (client, mut eventloop) = AsyncClient::new(self.options(), 16);
+        tracing::info!(
+            "Publishing usage to MQTT broker {}:{}",
+            self.settings.host,
+            self.settings.port
+        );
+
+        let cancel_token = self.cancel_token.renew();
+        loop {
+            tokio::select! {
+                event = eventloop.poll() => {
+                    // The event loop reconnects on the next poll after an error
+                    if let Err(e) = event {
+                        tracing::warn!("MQTT connection error: {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+                Some((provider_id, snapshot)) = receiver.recv() => {
+                    match serde_json::to_vec(&snapshot) {
+                        Ok(payload) => {
+                            if let Err(e) = client.try_publish(
+                                self.topic(&provider_id),
+                                QoS::AtLeastOnce,
+                                true,
+                                payload,
+                            ) {
+                                tracing::warn!("Failed to queue MQTT publish: {}", e);
+                            }
+                        }
+                        Err(e) => tracing::error!("Failed to serialize snapshot: {}", e),
+                    }
+                }
+                _ = cancel_token.cancelled() => {
+                    let _ = client.try_disconnect();
+                    break;
+                }
+            }
+        }
+
+        *self.receiver.lock().await = Some(receiver);
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> MqttSettings {
+        MqttSettings {
+            enabled: true,
+            host: "broker.local".to_string(),
+            port: 1884,
+            topic_prefix: "home/gptbar/".to_string(),
+            use_tls: false,
+        }
+    }
+
+    #[test]
+    fn test_topic() {
+        let agent = MqttAgent::new(settings());
+        assert_eq!(agent.topic("claude"), "home/gptbar/claude");
+    }
+
+    #[test]
+    fn test_options() {
+        let agent = MqttAgent::new(settings());
+        let options = agent.options();
+        assert_eq!(options.broker_address(), ("broker.local".to_string(), 1884));
+        assert_eq!(options.credentials(), None);
+
+        let agent = MqttAgent::new(settings()).with_credentials("user".into(), "pass".into());
+        assert_eq!(
+            agent.options().credentials(),
+            Some(("user".to_string(), "pass".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_queues_update() {
+        let agent = MqttAgent::new(settings());
+        agent.publish("claude", &UsageSnapshot::new());
+
+        let mut receiver = agent.receiver.lock().await.take().unwrap();
+        let (provider_id, _) = receiver.recv().await.unwrap();
+        assert_eq!(provider_id, "claude");
+    }
+}
@@ -1,7 +1,10 @@
 //! Notification agent - Sends notifications when usage thresholds are reached
 //!
 //! Monitors usage snapshots and sends system notifications when usage
-//! reaches warning (80%) or critical (95%) levels.
+//! reaches warning (80%) or critical (95%) levels. Providers and single
+//! windows can override those levels, and single windows can be muted.
+//! Sent notifications are logged in the history database when one is set,
+//! and broadcast to subscribers, which each receive them on their own task.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -9,11 +12,17 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use tokio::sync::RwLock;
-use tokio_util::sync::CancellationToken;
-
-use super::base::{Agent, AgentError, AgentStatus};
-use crate::providers::UsageSnapshot;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, RwLock};
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use crate::format;
+use crate::history::{HistoryStore, NotificationRecord};
+use crate::i18n;
+use crate::memory::{self, MemoryUsage};
+use crate::providers::{
+    PaceStatus, ProviderThresholds, RateWindow, Thresholds, UsageSnapshot, WindowSlot,
+};
 
 /// Notification threshold configuration
 #[derive(Debug, Clone)]
@@ -24,6 +33,8 @@ pub struct NotificationThresholds {
     pub critical_percent: f64,
     /// Minimum time between notifications for the same provider (in minutes)
     pub cooldown_minutes: u64,
+    /// Also notify when a window is used faster than its time passes
+    pub pace_alerts: bool,
 }
 
 impl Default for NotificationThresholds {
@@ -32,6 +43,7 @@ impl Default for NotificationThresholds {
             warning_percent: 80.0,
             critical_percent: 95.0,
             cooldown_minutes: 30,
+            pace_alerts: false,
         }
     }
 }
@@ -43,6 +55,7 @@ impl NotificationThresholds {
             warning_percent: warning,
             critical_percent: critical,
             cooldown_minutes: 30,
+            pace_alerts: false,
         }
     }
 
@@ -51,31 +64,67 @@ impl NotificationThresholds {
         self.cooldown_minutes = minutes;
         self
     }
+
+    /// Sets whether windows used ahead of pace notify
+    pub fn with_pace_alerts(mut self, enabled: bool) -> Self {
+        self.pace_alerts = enabled;
+        self
+    }
+
+    /// The warning and critical levels
+    pub fn levels(&self) -> Thresholds {
+        Thresholds::new(self.warning_percent, self.critical_percent)
+    }
 }
 
 /// Notification level
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NotificationLevel {
     /// Warning notification (approaching limit)
     Warning,
     /// Critical notification (near or at limit)
     Critical,
+    /// Usage ahead of the time elapsed in its window, before any threshold
+    AheadOfPace,
 }
 
-/// Callback type for sending notifications
-pub type NotifyCallback = Box<dyn Fn(&str, &str, NotificationLevel) + Send + Sync>;
+/// Notifications a subscriber can fall behind by before it misses some
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 16;
+
+/// Longest cooldown [`Agent::configure`] accepts, a week
+const MAX_COOLDOWN_MINUTES: u64 = 7 * 24 * 60;
+
+/// A notification sent for a provider
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotificationEvent {
+    pub provider_id: String,
+    /// Localized title, e.g. "Claude usage warning"
+    pub title: String,
+    /// Localized body with the usage percentage
+    pub message: String,
+    pub level: NotificationLevel,
+    /// Window that crossed the threshold
+    pub window: WindowSlot,
+}
 
 /// Agent that monitors usage and sends notifications
 pub struct NotificationAgent {
-    thresholds: NotificationThresholds,
+    thresholds: std::sync::RwLock<NotificationThresholds>,
+    /// Per-provider threshold overrides
+    overrides: RwLock<HashMap<String, ProviderThresholds>>,
+    /// Per-provider windows that never notify
+    muted_windows: RwLock<HashMap<String, Vec<WindowSlot>>>,
     status: RwLock<AgentStatus>,
-    cancel_token: CancellationToken,
+    cancel_token: StopSignal,
     /// Tracks the last notification time for each provider
     last_notifications: RwLock<HashMap<String, DateTime<Utc>>>,
-    /// Callback to send notifications
-    notify_callback: RwLock<Option<NotifyCallback>>,
+    /// Subscribers to sent notifications
+    sent: broadcast::Sender<NotificationEvent>,
     /// Current snapshots to monitor
     snapshots: Arc<RwLock<HashMap<String, UsageSnapshot>>>,
+    /// Where sent notifications are logged
+    history: Option<Arc<HistoryStore>>,
 }
 
 impl NotificationAgent {
@@ -87,21 +136,56 @@ impl NotificationAgent {
     /// Creates a new NotificationAgent with custom thresholds
     pub fn with_thresholds(thresholds: NotificationThresholds) -> Self {
         Self {
-            thresholds,
+            thresholds: std::sync::RwLock::new(thresholds),
+            overrides: RwLock::new(HashMap::new()),
+            muted_windows: RwLock::new(HashMap::new()),
             status: RwLock::new(AgentStatus::Idle),
-            cancel_token: CancellationToken::new(),
+            cancel_token: StopSignal::new(),
             last_notifications: RwLock::new(HashMap::new()),
-            notify_callback: RwLock::new(None),
+            sent: broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY).0,
             snapshots: Arc::new(RwLock::new(HashMap::new())),
+            history: None,
+        }
+    }
+
+    /// Sets per-provider threshold overrides
+    pub fn with_overrides(self, overrides: HashMap<String, ProviderThresholds>) -> Self {
+        Self {
+            overrides: RwLock::new(overrides),
+            ..self
+        }
+    }
+
+    /// Replaces the per-provider threshold overrides
+    pub async fn set_overrides(&self, overrides: HashMap<String, ProviderThresholds>) {
+        *self.overrides.write().await = overrides;
+    }
+
+    /// Sets per-provider windows that never notify
+    pub fn with_muted_windows(self, muted: HashMap<String, Vec<WindowSlot>>) -> Self {
+        Self {
+            muted_windows: RwLock::new(muted),
+            ..self
         }
     }
 
-    /// Sets the callback for sending notifications
-    pub async fn on_notify<F>(&self, callback: F)
-    where
-        F: Fn(&str, &str, NotificationLevel) + Send + Sync + 'static,
-    {
-        *self.notify_callback.write().await = Some(Box::new(callback));
+    /// Replaces the per-provider muted windows
+    pub async fn set_muted_windows(&self, muted: HashMap<String, Vec<WindowSlot>>) {
+        *self.muted_windows.write().await = muted;
+    }
+
+    /// Logs sent notifications in a history store
+    pub fn with_history(mut self, history: Arc<HistoryStore>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Subscribes to sent notifications
+    ///
+    /// Each receiver gets every notification sent after it subscribed; one
+    /// that falls more than a few notifications behind misses the oldest.
+    pub fn subscribe(&self) -> broadcast::Receiver<NotificationEvent> {
+        self.sent.subscribe()
     }
 
     /// Sets the snapshots to monitor (typically shared with RefreshAgent)
@@ -123,21 +207,61 @@ impl NotificationAgent {
 
     /// Checks a snapshot against thresholds and sends notification if needed
     async fn check_and_notify(&self, provider_id: &str, snapshot: &UsageSnapshot) {
-        // Get the highest usage across all windows
-        let max_usage = snapshot.max_usage();
-
-        let level = if max_usage >= self.thresholds.critical_percent {
-            Some(NotificationLevel::Critical)
-        } else if max_usage >= self.thresholds.warning_percent {
-            Some(NotificationLevel::Warning)
-        } else {
-            None
-        };
-
-        if let Some(level) = level {
+        let overrides = self.overrides.read().await;
+        let provider_thresholds = overrides.get(provider_id).cloned().unwrap_or_default();
+        drop(overrides);
+        let muted = self
+            .muted_windows
+            .read()
+            .await
+            .get(provider_id)
+            .cloned()
+            .unwrap_or_default();
+
+        // Most severe level across windows, each against its own thresholds,
+        // reported with the highest usage at that level
+        let global = self.thresholds().levels();
+        let alert = snapshot
+            .windows()
+            .filter(|(slot, _)| !muted.contains(slot))
+            .filter_map(|(slot, window)| {
+                let thresholds = provider_thresholds.resolve(slot, global);
+                let level = if window.is_critical(&thresholds) {
+                    NotificationLevel::Critical
+                } else if window.is_warning(&thresholds) {
+                    NotificationLevel::Warning
+                } else {
+                    return None;
+                };
+                Some((level, window, slot))
+            })
+            .max_by(|a, b| {
+                (a.0 == NotificationLevel::Critical)
+                    .cmp(&(b.0 == NotificationLevel::Critical))
+                    .then(a.1.used_percent.total_cmp(&b.1.used_percent))
+            });
+
+        // Otherwise the window furthest ahead of pace, if enabled
+        let alert = alert.or_else(|| {
+            if !self.thresholds().pace_alerts {
+                return None;
+            }
+            snapshot
+                .windows()
+                .filter(|(slot, _)| !muted.contains(slot))
+                .filter_map(|(slot, window)| {
+                    let pace = window.pace.filter(|p| p.status == PaceStatus::Ahead)?;
+                    Some((window.used_percent - pace.elapsed_percent, window, slot))
+                })
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, window, slot)| (NotificationLevel::AheadOfPace, window, slot))
+        });
+
+        if let Some((level, window, slot)) = alert {
             // Check cooldown
             if self.should_notify(provider_id).await {
-                self.send_notification(provider_id, max_usage, level).await;
+                self.send_notification(provider_id, window, level, slot)
+                    .await;
             }
         }
     }
@@ -147,7 +271,7 @@ impl NotificationAgent {
         let last_notifications = self.last_notifications.read().await;
 
         if let Some(last_time) = last_notifications.get(provider_id) {
-            let cooldown = chrono::Duration::minutes(self.thresholds.cooldown_minutes as i64);
+            let cooldown = chrono::Duration::minutes(self.thresholds().cooldown_minutes as i64);
             let now = Utc::now();
 
             if now - *last_time < cooldown {
@@ -159,46 +283,118 @@ impl NotificationAgent {
     }
 
     /// Sends a notification
-    async fn send_notification(&self, provider_id: &str, usage: f64, level: NotificationLevel) {
+    async fn send_notification(
+        &self,
+        provider_id: &str,
+        rate_window: &RateWindow,
+        level: NotificationLevel,
+        window: WindowSlot,
+    ) {
+        let usage = rate_window.used_percent;
         // Update last notification time
+        let now = Utc::now();
         self.last_notifications
             .write()
             .await
-            .insert(provider_id.to_string(), Utc::now());
+            .insert(provider_id.to_string(), now);
+
+        if let Some(history) = &self.history {
+            let record = NotificationRecord {
+                provider_id: provider_id.to_string(),
+                level,
+                percent: usage,
+                window: Some(window),
+                sent_at: now,
+            };
+            if let Err(e) = history.record_notification(&record) {
+                tracing::warn!("Failed to log notification for {}: {}", provider_id, e);
+            }
+        }
 
         // Format the message
-        let title = match level {
-            NotificationLevel::Warning => format!("{} Usage Warning", provider_id),
-            NotificationLevel::Critical => format!("{} Usage Critical!", provider_id),
+        let title_key = match level {
+            NotificationLevel::Warning => "notification.warning.title",
+            NotificationLevel::Critical => "notification.critical.title",
+            NotificationLevel::AheadOfPace => "notification.pace.title",
+        };
+        let title = i18n::t_args(title_key, &[("provider", provider_id)]);
+
+        let percent = format!("{:.1}", usage);
+        let message = match (rate_window.pace, rate_window.cost_usd) {
+            (Some(pace), _) if level == NotificationLevel::AheadOfPace => i18n::t_args(
+                "notification.pace.body",
+                &[
+                    ("percent", &percent),
+                    ("elapsed", &format!("{:.0}", pace.elapsed_percent)),
+                ],
+            ),
+            // Spend windows say how much was spent, in the display currency
+            (_, Some(cost)) => i18n::t_args(
+                "notification.body_cost",
+                &[("percent", &percent), ("cost", &format::format_cost(cost))],
+            ),
+            _ => i18n::t_args("notification.body", &[("percent", &percent)]),
         };
-
-        let message = format!("Usage is at {:.1}%", usage);
 
         tracing::info!(
             "Sending {} notification for {}: {}",
             match level {
                 NotificationLevel::Warning => "warning",
                 NotificationLevel::Critical => "critical",
+                NotificationLevel::AheadOfPace => "ahead of pace",
             },
             provider_id,
             message
         );
 
-        // Call the notification callback if set
-        if let Some(ref callback) = *self.notify_callback.read().await {
-            callback(&title, &message, level);
-        }
+        // Fails only when nobody is subscribed
+        let _ = self.sent.send(NotificationEvent {
+            provider_id: provider_id.to_string(),
+            title,
+            message,
+            level,
+            window,
+        });
     }
 
     /// Gets the current thresholds
-    pub fn thresholds(&self) -> &NotificationThresholds {
-        &self.thresholds
+    pub fn thresholds(&self) -> NotificationThresholds {
+        self.thresholds
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replaces the global thresholds
+    pub fn set_thresholds(&self, thresholds: NotificationThresholds) {
+        *self.thresholds.write().unwrap_or_else(|e| e.into_inner()) = thresholds;
     }
 
     /// Clears the notification history (resets cooldowns)
     pub async fn clear_history(&self) {
         self.last_notifications.write().await.clear();
     }
+
+    /// Forgets notifications whose cooldown passed by `now`
+    ///
+    /// They no longer hold back a notification, so keeping them would only
+    /// grow the map with every provider ever seen.
+    pub async fn prune_history(&self, now: DateTime<Utc>) {
+        let cooldown = chrono::Duration::minutes(self.thresholds().cooldown_minutes as i64);
+        self.last_notifications
+            .write()
+            .await
+            .retain(|_, last_time| now - *last_time < cooldown);
+    }
+
+    /// Notification times kept for cooldowns
+    pub async fn memory(&self) -> MemoryUsage {
+        let last_notifications = self.last_notifications.read().await;
+        MemoryUsage {
+            entries: last_notifications.len(),
+            bytes: memory::json_size(&*last_notifications),
+        }
+    }
 }
 
 impl Default for NotificationAgent {
@@ -217,6 +413,10 @@ impl Agent for NotificationAgent {
         "Notification Agent"
     }
 
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["refresh"]
+    }
+
     fn status(&self) -> AgentStatus {
         self.status
             .try_read()
@@ -236,16 +436,18 @@ impl Agent for NotificationAgent {
         *self.status.write().await = AgentStatus::Running;
 
         // Main loop - check snapshots periodically
+        let cancel_token = self.cancel_token.renew();
         loop {
             tokio::select! {
                 _ = tokio::time::sleep(Duration::from_secs(10)) => {
+                    self.prune_history(Utc::now()).await;
                     // Check all snapshots
                     let snapshots = self.snapshots.read().await.clone();
                     for (provider_id, snapshot) in snapshots {
                         self.check_and_notify(&provider_id, &snapshot).await;
                     }
                 }
-                _ = self.cancel_token.cancelled() => {
+                _ = cancel_token.cancelled() => {
                     tracing::info!("Notification agent cancelled");
                     break;
                 }
@@ -269,13 +471,81 @@ impl Agent for NotificationAgent {
         *self.status.write().await = AgentStatus::Stopped;
         Ok(())
     }
+
+    fn settings(&self) -> Option<serde_json::Value> {
+        let thresholds = self.thresholds();
+        let settings = NotificationSettings {
+            warning_percent: Some(thresholds.warning_percent),
+            critical_percent: Some(thresholds.critical_percent),
+            cooldown_minutes: Some(thresholds.cooldown_minutes),
+            pace_alerts: Some(thresholds.pace_alerts),
+        };
+        serde_json::to_value(settings).ok()
+    }
+
+    async fn configure(&self, config: serde_json::Value) -> Result<(), AgentError> {
+        let settings: NotificationSettings =
+            serde_json::from_value(config).map_err(|e| AgentError::InvalidConfig(e.to_string()))?;
+
+        let mut thresholds = self.thresholds();
+        thresholds.warning_percent = settings
+            .warning_percent
+            .unwrap_or(thresholds.warning_percent);
+        thresholds.critical_percent = settings
+            .critical_percent
+            .unwrap_or(thresholds.critical_percent);
+        thresholds.cooldown_minutes = settings
+            .cooldown_minutes
+            .unwrap_or(thresholds.cooldown_minutes);
+        thresholds.pace_alerts = settings.pace_alerts.unwrap_or(thresholds.pace_alerts);
+
+        if !(0.0..=100.0).contains(&thresholds.warning_percent)
+            || thresholds.critical_percent < thresholds.warning_percent
+        {
+            return Err(AgentError::InvalidConfig(format!(
+                "warning {}%, critical {}%",
+                thresholds.warning_percent, thresholds.critical_percent
+            )));
+        }
+        if thresholds.cooldown_minutes > MAX_COOLDOWN_MINUTES {
+            return Err(AgentError::InvalidConfig(format!(
+                "cooldown must be at most {} minutes",
+                MAX_COOLDOWN_MINUTES
+            )));
+        }
+
+        self.set_thresholds(thresholds);
+        Ok(())
+    }
+}
+
+/// Settings of the notification agent, as passed to [`Agent::configure`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationSettings {
+    /// Global warning level, unless a provider overrides it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning_percent: Option<f64>,
+    /// Global critical level, unless a provider overrides it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical_percent: Option<f64>,
+    /// Minimum minutes between notifications for the same provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_minutes: Option<u64>,
+    /// Notify when a window is used faster than its time passes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pace_alerts: Option<bool>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::providers::RateWindow;
-    use std::sync::atomic::{AtomicU32, Ordering};
+    use crate::providers::{RateWindow, WindowSlot};
+
+    /// Notifications received since the last call
+    fn received(receiver: &mut broadcast::Receiver<NotificationEvent>) -> Vec<NotificationEvent> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+    }
 
     #[test]
     fn test_notification_thresholds_default() {
@@ -302,67 +572,73 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_notification_agent_warning() {
+    async fn test_notification_agent_configure() {
         let agent = NotificationAgent::new();
-        let notify_count = Arc::new(AtomicU32::new(0));
-        let notify_count_clone = notify_count.clone();
 
         agent
-            .on_notify(move |_title, _message, _level| {
-                notify_count_clone.fetch_add(1, Ordering::SeqCst);
-            })
-            .await;
+            .configure(serde_json::json!({ "warning_percent": 60.0, "cooldown_minutes": 5 }))
+            .await
+            .unwrap();
+        let thresholds = agent.thresholds();
+        assert_eq!(thresholds.warning_percent, 60.0);
+        assert_eq!(thresholds.critical_percent, 95.0);
+        assert_eq!(thresholds.cooldown_minutes, 5);
+
+        // Rejected settings leave the thresholds alone
+        assert!(agent
+            .configure(serde_json::json!({ "critical_percent": 50.0 }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "interval_minutes": 1 }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "cooldown_minutes": u64::MAX }))
+            .await
+            .is_err());
+        assert_eq!(agent.thresholds().critical_percent, 95.0);
+        assert_eq!(agent.thresholds().cooldown_minutes, 5);
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_warning() {
+        let agent = NotificationAgent::new();
+        let mut sent = agent.subscribe();
 
         // Update with a warning-level snapshot
         let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(85.0));
         agent.update_snapshot("test-provider", &snapshot).await;
 
-        assert_eq!(notify_count.load(Ordering::SeqCst), 1);
+        assert_eq!(received(&mut sent).len(), 1);
     }
 
     #[tokio::test]
     async fn test_notification_agent_critical() {
         let agent = NotificationAgent::new();
-        let last_level = Arc::new(RwLock::new(None));
-        let last_level_clone = last_level.clone();
-
-        agent
-            .on_notify(move |_title, _message, level| {
-                let last_level = last_level_clone.clone();
-                tokio::spawn(async move {
-                    *last_level.write().await = Some(level);
-                });
-            })
-            .await;
+        let mut sent = agent.subscribe();
 
         // Update with a critical-level snapshot
         let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(98.0));
         agent.update_snapshot("test-provider", &snapshot).await;
 
-        // Give async callback time to run
-        tokio::time::sleep(Duration::from_millis(50)).await;
-
-        let level = *last_level.read().await;
-        assert_eq!(level, Some(NotificationLevel::Critical));
+        let sent = received(&mut sent);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].provider_id, "test-provider");
+        assert_eq!(sent[0].level, NotificationLevel::Critical);
+        assert_eq!(sent[0].window, WindowSlot::Primary);
     }
 
     #[tokio::test]
     async fn test_notification_agent_below_threshold() {
         let agent = NotificationAgent::new();
-        let notify_count = Arc::new(AtomicU32::new(0));
-        let notify_count_clone = notify_count.clone();
-
-        agent
-            .on_notify(move |_title, _message, _level| {
-                notify_count_clone.fetch_add(1, Ordering::SeqCst);
-            })
-            .await;
+        let mut sent = agent.subscribe();
 
         // Update with a normal-level snapshot
         let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(50.0));
         agent.update_snapshot("test-provider", &snapshot).await;
 
-        assert_eq!(notify_count.load(Ordering::SeqCst), 0);
+        assert_eq!(received(&mut sent).len(), 0);
     }
 
     #[tokio::test]
@@ -370,62 +646,189 @@ mod tests {
         // Use a very short cooldown for testing
         let thresholds = NotificationThresholds::new(80.0, 95.0).with_cooldown(1); // 1 minute
         let agent = NotificationAgent::with_thresholds(thresholds);
-        let notify_count = Arc::new(AtomicU32::new(0));
-        let notify_count_clone = notify_count.clone();
-
-        agent
-            .on_notify(move |_title, _message, _level| {
-                notify_count_clone.fetch_add(1, Ordering::SeqCst);
-            })
-            .await;
+        let mut sent = agent.subscribe();
 
         // First notification should go through
         let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(85.0));
         agent.update_snapshot("test-provider", &snapshot).await;
-        assert_eq!(notify_count.load(Ordering::SeqCst), 1);
+        assert_eq!(received(&mut sent).len(), 1);
 
         // Second notification should be blocked by cooldown
         agent.update_snapshot("test-provider", &snapshot).await;
-        assert_eq!(notify_count.load(Ordering::SeqCst), 1);
+        assert_eq!(received(&mut sent).len(), 0);
     }
 
     #[tokio::test]
     async fn test_notification_agent_clear_history() {
         let agent = NotificationAgent::new();
-        let notify_count = Arc::new(AtomicU32::new(0));
-        let notify_count_clone = notify_count.clone();
-
-        agent
-            .on_notify(move |_title, _message, _level| {
-                notify_count_clone.fetch_add(1, Ordering::SeqCst);
-            })
-            .await;
+        let mut sent = agent.subscribe();
 
         let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(85.0));
 
         // First notification
         agent.update_snapshot("test-provider", &snapshot).await;
-        assert_eq!(notify_count.load(Ordering::SeqCst), 1);
+        assert_eq!(received(&mut sent).len(), 1);
 
         // Clear history
         agent.clear_history().await;
 
         // Should notify again
         agent.update_snapshot("test-provider", &snapshot).await;
-        assert_eq!(notify_count.load(Ordering::SeqCst), 2);
+        assert_eq!(received(&mut sent).len(), 1);
     }
 
     #[tokio::test]
-    async fn test_notification_agent_multiple_providers() {
-        let agent = NotificationAgent::new();
-        let notify_count = Arc::new(AtomicU32::new(0));
-        let notify_count_clone = notify_count.clone();
+    async fn test_notification_agent_prune_history() {
+        let thresholds = NotificationThresholds::new(80.0, 95.0).with_cooldown(30);
+        let agent = NotificationAgent::with_thresholds(thresholds);
+        let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(85.0));
+        agent.update_snapshot("claude", &snapshot).await;
+        agent.update_snapshot("codex", &snapshot).await;
+        assert_eq!(agent.memory().await.entries, 2);
+
+        agent.prune_history(Utc::now()).await;
+        assert_eq!(agent.memory().await.entries, 2);
 
         agent
-            .on_notify(move |_title, _message, _level| {
-                notify_count_clone.fetch_add(1, Ordering::SeqCst);
-            })
+            .prune_history(Utc::now() + chrono::Duration::minutes(31))
             .await;
+        assert_eq!(agent.memory().await.entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_window_override() {
+        let mut claude = ProviderThresholds::default();
+        claude
+            .windows
+            .insert(WindowSlot::Secondary, Thresholds::new(50.0, 90.0));
+        let agent = NotificationAgent::new()
+            .with_overrides(HashMap::from([("claude".to_string(), claude)]));
+        let mut sent = agent.subscribe();
+
+        // 55% of the weekly window warns for Claude only
+        let snapshot = UsageSnapshot::new()
+            .with_primary(RateWindow::new(20.0))
+            .with_secondary(RateWindow::new(55.0));
+        agent.update_snapshot("openai", &snapshot).await;
+        assert!(received(&mut sent).is_empty());
+
+        agent.update_snapshot("claude", &snapshot).await;
+        let sent = received(&mut sent);
+        assert_eq!(sent[0].level, NotificationLevel::Warning);
+        assert_eq!(sent[0].window, WindowSlot::Secondary);
+        assert!(sent[0].message.contains("55.0"));
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_pace_alerts() {
+        let now = Utc::now();
+        // 70% used with 40% of the week elapsed
+        let snapshot = UsageSnapshot::new()
+            .with_secondary(
+                RateWindow::new(70.0)
+                    .with_window_minutes(10_000)
+                    .with_resets_at(now + chrono::Duration::minutes(6_000)),
+            )
+            .with_pace_at(now);
+
+        let agent = NotificationAgent::new();
+        let mut sent = agent.subscribe();
+        agent.update_snapshot("claude", &snapshot).await;
+        assert!(received(&mut sent).is_empty());
+
+        let agent = NotificationAgent::with_thresholds(
+            NotificationThresholds::default().with_pace_alerts(true),
+        );
+        let mut sent = agent.subscribe();
+        agent.update_snapshot("claude", &snapshot).await;
+        let sent = received(&mut sent);
+        assert_eq!(sent[0].level, NotificationLevel::AheadOfPace);
+        assert_eq!(sent[0].window, WindowSlot::Secondary);
+        assert!(sent[0].message.contains("70.0"));
+        assert!(sent[0].message.contains("40"));
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_reports_spend() {
+        let agent = NotificationAgent::new();
+        let mut sent = agent.subscribe();
+
+        let snapshot =
+            UsageSnapshot::new().with_primary(RateWindow::new(90.0).with_cost_usd(450.0));
+        agent.update_snapshot("claude", &snapshot).await;
+
+        let sent = received(&mut sent);
+        assert!(sent[0].message.contains("90.0"));
+        assert!(sent[0].message.contains(&format::format_cost(450.0)));
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_critical_wins_over_higher_warning() {
+        let mut provider = ProviderThresholds::default();
+        provider
+            .windows
+            .insert(WindowSlot::Secondary, Thresholds::new(40.0, 60.0));
+        let agent = NotificationAgent::new()
+            .with_overrides(HashMap::from([("claude".to_string(), provider)]));
+        let mut sent = agent.subscribe();
+
+        let snapshot = UsageSnapshot::new()
+            .with_primary(RateWindow::new(85.0))
+            .with_secondary(RateWindow::new(65.0));
+        agent.update_snapshot("claude", &snapshot).await;
+
+        let sent = received(&mut sent);
+        assert_eq!(sent[0].level, NotificationLevel::Critical);
+        assert!(sent[0].message.contains("65.0"));
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_muted_window() {
+        let agent = NotificationAgent::new().with_muted_windows(HashMap::from([(
+            "claude".to_string(),
+            vec![WindowSlot::Primary],
+        )]));
+        let mut sent = agent.subscribe();
+
+        // A full 5h session is ignored, the weekly window still notifies
+        let session_full = UsageSnapshot::new()
+            .with_primary(RateWindow::new(99.0))
+            .with_secondary(RateWindow::new(40.0));
+        agent.update_snapshot("claude", &session_full).await;
+        assert_eq!(received(&mut sent).len(), 0);
+
+        let weekly_high = UsageSnapshot::new()
+            .with_primary(RateWindow::new(99.0))
+            .with_secondary(RateWindow::new(85.0));
+        agent.update_snapshot("claude", &weekly_high).await;
+        assert_eq!(received(&mut sent).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_logs_to_history() {
+        let history = Arc::new(HistoryStore::open_in_memory().unwrap());
+        let agent = NotificationAgent::new().with_history(history.clone());
+
+        let snapshot = UsageSnapshot::new()
+            .with_primary(RateWindow::new(40.0))
+            .with_secondary(RateWindow::new(97.0));
+        agent.update_snapshot("claude", &snapshot).await;
+
+        let now = Utc::now();
+        let records = history
+            .notifications(now - chrono::Duration::minutes(1), now)
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].provider_id, "claude");
+        assert_eq!(records[0].level, NotificationLevel::Critical);
+        assert_eq!(records[0].percent, 97.0);
+        assert_eq!(records[0].window, Some(WindowSlot::Secondary));
+    }
+
+    #[tokio::test]
+    async fn test_notification_agent_multiple_providers() {
+        let agent = NotificationAgent::new();
+        let mut sent = agent.subscribe();
 
         let snapshot = UsageSnapshot::new().with_primary(RateWindow::new(85.0));
 
@@ -433,6 +836,6 @@ mod tests {
         agent.update_snapshot("provider-1", &snapshot).await;
         agent.update_snapshot("provider-2", &snapshot).await;
 
-        assert_eq!(notify_count.load(Ordering::SeqCst), 2);
+        assert_eq!(received(&mut sent).len(), 2);
     }
 }
@@ -1,19 +1,117 @@
 //! Refresh agent - Periodically fetches usage data from providers
 //!
 //! Runs in the background and updates usage snapshots at configurable intervals.
-
-use std::sync::Arc;
-use std::time::Duration;
+//! Providers are staggered across the interval: with N providers one is
+//! fetched every interval/N, in display order, so connections and UI updates
+//! are spread out instead of arriving in a burst. Ticks follow an absolute
+//! schedule, so slow fetches don't push later refreshes back, and the time
+//! of the next one is reported through [`Agent::next_run`]. The agent counts
+//! as ready once the fetch on start is done, so agents depending on it start
+//! with snapshots to work with.
+//! Keeps per-provider fetch metrics (duration, success and error counts, last
+//! HTTP status) for tuning intervals and spotting failing providers.
+//! A provider answering 429 with `Retry-After` is skipped until that time.
+//! New snapshots are broadcast to subscribers, so a slow consumer (history
+//! writer, MQTT publisher, UI) only delays itself and never the refreshes.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use tokio::sync::RwLock;
-use tokio_util::sync::CancellationToken;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch, RwLock};
+use tokio::time::MissedTickBehavior;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use crate::events::{self, AppEvent};
+use crate::memory::{self, MemoryUsage};
+use crate::providers::{
+    AuthObservation, AuthStateTracker, ChangeTolerance, Provider, ProviderError, UsageSnapshot,
+};
+use crate::security::Sanitizer;
+
+/// Updates a subscriber can fall behind by before it misses some
+const UPDATE_CHANNEL_CAPACITY: usize = 64;
+
+/// Longest refresh interval [`Agent::configure`] accepts, a day
+const MAX_INTERVAL_MINUTES: u64 = 24 * 60;
+
+/// Event emitted to the frontend for every new snapshot
+pub const USAGE_UPDATED_EVENT: &str = "usage-updated";
+
+/// A new snapshot, broadcast to subscribers of the refresh agent and sent to
+/// the frontend as the payload of [`USAGE_UPDATED_EVENT`]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UsageUpdate {
+    pub provider_id: String,
+    pub snapshot: UsageSnapshot,
+}
+
+/// Fetch statistics of one provider since the app started
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FetchMetrics {
+    /// Successful fetches
+    pub successes: u64,
+    /// Failed fetches
+    pub errors: u64,
+    /// Fetches that failed in a row since the last success
+    pub consecutive_errors: u32,
+    /// Duration of the last fetch in milliseconds
+    pub last_duration_ms: Option<u64>,
+    /// Sum of all fetch durations in milliseconds
+    pub total_duration_ms: u64,
+    /// HTTP status of the last failed fetch, when it got a response
+    pub last_http_status: Option<u16>,
+    /// Sanitized message of the last failed fetch
+    pub last_error: Option<String>,
+    /// When the last fetch finished
+    pub last_fetch_at: Option<DateTime<Utc>>,
+}
 
-use super::base::{Agent, AgentError, AgentStatus};
-use crate::providers::{Provider, UsageSnapshot};
+impl FetchMetrics {
+    /// Total number of fetches
+    pub fn fetches(&self) -> u64 {
+        self.successes + self.errors
+    }
 
-/// Callback type for when usage data is updated
-pub type UsageCallback = Box<dyn Fn(&str, &UsageSnapshot) + Send + Sync>;
+    /// Share of fetches that succeeded (0.0 - 1.0), if any ran
+    pub fn success_rate(&self) -> Option<f64> {
+        match self.fetches() {
+            0 => None,
+            n => Some(self.successes as f64 / n as f64),
+        }
+    }
+
+    /// Average fetch duration in milliseconds, if any ran
+    pub fn average_duration_ms(&self) -> Option<f64> {
+        match self.fetches() {
+            0 => None,
+            n => Some(self.total_duration_ms as f64 / n as f64),
+        }
+    }
+
+    fn record(&mut self, duration: Duration, result: Result<(), &ProviderError>) {
+        let duration_ms = duration.as_millis() as u64;
+        self.last_duration_ms = Some(duration_ms);
+        self.total_duration_ms += duration_ms;
+        self.last_fetch_at = Some(Utc::now());
+        match result {
+            Ok(()) => {
+                self.successes += 1;
+                self.consecutive_errors = 0;
+            }
+            Err(e) => {
+                self.errors += 1;
+                self.consecutive_errors += 1;
+                self.last_http_status = e.http_status();
+                self.last_error = Some(Sanitizer::sanitize_free_text(&e.to_string()));
+            }
+        }
+    }
+}
 
 /// Configuration for the refresh agent
 #[derive(Debug, Clone)]
@@ -22,6 +120,11 @@ pub struct RefreshConfig {
     pub interval: Duration,
     /// Whether to fetch immediately on start
     pub fetch_on_start: bool,
+    /// Spread provider fetches across the interval instead of fetching all at once
+    pub stagger: bool,
+    /// Snapshots within this of the last one sent to subscribers are only
+    /// cached, not sent
+    pub change_tolerance: ChangeTolerance,
 }
 
 impl Default for RefreshConfig {
@@ -29,6 +132,8 @@ impl Default for RefreshConfig {
         Self {
             interval: Duration::from_secs(5 * 60), // 5 minutes
             fetch_on_start: true,
+            stagger: true,
+            change_tolerance: ChangeTolerance::default(),
         }
     }
 }
@@ -39,6 +144,28 @@ impl RefreshConfig {
         Self {
             interval: Duration::from_secs(minutes * 60),
             fetch_on_start: true,
+            stagger: true,
+            change_tolerance: ChangeTolerance::default(),
+        }
+    }
+
+    /// Time between two staggered fetches when refreshing `providers` providers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use gptbar_lib::agents::RefreshConfig;
+    ///
+    /// let config = RefreshConfig::with_interval_minutes(4);
+    /// assert_eq!(config.tick(4), Duration::from_secs(60));
+    /// assert_eq!(config.tick(0), Duration::from_secs(240));
+    /// ```
+    pub fn tick(&self, providers: usize) -> Duration {
+        if self.stagger && providers > 1 {
+            self.interval / providers as u32
+        } else {
+            self.interval
         }
     }
 
@@ -47,18 +174,32 @@ impl RefreshConfig {
         Self {
             interval: Duration::from_secs(seconds),
             fetch_on_start: true,
+            stagger: true,
+            change_tolerance: ChangeTolerance::default(),
         }
     }
 }
 
 /// Agent that periodically refreshes usage data from providers
 pub struct RefreshAgent {
-    config: RefreshConfig,
+    config: std::sync::RwLock<RefreshConfig>,
     providers: RwLock<Vec<Arc<dyn Provider>>>,
     status: RwLock<AgentStatus>,
-    cancel_token: CancellationToken,
+    cancel_token: StopSignal,
     snapshots: RwLock<std::collections::HashMap<String, UsageSnapshot>>,
-    on_update: RwLock<Option<UsageCallback>>,
+    /// Last snapshot of each provider sent to subscribers
+    sent: RwLock<HashMap<String, UsageSnapshot>>,
+    updates: broadcast::Sender<UsageUpdate>,
+    refreshing_stale: AtomicBool,
+    metrics: RwLock<HashMap<String, FetchMetrics>>,
+    next_refresh_at: Mutex<Option<DateTime<Utc>>>,
+    ready: watch::Sender<bool>,
+    /// Learns providers' auth states from fetch results
+    auth_states: Option<Arc<AuthStateTracker>>,
+    /// Providers that rate limited us, with when to try them again
+    throttled: Mutex<HashMap<String, DateTime<Utc>>>,
+    /// Provider IDs of the active profile; all providers when unset
+    active: std::sync::RwLock<Option<Vec<String>>>,
 }
 
 impl RefreshAgent {
@@ -70,12 +211,20 @@ impl RefreshAgent {
     /// Creates a new RefreshAgent with custom configuration
     pub fn with_config(config: RefreshConfig) -> Self {
         Self {
-            config,
+            config: std::sync::RwLock::new(config),
             providers: RwLock::new(Vec::new()),
             status: RwLock::new(AgentStatus::Idle),
-            cancel_token: CancellationToken::new(),
+            cancel_token: StopSignal::new(),
             snapshots: RwLock::new(std::collections::HashMap::new()),
-            on_update: RwLock::new(None),
+            sent: RwLock::new(HashMap::new()),
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            refreshing_stale: AtomicBool::new(false),
+            metrics: RwLock::new(HashMap::new()),
+            next_refresh_at: Mutex::new(None),
+            ready: watch::channel(false).0,
+            auth_states: None,
+            throttled: Mutex::new(HashMap::new()),
+            active: std::sync::RwLock::new(None),
         }
     }
 
@@ -84,22 +233,61 @@ impl RefreshAgent {
         Self::with_config(RefreshConfig::with_interval_minutes(minutes))
     }
 
+    /// Records in `tracker` what each fetch tells about the provider's
+    /// credentials
+    pub fn with_auth_states(mut self, tracker: Arc<AuthStateTracker>) -> Self {
+        self.auth_states = Some(tracker);
+        self
+    }
+
     /// Adds a provider to monitor
     pub async fn add_provider(&self, provider: Arc<dyn Provider>) {
         self.providers.write().await.push(provider);
     }
 
+    /// Reorders providers so they are refreshed in `order`
+    ///
+    /// Providers not listed keep their relative order after the listed ones.
+    pub async fn set_order(&self, order: &[String]) {
+        let rank = |id: &str| order.iter().position(|p| p == id).unwrap_or(usize::MAX);
+        self.providers
+            .write()
+            .await
+            .sort_by_key(|p| rank(p.id()));
+    }
+
+    /// Limits background fetches to the providers of a profile
+    ///
+    /// `None` fetches every provider with credentials again.
+    pub fn set_active_providers(&self, providers: Option<&[String]>) {
+        *self.active.write().unwrap_or_else(|e| e.into_inner()) = providers.map(<[_]>::to_vec);
+    }
+
+    /// Whether a provider is refreshed: it has credentials and is in the
+    /// active profile, if there is one
+    fn is_refreshed(&self, provider: &dyn Provider) -> bool {
+        provider.is_enabled()
+            && self
+                .active
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_ref()
+                .is_none_or(|ids| ids.iter().any(|id| id == provider.id()))
+    }
+
     /// Removes all providers
     pub async fn clear_providers(&self) {
         self.providers.write().await.clear();
     }
 
-    /// Sets a callback to be called when usage data is updated
-    pub async fn on_update<F>(&self, callback: F)
-    where
-        F: Fn(&str, &UsageSnapshot) + Send + Sync + 'static,
-    {
-        *self.on_update.write().await = Some(Box::new(callback));
+    /// Subscribes to new snapshots
+    ///
+    /// Each receiver gets every update fetched after it subscribed, except
+    /// snapshots that didn't change beyond [`RefreshConfig::change_tolerance`];
+    /// one that falls too far behind misses the oldest. See
+    /// [`crate::events::consume`].
+    pub fn subscribe(&self) -> broadcast::Receiver<UsageUpdate> {
+        self.updates.subscribe()
     }
 
     /// Gets the current snapshot for a provider
@@ -112,35 +300,236 @@ impl RefreshAgent {
         self.snapshots.read().await.clone()
     }
 
+    /// Latest and last sent snapshots held, one of each per provider
+    pub async fn memory(&self) -> MemoryUsage {
+        let snapshots = self.snapshots.read().await;
+        let sent = self.sent.read().await;
+        MemoryUsage {
+            entries: snapshots.len() + sent.len(),
+            bytes: memory::json_size(&*snapshots) + memory::json_size(&*sent),
+        }
+    }
+
+    /// Gets the current configuration
+    pub fn config(&self) -> RefreshConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Gets the fetch metrics of every provider fetched so far
+    pub async fn metrics(&self) -> HashMap<String, FetchMetrics> {
+        self.metrics.read().await.clone()
+    }
+
+    /// When a provider that rate limited us can be fetched again, if not yet
+    pub fn throttled_until(&self, provider_id: &str) -> Option<DateTime<Utc>> {
+        self.throttled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(provider_id)
+            .copied()
+            .filter(|until| *until > Utc::now())
+    }
+
+    /// Holds off fetching a provider that asked us to wait `retry_after`
+    pub fn throttle(&self, provider_id: &str, retry_after: Duration) {
+        let until = Utc::now() + chrono::Duration::from_std(retry_after).unwrap_or_default();
+        tracing::info!("{} is rate limited, retrying at {}", provider_id, until);
+        self.throttled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(provider_id.to_string(), until);
+    }
+
     /// Fetches data from all providers once
     async fn fetch_all(&self) {
         let providers = self.providers.read().await.clone();
 
         for provider in providers {
-            if !provider.is_enabled() {
+            if self.is_refreshed(provider.as_ref()) {
+                self.fetch_provider(provider.as_ref()).await;
+            }
+        }
+    }
+
+    /// Fetches the provider whose turn it is, then advances to the next one
+    ///
+    /// Without staggering every provider is fetched.
+    async fn fetch_next(&self, turn: &mut usize) {
+        if !self.config().stagger {
+            self.fetch_all().await;
+            return;
+        }
+
+        let providers: Vec<_> = self
+            .providers
+            .read()
+            .await
+            .iter()
+            .filter(|p| self.is_refreshed(p.as_ref()))
+            .cloned()
+            .collect();
+        if providers.is_empty() {
+            return;
+        }
+
+        let provider = &providers[*turn % providers.len()];
+        *turn = (*turn + 1) % providers.len();
+        self.fetch_provider(provider.as_ref()).await;
+    }
+
+    /// Records when the next scheduled fetch happens
+    fn set_next_refresh(&self, deadline: Option<tokio::time::Instant>) {
+        let at = deadline.map(|deadline| {
+            let wait = deadline.saturating_duration_since(tokio::time::Instant::now());
+            Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default()
+        });
+        *self
+            .next_refresh_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = at;
+    }
+
+    /// Starts a schedule ticking every `period`, first one `period` from now
+    fn schedule(&self, period: Duration) -> tokio::time::Interval {
+        let first = tokio::time::Instant::now() + period;
+        let mut ticker = tokio::time::interval_at(first, period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        self.set_next_refresh(Some(first));
+        ticker
+    }
+
+    /// Number of providers refreshed in the background
+    async fn enabled_count(&self) -> usize {
+        self.providers
+            .read()
+            .await
+            .iter()
+            .filter(|p| self.is_refreshed(p.as_ref()))
+            .count()
+    }
+
+    /// Fetches providers whose snapshot is missing or older than `max_age`
+    ///
+    /// Used to pre-warm the popup when it opens. Returns the IDs that were
+    /// fetched; calls made while another one is running fetch nothing.
+    pub async fn refresh_stale(&self, max_age: chrono::Duration) -> Vec<String> {
+        if self.refreshing_stale.swap(true, Ordering::SeqCst) {
+            return Vec::new();
+        }
+
+        let providers = self.providers.read().await.clone();
+        let now = chrono::Utc::now();
+        let mut refreshed = Vec::new();
+
+        for provider in providers {
+            if !self.is_refreshed(provider.as_ref()) {
                 continue;
             }
+            let stale = self
+                .snapshots
+                .read()
+                .await
+                .get(provider.id())
+                .is_none_or(|s| s.is_stale(max_age, now));
+            if stale {
+                self.fetch_provider(provider.as_ref()).await;
+                refreshed.push(provider.id().to_string());
+            }
+        }
 
-            let provider_id = provider.id().to_string();
+        self.refreshing_stale.store(false, Ordering::SeqCst);
+        refreshed
+    }
 
-            match provider.fetch().await {
-                Ok(snapshot) => {
-                    tracing::debug!("Fetched usage for {}: {:?}", provider_id, snapshot);
+    /// Fetches one provider, storing the snapshot and notifying listeners if
+    /// it changed
+    #[tracing::instrument(name = "fetch", skip_all, fields(provider_id = provider.id()))]
+    async fn fetch_provider(&self, provider: &dyn Provider) {
+        let provider_id = provider.id().to_string();
+        if let Some(until) = self.throttled_until(&provider_id) {
+            tracing::debug!("Skipping {}, rate limited until {}", provider_id, until);
+            return;
+        }
 
-                    // Store the snapshot
-                    self.snapshots
-                        .write()
-                        .await
-                        .insert(provider_id.clone(), snapshot.clone());
+        let started = Instant::now();
+        let result = provider.fetch().await;
+        self.metrics
+            .write()
+            .await
+            .entry(provider_id.clone())
+            .or_default()
+            .record(started.elapsed(), result.as_ref().map(|_| ()));
+        crate::telemetry::record_fetch(&provider_id, result.as_ref().err().map(|e| e.code()));
+
+        if let Some(auth_states) = &self.auth_states {
+            let expires_at = match &result {
+                Ok(_) => provider.credentials_expire_at().await,
+                Err(_) => None,
+            };
+            if let Some(observation) = AuthObservation::of_fetch(&result, expires_at) {
+                auth_states.observe(&provider_id, &observation);
+            }
+        }
 
-                    // Call the callback if set
-                    if let Some(ref callback) = *self.on_update.read().await {
-                        callback(&provider_id, &snapshot);
-                    }
+        match &result {
+            Ok(_) => {
+                self.throttled
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&provider_id);
+            }
+            // Without a Retry-After the next scheduled fetch tries again
+            Err(e) => {
+                if let Some(retry_after) = e.retry_after() {
+                    self.throttle(&provider_id, retry_after);
                 }
-                Err(e) => {
-                    tracing::warn!("Failed to fetch usage for {}: {}", provider_id, e);
+                events::publish(AppEvent::FetchFailed {
+                    provider_id: provider_id.clone(),
+                    code: e.code(),
+                });
+            }
+        }
+
+        match result {
+            Ok(snapshot) => {
+                tracing::debug!("Fetched usage for {}: {:?}", provider_id, snapshot);
+
+                // Store the snapshot
+                self.snapshots
+                    .write()
+                    .await
+                    .insert(provider_id.clone(), snapshot.clone());
+
+                // Skip re-rendering and history rows for polls where usage
+                // didn't move
+                let tolerance = self.config().change_tolerance;
+                {
+                    let mut sent = self.sent.write().await;
+                    if sent
+                        .get(&provider_id)
+                        .is_some_and(|last| !snapshot.changed_since(last, &tolerance))
+                    {
+                        tracing::debug!("Usage for {} unchanged, not notifying", provider_id);
+                        return;
+                    }
+                    sent.insert(provider_id.clone(), snapshot.clone());
                 }
+
+                // Notify subscribers; fails only when there are none
+                let _ = self.updates.send(UsageUpdate {
+                    provider_id,
+                    snapshot,
+                });
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to fetch usage for {}: {}",
+                    provider_id,
+                    Sanitizer::sanitize_free_text(&e.to_string())
+                );
             }
         }
     }
@@ -182,28 +571,46 @@ impl Agent for RefreshAgent {
         // Set status to running
         *self.status.write().await = AgentStatus::Running;
 
-        // Reset cancellation token
-        // Note: In a real implementation, we'd need to handle this differently
-        // since CancellationToken doesn't have a reset method
+        // A fresh token, so the agent can be started again after a stop
+        let cancel_token = self.cancel_token.renew();
 
         // Fetch immediately if configured
-        if self.config.fetch_on_start {
+        if self.config().fetch_on_start {
             self.fetch_all().await;
         }
 
-        // Main loop
+        // Main loop, on an absolute schedule; ticks missed while the machine
+        // slept are skipped rather than fetched in a burst
+        let mut turn = 0;
+        let mut period = self.config().tick(self.enabled_count().await);
+        let mut ticker = self.schedule(period);
+        self.ready.send_replace(true);
+
         loop {
             tokio::select! {
-                _ = tokio::time::sleep(self.config.interval) => {
-                    self.fetch_all().await;
+                scheduled = ticker.tick() => {
+                    self.fetch_next(&mut turn).await;
+
+                    // Providers were added or removed, or the interval
+                    // changed: re-spread the ticks
+                    let tick = self.config().tick(self.enabled_count().await);
+                    if tick != period {
+                        period = tick;
+                        ticker = self.schedule(period);
+                    } else {
+                        let next = next_tick(scheduled, period, tokio::time::Instant::now());
+                        self.set_next_refresh(Some(next));
+                    }
                 }
-                _ = self.cancel_token.cancelled() => {
+                _ = cancel_token.cancelled() => {
                     tracing::info!("Refresh agent cancelled");
                     break;
                 }
             }
         }
 
+        self.set_next_refresh(None);
+        self.ready.send_replace(false);
         *self.status.write().await = AgentStatus::Stopped;
         Ok(())
     }
@@ -227,33 +634,149 @@ impl Agent for RefreshAgent {
         Ok(())
     }
 
+    async fn ready(&self) {
+        let mut ready = self.ready.subscribe();
+        let _ = ready.wait_for(|ready| *ready).await;
+    }
+
     async fn trigger(&self) -> Result<(), AgentError> {
         self.fetch_all().await;
         Ok(())
     }
+
+    fn next_run(&self) -> Option<DateTime<Utc>> {
+        *self
+            .next_refresh_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn settings(&self) -> Option<serde_json::Value> {
+        let config = self.config();
+        let settings = RefreshSettings {
+            interval_minutes: Some(config.interval.as_secs() / 60),
+            stagger: Some(config.stagger),
+            min_change_percent: Some(config.change_tolerance.percent),
+            min_reset_shift_seconds: Some(config.change_tolerance.reset_shift.num_seconds() as u64),
+        };
+        serde_json::to_value(settings).ok()
+    }
+
+    /// A new interval takes effect after the next scheduled fetch
+    async fn configure(&self, config: serde_json::Value) -> Result<(), AgentError> {
+        let settings: RefreshSettings =
+            serde_json::from_value(config).map_err(|e| AgentError::InvalidConfig(e.to_string()))?;
+        if settings
+            .interval_minutes
+            .is_some_and(|minutes| !(1..=MAX_INTERVAL_MINUTES).contains(&minutes))
+        {
+            return Err(AgentError::InvalidConfig(format!(
+                "interval must be between 1 and {} minutes",
+                MAX_INTERVAL_MINUTES
+            )));
+        }
+        if settings
+            .min_change_percent
+            .is_some_and(|percent| !(0.0..=100.0).contains(&percent))
+        {
+            return Err(AgentError::InvalidConfig(
+                "change tolerance must be between 0 and 100 percent".into(),
+            ));
+        }
+        let reset_shift = settings
+            .min_reset_shift_seconds
+            .map(|seconds| {
+                i64::try_from(seconds)
+                    .ok()
+                    .and_then(chrono::Duration::try_seconds)
+                    .ok_or_else(|| {
+                        AgentError::InvalidConfig("reset shift tolerance is too long".into())
+                    })
+            })
+            .transpose()?;
+
+        let mut config = self.config.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(minutes) = settings.interval_minutes {
+            config.interval = Duration::from_secs(minutes * 60);
+        }
+        if let Some(stagger) = settings.stagger {
+            config.stagger = stagger;
+        }
+        if let Some(percent) = settings.min_change_percent {
+            config.change_tolerance.percent = percent;
+        }
+        if let Some(reset_shift) = reset_shift {
+            config.change_tolerance.reset_shift = reset_shift;
+        }
+        Ok(())
+    }
+}
+
+/// Settings of the refresh agent, as passed to [`Agent::configure`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RefreshSettings {
+    /// Minutes between two refreshes of every provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval_minutes: Option<u64>,
+    /// Spread provider fetches across the interval
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stagger: Option<bool>,
+    /// Usage changes up to this many percentage points aren't sent to
+    /// subscribers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_change_percent: Option<f64>,
+    /// Reset time shifts up to this many seconds aren't sent to subscribers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_reset_shift_seconds: Option<u64>,
+}
+
+/// Deadline of the tick after one scheduled at `scheduled`, skipping any
+/// already missed at `now`, as `MissedTickBehavior::Skip` does
+fn next_tick(
+    scheduled: tokio::time::Instant,
+    period: Duration,
+    now: tokio::time::Instant,
+) -> tokio::time::Instant {
+    let next = scheduled + period;
+    if next > now || period.is_zero() {
+        return next;
+    }
+    let missed = (now - next).as_nanos() / period.as_nanos() + 1;
+    next + period * missed as u32
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::providers::{ProviderError, RateWindow};
+    use crate::providers::{AuthState, ProviderError, RateWindow};
     use std::sync::atomic::{AtomicU32, Ordering};
 
     // Mock provider for testing
     struct MockProvider {
         fetch_count: Arc<AtomicU32>,
+        fail: bool,
     }
 
     impl MockProvider {
         fn new() -> Self {
             Self {
                 fetch_count: Arc::new(AtomicU32::new(0)),
+                fail: false,
             }
         }
 
         fn with_counter(counter: Arc<AtomicU32>) -> Self {
             Self {
                 fetch_count: counter,
+                fail: false,
+            }
+        }
+
+        fn failing() -> Self {
+            Self {
+                fail: true,
+                ..Self::new()
             }
         }
 
@@ -278,6 +801,9 @@ mod tests {
 
         async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
             self.fetch_count.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(ProviderError::AuthFailed("token expired".to_string()));
+            }
             Ok(UsageSnapshot::new().with_primary(RateWindow::new(50.0)))
         }
 
@@ -307,6 +833,178 @@ mod tests {
         assert_eq!(config.interval, Duration::from_secs(600));
     }
 
+    #[test]
+    fn test_refresh_config_tick() {
+        let config = RefreshConfig::with_interval_seconds(300);
+        assert_eq!(config.tick(1), Duration::from_secs(300));
+        assert_eq!(config.tick(3), Duration::from_secs(100));
+
+        let unstaggered = RefreshConfig {
+            stagger: false,
+            ..config
+        };
+        assert_eq!(unstaggered.tick(3), Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_fetch_next_rotates() {
+        let agent = RefreshAgent::new();
+        let first = Arc::new(AtomicU32::new(0));
+        let second = Arc::new(AtomicU32::new(0));
+        agent
+            .add_provider(Arc::new(MockProvider::with_counter(first.clone())))
+            .await;
+        agent
+            .add_provider(Arc::new(MockProvider::with_counter(second.clone())))
+            .await;
+
+        let mut turn = 0;
+        agent.fetch_next(&mut turn).await;
+        assert_eq!(
+            (first.load(Ordering::SeqCst), second.load(Ordering::SeqCst)),
+            (1, 0)
+        );
+
+        agent.fetch_next(&mut turn).await;
+        agent.fetch_next(&mut turn).await;
+        assert_eq!(
+            (first.load(Ordering::SeqCst), second.load(Ordering::SeqCst)),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    fn test_next_tick_skips_missed_ticks() {
+        let start = tokio::time::Instant::now();
+        let period = Duration::from_secs(60);
+
+        // On time: the next tick is one period later
+        assert_eq!(
+            next_tick(start, period, start + Duration::from_secs(5)),
+            start + period
+        );
+
+        // After sleeping for 3.5 periods the schedule stays aligned
+        assert_eq!(
+            next_tick(start, period, start + Duration::from_secs(210)),
+            start + Duration::from_secs(240)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_reports_next_run() {
+        let agent = Arc::new(RefreshAgent::with_config(RefreshConfig {
+            fetch_on_start: false,
+            ..RefreshConfig::with_interval_seconds(60)
+        }));
+        agent.add_provider(Arc::new(MockProvider::new())).await;
+        assert_eq!(agent.next_run(), None);
+
+        let running = agent.clone();
+        let handle = tokio::spawn(async move { running.start().await });
+        agent.ready().await;
+
+        let next = agent.next_run().expect("next run while running");
+        let wait = next - Utc::now();
+        assert!(wait > chrono::Duration::seconds(55) && wait <= chrono::Duration::seconds(60));
+
+        agent.stop().await.unwrap();
+        handle.await.unwrap().unwrap();
+        assert_eq!(agent.next_run(), None);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_ready_after_first_fetch() {
+        let agent = Arc::new(RefreshAgent::with_interval(5));
+        let counter = Arc::new(AtomicU32::new(0));
+        agent
+            .add_provider(Arc::new(MockProvider::with_counter(counter.clone())))
+            .await;
+
+        let running = agent.clone();
+        let handle = tokio::spawn(async move { running.start().await });
+        agent.ready().await;
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert!(agent.get_snapshot("mock").await.is_some());
+
+        agent.stop().await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_restarts_after_stop() {
+        let agent = Arc::new(RefreshAgent::with_config(RefreshConfig {
+            fetch_on_start: false,
+            ..RefreshConfig::with_interval_seconds(60)
+        }));
+
+        for _ in 0..2 {
+            let running = agent.clone();
+            let handle = tokio::spawn(async move { running.start().await });
+            tokio::task::yield_now().await;
+            assert!(agent.status().is_running());
+
+            agent.stop().await.unwrap();
+            handle.await.unwrap().unwrap();
+            assert_eq!(agent.status(), AgentStatus::Stopped);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_configure() {
+        let agent = RefreshAgent::new();
+
+        agent
+            .configure(serde_json::json!({ "interval_minutes": 15, "stagger": false }))
+            .await
+            .unwrap();
+        let config = agent.config();
+        assert_eq!(config.interval, Duration::from_secs(900));
+        assert!(!config.stagger);
+
+        assert!(agent
+            .configure(serde_json::json!({ "interval_minutes": 0 }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "interval_minutes": u64::MAX }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "interval": "fast" }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "min_change_percent": -1.0 }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "min_reset_shift_seconds": u64::MAX }))
+            .await
+            .is_err());
+        assert!(agent
+            .configure(serde_json::json!({ "min_reset_shift_seconds": 10_000_000_000_000_000u64 }))
+            .await
+            .is_err());
+        assert_eq!(agent.config().interval, Duration::from_secs(900));
+
+        agent
+            .configure(
+                serde_json::json!({ "min_change_percent": 0.5, "min_reset_shift_seconds": 300 }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            agent.settings(),
+            Some(serde_json::json!({
+                "interval_minutes": 15,
+                "stagger": false,
+                "min_change_percent": 0.5,
+                "min_reset_shift_seconds": 300,
+            }))
+        );
+    }
+
     #[test]
     fn test_refresh_agent_new() {
         let agent = RefreshAgent::new();
@@ -347,22 +1045,134 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_refresh_agent_callback() {
+    async fn test_refresh_agent_metrics() {
         let agent = RefreshAgent::new();
-        let provider = Arc::new(MockProvider::new());
-        let callback_count = Arc::new(AtomicU32::new(0));
-        let callback_count_clone = callback_count.clone();
+        agent.add_provider(Arc::new(MockProvider::new())).await;
+        assert!(agent.metrics().await.is_empty());
 
-        agent.add_provider(provider).await;
+        agent.trigger().await.unwrap();
+        agent.trigger().await.unwrap();
+
+        let metrics = agent.metrics().await;
+        let mock = &metrics["mock"];
+        assert_eq!(mock.successes, 2);
+        assert_eq!(mock.errors, 0);
+        assert_eq!(mock.success_rate(), Some(1.0));
+        assert!(mock.last_duration_ms.is_some());
+        assert!(mock.last_fetch_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_metrics_count_errors() {
+        let agent = RefreshAgent::new();
+        agent.add_provider(Arc::new(MockProvider::failing())).await;
+
+        agent.trigger().await.unwrap();
+
+        let metrics = agent.metrics().await;
+        let mock = &metrics["mock"];
+        assert_eq!(mock.errors, 1);
+        assert_eq!(mock.success_rate(), Some(0.0));
+        assert_eq!(mock.last_http_status, None);
+        let error = mock.last_error.as_deref().unwrap();
+        assert!(error.contains("token expired"));
+        assert!(agent.get_snapshot("mock").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_records_auth_states() {
+        let auth_states = Arc::new(AuthStateTracker::default());
+        let agent = RefreshAgent::new().with_auth_states(auth_states.clone());
+        agent.add_provider(Arc::new(MockProvider::new())).await;
+
+        agent.trigger().await.unwrap();
+        assert_eq!(
+            auth_states.get("mock"),
+            Some(AuthState::Valid { expires_at: None })
+        );
+
+        agent.clear_providers().await;
+        agent.add_provider(Arc::new(MockProvider::failing())).await;
+        agent.trigger().await.unwrap();
+        assert_eq!(auth_states.get("mock"), Some(AuthState::Expired));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_skips_throttled_providers() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let agent = RefreshAgent::new();
         agent
-            .on_update(move |_id, _snapshot| {
-                callback_count_clone.fetch_add(1, Ordering::SeqCst);
-            })
+            .add_provider(Arc::new(MockProvider::with_counter(counter.clone())))
             .await;
 
+        agent.throttle("mock", Duration::from_secs(60));
+        assert!(agent.throttled_until("mock").is_some());
+        agent.trigger().await.unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        // An elapsed throttle no longer applies
+        agent.throttle("mock", Duration::ZERO);
+        assert_eq!(agent.throttled_until("mock"), None);
+        agent.trigger().await.unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_fetch_metrics_averages() {
+        let mut metrics = FetchMetrics::default();
+        assert_eq!(metrics.success_rate(), None);
+        assert_eq!(metrics.average_duration_ms(), None);
+
+        metrics.record(Duration::from_millis(100), Ok(()));
+        metrics.record(
+            Duration::from_millis(300),
+            Err(&ProviderError::Parse("bad json".to_string())),
+        );
+
+        assert_eq!(metrics.fetches(), 2);
+        assert_eq!(metrics.success_rate(), Some(0.5));
+        assert_eq!(metrics.average_duration_ms(), Some(200.0));
+        assert_eq!(metrics.last_duration_ms, Some(300));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_subscribe() {
+        let agent = RefreshAgent::new();
+        let provider = Arc::new(MockProvider::new());
+        let mut updates = agent.subscribe();
+
+        agent.add_provider(provider).await;
+        agent.trigger().await.unwrap();
+
+        let update = updates.try_recv().unwrap();
+        assert_eq!(update.provider_id, "mock");
+        assert_eq!(update.snapshot.primary.unwrap().used_percent, 50.0);
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_skips_unchanged_snapshots() {
+        let agent = RefreshAgent::new();
+        let mut updates = agent.subscribe();
+        agent.add_provider(Arc::new(MockProvider::new())).await;
+
+        agent.trigger().await.unwrap();
+        agent.trigger().await.unwrap();
+        assert!(updates.try_recv().is_ok());
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_multiple_subscribers() {
+        let agent = RefreshAgent::new();
+        let mut first = agent.subscribe();
+        let mut second = agent.subscribe();
+
+        agent.add_provider(Arc::new(MockProvider::new())).await;
         agent.trigger().await.unwrap();
 
-        assert_eq!(callback_count.load(Ordering::SeqCst), 1);
+        assert_eq!(first.try_recv().unwrap().provider_id, "mock");
+        assert_eq!(second.try_recv().unwrap().provider_id, "mock");
     }
 
     #[tokio::test]
@@ -378,6 +1188,56 @@ mod tests {
         assert!(snapshots.contains_key("mock"));
     }
 
+    #[tokio::test]
+    async fn test_refresh_agent_refresh_stale() {
+        let agent = RefreshAgent::new();
+        let counter = Arc::new(AtomicU32::new(0));
+        agent
+            .add_provider(Arc::new(MockProvider::with_counter(counter.clone())))
+            .await;
+
+        // Missing snapshots count as stale
+        let refreshed = agent.refresh_stale(chrono::Duration::minutes(5)).await;
+        assert_eq!(refreshed, vec!["mock".to_string()]);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        // A fresh snapshot is left alone
+        let refreshed = agent.refresh_stale(chrono::Duration::minutes(5)).await;
+        assert!(refreshed.is_empty());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_active_providers() {
+        let agent = RefreshAgent::new();
+        let counter = Arc::new(AtomicU32::new(0));
+        agent
+            .add_provider(Arc::new(MockProvider::with_counter(counter.clone())))
+            .await;
+
+        agent.set_active_providers(Some(&["claude".to_string()]));
+        assert_eq!(agent.enabled_count().await, 0);
+        agent.fetch_all().await;
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        agent.set_active_providers(None);
+        assert_eq!(agent.enabled_count().await, 1);
+        agent.fetch_all().await;
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_agent_set_order() {
+        let agent = RefreshAgent::new();
+        agent.add_provider(Arc::new(MockProvider::new())).await;
+        agent.add_provider(Arc::new(crate::providers::DemoProvider::new())).await;
+
+        agent.set_order(&["demo".to_string()]).await;
+
+        let ids: Vec<_> = agent.providers.read().await.iter().map(|p| p.id()).collect();
+        assert_eq!(ids, vec!["demo", "mock"]);
+    }
+
     #[tokio::test]
     async fn test_refresh_agent_clear_providers() {
         let agent = RefreshAgent::new();
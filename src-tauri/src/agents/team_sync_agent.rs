@@ -0,0 +1,119 @@
+//! Team sync agent - Publishes usage to a shared team endpoint
+//!
+//! Only registered when `team_sync` is on. After new snapshots arrive from
+//! the refresh agent, publishes the shared providers' windows as a
+//! [`MemberUsage`], at most once per [`PUBLISH_INTERVAL`]. See
+//! [`crate::team`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+use tokio::time::MissedTickBehavior;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use super::refresh_agent::RefreshAgent;
+use crate::team::{MemberUsage, TeamClient, TeamSyncSettings};
+
+/// Shortest delay between two publishes
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Agent that publishes usage to the team endpoint
+pub struct TeamSyncAgent {
+    settings: TeamSyncSettings,
+    client: TeamClient,
+    refresh: Arc<RefreshAgent>,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl TeamSyncAgent {
+    /// Creates an agent publishing the snapshots of `refresh` through
+    /// `client`
+    pub fn new(settings: TeamSyncSettings, client: TeamClient, refresh: Arc<RefreshAgent>) -> Self {
+        Self {
+            settings,
+            client,
+            refresh,
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    /// Publishes the latest snapshots, if there are any
+    async fn publish(&self) -> Result<(), String> {
+        let snapshots = self.refresh.get_all_snapshots().await;
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+        self.client
+            .publish(&MemberUsage::new(&self.settings, &snapshots))
+            .await
+    }
+}
+
+#[async_trait]
+impl Agent for TeamSyncAgent {
+    fn id(&self) -> &'static str {
+        "team_sync"
+    }
+
+    fn name(&self) -> &'static str {
+        "Team Sync"
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        let cancel_token = self.cancel_token.renew();
+        let mut updates = self.refresh.subscribe();
+        let mut ticker = tokio::time::interval(PUBLISH_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Snapshots fetched before the agent started are published too
+        let mut pending = true;
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Ok(_) | Err(RecvError::Lagged(_)) => pending = true,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick(), if pending => {
+                    match self.publish().await {
+                        Ok(()) => pending = false,
+                        Err(e) => tracing::warn!("{}", e),
+                    }
+                }
+                _ = cancel_token.cancelled() => break,
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn trigger(&self) -> Result<(), AgentError> {
+        self.publish().await.map_err(AgentError::OperationFailed)
+    }
+}
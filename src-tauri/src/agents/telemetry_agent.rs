@@ -0,0 +1,192 @@
+//! Telemetry agent - Sends opt-in anonymous counters once a day
+//!
+//! Only registered when telemetry is turned on and the build has a
+//! [`TELEMETRY_URL`]. Counts features seen on the event bus and sends the
+//! counters collected by [`telemetry`] as a [`TelemetryBatch`]; a batch that
+//! fails to send is kept for the next attempt.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+
+use super::base::{Agent, AgentError, AgentStatus, StopSignal};
+use crate::config::AppConfig;
+use crate::events::{self, AppEvent};
+use crate::telemetry::{self, TelemetryBatch, TELEMETRY_URL};
+
+/// Delay before the first batch, so short sessions still send one
+const FIRST_SEND_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Delay between batches
+const SEND_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Feature counted for an event, if any
+fn feature_of(event: &AppEvent) -> Option<&'static str> {
+    match event {
+        AppEvent::NotificationSent(_) => Some("notification"),
+        AppEvent::BudgetPeriodStarted(_) => Some("budget_rollover"),
+        AppEvent::DeepLinkOpened(_) => Some("deep_link"),
+        AppEvent::CredentialStoreFallback { .. } => Some("credential_file_fallback"),
+        AppEvent::UsageFrozen { .. } => Some("usage_freeze"),
+        _ => None,
+    }
+}
+
+/// Agent that sends telemetry batches
+pub struct TelemetryAgent {
+    url: String,
+    client: Client,
+    next_send: std::sync::Mutex<Option<DateTime<Utc>>>,
+    status: RwLock<AgentStatus>,
+    cancel_token: StopSignal,
+}
+
+impl TelemetryAgent {
+    /// Creates an agent sending to `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: Client::new(),
+            next_send: std::sync::Mutex::new(None),
+            status: RwLock::new(AgentStatus::Idle),
+            cancel_token: StopSignal::new(),
+        }
+    }
+
+    /// Creates an agent sending to the build's [`TELEMETRY_URL`], if it has
+    /// one
+    pub fn from_build() -> Option<Self> {
+        TELEMETRY_URL.filter(|url| !url.is_empty()).map(Self::new)
+    }
+
+    /// Sends the counters collected so far, if there are any
+    async fn send(&self) -> Result<(), String> {
+        let counters = telemetry::take();
+        if counters.is_empty() {
+            return Ok(());
+        }
+
+        let batch = TelemetryBatch::new(counters, &AppConfig::load());
+        let result = self
+            .client
+            .post(&self.url)
+            .json(&batch)
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|response| match response.status() {
+                status if status.is_success() => Ok(()),
+                status => Err(format!("HTTP {}", status)),
+            });
+        if result.is_err() {
+            telemetry::restore(batch.counters);
+        }
+        result
+    }
+
+    fn schedule(&self, delay: Duration) {
+        let next = chrono::Duration::from_std(delay)
+            .ok()
+            .map(|delay| Utc::now() + delay);
+        *self.next_send.lock().unwrap_or_else(|e| e.into_inner()) = next;
+    }
+}
+
+#[async_trait]
+impl Agent for TelemetryAgent {
+    fn id(&self) -> &'static str {
+        "telemetry"
+    }
+
+    fn name(&self) -> &'static str {
+        "Anonymous Telemetry"
+    }
+
+    fn status(&self) -> AgentStatus {
+        self.status
+            .try_read()
+            .map(|s| s.clone())
+            .unwrap_or(AgentStatus::Idle)
+    }
+
+    async fn start(&self) -> Result<(), AgentError> {
+        {
+            let status = self.status.read().await;
+            if status.is_running() {
+                return Err(AgentError::AlreadyRunning);
+            }
+        }
+
+        *self.status.write().await = AgentStatus::Running;
+
+        let cancel_token = self.cancel_token.renew();
+        let mut events = events::subscribe();
+        let mut delay = FIRST_SEND_DELAY;
+        loop {
+            self.schedule(delay);
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    event = events.recv() => match event {
+                        Ok(event) => {
+                            if let Some(feature) = feature_of(&event) {
+                                telemetry::record_feature(feature);
+                            }
+                        }
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => {
+                            // Nothing more to count; wait for the send
+                            (&mut sleep).await;
+                            break;
+                        }
+                    },
+                    _ = cancel_token.cancelled() => {
+                        *self.status.write().await = AgentStatus::Stopped;
+                        return Ok(());
+                    }
+                }
+            }
+
+            // Unsent counters are kept for the next batch
+            if let Err(e) = self.send().await {
+                tracing::debug!("Failed to send telemetry: {}", e);
+            }
+            delay = SEND_INTERVAL;
+        }
+    }
+
+    async fn stop(&self) -> Result<(), AgentError> {
+        self.cancel_token.cancel();
+        *self.status.write().await = AgentStatus::Stopped;
+        Ok(())
+    }
+
+    async fn trigger(&self) -> Result<(), AgentError> {
+        self.send().await.map_err(AgentError::OperationFailed)
+    }
+
+    fn next_run(&self) -> Option<DateTime<Utc>> {
+        *self.next_send.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_link::DeepLink;
+
+    #[test]
+    fn test_feature_of() {
+        assert_eq!(
+            feature_of(&AppEvent::DeepLinkOpened(DeepLink::Refresh)),
+            Some("deep_link")
+        );
+        assert_eq!(feature_of(&AppEvent::ConfigChanged), None);
+    }
+}
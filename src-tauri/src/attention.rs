@@ -0,0 +1,155 @@
+//! Providers needing the user's attention
+//!
+//! A provider needs attention when its service refused the credentials, a
+//! window is at its critical level, or background fetches keep failing.
+//! [`attention_items`] lists each of these; the tray shows how many there
+//! are and `get_attention_items` returns them to the frontend.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::agents::FetchMetrics;
+use crate::config::AppConfig;
+use crate::providers::{AuthState, UsageSnapshot};
+
+/// Failed fetches in a row before a provider counts as failing
+///
+/// A single failure is usually a network blip the next fetch gets past.
+pub const FAILING_AFTER: u32 = 2;
+
+/// Why a provider needs attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttentionReason {
+    /// The service refused the credentials
+    AuthExpired,
+    /// A window is at its critical level
+    CriticalUsage,
+    /// Background fetches keep failing
+    FetchFailing,
+}
+
+/// Something the user should act on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttentionItem {
+    pub provider_id: String,
+    pub reason: AttentionReason,
+    /// Sanitized message of the last failed fetch, for failing fetches
+    pub detail: Option<String>,
+}
+
+impl AttentionItem {
+    fn new(provider_id: &str, reason: AttentionReason) -> Self {
+        Self {
+            provider_id: provider_id.to_string(),
+            reason,
+            detail: None,
+        }
+    }
+}
+
+/// What needs attention among the active providers, in display order
+///
+/// Failing fetches of a provider whose credentials were refused are left
+/// out, since logging in again fixes both.
+pub fn attention_items(
+    config: &AppConfig,
+    auth_states: &HashMap<String, AuthState>,
+    snapshots: &HashMap<String, UsageSnapshot>,
+    metrics: &HashMap<String, FetchMetrics>,
+) -> Vec<AttentionItem> {
+    let critical = config.critical_providers(snapshots);
+    let mut items = Vec::new();
+    for provider_id in config.active_providers() {
+        let expired = auth_states.get(&provider_id) == Some(&AuthState::Expired);
+        if expired {
+            items.push(AttentionItem::new(
+                &provider_id,
+                AttentionReason::AuthExpired,
+            ));
+        }
+        if critical.contains(&provider_id) {
+            items.push(AttentionItem::new(
+                &provider_id,
+                AttentionReason::CriticalUsage,
+            ));
+        }
+        if let Some(failing) = metrics
+            .get(&provider_id)
+            .filter(|m| !expired && m.consecutive_errors >= FAILING_AFTER)
+        {
+            items.push(AttentionItem {
+                detail: failing.last_error.clone(),
+                ..AttentionItem::new(&provider_id, AttentionReason::FetchFailing)
+            });
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::RateWindow;
+
+    #[test]
+    fn test_attention_items() {
+        let config = AppConfig {
+            enabled_providers: vec!["claude".into(), "openai".into(), "gemini".into()],
+            ..AppConfig::default()
+        };
+        let auth_states = HashMap::from([
+            ("claude".to_string(), AuthState::Expired),
+            ("openai".to_string(), AuthState::Valid { expires_at: None }),
+        ]);
+        let snapshots = HashMap::from([(
+            "openai".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(99.0)),
+        )]);
+        let failing = FetchMetrics {
+            errors: 3,
+            consecutive_errors: 3,
+            last_error: Some("HTTP 502".to_string()),
+            ..Default::default()
+        };
+        let metrics = HashMap::from([
+            ("claude".to_string(), failing.clone()),
+            ("gemini".to_string(), failing),
+            (
+                "openai".to_string(),
+                FetchMetrics {
+                    consecutive_errors: 1,
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        let items = attention_items(&config, &auth_states, &snapshots, &metrics);
+        let reasons: Vec<_> = items
+            .iter()
+            .map(|item| (item.provider_id.as_str(), item.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("claude", AttentionReason::AuthExpired),
+                ("openai", AttentionReason::CriticalUsage),
+                ("gemini", AttentionReason::FetchFailing),
+            ]
+        );
+        assert_eq!(items[2].detail.as_deref(), Some("HTTP 502"));
+    }
+
+    #[test]
+    fn test_disabled_providers_need_no_attention() {
+        let config = AppConfig {
+            enabled_providers: vec!["claude".into()],
+            ..AppConfig::default()
+        };
+        let auth_states = HashMap::from([("openai".to_string(), AuthState::Expired)]);
+        assert!(
+            attention_items(&config, &auth_states, &HashMap::new(), &HashMap::new()).is_empty()
+        );
+    }
+}
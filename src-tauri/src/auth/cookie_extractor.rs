@@ -2,11 +2,52 @@
 //!
 //! Extracts cookies from Chrome, Edge, and Firefox browsers to enable
 //! authentication with web-based AI services.
-
-use rusqlite::Connection;
-use std::path::PathBuf;
+//!
+//! Every profile of every browser is read. Chrome and Edge list their
+//! profiles in the user data dir's `Local State`, and a user data dir
+//! outside the default place is set in [`AppConfig::browser_data_dirs`]. Expired cookies are skipped, and
+//! when several profiles hold a cookie of the same name only the most
+//! recently set one is kept; [`Cookie::source`] tells which profile it came
+//! from.
+//!
+//! Databases are opened read-only in place, write-ahead log included, so
+//! cookies a running browser hasn't checkpointed yet are seen. When the
+//! browser holds the file open exclusively, it is copied together with its
+//! `-wal` and `-shm` files into a directory only the current user can read,
+//! and the copies are deleted as soon as the read is done, even when it
+//! fails.
+
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::config::AppConfig;
+
+/// File name prefix of cookie database copies
+const TEMP_COPY_PREFIX: &str = "gptbar_cookies_";
+
+/// Seconds from 1601-01-01, where Chromium counts time from, to the Unix
+/// epoch
+const CHROMIUM_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Tells apart copies made by concurrent reads in one process
+static COPY_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Common public suffixes of more than one label
+///
+/// Browsers refuse cookies set for a public suffix; these are checked again
+/// so a `.co.uk` cookie never counts as one of a single site. Single-label
+/// suffixes such as `.ai` are always public.
+const MULTI_LABEL_SUFFIXES: &[&str] = &[
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "com.au", "net.au", "org.au", "co.nz", "co.jp", "ne.jp",
+    "co.kr", "co.in", "com.br", "com.cn", "com.mx", "com.ar", "com.tr", "co.za",
+];
+
 /// Errors that can occur during cookie extraction
 #[derive(Debug, Error)]
 pub enum CookieError {
@@ -26,6 +67,10 @@ pub enum CookieError {
     #[error("No cookies found for domain: {0}")]
     NoCookiesFound(String),
 
+    /// The named cookie isn't set for the domain
+    #[error("Cookie {name} not found for domain: {domain}")]
+    CookieNotFound { name: String, domain: String },
+
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -36,7 +81,8 @@ pub enum CookieError {
 }
 
 /// Supported browser types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BrowserType {
     /// Google Chrome
     Chrome,
@@ -62,6 +108,20 @@ impl BrowserType {
     }
 }
 
+/// Browser profile a cookie was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieSource {
+    pub browser: BrowserType,
+    /// Profile directory name, e.g. "Default" or "Profile 1"
+    pub profile: String,
+}
+
+impl fmt::Display for CookieSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.browser.name(), self.profile)
+    }
+}
+
 /// A single cookie extracted from a browser
 #[derive(Debug, Clone)]
 pub struct Cookie {
@@ -79,6 +139,10 @@ pub struct Cookie {
     pub secure: bool,
     /// Whether the cookie is HTTP-only
     pub http_only: bool,
+    /// Creation timestamp (Unix epoch)
+    pub created: Option<i64>,
+    /// Browser profile the cookie was read from
+    pub source: CookieSource,
 }
 
 impl Cookie {
@@ -86,6 +150,84 @@ impl Cookie {
     pub fn to_header_value(&self) -> String {
         format!("{}={}", self.name, self.value)
     }
+
+    /// Whether a browser would send this cookie to `host`
+    ///
+    /// A cookie stored for `claude.ai` is only sent to that host. One stored
+    /// for `.claude.ai` is also sent to its subdomains, unless the domain is
+    /// a public suffix.
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('.').to_ascii_lowercase();
+        match self.domain.strip_prefix('.') {
+            None => self.domain.eq_ignore_ascii_case(&host),
+            Some(domain) => {
+                let domain = domain.to_ascii_lowercase();
+                !is_public_suffix(&domain)
+                    && (host == domain || host.ends_with(&format!(".{}", domain)))
+            }
+        }
+    }
+
+    /// Whether the cookie expired by `now` (Unix epoch); session cookies
+    /// never do
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Orders cookies of one name: the most recently created wins, as it
+    /// comes from the latest sign-in, then the latest expiring, then the one
+    /// for the most specific domain and path, which the browser sends first
+    fn preference(&self) -> (Option<i64>, i64, usize, usize) {
+        (
+            self.created,
+            self.expires.unwrap_or(i64::MAX),
+            self.domain.trim_start_matches('.').len(),
+            self.path.len(),
+        )
+    }
+}
+
+/// Keeps the preferred cookie of each name, in the order names first appear
+///
+/// On a tie the earlier cookie stays, so browsers keep their preference
+/// order.
+fn deduplicate(cookies: impl IntoIterator<Item = Cookie>) -> Vec<Cookie> {
+    let mut kept: Vec<Cookie> = Vec::new();
+    for cookie in cookies {
+        match kept.iter_mut().find(|kept| kept.name == cookie.name) {
+            Some(kept) if cookie.preference() > kept.preference() => *kept = cookie,
+            Some(_) => {}
+            None => kept.push(cookie),
+        }
+    }
+    kept
+}
+
+/// Converts a Chromium timestamp, in microseconds since 1601, to the Unix
+/// epoch; zero means unset
+fn chromium_time(value: i64) -> Option<i64> {
+    (value != 0).then(|| value / 1_000_000 - CHROMIUM_EPOCH_OFFSET)
+}
+
+/// Whether `domain` is a public suffix, under which anyone can register
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || MULTI_LABEL_SUFFIXES.contains(&domain)
+}
+
+/// The registrable part of `host` (eTLD+1), e.g. `claude.ai` for
+/// `api.claude.ai` and `bbc.co.uk` for `www.bbc.co.uk`
+fn registrable_domain(host: &str) -> &str {
+    let labels = |count: usize| {
+        host.rmatch_indices('.')
+            .nth(count - 1)
+            .map_or(host, |(dot, _)| &host[dot + 1..])
+    };
+    let suffix = labels(2);
+    if is_public_suffix(suffix) {
+        labels(3)
+    } else {
+        suffix
+    }
 }
 
 /// Cookie extractor for Windows browsers
@@ -100,62 +242,70 @@ impl CookieExtractor {
         Self
     }
 
-    /// Returns the cookie database path for a browser
+    /// Returns the cookie database path of a browser's default profile
     pub fn cookie_path(browser: BrowserType) -> Result<PathBuf, CookieError> {
-        let local_app_data = std::env::var("LOCALAPPDATA")
-            .map_err(|_| CookieError::EnvVar("LOCALAPPDATA".into()))?;
-        let app_data = std::env::var("APPDATA")
-            .map_err(|_| CookieError::EnvVar("APPDATA".into()))?;
+        // Never empty
+        Ok(Self::profile_databases(browser)?.remove(0).1)
+    }
+
+    /// Returns the cookie database of every profile of a browser, the
+    /// default profile first
+    pub fn profile_databases(
+        browser: BrowserType,
+    ) -> Result<Vec<(CookieSource, PathBuf)>, CookieError> {
+        Self::databases_in(browser, &Self::profiles_dir(browser)?)
+    }
+
+    /// Cookie databases of the profiles in `root`, failing when there are
+    /// none
+    fn databases_in(
+        browser: BrowserType,
+        root: &Path,
+    ) -> Result<Vec<(CookieSource, PathBuf)>, CookieError> {
+        let databases = find_profile_databases(browser, root);
+        if databases.is_empty() {
+            return Err(CookieError::DatabaseNotFound {
+                browser: browser.name().into(),
+                path: root.to_string_lossy().into(),
+            });
+        }
+        Ok(databases)
+    }
+
+    /// Returns the directory holding a browser's profiles, the user data
+    /// dir of Chrome and Edge
+    ///
+    /// [`AppConfig::browser_data_dirs`] overrides the default location.
+    fn profiles_dir(browser: BrowserType) -> Result<PathBuf, CookieError> {
+        if let Some(dir) = AppConfig::load().browser_data_dirs.remove(&browser) {
+            return Ok(dir);
+        }
+
+        let env_dir = |name: &str| {
+            std::env::var(name)
+                .map(PathBuf::from)
+                .map_err(|_| CookieError::EnvVar(name.into()))
+        };
 
-        let path = match browser {
-            BrowserType::Chrome => PathBuf::from(&local_app_data)
+        Ok(match browser {
+            BrowserType::Chrome => env_dir("LOCALAPPDATA")?
                 .join("Google")
                 .join("Chrome")
-                .join("User Data")
-                .join("Default")
-                .join("Network")
-                .join("Cookies"),
-            BrowserType::Edge => PathBuf::from(&local_app_data)
+                .join("User Data"),
+            BrowserType::Edge => env_dir("LOCALAPPDATA")?
                 .join("Microsoft")
                 .join("Edge")
-                .join("User Data")
-                .join("Default")
-                .join("Network")
-                .join("Cookies"),
-            BrowserType::Firefox => {
-                // Firefox uses a profile directory
-                let profiles_dir = PathBuf::from(&app_data)
-                    .join("Mozilla")
-                    .join("Firefox")
-                    .join("Profiles");
-
-                // Find the default profile (ends with .default or .default-release)
-                if profiles_dir.exists() {
-                    for entry in std::fs::read_dir(&profiles_dir)? {
-                        let entry = entry?;
-                        let name = entry.file_name();
-                        let name_str = name.to_string_lossy();
-                        if name_str.ends_with(".default") || name_str.ends_with(".default-release")
-                        {
-                            return Ok(entry.path().join("cookies.sqlite"));
-                        }
-                    }
-                }
-                return Err(CookieError::DatabaseNotFound {
-                    browser: "Firefox".into(),
-                    path: profiles_dir.to_string_lossy().into(),
-                });
-            }
-        };
-
-        Ok(path)
+                .join("User Data"),
+            BrowserType::Firefox => env_dir("APPDATA")?
+                .join("Mozilla")
+                .join("Firefox")
+                .join("Profiles"),
+        })
     }
 
     /// Checks if a browser has cookies available
     pub fn is_browser_available(browser: BrowserType) -> bool {
-        Self::cookie_path(browser)
-            .map(|p| p.exists())
-            .unwrap_or(false)
+        Self::profile_databases(browser).is_ok()
     }
 
     /// Returns the first available browser
@@ -166,64 +316,120 @@ impl CookieExtractor {
             .find(|&b| Self::is_browser_available(b))
     }
 
-    /// Extracts cookies for a domain from a specific browser
+    /// Extracts cookies for a domain from every profile of a browser
     ///
     /// # Arguments
     ///
     /// * `browser` - The browser to extract from
-    /// * `domain` - The domain to get cookies for (e.g., "claude.ai")
+    /// * `domain` - The host to get cookies for (e.g., "claude.ai")
     ///
     /// # Returns
     ///
-    /// A list of cookies for the domain
+    /// The cookies the browser would send to the host, one of each name
     pub fn extract_cookies(
         &self,
         browser: BrowserType,
         domain: &str,
     ) -> Result<Vec<Cookie>, CookieError> {
-        let db_path = Self::cookie_path(browser)?;
-
-        if !db_path.exists() {
-            return Err(CookieError::DatabaseNotFound {
-                browser: browser.name().into(),
-                path: db_path.to_string_lossy().into(),
-            });
+        let cookies = deduplicate(self.read_cookies(browser, domain, None)?);
+        if cookies.is_empty() {
+            return Err(CookieError::NoCookiesFound(domain.into()));
         }
+        Ok(cookies)
+    }
 
-        // Chrome/Edge lock the database, so we need to copy it first
-        let temp_path = self.copy_database_if_locked(&db_path)?;
-        let db_path_to_use = temp_path.as_ref().unwrap_or(&db_path);
+    /// Extracts one cookie by name for a domain from a specific browser
+    ///
+    /// When several cookies of that name apply, the most recently created
+    /// one wins.
+    pub fn extract_cookie(
+        &self,
+        browser: BrowserType,
+        domain: &str,
+        name: &str,
+    ) -> Result<Cookie, CookieError> {
+        deduplicate(self.read_cookies(browser, domain, Some(name))?)
+            .pop()
+            .ok_or_else(|| CookieError::CookieNotFound {
+                name: name.into(),
+                domain: domain.into(),
+            })
+    }
 
-        let cookies = match browser {
-            BrowserType::Chrome | BrowserType::Edge => {
-                self.extract_chromium_cookies(db_path_to_use, domain)?
-            }
-            BrowserType::Firefox => self.extract_firefox_cookies(db_path_to_use, domain)?,
-        };
+    /// Extracts one cookie by name from all available browsers
+    ///
+    /// The most recently created one wins; on a tie browsers are preferred
+    /// in order: Chrome, Edge, Firefox.
+    pub fn extract_cookie_any_browser(
+        &self,
+        domain: &str,
+        name: &str,
+    ) -> Result<Cookie, CookieError> {
+        deduplicate(self.read_all_browsers(domain, Some(name)))
+            .pop()
+            .ok_or_else(|| CookieError::CookieNotFound {
+                name: name.into(),
+                domain: domain.into(),
+            })
+    }
 
-        // Clean up temp file
-        if let Some(temp) = temp_path {
-            let _ = std::fs::remove_file(temp);
+    /// Unexpired cookies any profile of a browser would send to `domain`,
+    /// only those called `name` if given
+    ///
+    /// A profile that can't be read is skipped; the error is returned only
+    /// when no profile had cookies.
+    fn read_cookies(
+        &self,
+        browser: BrowserType,
+        domain: &str,
+        name: Option<&str>,
+    ) -> Result<Vec<Cookie>, CookieError> {
+        let root = Self::profiles_dir(browser)?;
+        let mut cookies = Vec::new();
+        let mut failure = None;
+        for (source, path) in Self::databases_in(browser, &root)? {
+            let found = CookieDatabase::open(&path).and_then(|database| match browser {
+                BrowserType::Chrome | BrowserType::Edge => {
+                    self.extract_chromium_cookies(&database.conn, &root, &source, domain, name)
+                }
+                BrowserType::Firefox => {
+                    self.extract_firefox_cookies(&database.conn, &source, domain, name)
+                }
+            });
+            match found {
+                Ok(found) => cookies.extend(found),
+                Err(e) => {
+                    tracing::debug!("Skipping cookies of {}: {}", source, e);
+                    failure = Some(e);
+                }
+            }
         }
-
-        if cookies.is_empty() {
-            return Err(CookieError::NoCookiesFound(domain.into()));
+        match failure {
+            Some(e) if cookies.is_empty() => Err(e),
+            _ => Ok(cookies),
         }
+    }
 
-        Ok(cookies)
+    /// Cookies of all browsers, in their order of preference, skipping
+    /// those that can't be read
+    fn read_all_browsers(&self, domain: &str, name: Option<&str>) -> Vec<Cookie> {
+        BrowserType::all()
+            .iter()
+            .filter_map(|browser| self.read_cookies(*browser, domain, name).ok())
+            .flatten()
+            .collect()
     }
 
-    /// Extracts cookies from any available browser
+    /// Extracts cookies from all available browsers, one of each name
     ///
-    /// Tries browsers in order of preference: Chrome, Edge, Firefox
+    /// The most recently created cookie of a name wins; on a tie browsers
+    /// are preferred in order: Chrome, Edge, Firefox.
     pub fn extract_cookies_any_browser(&self, domain: &str) -> Result<Vec<Cookie>, CookieError> {
-        for browser in BrowserType::all() {
-            match self.extract_cookies(*browser, domain) {
-                Ok(cookies) => return Ok(cookies),
-                Err(_) => continue,
-            }
+        let cookies = deduplicate(self.read_all_browsers(domain, None));
+        if cookies.is_empty() {
+            return Err(CookieError::NoCookiesFound(domain.into()));
         }
-        Err(CookieError::NoCookiesFound(domain.into()))
+        Ok(cookies)
     }
 
     /// Formats cookies as a Cookie header value
@@ -246,41 +452,79 @@ impl CookieExtractor {
             .join("; ")
     }
 
-    /// Copies the database if it's locked by the browser
-    fn copy_database_if_locked(&self, path: &PathBuf) -> Result<Option<PathBuf>, CookieError> {
-        // Try to open directly first
-        if Connection::open(path).is_ok() {
-            return Ok(None);
-        }
-
-        // Copy to temp directory
-        let temp_dir = std::env::temp_dir();
-        let temp_path = temp_dir.join(format!("gptbar_cookies_{}.db", std::process::id()));
-
-        std::fs::copy(path, &temp_path)?;
+    /// Deletes cookie database copies in `dir` last modified over `older_than`
+    /// ago
+    ///
+    /// Copies are removed right after reading, so older ones were left by a
+    /// run that crashed or failed mid-read. Returns how many were deleted.
+    pub fn remove_stale_copies(dir: &Path, older_than: Duration) -> usize {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with(TEMP_COPY_PREFIX)
+                    && (name.ends_with(".db")
+                        || SIDECAR_SUFFIXES
+                            .iter()
+                            .any(|suffix| name.ends_with(&format!(".db{}", suffix))))
+            })
+            .filter(|entry| {
+                entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age >= older_than)
+            })
+            .filter(|entry| std::fs::remove_file(entry.path()).is_ok())
+            .count()
+    }
 
-        Ok(Some(temp_path))
+    /// Directory cookie databases are copied into when a browser holds them
+    /// open
+    ///
+    /// It is in the user's profile instead of the shared temp directory. On
+    /// Windows that is `%LOCALAPPDATA%\GPTBar`, since the config folder under
+    /// `%APPDATA%` roams and a copy left by a crash would sync to the
+    /// profile server; elsewhere it is the config folder.
+    pub fn copy_dir() -> PathBuf {
+        let app_dir = if cfg!(target_os = "windows") {
+            std::env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("GPTBar"))
+        } else {
+            AppConfig::config_dir()
+        };
+        app_dir
+            .map(|dir| dir.join("cookie-copies"))
+            .unwrap_or_else(|| std::env::temp_dir().join("gptbar-cookie-copies"))
     }
 
     /// Extracts cookies from Chrome/Edge database
+    ///
+    /// The query narrows the rows down to the host's site; only unexpired
+    /// ones matching the host are decrypted, with the key of the profile's
+    /// `user_data_dir`.
     fn extract_chromium_cookies(
         &self,
-        db_path: &PathBuf,
+        conn: &Connection,
+        user_data_dir: &Path,
+        source: &CookieSource,
         domain: &str,
+        name: Option<&str>,
     ) -> Result<Vec<Cookie>, CookieError> {
-        let conn = Connection::open(db_path)?;
-
         // Chrome uses host_key column
         let mut stmt = conn.prepare(
-            "SELECT name, encrypted_value, host_key, path, expires_utc, is_secure, is_httponly
+            "SELECT name, encrypted_value, host_key, path, expires_utc, is_secure, is_httponly,
+                    creation_utc
              FROM cookies
-             WHERE host_key LIKE ?1 OR host_key LIKE ?2",
+             WHERE host_key LIKE ?1 AND (?2 IS NULL OR name = ?2)",
         )?;
 
-        let domain_pattern = format!("%{}", domain);
-        let subdomain_pattern = format!(".{}", domain);
-
-        let rows = stmt.query_map([&domain_pattern, &subdomain_pattern], |row| {
+        let site_pattern = format!("%{}", registrable_domain(domain));
+        let rows = stmt.query_map(rusqlite::params![site_pattern, name], |row| {
             Ok((
                 row.get::<_, String>(0)?,           // name
                 row.get::<_, Vec<u8>>(1)?,          // encrypted_value
@@ -289,25 +533,33 @@ impl CookieExtractor {
                 row.get::<_, Option<i64>>(4)?,      // expires_utc
                 row.get::<_, bool>(5)?,             // is_secure
                 row.get::<_, bool>(6)?,             // is_httponly
+                row.get::<_, Option<i64>>(7)?,      // creation_utc
             ))
         })?;
 
+        let now = chrono::Utc::now().timestamp();
         let mut cookies = Vec::new();
         for row_result in rows {
-            let (name, encrypted_value, host_key, path, expires, secure, http_only) = row_result?;
-
-            // Decrypt the cookie value using DPAPI
-            let value = self.decrypt_chromium_cookie(&encrypted_value)?;
-
-            cookies.push(Cookie {
+            let (name, encrypted_value, host_key, path, expires, secure, http_only, created) =
+                row_result?;
+            let mut cookie = Cookie {
                 name,
-                value,
+                value: String::new(),
                 domain: host_key,
                 path,
-                expires,
+                expires: expires.and_then(chromium_time),
                 secure,
                 http_only,
-            });
+                created: created.and_then(chromium_time),
+                source: source.clone(),
+            };
+            if !cookie.matches_host(domain) || cookie.is_expired(now) {
+                continue;
+            }
+
+            // Decrypt the cookie value using DPAPI
+            cookie.value = self.decrypt_chromium_cookie(&encrypted_value, user_data_dir)?;
+            cookies.push(cookie);
         }
 
         Ok(cookies)
@@ -316,22 +568,20 @@ impl CookieExtractor {
     /// Extracts cookies from Firefox database
     fn extract_firefox_cookies(
         &self,
-        db_path: &PathBuf,
+        conn: &Connection,
+        source: &CookieSource,
         domain: &str,
+        name: Option<&str>,
     ) -> Result<Vec<Cookie>, CookieError> {
-        let conn = Connection::open(db_path)?;
-
-        // Firefox uses baseDomain column and stores values in plaintext
+        // Firefox stores values in plaintext
         let mut stmt = conn.prepare(
-            "SELECT name, value, host, path, expiry, isSecure, isHttpOnly
+            "SELECT name, value, host, path, expiry, isSecure, isHttpOnly, creationTime
              FROM moz_cookies
-             WHERE host LIKE ?1 OR host LIKE ?2",
+             WHERE host LIKE ?1 AND (?2 IS NULL OR name = ?2)",
         )?;
 
-        let domain_pattern = format!("%{}", domain);
-        let subdomain_pattern = format!(".{}", domain);
-
-        let rows = stmt.query_map([&domain_pattern, &subdomain_pattern], |row| {
+        let site_pattern = format!("%{}", registrable_domain(domain));
+        let rows = stmt.query_map(rusqlite::params![site_pattern, name], |row| {
             Ok(Cookie {
                 name: row.get(0)?,
                 value: row.get(1)?,
@@ -340,12 +590,21 @@ impl CookieExtractor {
                 expires: row.get(4)?,
                 secure: row.get(5)?,
                 http_only: row.get(6)?,
+                // Microseconds since the Unix epoch
+                created: row
+                    .get::<_, Option<i64>>(7)?
+                    .map(|created| created / 1_000_000),
+                source: source.clone(),
             })
         })?;
 
+        let now = chrono::Utc::now().timestamp();
         let mut cookies = Vec::new();
         for cookie_result in rows {
-            cookies.push(cookie_result?);
+            let cookie = cookie_result?;
+            if cookie.matches_host(domain) && !cookie.is_expired(now) {
+                cookies.push(cookie);
+            }
         }
 
         Ok(cookies)
@@ -353,7 +612,11 @@ impl CookieExtractor {
 
     /// Decrypts a Chrome/Edge cookie value using DPAPI or AES-GCM
     #[cfg(windows)]
-    fn decrypt_chromium_cookie(&self, encrypted: &[u8]) -> Result<String, CookieError> {
+    fn decrypt_chromium_cookie(
+        &self,
+        encrypted: &[u8],
+        user_data_dir: &Path,
+    ) -> Result<String, CookieError> {
         // Chrome cookies start with "v10" or "v11" prefix for newer encryption
         // Older cookies use plain DPAPI
 
@@ -363,7 +626,7 @@ impl CookieExtractor {
 
         // Check for v10/v11 prefix (AES-GCM encryption)
         if encrypted.len() > 3 && (&encrypted[..3] == b"v10" || &encrypted[..3] == b"v11") {
-            return self.decrypt_chromium_v10(encrypted);
+            return self.decrypt_chromium_v10(encrypted, user_data_dir);
         }
 
         // Use DPAPI for decryption (older Chrome versions)
@@ -379,14 +642,18 @@ impl CookieExtractor {
 
     /// Decrypts Chrome v10/v11 encrypted cookies using AES-GCM
     #[cfg(windows)]
-    fn decrypt_chromium_v10(&self, encrypted: &[u8]) -> Result<String, CookieError> {
+    fn decrypt_chromium_v10(
+        &self,
+        encrypted: &[u8],
+        user_data_dir: &Path,
+    ) -> Result<String, CookieError> {
         use aes_gcm::{
             aead::{Aead, KeyInit},
             Aes256Gcm, Nonce,
         };
 
-        // Get the encryption key from Chrome's Local State file
-        let key = self.get_chromium_encryption_key()?;
+        // Get the encryption key from the Local State file
+        let key = self.get_chromium_encryption_key(user_data_dir)?;
 
         // Structure: "v10" (3 bytes) + nonce (12 bytes) + ciphertext + tag (16 bytes)
         if encrypted.len() < 3 + 12 + 16 {
@@ -409,69 +676,52 @@ impl CookieExtractor {
             .map_err(|e| CookieError::Decryption(format!("UTF-8 error: {}", e)))
     }
 
-    /// Gets the encryption key from Chrome's Local State file
+    /// Gets the encryption key from a user data dir's Local State file
     #[cfg(windows)]
-    fn get_chromium_encryption_key(&self) -> Result<Vec<u8>, CookieError> {
+    fn get_chromium_encryption_key(&self, user_data_dir: &Path) -> Result<Vec<u8>, CookieError> {
         use base64::Engine;
         use crate::security::DpapiStore;
 
-        let local_app_data = std::env::var("LOCALAPPDATA")
-            .map_err(|_| CookieError::EnvVar("LOCALAPPDATA".into()))?;
+        let path = user_data_dir.join("Local State");
+        let content = std::fs::read_to_string(&path).map_err(|e| CookieError::Io(e))?;
+
+        // Parse JSON to get the encrypted key
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| CookieError::Decryption(format!("JSON parse error: {}", e)))?;
+
+        if let Some(encrypted_key_b64) = json
+            .get("os_crypt")
+            .and_then(|v| v.get("encrypted_key"))
+            .and_then(|v| v.as_str())
+        {
+            // Decode base64
+            let encrypted_key = base64::engine::general_purpose::STANDARD
+                .decode(encrypted_key_b64)
+                .map_err(|e| CookieError::Decryption(format!("Base64 error: {}", e)))?;
+
+            // Remove "DPAPI" prefix (5 bytes)
+            if encrypted_key.len() < 5 || &encrypted_key[..5] != b"DPAPI" {
+                return Err(CookieError::Decryption("Invalid key format".into()));
+            }
 
-        // Try Chrome first, then Edge
-        let local_state_paths = [
-            PathBuf::from(&local_app_data)
-                .join("Google")
-                .join("Chrome")
-                .join("User Data")
-                .join("Local State"),
-            PathBuf::from(&local_app_data)
-                .join("Microsoft")
-                .join("Edge")
-                .join("User Data")
-                .join("Local State"),
-        ];
+            // Decrypt with DPAPI
+            let dpapi = DpapiStore::new();
+            let key = dpapi
+                .decrypt(&encrypted_key[5..])
+                .map_err(|e| CookieError::Decryption(format!("DPAPI error: {}", e)))?;
 
-        for path in &local_state_paths {
-            if path.exists() {
-                let content = std::fs::read_to_string(path)
-                    .map_err(|e| CookieError::Io(e))?;
-
-                // Parse JSON to get the encrypted key
-                let json: serde_json::Value = serde_json::from_str(&content)
-                    .map_err(|e| CookieError::Decryption(format!("JSON parse error: {}", e)))?;
-
-                if let Some(encrypted_key_b64) = json
-                    .get("os_crypt")
-                    .and_then(|v| v.get("encrypted_key"))
-                    .and_then(|v| v.as_str())
-                {
-                    // Decode base64
-                    let encrypted_key = base64::engine::general_purpose::STANDARD
-                        .decode(encrypted_key_b64)
-                        .map_err(|e| CookieError::Decryption(format!("Base64 error: {}", e)))?;
-
-                    // Remove "DPAPI" prefix (5 bytes)
-                    if encrypted_key.len() < 5 || &encrypted_key[..5] != b"DPAPI" {
-                        return Err(CookieError::Decryption("Invalid key format".into()));
-                    }
-
-                    // Decrypt with DPAPI
-                    let dpapi = DpapiStore::new();
-                    let key = dpapi
-                        .decrypt(&encrypted_key[5..])
-                        .map_err(|e| CookieError::Decryption(format!("DPAPI error: {}", e)))?;
-
-                    return Ok(key);
-                }
-            }
+            return Ok(key);
         }
 
         Err(CookieError::Decryption("Could not find encryption key".into()))
     }
 
     #[cfg(not(windows))]
-    fn decrypt_chromium_cookie(&self, _encrypted: &[u8]) -> Result<String, CookieError> {
+    fn decrypt_chromium_cookie(
+        &self,
+        _encrypted: &[u8],
+        _user_data_dir: &Path,
+    ) -> Result<String, CookieError> {
         Err(CookieError::Decryption(
             "Cookie decryption only available on Windows".into(),
         ))
@@ -484,6 +734,219 @@ impl Default for CookieExtractor {
     }
 }
 
+/// Cookie databases of the profiles in `root`, the default profile first
+///
+/// Chrome and Edge profiles are those listed in `Local State`; without one,
+/// the folders named like profiles are used.
+fn find_profile_databases(browser: BrowserType, root: &Path) -> Vec<(CookieSource, PathBuf)> {
+    let profiles = match browser {
+        BrowserType::Chrome | BrowserType::Edge => local_state_profiles(root),
+        BrowserType::Firefox => None,
+    }
+    .unwrap_or_else(|| profile_dirs(browser, root));
+    let mut databases: Vec<_> = profiles
+        .into_iter()
+        .filter_map(|profile| {
+            let path = profile_cookie_path(browser, &root.join(&profile))?;
+            Some((CookieSource { browser, profile }, path))
+        })
+        .collect();
+    databases.sort_by_key(|(source, _)| {
+        (
+            !is_default_profile(browser, &source.profile),
+            source.profile.clone(),
+        )
+    });
+    databases
+}
+
+/// Profile folder names listed in a Chromium user data dir's `Local State`
+fn local_state_profiles(user_data_dir: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(user_data_dir.join("Local State")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let profiles = json.pointer("/profile/info_cache")?.as_object()?;
+    Some(profiles.keys().cloned().collect())
+}
+
+/// Folders in `root` that look like profiles of `browser`
+fn profile_dirs(browser: BrowserType, root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| match browser {
+            BrowserType::Chrome | BrowserType::Edge => {
+                name == "Default" || name.starts_with("Profile ")
+            }
+            BrowserType::Firefox => true,
+        })
+        .collect()
+}
+
+/// The cookie database in a profile folder, if there is one
+fn profile_cookie_path(browser: BrowserType, dir: &Path) -> Option<PathBuf> {
+    let candidates = match browser {
+        // Versions before 96 kept the database outside `Network`
+        BrowserType::Chrome | BrowserType::Edge => {
+            vec![dir.join("Network").join("Cookies"), dir.join("Cookies")]
+        }
+        BrowserType::Firefox => vec![dir.join("cookies.sqlite")],
+    };
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// Whether `profile` is the one a browser opens by default
+fn is_default_profile(browser: BrowserType, profile: &str) -> bool {
+    match browser {
+        BrowserType::Chrome | BrowserType::Edge => profile == "Default",
+        BrowserType::Firefox => {
+            profile.ends_with(".default") || profile.ends_with(".default-release")
+        }
+    }
+}
+
+/// A browser's cookie database opened read-only
+struct CookieDatabase {
+    conn: Connection,
+    /// Dropped after `conn`, since an open file can't be deleted on Windows
+    _copy: Option<TempCopy>,
+}
+
+impl CookieDatabase {
+    /// Opens the database in place, or a private copy when the browser
+    /// holds it open exclusively
+    fn open(path: &Path) -> Result<Self, CookieError> {
+        match open_read_only(path) {
+            Ok(conn) => Ok(Self { conn, _copy: None }),
+            Err(e) => {
+                tracing::debug!("Copying locked cookie database: {}", e);
+                let copy = TempCopy::create(path, &CookieExtractor::copy_dir())?;
+                // Opened writable so SQLite replays the copied write-ahead log
+                let conn =
+                    Connection::open_with_flags(&copy.path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+                Ok(Self {
+                    conn,
+                    _copy: Some(copy),
+                })
+            }
+        }
+    }
+}
+
+/// Opens a database read-only, checking it can be read
+///
+/// The browser's write-ahead log is read too, so cookies it hasn't
+/// checkpointed into the main file yet are seen.
+fn open_read_only(path: &Path) -> Result<Connection, rusqlite::Error> {
+    let conn = Connection::open_with_flags(
+        read_only_uri(path),
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )?;
+    // Opening is lazy; a file the browser holds exclusively fails here
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })?;
+    Ok(conn)
+}
+
+/// SQLite URI opening `path` read-only
+fn read_only_uri(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    let root = if path.starts_with('/') { "" } else { "/" };
+    format!("file://{}{}?mode=ro", root, path)
+}
+
+/// Suffixes of the files SQLite keeps next to a database in WAL mode
+const SIDECAR_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+/// Path of the sidecar file of `path` with `suffix`
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// A copy of a cookie database and its write-ahead log only the current
+/// user can read, deleted when dropped
+struct TempCopy {
+    path: PathBuf,
+}
+
+impl TempCopy {
+    /// Copies `source` and the sidecar files it has into `dir`, creating
+    /// the directory if needed
+    fn create(source: &Path, dir: &Path) -> Result<Self, CookieError> {
+        create_private_dir(dir)?;
+        let copy = Self {
+            path: dir.join(format!(
+                "{}{}_{}.db",
+                TEMP_COPY_PREFIX,
+                std::process::id(),
+                COPY_COUNTER.fetch_add(1, Ordering::Relaxed)
+            )),
+        };
+        // The guard exists first, so a failed copy is deleted too
+        copy_private(source, &copy.path)?;
+        for suffix in SIDECAR_SUFFIXES {
+            let sidecar = sidecar_path(source, suffix);
+            match copy_private(&sidecar, &sidecar_path(&copy.path, suffix)) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                result => result?,
+            }
+        }
+        Ok(copy)
+    }
+}
+
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        for suffix in SIDECAR_SUFFIXES {
+            let _ = fs::remove_file(sidecar_path(&self.path, suffix));
+        }
+    }
+}
+
+/// Copies `source` into a new file at `dest` only the current user can read
+fn copy_private(source: &Path, dest: &Path) -> std::io::Result<()> {
+    let mut reader = fs::File::open(source)?;
+    let mut writer = create_private_file(dest)?;
+    std::io::copy(&mut reader, &mut writer)?;
+    Ok(())
+}
+
+/// Creates a directory only the current user can open
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Creates a file only the current user can read
+///
+/// Files in the user's profile are private to them by default on Windows.
+fn create_private_file(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -504,6 +967,13 @@ mod tests {
         assert!(all.contains(&BrowserType::Firefox));
     }
 
+    fn chrome_default() -> CookieSource {
+        CookieSource {
+            browser: BrowserType::Chrome,
+            profile: "Default".to_string(),
+        }
+    }
+
     #[test]
     fn test_cookie_to_header() {
         let cookie = Cookie {
@@ -514,6 +984,8 @@ mod tests {
             expires: None,
             secure: true,
             http_only: true,
+            created: None,
+            source: chrome_default(),
         };
 
         assert_eq!(cookie.to_header_value(), "session=abc123");
@@ -530,6 +1002,8 @@ mod tests {
                 expires: None,
                 secure: false,
                 http_only: false,
+                created: None,
+                source: chrome_default(),
             },
             Cookie {
                 name: "b".to_string(),
@@ -539,6 +1013,8 @@ mod tests {
                 expires: None,
                 secure: false,
                 http_only: false,
+                created: None,
+                source: chrome_default(),
             },
         ];
 
@@ -563,6 +1039,332 @@ mod tests {
         let _ = CookieExtractor::is_browser_available(BrowserType::Firefox);
     }
 
+    fn cookie(name: &str, domain: &str, path: &str) -> Cookie {
+        Cookie {
+            name: name.to_string(),
+            value: format!("{}@{}{}", name, domain, path),
+            domain: domain.to_string(),
+            path: path.to_string(),
+            expires: None,
+            secure: true,
+            http_only: true,
+            created: None,
+            source: chrome_default(),
+        }
+    }
+
+    #[test]
+    fn test_cookie_matches_host() {
+        let host_only = cookie("a", "claude.ai", "/");
+        assert!(host_only.matches_host("claude.ai"));
+        assert!(host_only.matches_host("Claude.AI"));
+        assert!(!host_only.matches_host("api.claude.ai"));
+        assert!(!host_only.matches_host("notclaude.ai"));
+
+        let domain = cookie("a", ".claude.ai", "/");
+        assert!(domain.matches_host("claude.ai"));
+        assert!(domain.matches_host("api.claude.ai"));
+        assert!(!domain.matches_host("notclaude.ai"));
+        assert!(!domain.matches_host("claude.ai.evil.com"));
+
+        assert!(!cookie("a", "api.claude.ai", "/").matches_host("claude.ai"));
+        assert!(!cookie("a", ".ai", "/").matches_host("claude.ai"));
+        assert!(!cookie("a", ".co.uk", "/").matches_host("bbc.co.uk"));
+        assert!(cookie("a", ".bbc.co.uk", "/").matches_host("www.bbc.co.uk"));
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("claude.ai"), "claude.ai");
+        assert_eq!(registrable_domain("api.claude.ai"), "claude.ai");
+        assert_eq!(registrable_domain("www.bbc.co.uk"), "bbc.co.uk");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+    }
+
+    #[test]
+    fn test_deduplicate_prefers_freshest() {
+        let work = CookieSource {
+            browser: BrowserType::Edge,
+            profile: "Profile 1".to_string(),
+        };
+        let old = Cookie {
+            created: Some(1_000),
+            ..cookie("sessionKey", ".claude.ai", "/")
+        };
+        let new = Cookie {
+            created: Some(2_000),
+            source: work.clone(),
+            ..cookie("sessionKey", ".claude.ai", "/")
+        };
+        let other = cookie("lastActive", "claude.ai", "/");
+
+        let kept = deduplicate([old.clone(), other, new]);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].source, work);
+        assert_eq!(kept[1].name, "lastActive");
+
+        // Created together, the later expiring one wins, then the first
+        let expiring = Cookie {
+            expires: Some(5_000),
+            ..old.clone()
+        };
+        let lasting = Cookie {
+            expires: Some(9_000),
+            source: work.clone(),
+            ..old.clone()
+        };
+        assert_eq!(deduplicate([expiring, lasting])[0].source, work);
+        let twin = Cookie {
+            source: work,
+            ..old.clone()
+        };
+        assert_eq!(deduplicate([old, twin])[0].source, chrome_default());
+    }
+
+    #[test]
+    fn test_chromium_time() {
+        assert_eq!(chromium_time(0), None);
+        assert_eq!(chromium_time(13_400_000_000_000_000), Some(1_755_526_400));
+    }
+
+    #[test]
+    fn test_find_profile_databases() {
+        let root = tempfile::tempdir().unwrap();
+        let create = |path: &str| {
+            let path = root.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        };
+        create("Profile 2/Cookies");
+        create("Default/Network/Cookies");
+        create("System Profile/Network/Cookies");
+        std::fs::create_dir(root.path().join("Profile 3")).unwrap();
+
+        let profiles: Vec<_> = find_profile_databases(BrowserType::Chrome, root.path())
+            .into_iter()
+            .map(|(source, path)| (source.profile, path))
+            .collect();
+        assert_eq!(
+            profiles,
+            vec![
+                (
+                    "Default".to_string(),
+                    root.path().join("Default").join("Network").join("Cookies")
+                ),
+                (
+                    "Profile 2".to_string(),
+                    root.path().join("Profile 2").join("Cookies")
+                ),
+            ]
+        );
+
+        // Profiles listed in Local State replace the folder scan
+        std::fs::write(
+            root.path().join("Local State"),
+            r#"{"profile":{"info_cache":{"Default":{"name":"Personal"},"Work":{"name":"Work"}}}}"#,
+        )
+        .unwrap();
+        create("Work/Network/Cookies");
+        let profiles: Vec<_> = find_profile_databases(BrowserType::Edge, root.path())
+            .into_iter()
+            .map(|(source, _)| source.to_string())
+            .collect();
+        assert_eq!(profiles, vec!["Edge (Default)", "Edge (Work)"]);
+
+        create("a1b2.work/cookies.sqlite");
+        create("c3d4.default-release/cookies.sqlite");
+        let profiles: Vec<_> = find_profile_databases(BrowserType::Firefox, root.path())
+            .into_iter()
+            .map(|(source, _)| source.to_string())
+            .collect();
+        assert_eq!(
+            profiles,
+            vec!["Firefox (c3d4.default-release)", "Firefox (a1b2.work)"]
+        );
+    }
+
+    fn firefox_database(cookies: &[Cookie]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_cookies (name TEXT, value TEXT, host TEXT, path TEXT,
+                expiry INTEGER, isSecure INTEGER, isHttpOnly INTEGER, creationTime INTEGER)",
+        )
+        .unwrap();
+        for cookie in cookies {
+            conn.execute(
+                "INSERT INTO moz_cookies VALUES (?1, ?2, ?3, ?4, ?5, 1, 1, ?6)",
+                rusqlite::params![
+                    cookie.name,
+                    cookie.value,
+                    cookie.domain,
+                    cookie.path,
+                    cookie.expires,
+                    cookie.created.map(|created| created * 1_000_000),
+                ],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn firefox_default() -> CookieSource {
+        CookieSource {
+            browser: BrowserType::Firefox,
+            profile: "a1b2.default-release".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_extract_firefox_cookies_for_host() {
+        let conn = firefox_database(&[
+            cookie("sessionKey", ".claude.ai", "/"),
+            cookie("lastActive", "claude.ai", "/"),
+            cookie("sessionKey", "notclaude.ai", "/"),
+            cookie("consoleOnly", "console.claude.ai", "/"),
+            Cookie {
+                expires: Some(1_000_000_000),
+                ..cookie("expired", "claude.ai", "/")
+            },
+            Cookie {
+                created: Some(1_700_000_000),
+                ..cookie("created", "claude.ai", "/")
+            },
+        ]);
+        let extractor = CookieExtractor::new();
+        let source = firefox_default();
+
+        let cookies = extractor
+            .extract_firefox_cookies(&conn, &source, "claude.ai", None)
+            .unwrap();
+        let names: Vec<_> = cookies.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["sessionKey", "lastActive", "created"]);
+        assert_eq!(cookies[2].created, Some(1_700_000_000));
+        assert_eq!(cookies[2].source, source);
+
+        let session = extractor
+            .extract_firefox_cookies(&conn, &source, "claude.ai", Some("sessionKey"))
+            .unwrap();
+        assert_eq!(session.len(), 1);
+        assert_eq!(session[0].domain, ".claude.ai");
+    }
+
+    #[test]
+    fn test_read_only_uri() {
+        assert_eq!(
+            read_only_uri(Path::new("/home/me/.config/chromium/Default/Cookies")),
+            "file:///home/me/.config/chromium/Default/Cookies?mode=ro"
+        );
+        assert_eq!(
+            read_only_uri(Path::new("/tmp/100%?#/Cookies")),
+            "file:///tmp/100%25%3f%23/Cookies?mode=ro"
+        );
+    }
+
+    #[test]
+    fn test_open_reads_database_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cookies");
+        // The browser keeps its connection open, so the row stays in the
+        // write-ahead log
+        let browser = Connection::open(&path).unwrap();
+        browser
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 CREATE TABLE moz_cookies (name TEXT);
+                 INSERT INTO moz_cookies VALUES ('sessionKey');",
+            )
+            .unwrap();
+        assert!(sidecar_path(&path, "-wal").exists());
+
+        let database = CookieDatabase::open(&path).unwrap();
+        assert!(database._copy.is_none());
+        let count: i64 = database
+            .conn
+            .query_row("SELECT COUNT(*) FROM moz_cookies", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_temp_copy_includes_write_ahead_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("Cookies");
+        let browser = Connection::open(&source).unwrap();
+        browser
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 CREATE TABLE cookies (name TEXT);
+                 INSERT INTO cookies VALUES ('sessionKey');",
+            )
+            .unwrap();
+        let copies = dir.path().join("copies");
+
+        let copy = TempCopy::create(&source, &copies).unwrap();
+        for suffix in SIDECAR_SUFFIXES {
+            assert!(sidecar_path(&copy.path, suffix).exists());
+        }
+        {
+            let conn =
+                Connection::open_with_flags(&copy.path, OpenFlags::SQLITE_OPEN_READ_WRITE).unwrap();
+            let count: i64 = conn
+                .query_row("SELECT COUNT(*) FROM cookies", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(count, 1);
+        }
+
+        drop(copy);
+        assert_eq!(std::fs::read_dir(&copies).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_temp_copy_is_private_and_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("Cookies");
+        std::fs::write(&source, b"cookies").unwrap();
+        let copies = dir.path().join("copies");
+
+        let copy = TempCopy::create(&source, &copies).unwrap();
+        assert_eq!(std::fs::read(&copy.path).unwrap(), b"cookies");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&copies), 0o700);
+            assert_eq!(mode(&copy.path), 0o600);
+        }
+
+        let path = copy.path.clone();
+        drop(copy);
+        assert!(!path.exists());
+
+        // A failed copy leaves nothing behind
+        assert!(TempCopy::create(&dir.path().join("missing"), &copies).is_err());
+        assert_eq!(std::fs::read_dir(&copies).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_remove_stale_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let copy = dir.path().join("gptbar_cookies_1234.db");
+        let wal = sidecar_path(&copy, "-wal");
+        let other = dir.path().join("other.db");
+        std::fs::write(&copy, b"").unwrap();
+        std::fs::write(&wal, b"").unwrap();
+        std::fs::write(&other, b"").unwrap();
+
+        assert_eq!(
+            CookieExtractor::remove_stale_copies(dir.path(), Duration::from_secs(3600)),
+            0
+        );
+        assert_eq!(
+            CookieExtractor::remove_stale_copies(dir.path(), Duration::ZERO),
+            2
+        );
+        assert!(!copy.exists());
+        assert!(!wal.exists());
+        assert!(other.exists());
+    }
+
     #[test]
     fn test_extractor_creation() {
         let extractor = CookieExtractor::new();
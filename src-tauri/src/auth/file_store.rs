@@ -0,0 +1,329 @@
+//! Encrypted file fallback for the system keychain
+//!
+//! Used by [`keychain`](super::keychain) once the keychain itself fails,
+//! e.g. DPAPI errors on some Windows on ARM machines or a Keychain that
+//! can't be reached, so credentials saved meanwhile aren't lost.
+//!
+//! Values are encrypted with AES-256-GCM under a random key kept in a
+//! separate file readable only by the user. On Windows the key is further
+//! protected with DPAPI when DPAPI works. This is weaker than a keychain:
+//! anyone able to read both files as the user can decrypt them.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// File holding the encrypted values
+const STORE_FILE: &str = "credentials.enc.json";
+
+/// File holding the encryption key
+const KEY_FILE: &str = "credentials.key";
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// Errors of the encrypted file store
+#[derive(Debug, Error)]
+pub enum FileStoreError {
+    /// Reading or writing a file failed
+    #[error("File store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A file isn't in the expected format
+    #[error("Invalid file store data: {0}")]
+    InvalidFormat(String),
+
+    /// Encryption or decryption failed, e.g. after the key file changed
+    #[error("File store encryption failed")]
+    Crypto,
+}
+
+/// Encrypted values by service and account
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreFile {
+    entries: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Keychain-like storage in an encrypted file
+pub struct FileStore {
+    dir: PathBuf,
+    /// Serializes read-modify-write cycles on the store file
+    lock: Mutex<()>,
+}
+
+impl FileStore {
+    /// Creates a store keeping its files in `dir`
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Path of the file holding the encrypted values
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(STORE_FILE)
+    }
+
+    /// Reads a value
+    pub fn get(&self, service: &str, account: &str) -> Result<Option<String>, FileStoreError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let store = self.read()?;
+        match store.entries.get(service).and_then(|s| s.get(account)) {
+            Some(encrypted) => self.decrypt(encrypted).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes a value, replacing any older one
+    pub fn set(&self, service: &str, account: &str, value: &str) -> Result<(), FileStoreError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let encrypted = self.encrypt(value)?;
+        let mut store = self.read()?;
+        store
+            .entries
+            .entry(service.to_string())
+            .or_default()
+            .insert(account.to_string(), encrypted);
+        self.write(&store)
+    }
+
+    /// Deletes a value; returns whether there was one
+    pub fn delete(&self, service: &str, account: &str) -> Result<bool, FileStoreError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut store = self.read()?;
+        let Some(accounts) = store.entries.get_mut(service) else {
+            return Ok(false);
+        };
+        let removed = accounts.remove(account).is_some();
+        if accounts.is_empty() {
+            store.entries.remove(service);
+        }
+        if removed {
+            self.write(&store)?;
+        }
+        Ok(removed)
+    }
+
+    /// Whether any value is stored
+    pub fn has_entries(&self) -> Result<bool, FileStoreError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(!self.read()?.entries.is_empty())
+    }
+
+    fn read(&self) -> Result<StoreFile, FileStoreError> {
+        match fs::read_to_string(self.path()) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| FileStoreError::InvalidFormat(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(StoreFile::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, store: &StoreFile) -> Result<(), FileStoreError> {
+        let content = serde_json::to_string_pretty(store)
+            .map_err(|e| FileStoreError::InvalidFormat(e.to_string()))?;
+        write_private(&self.path(), content.as_bytes())
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm, FileStoreError> {
+        let key = self.load_or_create_key()?;
+        Aes256Gcm::new_from_slice(&key).map_err(|_| FileStoreError::Crypto)
+    }
+
+    /// Encrypts a value as base64 of its nonce followed by the ciphertext
+    fn encrypt(&self, value: &str) -> Result<String, FileStoreError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|_| FileStoreError::Crypto)?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        Ok(base64::engine::general_purpose::STANDARD.encode(data))
+    }
+
+    fn decrypt(&self, encrypted: &str) -> Result<String, FileStoreError> {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(encrypted)
+            .map_err(|e| FileStoreError::InvalidFormat(e.to_string()))?;
+        if data.len() < NONCE_LEN {
+            return Err(FileStoreError::InvalidFormat("Value too short".into()));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| FileStoreError::Crypto)?;
+        String::from_utf8(plaintext).map_err(|e| FileStoreError::InvalidFormat(e.to_string()))
+    }
+
+    /// Reads the key, creating a random one on first use
+    fn load_or_create_key(&self) -> Result<Vec<u8>, FileStoreError> {
+        let path = self.dir.join(KEY_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => unwrap_key(content.trim()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(&mut OsRng).to_vec();
+                write_private(&path, wrap_key(&key).as_bytes())?;
+                Ok(key)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Encodes the key for its file, protected with DPAPI where available
+///
+/// The prefix says how it was stored, so a key written without DPAPI still
+/// reads once DPAPI works again.
+fn wrap_key(key: &[u8]) -> String {
+    #[cfg(windows)]
+    {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(key);
+        match crate::security::DpapiStore::new().encrypt_string(&encoded) {
+            Ok(protected) => return format!("dpapi:{}", protected),
+            Err(e) => tracing::warn!("Storing the file store key without DPAPI: {}", e),
+        }
+    }
+
+    format!(
+        "raw:{}",
+        base64::engine::general_purpose::STANDARD.encode(key)
+    )
+}
+
+fn unwrap_key(content: &str) -> Result<Vec<u8>, FileStoreError> {
+    let encoded = match content.split_once(':') {
+        Some(("raw", encoded)) => encoded.to_string(),
+        #[cfg(windows)]
+        Some(("dpapi", protected)) => crate::security::DpapiStore::new()
+            .decrypt_string(protected)
+            .map_err(|_| FileStoreError::Crypto)?,
+        _ => return Err(FileStoreError::InvalidFormat("Unknown key format".into())),
+    };
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| FileStoreError::InvalidFormat(e.to_string()))
+}
+
+/// Writes a file only the current user can read
+fn write_private(path: &Path, content: &[u8]) -> Result<(), FileStoreError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        // Also tightens files created before
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(content)?;
+    }
+
+    // Files in the user's profile are private to them by default
+    #[cfg(not(unix))]
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::in_dir(dir.path());
+
+        assert_eq!(store.get("GPTBar", "openai").unwrap(), None);
+        store.set("GPTBar", "openai", "sk-secret").unwrap();
+        store.set("GPTBar", "gemini", "AIza-secret").unwrap();
+        store.set("Other", "openai", "other").unwrap();
+
+        // Survives reopening
+        let store = FileStore::in_dir(dir.path());
+        assert_eq!(
+            store.get("GPTBar", "openai").unwrap(),
+            Some("sk-secret".to_string())
+        );
+        assert_eq!(
+            store.get("Other", "openai").unwrap(),
+            Some("other".to_string())
+        );
+
+        assert!(store.delete("GPTBar", "openai").unwrap());
+        assert!(!store.delete("GPTBar", "openai").unwrap());
+        assert_eq!(store.get("GPTBar", "openai").unwrap(), None);
+        assert_eq!(
+            store.get("GPTBar", "gemini").unwrap(),
+            Some("AIza-secret".to_string())
+        );
+    }
+
+    #[test]
+    fn test_values_are_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::in_dir(dir.path());
+        store.set("GPTBar", "openai", "sk-secret").unwrap();
+
+        let content = fs::read_to_string(store.path()).unwrap();
+        assert!(content.contains("openai"));
+        assert!(!content.contains("sk-secret"));
+    }
+
+    #[test]
+    fn test_other_key_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::in_dir(dir.path());
+        store.set("GPTBar", "openai", "sk-secret").unwrap();
+
+        fs::remove_file(dir.path().join(KEY_FILE)).unwrap();
+        assert!(matches!(
+            store.get("GPTBar", "openai"),
+            Err(FileStoreError::Crypto)
+        ));
+    }
+
+    #[test]
+    fn test_key_format() {
+        let key = [7u8; 32];
+        assert_eq!(unwrap_key(&wrap_key(&key)).unwrap(), key);
+        assert!(unwrap_key("plain").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::in_dir(dir.path());
+        store.set("GPTBar", "openai", "sk-secret").unwrap();
+
+        for file in [STORE_FILE, KEY_FILE] {
+            let mode = fs::metadata(dir.path().join(file))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600, "{}", file);
+        }
+    }
+}
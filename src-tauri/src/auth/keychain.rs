@@ -0,0 +1,596 @@
+//! Cached system keychain lookups
+//!
+//! Providers look their keys up in the keychain on every availability check
+//! and refresh, and reading it can be slow or, on Linux, prompt to unlock
+//! the keyring. Lookups, including ones that found nothing, are cached for a
+//! few minutes. Writes and deletes through this module update the cache, and
+//! [`invalidate_all`] drops it when credentials change on login or logout.
+//!
+//! When the keychain itself fails, e.g. DPAPI errors on some Windows on ARM
+//! machines or a locked-down Keychain, values go to the encrypted
+//! [`FileStore`] instead of being lost. The switch is logged and published
+//! once as [`AppEvent::CredentialStoreFallback`], and [`fallback_reason`]
+//! tells the UI about it. Values left in the file are still found after a
+//! restart, and move back to the keychain once it accepts them.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::file_store::{FileStore, FileStoreError};
+use crate::config::AppConfig;
+use crate::events::{self, AppEvent};
+
+/// How long a lookup is reused before the keychain is read again
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// A cached lookup
+struct CachedEntry {
+    value: Option<String>,
+    fetched_at: Instant,
+}
+
+/// Keychain values by service and account, each kept for a TTL
+pub struct KeychainCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, String), CachedEntry>>,
+}
+
+impl KeychainCache {
+    /// Creates an empty cache keeping lookups for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached value, or calls `fetch` and caches its result when
+    /// there is none or it has expired
+    pub fn get_or_fetch(
+        &self,
+        service: &str,
+        account: &str,
+        fetch: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        let key = (service.to_string(), account.to_string());
+        if let Some(entry) = self.lock().get(&key) {
+            if entry.fetched_at.elapsed() < self.ttl {
+                return entry.value.clone();
+            }
+        }
+
+        // Not holding the lock while the keychain is read
+        let value = fetch();
+        self.insert(service, account, value.clone());
+        value
+    }
+
+    /// Caches a value just written to (or, with `None`, deleted from) the
+    /// keychain
+    pub fn insert(&self, service: &str, account: &str, value: Option<String>) {
+        self.lock().insert(
+            (service.to_string(), account.to_string()),
+            CachedEntry {
+                value,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Forgets one cached value
+    pub fn invalidate(&self, service: &str, account: &str) {
+        self.lock()
+            .remove(&(service.to_string(), account.to_string()));
+    }
+
+    /// Forgets every cached value of a service
+    pub fn invalidate_service(&self, service: &str) {
+        self.lock().retain(|(s, _), _| s != service);
+    }
+
+    /// Forgets every cached value
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, String), CachedEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Whether an error means the keychain can't be used at all, rather than
+/// one entry being missing or invalid
+fn is_unavailable(error: &keyring::Error) -> bool {
+    matches!(
+        error,
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)
+    )
+}
+
+pub(super) fn file_store_error(error: FileStoreError) -> keyring::Error {
+    keyring::Error::PlatformFailure(Box::new(error))
+}
+
+/// Routes keychain results to a [`FileStore`] once the keychain fails
+pub struct Fallback {
+    store: Option<FileStore>,
+    reason: OnceLock<String>,
+}
+
+impl Fallback {
+    /// Creates a fallback to `store`, or none when there is no store
+    pub fn new(store: Option<FileStore>) -> Self {
+        Self {
+            store,
+            reason: OnceLock::new(),
+        }
+    }
+
+    /// The keychain error that caused the switch, if it happened
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.get().map(String::as_str)
+    }
+
+    /// The store, once switched to it
+    fn active(&self) -> Option<&FileStore> {
+        self.reason.get().and(self.store.as_ref())
+    }
+
+    /// The store, once switched to it or while it holds values, e.g. ones
+    /// saved by an earlier run while the keychain was down
+    fn stored(&self) -> Option<&FileStore> {
+        self.active().or_else(|| {
+            self.store
+                .as_ref()
+                .filter(|store| store.has_entries().unwrap_or(false))
+        })
+    }
+
+    /// Switches to the store, announcing it the first time
+    fn activate(&self, error: &keyring::Error) -> Option<&FileStore> {
+        let store = self.store.as_ref()?;
+        let mut first = false;
+        self.reason.get_or_init(|| {
+            first = true;
+            error.to_string()
+        });
+        if first {
+            tracing::warn!(
+                "Keychain unavailable ({}), storing credentials in {}",
+                error,
+                store.path().display()
+            );
+            events::publish(AppEvent::CredentialStoreFallback {
+                reason: error.to_string(),
+            });
+        }
+        Some(store)
+    }
+
+    /// Completes a keychain read
+    ///
+    /// Entries missing from the keychain may have been written to the store
+    /// after the switch, in this run or an earlier one.
+    pub fn get(
+        &self,
+        service: &str,
+        account: &str,
+        keychain: keyring::Result<String>,
+    ) -> keyring::Result<Option<String>> {
+        let store = match keychain {
+            Ok(value) => return Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => match self.stored() {
+                Some(store) => store,
+                None => return Ok(None),
+            },
+            Err(e) if is_unavailable(&e) => match self.activate(&e) {
+                Some(store) => store,
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+        store.get(service, account).map_err(file_store_error)
+    }
+
+    /// Completes a keychain write
+    pub fn set(
+        &self,
+        service: &str,
+        account: &str,
+        value: &str,
+        keychain: keyring::Result<()>,
+    ) -> keyring::Result<()> {
+        match keychain {
+            Ok(()) => {
+                // The keychain works again; drop the older copy
+                if let Some(store) = self.stored() {
+                    let _ = store.delete(service, account);
+                }
+                Ok(())
+            }
+            Err(e) if is_unavailable(&e) => match self.activate(&e) {
+                Some(store) => store.set(service, account, value).map_err(file_store_error),
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Completes a keychain delete; returns whether there was a value in
+    /// either place
+    pub fn delete(
+        &self,
+        service: &str,
+        account: &str,
+        keychain: keyring::Result<()>,
+    ) -> keyring::Result<bool> {
+        let deleted = match keychain {
+            Ok(()) => true,
+            Err(keyring::Error::NoEntry) => false,
+            Err(e) if is_unavailable(&e) => {
+                if self.activate(&e).is_none() {
+                    return Err(e);
+                }
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        match self.stored() {
+            Some(store) => Ok(store.delete(service, account).map_err(file_store_error)? || deleted),
+            None => Ok(deleted),
+        }
+    }
+
+    /// Completes writing a value read from the store back to the keychain,
+    /// dropping the file copy once the keychain holds it
+    ///
+    /// A failed write leaves the value in the store, to be tried again on a
+    /// later read.
+    pub fn restore(&self, service: &str, account: &str, keychain: keyring::Result<()>) {
+        let Some(store) = self.store.as_ref() else {
+            return;
+        };
+        if let Err(e) = keychain {
+            tracing::debug!("Keeping {}/{} in the file store: {}", service, account, e);
+            return;
+        }
+        match store.delete(service, account) {
+            Ok(_) => tracing::info!("Moved {}/{} back to the keychain", service, account),
+            Err(e) => tracing::warn!(
+                "Failed to remove {}/{} from the file: {}",
+                service,
+                account,
+                e
+            ),
+        }
+    }
+}
+
+fn cache() -> &'static KeychainCache {
+    static CACHE: OnceLock<KeychainCache> = OnceLock::new();
+    CACHE.get_or_init(|| KeychainCache::new(DEFAULT_TTL))
+}
+
+fn fallback() -> &'static Fallback {
+    static FALLBACK: OnceLock<Fallback> = OnceLock::new();
+    FALLBACK.get_or_init(|| Fallback::new(AppConfig::config_dir().map(FileStore::in_dir)))
+}
+
+/// Why credentials are kept in the encrypted file instead of the keychain,
+/// if they are
+pub fn fallback_reason() -> Option<String> {
+    fallback().reason().map(String::from)
+}
+
+fn read(service: &str, account: &str) -> keyring::Result<Option<String>> {
+    let keychain = keyring::Entry::new(service, account).and_then(|entry| entry.get_password());
+    let missing = matches!(keychain, Err(keyring::Error::NoEntry));
+    let value = fallback().get(service, account, keychain)?;
+
+    // Found only in the file, so the keychain can take it back
+    if let (true, Some(value)) = (missing, &value) {
+        let keychain =
+            keyring::Entry::new(service, account).and_then(|entry| entry.set_password(value));
+        fallback().restore(service, account, keychain);
+    }
+    Ok(value)
+}
+
+/// Reads a password, from the cache when looked up recently
+///
+/// Missing entries and keychain errors both read as `None`.
+pub fn get_password(service: &str, account: &str) -> Option<String> {
+    cache().get_or_fetch(service, account, || read(service, account).ok().flatten())
+}
+
+/// Reads a password, telling a missing entry apart from a keychain error
+///
+/// Errors are not cached, so the next lookup tries the keychain again.
+pub fn try_get_password(service: &str, account: &str) -> keyring::Result<Option<String>> {
+    let mut error = None;
+    let value = cache().get_or_fetch(service, account, || match read(service, account) {
+        Ok(value) => value,
+        Err(e) => {
+            error = Some(e);
+            None
+        }
+    });
+    match error {
+        Some(e) => {
+            cache().invalidate(service, account);
+            Err(e)
+        }
+        None => Ok(value),
+    }
+}
+
+/// Writes a password and caches it
+pub fn set_password(service: &str, account: &str, value: &str) -> keyring::Result<()> {
+    let keychain =
+        keyring::Entry::new(service, account).and_then(|entry| entry.set_password(value));
+    fallback().set(service, account, value, keychain)?;
+    cache().insert(service, account, Some(value.to_string()));
+    Ok(())
+}
+
+/// Deletes a password; returns whether there was one
+pub fn delete_password(service: &str, account: &str) -> keyring::Result<bool> {
+    let keychain =
+        keyring::Entry::new(service, account).and_then(|entry| entry.delete_credential());
+    match fallback().delete(service, account, keychain) {
+        Ok(deleted) => {
+            cache().insert(service, account, None);
+            Ok(deleted)
+        }
+        Err(e) => {
+            cache().invalidate(service, account);
+            Err(e)
+        }
+    }
+}
+
+/// Forgets the cached lookups of a service, so the next ones read the
+/// keychain
+pub fn invalidate_service(service: &str) {
+    cache().invalidate_service(service);
+}
+
+/// Forgets every cached lookup, so the next ones read the keychain
+///
+/// Called when a provider logs in or out, since its credentials may have
+/// changed outside GPTBar.
+pub fn invalidate_all() {
+    cache().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_get_or_fetch_caches_hits_and_misses() {
+        let cache = KeychainCache::new(DEFAULT_TTL);
+        let fetches = Cell::new(0);
+        let fetch = |value: Option<&str>| {
+            fetches.set(fetches.get() + 1);
+            value.map(String::from)
+        };
+
+        assert_eq!(
+            cache.get_or_fetch("openai", "api_key", || fetch(Some("sk-1"))),
+            Some("sk-1".to_string())
+        );
+        assert_eq!(
+            cache.get_or_fetch("openai", "api_key", || fetch(Some("sk-2"))),
+            Some("sk-1".to_string())
+        );
+        assert_eq!(
+            cache.get_or_fetch("gemini", "api_key", || fetch(None)),
+            None
+        );
+        assert_eq!(
+            cache.get_or_fetch("gemini", "api_key", || fetch(None)),
+            None
+        );
+        assert_eq!(fetches.get(), 2);
+    }
+
+    #[test]
+    fn test_expired_entries_are_fetched_again() {
+        let cache = KeychainCache::new(Duration::ZERO);
+        cache.insert("openai", "api_key", Some("old".to_string()));
+        assert_eq!(
+            cache.get_or_fetch("openai", "api_key", || Some("new".to_string())),
+            Some("new".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let cache = KeychainCache::new(DEFAULT_TTL);
+        cache.insert("openai", "api_key", Some("sk-1".to_string()));
+        cache.insert("openai", "admin_key", Some("sk-admin".to_string()));
+        cache.insert("gemini", "api_key", None);
+
+        cache.invalidate("openai", "admin_key");
+        assert_eq!(
+            cache.get_or_fetch("openai", "api_key", || unreachable!()),
+            Some("sk-1".to_string())
+        );
+        assert_eq!(cache.get_or_fetch("openai", "admin_key", || None), None);
+
+        cache.invalidate_service("openai");
+        assert_eq!(
+            cache.get_or_fetch("openai", "api_key", || Some("sk-2".to_string())),
+            Some("sk-2".to_string())
+        );
+        assert_eq!(
+            cache.get_or_fetch("gemini", "api_key", || unreachable!()),
+            None
+        );
+
+        cache.clear();
+        assert_eq!(
+            cache.get_or_fetch("gemini", "api_key", || Some("key".to_string())),
+            Some("key".to_string())
+        );
+    }
+
+    fn unavailable() -> keyring::Error {
+        keyring::Error::NoStorageAccess("locked".into())
+    }
+
+    #[test]
+    fn test_fallback_after_keychain_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let fallback = Fallback::new(Some(FileStore::in_dir(dir.path())));
+
+        // Working keychain: nothing goes to the file
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(keyring::Error::NoEntry))
+                .unwrap(),
+            None
+        );
+        assert!(fallback.reason().is_none());
+
+        fallback
+            .set("GPTBar", "openai", "sk-1", Err(unavailable()))
+            .unwrap();
+        assert_eq!(fallback.reason(), Some(unavailable().to_string().as_str()));
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(unavailable()))
+                .unwrap(),
+            Some("sk-1".to_string())
+        );
+        // Written while the keychain was down, missing from it afterwards
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(keyring::Error::NoEntry))
+                .unwrap(),
+            Some("sk-1".to_string())
+        );
+        // Keychain values still win
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Ok("sk-2".to_string()))
+                .unwrap(),
+            Some("sk-2".to_string())
+        );
+
+        assert!(fallback
+            .delete("GPTBar", "openai", Err(unavailable()))
+            .unwrap());
+        assert!(!fallback
+            .delete("GPTBar", "openai", Err(keyring::Error::NoEntry))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_keychain_write_replaces_file_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let fallback = Fallback::new(Some(FileStore::in_dir(dir.path())));
+
+        fallback
+            .set("GPTBar", "openai", "sk-1", Err(unavailable()))
+            .unwrap();
+        fallback.set("GPTBar", "openai", "sk-2", Ok(())).unwrap();
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(keyring::Error::NoEntry))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_values_left_by_an_earlier_run_move_back_to_the_keychain() {
+        let dir = tempfile::tempdir().unwrap();
+        Fallback::new(Some(FileStore::in_dir(dir.path())))
+            .set("GPTBar", "openai", "sk-1", Err(unavailable()))
+            .unwrap();
+
+        // A new run whose keychain works, but lacks the value
+        let fallback = Fallback::new(Some(FileStore::in_dir(dir.path())));
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(keyring::Error::NoEntry))
+                .unwrap(),
+            Some("sk-1".to_string())
+        );
+        assert!(fallback.reason().is_none());
+
+        // A failed write keeps the file copy
+        fallback.restore("GPTBar", "openai", Err(unavailable()));
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(keyring::Error::NoEntry))
+                .unwrap(),
+            Some("sk-1".to_string())
+        );
+
+        fallback.restore("GPTBar", "openai", Ok(()));
+        assert_eq!(
+            fallback
+                .get("GPTBar", "openai", Err(keyring::Error::NoEntry))
+                .unwrap(),
+            None
+        );
+        assert!(!FileStore::in_dir(dir.path()).has_entries().unwrap());
+    }
+
+    #[test]
+    fn test_other_errors_are_not_routed() {
+        let dir = tempfile::tempdir().unwrap();
+        let fallback = Fallback::new(Some(FileStore::in_dir(dir.path())));
+
+        let result = fallback.set(
+            "GPTBar",
+            "openai",
+            "sk-1",
+            Err(keyring::Error::TooLong("account".into(), 255)),
+        );
+        assert!(matches!(result, Err(keyring::Error::TooLong(..))));
+        assert!(fallback.reason().is_none());
+
+        // Without a store the keychain error is kept
+        let fallback = Fallback::new(None);
+        assert!(matches!(
+            fallback.get("GPTBar", "openai", Err(unavailable())),
+            Err(keyring::Error::NoStorageAccess(_))
+        ));
+        assert!(fallback.reason().is_none());
+    }
+
+    /// Exercises the real keychain (Keychain on Apple Silicon, Credential
+    /// Manager and DPAPI on Windows on ARM), with the fallback taking over
+    /// when it fails
+    #[cfg(any(
+        all(target_os = "macos", target_arch = "aarch64"),
+        all(target_os = "windows", target_arch = "aarch64")
+    ))]
+    #[test]
+    fn test_platform_keychain_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let fallback = Fallback::new(Some(FileStore::in_dir(dir.path())));
+        let (service, account) = ("GPTBar-test", "keychain_roundtrip");
+        let entry = || keyring::Entry::new(service, account);
+
+        let keychain = entry().and_then(|e| e.set_password("secret"));
+        fallback.set(service, account, "secret", keychain).unwrap();
+
+        let keychain = entry().and_then(|e| e.get_password());
+        assert_eq!(
+            fallback.get(service, account, keychain).unwrap(),
+            Some("secret".to_string())
+        );
+
+        let keychain = entry().and_then(|e| e.delete_credential());
+        assert!(fallback.delete(service, account, keychain).unwrap());
+        let keychain = entry().and_then(|e| e.get_password());
+        assert_eq!(fallback.get(service, account, keychain).unwrap(), None);
+    }
+}
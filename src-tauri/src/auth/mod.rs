@@ -3,10 +3,14 @@
 //! Provides secure storage for tokens and credentials using:
 //! - Windows Credential Manager (via keyring crate)
 //! - DPAPI for additional encryption layer
+//! - Caching of keychain lookups
+//! - An encrypted file when the keychain fails
 //! - Cookie extraction from browsers
 
+pub mod keychain;
+mod file_store;
 mod secure_store;
 mod cookie_extractor;
 
-pub use secure_store::SecureStore;
-pub use cookie_extractor::{CookieExtractor, BrowserType};
+pub use secure_store::{SecureStore, TokenSync};
+pub use cookie_extractor::{Cookie, CookieExtractor, CookieError, CookieSource, BrowserType};
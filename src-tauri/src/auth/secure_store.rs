@@ -2,10 +2,20 @@
 //!
 //! Provides secure storage for OAuth tokens, API keys, and other credentials
 //! using the Windows Credential Manager (accessed via the keyring crate).
+//!
+//! Everything is stored under the "GPTBar" service. Older versions stored
+//! provider API keys under the provider's ID as the service, where another
+//! app's "openai" entry could overwrite them;
+//! [`SecureStore::migrate_api_keys`] moves those over.
+
+use std::collections::HashMap;
 
-use keyring::Entry;
 use thiserror::Error;
 
+#[cfg(test)]
+use super::file_store::FileStore;
+use super::keychain;
+
 /// Errors that can occur during secure storage operations
 #[derive(Debug, Error)]
 pub enum SecureStoreError {
@@ -22,6 +32,21 @@ pub enum SecureStoreError {
     InvalidFormat(String),
 }
 
+/// Account older versions stored API keys under, with the provider ID as
+/// the service
+const LEGACY_API_KEY_ACCOUNT: &str = "api_key";
+
+/// Outcome of [`SecureStore::sync_token`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSync {
+    /// No token was stored before
+    Stored,
+    /// A different token was stored and has been replaced
+    Rotated,
+    /// The same token was already stored
+    Unchanged,
+}
+
 /// Secure storage for tokens and credentials
 ///
 /// Uses Windows Credential Manager to store sensitive data securely.
@@ -46,21 +71,42 @@ pub enum SecureStoreError {
 /// ```
 pub struct SecureStore {
     service: &'static str,
+    backend: Backend,
+}
+
+/// Where a [`SecureStore`] keeps its values
+enum Backend {
+    /// The system keychain, through the [`keychain`] cache
+    Keychain,
+    /// An encrypted file, so tests leave the keychain alone
+    #[cfg(test)]
+    File(FileStore),
 }
 
 impl SecureStore {
     /// Creates a new SecureStore with the default service name
     pub fn new() -> Self {
-        Self {
-            service: "GPTBar",
-        }
+        Self::with_service("GPTBar")
     }
 
     /// Creates a new SecureStore with a custom service name
     ///
     /// Useful for testing or separating different credential sets.
     pub fn with_service(service: &'static str) -> Self {
-        Self { service }
+        Self {
+            service,
+            backend: Backend::Keychain,
+        }
+    }
+
+    /// Creates a SecureStore keeping its values in an encrypted file in
+    /// `dir` instead of the keychain
+    #[cfg(test)]
+    pub(crate) fn in_dir(dir: &std::path::Path) -> Self {
+        Self {
+            service: "GPTBar",
+            backend: Backend::File(FileStore::in_dir(dir)),
+        }
     }
 
     /// Returns the service name used for this store
@@ -75,8 +121,13 @@ impl SecureStore {
     /// * `key` - Identifier for the token (e.g., "claude-oauth", "copilot-token")
     /// * `token` - The secret token value to store
     pub fn set_token(&self, key: &str, token: &str) -> Result<(), SecureStoreError> {
-        let entry = Entry::new(self.service, key)?;
-        entry.set_password(token)?;
+        match &self.backend {
+            Backend::Keychain => keychain::set_password(self.service, key, token)?,
+            #[cfg(test)]
+            Backend::File(store) => store
+                .set(self.service, key, token)
+                .map_err(keychain::file_store_error)?,
+        }
         Ok(())
     }
 
@@ -88,13 +139,15 @@ impl SecureStore {
     ///
     /// # Returns
     ///
-    /// `Some(token)` if found, `None` if not stored
+    /// `Some(token)` if found, `None` if not stored. Recent lookups are
+    /// answered from the keychain cache.
     pub fn get_token(&self, key: &str) -> Result<Option<String>, SecureStoreError> {
-        let entry = Entry::new(self.service, key)?;
-        match entry.get_password() {
-            Ok(password) => Ok(Some(password)),
-            Err(keyring::Error::NoEntry) => Ok(None),
-            Err(e) => Err(SecureStoreError::Keyring(e)),
+        match &self.backend {
+            Backend::Keychain => Ok(keychain::try_get_password(self.service, key)?),
+            #[cfg(test)]
+            Backend::File(store) => Ok(store
+                .get(self.service, key)
+                .map_err(keychain::file_store_error)?),
         }
     }
 
@@ -108,11 +161,12 @@ impl SecureStore {
     ///
     /// `Ok(true)` if deleted, `Ok(false)` if not found
     pub fn delete_token(&self, key: &str) -> Result<bool, SecureStoreError> {
-        let entry = Entry::new(self.service, key)?;
-        match entry.delete_credential() {
-            Ok(()) => Ok(true),
-            Err(keyring::Error::NoEntry) => Ok(false),
-            Err(e) => Err(SecureStoreError::Keyring(e)),
+        match &self.backend {
+            Backend::Keychain => Ok(keychain::delete_password(self.service, key)?),
+            #[cfg(test)]
+            Backend::File(store) => Ok(store
+                .delete(self.service, key)
+                .map_err(keychain::file_store_error)?),
         }
     }
 
@@ -157,6 +211,54 @@ impl SecureStore {
         }
     }
 
+    /// Keeps a stored copy of a token read from elsewhere up to date
+    ///
+    /// Only writes when the token is new or has changed, and reports which.
+    pub fn sync_token(&self, key: &str, token: &str) -> Result<TokenSync, SecureStoreError> {
+        match self.get_token(key)? {
+            Some(stored) if stored == token => Ok(TokenSync::Unchanged),
+            Some(_) => {
+                self.set_token(key, token)?;
+                Ok(TokenSync::Rotated)
+            }
+            None => {
+                self.set_token(key, token)?;
+                Ok(TokenSync::Stored)
+            }
+        }
+    }
+
+    /// Entry a provider's API key is stored under, e.g. "openai-api-key"
+    pub fn api_key_entry(provider_id: &str) -> String {
+        format!("{}-api-key", provider_id)
+    }
+
+    /// Stores the API keys saved in the config under this service, moving
+    /// them out of the provider-named services older versions used
+    ///
+    /// `saved` maps provider IDs to their saved keys. A key only stays in
+    /// the config when writing it here failed, so it is newer than any
+    /// stored copy and replaces it. An old entry is only deleted when it
+    /// holds the saved key, since a service named after a provider may
+    /// belong to another app. Returns how many keys were stored or replaced.
+    pub fn migrate_api_keys(
+        &self,
+        saved: &HashMap<String, String>,
+    ) -> Result<usize, SecureStoreError> {
+        let mut stored = 0;
+        for (provider_id, key) in saved {
+            if self.sync_token(&Self::api_key_entry(provider_id), key)? != TokenSync::Unchanged {
+                stored += 1;
+            }
+            if keychain::get_password(provider_id, LEGACY_API_KEY_ACCOUNT).as_deref()
+                == Some(key.as_str())
+            {
+                keychain::delete_password(provider_id, LEGACY_API_KEY_ACCOUNT)?;
+            }
+        }
+        Ok(stored)
+    }
+
     /// Lists all known token keys for this service
     ///
     /// Note: This is a predefined list of known keys, not a dynamic enumeration
@@ -168,6 +270,15 @@ impl SecureStore {
             "copilot-token",
             "cursor-cookie",
             "gemini-token",
+            "mqtt-username",
+            "mqtt-password",
+            "api-token",
+            "claude-api-key",
+            "openai-api-key",
+            "gemini-api-key",
+            "codex-api-key",
+            "together-api-key",
+            "elevenlabs-api-key",
         ]
     }
 
@@ -320,10 +431,72 @@ mod tests {
         store.delete_token(test_key).unwrap();
     }
 
+    #[test]
+    fn test_sync_token() {
+        let store = test_store();
+        let test_key = "test-sync";
+
+        // Clean up
+        let _ = store.delete_token(test_key);
+
+        let sync = |token| store.sync_token(test_key, token).unwrap();
+        assert_eq!(sync("first"), TokenSync::Stored);
+        assert_eq!(sync("first"), TokenSync::Unchanged);
+        assert_eq!(sync("second"), TokenSync::Rotated);
+        assert_eq!(store.get_token(test_key).unwrap(), Some("second".to_string()));
+
+        // Clean up
+        store.delete_token(test_key).unwrap();
+    }
+
     #[test]
     fn test_known_keys() {
         let keys = SecureStore::known_keys();
         assert!(keys.contains(&"claude-oauth"));
         assert!(keys.contains(&"copilot-token"));
+        assert!(keys.contains(&SecureStore::api_key_entry("openai").as_str()));
+    }
+
+    #[test]
+    fn test_migrate_api_keys() {
+        let store = test_store();
+        let legacy = "gptbar-test-legacy";
+        let foreign = "gptbar-test-foreign";
+        let entry = |id| SecureStore::api_key_entry(id);
+
+        // Clean up
+        let _ = store.delete_token(&entry(legacy));
+        let _ = store.delete_token(&entry(foreign));
+
+        keychain::set_password(legacy, LEGACY_API_KEY_ACCOUNT, "sk-saved").unwrap();
+        keychain::set_password(foreign, LEGACY_API_KEY_ACCOUNT, "sk-other-app").unwrap();
+        // A store write that failed left an older key behind
+        store.set_token(&entry(foreign), "sk-stale").unwrap();
+        let saved = HashMap::from([
+            (legacy.to_string(), "sk-saved".to_string()),
+            (foreign.to_string(), "sk-entered".to_string()),
+        ]);
+
+        assert_eq!(store.migrate_api_keys(&saved).unwrap(), 2);
+        assert_eq!(
+            store.get_token(&entry(legacy)).unwrap(),
+            Some("sk-saved".to_string())
+        );
+        assert_eq!(
+            store.get_token(&entry(foreign)).unwrap(),
+            Some("sk-entered".to_string())
+        );
+        // Only the entry holding the saved key was ours to delete
+        assert_eq!(keychain::get_password(legacy, LEGACY_API_KEY_ACCOUNT), None);
+        assert_eq!(
+            keychain::get_password(foreign, LEGACY_API_KEY_ACCOUNT),
+            Some("sk-other-app".to_string())
+        );
+        assert_eq!(store.migrate_api_keys(&saved).unwrap(), 0);
+
+        // Clean up
+        keychain::delete_password(foreign, LEGACY_API_KEY_ACCOUNT).unwrap();
+        store.delete_token(&entry(legacy)).unwrap();
+        store.delete_token(&entry(foreign)).unwrap();
     }
 }
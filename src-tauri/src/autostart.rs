@@ -0,0 +1,483 @@
+//! Start on login
+//!
+//! [`AutoLaunch`] describes the command the system runs at login, normally
+//! GPTBar with [`HIDDEN_FLAG`] so it starts in the tray. Each platform keeps
+//! it in its usual place:
+//!
+//! - Windows: the `HKCU\...\Run` registry key, written with `reg`, or in an
+//!   MSIX package, which can't use the Run key, the package's
+//!   [`STARTUP_TASK_ID`] startup task
+//! - macOS: a LaunchAgent plist
+//! - Linux: an XDG autostart desktop file, or inside Flatpak a request to the
+//!   Background portal, since the sandbox can't write the host's autostart
+//!   directory
+//!
+//! Failures are returned rather than ignored so the settings UI can show
+//! them, e.g. when `reg` or `gdbus` can't be run.
+
+use std::path::PathBuf;
+
+use crate::config::HIDDEN_FLAG;
+#[cfg(target_os = "linux")]
+use crate::sandbox::is_flatpak;
+
+/// Task ID of the startup task an MSIX package declares in its manifest
+pub const STARTUP_TASK_ID: &str = "GPTBarStartup";
+
+/// A command the system runs at login
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoLaunch {
+    name: String,
+    exe: PathBuf,
+    args: Vec<String>,
+}
+
+impl AutoLaunch {
+    /// Creates an entry named `name` running `exe` without arguments
+    pub fn new(name: impl Into<String>, exe: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            exe: exe.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Passes `args` to the executable
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The entry for this GPTBar executable, starting hidden in the tray
+    pub fn current() -> Result<Self, String> {
+        let exe = std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
+        Ok(Self::new("GPTBar", exe).with_args([HIDDEN_FLAG]))
+    }
+
+    /// Adds or removes the entry
+    pub fn set_enabled(&self, enabled: bool) -> Result<(), String> {
+        if enabled {
+            self.enable()
+        } else {
+            self.disable()
+        }
+    }
+
+    /// The command line, with the executable path quoted
+    fn command_line(&self) -> String {
+        std::iter::once(format!("\"{}\"", self.exe.display()))
+            .chain(self.args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // ========================================================================
+    // Windows (Registry, or the startup task in an MSIX package)
+    // ========================================================================
+
+    #[cfg(target_os = "windows")]
+    const RUN_KEY: &'static str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+    #[cfg(target_os = "windows")]
+    fn reg(args: &[&str]) -> Result<std::process::Output, String> {
+        std::process::Command::new("reg")
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run reg command: {}", e))
+    }
+
+    #[cfg(target_os = "windows")]
+    fn startup_task() -> Result<windows::ApplicationModel::StartupTask, String> {
+        use windows::core::HSTRING;
+        use windows::ApplicationModel::StartupTask;
+
+        StartupTask::GetAsync(&HSTRING::from(STARTUP_TASK_ID))
+            .and_then(|op| op.get())
+            .map_err(|e| format!("Failed to get startup task {}: {}", STARTUP_TASK_ID, e))
+    }
+
+    /// Adds the entry, replacing an older one of the same name
+    ///
+    /// In an MSIX package this asks Windows to enable the startup task,
+    /// which fails once the user turned it off in Task Manager.
+    #[cfg(target_os = "windows")]
+    pub fn enable(&self) -> Result<(), String> {
+        use windows::ApplicationModel::StartupTaskState;
+
+        if is_packaged() {
+            let state = Self::startup_task()?
+                .RequestEnableAsync()
+                .and_then(|op| op.get())
+                .map_err(|e| format!("Failed to enable startup task: {}", e))?;
+            return match state {
+                StartupTaskState::Enabled | StartupTaskState::EnabledByPolicy => Ok(()),
+                StartupTaskState::DisabledByPolicy => {
+                    Err("Start on login is turned off by policy".to_string())
+                }
+                _ => {
+                    Err("Start on login was turned off in Task Manager's Startup apps".to_string())
+                }
+            };
+        }
+
+        let command = self.command_line();
+        let output = Self::reg(&[
+            "add",
+            Self::RUN_KEY,
+            "/v",
+            &self.name,
+            "/t",
+            "REG_SZ",
+            "/d",
+            &command,
+            "/f",
+        ])?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to add registry key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Removes the entry if there is one
+    #[cfg(target_os = "windows")]
+    pub fn disable(&self) -> Result<(), String> {
+        if is_packaged() {
+            return Self::startup_task()?
+                .Disable()
+                .map_err(|e| format!("Failed to disable startup task: {}", e));
+        }
+
+        if self.is_enabled()? != Some(true) {
+            return Ok(());
+        }
+        let output = Self::reg(&["delete", Self::RUN_KEY, "/v", &self.name, "/f"])?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to delete registry key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the entry exists
+    #[cfg(target_os = "windows")]
+    pub fn is_enabled(&self) -> Result<Option<bool>, String> {
+        use windows::ApplicationModel::StartupTaskState;
+
+        if is_packaged() {
+            let state = Self::startup_task()?
+                .State()
+                .map_err(|e| format!("Failed to read startup task: {}", e))?;
+            return Ok(Some(matches!(
+                state,
+                StartupTaskState::Enabled | StartupTaskState::EnabledByPolicy
+            )));
+        }
+
+        let output = Self::reg(&["query", Self::RUN_KEY, "/v", &self.name])?;
+        Ok(Some(output.status.success()))
+    }
+
+    // ========================================================================
+    // macOS (LaunchAgent plist)
+    // ========================================================================
+
+    #[cfg(target_os = "macos")]
+    fn launch_agent_path() -> Option<PathBuf> {
+        std::env::var("HOME")
+            .ok()
+            .map(|h| PathBuf::from(h).join("Library/LaunchAgents/com.gptbar.app.plist"))
+    }
+
+    /// Adds the entry, replacing an older one
+    #[cfg(target_os = "macos")]
+    pub fn enable(&self) -> Result<(), String> {
+        let plist_path = Self::launch_agent_path().ok_or("Could not determine LaunchAgent path")?;
+
+        let arguments: String = std::iter::once(self.exe.display().to_string())
+            .chain(self.args.iter().cloned())
+            .map(|arg| format!("        <string>{}</string>\n", arg))
+            .collect();
+        let plist_content = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.gptbar.app</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <false/>
+</dict>
+</plist>
+"#,
+            arguments
+        );
+
+        // Create LaunchAgents directory if needed
+        if let Some(parent) = plist_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create LaunchAgents dir: {}", e))?;
+        }
+
+        std::fs::write(&plist_path, plist_content)
+            .map_err(|e| format!("Failed to write plist: {}", e))
+    }
+
+    /// Removes the entry if there is one
+    #[cfg(target_os = "macos")]
+    pub fn disable(&self) -> Result<(), String> {
+        let plist_path = Self::launch_agent_path().ok_or("Could not determine LaunchAgent path")?;
+        remove_if_present(&plist_path)
+    }
+
+    /// Whether the entry exists
+    #[cfg(target_os = "macos")]
+    pub fn is_enabled(&self) -> Result<Option<bool>, String> {
+        Ok(Self::launch_agent_path().map(|p| p.exists()))
+    }
+
+    // ========================================================================
+    // Linux (XDG autostart, or the Background portal inside Flatpak)
+    // ========================================================================
+
+    #[cfg(target_os = "linux")]
+    fn autostart_path() -> Option<PathBuf> {
+        std::env::var("XDG_CONFIG_HOME")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var("HOME")
+                    .ok()
+                    .map(|p| PathBuf::from(p).join(".config"))
+            })
+            .map(|p| p.join("autostart/gptbar.desktop"))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn desktop_entry(&self) -> String {
+        format!(
+            r#"[Desktop Entry]
+Type=Application
+Name={}
+Comment=Monitor AI provider usage from system tray
+Exec={}
+Icon=gptbar
+Terminal=false
+Categories=Utility;
+StartupNotify=false
+X-GNOME-Autostart-enabled=true
+"#,
+            self.name,
+            self.command_line()
+        )
+    }
+
+    /// Options for the Background portal's `RequestBackground`, in GVariant
+    /// text format
+    ///
+    /// The portal runs the command inside the sandbox, so it is named by the
+    /// executable's file name rather than its path.
+    #[cfg(target_os = "linux")]
+    fn portal_options(&self, enabled: bool) -> String {
+        let program = self
+            .exe
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.exe.display().to_string());
+        let commandline: Vec<String> = std::iter::once(program)
+            .chain(self.args.iter().cloned())
+            .map(|arg| gvariant_string(&arg))
+            .collect();
+        format!(
+            "{{'reason': <{}>, 'autostart': <{}>, 'commandline': <[{}]>}}",
+            gvariant_string(&format!("{} keeps showing usage from the tray", self.name)),
+            enabled,
+            commandline.join(", ")
+        )
+    }
+
+    /// Asks the Background portal to add or remove the entry
+    ///
+    /// The user may be asked to allow it; the answer arrives later and isn't
+    /// waited for.
+    #[cfg(target_os = "linux")]
+    fn request_background(&self, enabled: bool) -> Result<(), String> {
+        let output = std::process::Command::new("gdbus")
+            .args([
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.portal.Desktop",
+                "--object-path",
+                "/org/freedesktop/portal/desktop",
+                "--method",
+                "org.freedesktop.portal.Background.RequestBackground",
+                "",
+                &self.portal_options(enabled),
+            ])
+            .output()
+            .map_err(|e| format!("Failed to run gdbus: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Background portal request failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds the entry, replacing an older one
+    #[cfg(target_os = "linux")]
+    pub fn enable(&self) -> Result<(), String> {
+        if is_flatpak() {
+            return self.request_background(true);
+        }
+
+        let desktop_path = Self::autostart_path().ok_or("Could not determine autostart path")?;
+
+        // Create autostart directory if needed
+        if let Some(parent) = desktop_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create autostart dir: {}", e))?;
+        }
+
+        std::fs::write(&desktop_path, self.desktop_entry())
+            .map_err(|e| format!("Failed to write desktop file: {}", e))
+    }
+
+    /// Removes the entry if there is one
+    #[cfg(target_os = "linux")]
+    pub fn disable(&self) -> Result<(), String> {
+        if is_flatpak() {
+            return self.request_background(false);
+        }
+
+        let desktop_path = Self::autostart_path().ok_or("Could not determine autostart path")?;
+        remove_if_present(&desktop_path)
+    }
+
+    /// Whether the entry exists, or `None` inside Flatpak, where the portal
+    /// keeps it out of the sandbox's sight
+    #[cfg(target_os = "linux")]
+    pub fn is_enabled(&self) -> Result<Option<bool>, String> {
+        if is_flatpak() {
+            return Ok(None);
+        }
+        Ok(Self::autostart_path().map(|p| p.exists()))
+    }
+
+    // ========================================================================
+    // Other platforms
+    // ========================================================================
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    pub fn enable(&self) -> Result<(), String> {
+        Err("Start on login is not supported on this platform".to_string())
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    pub fn disable(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    pub fn is_enabled(&self) -> Result<Option<bool>, String> {
+        Ok(Some(false))
+    }
+}
+
+/// Whether this process was started by the system at login
+///
+/// Entries outside MSIX pass [`HIDDEN_FLAG`] instead, since a startup task
+/// can't pass arguments.
+pub fn launched_at_login() -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::ApplicationModel::Activation::ActivationKind;
+        use windows::ApplicationModel::AppInstance;
+
+        is_packaged()
+            && AppInstance::GetActivatedEventArgs()
+                .and_then(|args| args.Kind())
+                .is_ok_and(|kind| kind == ActivationKind::StartupTask)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    false
+}
+
+/// Whether GPTBar runs from an MSIX package
+#[cfg(target_os = "windows")]
+pub fn is_packaged() -> bool {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::APPMODEL_ERROR_NO_PACKAGE;
+    use windows::Win32::Storage::Packaging::Appx::GetCurrentPackageFullName;
+
+    let mut length = 0u32;
+    // Asking for the length alone fails with a buffer error when packaged
+    let result = unsafe { GetCurrentPackageFullName(&mut length, PWSTR::null()) };
+    result != APPMODEL_ERROR_NO_PACKAGE
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn remove_if_present(path: &std::path::Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+/// Quotes a string for GVariant text format
+#[cfg(target_os = "linux")]
+fn gvariant_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let entry = AutoLaunch::new("GPTBar", "/opt/GPT Bar/gptbar").with_args(["--hidden"]);
+        assert_eq!(entry.command_line(), r#""/opt/GPT Bar/gptbar" --hidden"#);
+
+        let entry = AutoLaunch::new("GPTBar", "/usr/bin/gptbar");
+        assert_eq!(entry.command_line(), r#""/usr/bin/gptbar""#);
+    }
+
+    #[test]
+    fn test_current_starts_hidden() {
+        let entry = AutoLaunch::current().unwrap();
+        assert_eq!(entry.args, vec![HIDDEN_FLAG.to_string()]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_entries() {
+        let entry = AutoLaunch::new("GPTBar", "/app/bin/gptbar").with_args(["--hidden"]);
+        assert!(entry
+            .desktop_entry()
+            .contains("\nExec=\"/app/bin/gptbar\" --hidden\n"));
+        assert_eq!(
+            entry.portal_options(true),
+            "{'reason': <'GPTBar keeps showing usage from the tray'>, \
+             'autostart': <true>, 'commandline': <['gptbar', '--hidden']>}"
+        );
+        assert_eq!(gvariant_string(r"it's a\b"), r"'it\'s a\\b'");
+    }
+}
@@ -0,0 +1,191 @@
+//! Budget periods
+//!
+//! Spend is measured per budget period: a calendar month, a month starting
+//! on the billing anchor day, or a week starting Monday. Periods are in UTC,
+//! like provider billing. [`BudgetTracker`] notices when a provider's period
+//! rolls over so gauges can be reset and a "new billing period" event sent.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How a provider's spend is grouped into periods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    /// From the first of each month
+    #[default]
+    CalendarMonth,
+    /// From the same day each month, or the month's last day if shorter
+    BillingAnchor { day: u32 },
+    /// From Monday each week
+    Weekly,
+}
+
+impl BudgetPeriod {
+    /// Start and end of the period containing `now`
+    pub fn bounds(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = now.date_naive();
+        let (start, end) = match *self {
+            Self::CalendarMonth => {
+                let start = today.with_day(1).unwrap_or(today);
+                (start, start + Months::new(1))
+            }
+            Self::BillingAnchor { day } => {
+                let this_month = today.with_day(1).unwrap_or(today);
+                let mut start = anchor_day(this_month, day);
+                if start > today {
+                    start = anchor_day(this_month - Months::new(1), day);
+                }
+                let next_month = start.with_day(1).unwrap_or(start) + Months::new(1);
+                (start, anchor_day(next_month, day))
+            }
+            Self::Weekly => {
+                let start = today - Days::new(today.weekday().num_days_from_monday().into());
+                (start, start + Days::new(7))
+            }
+        };
+        (midnight(start), midnight(end))
+    }
+}
+
+/// `day` of the month starting at `first`, clamped to the month's length
+fn anchor_day(first: NaiveDate, day: u32) -> NaiveDate {
+    let last = (first + Months::new(1)).pred_opt().unwrap_or(first).day();
+    first.with_day(day.clamp(1, last)).unwrap_or(first)
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+/// A provider entered a new budget period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetRollover {
+    pub provider_id: String,
+    /// Start of the new period
+    pub start: DateTime<Utc>,
+    /// End of the new period
+    pub end: DateTime<Utc>,
+}
+
+/// Remembers each provider's current period to detect rollovers
+#[derive(Debug, Default)]
+pub struct BudgetTracker {
+    starts: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl BudgetTracker {
+    /// Creates a tracker that hasn't seen any period yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the period containing `now` for a provider
+    ///
+    /// Returns the new period when it differs from the one last seen. The
+    /// first period seen for a provider is not a rollover.
+    pub fn observe(
+        &self,
+        provider_id: &str,
+        period: BudgetPeriod,
+        now: DateTime<Utc>,
+    ) -> Option<BudgetRollover> {
+        let (start, end) = period.bounds(now);
+        let mut starts = self.starts.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = starts.insert(provider_id.to_string(), start)?;
+        (previous != start).then(|| BudgetRollover {
+            provider_id: provider_id.to_string(),
+            start,
+            end,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 10, 0, 0).unwrap()
+    }
+
+    fn day(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_calendar_month_bounds() {
+        let (start, end) = BudgetPeriod::CalendarMonth.bounds(at(2025, 12, 15));
+        assert_eq!(start, day(2025, 12, 1));
+        assert_eq!(end, day(2026, 1, 1));
+    }
+
+    #[test]
+    fn test_billing_anchor_bounds() {
+        let period = BudgetPeriod::BillingAnchor { day: 15 };
+        assert_eq!(
+            period.bounds(at(2026, 3, 20)),
+            (day(2026, 3, 15), day(2026, 4, 15))
+        );
+        assert_eq!(
+            period.bounds(at(2026, 1, 3)),
+            (day(2025, 12, 15), day(2026, 1, 15))
+        );
+
+        // Clamped to the last day of shorter months
+        let period = BudgetPeriod::BillingAnchor { day: 31 };
+        assert_eq!(
+            period.bounds(at(2026, 2, 10)),
+            (day(2026, 1, 31), day(2026, 2, 28))
+        );
+        assert_eq!(
+            period.bounds(at(2026, 3, 1)),
+            (day(2026, 2, 28), day(2026, 3, 31))
+        );
+    }
+
+    #[test]
+    fn test_weekly_bounds() {
+        // 2026-03-05 is a Thursday
+        let (start, end) = BudgetPeriod::Weekly.bounds(at(2026, 3, 5));
+        assert_eq!(start, day(2026, 3, 2));
+        assert_eq!(end, day(2026, 3, 9));
+
+        let (start, _) = BudgetPeriod::Weekly.bounds(day(2026, 3, 2));
+        assert_eq!(start, day(2026, 3, 2));
+    }
+
+    #[test]
+    fn test_tracker_detects_rollover() {
+        let tracker = BudgetTracker::new();
+        let period = BudgetPeriod::CalendarMonth;
+
+        assert_eq!(tracker.observe("claude", period, at(2026, 3, 30)), None);
+        assert_eq!(tracker.observe("claude", period, at(2026, 3, 31)), None);
+        assert_eq!(
+            tracker.observe("claude", period, at(2026, 4, 1)),
+            Some(BudgetRollover {
+                provider_id: "claude".to_string(),
+                start: day(2026, 4, 1),
+                end: day(2026, 5, 1),
+            })
+        );
+        assert_eq!(tracker.observe("claude", period, at(2026, 4, 2)), None);
+    }
+
+    #[test]
+    fn test_period_serialization() {
+        let json = serde_json::to_value(BudgetPeriod::BillingAnchor { day: 15 }).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "billing_anchor", "day": 15 })
+        );
+
+        let period: BudgetPeriod = serde_json::from_str(r#"{"kind":"weekly"}"#).unwrap();
+        assert_eq!(period, BudgetPeriod::Weekly);
+    }
+}
@@ -0,0 +1,303 @@
+//! Command-line mode
+//!
+//! Subcommands print usage for status bars on tiling window managers, which
+//! often have no system tray:
+//!
+//! - `gptbar waybar [--provider <id>]` prints a waybar custom-module JSON line
+//! - `gptbar polybar [--provider <id>]` prints a plain text line
+//! - `gptbar tui` opens the terminal UI, which refreshes on its own
+//!
+//! `gptbar <gptbar://...>`, which the OS runs for [deep links](crate::deep_link),
+//! hands the link to a running instance and exits, or starts the GUI when none
+//! is running.
+//!
+//! Status bar data comes from a running GPTBar instance over IPC when
+//! available, otherwise from a one-shot fetch of the enabled providers.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::agents::{default_socket_path, IpcAgent, NotificationThresholds, UsageReport};
+use crate::config::AppConfig;
+use crate::deep_link::DeepLink;
+use crate::providers::{ProviderRegistry, UsageSnapshot, AGGREGATE_ID};
+
+/// Output in waybar's custom module `return-type: json` format
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct WaybarOutput {
+    /// Text shown in the bar
+    pub text: String,
+    /// Per-provider breakdown shown on hover
+    pub tooltip: String,
+    /// CSS class: `normal`, `warning`, `critical` or `unavailable`
+    pub class: String,
+    /// Usage percentage for format-icons
+    pub percentage: u8,
+}
+
+/// Runs a subcommand if one was given
+///
+/// Returns the process exit code, or `None` when the arguments don't name a
+/// subcommand and the GUI should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    if let Some((url, _)) = DeepLink::from_args(args) {
+        return forward_link(&url).then_some(0);
+    }
+
+    let command = args.get(1)?.as_str();
+    if command == "tui" {
+        return Some(run_tui());
+    }
+    if !matches!(command, "waybar" | "polybar") {
+        return None;
+    }
+
+    let provider = args
+        .iter()
+        .position(|a| a == "--provider")
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+        .unwrap_or(AGGREGATE_ID);
+
+    let report = match load_report() {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("gptbar: {}", e);
+            return Some(1);
+        }
+    };
+
+    let config = AppConfig::load();
+    let output = waybar_output(
+        &report,
+        provider,
+        &config.active_providers(),
+        &NotificationThresholds::default(),
+    );
+
+    match command {
+        "waybar" => match serde_json::to_string(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("gptbar: {}", e);
+                return Some(1);
+            }
+        },
+        _ => println!("{}", output.text),
+    }
+
+    Some(0)
+}
+
+#[cfg(feature = "tui")]
+fn run_tui() -> i32 {
+    match crate::tui::run() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("gptbar: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui() -> i32 {
+    eprintln!("gptbar: built without the terminal UI");
+    2
+}
+
+/// Hands a deep link to the running app; returns whether one took it
+fn forward_link(url: &str) -> bool {
+    let Ok(runtime) = tokio::runtime::Runtime::new() else {
+        return false;
+    };
+    runtime.block_on(async {
+        IpcAgent::query(&default_socket_path(), &format!("OPEN {}", url))
+            .await
+            .ok()
+            .and_then(|response| serde_json::from_str::<serde_json::Value>(&response).ok())
+            .is_some_and(|response| response["ok"] == true)
+    })
+}
+
+/// Reads usage from the running app, or fetches it directly
+fn load_report() -> Result<UsageReport, String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+
+    runtime.block_on(async {
+        if let Ok(response) = IpcAgent::query(&default_socket_path(), "GET usage").await {
+            if let Ok(report) = serde_json::from_str(&response) {
+                return Ok(report);
+            }
+        }
+        Ok(fetch_report().await)
+    })
+}
+
+/// One-shot fetch of all enabled providers
+async fn fetch_report() -> UsageReport {
+    let config = AppConfig::load();
+    let registry = ProviderRegistry::new();
+    let mut providers = HashMap::new();
+
+    for id in &config.enabled_providers {
+        if let Some(provider) = registry.get(id) {
+            match provider.fetch().await {
+                Ok(snapshot) => {
+                    providers.insert(id.clone(), snapshot);
+                }
+                Err(e) => tracing::debug!("Failed to fetch {}: {}", id, e),
+            }
+        }
+    }
+
+    let aggregate = config
+        .usage_aggregate()
+        .snapshot(&providers, &config.enabled_providers);
+    UsageReport {
+        providers,
+        aggregate,
+    }
+}
+
+/// Formats a usage report for waybar
+///
+/// `provider` selects the snapshot shown in the bar (`aggregate` for the
+/// combined view); the tooltip lists every provider in display order.
+pub fn waybar_output(
+    report: &UsageReport,
+    provider: &str,
+    order: &[String],
+    thresholds: &NotificationThresholds,
+) -> WaybarOutput {
+    let snapshot: Option<&UsageSnapshot> = if provider == AGGREGATE_ID {
+        report.aggregate.as_ref()
+    } else {
+        report.providers.get(provider)
+    };
+
+    let tooltip = order
+        .iter()
+        .filter_map(|id| {
+            report
+                .providers
+                .get(id)
+                .map(|s| format!("{}: {:.0}%", id, s.max_usage()))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let Some(snapshot) = snapshot else {
+        return WaybarOutput {
+            text: "--".to_string(),
+            tooltip: if tooltip.is_empty() {
+                "No usage data".to_string()
+            } else {
+                tooltip
+            },
+            class: "unavailable".to_string(),
+            percentage: 0,
+        };
+    };
+
+    let percent = snapshot.max_usage();
+    let class = if percent >= thresholds.critical_percent {
+        "critical"
+    } else if percent >= thresholds.warning_percent {
+        "warning"
+    } else {
+        "normal"
+    };
+
+    WaybarOutput {
+        text: format!("{:.0}%", percent),
+        tooltip,
+        class: class.to_string(),
+        percentage: percent.clamp(0.0, 100.0).round() as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::RateWindow;
+
+    fn report() -> UsageReport {
+        let mut providers = HashMap::new();
+        providers.insert(
+            "claude".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(85.0)),
+        );
+        providers.insert(
+            "openai".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(12.4)),
+        );
+        UsageReport {
+            providers,
+            aggregate: Some(UsageSnapshot::new().with_primary(RateWindow::new(85.0))),
+        }
+    }
+
+    fn order() -> Vec<String> {
+        vec!["claude".to_string(), "openai".to_string()]
+    }
+
+    #[test]
+    fn test_waybar_aggregate() {
+        let output = waybar_output(
+            &report(),
+            AGGREGATE_ID,
+            &order(),
+            &NotificationThresholds::default(),
+        );
+        assert_eq!(output.text, "85%");
+        assert_eq!(output.class, "warning");
+        assert_eq!(output.percentage, 85);
+        assert_eq!(output.tooltip, "claude: 85%\nopenai: 12%");
+    }
+
+    #[test]
+    fn test_waybar_single_provider() {
+        let output = waybar_output(
+            &report(),
+            "openai",
+            &order(),
+            &NotificationThresholds::default(),
+        );
+        assert_eq!(output.text, "12%");
+        assert_eq!(output.class, "normal");
+    }
+
+    #[test]
+    fn test_waybar_without_data() {
+        let output = waybar_output(
+            &UsageReport::default(),
+            AGGREGATE_ID,
+            &order(),
+            &NotificationThresholds::default(),
+        );
+        assert_eq!(output.text, "--");
+        assert_eq!(output.class, "unavailable");
+        assert_eq!(output.tooltip, "No usage data");
+    }
+
+    #[test]
+    fn test_waybar_json_format() {
+        let output = waybar_output(
+            &report(),
+            "claude",
+            &order(),
+            &NotificationThresholds::new(50.0, 80.0),
+        );
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["class"], "critical");
+        assert_eq!(json["percentage"], 85);
+    }
+
+    #[test]
+    fn test_run_without_subcommand() {
+        assert_eq!(run(&["gptbar".to_string()]), None);
+        assert_eq!(run(&["gptbar".to_string(), "--minimized".to_string()]), None);
+    }
+}
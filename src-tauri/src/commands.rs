@@ -1,78 +1,123 @@
 //! Tauri IPC Commands
 //!
 //! All commands that can be called from the frontend via Tauri IPC.
+//!
+//! Provider commands fail with a [`ProviderErrorInfo`]
+//! (`{ code, message, retry_after_secs?, doc_url?, remediation? }`) so the UI
+//! can tell error kinds apart and say what to do about them; other commands
+//! fail with a plain message string.
 
+use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
-use crate::config::{AppConfig, ProviderSettings};
-use crate::providers::{Provider, ProviderMetadata, UsageSnapshot};
+use crate::accessibility::UiPreferences;
+use crate::agents::{
+    api_token, AgentInfo, FetchMetrics, TelemetryAgent, MQTT_PASSWORD_KEY, MQTT_USERNAME_KEY,
+};
+use crate::attention::AttentionItem;
+use crate::auth::{keychain, SecureStore};
+use crate::config::{AppConfig, AppearanceSettings, Profile, ProviderSettings};
+use crate::crash::{self, CrashReport};
+use crate::diagnostics::DiagnosticBundle;
+use crate::format::{self, Currency, ResetStyle};
+use crate::history::{ExportFormat, HistoryEntry, HistoryRange, NotificationRecord, UsageStats};
+use crate::i18n::{self, Language};
+use crate::logging;
+use crate::memory::MemoryReport;
+#[cfg(feature = "claude")]
+use crate::providers::ClaudeLogCollector;
+#[cfg(feature = "codex")]
+use crate::providers::CodexLogCollector;
+#[cfg(feature = "openai")]
+use crate::providers::OrganizationInfo;
+use crate::providers::{
+    settings_api_key, AuthObservation, AuthState, CredentialReport, ProjectUsage, Provider,
+    ProviderError, ProviderErrorInfo, ProviderMetadata, ProviderPage, ProviderThresholds,
+    SettingKind, UsageSnapshot, WindowSlot,
+};
+use crate::security::OsAuth;
+use crate::team::{TeamClient, TeamView, TEAM_TOKEN_KEY};
+use crate::telemetry::{self, TelemetryBatch};
+use crate::tray::{TrayIconStyle, TrayState, TrayTitleSource};
+use crate::updates::AppVersionInfo;
+use crate::window::{self, PopupState};
 use crate::AppState;
 
 /// Fetches usage data from Claude
 #[tauri::command]
 pub async fn fetch_usage(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<UsageSnapshot, String> {
-    let state = state.read().await;
-    state.claude.fetch().await.map_err(|e| e.to_string())
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<UsageSnapshot, ProviderErrorInfo> {
+    let provider = find_provider(&state, "claude")?;
+    let snapshot = provider
+        .fetch()
+        .await
+        .map_err(|e| e.to_info_for(provider.as_ref()))?;
+    Ok(AppConfig::load().for_display("claude", snapshot))
 }
 
 /// Gets the cached usage snapshot for Claude
 #[tauri::command]
 pub async fn get_cached_usage(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<Option<UsageSnapshot>, String> {
-    let state = state.read().await;
-    if let Some(_agent) = state.agent_manager.get("refresh").await {
-        // Downcast to RefreshAgent would be needed here
-        // For now, return None
-    }
-    Ok(None)
+    Ok(state
+        .refresh
+        .get_snapshot("claude")
+        .await
+        .map(|snapshot| AppConfig::load().for_display("claude", snapshot.into_cached())))
 }
 
 /// Checks if Claude authentication is available
 #[tauri::command]
-pub async fn is_claude_available(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<bool, String> {
-    let state = state.read().await;
-    Ok(state.claude.is_available().await)
+pub async fn is_claude_available(state: tauri::State<'_, Arc<AppState>>) -> Result<bool, String> {
+    match state.registry.get("claude") {
+        Some(claude) => Ok(state.auth_states.check(claude.as_ref()).await.is_usable()),
+        None => Ok(false),
+    }
 }
 
 /// Initiates Claude login
 #[tauri::command]
 pub async fn login_claude(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<bool, String> {
-    let state = state.read().await;
-    state.claude.login().await.map_err(|e| e.to_string())
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<bool, ProviderErrorInfo> {
+    AppConfig::load().ensure_not_viewer()?;
+    keychain::invalidate_all();
+    let provider = find_provider(&state, "claude")?;
+    let result = provider.login().await;
+    credentials_changed(&state, "claude").await;
+    result.map_err(|e| e.to_info_for(provider.as_ref()))
 }
 
 /// Logs out from Claude
 #[tauri::command]
 pub async fn logout_claude(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<(), String> {
-    let state = state.read().await;
-    state.claude.logout().await.map_err(|e| e.to_string())
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), ProviderErrorInfo> {
+    AppConfig::load().ensure_not_viewer()?;
+    keychain::invalidate_all();
+    let provider = find_provider(&state, "claude")?;
+    let result = provider.logout().await;
+    logged_out(&state, "claude", &result).await;
+    result.map_err(|e| e.to_info_for(provider.as_ref()))
 }
 
 /// Reloads OAuth token from Claude Code CLI credentials
+#[cfg(feature = "claude")]
 #[tauri::command]
 pub async fn reload_token(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<bool, String> {
-    let state = state.read().await;
-    state.claude.reload_token().await.map_err(|e| e.to_string())
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<bool, ProviderErrorInfo> {
+    let result = state.claude.reload_token().await;
+    credentials_changed(&state, "claude").await;
+    result.map_err(|e| e.to_info_for(&*state.claude))
 }
 
 /// Triggers an immediate refresh of usage data
 #[tauri::command]
-pub async fn trigger_refresh(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<(), String> {
-    let state = state.read().await;
+pub async fn trigger_refresh(state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
     state
         .agent_manager
         .trigger_agent("refresh")
@@ -83,14 +128,215 @@ pub async fn trigger_refresh(
 /// Gets the status of all agents
 #[tauri::command]
 pub async fn get_agent_status(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
-) -> Result<Vec<(String, String)>, String> {
-    let state = state.read().await;
-    let status = state.agent_manager.status().await;
-    Ok(status
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<AgentInfo>, String> {
+    Ok(state.agent_manager.info().await)
+}
+
+/// Starts a stopped agent
+#[tauri::command]
+pub async fn start_agent(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state
+        .agent_manager
+        .start_agent(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Stops a running agent until it is started again or the app restarts
+#[tauri::command]
+pub async fn stop_agent(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state
+        .agent_manager
+        .stop_agent(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Changes and saves the settings of an agent, e.g. `{"interval_minutes": 10}`
+/// for the refresh agent
+#[tauri::command]
+pub async fn set_agent_config(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+    config: serde_json::Value,
+) -> Result<(), String> {
+    state
+        .agent_manager
+        .configure_agent(&id, config.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut app_config = AppConfig::load();
+    app_config.set_agent_config(&id, config);
+    app_config.save()
+}
+
+// ============================================================================
+// App State Snapshot
+// ============================================================================
+
+/// Everything the popup needs to render, gathered in one IPC round-trip
+#[derive(Debug, Serialize)]
+pub struct AppStateSnapshot {
+    /// Metadata of all providers, in display order
+    pub providers: Vec<ProviderMetadata>,
+    /// Enabled provider IDs in display order, pinned ones first, by urgency
+    /// when `provider_sort` is `urgency`
+    pub enabled_providers: Vec<String>,
+    /// Whether each enabled provider has credentials
+    pub available: HashMap<String, bool>,
+    /// What is known about each enabled provider's credentials
+    pub auth_states: HashMap<String, AuthState>,
+    /// Latest snapshot of each provider from the refresh agent
+    pub snapshots: HashMap<String, UsageSnapshot>,
+    /// Providers whose latest snapshot is older than two refresh intervals
+    pub stale: Vec<String>,
+    /// Status and next scheduled run of each agent
+    pub agents: Vec<AgentInfo>,
+    /// Current configuration, redacted like `get_config`
+    pub config: AppConfig,
+    /// Whether demo usage is shown instead of real providers
+    pub demo_mode: bool,
+    /// Why credentials are kept in an encrypted file instead of the
+    /// keychain, if they are
+    pub credential_store_fallback: Option<String>,
+}
+
+/// Looks up one of the app's configured providers by ID
+///
+/// Providers left out of this build are unknown.
+fn find_provider(state: &AppState, provider_id: &str) -> Result<Arc<dyn Provider>, ProviderError> {
+    state
+        .registry
+        .get(provider_id)
+        .ok_or_else(|| ProviderError::UnknownProvider(provider_id.to_string()))
+}
+
+/// Gets the full state the popup renders from
+///
+/// Replaces the separate provider, config, availability, snapshot and agent
+/// status calls made on every open.
+#[tauri::command]
+pub async fn get_app_state(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<AppStateSnapshot, String> {
+    let config = get_config()?;
+
+    let mut providers = state.registry.metadata();
+    config.order_metadata(&mut providers);
+    let mut enabled_providers = config.active_providers();
+
+    let mut auth_states = HashMap::new();
+    for provider_id in &enabled_providers {
+        if let Some(provider) = state.registry.get(provider_id) {
+            auth_states.insert(
+                provider_id.clone(),
+                state.auth_states.check(provider.as_ref()).await,
+            );
+        }
+    }
+    let available = auth_states
+        .iter()
+        .map(|(id, auth)| (id.clone(), auth.is_usable()))
+        .collect();
+
+    let snapshots: HashMap<String, UsageSnapshot> = state
+        .refresh
+        .get_all_snapshots()
+        .await
         .into_iter()
-        .map(|(id, s)| (id.to_string(), format!("{:?}", s)))
-        .collect())
+        .map(|(id, snapshot)| {
+            let snapshot = config.for_display(&id, snapshot);
+            (id, snapshot)
+        })
+        .collect();
+    let now = chrono::Utc::now();
+    let mut stale: Vec<String> = snapshots
+        .iter()
+        .filter(|(_, snapshot)| snapshot.is_stale(config.stale_after(), now))
+        .map(|(id, _)| id.clone())
+        .collect();
+    stale.sort();
+
+    config.sort_providers(&mut providers, |provider| provider.id.as_str(), &snapshots);
+    config.sort_providers(&mut enabled_providers, |id| id.as_str(), &snapshots);
+
+    let agents = state.agent_manager.info().await;
+
+    Ok(AppStateSnapshot {
+        providers,
+        enabled_providers,
+        available,
+        auth_states,
+        snapshots,
+        stale,
+        agents,
+        demo_mode: config.is_demo(),
+        config,
+        credential_store_fallback: keychain::fallback_reason(),
+    })
+}
+
+// ============================================================================
+// Crash Report Commands
+// ============================================================================
+
+/// Gets the crash report left by an earlier run, if any
+#[tauri::command]
+pub fn get_crash_report() -> Option<CrashReport> {
+    crash::pending()
+}
+
+/// Opens a GitHub issue filled in with the pending crash report, then
+/// deletes the report
+#[tauri::command]
+pub fn report_crash() -> Result<(), String> {
+    let report = crash::pending().ok_or("No crash report")?;
+    opener::open(report.issue_url()).map_err(|e| format!("Failed to open browser: {}", e))?;
+    crash::dismiss()
+}
+
+/// Deletes the pending crash report without reporting it
+#[tauri::command]
+pub fn dismiss_crash_report() -> Result<(), String> {
+    crash::dismiss()
+}
+
+// ============================================================================
+// Diagnostic Commands
+// ============================================================================
+
+/// Saves a sanitized diagnostic bundle to a zip chosen in a save dialog
+///
+/// Returns the written path, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn generate_diagnostic_bundle(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let bundle = DiagnosticBundle::collect(&state).await;
+
+    let file_name = format!(
+        "gptbar-diagnostics-{}.zip",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    let path = app
+        .dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter("ZIP", &["zip"])
+        .blocking_save_file();
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+
+    bundle.save(&path)?;
+    telemetry::record_feature("diagnostic_bundle");
+    Ok(Some(path.display().to_string()))
 }
 
 // ============================================================================
@@ -98,16 +344,48 @@ pub async fn get_agent_status(
 // ============================================================================
 
 /// Gets the current configuration
+///
+/// API keys are stripped when the OS re-authentication gate is enabled, or
+/// in viewer mode; use `reveal_provider_api_key` to read one.
 #[tauri::command]
 pub fn get_config() -> Result<AppConfig, String> {
-    Ok(AppConfig::load())
+    let config = AppConfig::load();
+    if config.require_os_auth || config.viewer_mode {
+        Ok(config.redacted())
+    } else {
+        Ok(config)
+    }
+}
+
+/// Whether the app is showing demo usage instead of real providers
+#[tauri::command]
+pub fn is_demo_mode() -> bool {
+    AppConfig::load().is_demo()
 }
 
 /// Saves the configuration
 #[tauri::command]
-pub fn save_config(config: AppConfig) -> Result<(), String> {
+pub fn save_config(mut config: AppConfig) -> Result<(), String> {
+    let stored = AppConfig::load();
+    if stored.require_os_auth || stored.viewer_mode {
+        // The UI only ever saw a redacted config; keep the stored keys
+        config.merge_secrets_from(&stored);
+        // Disabling the gate goes through set_require_os_auth
+        config.require_os_auth = stored.require_os_auth;
+    }
+    // Only the config file turns viewer mode off
+    config.viewer_mode = stored.viewer_mode;
+    // Freeze commands and hooks run programs, so they only come from the
+    // config file
+    config.usage_freeze = stored.usage_freeze;
+    config.hooks = stored.hooks;
+    // Only touch the entry when it needs to change; inside Flatpak every
+    // request can ask the user again
+    let autostart_changed = AppConfig::is_autostart_enabled() != Ok(config.start_on_login);
     config.save()?;
-    config.set_autostart()?;
+    if autostart_changed {
+        config.set_autostart()?;
+    }
     Ok(())
 }
 
@@ -128,98 +406,816 @@ pub fn set_start_on_login(enabled: bool) -> Result<(), String> {
     config.set_autostart()
 }
 
+/// Sets whether the popup opens when the app starts
+///
+/// Start-on-login entries pass `--hidden`, which keeps it closed regardless.
+#[tauri::command]
+pub fn set_show_window_on_launch(enabled: bool) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.show_window_on_launch = enabled;
+    config.save()
+}
+
 /// Checks if autostart is currently enabled
 #[tauri::command]
-pub fn is_autostart_enabled() -> bool {
+pub fn is_autostart_enabled() -> Result<bool, String> {
     AppConfig::is_autostart_enabled()
 }
 
+/// Sets whether the latest release is looked up on GitHub
+#[tauri::command]
+pub fn set_check_for_updates(enabled: bool) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.check_for_updates = enabled;
+    config.save()
+}
+
+/// Gets the running version and build date, and the latest release with
+/// its changelog when update checks are on
+#[tauri::command]
+pub async fn get_app_version_info() -> AppVersionInfo {
+    AppVersionInfo::current(AppConfig::load().check_for_updates).await
+}
+
+/// Turns anonymous telemetry on or off
+///
+/// Turning it off stops the telemetry agent and drops unsent counters.
+#[tauri::command]
+pub async fn set_telemetry_enabled(
+    state: tauri::State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.telemetry_enabled = enabled;
+    config.save()?;
+    telemetry::set_enabled(enabled);
+
+    if !enabled {
+        state
+            .agent_manager
+            .stop_agent("telemetry")
+            .await
+            .map_err(|e| e.to_string())?;
+        state.agent_manager.unregister("telemetry").await;
+        return Ok(());
+    }
+    if state.agent_manager.get("telemetry").await.is_some() {
+        return Ok(());
+    }
+    // Builds without an endpoint only count, for the preview
+    let Some(agent) = TelemetryAgent::from_build() else {
+        return Ok(());
+    };
+    state.agent_manager.register(Arc::new(agent)).await;
+    state
+        .agent_manager
+        .start_agent("telemetry")
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Gets the batch telemetry would send now, to show what is shared
+#[tauri::command]
+pub fn get_telemetry_preview() -> TelemetryBatch {
+    TelemetryBatch::new(telemetry::peek(), &AppConfig::load())
+}
+
+/// Opens the latest release's page in the default browser
+#[tauri::command]
+pub async fn open_latest_release() -> Result<(), String> {
+    let info = AppVersionInfo::current(true).await;
+    let release = info.latest.ok_or_else(|| info.error.unwrap_or_default())?;
+    opener::open(release.url).map_err(|e| format!("Failed to open browser: {}", e))
+}
+
+// ============================================================================
+// Localization Commands
+// ============================================================================
+
+/// Gets the active UI language
+#[tauri::command]
+pub fn get_language() -> Language {
+    i18n::language()
+}
+
+/// Sets and persists the UI language
+#[tauri::command]
+pub fn set_language(language: Language) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.language = Some(language);
+    config.save()?;
+    i18n::set_language(language);
+    Ok(())
+}
+
+/// Sets how reset times are shown
+#[tauri::command]
+pub fn set_reset_style(style: ResetStyle) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.reset_style = style;
+    config.save()
+}
+
+/// Formats a reset time in the active language and local timezone
+///
+/// Uses the configured style unless `style` is given.
+#[tauri::command]
+pub fn format_reset(resets_at: chrono::DateTime<chrono::Utc>, style: Option<ResetStyle>) -> String {
+    format::format_reset(
+        resets_at,
+        style.unwrap_or_else(|| AppConfig::load().reset_style),
+    )
+}
+
+/// Gets the currency costs are shown in, with its rate against USD
+#[tauri::command]
+pub fn get_currency() -> Currency {
+    format::currency()
+}
+
+// ============================================================================
+// Tray Commands
+// ============================================================================
+
+/// Sets what the menu-bar title shows next to the tray icon (macOS only)
+#[tauri::command]
+pub fn set_tray_title(
+    app: tauri::AppHandle,
+    tray: tauri::State<'_, Arc<TrayState>>,
+    source: TrayTitleSource,
+) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.tray_title = source;
+    config.save()?;
+    tray.refresh(&app);
+    Ok(())
+}
+
+/// Sets how the tray icon is drawn
+#[tauri::command]
+pub fn set_tray_icon_style(
+    app: tauri::AppHandle,
+    tray: tauri::State<'_, Arc<TrayState>>,
+    style: TrayIconStyle,
+) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.tray_icon_style = style;
+    config.save()?;
+    tray.refresh(&app);
+    Ok(())
+}
+
+// ============================================================================
+// Window Commands
+// ============================================================================
+
+/// Hides the popup, e.g. when Escape is pressed
+#[tauri::command]
+pub fn hide_window(window: tauri::WebviewWindow) -> Result<(), String> {
+    window.hide().map_err(|e| e.to_string())
+}
+
+/// Opens the dashboard window with the full usage history
+#[tauri::command]
+pub fn open_dashboard(app: tauri::AppHandle) -> Result<(), String> {
+    telemetry::record_feature("dashboard");
+    window::open_dashboard(&app).map_err(|e| e.to_string())
+}
+
+/// Pins the popup open so it stays visible when it loses focus
+#[tauri::command]
+pub fn set_window_pinned(
+    window: tauri::WebviewWindow,
+    popup: tauri::State<'_, Arc<PopupState>>,
+    pinned: bool,
+) -> Result<(), String> {
+    popup.set_pinned(pinned);
+    if pinned {
+        // Remember the current spot even if the popup is never moved
+        if let Ok(position) = window.outer_position() {
+            popup.moved(position.x, position.y);
+        }
+    }
+    popup.save()
+}
+
+// ============================================================================
+// Security Commands
+// ============================================================================
+
+/// Returns a provider's stored API key, asking for OS re-authentication first
+/// when the gate is enabled
+#[tauri::command]
+pub async fn reveal_provider_api_key(provider_id: String) -> Result<Option<String>, String> {
+    let config = AppConfig::load();
+    config.ensure_not_viewer().map_err(|e| e.to_string())?;
+    let enabled = config.require_os_auth;
+
+    // Keys live in the secure store, with the config file as a fallback
+    tokio::task::spawn_blocking(move || {
+        OsAuth::require(enabled, "Reveal API key").map_err(|e| e.to_string())?;
+        Ok(settings_api_key(&provider_id))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Enables or disables the OS re-authentication gate
+///
+/// Turning the gate off requires passing it once more.
+#[tauri::command]
+pub async fn set_require_os_auth(enabled: bool) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config.ensure_not_viewer().map_err(|e| e.to_string())?;
+
+    if config.require_os_auth && !enabled {
+        tokio::task::spawn_blocking(|| OsAuth::verify("Disable secret protection"))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+    }
+
+    config.require_os_auth = enabled;
+    config.save()
+}
+
+// ============================================================================
+// Integration Commands
+// ============================================================================
+
+/// Stores the MQTT broker credentials in the secure store
+///
+/// An empty username removes the stored credentials. Takes effect the next
+/// time the app starts, like the rest of the MQTT settings.
+#[tauri::command]
+pub fn set_mqtt_credentials(username: String, password: String) -> Result<(), String> {
+    AppConfig::load()
+        .ensure_not_viewer()
+        .map_err(|e| e.to_string())?;
+    let store = SecureStore::new();
+
+    if username.is_empty() {
+        store
+            .delete_token(MQTT_USERNAME_KEY)
+            .map_err(|e| e.to_string())?;
+        store
+            .delete_token(MQTT_PASSWORD_KEY)
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    store
+        .set_token(MQTT_USERNAME_KEY, &username)
+        .map_err(|e| e.to_string())?;
+    store
+        .set_token(MQTT_PASSWORD_KEY, &password)
+        .map_err(|e| e.to_string())
+}
+
+/// Returns the REST API bearer token, asking for OS re-authentication first
+/// when the gate is enabled
+///
+/// A token is generated on first use.
+#[tauri::command]
+pub async fn reveal_api_token() -> Result<String, String> {
+    let config = AppConfig::load();
+    config.ensure_not_viewer().map_err(|e| e.to_string())?;
+    let enabled = config.require_os_auth;
+
+    tokio::task::spawn_blocking(move || OsAuth::require(enabled, "Reveal API token"))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    api_token(&SecureStore::new())
+}
+
+/// Stores the team sync token in the secure store
+///
+/// An empty token removes it. Takes effect the next time the app starts,
+/// like the rest of the team sync settings.
+#[tauri::command]
+pub fn set_team_token(token: String) -> Result<(), String> {
+    AppConfig::load()
+        .ensure_not_viewer()
+        .map_err(|e| e.to_string())?;
+    let store = SecureStore::new();
+    if token.is_empty() {
+        store
+            .delete_token(TEAM_TOKEN_KEY)
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    store
+        .set_token(TEAM_TOKEN_KEY, &token)
+        .map_err(|e| e.to_string())
+}
+
+/// Fetches every team member's usage, combined per window
+///
+/// Returns `None` when team sync is off.
+#[tauri::command]
+pub async fn get_team_usage() -> Result<Option<TeamView>, String> {
+    let settings = AppConfig::load().team_sync;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let members = TeamClient::from_settings(&settings, &SecureStore::new())?
+        .fetch()
+        .await?;
+    Ok(Some(TeamView::new(members, chrono::Utc::now())))
+}
+
+/// Gets the OS accessibility preferences the UI should follow
+#[tauri::command]
+pub async fn get_ui_preferences() -> Result<UiPreferences, String> {
+    // Reading them runs helper programs on some platforms
+    tokio::task::spawn_blocking(UiPreferences::detect)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Reports what each subsystem holds in memory, for debugging growth in
+/// long-running sessions
+#[tauri::command]
+pub async fn get_memory_report(
+    state: tauri::State<'_, Arc<AppState>>,
+    tray: tauri::State<'_, Arc<TrayState>>,
+) -> Result<MemoryReport, String> {
+    let report = state.memory_report().await;
+    Ok(report.with("tray_icons", tray.icon_memory()))
+}
+
+/// Logs GPTBar at `level` (`error` to `trace`) until the app quits, keeping
+/// other crates at info; returns the filter now in effect
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<String, String> {
+    logging::set_level(&level)
+}
+
+/// Replaces the log filter until the app quits, in `RUST_LOG` syntax; an
+/// empty filter restores the default
+#[tauri::command]
+pub fn set_log_filter(filter: String) -> Result<String, String> {
+    logging::set_filter(&filter)
+}
+
+/// Gets the log filter in effect
+#[tauri::command]
+pub fn get_log_filter() -> Option<String> {
+    logging::current_filter()
+}
+
+/// Sets the popup's background effect and opacity, applying them right away
+#[tauri::command]
+pub fn set_appearance(app: tauri::AppHandle, appearance: AppearanceSettings) -> Result<(), String> {
+    use tauri::Manager;
+
+    let appearance = appearance.clamped();
+    if let Some(window) = app.get_webview_window("main") {
+        window::apply_appearance(&window, &appearance).map_err(|e| e.to_string())?;
+    }
+    let mut config = AppConfig::load();
+    config.appearance = appearance;
+    config.save()
+}
+
 // ============================================================================
 // Generic Provider Commands
 // ============================================================================
 
 /// Fetches usage data from a specific provider
+///
+/// Errors carry that provider's guidance on resolving them and a link to
+/// the page that helps.
 #[tauri::command]
 pub async fn fetch_provider_usage(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     provider_id: String,
-) -> Result<UsageSnapshot, String> {
-    let state = state.read().await;
-
-    match provider_id.as_str() {
-        "claude" => state.claude.fetch().await.map_err(|e| e.to_string()),
-        "openai" => state.openai.fetch().await.map_err(|e| e.to_string()),
-        "gemini" => state.gemini.fetch().await.map_err(|e| e.to_string()),
-        "codex" => state.codex.fetch().await.map_err(|e| e.to_string()),
-        _ => Err(format!("Unknown provider: {}", provider_id)),
+) -> Result<UsageSnapshot, ProviderErrorInfo> {
+    let provider = find_provider(&state, &provider_id)?;
+
+    // Don't ask again before a provider that rate limited us said to
+    if let Some(until) = state.refresh.throttled_until(&provider_id) {
+        let error = ProviderError::RateLimited {
+            retry_after: (until - chrono::Utc::now()).to_std().ok(),
+        };
+        return Err(error.to_info_for(provider.as_ref()));
+    }
+
+    let result = provider.fetch().await;
+    if let Some(retry_after) = result.as_ref().err().and_then(|e| e.retry_after()) {
+        state.refresh.throttle(&provider_id, retry_after);
     }
+    let snapshot = result.map_err(|e| e.to_info_for(provider.as_ref()))?;
+    Ok(AppConfig::load().for_display(&provider_id, snapshot))
 }
 
 /// Checks if a provider's authentication is available
+///
+/// Answers from the provider's auth state when it was checked or fetched
+/// recently.
 #[tauri::command]
 pub async fn is_provider_available(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     provider_id: String,
-) -> Result<bool, String> {
-    let state = state.read().await;
-
-    match provider_id.as_str() {
-        "claude" => Ok(state.claude.is_available().await),
-        "openai" => Ok(state.openai.is_available().await),
-        "gemini" => Ok(state.gemini.is_available().await),
-        "codex" => Ok(state.codex.is_available().await),
-        _ => Err(format!("Unknown provider: {}", provider_id)),
+) -> Result<bool, ProviderErrorInfo> {
+    let provider = find_provider(&state, &provider_id)?;
+    Ok(state.auth_states.check(provider.as_ref()).await.is_usable())
+}
+
+/// Gets what is known about each provider's credentials
+///
+/// Found, accepted by the last fetch, refused, missing or logged out, for
+/// every compiled-in provider.
+#[tauri::command]
+pub async fn get_provider_states(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, AuthState>, String> {
+    let mut states = HashMap::new();
+    for provider in state.registry.all() {
+        states.insert(
+            provider.id().to_string(),
+            state.auth_states.check(provider.as_ref()).await,
+        );
     }
+    Ok(states)
+}
+
+/// Lists what needs the user's attention: refused credentials, critical
+/// usage and failing fetches of the active providers
+#[tauri::command]
+pub async fn get_attention_items(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<AttentionItem>, String> {
+    Ok(state.attention_items().await)
+}
+
+/// Opens one of a provider's web pages in the default browser
+#[tauri::command]
+pub async fn open_provider_page(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+    page: ProviderPage,
+) -> Result<(), ProviderErrorInfo> {
+    let provider = find_provider(&state, &provider_id)?;
+    provider
+        .open_page(page)
+        .map_err(|e| e.to_info_for(provider.as_ref()))
 }
 
 /// Initiates login for a provider
 #[tauri::command]
 pub async fn login_provider(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     provider_id: String,
-) -> Result<bool, String> {
-    let state = state.read().await;
-
-    match provider_id.as_str() {
-        "claude" => state.claude.login().await.map_err(|e| e.to_string()),
-        "openai" => state.openai.login().await.map_err(|e| e.to_string()),
-        "gemini" => state.gemini.login().await.map_err(|e| e.to_string()),
-        "codex" => state.codex.login().await.map_err(|e| e.to_string()),
-        _ => Err(format!("Unknown provider: {}", provider_id)),
-    }
+) -> Result<bool, ProviderErrorInfo> {
+    AppConfig::load().ensure_not_viewer()?;
+    // Re-read the keychain, credentials may have changed outside GPTBar
+    keychain::invalidate_all();
+
+    let provider = find_provider(&state, &provider_id)?;
+    let result = provider.login().await;
+    credentials_changed(&state, &provider_id).await;
+    result.map_err(|e| e.to_info_for(provider.as_ref()))
 }
 
 /// Logs out from a provider
 #[tauri::command]
 pub async fn logout_provider(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
     provider_id: String,
-) -> Result<(), String> {
-    let state = state.read().await;
-
-    match provider_id.as_str() {
-        "claude" => state.claude.logout().await.map_err(|e| e.to_string()),
-        "openai" => state.openai.logout().await.map_err(|e| e.to_string()),
-        "gemini" => state.gemini.logout().await.map_err(|e| e.to_string()),
-        "codex" => state.codex.logout().await.map_err(|e| e.to_string()),
-        _ => Err(format!("Unknown provider: {}", provider_id)),
+) -> Result<(), ProviderErrorInfo> {
+    AppConfig::load().ensure_not_viewer()?;
+    // Re-read the keychain, credentials may have changed outside GPTBar
+    keychain::invalidate_all();
+
+    let provider = find_provider(&state, &provider_id)?;
+    let result = provider.logout().await;
+    logged_out(&state, &provider_id, &result).await;
+    result.map_err(|e| e.to_info_for(provider.as_ref()))
+}
+
+/// Checks a provider's credentials again after they changed in GPTBar
+///
+/// The auth state tracker publishes
+/// [`AuthStateChanged`](crate::events::AppEvent::AuthStateChanged) if the
+/// state changed.
+async fn credentials_changed(state: &AppState, provider_id: &str) {
+    if let Some(provider) = state.registry.get(provider_id) {
+        state
+            .auth_states
+            .credentials_changed(provider.as_ref())
+            .await;
+    }
+}
+
+/// Marks a provider as logged out, or checks it again if logging out failed
+async fn logged_out(state: &AppState, provider_id: &str, result: &Result<(), ProviderError>) {
+    if result.is_ok() {
+        state
+            .auth_states
+            .observe(provider_id, &AuthObservation::LoggedOut);
+    } else {
+        credentials_changed(state, provider_id).await;
+    }
+}
+
+/// Validates and applies a provider's settings, then saves them
+///
+/// `values` maps keys from the provider's settings schema to their new
+/// values; keys left out are unchanged and empty values clear the field.
+#[tauri::command]
+pub async fn set_provider_settings(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+    values: HashMap<String, String>,
+) -> Result<(), ProviderErrorInfo> {
+    let provider = find_provider(&state, &provider_id)?;
+    let mut config = AppConfig::load();
+    // Other settings can still be changed in viewer mode, keys can't
+    let sets_secret = provider
+        .settings_schema()
+        .iter()
+        .any(|field| field.kind == SettingKind::Secret && values.contains_key(&field.key));
+    if sets_secret {
+        config.ensure_not_viewer()?;
+    }
+    provider
+        .apply_settings(&values)
+        .await
+        .map_err(|e| e.to_info_for(provider.as_ref()))?;
+
+    for field in provider.settings_schema() {
+        let Some(value) = values.get(&field.key).map(|v| v.trim()) else {
+            continue;
+        };
+
+        // Secrets stay out of `values`, which the UI can read back
+        if field.kind == SettingKind::Secret {
+            config
+                .save_api_key(&provider_id, value)
+                .map_err(ProviderError::Storage)?;
+            continue;
+        }
+
+        let settings = config
+            .provider_settings
+            .entry(provider_id.clone())
+            .or_insert_with(ProviderSettings::default);
+        if value.is_empty() {
+            settings.values.remove(&field.key);
+        } else {
+            settings.values.insert(field.key, value.to_string());
+        }
     }
+
+    config.save().map_err(|e| ProviderError::Storage(e).into())
 }
 
-/// Gets metadata for all available providers
+/// Gets the saved non-secret settings of a provider
+#[tauri::command]
+pub fn get_provider_settings(provider_id: String) -> Result<HashMap<String, String>, String> {
+    let config = AppConfig::load();
+    Ok(config
+        .provider_settings
+        .get(&provider_id)
+        .map(|s| s.values.clone())
+        .unwrap_or_default())
+}
+
+/// Lists the OpenAI organizations the configured API key can access
+#[cfg(feature = "openai")]
+#[tauri::command]
+pub async fn list_openai_organizations(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<OrganizationInfo>, ProviderErrorInfo> {
+    state
+        .openai
+        .organizations()
+        .await
+        .map_err(|e| e.to_info_for(&*state.openai))
+}
+
+/// Reports where each provider looks for credentials and what is there
+///
+/// Nothing found is used; secrets are masked, or left out in viewer mode.
+/// Explains why a provider is unavailable.
+#[tauri::command]
+pub async fn discover_credentials(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<CredentialReport, String> {
+    let providers = state.registry.real_providers();
+
+    // Keychain and file access can block
+    let report = tokio::task::spawn_blocking(move || CredentialReport::discover(&providers))
+        .await
+        .map_err(|e| e.to_string())?;
+    if AppConfig::load().viewer_mode {
+        return Ok(report.without_previews());
+    }
+    Ok(report)
+}
+
+/// Gets metadata for all available providers, in display order
 #[tauri::command]
 pub async fn get_providers(
-    state: tauri::State<'_, Arc<RwLock<AppState>>>,
+    state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<Vec<ProviderMetadata>, String> {
-    let state = state.read().await;
-    Ok(state.registry.metadata())
+    let config = AppConfig::load();
+    let mut metadata = state.registry.metadata();
+    config.order_metadata(&mut metadata);
+    let snapshots = state.refresh.get_all_snapshots().await;
+    config.sort_providers(&mut metadata, |provider| provider.id.as_str(), &snapshots);
+    Ok(metadata)
+}
+
+/// Gets the aggregate usage across enabled providers from the latest refresh
+#[tauri::command]
+pub async fn get_aggregate_usage(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Option<UsageSnapshot>, String> {
+    let config = AppConfig::load();
+    let snapshots = state.refresh.get_all_snapshots().await;
+    Ok(config
+        .usage_aggregate()
+        .snapshot(&snapshots, &config.active_providers()))
+}
+
+/// Gets recorded snapshots from the last `hours` hours, oldest first
+///
+/// Omit `provider_id` to include every provider.
+#[tauri::command]
+pub async fn get_history(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: Option<String>,
+    hours: i64,
+) -> Result<Vec<HistoryEntry>, String> {
+    state
+        .history
+        .recent(provider_id.as_deref(), hours)
+        .map_err(|e| e.to_string())
+}
+
+/// Gets today's and this week's usage aggregates for a provider, with time
+/// counted above the warning threshold
+#[tauri::command]
+pub async fn get_usage_stats(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+) -> Result<UsageStats, String> {
+    let thresholds = state.notification.thresholds();
+    state
+        .history
+        .usage_stats(&provider_id, thresholds.warning_percent)
+        .map_err(|e| e.to_string())
+}
+
+/// Gets per-provider fetch metrics of the refresh agent
+#[tauri::command]
+pub async fn get_refresh_metrics(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, FetchMetrics>, String> {
+    Ok(state.refresh.metrics().await)
+}
+
+/// Data for the dashboard window
+#[derive(Debug, Serialize)]
+pub struct DashboardData {
+    /// Metadata of all providers, in display order
+    pub providers: Vec<ProviderMetadata>,
+    /// Latest snapshot of each provider, with cost and per-model detail
+    pub snapshots: HashMap<String, UsageSnapshot>,
+    /// Recorded snapshots in the selected range, oldest first
+    pub history: Vec<HistoryEntry>,
+}
+
+/// Gets everything the dashboard charts for `range`
+#[tauri::command]
+pub async fn get_dashboard(
+    state: tauri::State<'_, Arc<AppState>>,
+    range: HistoryRange,
+) -> Result<DashboardData, String> {
+    let config = AppConfig::load();
+    let mut providers = state.registry.metadata();
+    config.order_metadata(&mut providers);
+    let snapshots = state
+        .refresh
+        .get_all_snapshots()
+        .await
+        .into_iter()
+        .map(|(id, snapshot)| {
+            let snapshot = config.for_display(&id, snapshot);
+            (id, snapshot)
+        })
+        .collect();
+
+    let now = chrono::Utc::now();
+    let history = state
+        .history
+        .query(None, range.since(now), now)
+        .map_err(|e| e.to_string())?;
+
+    Ok(DashboardData {
+        providers,
+        snapshots,
+        history,
+    })
 }
 
-/// Gets list of enabled provider IDs in display order
+/// Gets notifications sent in `range`, newest first
+#[tauri::command]
+pub async fn get_notification_history(
+    state: tauri::State<'_, Arc<AppState>>,
+    range: HistoryRange,
+) -> Result<Vec<NotificationRecord>, String> {
+    let now = chrono::Utc::now();
+    state
+        .history
+        .notifications(range.since(now), now)
+        .map_err(|e| e.to_string())
+}
+
+/// Gets usage logged by the Claude Code and Codex CLIs in `range`, summed
+/// per project, most expensive first
+#[cfg_attr(
+    not(any(feature = "claude", feature = "codex")),
+    allow(unused_variables, unused_mut)
+)]
+#[tauri::command]
+pub async fn get_usage_by_project(range: HistoryRange) -> Result<Vec<ProjectUsage>, String> {
+    let since = range
+        .since(chrono::Utc::now())
+        .with_timezone(&chrono::Local)
+        .date_naive();
+
+    tokio::task::spawn_blocking(move || {
+        let mut projects = Vec::new();
+        #[cfg(feature = "claude")]
+        if let Some(collector) = ClaudeLogCollector::new() {
+            let usage = collector.collect(since).map_err(|e| e.to_string())?;
+            projects.extend(usage.by_project("claude"));
+        }
+        #[cfg(feature = "codex")]
+        if let Some(collector) = CodexLogCollector::new() {
+            let usage = collector.collect(since).map_err(|e| e.to_string())?;
+            projects.extend(usage.by_project("codex"));
+        }
+        ProjectUsage::sort_by_cost(&mut projects);
+        Ok(projects)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Exports recorded history to a file chosen in a save dialog
+///
+/// Omit `provider_id` to include every provider. Returns the written path,
+/// or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_history(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: Option<String>,
+    range: HistoryRange,
+    format: ExportFormat,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let now = chrono::Utc::now();
+    let entries = state
+        .history
+        .query(provider_id.as_deref(), range.since(now), now)
+        .map_err(|e| e.to_string())?;
+    let content = format.render(&entries).map_err(|e| e.to_string())?;
+
+    let file_name = format!(
+        "gptbar-{}-{}.{}",
+        provider_id.as_deref().unwrap_or("history"),
+        now.format("%Y%m%d"),
+        format.extension()
+    );
+    let path = app
+        .dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        .blocking_save_file();
+
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write export: {}", e))?;
+    telemetry::record_feature("history_export");
+    Ok(Some(path.display().to_string()))
+}
+
+/// Gets list of enabled provider IDs in display order, pinned ones first
 #[tauri::command]
 pub fn get_enabled_providers() -> Result<Vec<String>, String> {
     let config = AppConfig::load();
-    Ok(config.enabled_providers)
+    Ok(config.active_providers())
 }
 
 /// Enables or disables a provider
@@ -246,47 +1242,212 @@ pub fn set_provider_enabled(provider_id: String, enabled: bool) -> Result<(), St
 }
 
 /// Sets the order of enabled providers
+///
+/// Pinned providers still come first; the refresh agent follows the new order.
 #[tauri::command]
-pub fn set_provider_order(order: Vec<String>) -> Result<(), String> {
+pub async fn set_provider_order(
+    state: tauri::State<'_, Arc<AppState>>,
+    order: Vec<String>,
+) -> Result<(), String> {
     let mut config = AppConfig::load();
     config.enabled_providers = order;
-    config.save()
+    config.save()?;
+
+    state.refresh.set_order(&config.ordered_providers()).await;
+    Ok(())
 }
 
-/// Sets the API key for a provider
+/// Pins a provider to the top of the list and of the refresh order
 #[tauri::command]
-pub fn set_provider_api_key(provider_id: String, api_key: String) -> Result<(), String> {
+pub async fn set_provider_pinned(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+    pinned: bool,
+) -> Result<(), String> {
     let mut config = AppConfig::load();
-
     config
         .provider_settings
-        .entry(provider_id.clone())
+        .entry(provider_id)
         .or_insert_with(ProviderSettings::default)
-        .api_key = if api_key.is_empty() {
-        None
-    } else {
-        Some(api_key)
-    };
+        .pinned = pinned;
+    config.save()?;
+
+    state.refresh.set_order(&config.ordered_providers()).await;
+    Ok(())
+}
 
+/// Gets the provider profiles from the config file
+#[tauri::command]
+pub fn get_profiles() -> Vec<Profile> {
+    AppConfig::load().profiles
+}
+
+/// Switches to a profile, or back to all enabled providers with `None`
+///
+/// Only the profile's providers are fetched and shown from then on.
+#[tauri::command]
+pub async fn set_active_profile(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+    tray: tauri::State<'_, Arc<TrayState>>,
+    name: Option<String>,
+) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    if let Some(name) = &name {
+        if config.profile(name).is_none() {
+            return Err(format!("Unknown profile: {}", name));
+        }
+    }
+    config.active_profile = name;
     config.save()?;
 
-    // Also store in system keychain for security
-    if let Ok(entry) = keyring::Entry::new(&provider_id, "api_key") {
-        if config
-            .provider_settings
-            .get(&provider_id)
-            .and_then(|s| s.api_key.as_ref())
-            .is_some()
+    if !config.is_demo() {
+        state
+            .refresh
+            .set_active_providers(config.profile_providers());
+    }
+    tray.refresh(&app);
+    Ok(())
+}
+
+/// Sets a provider's warning/critical threshold overrides
+///
+/// Pass empty thresholds to go back to the global levels.
+#[tauri::command]
+pub async fn set_provider_thresholds(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+    thresholds: ProviderThresholds,
+) -> Result<(), String> {
+    let levels = thresholds.all.iter().chain(thresholds.windows.values());
+    for level in levels {
+        if !(0.0..=100.0).contains(&level.warning_percent)
+            || level.critical_percent < level.warning_percent
         {
-            let key = config.provider_settings[&provider_id]
-                .api_key
-                .as_ref()
-                .unwrap();
-            let _ = entry.set_password(key);
-        } else {
-            let _ = entry.delete_credential();
+            return Err(format!(
+                "Invalid thresholds: warning {}%, critical {}%",
+                level.warning_percent, level.critical_percent
+            ));
         }
     }
 
+    let mut config = AppConfig::load();
+    config
+        .provider_settings
+        .entry(provider_id)
+        .or_insert_with(ProviderSettings::default)
+        .thresholds = thresholds;
+    config.save()?;
+
+    state
+        .notification
+        .set_overrides(config.threshold_overrides())
+        .await;
+    Ok(())
+}
+
+/// Gets the windows of a provider that trigger notifications
+#[tauri::command]
+pub fn get_notified_windows(provider_id: String) -> Vec<WindowSlot> {
+    let muted = AppConfig::load()
+        .provider_settings
+        .get(&provider_id)
+        .map(|settings| settings.muted_windows.clone())
+        .unwrap_or_default();
+    WindowSlot::ALL
+        .into_iter()
+        .filter(|slot| !muted.contains(slot))
+        .collect()
+}
+
+/// Sets whether a provider's window triggers notifications
+#[tauri::command]
+pub async fn set_window_notifications(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+    window: WindowSlot,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    let muted = &mut config
+        .provider_settings
+        .entry(provider_id)
+        .or_insert_with(ProviderSettings::default)
+        .muted_windows;
+    muted.retain(|slot| *slot != window);
+    if !enabled {
+        muted.push(window);
+    }
+    config.save()?;
+
+    state
+        .notification
+        .set_muted_windows(config.muted_windows())
+        .await;
+    Ok(())
+}
+
+/// Gets the window that stands for a provider in the tray and aggregate
+///
+/// `None` means its highest window.
+#[tauri::command]
+pub fn get_headline_window(provider_id: String) -> Option<WindowSlot> {
+    AppConfig::load()
+        .provider_settings
+        .get(&provider_id)
+        .and_then(|settings| settings.headline_window)
+}
+
+/// Sets the window that stands for a provider in the tray and aggregate
+///
+/// Pass `None` to go back to its highest window.
+#[tauri::command]
+pub fn set_headline_window(
+    app: tauri::AppHandle,
+    tray: tauri::State<'_, Arc<TrayState>>,
+    provider_id: String,
+    window: Option<WindowSlot>,
+) -> Result<(), String> {
+    let mut config = AppConfig::load();
+    config
+        .provider_settings
+        .entry(provider_id)
+        .or_insert_with(ProviderSettings::default)
+        .headline_window = window;
+    config.save()?;
+    tray.refresh(&app);
+    Ok(())
+}
+
+/// Sets the API key for a provider
+#[tauri::command]
+pub async fn set_provider_api_key(
+    state: tauri::State<'_, Arc<AppState>>,
+    provider_id: String,
+    api_key: String,
+) -> Result<(), String> {
+    AppConfig::load()
+        .ensure_not_viewer()
+        .map_err(|e| e.to_string())?;
+    // Entering a key explicitly re-enables a provider that was logged out
+    if !api_key.is_empty() {
+        match provider_id.as_str() {
+            #[cfg(feature = "openai")]
+            "openai" => state.openai.set_api_key(&api_key).await,
+            #[cfg(feature = "gemini")]
+            "gemini" => state.gemini.set_api_key(&api_key).await,
+            #[cfg(feature = "codex")]
+            "codex" => state.codex.set_api_key(&api_key).await,
+            #[cfg(feature = "together")]
+            "together" => state.together.set_api_key(&api_key).await,
+            #[cfg(feature = "elevenlabs")]
+            "elevenlabs" => state.elevenlabs.set_api_key(&api_key).await,
+            _ => {}
+        }
+    }
+
+    let mut config = AppConfig::load();
+    config.save_api_key(&provider_id, &api_key)?;
+    credentials_changed(&state, &provider_id).await;
     Ok(())
 }
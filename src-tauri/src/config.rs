@@ -4,9 +4,28 @@
 //! Supports Windows, macOS, and Linux.
 
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+
+use crate::agents::NotificationSettings;
+use crate::auth::{BrowserType, SecureStore};
+use crate::autostart::AutoLaunch;
+use crate::budget::BudgetPeriod;
+use crate::events::{self, AppEvent};
+use crate::format::{Currency, ResetStyle};
+use crate::freeze::FreezeSettings;
+use crate::hooks::HooksSettings;
+use crate::i18n::Language;
+use crate::logging::LogFormat;
+use crate::providers::{
+    AggregateMode, ProviderError, ProviderMetadata, ProviderThresholds, Severity, Thresholds,
+    UsageAggregate, UsageSnapshot, WindowSlot, DEMO_ID,
+};
+use crate::team::TeamSyncSettings;
+use crate::tray::{TrayIconStyle, TrayTitleSource};
 
 /// Settings for individual providers
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -16,6 +35,210 @@ pub struct ProviderSettings {
     /// API key for providers that need it (OpenAI, Gemini)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    /// Non-secret values from the provider's settings schema
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub values: HashMap<String, String>,
+    /// Keep this provider ahead of the others, whatever the saved order
+    #[serde(default)]
+    pub pinned: bool,
+    /// Warning/critical levels overriding the global 80/95%
+    #[serde(default, skip_serializing_if = "ProviderThresholds::is_empty")]
+    pub thresholds: ProviderThresholds,
+    /// Windows that never trigger notifications
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub muted_windows: Vec<WindowSlot>,
+    /// Period spend is summed over; calendar months if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_period: Option<BudgetPeriod>,
+    /// Window whose usage stands for the provider in the tray and aggregate;
+    /// its highest window if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headline_window: Option<WindowSlot>,
+    /// Logged out; keys found outside GPTBar are ignored until one is
+    /// entered again
+    #[serde(default)]
+    pub logged_out: bool,
+}
+
+/// MQTT broker settings for publishing usage updates
+///
+/// Credentials are kept in the `SecureStore`, not here.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MqttSettings {
+    /// Whether usage updates are published
+    pub enabled: bool,
+    /// Broker host name
+    pub host: String,
+    /// Broker port
+    pub port: u16,
+    /// Topics are `<topic_prefix>/<provider_id>`
+    pub topic_prefix: String,
+    /// Connect over TLS
+    pub use_tls: bool,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            topic_prefix: "gptbar".to_string(),
+            use_tls: false,
+        }
+    }
+}
+
+/// Local REST API server settings
+///
+/// The bearer token is kept in the `SecureStore`, not here.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ApiServerSettings {
+    /// Whether the server is started
+    pub enabled: bool,
+    /// Address to listen on; use `0.0.0.0` to allow other machines on the LAN
+    pub bind_address: IpAddr,
+    /// Port to listen on
+    pub port: u16,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 7727,
+        }
+    }
+}
+
+/// Currency costs are shown in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CurrencySettings {
+    /// ISO 4217 code, e.g. "EUR"
+    pub code: String,
+    /// Fixed units of `code` per US dollar; fetched daily from the European
+    /// Central Bank when unset
+    pub rate: Option<f64>,
+}
+
+impl Default for CurrencySettings {
+    fn default() -> Self {
+        Self {
+            code: "USD".to_string(),
+            rate: None,
+        }
+    }
+}
+
+impl CurrencySettings {
+    /// The display currency, unless its rate has to be fetched
+    pub fn fixed(&self) -> Option<Currency> {
+        if self.code.eq_ignore_ascii_case("USD") {
+            return Some(Currency::default());
+        }
+        self.rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| Currency::new(self.code.as_str(), rate))
+    }
+}
+
+/// Popup window behavior
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct WindowSettings {
+    /// Keep the popup open when it loses focus
+    pub pinned: bool,
+    /// Where the pinned popup was last moved to, in physical pixels
+    pub position: Option<WindowPosition>,
+    /// Size the popup was resized to, in physical pixels
+    pub size: Option<WindowSize>,
+}
+
+/// A window position in physical pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WindowPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A window size in physical pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WindowSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Background effect behind the popup
+///
+/// Effects a platform doesn't support leave the background translucent
+/// without one.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowEffect {
+    /// Opaque background
+    #[default]
+    None,
+    /// See-through background without blur
+    Transparent,
+    /// Blurred desktop behind the popup, on Windows 10 and 11
+    Acrylic,
+    /// Background tinted by the wallpaper, on Windows 11
+    Mica,
+    /// Blurred desktop behind the popup, on macOS
+    Vibrancy,
+}
+
+/// How the popup background is drawn
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AppearanceSettings {
+    pub effect: WindowEffect,
+    /// Opacity of the popup background while an effect is on, from 0.2 to 1
+    pub opacity: f64,
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        Self {
+            effect: WindowEffect::None,
+            opacity: 0.8,
+        }
+    }
+}
+
+impl AppearanceSettings {
+    /// Keeps the opacity high enough for the text to stay readable
+    pub fn clamped(self) -> Self {
+        let opacity = if self.opacity.is_finite() {
+            self.opacity.clamp(0.2, 1.0)
+        } else {
+            Self::default().opacity
+        };
+        Self { opacity, ..self }
+    }
+}
+
+/// How providers are ordered in the popup
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderSort {
+    /// Saved order
+    #[default]
+    Configured,
+    /// Most urgent first: critical, then warning, then by usage
+    Urgency,
+}
+
+/// A named group of providers, such as one client's accounts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// Provider IDs fetched and shown while the profile is active
+    pub providers: Vec<String>,
 }
 
 /// Application configuration
@@ -28,15 +251,118 @@ pub struct AppConfig {
     /// List of enabled provider IDs in display order
     #[serde(default = "default_enabled_providers")]
     pub enabled_providers: Vec<String>,
+    /// Named groups of providers to switch between
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+    /// Name of the profile limiting which providers are used; all enabled
+    /// providers when unset
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    /// How providers are ordered in the popup; pinned ones stay first
+    #[serde(default)]
+    pub provider_sort: ProviderSort,
     /// Per-provider settings
     #[serde(default)]
     pub provider_settings: HashMap<String, ProviderSettings>,
+    /// Require OS re-authentication before revealing or exporting secrets
+    #[serde(default)]
+    pub require_os_auth: bool,
+    /// Read-only mode for shared machines: credentials can't be added,
+    /// removed or revealed, and usage comes from credentials already there
+    #[serde(default)]
+    pub viewer_mode: bool,
+    /// Save OAuth tokens found in CLI credential files to the secure store,
+    /// so usage keeps working if the files are removed
+    #[serde(default)]
+    pub store_discovered_tokens: bool,
+    /// Folder holding a browser's profiles when not in the default place:
+    /// the user data dir of a portable or relocated Chrome or Edge, or
+    /// Firefox's `Profiles` folder
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub browser_data_dirs: HashMap<BrowserType, PathBuf>,
+    /// UI language; detected from the system locale when unset
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// Text shown next to the tray icon (macOS menu bar only)
+    #[serde(default)]
+    pub tray_title: TrayTitleSource,
+    /// How the tray icon is drawn
+    #[serde(default)]
+    pub tray_icon_style: TrayIconStyle,
+    /// How provider usages are combined into the aggregate view
+    #[serde(default)]
+    pub aggregate_mode: AggregateMode,
+    /// Per-provider weights for the averaged aggregate (default 1.0)
+    #[serde(default)]
+    pub aggregate_weights: HashMap<String, f64>,
+    /// Serve usage to external tools over a local socket / named pipe
+    #[serde(default = "default_ipc_enabled")]
+    pub ipc_enabled: bool,
+    /// Publish usage updates to an MQTT broker
+    #[serde(default)]
+    pub mqtt: MqttSettings,
+    /// Serve usage over a local REST API
+    #[serde(default)]
+    pub api_server: ApiServerSettings,
+    /// Add token and cost usage from local Claude Code logs to the Claude panel
+    #[serde(default)]
+    pub claude_local_usage: bool,
+    /// Show generated usage from the demo provider instead of real accounts
+    #[serde(default)]
+    pub demo_mode: bool,
+    /// Popup window behavior and pinned geometry
+    #[serde(default)]
+    pub window: WindowSettings,
+    /// Background effect and opacity of the popup
+    #[serde(default)]
+    pub appearance: AppearanceSettings,
+    /// Open the popup when the app starts instead of only showing the tray icon
+    #[serde(default)]
+    pub show_window_on_launch: bool,
+    /// Show reset times as time left or as a local date and time
+    #[serde(default)]
+    pub reset_style: ResetStyle,
+    /// Currency costs are converted to for display
+    #[serde(default)]
+    pub currency: CurrencySettings,
+    /// Ask GitHub for the latest release when the settings are opened
+    #[serde(default)]
+    pub check_for_updates: bool,
+    /// Send anonymous feature and error counts, see `telemetry`
+    #[serde(default)]
+    pub telemetry_enabled: bool,
+    /// Marker file and commands for providers whose usage runs out
+    #[serde(default)]
+    pub usage_freeze: FreezeSettings,
+    /// Commands run on events, off by default
+    #[serde(default)]
+    pub hooks: HooksSettings,
+    /// Share usage with teammates through a self-hosted endpoint
+    #[serde(default)]
+    pub team_sync: TeamSyncSettings,
+    /// Write logs as text or as JSON lines; `GPTBAR_LOG_FORMAT` overrides it
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// Per-agent settings by agent ID, applied with `Agent::configure` at startup
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub agents: HashMap<String, serde_json::Value>,
 }
 
+/// Command-line flag that keeps the popup hidden at startup, passed by the
+/// start-on-login entries
+pub const HIDDEN_FLAG: &str = "--hidden";
+
+/// Name of the config file in the config directory
+const CONFIG_FILE: &str = "config.json";
+
 fn default_enabled_providers() -> Vec<String> {
     vec!["claude".to_string()]
 }
 
+fn default_ipc_enabled() -> bool {
+    true
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         let mut provider_settings = HashMap::new();
@@ -45,6 +371,13 @@ impl Default for AppConfig {
             ProviderSettings {
                 enabled: true,
                 api_key: None,
+                values: HashMap::new(),
+                pinned: false,
+                thresholds: ProviderThresholds::default(),
+                muted_windows: Vec::new(),
+                budget_period: None,
+                headline_window: None,
+                logged_out: false,
             },
         );
 
@@ -52,14 +385,92 @@ impl Default for AppConfig {
             refresh_interval: 5,
             start_on_login: false,
             enabled_providers: default_enabled_providers(),
+            profiles: Vec::new(),
+            active_profile: None,
+            provider_sort: ProviderSort::default(),
             provider_settings,
+            require_os_auth: false,
+            viewer_mode: false,
+            store_discovered_tokens: false,
+            browser_data_dirs: HashMap::new(),
+            language: None,
+            tray_title: TrayTitleSource::default(),
+            tray_icon_style: TrayIconStyle::default(),
+            aggregate_mode: AggregateMode::default(),
+            aggregate_weights: HashMap::new(),
+            ipc_enabled: default_ipc_enabled(),
+            mqtt: MqttSettings::default(),
+            api_server: ApiServerSettings::default(),
+            claude_local_usage: false,
+            demo_mode: false,
+            window: WindowSettings::default(),
+            appearance: AppearanceSettings::default(),
+            show_window_on_launch: false,
+            reset_style: ResetStyle::default(),
+            currency: CurrencySettings::default(),
+            check_for_updates: false,
+            telemetry_enabled: false,
+            usage_freeze: FreezeSettings::default(),
+            hooks: HooksSettings::default(),
+            team_sync: TeamSyncSettings::default(),
+            log_format: LogFormat::default(),
+            agents: HashMap::new(),
         }
     }
 }
 
 impl AppConfig {
+    /// Whether demo mode is on, from the config file or the `--demo` flag
+    pub fn is_demo(&self) -> bool {
+        self.demo_mode || std::env::args().any(|arg| arg == "--demo")
+    }
+
+    /// Fails with [`ProviderError::ViewerMode`] in viewer mode, for commands
+    /// that change or reveal credentials
+    pub fn ensure_not_viewer(&self) -> Result<(), ProviderError> {
+        if self.viewer_mode {
+            Err(ProviderError::ViewerMode)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether to open the popup at startup; `--hidden` or a start on login
+    /// always starts in the tray
+    pub fn shows_window_on_launch(&self) -> bool {
+        self.show_window_on_launch
+            && !std::env::args().any(|arg| arg == HIDDEN_FLAG)
+            && !crate::autostart::launched_at_login()
+    }
+
+    /// Providers to show and aggregate; only the demo provider in demo mode
+    ///
+    /// With a profile active, only the enabled providers in it.
+    pub fn active_providers(&self) -> Vec<String> {
+        if self.is_demo() {
+            return vec![DEMO_ID.to_string()];
+        }
+        let mut providers = self.ordered_providers();
+        if let Some(profile) = self.profile_providers() {
+            providers.retain(|p| profile.contains(p));
+        }
+        providers
+    }
+
+    /// Looks up a profile by name
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Providers of the active profile, or `None` when no known profile is
+    /// active
+    pub fn profile_providers(&self) -> Option<&[String]> {
+        let name = self.active_profile.as_deref()?;
+        self.profile(name).map(|p| p.providers.as_slice())
+    }
+
     /// Gets the config directory path (cross-platform)
-    fn config_dir() -> Option<PathBuf> {
+    pub(crate) fn config_dir() -> Option<PathBuf> {
         #[cfg(target_os = "windows")]
         {
             std::env::var("APPDATA")
@@ -90,7 +501,8 @@ impl AppConfig {
     }
 
     /// Gets the config file path
-    fn config_path() -> Option<PathBuf> {
+    #[cfg_attr(not(feature = "claude"), allow(dead_code))]
+    pub(crate) fn config_path() -> Option<PathBuf> {
         let config_dir = Self::config_dir()?;
 
         // Create directory if it doesn't exist
@@ -98,29 +510,39 @@ impl AppConfig {
             fs::create_dir_all(&config_dir).ok()?;
         }
 
-        Some(config_dir.join("config.json"))
+        Some(config_dir.join(CONFIG_FILE))
     }
 
     /// Loads configuration from disk
     pub fn load() -> Self {
-        if let Some(path) = Self::config_path() {
-            if path.exists() {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(config) = serde_json::from_str(&content) {
-                        return config;
-                    }
-                }
-            }
-        }
-        Self::default()
+        Self::config_dir()
+            .map(|dir| Self::load_in(&dir))
+            .unwrap_or_default()
+    }
+
+    /// Loads the configuration kept in `dir`, or the defaults when there is
+    /// none
+    pub(crate) fn load_in(dir: &Path) -> Self {
+        fs::read_to_string(dir.join(CONFIG_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
     }
 
     /// Saves configuration to disk
     pub fn save(&self) -> Result<(), String> {
-        let path = Self::config_path().ok_or("Could not determine config path")?;
+        let dir = Self::config_dir().ok_or("Could not determine config path")?;
+        self.save_in(&dir)
+    }
+
+    /// Saves the configuration in `dir`, creating it if needed
+    pub(crate) fn save_in(&self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
         let content = serde_json::to_string_pretty(self)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
-        fs::write(&path, content).map_err(|e| format!("Failed to write config: {}", e))?;
+        fs::write(dir.join(CONFIG_FILE), content)
+            .map_err(|e| format!("Failed to write config: {}", e))?;
+        events::publish(AppEvent::ConfigChanged);
         Ok(())
     }
 
@@ -129,6 +551,108 @@ impl AppConfig {
         self.enabled_providers.contains(&provider_id.to_string())
     }
 
+    /// Whether a provider is pinned to the top
+    pub fn is_provider_pinned(&self, provider_id: &str) -> bool {
+        self.provider_settings
+            .get(provider_id)
+            .is_some_and(|s| s.pinned)
+    }
+
+    /// Sort key for a provider: pinned first, then by saved order
+    ///
+    /// Providers missing from `enabled_providers` sort after the others.
+    pub fn provider_rank(&self, provider_id: &str) -> (bool, usize) {
+        let position = self
+            .enabled_providers
+            .iter()
+            .position(|p| p == provider_id)
+            .unwrap_or(usize::MAX);
+        (!self.is_provider_pinned(provider_id), position)
+    }
+
+    /// Enabled providers in display order, pinned ones first
+    pub fn ordered_providers(&self) -> Vec<String> {
+        let mut providers = self.enabled_providers.clone();
+        providers.sort_by_key(|p| self.provider_rank(p));
+        providers
+    }
+
+    /// Marks pinned providers and sorts metadata into display order
+    ///
+    /// Providers outside the saved order are sorted by ID so the result is
+    /// stable.
+    pub fn order_metadata(&self, metadata: &mut [ProviderMetadata]) {
+        for provider in metadata.iter_mut() {
+            provider.pinned = self.is_provider_pinned(&provider.id);
+        }
+        metadata.sort_by(|a, b| {
+            self.provider_rank(&a.id)
+                .cmp(&self.provider_rank(&b.id))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+    }
+
+    /// Reorders providers by urgency when that sort mode is chosen
+    ///
+    /// Pinned providers stay first. The others go from critical to warning
+    /// to normal, then by headline usage, highest first; providers without a
+    /// snapshot go last. Ties keep their current order, so `items` should
+    /// already be in display order.
+    pub fn sort_providers<T>(
+        &self,
+        items: &mut [T],
+        id: impl Fn(&T) -> &str,
+        snapshots: &HashMap<String, UsageSnapshot>,
+    ) {
+        if self.provider_sort != ProviderSort::Urgency {
+            return;
+        }
+        let urgencies = self.urgencies(snapshots);
+        items.sort_by(|a, b| {
+            let (a, b) = (id(a), id(b));
+            let pinned = self.is_provider_pinned(b).cmp(&self.is_provider_pinned(a));
+            pinned.then_with(|| match (urgencies.get(a), urgencies.get(b)) {
+                (Some((a_severity, a_usage)), Some((b_severity, b_usage))) => b_severity
+                    .cmp(a_severity)
+                    .then_with(|| b_usage.total_cmp(a_usage)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+        });
+    }
+
+    /// Most severe window level and headline usage of each provider with a
+    /// snapshot
+    fn urgencies(
+        &self,
+        snapshots: &HashMap<String, UsageSnapshot>,
+    ) -> HashMap<String, (Severity, f64)> {
+        let global = self.global_thresholds();
+        let headlines = self.headline_windows();
+        snapshots
+            .iter()
+            .map(|(id, snapshot)| {
+                let thresholds = self
+                    .provider_settings
+                    .get(id)
+                    .map(|settings| settings.thresholds.clone())
+                    .unwrap_or_default();
+                let severity = snapshot
+                    .windows()
+                    .map(|(slot, window)| {
+                        thresholds
+                            .resolve(slot, global)
+                            .severity(window.used_percent)
+                    })
+                    .max()
+                    .unwrap_or(Severity::Normal);
+                let usage = snapshot.headline_usage(headlines.get(id).copied());
+                (id.clone(), (severity, usage))
+            })
+            .collect()
+    }
+
     /// Get API key for a provider
     pub fn get_provider_api_key(&self, provider_id: &str) -> Option<String> {
         self.provider_settings
@@ -136,205 +660,238 @@ impl AppConfig {
             .and_then(|s| s.api_key.clone())
     }
 
-    // ========================================================================
-    // Windows auto-start (Registry)
-    // ========================================================================
+    /// API keys saved in the settings, by provider ID
+    pub fn saved_api_keys(&self) -> HashMap<String, String> {
+        self.provider_settings
+            .iter()
+            .filter_map(|(id, settings)| Some((id.clone(), settings.api_key.clone()?)))
+            .collect()
+    }
 
-    #[cfg(target_os = "windows")]
-    pub fn set_autostart(&self) -> Result<(), String> {
-        use std::process::Command;
-
-        let exe_path =
-            std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
-
-        if self.start_on_login {
-            let output = Command::new("reg")
-                .args([
-                    "add",
-                    r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run",
-                    "/v",
-                    "GPTBar",
-                    "/t",
-                    "REG_SZ",
-                    "/d",
-                    &exe_path.to_string_lossy(),
-                    "/f",
-                ])
-                .output()
-                .map_err(|e| format!("Failed to run reg command: {}", e))?;
-
-            if !output.status.success() {
-                return Err("Failed to add registry key".to_string());
-            }
+    /// Sets or clears (with an empty key) a provider's API key and saves
+    ///
+    /// The key goes to the [`SecureStore`], which providers read first; the
+    /// config file only keeps it when the store can't take it. Entering a
+    /// key undoes a logout.
+    pub fn save_api_key(&mut self, provider_id: &str, api_key: &str) -> Result<(), String> {
+        let store = SecureStore::new();
+        let entry = SecureStore::api_key_entry(provider_id);
+        let settings = self
+            .provider_settings
+            .entry(provider_id.to_string())
+            .or_default();
+        if api_key.is_empty() {
+            settings.api_key = None;
+            let _ = store.delete_token(&entry);
         } else {
-            let _ = Command::new("reg")
-                .args([
-                    "delete",
-                    r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run",
-                    "/v",
-                    "GPTBar",
-                    "/f",
-                ])
-                .output();
+            settings.api_key = match store.set_token(&entry, api_key) {
+                Ok(()) => None,
+                Err(e) => {
+                    tracing::warn!("Keeping {} API key in the config file: {}", provider_id, e);
+                    Some(api_key.to_string())
+                }
+            };
+            settings.logged_out = false;
         }
 
-        Ok(())
+        self.save()
     }
 
-    #[cfg(target_os = "windows")]
-    pub fn is_autostart_enabled() -> bool {
-        use std::process::Command;
-
-        Command::new("reg")
-            .args([
-                "query",
-                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run",
-                "/v",
-                "GPTBar",
-            ])
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+    /// Clears the API keys in `migrated` from the settings, once they are in
+    /// the [`SecureStore`]
+    ///
+    /// A key changed since is kept. Returns whether any was cleared.
+    pub fn forget_migrated_api_keys(&mut self, migrated: &HashMap<String, String>) -> bool {
+        let mut cleared = false;
+        for (id, key) in migrated {
+            if let Some(settings) = self.provider_settings.get_mut(id) {
+                if settings.api_key.as_ref() == Some(key) {
+                    settings.api_key = None;
+                    cleared = true;
+                }
+            }
+        }
+        cleared
     }
 
-    // ========================================================================
-    // macOS auto-start (LaunchAgent plist)
-    // ========================================================================
+    /// Builds the aggregate view configured for the enabled providers
+    pub fn usage_aggregate(&self) -> UsageAggregate {
+        let aggregate = self.aggregate_weights.iter().fold(
+            UsageAggregate::new(self.aggregate_mode),
+            |aggregate, (id, weight)| aggregate.with_weight(id.clone(), *weight),
+        );
+        self.headline_windows()
+            .into_iter()
+            .fold(aggregate, |aggregate, (id, slot)| {
+                aggregate.with_headline(id, slot)
+            })
+    }
 
-    #[cfg(target_os = "macos")]
-    fn launch_agent_path() -> Option<PathBuf> {
-        std::env::var("HOME")
-            .ok()
-            .map(|h| PathBuf::from(h).join("Library/LaunchAgents/com.gptbar.app.plist"))
+    /// Headline window of every provider that chose one
+    pub fn headline_windows(&self) -> HashMap<String, WindowSlot> {
+        self.provider_settings
+            .iter()
+            .filter_map(|(id, settings)| Some((id.clone(), settings.headline_window?)))
+            .collect()
     }
 
-    #[cfg(target_os = "macos")]
-    pub fn set_autostart(&self) -> Result<(), String> {
-        let plist_path =
-            Self::launch_agent_path().ok_or("Could not determine LaunchAgent path")?;
-
-        if self.start_on_login {
-            let exe_path =
-                std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
-
-            let plist_content = format!(
-                r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-    <key>Label</key>
-    <string>com.gptbar.app</string>
-    <key>ProgramArguments</key>
-    <array>
-        <string>{}</string>
-    </array>
-    <key>RunAtLoad</key>
-    <true/>
-    <key>KeepAlive</key>
-    <false/>
-</dict>
-</plist>
-"#,
-                exe_path.display()
-            );
+    /// Threshold overrides of every provider that has any
+    pub fn threshold_overrides(&self) -> HashMap<String, ProviderThresholds> {
+        self.provider_settings
+            .iter()
+            .filter(|(_, settings)| !settings.thresholds.is_empty())
+            .map(|(id, settings)| (id.clone(), settings.thresholds.clone()))
+            .collect()
+    }
 
-            // Create LaunchAgents directory if needed
-            if let Some(parent) = plist_path.parent() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| format!("Failed to create LaunchAgents dir: {}", e))?;
-            }
+    /// Global warning and critical levels, as saved for the notification
+    /// agent
+    pub fn global_thresholds(&self) -> Thresholds {
+        let defaults = Thresholds::default();
+        let saved = self
+            .agents
+            .get("notification")
+            .and_then(|settings| {
+                serde_json::from_value::<NotificationSettings>(settings.clone()).ok()
+            })
+            .unwrap_or_default();
+        Thresholds::new(
+            saved.warning_percent.unwrap_or(defaults.warning_percent),
+            saved.critical_percent.unwrap_or(defaults.critical_percent),
+        )
+    }
 
-            fs::write(&plist_path, plist_content)
-                .map_err(|e| format!("Failed to write plist: {}", e))?;
-        } else {
-            // Remove plist file
-            let _ = fs::remove_file(&plist_path);
-        }
+    /// Prepares a snapshot to be served to the UI, filling in each window's
+    /// reset text and severity
+    pub fn for_display(&self, provider_id: &str, snapshot: UsageSnapshot) -> UsageSnapshot {
+        let thresholds = self
+            .provider_settings
+            .get(provider_id)
+            .map(|settings| settings.thresholds.clone())
+            .unwrap_or_default();
+        snapshot
+            .with_reset_text(self.reset_style)
+            .with_severity(&thresholds, self.global_thresholds())
+    }
 
-        Ok(())
+    /// Whether any active provider has a window at its critical level,
+    /// muted windows included
+    pub fn any_critical(&self, snapshots: &HashMap<String, UsageSnapshot>) -> bool {
+        !self.critical_providers(snapshots).is_empty()
     }
 
-    #[cfg(target_os = "macos")]
-    pub fn is_autostart_enabled() -> bool {
-        Self::launch_agent_path()
-            .map(|p| p.exists())
-            .unwrap_or(false)
+    /// Active providers with a window at its critical level, muted windows
+    /// included, in display order
+    pub fn critical_providers(&self, snapshots: &HashMap<String, UsageSnapshot>) -> Vec<String> {
+        let global = self.global_thresholds();
+        let overrides = self.threshold_overrides();
+        self.active_providers()
+            .into_iter()
+            .filter(|id| {
+                let Some(snapshot) = snapshots.get(id) else {
+                    return false;
+                };
+                let thresholds = overrides.get(id).cloned().unwrap_or_default();
+                snapshot
+                    .windows()
+                    .any(|(slot, window)| window.is_critical(&thresholds.resolve(slot, global)))
+            })
+            .collect()
     }
 
-    // ========================================================================
-    // Linux auto-start (.desktop file in autostart)
-    // ========================================================================
+    /// Muted windows of every provider that has any
+    pub fn muted_windows(&self) -> HashMap<String, Vec<WindowSlot>> {
+        self.provider_settings
+            .iter()
+            .filter(|(_, settings)| !settings.muted_windows.is_empty())
+            .map(|(id, settings)| (id.clone(), settings.muted_windows.clone()))
+            .collect()
+    }
 
-    #[cfg(target_os = "linux")]
-    fn autostart_path() -> Option<PathBuf> {
-        std::env::var("XDG_CONFIG_HOME")
-            .ok()
-            .map(PathBuf::from)
-            .or_else(|| std::env::var("HOME").ok().map(|p| PathBuf::from(p).join(".config")))
-            .map(|p| p.join("autostart/gptbar.desktop"))
+    /// Budget period of a provider, calendar months unless one was chosen
+    pub fn budget_period(&self, provider_id: &str) -> BudgetPeriod {
+        self.provider_settings
+            .get(provider_id)
+            .and_then(|settings| settings.budget_period)
+            .unwrap_or_default()
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn set_autostart(&self) -> Result<(), String> {
-        let desktop_path = Self::autostart_path().ok_or("Could not determine autostart path")?;
-
-        if self.start_on_login {
-            let exe_path =
-                std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
-
-            let desktop_content = format!(
-                r#"[Desktop Entry]
-Type=Application
-Name=GPTBar
-Comment=Monitor AI provider usage from system tray
-Exec={}
-Icon=gptbar
-Terminal=false
-Categories=Utility;
-StartupNotify=false
-X-GNOME-Autostart-enabled=true
-"#,
-                exe_path.display()
-            );
+    /// Budget period of every active provider
+    pub fn budget_periods(&self) -> HashMap<String, BudgetPeriod> {
+        self.active_providers()
+            .into_iter()
+            .map(|id| {
+                let period = self.budget_period(&id);
+                (id, period)
+            })
+            .collect()
+    }
 
-            // Create autostart directory if needed
-            if let Some(parent) = desktop_path.parent() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| format!("Failed to create autostart dir: {}", e))?;
+    /// Saves settings passed to an agent
+    ///
+    /// Fields of a JSON object are merged into the saved ones, so a partial
+    /// update keeps the fields it leaves out.
+    pub fn set_agent_config(&mut self, id: &str, config: serde_json::Value) {
+        match (self.agents.get_mut(id), config) {
+            (Some(serde_json::Value::Object(saved)), serde_json::Value::Object(fields)) => {
+                saved.extend(fields);
+            }
+            (_, config) => {
+                self.agents.insert(id.to_string(), config);
             }
-
-            fs::write(&desktop_path, desktop_content)
-                .map_err(|e| format!("Failed to write desktop file: {}", e))?;
-        } else {
-            // Remove desktop file
-            let _ = fs::remove_file(&desktop_path);
         }
+    }
 
-        Ok(())
+    /// Time between background refreshes
+    pub fn refresh_period(&self) -> chrono::Duration {
+        chrono::Duration::minutes(i64::from(self.refresh_interval.max(1)))
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn is_autostart_enabled() -> bool {
-        Self::autostart_path()
-            .map(|p| p.exists())
-            .unwrap_or(false)
+    /// Age after which a snapshot counts as stale: two missed refreshes
+    pub fn stale_after(&self) -> chrono::Duration {
+        self.refresh_period() * 2
+    }
+
+    /// Returns a copy with all API keys removed, for display in the UI
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        for settings in config.provider_settings.values_mut() {
+            settings.api_key = None;
+        }
+        config
+    }
+
+    /// Restores API keys that were stripped by `redacted()`
+    ///
+    /// Keys present in `self` win; missing ones are taken from `stored`.
+    pub fn merge_secrets_from(&mut self, stored: &AppConfig) {
+        for (id, settings) in self.provider_settings.iter_mut() {
+            if settings.api_key.is_none() {
+                settings.api_key = stored.get_provider_api_key(id);
+            }
+        }
     }
 
-    // Fallback for other platforms
-    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    /// Adds or removes the start-on-login entry to match `start_on_login`
     pub fn set_autostart(&self) -> Result<(), String> {
-        Ok(())
+        AutoLaunch::current()?.set_enabled(self.start_on_login)
     }
 
-    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-    pub fn is_autostart_enabled() -> bool {
-        false
+    /// Whether GPTBar starts on login
+    ///
+    /// Falls back to the saved setting where the system doesn't tell, as
+    /// inside Flatpak.
+    pub fn is_autostart_enabled() -> Result<bool, String> {
+        Ok(AutoLaunch::current()?
+            .is_enabled()?
+            .unwrap_or_else(|| Self::load().start_on_login))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::RateWindow;
 
     #[test]
     fn test_default_config() {
@@ -359,6 +916,24 @@ mod tests {
         assert_eq!(loaded.enabled_providers.len(), 2);
     }
 
+    #[test]
+    fn test_shows_window_on_launch() {
+        let mut config = AppConfig::default();
+        assert!(!config.shows_window_on_launch());
+
+        config.show_window_on_launch = true;
+        assert!(config.shows_window_on_launch());
+    }
+
+    #[test]
+    fn test_stale_after() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.stale_after(), chrono::Duration::minutes(10));
+
+        config.refresh_interval = 0;
+        assert_eq!(config.stale_after(), chrono::Duration::minutes(2));
+    }
+
     #[test]
     fn test_provider_enabled() {
         let config = AppConfig::default();
@@ -374,6 +949,13 @@ mod tests {
             ProviderSettings {
                 enabled: true,
                 api_key: Some("sk-test-key".to_string()),
+                values: HashMap::new(),
+                pinned: false,
+                thresholds: ProviderThresholds::default(),
+                muted_windows: Vec::new(),
+                budget_period: None,
+                headline_window: None,
+                logged_out: false,
             },
         );
 
@@ -384,6 +966,373 @@ mod tests {
         assert_eq!(config.get_provider_api_key("claude"), None);
     }
 
+    #[test]
+    fn test_forget_migrated_api_keys() {
+        let mut config = AppConfig::default();
+        for (id, key) in [("openai", "sk-moved"), ("gemini", "AIza-changed")] {
+            config.provider_settings.insert(
+                id.to_string(),
+                ProviderSettings {
+                    api_key: Some(key.to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        let migrated = HashMap::from([
+            ("openai".to_string(), "sk-moved".to_string()),
+            ("gemini".to_string(), "AIza-saved".to_string()),
+        ]);
+
+        assert!(config.forget_migrated_api_keys(&migrated));
+        assert_eq!(config.get_provider_api_key("openai"), None);
+        // Changed after the migration read it
+        assert_eq!(
+            config.get_provider_api_key("gemini").as_deref(),
+            Some("AIza-changed")
+        );
+        assert!(!config.forget_migrated_api_keys(&migrated));
+    }
+
+    #[test]
+    fn test_redacted_and_merge_secrets() {
+        let mut config = AppConfig::default();
+        config.provider_settings.insert(
+            "openai".to_string(),
+            ProviderSettings {
+                enabled: true,
+                api_key: Some("sk-test-key".to_string()),
+                values: HashMap::new(),
+                pinned: false,
+                thresholds: ProviderThresholds::default(),
+                muted_windows: Vec::new(),
+                budget_period: None,
+                headline_window: None,
+                logged_out: false,
+            },
+        );
+
+        let mut redacted = config.redacted();
+        assert_eq!(redacted.get_provider_api_key("openai"), None);
+
+        redacted.merge_secrets_from(&config);
+        assert_eq!(
+            redacted.get_provider_api_key("openai"),
+            Some("sk-test-key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_viewer_mode_rejects_credential_changes() {
+        let mut config = AppConfig::default();
+        assert!(config.ensure_not_viewer().is_ok());
+
+        config.viewer_mode = true;
+        let err = config.ensure_not_viewer().unwrap_err();
+        assert_eq!(err.code().as_str(), "viewer_mode");
+    }
+
+    #[test]
+    fn test_active_profile_limits_providers() {
+        let mut config = AppConfig {
+            enabled_providers: vec!["claude".into(), "openai".into(), "gemini".into()],
+            profiles: vec![Profile {
+                name: "Work".to_string(),
+                providers: vec!["gemini".into(), "claude".into(), "codex".into()],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(config.active_providers().len(), 3);
+
+        config.active_profile = Some("Work".to_string());
+        // Enabled order is kept; disabled providers stay hidden
+        assert_eq!(config.active_providers(), vec!["claude", "gemini"]);
+
+        // A profile removed from the file no longer filters
+        config.active_profile = Some("Personal".to_string());
+        assert!(config.profile_providers().is_none());
+        assert_eq!(config.active_providers().len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "all-providers")]
+    fn test_pinned_providers_come_first() {
+        let mut config = AppConfig {
+            enabled_providers: vec!["claude".into(), "openai".into(), "gemini".into()],
+            ..Default::default()
+        };
+        config
+            .provider_settings
+            .entry("gemini".to_string())
+            .or_default()
+            .pinned = true;
+
+        assert_eq!(config.ordered_providers(), vec!["gemini", "claude", "openai"]);
+        assert!(config.provider_rank("openai") < config.provider_rank("codex"));
+
+        let mut metadata = crate::providers::ProviderRegistry::new().metadata();
+        config.order_metadata(&mut metadata);
+        let ids: Vec<_> = metadata.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids[..3], ["gemini", "claude", "openai"]);
+        let position = |id| ids.iter().position(|i| *i == id).unwrap();
+        assert!(position("openai") < position("codex"));
+        assert!(metadata[0].pinned);
+        assert!(metadata[1..].iter().all(|m| !m.pinned));
+    }
+
+    #[test]
+    fn test_demo_mode_shows_only_demo_provider() {
+        let mut config = AppConfig::default();
+        assert!(!config.is_demo());
+        assert_eq!(config.active_providers(), vec!["claude".to_string()]);
+
+        config.demo_mode = true;
+        assert_eq!(config.active_providers(), vec![DEMO_ID.to_string()]);
+    }
+
+    #[test]
+    fn test_usage_aggregate_from_config() {
+        let mut config = AppConfig {
+            aggregate_mode: AggregateMode::Average,
+            ..AppConfig::default()
+        };
+        config.aggregate_weights.insert("openai".to_string(), 3.0);
+
+        let mut snapshots = HashMap::new();
+        snapshots.insert(
+            "claude".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(90.0)),
+        );
+        snapshots.insert(
+            "openai".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(10.0)),
+        );
+        let enabled = vec!["claude".to_string(), "openai".to_string()];
+
+        let aggregate = config.usage_aggregate().snapshot(&snapshots, &enabled);
+        assert_eq!(aggregate.map(|s| s.max_usage()), Some(30.0));
+
+        snapshots.insert(
+            "claude".to_string(),
+            UsageSnapshot::new()
+                .with_primary(RateWindow::new(90.0))
+                .with_secondary(RateWindow::new(50.0)),
+        );
+        config
+            .provider_settings
+            .entry("claude".to_string())
+            .or_default()
+            .headline_window = Some(WindowSlot::Secondary);
+        let aggregate = config.usage_aggregate().snapshot(&snapshots, &enabled);
+        // (50 * 1 + 10 * 3) / 4
+        assert_eq!(aggregate.map(|s| s.max_usage()), Some(20.0));
+    }
+
+    #[test]
+    fn test_sort_providers_by_urgency() {
+        let mut config = AppConfig {
+            enabled_providers: vec![
+                "claude".into(),
+                "openai".into(),
+                "gemini".into(),
+                "codex".into(),
+                "together".into(),
+            ],
+            ..AppConfig::default()
+        };
+        let snapshots = HashMap::from([
+            (
+                "claude".to_string(),
+                UsageSnapshot::new().with_primary(RateWindow::new(10.0)),
+            ),
+            (
+                "openai".to_string(),
+                UsageSnapshot::new().with_primary(RateWindow::new(85.0)),
+            ),
+            (
+                "gemini".to_string(),
+                UsageSnapshot::new().with_primary(RateWindow::new(40.0)),
+            ),
+            (
+                "codex".to_string(),
+                UsageSnapshot::new()
+                    .with_primary(RateWindow::new(20.0))
+                    .with_secondary(RateWindow::new(97.0)),
+            ),
+        ]);
+
+        // The saved order is kept unless urgency is chosen
+        let mut ids = config.ordered_providers();
+        config.sort_providers(&mut ids, |id| id.as_str(), &snapshots);
+        assert_eq!(ids, config.enabled_providers);
+
+        config.provider_sort = ProviderSort::Urgency;
+        config.sort_providers(&mut ids, |id| id.as_str(), &snapshots);
+        assert_eq!(ids, vec!["codex", "openai", "gemini", "claude", "together"]);
+
+        config
+            .provider_settings
+            .entry("claude".to_string())
+            .or_default()
+            .pinned = true;
+        let mut ids = config.ordered_providers();
+        config.sort_providers(&mut ids, |id| id.as_str(), &snapshots);
+        assert_eq!(ids, vec!["claude", "codex", "openai", "gemini", "together"]);
+    }
+
+    #[test]
+    fn test_any_critical() {
+        let mut config = AppConfig::default();
+        let mut snapshots = HashMap::new();
+        snapshots.insert(
+            "claude".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(92.0)),
+        );
+        assert!(!config.any_critical(&snapshots));
+
+        config.set_agent_config(
+            "notification",
+            serde_json::json!({ "critical_percent": 90.0 }),
+        );
+        assert_eq!(config.global_thresholds(), Thresholds::new(80.0, 90.0));
+        assert!(config.any_critical(&snapshots));
+        assert_eq!(config.critical_providers(&snapshots), vec!["claude"]);
+
+        // Disabled providers don't count
+        snapshots.insert(
+            "openai".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(100.0)),
+        );
+        config.set_agent_config(
+            "notification",
+            serde_json::json!({ "critical_percent": 95.0 }),
+        );
+        assert!(!config.any_critical(&snapshots));
+    }
+
+    #[test]
+    fn test_mqtt_settings_defaults() {
+        let json = r#"{"refresh_interval":5,"start_on_login":false,"mqtt":{"host":"broker.lan"}}"#;
+        let loaded: AppConfig = serde_json::from_str(json).unwrap();
+        assert!(!loaded.mqtt.enabled);
+        assert_eq!(loaded.mqtt.host, "broker.lan");
+        assert_eq!(loaded.mqtt.port, 1883);
+        assert_eq!(loaded.mqtt.topic_prefix, "gptbar");
+    }
+
+    #[test]
+    fn test_api_server_localhost_by_default() {
+        let config = AppConfig::default();
+        assert!(!config.api_server.enabled);
+        assert!(config.api_server.bind_address.is_loopback());
+
+        let json = r#"{"enabled":true,"bind_address":"0.0.0.0"}"#;
+        let settings: ApiServerSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.bind_address, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(settings.port, 7727);
+    }
+
+    #[test]
+    fn test_threshold_overrides() {
+        let json = r#"{"refresh_interval":5,"start_on_login":false,"provider_settings":{
+            "claude":{"enabled":true,"thresholds":{"windows":{"secondary":{"warning_percent":50.0,"critical_percent":90.0}}}},
+            "openai":{"enabled":true}
+        }}"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+        let overrides = config.threshold_overrides();
+
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(
+            overrides["claude"].resolve(WindowSlot::Secondary, Thresholds::default()),
+            Thresholds::new(50.0, 90.0)
+        );
+    }
+
+    #[test]
+    fn test_currency_fixed() {
+        let settings = |code: &str, rate| CurrencySettings {
+            code: code.to_string(),
+            rate,
+        };
+
+        assert_eq!(settings("USD", None).fixed(), Some(Currency::default()));
+        assert_eq!(
+            settings("eur", Some(0.92)).fixed(),
+            Some(Currency::new("EUR", 0.92))
+        );
+        assert_eq!(settings("EUR", None).fixed(), None);
+        assert_eq!(settings("EUR", Some(0.0)).fixed(), None);
+    }
+
+    #[test]
+    fn test_appearance_clamped() {
+        let appearance = |opacity| AppearanceSettings {
+            effect: WindowEffect::Acrylic,
+            opacity,
+        };
+
+        assert_eq!(appearance(0.6).clamped(), appearance(0.6));
+        assert_eq!(appearance(0.0).clamped(), appearance(0.2));
+        assert_eq!(appearance(3.0).clamped(), appearance(1.0));
+        assert_eq!(appearance(f64::NAN).clamped(), appearance(0.8));
+    }
+
+    #[test]
+    fn test_muted_windows() {
+        let json = r#"{"refresh_interval":5,"start_on_login":false,"provider_settings":{
+            "claude":{"enabled":true,"muted_windows":["primary"]},
+            "openai":{"enabled":true}
+        }}"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+        let muted = config.muted_windows();
+
+        assert_eq!(muted.len(), 1);
+        assert_eq!(muted["claude"], vec![WindowSlot::Primary]);
+    }
+
+    #[test]
+    fn test_browser_data_dirs() {
+        let json = r#"{"refresh_interval":5,"start_on_login":false,
+            "browser_data_dirs":{"chrome":"D:\\PortableChrome\\Data\\profile"}}"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            config.browser_data_dirs[&BrowserType::Chrome],
+            PathBuf::from(r"D:\PortableChrome\Data\profile")
+        );
+        assert!(!config.browser_data_dirs.contains_key(&BrowserType::Edge));
+    }
+
+    #[test]
+    fn test_budget_periods() {
+        let json = r#"{"refresh_interval":5,"start_on_login":false,
+            "enabled_providers":["claude","openai"],"provider_settings":{
+            "claude":{"enabled":true,"budget_period":{"kind":"billing_anchor","day":15}},
+            "openai":{"enabled":true}
+        }}"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+        let periods = config.budget_periods();
+
+        assert_eq!(periods["claude"], BudgetPeriod::BillingAnchor { day: 15 });
+        assert_eq!(periods["openai"], BudgetPeriod::CalendarMonth);
+        assert_eq!(config.budget_period("gemini"), BudgetPeriod::CalendarMonth);
+    }
+
+    #[test]
+    fn test_set_agent_config_merges_fields() {
+        let mut config = AppConfig::default();
+        config.set_agent_config("refresh", serde_json::json!({ "interval_minutes": 10 }));
+        config.set_agent_config("refresh", serde_json::json!({ "stagger": false }));
+        config.set_agent_config("refresh", serde_json::json!({ "interval_minutes": 15 }));
+
+        let json = serde_json::to_string(&config).unwrap();
+        let loaded: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded.agents["refresh"],
+            serde_json::json!({ "interval_minutes": 15, "stagger": false })
+        );
+        assert!(!json.contains("\"notification\""));
+    }
+
     #[test]
     fn test_config_dir_exists() {
         // This test just verifies the function doesn't panic
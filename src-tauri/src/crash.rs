@@ -0,0 +1,292 @@
+//! Crash reports
+//!
+//! [`install_hook`] writes a report of the first panic of a run to the
+//! config folder, including panics on background tasks, which would
+//! otherwise only end that task. On the next launch the popup offers to open
+//! a GitHub issue with the report, or to dismiss it.
+//!
+//! Reports only hold the panic message with tokens redacted, the backtrace,
+//! the app version and platform, and a [`ConfigSummary`] without keys, hosts
+//! or paths.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::i18n::Language;
+use crate::security::Sanitizer;
+
+/// Report file in the config folder
+const REPORT_FILE: &str = "crash-report.json";
+
+/// Where new issues are opened
+const ISSUES_URL: &str = "https://github.com/episuarez/gptBar/issues/new";
+
+/// Longest issue body passed in the URL; GitHub rejects much longer URLs
+const MAX_ISSUE_BODY: usize = 6000;
+
+/// Settings that help reproduce a crash, without secrets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigSummary {
+    pub enabled_providers: Vec<String>,
+    pub refresh_interval: u32,
+    pub language: Option<Language>,
+    pub demo_mode: bool,
+    pub ipc_enabled: bool,
+    pub mqtt_enabled: bool,
+    pub api_server_enabled: bool,
+    pub require_os_auth: bool,
+    pub claude_local_usage: bool,
+}
+
+impl From<&AppConfig> for ConfigSummary {
+    fn from(config: &AppConfig) -> Self {
+        Self {
+            enabled_providers: config.enabled_providers.clone(),
+            refresh_interval: config.refresh_interval,
+            language: config.language,
+            demo_mode: config.demo_mode,
+            ipc_enabled: config.ipc_enabled,
+            mqtt_enabled: config.mqtt.enabled,
+            api_server_enabled: config.api_server.enabled,
+            require_os_auth: config.require_os_auth,
+            claude_local_usage: config.claude_local_usage,
+        }
+    }
+}
+
+/// What was known about a panic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    /// Name of the panicking thread, e.g. a Tokio worker
+    pub thread: Option<String>,
+    /// Panic message, tokens redacted
+    pub message: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub config: ConfigSummary,
+    pub crashed_at: DateTime<Utc>,
+}
+
+impl CrashReport {
+    /// Builds a report of the current panic
+    pub fn from_panic(info: &std::panic::PanicHookInfo<'_>) -> Self {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            thread: std::thread::current().name().map(String::from),
+            message: Sanitizer::redact_tokens(&message),
+            location: info.location().map(|l| l.to_string()),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            config: ConfigSummary::from(&AppConfig::load()),
+            crashed_at: Utc::now(),
+        }
+    }
+
+    /// Writes the report to `dir`, replacing an older one
+    pub fn save_in(&self, dir: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+        let path = dir.join(REPORT_FILE);
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write crash report: {}", e))?;
+        Ok(path)
+    }
+
+    /// The report left in `dir` by an earlier run, if any
+    pub fn pending_in(dir: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(dir.join(REPORT_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Deletes the report in `dir`
+    pub fn dismiss_in(dir: &Path) -> Result<(), String> {
+        match std::fs::remove_file(dir.join(REPORT_FILE)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to delete crash report: {}", e)),
+        }
+    }
+
+    /// The report as a Markdown issue body, the backtrace cut to fit a URL
+    pub fn issue_body(&self) -> String {
+        let mut body = String::new();
+        let _ = writeln!(body, "**What were you doing when GPTBar crashed?**\n\n\n");
+        let _ = writeln!(body, "### Crash report\n");
+        let _ = writeln!(body, "- Version: {}", self.version);
+        let _ = writeln!(body, "- Platform: {} {}", self.os, self.arch);
+        let _ = writeln!(body, "- Crashed at: {}", self.crashed_at.to_rfc3339());
+        if let Some(thread) = &self.thread {
+            let _ = writeln!(body, "- Thread: {}", thread);
+        }
+        if let Some(location) = &self.location {
+            let _ = writeln!(body, "- Location: `{}`", location);
+        }
+        let _ = writeln!(body, "\n```\n{}\n```\n", self.message);
+        let config = serde_json::to_string_pretty(&self.config).unwrap_or_default();
+        let _ = writeln!(
+            body,
+            "<details><summary>Settings</summary>\n\n```json\n{}\n```\n</details>\n",
+            config
+        );
+
+        let fence_open = "<details><summary>Backtrace</summary>\n\n```\n";
+        let fence_close = "\n```\n</details>\n";
+        let room = MAX_ISSUE_BODY.saturating_sub(body.len() + fence_open.len() + fence_close.len());
+        let backtrace = truncate(&self.backtrace, room);
+        let _ = write!(body, "{}{}{}", fence_open, backtrace, fence_close);
+        body
+    }
+
+    /// URL of a new GitHub issue filled in with the report
+    pub fn issue_url(&self) -> String {
+        let title = format!("Crash: {}", truncate(&self.message, 80));
+        reqwest::Url::parse_with_params(
+            ISSUES_URL,
+            [("title", title.as_str()), ("body", &self.issue_body())],
+        )
+        .map(String::from)
+        .unwrap_or_else(|_| ISSUES_URL.to_string())
+    }
+}
+
+/// Cuts `text` to at most `max` bytes on a character boundary
+fn truncate(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Installs a panic hook writing a [`CrashReport`] of the first panic
+///
+/// The previous hook still runs, so panics are printed as before.
+pub fn install_hook() {
+    static WRITTEN: AtomicBool = AtomicBool::new(false);
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Later panics are usually caused by the first one
+        if !WRITTEN.swap(true, Ordering::SeqCst) {
+            let report = CrashReport::from_panic(info);
+            tracing::error!(
+                "Panic on thread {}: {}",
+                report.thread.as_deref().unwrap_or("<unnamed>"),
+                report.message
+            );
+            match AppConfig::config_dir().map(|dir| report.save_in(&dir)) {
+                Some(Ok(path)) => tracing::error!("Crash report written to {}", path.display()),
+                Some(Err(e)) => tracing::error!("{}", e),
+                None => tracing::error!("No config dir for the crash report"),
+            }
+        }
+        previous(info);
+    }));
+}
+
+/// The report left by an earlier run, if any
+pub fn pending() -> Option<CrashReport> {
+    CrashReport::pending_in(&AppConfig::config_dir()?)
+}
+
+/// Deletes the report left by an earlier run
+pub fn dismiss() -> Result<(), String> {
+    match AppConfig::config_dir() {
+        Some(dir) => CrashReport::dismiss_in(&dir),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> CrashReport {
+        CrashReport {
+            version: "1.2.3".to_string(),
+            os: "linux".to_string(),
+            arch: "aarch64".to_string(),
+            thread: Some("tokio-runtime-worker".to_string()),
+            message: "index out of bounds".to_string(),
+            location: Some("src/agents/refresh_agent.rs:10:5".to_string()),
+            backtrace: "frame\n".repeat(5000),
+            config: ConfigSummary::from(&AppConfig::default()),
+            crashed_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_save_pending_dismiss() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(CrashReport::pending_in(dir.path()).is_none());
+
+        let report = report();
+        report.save_in(dir.path()).unwrap();
+        assert_eq!(CrashReport::pending_in(dir.path()), Some(report));
+
+        CrashReport::dismiss_in(dir.path()).unwrap();
+        assert!(CrashReport::pending_in(dir.path()).is_none());
+        // Nothing left to dismiss
+        CrashReport::dismiss_in(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn test_issue_body_fits_url() {
+        let body = report().issue_body();
+        assert!(body.len() <= MAX_ISSUE_BODY);
+        assert!(body.contains("- Version: 1.2.3"));
+        assert!(body.contains("- Location: `src/agents/refresh_agent.rs:10:5`"));
+        assert!(body.contains("index out of bounds"));
+        assert!(body.ends_with("```\n</details>\n"));
+    }
+
+    #[test]
+    fn test_issue_url() {
+        let url = reqwest::Url::parse(&report().issue_url()).unwrap();
+        let title = url
+            .query_pairs()
+            .find(|(k, _)| k == "title")
+            .map(|(_, v)| v.into_owned());
+        assert_eq!(title.as_deref(), Some("Crash: index out of bounds"));
+    }
+
+    #[test]
+    fn test_config_summary_has_no_secrets() {
+        let mut config = AppConfig::default();
+        config
+            .provider_settings
+            .entry("openai".to_string())
+            .or_default()
+            .api_key = Some("sk-secret".to_string());
+        let json = serde_json::to_string(&ConfigSummary::from(&config)).unwrap();
+        assert!(!json.contains("sk-secret"));
+    }
+
+    #[test]
+    fn test_truncate_on_char_boundary() {
+        assert_eq!(truncate("héllo", 2), "h");
+        assert_eq!(truncate("héllo", 3), "hé");
+        assert_eq!(truncate("hi", 10), "hi");
+    }
+}
@@ -0,0 +1,248 @@
+//! `gptbar://` deep links
+//!
+//! Scripts, browser bookmarks and OAuth redirects can drive the app through
+//! URLs:
+//!
+//! - `gptbar://show` shows the popup
+//! - `gptbar://refresh` refreshes every provider
+//! - `gptbar://provider/<id>` shows the popup on one provider
+//!
+//! Windows and Linux start `gptbar <url>` for each link. That process hands
+//! the link to the running app over the IPC socket and exits, or starts the
+//! app itself when none is running. macOS delivers links to the running app
+//! directly. Opened links are published as
+//! [`AppEvent::DeepLinkOpened`](crate::events::AppEvent::DeepLinkOpened).
+
+use serde::Serialize;
+
+/// URL scheme GPTBar handles
+pub const SCHEME: &str = "gptbar";
+
+/// What a deep link asks the app to do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// Show the popup
+    Show,
+    /// Refresh every provider
+    Refresh,
+    /// Show the popup on one provider
+    Provider { provider_id: String },
+}
+
+impl DeepLink {
+    /// Parses a `gptbar://` URL, ignoring its query and fragment
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gptbar_lib::deep_link::DeepLink;
+    ///
+    /// assert_eq!(DeepLink::parse("gptbar://refresh"), Some(DeepLink::Refresh));
+    /// assert_eq!(
+    ///     DeepLink::parse("gptbar://provider/claude/"),
+    ///     Some(DeepLink::Provider { provider_id: "claude".into() })
+    /// );
+    /// assert_eq!(DeepLink::parse("https://example.com"), None);
+    /// ```
+    pub fn parse(url: &str) -> Option<Self> {
+        let (scheme, rest) = url.trim().split_once(':')?;
+        if !scheme.eq_ignore_ascii_case(SCHEME) {
+            return None;
+        }
+        let path = rest
+            .trim_start_matches('/')
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+
+        match path.split_once('/') {
+            None if path.eq_ignore_ascii_case("show") => Some(Self::Show),
+            None if path.eq_ignore_ascii_case("refresh") => Some(Self::Refresh),
+            Some((action, id)) if action.eq_ignore_ascii_case("provider") && is_provider_id(id) => {
+                Some(Self::Provider {
+                    provider_id: id.to_ascii_lowercase(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The first deep link among command-line arguments, if any
+    pub fn from_args(args: &[String]) -> Option<(String, Self)> {
+        args.iter()
+            .skip(1)
+            .find_map(|arg| Some((arg.clone(), Self::parse(arg)?)))
+    }
+}
+
+fn is_provider_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Registers GPTBar as the current user's handler of `gptbar://` links
+///
+/// Done on every start, so the handler follows the executable when it
+/// moves. macOS reads the scheme from the app bundle's `Info.plist` instead.
+#[cfg(target_os = "windows")]
+pub fn register() -> Result<(), String> {
+    use std::process::Command;
+
+    let exe_path = std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe_path.display());
+
+    let entries: [(String, Option<&str>, &str); 3] = [
+        (key.clone(), None, "URL:GPTBar"),
+        (key.clone(), Some("URL Protocol"), ""),
+        (format!(r"{}\shell\open\command", key), None, &command),
+    ];
+    for (key, name, value) in &entries {
+        let mut args = vec!["add", key.as_str()];
+        match name {
+            Some(name) => args.extend(["/v", name]),
+            None => args.push("/ve"),
+        }
+        args.extend(["/t", "REG_SZ", "/d", value, "/f"]);
+
+        let output = Command::new("reg")
+            .args(&args)
+            .output()
+            .map_err(|e| format!("Failed to run reg command: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("Failed to add registry key {}", key));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn desktop_file_path() -> Option<std::path::PathBuf> {
+    std::env::var("XDG_DATA_HOME")
+        .ok()
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|h| std::path::PathBuf::from(h).join(".local/share"))
+        })
+        .map(|p| p.join("applications/gptbar-url-handler.desktop"))
+}
+
+/// Registers GPTBar as the current user's handler of `gptbar://` links
+///
+/// Done on every start, so the handler follows the executable when it
+/// moves. macOS reads the scheme from the app bundle's `Info.plist` instead.
+#[cfg(target_os = "linux")]
+pub fn register() -> Result<(), String> {
+    use std::fs;
+    use std::process::Command;
+
+    let desktop_path = desktop_file_path().ok_or("Could not determine applications path")?;
+    let exe_path = std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
+
+    let desktop_content = format!(
+        r#"[Desktop Entry]
+Type=Application
+Name=GPTBar
+Exec="{}" %u
+Icon=gptbar
+Terminal=false
+NoDisplay=true
+MimeType=x-scheme-handler/{};
+"#,
+        exe_path.display(),
+        SCHEME
+    );
+
+    // Unchanged since the last start
+    if fs::read_to_string(&desktop_path).is_ok_and(|current| current == desktop_content) {
+        return Ok(());
+    }
+
+    if let Some(parent) = desktop_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create applications dir: {}", e))?;
+    }
+    fs::write(&desktop_path, desktop_content)
+        .map_err(|e| format!("Failed to write desktop file: {}", e))?;
+
+    let output = Command::new("xdg-mime")
+        .args([
+            "default",
+            "gptbar-url-handler.desktop",
+            &format!("x-scheme-handler/{}", SCHEME),
+        ])
+        .output()
+        .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+    if !output.status.success() {
+        return Err("xdg-mime could not set the URL handler".to_string());
+    }
+
+    Ok(())
+}
+
+/// Registers GPTBar as the current user's handler of `gptbar://` links
+///
+/// macOS reads the scheme from the app bundle's `Info.plist`.
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn register() -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(DeepLink::parse("gptbar://show"), Some(DeepLink::Show));
+        assert_eq!(
+            DeepLink::parse("GPTBar://Refresh/"),
+            Some(DeepLink::Refresh)
+        );
+        assert_eq!(DeepLink::parse("gptbar:refresh"), Some(DeepLink::Refresh));
+        assert_eq!(
+            DeepLink::parse("gptbar://provider/Claude?from=bookmark#top"),
+            Some(DeepLink::Provider {
+                provider_id: "claude".to_string()
+            })
+        );
+
+        assert_eq!(DeepLink::parse("gptbar://provider/"), None);
+        assert_eq!(DeepLink::parse("gptbar://provider/../etc"), None);
+        assert_eq!(DeepLink::parse("gptbar://quit"), None);
+        assert_eq!(DeepLink::parse("other://show"), None);
+        assert_eq!(DeepLink::parse("--hidden"), None);
+    }
+
+    #[test]
+    fn test_from_args() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            DeepLink::from_args(&args(&["gptbar", "--hidden", "gptbar://show"])),
+            Some(("gptbar://show".to_string(), DeepLink::Show))
+        );
+        // The program path is never a link
+        assert_eq!(DeepLink::from_args(&args(&["gptbar://show"])), None);
+        assert_eq!(DeepLink::from_args(&args(&["gptbar", "waybar"])), None);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = serde_json::to_value(DeepLink::Provider {
+            provider_id: "openai".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "action": "provider", "provider_id": "openai" })
+        );
+    }
+}
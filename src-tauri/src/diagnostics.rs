@@ -0,0 +1,264 @@
+//! Diagnostic bundles for bug reports
+//!
+//! A bundle is a zip of recent log lines, the config without keys, the
+//! credential discovery report, fetch metrics, agent status, a pending crash
+//! report and system facts. Every file goes through
+//! [`Sanitizer::redact_tokens`] before it is added.
+//!
+//! Logs are only printed to stdout, so the app's log writer, [`log_writer`],
+//! also keeps the last lines in memory for bundles.
+
+use std::collections::VecDeque;
+use std::io::{Seek, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use zip::write::SimpleFileOptions;
+
+use crate::auth::keychain;
+use crate::config::AppConfig;
+use crate::crash;
+use crate::memory::MemoryUsage;
+use crate::providers::CredentialReport;
+use crate::security::Sanitizer;
+use crate::AppState;
+
+/// Log lines kept for bundles
+const LOG_LINES: usize = 1000;
+
+/// The most recent log lines, without terminal colors
+pub struct LogBuffer {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+
+impl LogBuffer {
+    /// Creates an empty buffer keeping the last `capacity` lines
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Appends the lines of `text`
+    pub fn push(&self, text: &str) {
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(strip_ansi(line));
+        }
+    }
+
+    /// The kept lines, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+
+    /// Lines kept and their length
+    pub fn memory(&self) -> MemoryUsage {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        MemoryUsage {
+            entries: lines.len(),
+            bytes: lines.iter().map(String::len).sum(),
+        }
+    }
+}
+
+/// Removes ANSI escape sequences, as used for log colors
+fn strip_ansi(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip up to and including the final letter, e.g. `\x1b[2m`
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn log_buffer() -> &'static LogBuffer {
+    static BUFFER: OnceLock<LogBuffer> = OnceLock::new();
+    BUFFER.get_or_init(|| LogBuffer::new(LOG_LINES))
+}
+
+/// Memory held by the app's log buffer
+pub fn log_memory() -> MemoryUsage {
+    log_buffer().memory()
+}
+
+/// Log output that goes to stdout and to the buffer bundles read
+pub struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        log_buffer().push(&String::from_utf8_lossy(buf));
+        // A closed stdout, as with the Windows GUI subsystem, only loses the
+        // printed copy
+        let _ = std::io::stdout().write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
+
+/// Makes a [`LogWriter`], for `tracing_subscriber::fmt().with_writer()`
+pub fn log_writer() -> LogWriter {
+    LogWriter
+}
+
+/// Facts about the machine and build
+#[derive(Debug, Serialize)]
+pub struct SystemInfo {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub flatpak: bool,
+    /// Why credentials are kept in an encrypted file, if they are
+    pub credential_store_fallback: Option<String>,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SystemInfo {
+    /// Describes the current process
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            flatpak: crate::sandbox::is_flatpak(),
+            credential_store_fallback: keychain::fallback_reason(),
+            generated_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Sanitized files for a bug report, written out as a zip
+#[derive(Debug, Default)]
+pub struct DiagnosticBundle {
+    files: Vec<(String, String)>,
+}
+
+impl DiagnosticBundle {
+    /// Gathers everything a bug report needs from the running app
+    pub async fn collect(state: &AppState) -> Self {
+        let mut bundle = Self::default();
+        bundle.add_json("system.json", &SystemInfo::current());
+        bundle.add_json("config.json", &AppConfig::load().redacted());
+
+        let providers = state.registry.real_providers();
+        // Keychain and file access can block
+        match tokio::task::spawn_blocking(move || CredentialReport::discover(&providers)).await {
+            Ok(report) => bundle.add_json("credentials.json", &report),
+            Err(e) => bundle.add("credentials.json", format!("Discovery failed: {}", e)),
+        }
+
+        bundle.add_json("metrics.json", &state.refresh.metrics().await);
+        bundle.add_json("agents.json", &state.agent_manager.info().await);
+        if let Some(report) = crash::pending() {
+            bundle.add_json("crash-report.json", &report);
+        }
+        bundle.add("gptbar.log", log_buffer().lines().join("\n"));
+        bundle
+    }
+
+    /// Adds a file, with tokens redacted
+    pub fn add(&mut self, name: &str, content: String) {
+        self.files
+            .push((name.to_string(), Sanitizer::redact_tokens(&content)));
+    }
+
+    /// Adds a value as a pretty-printed JSON file, with tokens redacted
+    pub fn add_json<T: Serialize>(&mut self, name: &str, value: &T) {
+        let content = serde_json::to_string_pretty(value)
+            .unwrap_or_else(|e| format!("Failed to serialize: {}", e));
+        self.add(name, content);
+    }
+
+    /// Names of the files added so far
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Writes the files as a zip
+    pub fn write_zip(&self, writer: impl Write + Seek) -> Result<(), String> {
+        let mut zip = zip::ZipWriter::new(writer);
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, content) in &self.files {
+            zip.start_file(name.as_str(), options)
+                .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+            zip.write_all(content.as_bytes())
+                .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+        }
+        zip.finish()
+            .map_err(|e| format!("Failed to finish zip: {}", e))?;
+        Ok(())
+    }
+
+    /// Writes the zip to `path`
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        self.write_zip(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_log_buffer_keeps_last_lines() {
+        let buffer = LogBuffer::new(2);
+        buffer.push("\u{1b}[2m2026-01-01\u{1b}[0m \u{1b}[32m INFO\u{1b}[0m first\n");
+        buffer.push("second\n\nthird\n");
+        assert_eq!(buffer.lines(), vec!["second", "third"]);
+
+        let buffer = LogBuffer::new(5);
+        buffer.push("\u{1b}[2m2026-01-01\u{1b}[0m \u{1b}[32m INFO\u{1b}[0m first\n");
+        assert_eq!(buffer.lines(), vec!["2026-01-01  INFO first"]);
+    }
+
+    #[test]
+    fn test_bundle_redacts_and_zips() {
+        let mut bundle = DiagnosticBundle::default();
+        bundle.add(
+            "gptbar.log",
+            "WARN Fetch failed: invalid key sk-ant-REDACTED".to_string(),
+        );
+        bundle.add_json("system.json", &SystemInfo::current());
+        assert_eq!(
+            bundle.file_names().collect::<Vec<_>>(),
+            vec!["gptbar.log", "system.json"]
+        );
+
+        let mut zip_data = Cursor::new(Vec::new());
+        bundle.write_zip(&mut zip_data).unwrap();
+
+        let mut archive = zip::ZipArchive::new(zip_data).unwrap();
+        let mut log = String::new();
+        archive
+            .by_name("gptbar.log")
+            .unwrap()
+            .read_to_string(&mut log)
+            .unwrap();
+        assert!(log.starts_with("WARN Fetch failed"));
+        assert!(!log.contains("abcdefghijkl"));
+        assert!(archive.by_name("system.json").is_ok());
+    }
+}
@@ -0,0 +1,173 @@
+//! Crate-wide event bus
+//!
+//! Subsystems publish [`AppEvent`]s on one process-wide broadcast channel
+//! and subscribe to the ones they care about, instead of registering
+//! callbacks with each other. The app forwards every event to the frontend
+//! as [`APP_EVENT`].
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::agents::{NotificationEvent, UsageUpdate};
+use crate::budget::BudgetRollover;
+use crate::deep_link::DeepLink;
+use crate::providers::{AuthState, ProviderErrorCode, WindowSlot};
+
+/// Frontend event carrying every [`AppEvent`]
+pub const APP_EVENT: &str = "app-event";
+
+/// Events a subscriber can fall behind by before it misses some
+const CHANNEL_CAPACITY: usize = 128;
+
+/// Something that happened in the app
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppEvent {
+    /// A provider was fetched
+    SnapshotUpdated(Box<UsageUpdate>),
+    /// A background fetch of a provider failed
+    FetchFailed {
+        provider_id: String,
+        code: ProviderErrorCode,
+    },
+    /// A provider's credentials were found, accepted, refused or removed,
+    /// e.g. after a login, a logout or its credentials expiring
+    AuthStateChanged {
+        provider_id: String,
+        state: AuthState,
+        /// The state before
+        previous: AuthState,
+        /// Whether the provider has usable credentials now
+        available: bool,
+    },
+    /// An agent was started or stopped
+    AgentStateChanged { agent_id: String, running: bool },
+    /// The config file was saved
+    ConfigChanged,
+    /// A usage notification was sent
+    NotificationSent(NotificationEvent),
+    /// A provider entered a new billing period
+    BudgetPeriodStarted(BudgetRollover),
+    /// A `gptbar://` link was opened
+    DeepLinkOpened(DeepLink),
+    /// The keychain failed and credentials are now kept in an encrypted file
+    CredentialStoreFallback {
+        /// The keychain error
+        reason: String,
+    },
+    /// A rate window started over
+    WindowReset {
+        provider_id: String,
+        window: WindowSlot,
+    },
+    /// A provider's usage reached the freeze level
+    UsageFrozen { provider_id: String, percent: f64 },
+    /// A frozen provider's usage dropped below the freeze level
+    UsageUnfrozen { provider_id: String, percent: f64 },
+}
+
+fn sender() -> &'static broadcast::Sender<AppEvent> {
+    static SENDER: OnceLock<broadcast::Sender<AppEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Sends an event to every current subscriber
+pub fn publish(event: AppEvent) {
+    // Fails only when nobody is subscribed
+    let _ = sender().send(event);
+}
+
+/// Subscribes to every event published from now on
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    sender().subscribe()
+}
+
+/// Calls `handler` with every message from `receiver` until the channel closes
+///
+/// Meant to run on its own task, so a slow handler only delays itself.
+/// Messages missed by falling too far behind are skipped with a warning.
+pub async fn consume<T, F>(mut receiver: broadcast::Receiver<T>, mut handler: F)
+where
+    T: Clone,
+    F: FnMut(T),
+{
+    loop {
+        match receiver.recv().await {
+            Ok(message) => handler(message),
+            Err(RecvError::Lagged(missed)) => {
+                tracing::warn!("Event subscriber fell behind, skipped {} events", missed);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let mut first = subscribe();
+        let mut second = subscribe();
+
+        publish(AppEvent::AgentStateChanged {
+            agent_id: "test-bus".to_string(),
+            running: true,
+        });
+
+        // Other tests publish on the same bus
+        for receiver in [&mut first, &mut second] {
+            let found = std::iter::from_fn(|| receiver.try_recv().ok()).any(|event| {
+                matches!(event, AppEvent::AgentStateChanged { agent_id, running: true }
+                    if agent_id == "test-bus")
+            });
+            assert!(found);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consume_skips_missed_messages() {
+        let (sender, receiver) = broadcast::channel(4);
+        for i in 0..6 {
+            sender.send(i).unwrap();
+        }
+        drop(sender);
+
+        let mut received = Vec::new();
+        consume(receiver, |i| received.push(i)).await;
+        assert_eq!(received, vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let json = serde_json::to_value(AppEvent::AuthStateChanged {
+            provider_id: "claude".to_string(),
+            state: AuthState::Expired,
+            previous: AuthState::Valid { expires_at: None },
+            available: false,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "auth_state_changed",
+                "provider_id": "claude",
+                "state": { "status": "expired" },
+                "previous": { "status": "valid", "expires_at": null },
+                "available": false,
+            })
+        );
+
+        let json = serde_json::to_value(AppEvent::ConfigChanged).unwrap();
+        assert_eq!(json, serde_json::json!({ "type": "config_changed" }));
+
+        let json = serde_json::to_value(AppEvent::DeepLinkOpened(DeepLink::Refresh)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "type": "deep_link_opened", "action": "refresh" })
+        );
+    }
+}
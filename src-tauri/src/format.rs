@@ -0,0 +1,219 @@
+//! Display formatting of usage values
+//!
+//! Formats reset times in the user's language and local timezone, either
+//! relative to now ("in 2h 5m") or as a wall-clock time ("today at 15:45"),
+//! so the frontend doesn't have to parse RFC 3339 timestamps itself.
+//!
+//! Costs are kept in USD and converted to the display [`Currency`] only when
+//! formatted, so a rate change never rewrites recorded history.
+
+use std::sync::{OnceLock, RwLock};
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, Language};
+
+/// How reset times are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetStyle {
+    /// Time left until the reset, e.g. "in 2h 5m"
+    #[default]
+    Relative,
+    /// Local date and time of the reset, e.g. "today at 15:45"
+    Absolute,
+}
+
+/// Currency costs are shown in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. "EUR"
+    pub code: String,
+    /// Units of this currency per US dollar
+    pub per_usd: f64,
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::new("USD", 1.0)
+    }
+}
+
+impl Currency {
+    /// Creates a currency with its rate against the US dollar
+    pub fn new(code: impl Into<String>, per_usd: f64) -> Self {
+        Self {
+            code: code.into().to_uppercase(),
+            per_usd,
+        }
+    }
+
+    /// Converts an amount in USD to this currency
+    pub fn convert(&self, usd: f64) -> f64 {
+        usd * self.per_usd
+    }
+
+    /// Formats an amount in USD in this currency, e.g. "€12.34"
+    pub fn format(&self, usd: f64) -> String {
+        let amount = self.convert(usd);
+        match self.code.as_str() {
+            "USD" => format!("${:.2}", amount),
+            "EUR" => format!("€{:.2}", amount),
+            "GBP" => format!("£{:.2}", amount),
+            "JPY" => format!("¥{:.0}", amount),
+            code => format!("{:.2} {}", amount, code),
+        }
+    }
+}
+
+fn current_currency() -> &'static RwLock<Currency> {
+    static CURRENT: OnceLock<RwLock<Currency>> = OnceLock::new();
+    CURRENT.get_or_init(|| RwLock::new(Currency::default()))
+}
+
+/// Sets the currency costs are shown in
+pub fn set_currency(currency: Currency) {
+    if let Ok(mut current) = current_currency().write() {
+        *current = currency;
+    }
+}
+
+/// Returns the currency costs are shown in
+pub fn currency() -> Currency {
+    current_currency()
+        .read()
+        .map(|c| c.clone())
+        .unwrap_or_default()
+}
+
+/// Formats an amount in USD in the display currency
+pub fn format_cost(usd: f64) -> String {
+    currency().format(usd)
+}
+
+/// Formats a reset time in the active language and the local timezone
+pub fn format_reset(resets_at: DateTime<Utc>, style: ResetStyle) -> String {
+    format_reset_in(resets_at, style, Utc::now(), &Local, i18n::language())
+}
+
+/// Formats a reset time relative to `now` in a given timezone and language
+pub fn format_reset_in<Tz: TimeZone>(
+    resets_at: DateTime<Utc>,
+    style: ResetStyle,
+    now: DateTime<Utc>,
+    timezone: &Tz,
+    language: Language,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let t = |key: &str, args: &[(&str, &str)]| i18n::t_in(language, key, args);
+
+    if resets_at <= now {
+        return t("reset.now", &[]);
+    }
+
+    match style {
+        ResetStyle::Relative => {
+            let left = resets_at - now;
+            let (days, hours, minutes) = (
+                left.num_days(),
+                left.num_hours() % 24,
+                left.num_minutes() % 60,
+            );
+            let span = if days > 0 {
+                format!("{}d {}h", days, hours)
+            } else if hours > 0 {
+                format!("{}h {}m", hours, minutes)
+            } else {
+                format!("{}m", minutes.max(1))
+            };
+            t("reset.relative", &[("span", &span)])
+        }
+        ResetStyle::Absolute => {
+            let local = resets_at.with_timezone(timezone);
+            let time = local.format(&t("format.time", &[])).to_string();
+            if local.date_naive() == now.with_timezone(timezone).date_naive() {
+                t("reset.absolute.today", &[("time", &time)])
+            } else {
+                let date = local.format(&t("format.date", &[])).to_string();
+                t("reset.absolute.date", &[("date", &date), ("time", &time)])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn now() -> DateTime<Utc> {
+        "2026-03-05T10:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_currency_format() {
+        assert_eq!(Currency::default().format(12.345), "$12.35");
+        assert_eq!(Currency::new("eur", 0.5).format(10.0), "€5.00");
+        assert_eq!(Currency::new("JPY", 150.0).format(2.0), "¥300");
+        assert_eq!(Currency::new("CHF", 0.9).format(10.0), "9.00 CHF");
+    }
+
+    #[test]
+    fn test_relative() {
+        let format = |minutes: i64| {
+            format_reset_in(
+                now() + chrono::Duration::minutes(minutes),
+                ResetStyle::Relative,
+                now(),
+                &Utc,
+                Language::En,
+            )
+        };
+
+        assert_eq!(format(0), "now");
+        assert_eq!(format(42), "in 42m");
+        assert_eq!(format(125), "in 2h 5m");
+        assert_eq!(format(3 * 24 * 60 + 60), "in 3d 1h");
+    }
+
+    #[test]
+    fn test_absolute_uses_timezone() {
+        let madrid = FixedOffset::east_opt(3600).unwrap();
+        let today = now() + chrono::Duration::hours(5);
+        let tomorrow = now() + chrono::Duration::hours(26);
+
+        assert_eq!(
+            format_reset_in(today, ResetStyle::Absolute, now(), &madrid, Language::Es),
+            "hoy a las 16:00"
+        );
+        assert_eq!(
+            format_reset_in(tomorrow, ResetStyle::Absolute, now(), &madrid, Language::Es),
+            "06/03 a las 13:00"
+        );
+        assert_eq!(
+            format_reset_in(today, ResetStyle::Absolute, now(), &Utc, Language::En),
+            "today at 3:00 PM"
+        );
+    }
+
+    #[test]
+    fn test_absolute_day_boundary_follows_timezone() {
+        // 23:30 UTC is already tomorrow one hour east
+        let resets_at: DateTime<Utc> = "2026-03-05T23:30:00Z".parse().unwrap();
+        let madrid = FixedOffset::east_opt(3600).unwrap();
+
+        assert_eq!(
+            format_reset_in(
+                resets_at,
+                ResetStyle::Absolute,
+                now(),
+                &madrid,
+                Language::En
+            ),
+            "Mar 6 at 12:30 AM"
+        );
+    }
+}
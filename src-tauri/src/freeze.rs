@@ -0,0 +1,258 @@
+//! Usage freeze actions
+//!
+//! When a provider's usage reaches the freeze level (100% unless changed),
+//! scripts and agents that use its API can be told to pause: a marker file
+//! lists the frozen providers while there are any, and a
+//! [`AppEvent::UsageFrozen`] event is published, on which the app runs the
+//! configured shell command. When usage drops again the provider is removed
+//! from the marker file, the file is deleted once none are left, and
+//! [`AppEvent::UsageUnfrozen`] runs the unfreeze command.
+//!
+//! Commands are only read from the config file, never set from the UI, and
+//! run like [`hooks`](crate::hooks), even while other hooks are off.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, AppEvent};
+use crate::providers::UsageSnapshot;
+
+/// Actions taken when a provider's usage runs out
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FreezeSettings {
+    /// Usage percentage at which a provider is frozen
+    pub percent: f64,
+    /// File that exists while any provider is frozen, listing them as JSON
+    pub marker_file: Option<PathBuf>,
+    /// Shell command run when a provider is frozen
+    pub command: Option<String>,
+    /// Shell command run when a frozen provider's usage drops again
+    pub unfreeze_command: Option<String>,
+}
+
+impl Default for FreezeSettings {
+    fn default() -> Self {
+        Self {
+            percent: 100.0,
+            marker_file: None,
+            command: None,
+            unfreeze_command: None,
+        }
+    }
+}
+
+impl FreezeSettings {
+    /// Whether any action is configured
+    pub fn is_enabled(&self) -> bool {
+        self.marker_file.is_some() || self.command.is_some() || self.unfreeze_command.is_some()
+    }
+}
+
+/// A frozen provider, as listed in the marker file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrozenProvider {
+    /// Highest window usage when it was frozen
+    pub percent: f64,
+    pub frozen_at: DateTime<Utc>,
+}
+
+/// Tracks which providers are frozen and keeps the marker file in sync
+pub struct UsageFreeze {
+    settings: FreezeSettings,
+    frozen: Mutex<BTreeMap<String, FrozenProvider>>,
+}
+
+impl UsageFreeze {
+    /// Creates a tracker with no provider frozen
+    ///
+    /// A marker file left by an earlier run is removed, so it only lists
+    /// providers frozen since the app started.
+    pub fn new(settings: FreezeSettings) -> Self {
+        if let Some(path) = &settings.marker_file {
+            if let Err(e) = remove_marker(path) {
+                tracing::warn!("{}", e);
+            }
+        }
+        Self {
+            settings,
+            frozen: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Checks a new snapshot, freezing or unfreezing its provider
+    pub fn update(&self, provider_id: &str, snapshot: &UsageSnapshot) {
+        let percent = snapshot.max_usage();
+        let mut frozen = self.frozen.lock().unwrap_or_else(|e| e.into_inner());
+
+        let event = match (
+            percent >= self.settings.percent,
+            frozen.contains_key(provider_id),
+        ) {
+            (true, false) => {
+                frozen.insert(
+                    provider_id.to_string(),
+                    FrozenProvider {
+                        percent,
+                        frozen_at: Utc::now(),
+                    },
+                );
+                AppEvent::UsageFrozen {
+                    provider_id: provider_id.to_string(),
+                    percent,
+                }
+            }
+            (false, true) => {
+                frozen.remove(provider_id);
+                AppEvent::UsageUnfrozen {
+                    provider_id: provider_id.to_string(),
+                    percent,
+                }
+            }
+            _ => return,
+        };
+
+        if let Some(path) = &self.settings.marker_file {
+            let result = if frozen.is_empty() {
+                remove_marker(path)
+            } else {
+                write_marker(path, &frozen)
+            };
+            if let Err(e) = result {
+                tracing::warn!("{}", e);
+            }
+        }
+        drop(frozen);
+
+        tracing::info!("Usage freeze: {:?}", event);
+        events::publish(event);
+    }
+
+    /// Providers frozen now
+    pub fn frozen(&self) -> BTreeMap<String, FrozenProvider> {
+        self.frozen
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+fn write_marker(path: &Path, frozen: &BTreeMap<String, FrozenProvider>) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(frozen)
+        .map_err(|e| format!("Failed to serialize frozen providers: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn remove_marker(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to delete {}: {}", path.display(), e)),
+    }
+}
+
+/// The configured command for a freeze event, with the environment it runs
+/// with: `GPTBAR_PROVIDER`, `GPTBAR_PERCENT` and `GPTBAR_FROZEN` (`1` or
+/// `0`)
+pub fn command_for(
+    settings: &FreezeSettings,
+    event: &AppEvent,
+) -> Option<(String, Vec<(&'static str, String)>)> {
+    let (command, provider_id, percent, frozen) = match event {
+        AppEvent::UsageFrozen {
+            provider_id,
+            percent,
+        } => (settings.command.as_ref()?, provider_id, percent, "1"),
+        AppEvent::UsageUnfrozen {
+            provider_id,
+            percent,
+        } => (
+            settings.unfreeze_command.as_ref()?,
+            provider_id,
+            percent,
+            "0",
+        ),
+        _ => return None,
+    };
+    Some((
+        command.clone(),
+        vec![
+            ("GPTBAR_PROVIDER", provider_id.clone()),
+            ("GPTBAR_PERCENT", format!("{:.1}", percent)),
+            ("GPTBAR_FROZEN", frozen.to_string()),
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::RateWindow;
+
+    fn snapshot(percent: f64) -> UsageSnapshot {
+        UsageSnapshot::new().with_primary(RateWindow::new(percent))
+    }
+
+    #[test]
+    fn test_marker_file_follows_frozen_providers() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("pause").join("frozen.json");
+        std::fs::create_dir_all(marker.parent().unwrap()).unwrap();
+        std::fs::write(&marker, "{}").unwrap();
+
+        let freeze = UsageFreeze::new(FreezeSettings {
+            marker_file: Some(marker.clone()),
+            ..Default::default()
+        });
+        // Left over from an earlier run
+        assert!(!marker.exists());
+
+        freeze.update("claude", &snapshot(99.0));
+        assert!(!marker.exists());
+
+        freeze.update("claude", &snapshot(100.0));
+        freeze.update("openai", &snapshot(120.0));
+        let listed: BTreeMap<String, FrozenProvider> =
+            serde_json::from_str(&std::fs::read_to_string(&marker).unwrap()).unwrap();
+        assert_eq!(listed.keys().collect::<Vec<_>>(), vec!["claude", "openai"]);
+
+        freeze.update("claude", &snapshot(3.0));
+        assert_eq!(freeze.frozen().keys().collect::<Vec<_>>(), vec!["openai"]);
+        assert!(marker.exists());
+
+        freeze.update("openai", &snapshot(0.0));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_command_for() {
+        let settings = FreezeSettings {
+            command: Some("touch ~/.pause-agents".to_string()),
+            ..Default::default()
+        };
+        let frozen = AppEvent::UsageFrozen {
+            provider_id: "claude".to_string(),
+            percent: 100.0,
+        };
+        let (command, env) = command_for(&settings, &frozen).unwrap();
+        assert_eq!(command, "touch ~/.pause-agents");
+        assert!(env.contains(&("GPTBAR_PROVIDER", "claude".to_string())));
+        assert!(env.contains(&("GPTBAR_PERCENT", "100.0".to_string())));
+
+        // No unfreeze command configured
+        let unfrozen = AppEvent::UsageUnfrozen {
+            provider_id: "claude".to_string(),
+            percent: 10.0,
+        };
+        assert!(command_for(&settings, &unfrozen).is_none());
+        assert!(command_for(&settings, &AppEvent::ConfigChanged).is_none());
+    }
+}
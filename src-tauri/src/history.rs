@@ -0,0 +1,794 @@
+//! Usage history store
+//!
+//! Persists every refreshed snapshot in a SQLite database next to the config
+//! file, so usage can be charted, exported (CSV or JSON) and served after
+//! the fact.
+//! Account identity is stripped before storing.
+//!
+//! Sent notifications are logged in the same database, so past alerts can be
+//! listed after the toast is gone.
+//!
+//! Today's and this week's peak, average and time spent above a threshold
+//! are computed from the recorded snapshots, for pacing feedback.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::agents::NotificationLevel;
+use crate::config::AppConfig;
+use crate::memory::MemoryUsage;
+use crate::providers::{UsageSnapshot, WindowSlot};
+
+/// Errors that can occur while reading or writing history
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    /// Database operation failed
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    /// Stored snapshot could not be (de)serialized
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    /// No location for the database file
+    #[error("Could not determine history path")]
+    NoPath,
+
+    /// Requested time range cannot be represented
+    #[error("Time range out of bounds")]
+    InvalidRange,
+}
+
+/// A recorded snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    /// Provider the snapshot belongs to
+    pub provider_id: String,
+    /// The snapshot as fetched
+    pub snapshot: UsageSnapshot,
+}
+
+/// A sent usage notification
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotificationRecord {
+    /// Provider the notification was about
+    pub provider_id: String,
+    /// Warning or critical
+    pub level: NotificationLevel,
+    /// Usage percentage of the window that triggered it
+    pub percent: f64,
+    /// Window that triggered it
+    pub window: Option<WindowSlot>,
+    /// When it was sent
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Time span selected for an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryRange {
+    /// Last 24 hours
+    Day,
+    /// Last 7 days
+    Week,
+    /// Last 30 days
+    Month,
+    /// Everything recorded
+    All,
+}
+
+impl HistoryRange {
+    /// Returns the start of the range relative to `now`
+    pub fn since(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            HistoryRange::Day => now - chrono::Duration::days(1),
+            HistoryRange::Week => now - chrono::Duration::days(7),
+            HistoryRange::Month => now - chrono::Duration::days(30),
+            HistoryRange::All => DateTime::<Utc>::UNIX_EPOCH,
+        }
+    }
+}
+
+/// File format for exported history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// One row per snapshot, one column pair per window
+    Csv,
+    /// Array of `HistoryEntry` objects
+    Json,
+}
+
+impl ExportFormat {
+    /// Returns the file extension (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    /// Renders entries in this format
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gptbar_lib::history::{ExportFormat, HistoryEntry};
+    /// use gptbar_lib::providers::{RateWindow, UsageSnapshot};
+    ///
+    /// let entry = HistoryEntry {
+    ///     provider_id: "claude".to_string(),
+    ///     snapshot: UsageSnapshot::new().with_primary(RateWindow::new(42.0)),
+    /// };
+    /// let csv = ExportFormat::Csv.render(&[entry]).unwrap();
+    /// assert!(csv.lines().nth(1).unwrap().contains(",claude,42,"));
+    /// ```
+    pub fn render(&self, entries: &[HistoryEntry]) -> Result<String, HistoryError> {
+        match self {
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(entries)?),
+            ExportFormat::Csv => Ok(to_csv(entries)),
+        }
+    }
+}
+
+fn to_csv(entries: &[HistoryEntry]) -> String {
+    let mut csv = String::from(
+        "recorded_at,provider_id,primary_percent,primary_resets_at,\
+         secondary_percent,secondary_resets_at,tertiary_percent,tertiary_resets_at\n",
+    );
+
+    for entry in entries {
+        let snapshot = &entry.snapshot;
+        let mut fields = vec![
+            snapshot.updated_at.to_rfc3339(),
+            csv_field(&entry.provider_id),
+        ];
+        for window in [&snapshot.primary, &snapshot.secondary, &snapshot.tertiary] {
+            fields.push(
+                window
+                    .as_ref()
+                    .map(|w| w.used_percent.to_string())
+                    .unwrap_or_default(),
+            );
+            fields.push(
+                window
+                    .as_ref()
+                    .and_then(|w| w.resets_at)
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default(),
+            );
+        }
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Quotes a field if it contains CSV delimiters
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Longest time a recorded snapshot is assumed to hold; longer gaps mean
+/// the app wasn't running
+const MAX_SAMPLE_SPAN_MINUTES: i64 = 60;
+
+/// Rows an in-memory store keeps per table, about a week of 5-minute
+/// refreshes of a few providers
+const IN_MEMORY_CAPACITY: usize = 10_000;
+
+/// Usage aggregates of one provider over a period
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct PeriodStats {
+    /// Highest window usage recorded, if anything was
+    pub max_percent: Option<f64>,
+    /// Average of the highest window usage, weighted by how long each
+    /// snapshot held
+    pub average_percent: Option<f64>,
+    /// Minutes spent at or above the threshold
+    pub minutes_above: i64,
+    /// Number of snapshots recorded in the period
+    pub samples: usize,
+}
+
+impl PeriodStats {
+    /// Computes stats from entries oldest first
+    ///
+    /// Each snapshot holds until the next one, the last until `until`, and
+    /// none for longer than an hour.
+    pub fn from_entries(entries: &[HistoryEntry], until: DateTime<Utc>, threshold: f64) -> Self {
+        let max_span = chrono::Duration::minutes(MAX_SAMPLE_SPAN_MINUTES);
+        let mut stats = Self {
+            samples: entries.len(),
+            ..Self::default()
+        };
+        let mut weighted_sum = 0.0;
+        let mut total_seconds = 0;
+
+        for (i, entry) in entries.iter().enumerate() {
+            let percent = entry.snapshot.max_usage();
+            let end = entries
+                .get(i + 1)
+                .map_or(until, |next| next.snapshot.updated_at);
+            let span = (end - entry.snapshot.updated_at).clamp(chrono::Duration::zero(), max_span);
+
+            stats.max_percent = Some(stats.max_percent.map_or(percent, |max| max.max(percent)));
+            weighted_sum += percent * span.num_seconds() as f64;
+            total_seconds += span.num_seconds();
+            if percent >= threshold {
+                stats.minutes_above += span.num_minutes();
+            }
+        }
+
+        stats.average_percent = if total_seconds > 0 {
+            Some(weighted_sum / total_seconds as f64)
+        } else {
+            // A single snapshot taken just now
+            stats.max_percent
+        };
+        stats
+    }
+}
+
+/// Today's and this week's usage aggregates of one provider
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UsageStats {
+    pub provider_id: String,
+    /// Usage percentage counted in `minutes_above`
+    pub threshold_percent: f64,
+    /// Since local midnight
+    pub today: PeriodStats,
+    /// Since Monday at local midnight
+    pub week: PeriodStats,
+}
+
+/// Start of the day containing `date` in a timezone, or the earliest time
+/// that day when midnight is skipped by a DST change
+fn start_of_day<Tz: TimeZone>(date: NaiveDate, timezone: &Tz) -> DateTime<Utc> {
+    (0..24)
+        .find_map(|hour| {
+            timezone
+                .from_local_datetime(&date.and_hms_opt(hour, 0, 0)?)
+                .earliest()
+        })
+        .map_or_else(
+            || date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+            |start| start.with_timezone(&Utc),
+        )
+}
+
+/// SQLite-backed store of past usage snapshots
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+    /// Most rows kept per table, dropping the oldest; unbounded when `None`
+    capacity: Option<usize>,
+}
+
+impl HistoryStore {
+    /// Opens the database at the default location, creating it if needed
+    pub fn open_default() -> Result<Self, HistoryError> {
+        Self::open(Self::default_path().ok_or(HistoryError::NoPath)?)
+    }
+
+    /// Opens (or creates) the database at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens a throwaway in-memory database
+    ///
+    /// It keeps only the newest rows, so it doesn't grow while the app runs
+    /// for weeks in demo mode or without a database file.
+    pub fn open_in_memory() -> Result<Self, HistoryError> {
+        Ok(Self::init(Connection::open_in_memory()?)?.with_capacity(IN_MEMORY_CAPACITY))
+    }
+
+    /// Keeps at most `rows` snapshots and `rows` notifications, dropping the
+    /// oldest
+    pub fn with_capacity(mut self, rows: usize) -> Self {
+        self.capacity = Some(rows);
+        self
+    }
+
+    /// Returns `history.db` in the config directory
+    pub fn default_path() -> Option<PathBuf> {
+        AppConfig::config_dir().map(|dir| dir.join("history.db"))
+    }
+
+    fn init(conn: Connection) -> Result<Self, HistoryError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage_history (
+                id INTEGER PRIMARY KEY,
+                provider_id TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                max_percent REAL NOT NULL,
+                snapshot TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_usage_history_provider_time
+                ON usage_history (provider_id, recorded_at);
+            CREATE TABLE IF NOT EXISTS notification_history (
+                id INTEGER PRIMARY KEY,
+                provider_id TEXT NOT NULL,
+                sent_at INTEGER NOT NULL,
+                record TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_notification_history_time
+                ON notification_history (sent_at);",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            capacity: None,
+        })
+    }
+
+    /// Drops the oldest rows of `table` beyond the capacity
+    ///
+    /// Rows are only ever appended, so their IDs are consecutive.
+    fn trim(&self, conn: &Connection, table: &str) -> Result<(), HistoryError> {
+        if let Some(capacity) = self.capacity {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE id <= (SELECT MAX(id) FROM {table}) - ?1"),
+                params![capacity as i64],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Rows kept and the size of the database
+    pub fn memory(&self) -> Result<MemoryUsage, HistoryError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let entries: i64 = conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM usage_history)
+                  + (SELECT COUNT(*) FROM notification_history)",
+            [],
+            |row| row.get(0),
+        )?;
+        let pages: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(MemoryUsage {
+            entries: entries as usize,
+            bytes: (pages * page_size) as usize,
+        })
+    }
+
+    /// Records a snapshot for a provider
+    pub fn record(&self, provider_id: &str, snapshot: &UsageSnapshot) -> Result<(), HistoryError> {
+        let mut snapshot = snapshot.clone();
+        snapshot.identity = None;
+
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT INTO usage_history (provider_id, recorded_at, max_percent, snapshot)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                provider_id,
+                snapshot.updated_at.timestamp(),
+                snapshot.max_usage(),
+                serde_json::to_string(&snapshot)?,
+            ],
+        )?;
+        self.trim(&conn, "usage_history")
+    }
+
+    /// Returns entries recorded between `since` and `until` (inclusive), oldest first
+    ///
+    /// Pass `None` as `provider_id` to include every provider.
+    pub fn query(
+        &self,
+        provider_id: Option<&str>,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(
+            "SELECT provider_id, snapshot FROM usage_history
+             WHERE (?1 IS NULL OR provider_id = ?1)
+               AND recorded_at BETWEEN ?2 AND ?3
+             ORDER BY recorded_at, id",
+        )?;
+
+        let rows = stmt.query_map(
+            params![provider_id, since.timestamp(), until.timestamp()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (provider_id, json) = row?;
+            entries.push(HistoryEntry {
+                provider_id,
+                snapshot: serde_json::from_str(&json)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Logs a sent notification
+    pub fn record_notification(&self, record: &NotificationRecord) -> Result<(), HistoryError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT INTO notification_history (provider_id, sent_at, record)
+             VALUES (?1, ?2, ?3)",
+            params![
+                record.provider_id,
+                record.sent_at.timestamp(),
+                serde_json::to_string(record)?,
+            ],
+        )?;
+        self.trim(&conn, "notification_history")
+    }
+
+    /// Returns notifications sent between `since` and `until` (inclusive), newest first
+    pub fn notifications(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<NotificationRecord>, HistoryError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(
+            "SELECT record FROM notification_history
+             WHERE sent_at BETWEEN ?1 AND ?2
+             ORDER BY sent_at DESC, id DESC",
+        )?;
+
+        let rows = stmt.query_map(params![since.timestamp(), until.timestamp()], |row| {
+            row.get::<_, String>(0)
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(serde_json::from_str(&row?)?);
+        }
+        Ok(records)
+    }
+
+    /// Deletes snapshots recorded and notifications sent before `before`
+    ///
+    /// Returns how many rows were deleted. The file only shrinks after a
+    /// [`HistoryStore::vacuum`].
+    pub fn prune(&self, before: DateTime<Utc>) -> Result<usize, HistoryError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let snapshots = conn.execute(
+            "DELETE FROM usage_history WHERE recorded_at < ?1",
+            params![before.timestamp()],
+        )?;
+        let notifications = conn.execute(
+            "DELETE FROM notification_history WHERE sent_at < ?1",
+            params![before.timestamp()],
+        )?;
+        Ok(snapshots + notifications)
+    }
+
+    /// Rebuilds the database file without the space of deleted rows
+    pub fn vacuum(&self) -> Result<(), HistoryError> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Returns entries from the last `hours` hours
+    pub fn recent(
+        &self,
+        provider_id: Option<&str>,
+        hours: i64,
+    ) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let now = Utc::now();
+        let from = chrono::Duration::try_hours(hours)
+            .and_then(|window| now.checked_sub_signed(window))
+            .ok_or(HistoryError::InvalidRange)?;
+        self.query(provider_id, from, now)
+    }
+
+    /// Returns today's and this week's aggregates for a provider, in the
+    /// local timezone
+    pub fn usage_stats(
+        &self,
+        provider_id: &str,
+        threshold_percent: f64,
+    ) -> Result<UsageStats, HistoryError> {
+        self.usage_stats_in(provider_id, threshold_percent, Utc::now(), &Local)
+    }
+
+    /// Returns the aggregates of the day and week containing `now` in a
+    /// given timezone
+    pub fn usage_stats_in<Tz: TimeZone>(
+        &self,
+        provider_id: &str,
+        threshold_percent: f64,
+        now: DateTime<Utc>,
+        timezone: &Tz,
+    ) -> Result<UsageStats, HistoryError> {
+        let today = now.with_timezone(timezone).date_naive();
+        let monday = today - chrono::Days::new(today.weekday().num_days_from_monday().into());
+        let day_start = start_of_day(today, timezone);
+
+        let week = self.query(Some(provider_id), start_of_day(monday, timezone), now)?;
+        let first_today = week.partition_point(|e| e.snapshot.updated_at < day_start);
+
+        Ok(UsageStats {
+            provider_id: provider_id.to_string(),
+            threshold_percent,
+            today: PeriodStats::from_entries(&week[first_today..], now, threshold_percent),
+            week: PeriodStats::from_entries(&week, now, threshold_percent),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{IdentitySnapshot, RateWindow};
+    use chrono::TimeZone;
+
+    fn from_timestamp(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(seconds, 0).unwrap()
+    }
+
+    fn snapshot_at(seconds: i64, percent: f64) -> UsageSnapshot {
+        let mut snapshot = UsageSnapshot::new().with_primary(RateWindow::new(percent));
+        snapshot.updated_at = from_timestamp(seconds);
+        snapshot
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.record("claude", &snapshot_at(1_000, 10.0)).unwrap();
+        store.record("openai", &snapshot_at(1_500, 20.0)).unwrap();
+        store.record("claude", &snapshot_at(2_000, 30.0)).unwrap();
+
+        let all = store
+            .query(None, from_timestamp(0), from_timestamp(3_000))
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[1].provider_id, "openai");
+
+        let claude = store
+            .query(Some("claude"), from_timestamp(1_001), from_timestamp(3_000))
+            .unwrap();
+        assert_eq!(claude.len(), 1);
+        assert_eq!(claude[0].snapshot.max_usage(), 30.0);
+    }
+
+    #[test]
+    fn test_record_strips_identity() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let mut snapshot = snapshot_at(1_000, 10.0);
+        snapshot.identity = Some(IdentitySnapshot::default());
+        store.record("claude", &snapshot).unwrap();
+
+        let entries = store
+            .query(Some("claude"), from_timestamp(0), from_timestamp(2_000))
+            .unwrap();
+        assert!(entries[0].snapshot.identity.is_none());
+    }
+
+    #[test]
+    fn test_notification_log() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let record = |seconds: i64, level: NotificationLevel| NotificationRecord {
+            provider_id: "claude".to_string(),
+            level,
+            percent: 85.0,
+            window: Some(WindowSlot::Secondary),
+            sent_at: from_timestamp(seconds),
+        };
+        store
+            .record_notification(&record(1_000, NotificationLevel::Warning))
+            .unwrap();
+        store
+            .record_notification(&record(2_000, NotificationLevel::Critical))
+            .unwrap();
+
+        let all = store
+            .notifications(from_timestamp(0), from_timestamp(3_000))
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![
+                record(2_000, NotificationLevel::Critical),
+                record(1_000, NotificationLevel::Warning)
+            ]
+        );
+
+        let recent = store
+            .notifications(from_timestamp(1_500), from_timestamp(3_000))
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut snapshot = snapshot_at(0, 12.5);
+        snapshot.secondary =
+            Some(RateWindow::new(40.0).with_resets_at(from_timestamp(3_600)));
+        let entries = vec![HistoryEntry {
+            provider_id: "claude".to_string(),
+            snapshot,
+        }];
+
+        let csv = ExportFormat::Csv.render(&entries).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].split(',').count(), 8);
+        assert_eq!(
+            lines[1],
+            "1970-01-01T00:00:00+00:00,claude,12.5,,40,1970-01-01T01:00:00+00:00,,"
+        );
+    }
+
+    #[test]
+    fn test_export_json_round_trips() {
+        let entries = vec![HistoryEntry {
+            provider_id: "openai".to_string(),
+            snapshot: snapshot_at(1_000, 5.0),
+        }];
+        let json = ExportFormat::Json.render(&entries).unwrap();
+        let parsed: Vec<HistoryEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, entries);
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("claude"), "claude");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_range_since() {
+        let now = from_timestamp(10 * 86_400);
+        assert_eq!(HistoryRange::Day.since(now), from_timestamp(9 * 86_400));
+        assert_eq!(HistoryRange::Week.since(now), from_timestamp(3 * 86_400));
+        assert_eq!(HistoryRange::All.since(now), from_timestamp(0));
+    }
+
+    #[test]
+    fn test_period_stats() {
+        // 30 minutes at 50%, 20 at 90%, then 10 at 60% until now
+        let entries: Vec<_> = [(0, 50.0), (1_800, 90.0), (3_000, 60.0)]
+            .into_iter()
+            .map(|(seconds, percent)| HistoryEntry {
+                provider_id: "claude".to_string(),
+                snapshot: snapshot_at(seconds, percent),
+            })
+            .collect();
+
+        let stats = PeriodStats::from_entries(&entries, from_timestamp(3_600), 80.0);
+        assert_eq!(stats.max_percent, Some(90.0));
+        assert_eq!(stats.average_percent, Some(65.0));
+        assert_eq!(stats.minutes_above, 20);
+        assert_eq!(stats.samples, 3);
+
+        assert_eq!(
+            PeriodStats::from_entries(&[], from_timestamp(3_600), 80.0),
+            PeriodStats::default()
+        );
+    }
+
+    #[test]
+    fn test_period_stats_caps_gaps() {
+        // The app was closed overnight at 95%
+        let entries = [HistoryEntry {
+            provider_id: "claude".to_string(),
+            snapshot: snapshot_at(0, 95.0),
+        }];
+        let stats = PeriodStats::from_entries(&entries, from_timestamp(8 * 3_600), 80.0);
+        assert_eq!(stats.minutes_above, 60);
+    }
+
+    #[test]
+    fn test_usage_stats_splits_day_and_week() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let madrid = chrono::FixedOffset::east_opt(3_600).unwrap();
+        // Wednesday 2026-03-04 12:00 in Madrid
+        let now: DateTime<Utc> = "2026-03-04T11:00:00Z".parse().unwrap();
+        let at = |time: &str, percent: f64| {
+            let mut snapshot = UsageSnapshot::new().with_primary(RateWindow::new(percent));
+            snapshot.updated_at = time.parse().unwrap();
+            snapshot
+        };
+
+        for (provider, time, percent) in [
+            // Sunday, last week
+            ("claude", "2026-03-01T12:00:00Z", 99.0),
+            // Monday
+            ("claude", "2026-03-02T09:00:00Z", 85.0),
+            // 23:30 UTC on Tuesday is already Wednesday in Madrid
+            ("claude", "2026-03-03T23:30:00Z", 40.0),
+            ("openai", "2026-03-04T10:00:00Z", 100.0),
+        ] {
+            store.record(provider, &at(time, percent)).unwrap();
+        }
+
+        let stats = store.usage_stats_in("claude", 80.0, now, &madrid).unwrap();
+        assert_eq!(stats.today.samples, 1);
+        assert_eq!(stats.today.max_percent, Some(40.0));
+        assert_eq!(stats.week.samples, 2);
+        assert_eq!(stats.week.max_percent, Some(85.0));
+        assert_eq!(stats.week.minutes_above, 60);
+    }
+
+    #[test]
+    fn test_capacity_keeps_newest_rows() {
+        let store = HistoryStore::open_in_memory().unwrap().with_capacity(2);
+        for (seconds, percent) in [(1_000, 10.0), (2_000, 20.0), (3_000, 30.0)] {
+            store
+                .record("claude", &snapshot_at(seconds, percent))
+                .unwrap();
+        }
+
+        let kept = store
+            .query(None, from_timestamp(0), from_timestamp(4_000))
+            .unwrap();
+        let percents: Vec<f64> = kept.iter().map(|e| e.snapshot.max_usage()).collect();
+        assert_eq!(percents, vec![20.0, 30.0]);
+        assert_eq!(store.memory().unwrap().entries, 2);
+    }
+
+    #[test]
+    fn test_prune_and_vacuum() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.record("claude", &snapshot_at(1_000, 10.0)).unwrap();
+        store.record("claude", &snapshot_at(3_000, 30.0)).unwrap();
+        store
+            .record_notification(&NotificationRecord {
+                provider_id: "claude".to_string(),
+                level: NotificationLevel::Warning,
+                percent: 80.0,
+                window: None,
+                sent_at: from_timestamp(1_500),
+            })
+            .unwrap();
+
+        assert_eq!(store.prune(from_timestamp(2_000)).unwrap(), 2);
+        store.vacuum().unwrap();
+        let kept = store
+            .query(None, from_timestamp(0), from_timestamp(4_000))
+            .unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].snapshot.max_usage(), 30.0);
+        assert!(store
+            .notifications(from_timestamp(0), from_timestamp(4_000))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_history_persists_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+
+        HistoryStore::open(&path)
+            .unwrap()
+            .record("gemini", &UsageSnapshot::new())
+            .unwrap();
+
+        let reopened = HistoryStore::open(&path).unwrap();
+        assert_eq!(reopened.recent(Some("gemini"), 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_recent_rejects_out_of_range_window() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        assert!(matches!(
+            store.recent(None, i64::MAX),
+            Err(HistoryError::InvalidRange)
+        ));
+        assert!(matches!(
+            store.recent(None, 10_000_000_000),
+            Err(HistoryError::InvalidRange)
+        ));
+    }
+}
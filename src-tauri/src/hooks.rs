@@ -0,0 +1,446 @@
+//! Scriptable hooks
+//!
+//! Hooks are commands from the config file run on events: a usage
+//! threshold crossed, a rate window reset, credentials lost, a new budget
+//! period, or a provider frozen or unfrozen (see [`freeze`](crate::freeze)).
+//! They are off until `hooks.enabled` is set.
+//!
+//! Each command runs through `sh -c` (`cmd /C` on Windows) with the event as
+//! JSON on stdin. It gets a cleared environment apart from the basics a
+//! shell needs plus `GPTBAR_EVENT` and `GPTBAR_PROVIDER`, runs in the temp
+//! folder, and is killed when it outlives the timeout.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::agents::NotificationLevel;
+use crate::events::{self, AppEvent};
+use crate::providers::{UsageSnapshot, WindowSlot};
+
+/// Variables passed on from GPTBar's environment
+const INHERITED_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "TMPDIR",
+    "USERPROFILE",
+    "SystemRoot",
+    "TEMP",
+    "TMP",
+];
+
+/// Longest command output kept for the log
+const MAX_LOGGED_OUTPUT: usize = 500;
+
+/// A reset time that moves by less than this is the same window
+const RESET_JITTER: chrono::Duration = chrono::Duration::minutes(1);
+
+/// Events hooks can run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A warning or critical notification was sent
+    ThresholdCrossed,
+    /// A rate window started over
+    WindowReset,
+    /// A provider's credentials stopped working
+    AuthLost,
+    /// A provider entered a new budget period
+    BudgetPeriodStarted,
+    /// A provider's usage reached the freeze level
+    UsageFrozen,
+    /// A frozen provider's usage dropped again
+    UsageUnfrozen,
+}
+
+impl HookEvent {
+    /// The hook event for an app event and the provider it is about, if any
+    pub fn of(event: &AppEvent) -> Option<(Self, &str)> {
+        match event {
+            AppEvent::NotificationSent(sent)
+                if matches!(
+                    sent.level,
+                    NotificationLevel::Warning | NotificationLevel::Critical
+                ) =>
+            {
+                Some((Self::ThresholdCrossed, &sent.provider_id))
+            }
+            AppEvent::WindowReset { provider_id, .. } => Some((Self::WindowReset, provider_id)),
+            AppEvent::AuthStateChanged {
+                provider_id,
+                previous,
+                available: false,
+                ..
+            } if previous.is_usable() => Some((Self::AuthLost, provider_id)),
+            AppEvent::BudgetPeriodStarted(rollover) => {
+                Some((Self::BudgetPeriodStarted, &rollover.provider_id))
+            }
+            AppEvent::UsageFrozen { provider_id, .. } => Some((Self::UsageFrozen, provider_id)),
+            AppEvent::UsageUnfrozen { provider_id, .. } => Some((Self::UsageUnfrozen, provider_id)),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::ThresholdCrossed => "threshold_crossed",
+            Self::WindowReset => "window_reset",
+            Self::AuthLost => "auth_lost",
+            Self::BudgetPeriodStarted => "budget_period_started",
+            Self::UsageFrozen => "usage_frozen",
+            Self::UsageUnfrozen => "usage_unfrozen",
+        }
+    }
+}
+
+/// A command run on an event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Hook {
+    /// Event the command runs on
+    pub on: HookEvent,
+    /// Shell command
+    pub command: String,
+    /// Only run for this provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+/// Hook settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HooksSettings {
+    /// Whether hooks run at all
+    pub enabled: bool,
+    /// Seconds a command may run before it is killed
+    pub timeout_secs: u64,
+    pub hooks: Vec<Hook>,
+}
+
+impl Default for HooksSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: 10,
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl HooksSettings {
+    /// The hooks to run for `event`
+    pub fn matching<'a>(&'a self, event: &AppEvent) -> Vec<&'a Hook> {
+        let Some((on, provider_id)) = HookEvent::of(event).filter(|_| self.enabled) else {
+            return Vec::new();
+        };
+        self.hooks
+            .iter()
+            .filter(|hook| hook.on == on)
+            .filter(|hook| hook.provider.as_deref().is_none_or(|p| p == provider_id))
+            .collect()
+    }
+
+    /// Runs the hooks for `event`, each on its own task
+    pub fn dispatch(&self, event: &AppEvent) {
+        let hooks = self.matching(event);
+        if hooks.is_empty() {
+            return;
+        }
+        let Some((on, provider_id)) = HookEvent::of(event) else {
+            return;
+        };
+        let payload = match serde_json::to_string(event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Failed to serialize hook payload: {}", e);
+                return;
+            }
+        };
+        let env = vec![
+            ("GPTBAR_EVENT", on.as_str().to_string()),
+            ("GPTBAR_PROVIDER", provider_id.to_string()),
+        ];
+        for hook in hooks {
+            self.spawn(hook.command.clone(), payload.clone(), env.clone());
+        }
+    }
+
+    /// Runs `command` on its own task with the hooks' timeout, logging the
+    /// outcome
+    pub fn spawn(&self, command: String, stdin: String, env: Vec<(&'static str, String)>) {
+        let timeout = Duration::from_secs(self.timeout_secs.max(1));
+        tokio::spawn(async move {
+            match run_command(&command, &stdin, env, timeout).await {
+                Ok(_) => tracing::debug!("Hook `{}` succeeded", command),
+                Err(e) => tracing::warn!("Hook `{}` failed: {}", command, e),
+            }
+        });
+    }
+}
+
+/// Runs `command` through the system shell with `stdin` as its input and a
+/// cleared environment plus `env`, killing it after `timeout`
+///
+/// Returns the command's output on success.
+pub async fn run_command(
+    command: &str,
+    stdin: &str,
+    env: Vec<(&'static str, String)>,
+    timeout: Duration,
+) -> Result<String, String> {
+    let mut process = if cfg!(target_os = "windows") {
+        let mut process = tokio::process::Command::new("cmd");
+        process.args(["/C", command]);
+        process
+    } else {
+        let mut process = tokio::process::Command::new("sh");
+        process.args(["-c", command]);
+        process
+    };
+    process
+        .env_clear()
+        .envs(
+            INHERITED_ENV
+                .iter()
+                .filter_map(|name| std::env::var_os(name).map(|value| (*name, value))),
+        )
+        .envs(env)
+        .current_dir(std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    {
+        // CREATE_NO_WINDOW, so hooks don't flash a console
+        process.creation_flags(0x0800_0000);
+    }
+
+    let mut child = process
+        .spawn()
+        .map_err(|e| format!("Failed to start: {}", e))?;
+    let input = child.stdin.take();
+    let write = async move {
+        if let Some(mut input) = input {
+            // A command that doesn't read stdin may close it early
+            let _ = input.write_all(stdin.as_bytes()).await;
+        }
+    };
+
+    // The write counts towards the timeout too, since a command that never
+    // reads a payload larger than the pipe holds blocks it; dropping the
+    // child on timeout kills it
+    let (_, output) = tokio::time::timeout(timeout, async {
+        tokio::join!(write, child.wait_with_output())
+    })
+    .await
+    .map_err(|_| format!("Killed after {}s", timeout.as_secs()))?;
+    let output = output.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Exited with {:?}: {}",
+            output.status.code(),
+            truncate(stderr.trim())
+        ));
+    }
+    Ok(truncate(String::from_utf8_lossy(&output.stdout).trim()).to_string())
+}
+
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_LOGGED_OUTPUT) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Detects rate windows that started over, from their reset times
+///
+/// A window resets when a new snapshot's reset time is later than the
+/// previous one and that one has passed. Windows without a reset time are
+/// never reported.
+#[derive(Debug, Default)]
+pub struct ResetDetector {
+    resets: Mutex<HashMap<(String, WindowSlot), DateTime<Utc>>>,
+}
+
+impl ResetDetector {
+    /// Creates a detector that hasn't seen any window yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a snapshot, returning the windows that reset since the last
+    pub fn update(
+        &self,
+        provider_id: &str,
+        snapshot: &UsageSnapshot,
+        now: DateTime<Utc>,
+    ) -> Vec<WindowSlot> {
+        let mut resets = self.resets.lock().unwrap_or_else(|e| e.into_inner());
+        let mut reset = Vec::new();
+        for (slot, window) in snapshot.windows() {
+            let Some(resets_at) = window.resets_at else {
+                continue;
+            };
+            let previous = resets.insert((provider_id.to_string(), slot), resets_at);
+            if previous.is_some_and(|previous| {
+                resets_at - previous > RESET_JITTER && now + RESET_JITTER >= previous
+            }) {
+                reset.push(slot);
+            }
+        }
+        reset
+    }
+
+    /// Records a snapshot and publishes [`AppEvent::WindowReset`] for each
+    /// window that reset
+    pub fn publish(&self, provider_id: &str, snapshot: &UsageSnapshot) {
+        for window in self.update(provider_id, snapshot, Utc::now()) {
+            events::publish(AppEvent::WindowReset {
+                provider_id: provider_id.to_string(),
+                window,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::NotificationEvent;
+    use crate::providers::{AuthState, RateWindow};
+
+    fn settings() -> HooksSettings {
+        serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "hooks": [
+                { "on": "auth_lost", "command": "notify-send lost" },
+                { "on": "threshold_crossed", "command": "echo", "provider": "openai" }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn notification(provider_id: &str, level: NotificationLevel) -> AppEvent {
+        AppEvent::NotificationSent(NotificationEvent {
+            provider_id: provider_id.to_string(),
+            title: String::new(),
+            message: String::new(),
+            level,
+            window: WindowSlot::Primary,
+        })
+    }
+
+    #[test]
+    fn test_matching() {
+        let settings = settings();
+        assert_eq!(settings.timeout_secs, 10);
+
+        let lost = AppEvent::AuthStateChanged {
+            provider_id: "claude".to_string(),
+            state: AuthState::Expired,
+            previous: AuthState::Valid { expires_at: None },
+            available: false,
+        };
+        assert_eq!(settings.matching(&lost).len(), 1);
+        let restored = AppEvent::AuthStateChanged {
+            provider_id: "claude".to_string(),
+            state: AuthState::Valid { expires_at: None },
+            previous: AuthState::Expired,
+            available: true,
+        };
+        assert!(settings.matching(&restored).is_empty());
+        // Only when it was usable before
+        let still_missing = AppEvent::AuthStateChanged {
+            provider_id: "claude".to_string(),
+            state: AuthState::Revoked,
+            previous: AuthState::Missing,
+            available: false,
+        };
+        assert!(settings.matching(&still_missing).is_empty());
+
+        // Only for the hook's provider, and not for pace alerts
+        assert_eq!(
+            settings
+                .matching(&notification("openai", NotificationLevel::Critical))
+                .len(),
+            1
+        );
+        assert!(settings
+            .matching(&notification("claude", NotificationLevel::Critical))
+            .is_empty());
+        assert!(settings
+            .matching(&notification("openai", NotificationLevel::AheadOfPace))
+            .is_empty());
+
+        // Off by default
+        let disabled = HooksSettings {
+            enabled: false,
+            ..settings
+        };
+        assert!(disabled.matching(&lost).is_empty());
+    }
+
+    #[test]
+    fn test_reset_detector() {
+        let detector = ResetDetector::new();
+        let now = Utc::now();
+        let snapshot = |resets_at| {
+            UsageSnapshot::new()
+                .with_primary(RateWindow::new(50.0).with_resets_at(resets_at))
+                .with_secondary(RateWindow::new(10.0))
+        };
+
+        let first = now + chrono::Duration::minutes(30);
+        assert!(detector.update("claude", &snapshot(first), now).is_empty());
+        // Same window, reset time moved slightly
+        let jittered = first + chrono::Duration::seconds(20);
+        assert!(detector
+            .update("claude", &snapshot(jittered), now)
+            .is_empty());
+
+        let later = now + chrono::Duration::minutes(31);
+        let next = first + chrono::Duration::hours(5);
+        assert_eq!(
+            detector.update("claude", &snapshot(next), later),
+            vec![WindowSlot::Primary]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command_passes_stdin_and_env() {
+        let output = run_command(
+            "cat; echo \" $GPTBAR_EVENT\"; echo \"$CARGO_MANIFEST_DIR\"",
+            "{\"type\":\"auth_lost\"}",
+            vec![("GPTBAR_EVENT", "auth_lost".to_string())],
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        // Cargo's variables are not passed on
+        assert_eq!(output, "{\"type\":\"auth_lost\"} auth_lost");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command_times_out() {
+        let result = run_command("sleep 5", "", Vec::new(), Duration::from_millis(200)).await;
+        assert!(result.unwrap_err().starts_with("Killed after"));
+
+        // A payload the command never reads can't outlast the timeout
+        let payload = "x".repeat(1024 * 1024);
+        let result = run_command("sleep 5", &payload, Vec::new(), Duration::from_millis(200)).await;
+        assert!(result.unwrap_err().starts_with("Killed after"));
+
+        let result = run_command("exit 3", "", Vec::new(), Duration::from_secs(5)).await;
+        assert!(result.unwrap_err().starts_with("Exited with Some(3)"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Internationalization for user-facing strings
+//!
+//! Strings generated in Rust (notifications, window descriptions, error
+//! messages) are looked up by key in the locale files under `locales/`.
+//! Missing keys fall back to English, then to the key itself.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Supported UI languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// English
+    #[default]
+    En,
+    /// Spanish
+    Es,
+}
+
+impl Language {
+    /// Returns all supported languages
+    pub fn all() -> &'static [Language] {
+        &[Self::En, Self::Es]
+    }
+
+    /// Returns the language code (e.g., "en")
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Es => "es",
+        }
+    }
+
+    /// Parses a locale string such as "es", "es_ES.UTF-8" or "es-MX"
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let lang = locale
+            .split(['_', '-', '.'])
+            .next()?
+            .to_lowercase();
+        Self::all().iter().copied().find(|l| l.code() == lang)
+    }
+
+    /// Detects the language from the environment, defaulting to English
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find_map(|v| Self::from_locale(&v))
+            .unwrap_or_default()
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Self::En => include_str!("../locales/en.json"),
+            Self::Es => include_str!("../locales/es.json"),
+        }
+    }
+}
+
+type Catalog = HashMap<String, String>;
+
+fn catalogs() -> &'static HashMap<Language, Catalog> {
+    static CATALOGS: OnceLock<HashMap<Language, Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        Language::all()
+            .iter()
+            .map(|lang| {
+                let catalog = serde_json::from_str(lang.source()).unwrap_or_else(|e| {
+                    tracing::error!("Invalid locale file for {}: {}", lang.code(), e);
+                    HashMap::new()
+                });
+                (*lang, catalog)
+            })
+            .collect()
+    })
+}
+
+fn current() -> &'static RwLock<Language> {
+    static CURRENT: OnceLock<RwLock<Language>> = OnceLock::new();
+    CURRENT.get_or_init(|| RwLock::new(Language::detect()))
+}
+
+/// Sets the active language
+pub fn set_language(language: Language) {
+    if let Ok(mut current) = current().write() {
+        *current = language;
+    }
+}
+
+/// Returns the active language
+pub fn language() -> Language {
+    current().read().map(|l| *l).unwrap_or_default()
+}
+
+/// Translates a key into the active language
+pub fn t(key: &str) -> String {
+    lookup(language(), key)
+}
+
+/// Translates a key and substitutes `{name}` placeholders
+pub fn t_args(key: &str, args: &[(&str, &str)]) -> String {
+    t_in(language(), key, args)
+}
+
+/// Translates a key into a specific language with placeholder substitution
+///
+/// # Examples
+///
+/// ```
+/// use gptbar_lib::i18n::{self, Language};
+///
+/// assert_eq!(
+///     i18n::t_in(Language::Es, "notification.body", &[("percent", "85.0")]),
+///     "El uso está al 85.0%"
+/// );
+/// ```
+pub fn t_in(language: Language, key: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(lookup(language, key), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// Whether the English catalog, which every language falls back to, has a key
+pub fn has(key: &str) -> bool {
+    catalogs()
+        .get(&Language::En)
+        .is_some_and(|c| c.contains_key(key))
+}
+
+fn lookup(language: Language, key: &str) -> String {
+    let catalogs = catalogs();
+    catalogs
+        .get(&language)
+        .and_then(|c| c.get(key))
+        .or_else(|| catalogs.get(&Language::En).and_then(|c| c.get(key)))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_locale() {
+        assert_eq!(Language::from_locale("es_ES.UTF-8"), Some(Language::Es));
+        assert_eq!(Language::from_locale("en-US"), Some(Language::En));
+        assert_eq!(Language::from_locale("fr_FR"), None);
+    }
+
+    #[test]
+    fn test_locales_have_same_keys() {
+        let catalogs = catalogs();
+        let en = &catalogs[&Language::En];
+        for lang in Language::all() {
+            let catalog = &catalogs[lang];
+            for key in en.keys() {
+                assert!(catalog.contains_key(key), "{} missing {}", lang.code(), key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_translation_with_args() {
+        assert_eq!(
+            t_in(Language::En, "notification.warning.title", &[("provider", "Claude")]),
+            "Claude Usage Warning"
+        );
+    }
+
+    #[test]
+    fn test_missing_key_falls_back_to_key() {
+        assert_eq!(t_in(Language::Es, "does.not.exist", &[]), "does.not.exist");
+        assert!(!has("does.not.exist"));
+        assert!(has("notification.body"));
+    }
+}
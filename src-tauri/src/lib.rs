@@ -5,11 +5,54 @@
 //!
 //! ## Features
 //!
-//! - Monitor multiple AI providers (Claude, OpenAI, Gemini, Codex)
+//! - Monitor multiple AI providers (Claude, OpenAI, Gemini, Gemini Code
+//!   Assist, Codex, Together AI, ElevenLabs, Windsurf)
+//! - Named provider profiles ("Work", "Personal") to switch which accounts
+//!   are fetched and shown
 //! - System tray integration with visual usage indicators
+//! - Optional usage percentage next to the macOS menu-bar icon
+//! - Usage badge drawn into the tray icon on Windows
+//! - Tray icon variants for light and dark menu bars and taskbars
+//! - Optional usage arc rendered as the tray icon
+//! - Blinking tray icon while any provider is critical, until clicked
+//! - Tray dot and count of providers needing attention: refused
+//!   credentials, critical usage or failing fetches
+//! - Marker file and shell commands when a provider's usage runs out, so
+//!   scripts using its API can pause
+//! - Opt-in hooks running commands on threshold, reset, auth and budget
+//!   events, with the event as JSON on stdin
+//! - Resizable popup that closes on Escape and can be pinned open
+//! - Translucent popup with acrylic or mica on Windows and vibrancy on macOS
 //! - Notifications when approaching usage limits
+//! - Costs shown in another currency at a fixed or daily ECB exchange rate
+//! - Spend budgets per calendar month, billing cycle or week, with a
+//!   "new billing period" event on rollover
+//! - English and Spanish localization of notifications and messages
+//! - OS reduce motion, high contrast and text size preferences, and text
+//!   severity levels next to the usage colors
 //! - Secure credential storage (Windows Credential Manager, macOS Keychain, Linux Secret Service)
+//! - Read-only viewer mode for shared machines, without login or API keys
 //! - Background refresh with configurable intervals
+//! - Crash reports of panics, offered as a GitHub issue on the next launch
+//! - Opt-in check for a newer GitHub release, with its changelog
+//! - Opt-in anonymous telemetry of feature use and error categories
+//! - Nightly pruning and vacuuming of the history database, keeping 90 days
+//!   by default
+//! - Memory report of each subsystem, with bounded snapshot, notification,
+//!   log and icon caches for long-running sessions
+//! - Optional JSON log lines with provider and agent IDs, for external log
+//!   pipelines
+//! - Sanitized diagnostic bundles (logs, config, provider and agent status)
+//!   for bug reports
+//! - Local socket / named pipe for shell prompts and status bars
+//! - `gptbar://` deep links to show the popup, refresh, or open a provider
+//! - `waybar` / `polybar` subcommands for trayless desktops
+//! - `tui` subcommand with gauges and history sparklines for terminals
+//! - Usage publishing to MQTT for home-automation dashboards
+//! - Opt-in team sync through a self-hosted endpoint, with combined usage
+//!   of teammates in the dashboard
+//! - Local usage history, a dashboard window with charts, and an opt-in REST API
+//! - Demo mode with generated usage, for UI work and screenshots
 //! - Cross-platform support (Windows, macOS, Linux)
 //!
 //! ## Architecture
@@ -18,188 +61,734 @@
 //!
 //! - **Providers**: AI service integrations (Claude, OpenAI, Gemini, Codex)
 //! - **Auth**: Secure credential storage and cookie extraction
-//! - **Agents**: Background tasks (refresh, notifications)
+//! - **Agents**: Background tasks (refresh, notifications, IPC server, MQTT, REST API)
+//! - **Events**: Process-wide bus of snapshot, auth, agent, config,
+//!   notification and billing period events, forwarded to the frontend
+//! - **Budget**: Billing periods spend is summed over, and their rollovers
+//! - **History**: SQLite store of past snapshots
 //! - **Security**: Sanitization, secure strings, platform-specific encryption
+//!
+//! Only the tray app, its windows and the frontend commands depend on Tauri,
+//! behind the default `desktop` feature. Build with
+//! `--no-default-features` to embed provider monitoring in another program
+//! through [`AppState`] and the [`events`] bus. Each provider is a feature
+//! of its own, so that needs `--features all-providers` or a subset such as
+//! `claude,codex`.
 
+pub mod accessibility;
 pub mod agents;
+pub mod attention;
 pub mod auth;
+pub mod autostart;
+pub mod budget;
+pub mod cli;
+#[cfg(feature = "desktop")]
 mod commands;
 pub mod config;
+pub mod crash;
+pub mod deep_link;
+pub mod diagnostics;
+pub mod events;
+pub mod format;
+pub mod freeze;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod logging;
+pub mod memory;
 pub mod providers;
+pub mod sandbox;
 pub mod security;
+pub mod startup;
+pub mod team;
+pub mod telemetry;
+pub mod tray;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod updates;
+#[cfg(feature = "desktop")]
+pub mod window;
 
+use std::future::Future;
 use std::sync::Arc;
-use tauri::{
-    image::Image,
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager, PhysicalPosition, WindowEvent,
-};
+#[cfg(feature = "desktop")]
+use tauri::{Emitter, Manager, WindowEvent};
 
-use agents::{AgentManager, NotificationAgent, RefreshAgent};
-use providers::{ClaudeProvider, CodexProvider, GeminiProvider, OpenAIProvider, ProviderRegistry};
+#[cfg(feature = "desktop")]
+use agents::USAGE_UPDATED_EVENT;
+use agents::{
+    api_token, Agent, AgentError, AgentManager, ApiState, BudgetAgent, CurrencyAgent, HttpAgent,
+    IpcAgent, MaintenanceAgent, MqttAgent, NotificationAgent, RefreshAgent, TeamSyncAgent,
+    TelemetryAgent, UsageUpdate,
+};
+use attention::AttentionItem;
+use auth::SecureStore;
+#[cfg(feature = "desktop")]
+use deep_link::DeepLink;
+use events::AppEvent;
+#[cfg(feature = "desktop")]
+use events::APP_EVENT;
+use history::HistoryStore;
+use memory::MemoryReport;
+#[cfg(feature = "codex")]
+use providers::CodexProvider;
+#[cfg(feature = "elevenlabs")]
+use providers::ElevenLabsProvider;
+#[cfg(feature = "gemini-code-assist")]
+use providers::GeminiCodeAssistProvider;
+#[cfg(feature = "gemini")]
+use providers::GeminiProvider;
+#[cfg(feature = "openai")]
+use providers::OpenAIProvider;
+#[cfg(feature = "together")]
+use providers::TogetherProvider;
+#[cfg(feature = "windsurf")]
+use providers::WindsurfProvider;
+use providers::{AuthStateTracker, DemoProvider, ProviderRegistry};
+#[cfg(feature = "claude")]
+use providers::{ClaudeConfig, ClaudeProvider};
+use startup::StartupTimer;
+use team::TeamClient;
 
-/// Application state shared across the Tauri app
+/// Application state shared across the Tauri app, or owned by a program
+/// embedding the library
+///
+/// Shared as a plain `Arc<AppState>`: every field synchronizes itself, so
+/// commands never wait on each other for the state as a whole.
 pub struct AppState {
     /// Agent manager for background tasks
     pub agent_manager: AgentManager,
-    /// Provider registry
+    /// Compiled-in providers, the same instances as the fields below
     pub registry: ProviderRegistry,
     /// Claude provider (for backwards compatibility)
+    #[cfg(feature = "claude")]
     pub claude: Arc<ClaudeProvider>,
     /// OpenAI provider
+    #[cfg(feature = "openai")]
     pub openai: Arc<OpenAIProvider>,
     /// Gemini provider
+    #[cfg(feature = "gemini")]
     pub gemini: Arc<GeminiProvider>,
     /// Codex provider
+    #[cfg(feature = "codex")]
     pub codex: Arc<CodexProvider>,
+    /// Together AI provider
+    #[cfg(feature = "together")]
+    pub together: Arc<TogetherProvider>,
+    /// ElevenLabs provider
+    #[cfg(feature = "elevenlabs")]
+    pub elevenlabs: Arc<ElevenLabsProvider>,
+    /// Refresh agent (also registered with the agent manager)
+    pub refresh: Arc<RefreshAgent>,
+    /// Recorded usage history
+    pub history: Arc<HistoryStore>,
+    /// Notification agent (also registered with the agent manager)
+    pub notification: Arc<NotificationAgent>,
+    /// Last known auth state of each provider
+    pub auth_states: Arc<AuthStateTracker>,
 }
 
 impl AppState {
     /// Creates a new AppState with default configuration
+    ///
+    /// Only builds what the first window needs; agents reading the keychain
+    /// are added by [`AppState::start`].
     pub async fn new() -> Self {
-        let claude = Arc::new(ClaudeProvider::new());
+        let mut timer = StartupTimer::new("App state");
+        let config = config::AppConfig::load();
+        timer.phase("config");
+        let mut registry = ProviderRegistry::empty();
+        #[cfg(feature = "claude")]
+        let claude = Arc::new(ClaudeProvider::with_config(ClaudeConfig {
+            local_usage: config.claude_local_usage,
+            budget_period: config.budget_period("claude"),
+            // Viewer mode leaves the keychain as it is
+            store_token: config.store_discovered_tokens && !config.viewer_mode,
+            ..Default::default()
+        }));
+        #[cfg(feature = "claude")]
+        {
+            if !config.store_discovered_tokens && !config.viewer_mode {
+                // Don't keep a copy saved before the setting was turned off;
+                // the keychain can be slow, so off the startup path
+                let claude = claude.clone();
+                tokio::task::spawn_blocking(move || claude.forget_stored_token());
+            }
+            registry.register(claude.clone());
+        }
+        #[cfg(feature = "openai")]
         let openai = Arc::new(OpenAIProvider::new());
+        #[cfg(feature = "openai")]
+        registry.register(openai.clone());
+        #[cfg(feature = "gemini")]
         let gemini = Arc::new(GeminiProvider::new());
+        #[cfg(feature = "gemini")]
+        registry.register(gemini.clone());
+        #[cfg(feature = "gemini-code-assist")]
+        registry.register(Arc::new(GeminiCodeAssistProvider::new()));
+        #[cfg(feature = "codex")]
         let codex = Arc::new(CodexProvider::new());
-        let registry = ProviderRegistry::new();
+        #[cfg(feature = "codex")]
+        registry.register(codex.clone());
+        #[cfg(feature = "together")]
+        let together = Arc::new(TogetherProvider::new());
+        #[cfg(feature = "together")]
+        registry.register(together.clone());
+        #[cfg(feature = "elevenlabs")]
+        let elevenlabs = Arc::new(ElevenLabsProvider::new());
+        #[cfg(feature = "elevenlabs")]
+        registry.register(elevenlabs.clone());
+        #[cfg(feature = "windsurf")]
+        registry.register(Arc::new(WindsurfProvider::new()));
+        let demo = config.is_demo();
+
+        // Apply values saved from the provider settings UI
+        for provider in registry.all() {
+            if let Some(settings) = config.provider_settings.get(provider.id()) {
+                if let Err(e) = provider.apply_settings(&settings.values).await {
+                    tracing::warn!("Ignoring saved settings for {}: {}", provider.id(), e);
+                }
+            }
+        }
+        timer.phase("providers");
         let agent_manager = AgentManager::new();
 
         // Create and register agents
-        let refresh = Arc::new(RefreshAgent::with_interval(5)); // 5 minute refresh
-        let notification = Arc::new(NotificationAgent::new());
+        let auth_states = Arc::new(AuthStateTracker::default());
+        let refresh = Arc::new(
+            RefreshAgent::with_interval(5) // 5 minute refresh
+                .with_auth_states(auth_states.clone()),
+        );
+
+        // Record every refresh; fall back to memory so the app still starts.
+        // Generated demo usage is kept out of the real history.
+        let history = Arc::new(
+            if demo {
+                HistoryStore::open_in_memory()
+            } else {
+                HistoryStore::open_default()
+            }
+            .or_else(|e| {
+                tracing::warn!("Failed to open history database: {}", e);
+                HistoryStore::open_in_memory()
+            })
+            .expect("failed to open in-memory history database"),
+        );
+        timer.phase("history");
+        let notification = Arc::new(
+            NotificationAgent::new()
+                .with_overrides(config.threshold_overrides())
+                .with_muted_windows(config.muted_windows())
+                .with_history(history.clone()),
+        );
+
+        // Add all providers to refresh agent; demo mode only refreshes generated usage
+        if demo {
+            let provider = Arc::new(DemoProvider::new());
+            registry.register(provider.clone());
+            refresh.add_provider(provider).await;
+        } else {
+            for provider in registry.all() {
+                refresh.add_provider(provider).await;
+            }
+        }
+
+        refresh.set_order(&config.ordered_providers()).await;
+        if !demo {
+            refresh.set_active_providers(config.profile_providers());
+        }
 
-        // Add all providers to refresh agent
-        refresh.add_provider(claude.clone()).await;
-        refresh.add_provider(openai.clone()).await;
-        refresh.add_provider(gemini.clone()).await;
-        refresh.add_provider(codex.clone()).await;
+        agent_manager.register(refresh.clone()).await;
+        agent_manager.register(notification.clone()).await;
+        agent_manager
+            .register(Arc::new(
+                BudgetAgent::new(refresh.clone()).with_periods(config.budget_periods()),
+            ))
+            .await;
+        agent_manager
+            .register(Arc::new(MaintenanceAgent::new(history.clone())))
+            .await;
+
+        // Costs are shown in USD until a fetched rate arrives
+        match config.currency.fixed() {
+            Some(currency) => format::set_currency(currency),
+            None => {
+                agent_manager
+                    .register(Arc::new(CurrencyAgent::new(
+                        config.currency.code.as_str(),
+                        refresh.clone(),
+                    )))
+                    .await
+            }
+        }
+
+        // Put agent output on the event bus, which everything else listens to
+        spawn(events::consume(refresh.subscribe(), |update| {
+            events::publish(AppEvent::SnapshotUpdated(Box::new(update)))
+        }));
+        spawn(events::consume(notification.subscribe(), |sent| {
+            events::publish(AppEvent::NotificationSent(sent))
+        }));
+
+        let recorder = history.clone();
+        spawn_snapshot_consumer(move |update| {
+            if let Err(e) = recorder.record(&update.provider_id, &update.snapshot) {
+                tracing::warn!("Failed to record history for {}: {}", update.provider_id, e);
+            }
+        });
+
+        // Generated demo usage never pauses real scripts
+        if config.usage_freeze.is_enabled() && !demo {
+            let freeze = freeze::UsageFreeze::new(config.usage_freeze.clone());
+            spawn_snapshot_consumer(move |update| {
+                freeze.update(&update.provider_id, &update.snapshot)
+            });
+        }
+
+        let resets = hooks::ResetDetector::new();
+        spawn_snapshot_consumer(move |update| {
+            resets.publish(&update.provider_id, &update.snapshot)
+        });
+
+        // Hooks and freeze commands from the config file
+        let hooks = config.hooks.clone();
+        let freeze_settings = config.usage_freeze.clone();
+        spawn(events::consume(events::subscribe(), move |event| {
+            hooks.dispatch(&event);
+            if let Some((command, env)) = freeze::command_for(&freeze_settings, &event) {
+                let payload = serde_json::to_string(&event).unwrap_or_default();
+                hooks.spawn(command, payload, env);
+            }
+        }));
+
+        if config.ipc_enabled {
+            agent_manager
+                .register(Arc::new(IpcAgent::new(refresh.clone())))
+                .await;
+        }
+
+        telemetry::set_enabled(config.telemetry_enabled);
+        if config.telemetry_enabled {
+            if let Some(agent) = TelemetryAgent::from_build() {
+                agent_manager.register(Arc::new(agent)).await;
+            }
+        }
 
-        agent_manager.register(refresh).await;
-        agent_manager.register(notification).await;
+        // Saved agent settings; an agent that rejects them keeps its defaults
+        for (id, settings) in &config.agents {
+            if let Err(e) = agent_manager.configure_agent(id, settings.clone()).await {
+                tracing::warn!("Ignoring saved settings for agent {}: {}", id, e);
+            }
+        }
+        timer.phase("agents");
+        timer.finish();
 
         Self {
             agent_manager,
             registry,
+            #[cfg(feature = "claude")]
             claude,
+            #[cfg(feature = "openai")]
             openai,
+            #[cfg(feature = "gemini")]
             gemini,
+            #[cfg(feature = "codex")]
             codex,
+            #[cfg(feature = "together")]
+            together,
+            #[cfg(feature = "elevenlabs")]
+            elevenlabs,
+            refresh,
+            history,
+            notification,
+            auth_states,
+        }
+    }
+
+    /// Adds the agents that need stored secrets, then starts every agent
+    ///
+    /// The MQTT credentials, REST API token and team token are only read
+    /// from the keychain here, for the agents turned on.
+    pub async fn start(&self) -> Result<(), AgentError> {
+        let mut timer = StartupTimer::new("Agent startup");
+        let config = config::AppConfig::load();
+        let mut added = Vec::new();
+
+        if config.mqtt.enabled {
+            let settings = config.mqtt.clone();
+            let mqtt = Arc::new(
+                unblock(move || {
+                    MqttAgent::new(settings).with_stored_credentials(&SecureStore::new())
+                })
+                .await?,
+            );
+            let publisher = mqtt.clone();
+            spawn_snapshot_consumer(move |update| {
+                publisher.publish(&update.provider_id, &update.snapshot)
+            });
+            added.push(mqtt as Arc<dyn Agent>);
+        }
+
+        if config.api_server.enabled {
+            match unblock(|| api_token(&SecureStore::new())).await? {
+                Ok(token) => {
+                    let mut metadata = self.registry.metadata();
+                    config.order_metadata(&mut metadata);
+                    let state =
+                        ApiState::new(self.refresh.clone(), self.history.clone(), metadata, token);
+                    added.push(Arc::new(HttpAgent::new(config.api_server.clone(), state)));
+                }
+                Err(e) => tracing::error!("REST API disabled, no token available: {}", e),
+            }
+        }
+
+        if config.team_sync.enabled && !config.is_demo() {
+            let mut settings = config.team_sync.clone();
+            if settings.ensure_member_id() {
+                let mut stored = config::AppConfig::load();
+                stored.team_sync.member_id = settings.member_id.clone();
+                if let Err(e) = stored.save() {
+                    tracing::warn!("Failed to save team member ID: {}", e);
+                }
+            }
+            let client_settings = settings.clone();
+            match unblock(move || TeamClient::from_settings(&client_settings, &SecureStore::new()))
+                .await?
+            {
+                Ok(client) => added.push(Arc::new(TeamSyncAgent::new(
+                    settings,
+                    client,
+                    self.refresh.clone(),
+                ))),
+                Err(e) => tracing::error!("Team sync disabled: {}", e),
+            }
+        }
+
+        // Viewer mode leaves the keychain as it is
+        if !config.viewer_mode {
+            let saved = config.saved_api_keys();
+            let migrating = saved.clone();
+            match unblock(move || SecureStore::new().migrate_api_keys(&migrating)).await? {
+                Ok(stored) => {
+                    if stored > 0 {
+                        tracing::info!("Moved {} saved API keys to the secure store", stored);
+                    }
+                    // Every saved key is in the store now, so the config
+                    // file needn't keep them in plain text
+                    let mut stored = config::AppConfig::load();
+                    if stored.forget_migrated_api_keys(&saved) {
+                        if let Err(e) = stored.save() {
+                            tracing::warn!("Failed to clear migrated API keys: {}", e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to move saved API keys to the secure store: {}", e)
+                }
+            }
+        }
+        timer.phase("secrets");
+
+        let manager = &self.agent_manager;
+        for agent in added {
+            let id = agent.id();
+            manager.register(agent).await;
+            if let Some(settings) = config.agents.get(id) {
+                if let Err(e) = manager.configure_agent(id, settings.clone()).await {
+                    tracing::warn!("Ignoring saved settings for agent {}: {}", id, e);
+                }
+            }
+        }
+
+        let result = manager.start_all().await;
+        timer.phase("agents");
+        timer.finish();
+        result
+    }
+
+    /// What needs the user's attention among the active providers
+    pub async fn attention_items(&self) -> Vec<AttentionItem> {
+        self.attention_items_for(&config::AppConfig::load()).await
+    }
+
+    /// What needs the user's attention among the providers `config` has
+    /// active
+    async fn attention_items_for(&self, config: &config::AppConfig) -> Vec<AttentionItem> {
+        let auth_states = config
+            .active_providers()
+            .into_iter()
+            .map(|id| {
+                let state = self.auth_states.state(&id);
+                (id, state)
+            })
+            .collect();
+        attention::attention_items(
+            config,
+            &auth_states,
+            &self.refresh.get_all_snapshots().await,
+            &self.refresh.metrics().await,
+        )
+    }
+
+    /// What each part of the core holds in memory
+    pub async fn memory_report(&self) -> MemoryReport {
+        let report = MemoryReport::new()
+            .with("snapshots", self.refresh.memory().await)
+            .with("notification_cooldowns", self.notification.memory().await)
+            .with("log_buffer", diagnostics::log_memory());
+        match self.history.memory() {
+            Ok(usage) => report.with("history", usage),
+            Err(e) => {
+                tracing::warn!("Failed to measure history: {}", e);
+                report
+            }
         }
     }
 }
 
+/// Runs blocking work, such as a keychain read, on the blocking thread pool
+async fn unblock<T, F>(work: F) -> Result<T, AgentError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AgentError::OperationFailed(e.to_string()))
+}
+
+/// Calls `handler` with every new snapshot published on the event bus, on
+/// its own task
+fn spawn_snapshot_consumer<F>(mut handler: F)
+where
+    F: FnMut(UsageUpdate) + Send + 'static,
+{
+    spawn(events::consume(events::subscribe(), move |event| {
+        if let AppEvent::SnapshotUpdated(update) = event {
+            handler(*update);
+        }
+    }));
+}
+
+/// Runs a background task on Tauri's runtime, or on the embedding program's
+/// runtime
+fn spawn<F>(task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "desktop")]
+    tauri::async_runtime::spawn(task);
+    #[cfg(not(feature = "desktop"))]
+    tokio::spawn(task);
+}
+
 // ============================================================================
 // Tauri App Entry Point
 // ============================================================================
 
 /// Initializes and runs the Tauri application
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("gptbar=debug".parse().unwrap())
-                .add_directive("info".parse().unwrap()),
-        )
-        .init();
+    let config = config::AppConfig::load();
+    logging::init(logging::LogFormat::resolve(config.log_format));
 
     tracing::info!("Starting GPTBar...");
 
+    crash::install_hook();
+
+    if let Some(language) = config.language {
+        i18n::set_language(language);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
-            // Create app state
-            let state = tokio::runtime::Runtime::new()
-                .unwrap()
-                .block_on(async { AppState::new().await });
+            let mut timer = StartupTimer::new("Setup");
 
-            let state = Arc::new(tokio::sync::RwLock::new(state));
+            // Create app state on Tauri's runtime, which its tasks keep
+            // running on
+            let state = Arc::new(tauri::async_runtime::block_on(AppState::new()));
+            timer.phase("state");
 
             // Manage state
             app.manage(state.clone());
 
-            // Start agents in background
+            // Read once here; the listeners below keep their own copy,
+            // reloaded when it changes, instead of reading it per snapshot
+            let config = config::AppConfig::load();
+
+            // Popup pinning survives restarts
+            let popup = Arc::new(window::PopupState::new(&config.window));
+            app.manage(popup.clone());
+
+            // Keep the tray indicators in sync with refreshes
+            let tray_state = Arc::new(tray::TrayState::new());
+            app.manage(tray_state.clone());
+
+            tray_state.set_brand_colors(&state.registry.metadata());
+
+            let handle = app.handle().clone();
+            let tray_updates = tray_state.clone();
+            spawn_snapshot_consumer(move |update| {
+                tray_updates.update(&handle, &update.provider_id, &update.snapshot);
+            });
+
+            // Count what needs attention whenever usage, credentials, fetch
+            // results or settings change
+            let handle = app.handle().clone();
+            let attention_state = state.clone();
+            let attention_tray = tray_state.clone();
+            let mut attention_config = Arc::new(config.clone());
+            tauri::async_runtime::spawn(events::consume(events::subscribe(), move |event| {
+                if matches!(event, AppEvent::ConfigChanged) {
+                    attention_config = Arc::new(config::AppConfig::load());
+                }
+                if matches!(
+                    event,
+                    AppEvent::SnapshotUpdated(_)
+                        | AppEvent::AuthStateChanged { .. }
+                        | AppEvent::FetchFailed { .. }
+                        | AppEvent::ConfigChanged
+                ) {
+                    let state = attention_state.clone();
+                    let tray = attention_tray.clone();
+                    let handle = handle.clone();
+                    let config = attention_config.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let count = state.attention_items_for(&config).await.len();
+                        tray.set_attention(&handle, count);
+                    });
+                }
+            }));
+
+            // Bridge the event bus to the frontend, streaming new snapshots
+            // to the popup as they arrive
+            let handle = app.handle().clone();
+            let mut display_config = config;
+            tauri::async_runtime::spawn(events::consume(events::subscribe(), move |event| {
+                if matches!(event, AppEvent::ConfigChanged) {
+                    display_config = config::AppConfig::load();
+                }
+                if let AppEvent::SnapshotUpdated(update) = &event {
+                    let update = UsageUpdate {
+                        provider_id: update.provider_id.clone(),
+                        snapshot: display_config
+                            .for_display(&update.provider_id, update.snapshot.clone()),
+                    };
+                    if let Err(e) = handle.emit(USAGE_UPDATED_EVENT, update) {
+                        tracing::warn!("Failed to emit usage update: {}", e);
+                    }
+                }
+                if let Err(e) = handle.emit(APP_EVENT, &event) {
+                    tracing::warn!("Failed to emit app event: {}", e);
+                }
+            }));
+
+            // Act on deep links, whether forwarded over IPC or opened on macOS
+            let handle = app.handle().clone();
+            let links_state = state.clone();
+            tauri::async_runtime::spawn(events::consume(events::subscribe(), move |event| {
+                match event {
+                    // The popup selects the provider itself
+                    AppEvent::DeepLinkOpened(DeepLink::Show | DeepLink::Provider { .. }) => {
+                        window::show_popup(&handle);
+                    }
+                    AppEvent::DeepLinkOpened(DeepLink::Refresh) => {
+                        let state = links_state.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = state.agent_manager.trigger_agent("refresh").await {
+                                tracing::warn!("Failed to refresh from link: {}", e);
+                            }
+                        });
+                    }
+                    _ => {}
+                }
+            }));
+
+            // Keep the gptbar:// handler pointing at this executable
+            std::thread::spawn(|| {
+                if let Err(e) = deep_link::register() {
+                    tracing::warn!("Failed to register gptbar:// links: {}", e);
+                }
+            });
+
+            // Start agents in background, then act on a link the app was
+            // started with
             let state_clone = state.clone();
             tauri::async_runtime::spawn(async move {
-                let state = state_clone.read().await;
-                if let Err(e) = state.agent_manager.start_all().await {
+                if let Err(e) = state_clone.start().await {
                     tracing::error!("Failed to start agents: {}", e);
                 }
+                let args: Vec<String> = std::env::args().collect();
+                if let Some((_, link)) = DeepLink::from_args(&args) {
+                    events::publish(AppEvent::DeepLinkOpened(link));
+                }
             });
 
             // Create system tray icon
-            let icon = Image::from_path("icons/icon.png")
-                .or_else(|_| Image::from_path("icons/32x32.png"))
-                .unwrap_or_else(|_| {
-                    Image::from_bytes(include_bytes!("../icons/32x32.png"))
-                        .expect("Failed to load embedded icon")
-                });
-
-            // Window dimensions (increased for new design)
-            const WINDOW_WIDTH: i32 = 300;
-            const WINDOW_HEIGHT: i32 = 520;
-            const MARGIN: i32 = 10;
-
-            let _tray = TrayIconBuilder::new()
-                .icon(icon)
-                .tooltip("GPTBar - Click to view usage")
-                .on_tray_icon_event(move |tray, event| {
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        let app = tray.app_handle();
-
-                        // Get or create the popup window
-                        if let Some(window) = app.get_webview_window("main") {
-                            if window.is_visible().unwrap_or(false) {
-                                let _ = window.hide();
-                            } else {
-                                // Position near tray icon
-                                if let Some(rect) = tray.rect().ok().flatten() {
-                                    let (tray_x, tray_y) = match rect.position {
-                                        tauri::Position::Physical(p) => (p.x, p.y),
-                                        tauri::Position::Logical(l) => (l.x as i32, l.y as i32),
-                                    };
-                                    let (tray_w, _tray_h) = match rect.size {
-                                        tauri::Size::Physical(s) => (s.width as i32, s.height as i32),
-                                        tauri::Size::Logical(s) => (s.width as i32, s.height as i32),
-                                    };
-
-                                    // Position: horizontally centered on tray icon, above the taskbar
-                                    let x = tray_x + (tray_w / 2) - (WINDOW_WIDTH / 2);
-                                    let y = tray_y - WINDOW_HEIGHT - MARGIN;
-
-                                    let _ = window.set_position(tauri::Position::Physical(
-                                        PhysicalPosition::new(x, y),
-                                    ));
-                                }
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                    }
-                })
-                .build(app)?;
+            tray::create(app)?;
+            timer.phase("tray");
 
-            // Listen for window focus loss to auto-hide
+            // Listen for window focus loss to auto-hide unless pinned, for
+            // moves of the pinned popup, and for theme and scale changes to
+            // re-render the tray icon
             let main_window = app.get_webview_window("main");
             if let Some(window) = main_window {
+                if let Ok(scale_factor) = window.scale_factor() {
+                    tray_state.set_scale_factor(app.handle(), scale_factor);
+                }
+                tray_state.set_theme(app.handle(), window.theme().ok());
+
+                let appearance = config::AppConfig::load().appearance;
+                if let Err(e) = window::apply_appearance(&window, &appearance) {
+                    tracing::warn!("Failed to apply window effect: {}", e);
+                }
+
+                // Restore the remembered size; it is clamped to the monitor
+                // when the popup is shown
+                if let Some(size) = popup.size() {
+                    let _ = window.set_size(tauri::PhysicalSize::new(size.width, size.height));
+                }
+
+                // The window starts hidden; only the tray icon appears unless
+                // the user asked to see the popup on launch
+                if config::AppConfig::load().shows_window_on_launch() {
+                    window::show_popup(app.handle());
+                }
+
                 let window_clone = window.clone();
-                window.on_window_event(move |event| {
-                    if let WindowEvent::Focused(focused) = event {
-                        if !focused {
+                let handle = app.handle().clone();
+                window.on_window_event(move |event| match event {
+                    WindowEvent::Focused(false) => {
+                        if let Err(e) = popup.save() {
+                            tracing::warn!("Failed to save window geometry: {}", e);
+                        }
+                        if !popup.is_pinned() {
                             // Window lost focus - hide it
                             let _ = window_clone.hide();
                         }
                     }
+                    WindowEvent::Moved(position) => {
+                        popup.moved(position.x, position.y);
+                    }
+                    WindowEvent::Resized(size) => {
+                        popup.resized(size.width, size.height);
+                    }
+                    WindowEvent::ThemeChanged(theme) => {
+                        tray_state.set_theme(&handle, Some(*theme));
+                    }
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        tray_state.set_scale_factor(&handle, *scale_factor);
+                    }
+                    _ => {}
                 });
             }
+            timer.phase("window");
+            timer.finish();
 
             tracing::info!("GPTBar initialized successfully");
             Ok(())
@@ -211,27 +800,108 @@ pub fn run() {
             commands::is_claude_available,
             commands::login_claude,
             commands::logout_claude,
+            #[cfg(feature = "claude")]
             commands::reload_token,
             // Generic provider commands
             commands::fetch_provider_usage,
             commands::is_provider_available,
+            commands::get_provider_states,
+            commands::get_attention_items,
+            commands::open_provider_page,
             commands::login_provider,
             commands::logout_provider,
             commands::get_providers,
+            commands::get_aggregate_usage,
+            commands::get_history,
+            commands::get_usage_stats,
+            commands::export_history,
+            commands::get_dashboard,
+            commands::get_notification_history,
+            commands::get_usage_by_project,
+            commands::get_refresh_metrics,
             commands::get_enabled_providers,
             commands::set_provider_enabled,
             commands::set_provider_order,
+            commands::set_provider_pinned,
+            commands::get_profiles,
+            commands::set_active_profile,
+            commands::set_provider_thresholds,
+            commands::get_notified_windows,
+            commands::set_window_notifications,
+            commands::get_headline_window,
+            commands::set_headline_window,
             commands::set_provider_api_key,
+            commands::set_provider_settings,
+            commands::get_provider_settings,
+            #[cfg(feature = "openai")]
+            commands::list_openai_organizations,
+            commands::discover_credentials,
+            // Popup state
+            commands::get_app_state,
+            // Crash report commands
+            commands::get_crash_report,
+            commands::report_crash,
+            commands::dismiss_crash_report,
+            commands::generate_diagnostic_bundle,
             // Agent commands
             commands::trigger_refresh,
             commands::get_agent_status,
+            commands::start_agent,
+            commands::stop_agent,
+            commands::set_agent_config,
             // Config commands
             commands::get_config,
+            commands::is_demo_mode,
             commands::save_config,
             commands::set_refresh_interval,
             commands::set_start_on_login,
+            commands::set_show_window_on_launch,
             commands::is_autostart_enabled,
+            commands::set_check_for_updates,
+            commands::set_telemetry_enabled,
+            commands::get_telemetry_preview,
+            commands::get_app_version_info,
+            commands::open_latest_release,
+            // Security commands
+            commands::reveal_provider_api_key,
+            commands::set_require_os_auth,
+            // Integration commands
+            commands::set_mqtt_credentials,
+            commands::reveal_api_token,
+            commands::set_team_token,
+            commands::get_team_usage,
+            commands::get_ui_preferences,
+            commands::get_memory_report,
+            commands::set_log_level,
+            commands::set_log_filter,
+            commands::get_log_filter,
+            commands::set_appearance,
+            // Localization commands
+            commands::get_language,
+            commands::set_language,
+            commands::set_reset_style,
+            commands::format_reset,
+            commands::get_currency,
+            // Tray commands
+            commands::set_tray_title,
+            commands::set_tray_icon_style,
+            // Window commands
+            commands::hide_window,
+            commands::open_dashboard,
+            commands::set_window_pinned,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, _event| {
+            // macOS hands links to the running app instead of starting it
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = _event {
+                for url in urls {
+                    match DeepLink::parse(url.as_str()) {
+                        Some(link) => events::publish(AppEvent::DeepLinkOpened(link)),
+                        None => tracing::warn!("Ignoring unknown link {}", url),
+                    }
+                }
+            }
+        });
 }
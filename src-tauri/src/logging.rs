@@ -0,0 +1,359 @@
+//! Log output setup
+//!
+//! Logs go to stdout and the diagnostic log buffer, as colored text by
+//! default. With [`LogFormat::Json`], chosen in the config file or with
+//! `GPTBAR_LOG_FORMAT=json`, each event is one JSON object per line instead,
+//! for shipping logs into another observability stack:
+//!
+//! ```json
+//! {"timestamp":"2025-01-01T12:00:00.000Z","level":"WARN","target":"gptbar_lib::agents::refresh_agent","provider_id":"claude","agent_id":"refresh","message":"Failed to fetch usage for claude: ..."}
+//! ```
+//!
+//! Fields of the spans an event happened in are added next to its own, and
+//! `provider_id` and `agent_id` are always present, `null` outside a
+//! provider fetch or an agent. The JSON is written here instead of with
+//! tracing-subscriber's `json` feature, which would add `tracing-serde` for
+//! the same lines.
+//!
+//! The filter can be changed while the app runs with [`set_level`] or
+//! [`set_filter`], e.g. to capture an intermittent fetch failure at debug
+//! level without restarting. Changes last until the app quits.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::diagnostics;
+
+/// Environment variable that overrides the configured [`LogFormat`]
+pub const LOG_FORMAT_ENV: &str = "GPTBAR_LOG_FORMAT";
+
+/// Fields every JSON line has, `null` when unknown
+const CONTEXT_FIELDS: [&str; 2] = ["provider_id", "agent_id"];
+
+/// Filter when `RUST_LOG` is unset: debug for GPTBar, info for the rest
+pub const DEFAULT_FILTER: &str = "info,gptbar=debug";
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// One JSON object per event
+    Json,
+}
+
+impl LogFormat {
+    /// Parses a `GPTBAR_LOG_FORMAT` value, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// `configured`, unless `GPTBAR_LOG_FORMAT` names another format
+    pub fn resolve(configured: LogFormat) -> Self {
+        std::env::var(LOG_FORMAT_ENV)
+            .ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or(configured)
+    }
+}
+
+/// Installs the global log subscriber writing in `format`
+///
+/// `RUST_LOG` adds to the default filter of debug for GPTBar and info for
+/// everything else.
+pub fn init(format: LogFormat) {
+    let filter = EnvFilter::from_default_env()
+        .add_directive("gptbar=debug".parse().unwrap())
+        .add_directive("info".parse().unwrap());
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Text => registry
+            .with(tracing_subscriber::fmt::layer().with_writer(diagnostics::log_writer))
+            .init(),
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .fmt_fields(JsonFields)
+                    .event_format(JsonFormat)
+                    .with_ansi(false)
+                    .with_writer(diagnostics::log_writer),
+            )
+            .init(),
+    }
+}
+
+/// Filter logging GPTBar at `level` and other crates at info, or less when
+/// `level` is quieter
+///
+/// `level` is one of `off`, `error`, `warn`, `info`, `debug` or `trace`.
+pub fn level_filter(level: &str) -> Result<String, String> {
+    let level: LevelFilter = level
+        .trim()
+        .parse()
+        .map_err(|_| format!("Unknown log level: {}", level))?;
+    let others = level.min(LevelFilter::INFO);
+    Ok(format!("{},gptbar={}", others, level).to_lowercase())
+}
+
+/// Replaces the log filter, in `RUST_LOG` syntax such as
+/// `info,gptbar=trace,reqwest=debug`; an empty filter restores the default
+///
+/// Returns the filter now in effect.
+pub fn set_filter(filter: &str) -> Result<String, String> {
+    let filter = match filter.trim() {
+        "" => DEFAULT_FILTER,
+        filter => filter,
+    };
+    let parsed =
+        EnvFilter::try_new(filter).map_err(|e| format!("Invalid log filter {}: {}", filter, e))?;
+    let handle = FILTER.get().ok_or("Logging is not initialized")?;
+    handle
+        .reload(parsed)
+        .map_err(|e| format!("Failed to change the log filter: {}", e))?;
+    tracing::info!("Log filter set to {}", filter);
+    Ok(filter.to_string())
+}
+
+/// Logs GPTBar at `level` from now on, see [`level_filter`]
+pub fn set_level(level: &str) -> Result<String, String> {
+    set_filter(&level_filter(level)?)
+}
+
+/// The log filter in effect, if logging was initialized
+pub fn current_filter() -> Option<String> {
+    FILTER.get()?.with_current(|filter| filter.to_string()).ok()
+}
+
+/// Collects recorded fields into a JSON object
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0
+            .insert(field.name().to_string(), value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+/// Stores span fields as a JSON object, for [`JsonFormat`] to read back
+pub struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor(parse_object(&current.fields));
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+/// Parses fields stored by [`JsonFields`]; anything else yields no fields
+fn parse_object(fields: &str) -> Map<String, Value> {
+    match serde_json::from_str(fields) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// Writes each event as one line of JSON
+pub struct JsonFormat;
+
+impl<S> FormatEvent<S, JsonFields> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+                .into(),
+        );
+        line.insert("level".to_string(), metadata.level().as_str().into());
+        line.insert("target".to_string(), metadata.target().into());
+        for field in CONTEXT_FIELDS {
+            line.insert(field.to_string(), Value::Null);
+        }
+
+        // Outermost span first, so inner spans and the event itself win
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<FormattedFields<JsonFields>>() {
+                    line.extend(parse_object(&fields.fields));
+                }
+            }
+        }
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        line.extend(visitor.0);
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing::Instrument;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn json_lines(log: impl FnOnce()) -> Vec<Value> {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(JsonFields)
+                .event_format(JsonFormat)
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        );
+        tracing::subscriber::with_default(subscriber, log);
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_level_filter() {
+        assert_eq!(level_filter("debug").unwrap(), "info,gptbar=debug");
+        assert_eq!(level_filter(" TRACE ").unwrap(), "info,gptbar=trace");
+        assert_eq!(level_filter("warn").unwrap(), "warn,gptbar=warn");
+        assert!(level_filter("verbose").is_err());
+        assert!(EnvFilter::try_new(level_filter("off").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(LogFormat::parse("JSON\n"), Some(LogFormat::Json));
+        assert_eq!(LogFormat::parse("text"), Some(LogFormat::Text));
+        assert_eq!(LogFormat::parse("yaml"), None);
+    }
+
+    #[test]
+    fn test_json_lines_carry_span_fields() {
+        let lines = json_lines(|| {
+            tracing::info!("Outside");
+            let agent = tracing::info_span!("agent", agent_id = "refresh");
+            let _agent = agent.enter();
+            let fetch = tracing::info_span!("fetch", provider_id = "claude");
+            let _fetch = fetch.enter();
+            tracing::warn!(status = 429, "Failed to fetch usage for {}", "claude");
+        });
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"], "Outside");
+        assert_eq!(lines[0]["provider_id"], Value::Null);
+        assert_eq!(lines[0]["agent_id"], Value::Null);
+
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["message"], "Failed to fetch usage for claude");
+        assert_eq!(lines[1]["provider_id"], "claude");
+        assert_eq!(lines[1]["agent_id"], "refresh");
+        assert_eq!(lines[1]["status"], 429);
+    }
+
+    #[test]
+    fn test_json_lines_in_instrumented_futures() {
+        let lines = json_lines(|| {
+            let span = tracing::info_span!("agent", agent_id = tracing::field::Empty);
+            span.record("agent_id", "notification");
+            let task = async { tracing::info!("Checking snapshots") }.instrument(span);
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(task);
+        });
+
+        assert_eq!(lines[0]["agent_id"], "notification");
+    }
+}
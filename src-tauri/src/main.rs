@@ -2,5 +2,17 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    gptbar_lib::run()
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = gptbar_lib::cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    #[cfg(feature = "desktop")]
+    gptbar_lib::run();
+
+    #[cfg(not(feature = "desktop"))]
+    {
+        eprintln!("gptbar: built without the tray app, run `gptbar tui` instead");
+        std::process::exit(2);
+    }
 }
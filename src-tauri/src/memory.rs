@@ -0,0 +1,140 @@
+//! Memory use of the long-lived parts of the app
+//!
+//! The tray app runs for weeks, so everything it keeps in memory is bounded:
+//! one snapshot per provider, notification cooldowns until they pass, a
+//! capped in-memory history and log buffer, and a capped icon cache. A
+//! [`MemoryReport`] lists what each part holds next to the process's
+//! resident memory, to check that it stays flat.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// What one part of the app holds in memory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    /// Items held, such as snapshots, log lines or rows
+    pub entries: usize,
+    /// Approximate size of the items, not counting allocator overhead
+    pub bytes: usize,
+}
+
+/// Memory held by each part of the app
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MemoryReport {
+    /// Resident memory of the whole process, when the OS reports it
+    pub resident_bytes: Option<u64>,
+    /// Usage by part, such as `snapshots` or `log_buffer`
+    pub subsystems: BTreeMap<&'static str, MemoryUsage>,
+    /// Sum of the parts' approximate sizes
+    pub tracked_bytes: usize,
+}
+
+impl MemoryReport {
+    /// Starts a report with the process's resident memory
+    pub fn new() -> Self {
+        Self {
+            resident_bytes: resident_bytes(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the usage of one part
+    pub fn with(mut self, subsystem: &'static str, usage: MemoryUsage) -> Self {
+        self.tracked_bytes += usage.bytes;
+        self.subsystems.insert(subsystem, usage);
+        self
+    }
+}
+
+/// Approximate size of a value, as its serialized JSON
+pub fn json_size<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value)
+        .map(|json| json.len())
+        .unwrap_or(0)
+}
+
+/// Parses the `VmRSS` line of `/proc/self/status`, given in kB
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Resident memory of this process
+#[cfg(target_os = "linux")]
+pub fn resident_bytes() -> Option<u64> {
+    parse_vm_rss(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// Resident memory of this process
+#[cfg(target_os = "macos")]
+pub fn resident_bytes() -> Option<u64> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()?;
+    let kb: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Resident memory (working set) of this process
+#[cfg(target_os = "windows")]
+pub fn resident_bytes() -> Option<u64> {
+    use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::GetCurrentProcess;
+
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+    let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    // SAFETY: counters is a valid PROCESS_MEMORY_COUNTERS of the given size,
+    // and the current process pseudo handle needs no closing
+    unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) }.ok()?;
+    Some(counters.WorkingSetSize as u64)
+}
+
+/// Resident memory of this process
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn resident_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tgptbar\nVmPeak:\t  900000 kB\nVmRSS:\t   51200 kB\n";
+        assert_eq!(parse_vm_rss(status), Some(51200 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tgptbar\n"), None);
+    }
+
+    #[test]
+    fn test_report_sums_subsystems() {
+        let report = MemoryReport::default()
+            .with(
+                "snapshots",
+                MemoryUsage {
+                    entries: 4,
+                    bytes: 2000,
+                },
+            )
+            .with(
+                "log_buffer",
+                MemoryUsage {
+                    entries: 10,
+                    bytes: 800,
+                },
+            );
+
+        assert_eq!(report.tracked_bytes, 2800);
+        assert_eq!(
+            report.subsystems.keys().copied().collect::<Vec<_>>(),
+            vec!["log_buffer", "snapshots"]
+        );
+    }
+}
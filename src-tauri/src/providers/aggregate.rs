@@ -0,0 +1,199 @@
+//! Aggregate usage view
+//!
+//! Combines the latest snapshots of several providers into one synthetic
+//! snapshot, so the tray icon, tooltip and summary card share a single number
+//! instead of each re-implementing the logic.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::base::{RateWindow, UsageSnapshot, WindowSlot};
+
+/// Provider ID under which the aggregate snapshot is exposed
+pub const AGGREGATE_ID: &str = "aggregate";
+
+/// How provider usages are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateMode {
+    /// Highest usage across providers
+    #[default]
+    Max,
+    /// Weighted average of provider usages
+    Average,
+}
+
+/// Builds the aggregate snapshot from per-provider snapshots
+#[derive(Debug, Clone, Default)]
+pub struct UsageAggregate {
+    mode: AggregateMode,
+    weights: HashMap<String, f64>,
+    headlines: HashMap<String, WindowSlot>,
+}
+
+impl UsageAggregate {
+    /// Creates an aggregate with the given mode and equal weights
+    pub fn new(mode: AggregateMode) -> Self {
+        Self {
+            mode,
+            weights: HashMap::new(),
+            headlines: HashMap::new(),
+        }
+    }
+
+    /// Sets a provider's weight in `Average` mode (default 1.0)
+    pub fn with_weight(mut self, provider_id: impl Into<String>, weight: f64) -> Self {
+        self.weights.insert(provider_id.into(), weight.max(0.0));
+        self
+    }
+
+    /// Sets the window a provider contributes (default its highest one)
+    pub fn with_headline(mut self, provider_id: impl Into<String>, slot: WindowSlot) -> Self {
+        self.headlines.insert(provider_id.into(), slot);
+        self
+    }
+
+    /// Combines the snapshots of the listed providers
+    ///
+    /// Each provider contributes its headline window usage. Returns `None`
+    /// when none of the listed providers has a snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use gptbar_lib::providers::{AggregateMode, RateWindow, UsageAggregate, UsageSnapshot};
+    ///
+    /// let mut snapshots = HashMap::new();
+    /// snapshots.insert("claude".to_string(), UsageSnapshot::new().with_primary(RateWindow::new(80.0)));
+    /// snapshots.insert("openai".to_string(), UsageSnapshot::new().with_primary(RateWindow::new(20.0)));
+    /// let enabled = vec!["claude".to_string(), "openai".to_string()];
+    ///
+    /// let max = UsageAggregate::new(AggregateMode::Max).snapshot(&snapshots, &enabled).unwrap();
+    /// assert_eq!(max.max_usage(), 80.0);
+    ///
+    /// let avg = UsageAggregate::new(AggregateMode::Average).snapshot(&snapshots, &enabled).unwrap();
+    /// assert_eq!(avg.max_usage(), 50.0);
+    /// ```
+    pub fn snapshot(
+        &self,
+        snapshots: &HashMap<String, UsageSnapshot>,
+        provider_ids: &[String],
+    ) -> Option<UsageSnapshot> {
+        let included: Vec<(&str, &UsageSnapshot)> = provider_ids
+            .iter()
+            .filter_map(|id| snapshots.get(id).map(|s| (id.as_str(), s)))
+            .collect();
+
+        let updated_at = included.iter().map(|(_, s)| s.updated_at).max()?;
+
+        let percent = match self.mode {
+            AggregateMode::Max => included
+                .iter()
+                .map(|(id, s)| self.usage(id, s))
+                .fold(0.0, f64::max),
+            AggregateMode::Average => {
+                let total_weight: f64 = included.iter().map(|(id, _)| self.weight(id)).sum();
+                let sum: f64 = included
+                    .iter()
+                    .map(|(id, s)| self.usage(id, s) * self.weight(id))
+                    .sum();
+                if total_weight > 0.0 {
+                    sum / total_weight
+                } else {
+                    0.0
+                }
+            }
+        };
+
+        let mut snapshot = UsageSnapshot::new().with_primary(RateWindow::new(percent));
+        snapshot.updated_at = updated_at;
+        Some(snapshot)
+    }
+
+    fn usage(&self, provider_id: &str, snapshot: &UsageSnapshot) -> f64 {
+        snapshot.headline_usage(self.headlines.get(provider_id).copied())
+    }
+
+    fn weight(&self, provider_id: &str) -> f64 {
+        self.weights.get(provider_id).copied().unwrap_or(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshots() -> HashMap<String, UsageSnapshot> {
+        let mut map = HashMap::new();
+        map.insert(
+            "claude".to_string(),
+            UsageSnapshot::new()
+                .with_primary(RateWindow::new(30.0))
+                .with_secondary(RateWindow::new(90.0)),
+        );
+        map.insert(
+            "openai".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(10.0)),
+        );
+        map
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_aggregate_max_uses_highest_window() {
+        let aggregate = UsageAggregate::new(AggregateMode::Max);
+        let snapshot = aggregate
+            .snapshot(&snapshots(), &ids(&["claude", "openai"]))
+            .unwrap();
+        assert_eq!(snapshot.max_usage(), 90.0);
+    }
+
+    #[test]
+    fn test_aggregate_weighted_average() {
+        let aggregate = UsageAggregate::new(AggregateMode::Average).with_weight("openai", 3.0);
+        let snapshot = aggregate
+            .snapshot(&snapshots(), &ids(&["claude", "openai"]))
+            .unwrap();
+        // (90 * 1 + 10 * 3) / 4
+        assert_eq!(snapshot.max_usage(), 30.0);
+    }
+
+    #[test]
+    fn test_aggregate_only_listed_providers() {
+        let aggregate = UsageAggregate::default();
+        let snapshot = aggregate.snapshot(&snapshots(), &ids(&["openai"])).unwrap();
+        assert_eq!(snapshot.max_usage(), 10.0);
+
+        assert!(aggregate.snapshot(&snapshots(), &ids(&["gemini"])).is_none());
+    }
+
+    #[test]
+    fn test_aggregate_headline_window() {
+        let aggregate =
+            UsageAggregate::new(AggregateMode::Max).with_headline("claude", WindowSlot::Primary);
+        let snapshot = aggregate
+            .snapshot(&snapshots(), &ids(&["claude", "openai"]))
+            .unwrap();
+        assert_eq!(snapshot.max_usage(), 30.0);
+
+        // A missing window falls back to the highest one
+        let aggregate = UsageAggregate::default().with_headline("openai", WindowSlot::Tertiary);
+        let snapshot = aggregate.snapshot(&snapshots(), &ids(&["openai"])).unwrap();
+        assert_eq!(snapshot.max_usage(), 10.0);
+    }
+
+    #[test]
+    fn test_aggregate_zero_weights() {
+        let aggregate = UsageAggregate::new(AggregateMode::Average)
+            .with_weight("claude", 0.0)
+            .with_weight("openai", 0.0);
+        let snapshot = aggregate
+            .snapshot(&snapshots(), &ids(&["claude", "openai"]))
+            .unwrap();
+        assert_eq!(snapshot.max_usage(), 0.0);
+    }
+}
@@ -0,0 +1,332 @@
+//! Per-provider authentication state
+//!
+//! `Provider::is_available` only answers yes or no. [`AuthState`] tells
+//! apart credentials that were found but not used yet, that a fetch
+//! accepted, that the service refused, that were never there and that were
+//! logged out. [`AuthStateTracker`] keeps each provider's state and moves it
+//! along as [`AuthObservation`]s come in from credential checks, fetches,
+//! logins and logouts, publishing [`AppEvent::AuthStateChanged`] when it
+//! changes.
+//!
+//! Credential checks read files and the keychain, and the popup asks for
+//! them every time it opens, so a check is reused for a while.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::base::{AuthMethod, Provider, ProviderError, UsageSnapshot};
+use crate::events::{self, AppEvent};
+
+/// How long a credential check is reused
+pub const AUTH_CHECK_TTL: Duration = Duration::from_secs(60);
+
+/// What is known about a provider's credentials
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuthState {
+    /// Not checked yet
+    #[default]
+    Unknown,
+    /// Credentials were found but no fetch has used them yet
+    Discovered {
+        /// How the provider would sign in
+        source: AuthMethod,
+    },
+    /// The last fetch was accepted
+    Valid {
+        /// When the credentials stop working, if the provider knows
+        expires_at: Option<DateTime<Utc>>,
+    },
+    /// The service refused the credentials
+    Expired,
+    /// No credentials were found
+    Missing,
+    /// Logged out in GPTBar; found credentials are ignored until login
+    Revoked,
+}
+
+impl AuthState {
+    /// Whether the provider can be fetched in this state
+    pub fn is_usable(&self) -> bool {
+        matches!(self, Self::Discovered { .. } | Self::Valid { .. })
+    }
+
+    /// The state after `observation`
+    ///
+    /// Routine credential checks only see whether credentials are there, so
+    /// they don't overrule what a fetch learned about them.
+    pub fn next(&self, observation: &AuthObservation) -> AuthState {
+        match observation {
+            AuthObservation::Checked(Some(source)) => match self {
+                Self::Valid { .. } | Self::Expired => self.clone(),
+                _ => Self::Discovered { source: *source },
+            },
+            AuthObservation::Checked(None) => match self {
+                Self::Revoked => Self::Revoked,
+                _ => Self::Missing,
+            },
+            AuthObservation::CredentialsChanged(Some(source)) => {
+                Self::Discovered { source: *source }
+            }
+            AuthObservation::CredentialsChanged(None) => Self::Missing,
+            AuthObservation::FetchSucceeded { expires_at } => Self::Valid {
+                expires_at: *expires_at,
+            },
+            AuthObservation::FetchRefused => Self::Expired,
+            AuthObservation::FetchUnauthenticated => match self {
+                Self::Revoked => Self::Revoked,
+                _ => Self::Missing,
+            },
+            AuthObservation::LoggedOut => Self::Revoked,
+        }
+    }
+}
+
+/// Something that tells about a provider's credentials
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthObservation {
+    /// A routine check found credentials for a method, or none
+    Checked(Option<AuthMethod>),
+    /// Credentials were checked right after a login or a saved key
+    CredentialsChanged(Option<AuthMethod>),
+    /// A fetch was accepted
+    FetchSucceeded { expires_at: Option<DateTime<Utc>> },
+    /// A fetch was refused for its credentials
+    FetchRefused,
+    /// A fetch found no credentials to send
+    FetchUnauthenticated,
+    /// The user logged out in GPTBar
+    LoggedOut,
+}
+
+impl AuthObservation {
+    /// What a fetch result tells, if anything
+    ///
+    /// Failures unrelated to credentials, like network errors, tell nothing.
+    pub fn of_fetch(
+        result: &Result<UsageSnapshot, ProviderError>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Option<Self> {
+        match result {
+            Ok(_) => Some(Self::FetchSucceeded { expires_at }),
+            Err(ProviderError::AuthFailed(_)) => Some(Self::FetchRefused),
+            Err(ProviderError::AuthRequired) => Some(Self::FetchUnauthenticated),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Last known authentication state of each provider
+pub struct AuthStateTracker {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (AuthState, Instant)>>,
+}
+
+impl AuthStateTracker {
+    /// Creates an empty tracker reusing credential checks for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns a provider's state, checking its credentials only when the
+    /// last observation is missing or expired
+    pub async fn check(&self, provider: &dyn Provider) -> AuthState {
+        if let Some(state) = self.get(provider.id()) {
+            return state;
+        }
+        self.recheck(provider).await
+    }
+
+    /// Checks a provider's credentials, ignoring the last observation
+    pub async fn recheck(&self, provider: &dyn Provider) -> AuthState {
+        let found = provider.check_credentials().await;
+        self.observe(provider.id(), &AuthObservation::Checked(found))
+    }
+
+    /// Checks a provider's credentials after they were changed in GPTBar,
+    /// dropping what earlier fetches learned
+    pub async fn credentials_changed(&self, provider: &dyn Provider) -> AuthState {
+        let found = provider.check_credentials().await;
+        self.observe(provider.id(), &AuthObservation::CredentialsChanged(found))
+    }
+
+    /// A provider's state, if observed recently
+    pub fn get(&self, provider_id: &str) -> Option<AuthState> {
+        self.lock()
+            .get(provider_id)
+            .filter(|(_, observed_at)| observed_at.elapsed() < self.ttl)
+            .map(|(state, _)| state.clone())
+    }
+
+    /// A provider's last known state, however old
+    pub fn state(&self, provider_id: &str) -> AuthState {
+        self.lock()
+            .get(provider_id)
+            .map(|(state, _)| state.clone())
+            .unwrap_or_default()
+    }
+
+    /// Applies an observation to a provider's state, publishing a change
+    /// event when the state differs from the last known one
+    ///
+    /// Returns the new state. The first observation of a provider is not a
+    /// change.
+    pub fn observe(&self, provider_id: &str, observation: &AuthObservation) -> AuthState {
+        let mut entries = self.lock();
+        let previous = entries.get(provider_id).map(|(state, _)| state.clone());
+        let state = previous.clone().unwrap_or_default().next(observation);
+        entries.insert(provider_id.to_string(), (state.clone(), Instant::now()));
+        drop(entries);
+
+        if let Some(previous) = previous.filter(|previous| *previous != state) {
+            tracing::info!("{} auth state: {:?} -> {:?}", provider_id, previous, state);
+            events::publish(AppEvent::AuthStateChanged {
+                provider_id: provider_id.to_string(),
+                available: state.is_usable(),
+                state: state.clone(),
+                previous,
+            });
+        }
+        state
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (AuthState, Instant)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for AuthStateTracker {
+    fn default() -> Self {
+        Self::new(AUTH_CHECK_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::DemoProvider;
+
+    #[test]
+    fn test_transitions() {
+        let found = AuthObservation::Checked(Some(AuthMethod::OAuth));
+        let discovered = AuthState::Unknown.next(&found);
+        assert_eq!(
+            discovered,
+            AuthState::Discovered {
+                source: AuthMethod::OAuth
+            }
+        );
+
+        // A check doesn't overrule what a fetch learned
+        let valid = discovered.next(&AuthObservation::FetchSucceeded { expires_at: None });
+        assert_eq!(valid.next(&found), valid);
+        let expired = valid.next(&AuthObservation::FetchRefused);
+        assert_eq!(expired.next(&found), AuthState::Expired);
+        assert!(!expired.is_usable());
+
+        // A login does
+        assert!(expired
+            .next(&AuthObservation::CredentialsChanged(Some(
+                AuthMethod::OAuth
+            )))
+            .is_usable());
+
+        // Logged out stays logged out until credentials change
+        let revoked = valid.next(&AuthObservation::LoggedOut);
+        assert_eq!(
+            revoked.next(&AuthObservation::Checked(None)),
+            AuthState::Revoked
+        );
+        assert_eq!(
+            revoked.next(&AuthObservation::FetchUnauthenticated),
+            AuthState::Revoked
+        );
+        assert_eq!(
+            AuthState::Unknown.next(&AuthObservation::Checked(None)),
+            AuthState::Missing
+        );
+    }
+
+    #[test]
+    fn test_observe_publishes_changes() {
+        let tracker = AuthStateTracker::default();
+        let mut events = events::subscribe();
+
+        tracker.observe(
+            "test-auth-state",
+            &AuthObservation::FetchSucceeded { expires_at: None },
+        );
+        tracker.observe(
+            "test-auth-state",
+            &AuthObservation::FetchSucceeded { expires_at: None },
+        );
+        tracker.observe("test-auth-state", &AuthObservation::FetchRefused);
+        assert_eq!(tracker.get("test-auth-state"), Some(AuthState::Expired));
+
+        // Other tests publish on the same bus
+        let changes: Vec<(AuthState, AuthState, bool)> =
+            std::iter::from_fn(|| events.try_recv().ok())
+                .filter_map(|event| match event {
+                    AppEvent::AuthStateChanged {
+                        provider_id,
+                        state,
+                        previous,
+                        available,
+                    } if provider_id == "test-auth-state" => Some((previous, state, available)),
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(
+            changes,
+            vec![(
+                AuthState::Valid { expires_at: None },
+                AuthState::Expired,
+                false
+            )]
+        );
+    }
+
+    #[test]
+    fn test_expired_observations_are_not_used() {
+        let tracker = AuthStateTracker::new(Duration::ZERO);
+        tracker.observe("claude", &AuthObservation::LoggedOut);
+        assert_eq!(tracker.get("claude"), None);
+        assert_eq!(tracker.state("claude"), AuthState::Revoked);
+        assert_eq!(tracker.state("openai"), AuthState::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_check_uses_last_observation() {
+        let tracker = AuthStateTracker::default();
+        let provider = DemoProvider::new();
+
+        // The demo provider always has credentials; a recent fetch wins
+        tracker.observe(provider.id(), &AuthObservation::FetchRefused);
+        assert_eq!(tracker.check(&provider).await, AuthState::Expired);
+        assert_eq!(tracker.recheck(&provider).await, AuthState::Expired);
+        assert!(tracker.credentials_changed(&provider).await.is_usable());
+        assert!(tracker.check(&provider).await.is_usable());
+    }
+
+    #[test]
+    fn test_serialization() {
+        assert_eq!(
+            serde_json::to_value(AuthState::Discovered {
+                source: AuthMethod::ApiToken
+            })
+            .unwrap(),
+            serde_json::json!({ "status": "discovered", "source": "ApiToken" })
+        );
+        assert_eq!(
+            serde_json::to_value(AuthState::Revoked).unwrap(),
+            serde_json::json!({ "status": "revoked" })
+        );
+    }
+}
@@ -3,10 +3,14 @@
 //! Defines the core abstractions used by all providers following SOLID principles.
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use thiserror::Error;
 
+use super::discovery::CredentialProbe;
+use crate::format::{format_reset, ResetStyle};
+
 /// Represents a rate limit window with usage information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RateWindow {
@@ -18,6 +22,23 @@ pub struct RateWindow {
     pub resets_at: Option<DateTime<Utc>>,
     /// Human-readable reset description
     pub reset_description: Option<String>,
+    /// Change in `used_percent` since the previous fetch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_percent: Option<f64>,
+    /// Spend counted so far, for windows measuring spend against a budget
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Usage compared to the time elapsed, for windows with a known length
+    /// and reset time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pace: Option<Pace>,
+    /// `resets_at` formatted for display, filled in when served to the UI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_text: Option<String>,
+    /// Level of `used_percent` against the provider's thresholds, filled in
+    /// when served to the UI so it can say what the colors mean
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
 }
 
 impl RateWindow {
@@ -28,6 +49,11 @@ impl RateWindow {
             window_minutes: None,
             resets_at: None,
             reset_description: None,
+            delta_percent: None,
+            cost_usd: None,
+            pace: None,
+            reset_text: None,
+            severity: None,
         }
     }
 
@@ -49,14 +75,64 @@ impl RateWindow {
         self
     }
 
-    /// Returns true if usage is at warning level (>= 80%)
-    pub fn is_warning(&self) -> bool {
-        self.used_percent >= 80.0
+    /// Sets the spend counted so far
+    pub fn with_cost_usd(mut self, cost_usd: f64) -> Self {
+        self.cost_usd = Some(cost_usd);
+        self
+    }
+
+    /// Sets the change in usage since the previous fetch
+    pub fn with_delta_percent(mut self, delta: f64) -> Self {
+        self.delta_percent = Some(delta);
+        self
+    }
+
+    /// Returns true if usage is at the warning level
+    pub fn is_warning(&self, thresholds: &Thresholds) -> bool {
+        self.used_percent >= thresholds.warning_percent
     }
 
-    /// Returns true if usage is at critical level (>= 95%)
-    pub fn is_critical(&self) -> bool {
-        self.used_percent >= 95.0
+    /// Returns true if usage is at the critical level
+    pub fn is_critical(&self, thresholds: &Thresholds) -> bool {
+        self.used_percent >= thresholds.critical_percent
+    }
+
+    /// Compares usage to the share of the window elapsed at `now`
+    ///
+    /// Needs both the window length and its reset time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use gptbar_lib::providers::{PaceStatus, RateWindow};
+    ///
+    /// // 70% used with 40% of the week elapsed
+    /// let now = Utc::now();
+    /// let window = RateWindow::new(70.0)
+    ///     .with_window_minutes(10_000)
+    ///     .with_resets_at(now + Duration::minutes(6_000));
+    ///
+    /// let pace = window.pace_at(now).unwrap();
+    /// assert_eq!(pace.elapsed_percent, 40.0);
+    /// assert_eq!(pace.status, PaceStatus::Ahead);
+    /// ```
+    pub fn pace_at(&self, now: DateTime<Utc>) -> Option<Pace> {
+        let minutes = self.window_minutes.filter(|m| *m > 0)?;
+        let left = (self.resets_at? - now).num_seconds() as f64 / 60.0;
+        let elapsed_percent = ((1.0 - left / minutes as f64) * 100.0).clamp(0.0, 100.0);
+
+        let status = if self.used_percent > elapsed_percent + PACE_TOLERANCE_PERCENT {
+            PaceStatus::Ahead
+        } else if self.used_percent < elapsed_percent - PACE_TOLERANCE_PERCENT {
+            PaceStatus::Behind
+        } else {
+            PaceStatus::OnPace
+        };
+        Some(Pace {
+            elapsed_percent,
+            status,
+        })
     }
 }
 
@@ -66,6 +142,120 @@ impl Default for RateWindow {
     }
 }
 
+/// Percentage points usage may differ from the elapsed time and still be
+/// on pace
+pub const PACE_TOLERANCE_PERCENT: f64 = 10.0;
+
+/// Whether usage would run out before the window resets at this rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaceStatus {
+    /// Using less than the time elapsed
+    Behind,
+    /// Using about as much as the time elapsed
+    OnPace,
+    /// Using more than the time elapsed; the limit comes before the reset
+    Ahead,
+}
+
+/// A window's usage compared to the time elapsed in it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pace {
+    /// Share of the window's time elapsed (0.0 - 100.0)
+    pub elapsed_percent: f64,
+    pub status: PaceStatus,
+}
+
+/// Usage levels at which a window counts as warning or critical
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Thresholds {
+    /// Usage percentage of the warning level
+    pub warning_percent: f64,
+    /// Usage percentage of the critical level
+    pub critical_percent: f64,
+}
+
+impl Thresholds {
+    /// Creates thresholds with custom warning and critical levels
+    pub fn new(warning_percent: f64, critical_percent: f64) -> Self {
+        Self {
+            warning_percent,
+            critical_percent,
+        }
+    }
+
+    /// Level of a usage percentage
+    pub fn severity(&self, percent: f64) -> Severity {
+        if percent >= self.critical_percent {
+            Severity::Critical
+        } else if percent >= self.warning_percent {
+            Severity::Warning
+        } else {
+            Severity::Normal
+        }
+    }
+}
+
+/// How close a window is to its limit, as shown by its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Default for Thresholds {
+    /// Warning at 80%, critical at 95%
+    fn default() -> Self {
+        Self::new(80.0, 95.0)
+    }
+}
+
+/// One of the three rate windows of a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowSlot {
+    Primary,
+    Secondary,
+    Tertiary,
+}
+
+impl WindowSlot {
+    /// All slots, in display order
+    pub const ALL: [WindowSlot; 3] = [Self::Primary, Self::Secondary, Self::Tertiary];
+}
+
+/// Threshold overrides for one provider
+///
+/// A window's own override wins over the provider-wide one; windows with
+/// neither use the global thresholds.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProviderThresholds {
+    /// Thresholds for all of the provider's windows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all: Option<Thresholds>,
+    /// Thresholds for single windows
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub windows: HashMap<WindowSlot, Thresholds>,
+}
+
+impl ProviderThresholds {
+    /// Returns the thresholds that apply to a window
+    pub fn resolve(&self, slot: WindowSlot, global: Thresholds) -> Thresholds {
+        self.windows
+            .get(&slot)
+            .copied()
+            .or(self.all)
+            .unwrap_or(global)
+    }
+
+    /// Whether nothing is overridden
+    pub fn is_empty(&self) -> bool {
+        self.all.is_none() && self.windows.is_empty()
+    }
+}
+
 /// Identity information for a provider account
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct IdentitySnapshot {
@@ -102,6 +292,178 @@ impl IdentitySnapshot {
     }
 }
 
+/// Token counts with their estimated cost
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TokenUsage {
+    /// Uncached input tokens
+    pub input_tokens: u64,
+    /// Output tokens
+    pub output_tokens: u64,
+    /// Tokens written to the prompt cache
+    pub cache_creation_tokens: u64,
+    /// Tokens read from the prompt cache
+    pub cache_read_tokens: u64,
+    /// Estimated cost in US dollars
+    pub cost_usd: f64,
+}
+
+impl TokenUsage {
+    /// Returns the sum of all token counts
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+
+    /// Adds another usage to this one
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// Usage attributed to a single model
+///
+/// Providers fill in whichever measures they have: a share of a limit,
+/// a token count, a cost, or several of these.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelUsage {
+    /// Model name as reported by the provider
+    pub model: String,
+    /// Share of the provider's limit used by this model (0.0 - 100.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>,
+    /// Tokens used by this model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+    /// Cost in US dollars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+impl ModelUsage {
+    /// Creates an entry for a model with no measures set
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            percent: None,
+            tokens: None,
+            cost_usd: None,
+        }
+    }
+
+    /// Sets the share of the limit used
+    pub fn with_percent(mut self, percent: f64) -> Self {
+        self.percent = Some(percent);
+        self
+    }
+
+    /// Sets the token count
+    pub fn with_tokens(mut self, tokens: u64) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Sets the cost
+    pub fn with_cost_usd(mut self, cost_usd: f64) -> Self {
+        self.cost_usd = Some(cost_usd);
+        self
+    }
+}
+
+/// Usage logged locally for one project on one day
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DailyUsage {
+    /// Local calendar day
+    pub date: NaiveDate,
+    /// Project name (last component of the working directory)
+    pub project: String,
+    /// Tokens and cost for the day
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+}
+
+/// Usage computed from local CLI logs rather than the provider's API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct LocalUsage {
+    /// Per-project, per-day totals, ordered by date then project
+    pub daily: Vec<DailyUsage>,
+}
+
+impl LocalUsage {
+    /// Returns the combined usage of all projects on a day
+    pub fn total_for(&self, date: NaiveDate) -> TokenUsage {
+        let mut total = TokenUsage::default();
+        for day in self.daily.iter().filter(|d| d.date == date) {
+            total.add(&day.usage);
+        }
+        total
+    }
+
+    /// Sums each project's usage over all days, most expensive first
+    pub fn by_project(&self, provider_id: &str) -> Vec<ProjectUsage> {
+        let mut totals: BTreeMap<&str, TokenUsage> = BTreeMap::new();
+        for day in &self.daily {
+            totals.entry(&day.project).or_default().add(&day.usage);
+        }
+        let mut projects: Vec<_> = totals
+            .into_iter()
+            .map(|(project, usage)| ProjectUsage {
+                provider_id: provider_id.to_string(),
+                project: project.to_string(),
+                usage,
+            })
+            .collect();
+        ProjectUsage::sort_by_cost(&mut projects);
+        projects
+    }
+}
+
+/// Usage logged locally for one project over several days
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProjectUsage {
+    /// Provider whose CLI logged the usage
+    pub provider_id: String,
+    /// Project name (last component of the working directory)
+    pub project: String,
+    /// Tokens and cost summed over the days
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+}
+
+impl ProjectUsage {
+    /// Orders projects by cost, then by tokens for unpriced usage
+    pub fn sort_by_cost(projects: &mut [ProjectUsage]) {
+        projects.sort_by(|a, b| {
+            b.usage
+                .cost_usd
+                .partial_cmp(&a.usage.cost_usd)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.usage.total_tokens().cmp(&a.usage.total_tokens()))
+        });
+    }
+}
+
+/// How far a snapshot has to move from the previous one to count as changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangeTolerance {
+    /// Largest change of a window's usage percentage that is ignored
+    pub percent: f64,
+    /// Largest shift of a window's reset time that is ignored; some
+    /// providers report resets relative to the request time, so they drift
+    pub reset_shift: chrono::Duration,
+}
+
+impl Default for ChangeTolerance {
+    fn default() -> Self {
+        Self {
+            percent: 0.0,
+            reset_shift: chrono::Duration::minutes(1),
+        }
+    }
+}
+
 /// A snapshot of usage data from a provider
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UsageSnapshot {
@@ -115,6 +477,18 @@ pub struct UsageSnapshot {
     pub updated_at: DateTime<Utc>,
     /// Account identity information
     pub identity: Option<IdentitySnapshot>,
+    /// Seconds since the snapshot the window deltas were computed against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_seconds: Option<i64>,
+    /// Token and cost usage parsed from local CLI logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_usage: Option<LocalUsage>,
+    /// Per-model breakdown, largest first, when the provider has one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_model: Vec<ModelUsage>,
+    /// Where the data came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SnapshotSource>,
 }
 
 impl UsageSnapshot {
@@ -126,6 +500,10 @@ impl UsageSnapshot {
             tertiary: None,
             updated_at: Utc::now(),
             identity: None,
+            delta_seconds: None,
+            local_usage: None,
+            by_model: Vec::new(),
+            source: None,
         }
     }
 
@@ -153,6 +531,118 @@ impl UsageSnapshot {
         self
     }
 
+    /// Sets usage parsed from local CLI logs
+    pub fn with_local_usage(mut self, local_usage: LocalUsage) -> Self {
+        self.local_usage = Some(local_usage);
+        self
+    }
+
+    /// Sets the per-model breakdown
+    pub fn with_model_usage(mut self, by_model: Vec<ModelUsage>) -> Self {
+        self.by_model = by_model;
+        self
+    }
+
+    /// Records the origin of the snapshot's main data, fetched now
+    pub fn with_source(mut self, kind: UsageSource) -> Self {
+        self.source = Some(SnapshotSource::new(kind));
+        self
+    }
+
+    /// Records another origin merged into the snapshot
+    ///
+    /// Becomes the main source if none was set.
+    pub fn with_supplement(mut self, kind: UsageSource) -> Self {
+        match &mut self.source {
+            Some(source) => source.supplements.push(kind),
+            None => self.source = Some(SnapshotSource::new(kind)),
+        }
+        self
+    }
+
+    /// Marks the snapshot as served from cache
+    ///
+    /// The original source moves to the front of `supplements`; `fetched_at`
+    /// keeps the time of the original fetch.
+    pub fn into_cached(mut self) -> Self {
+        let source = self
+            .source
+            .get_or_insert_with(|| SnapshotSource::new(UsageSource::Cached));
+        if source.kind != UsageSource::Cached {
+            let original = std::mem::replace(&mut source.kind, UsageSource::Cached);
+            source.supplements.insert(0, original);
+        }
+        self
+    }
+
+    /// Fills in per-window deltas relative to a previous snapshot
+    ///
+    /// Windows missing from either snapshot get no delta.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gptbar_lib::providers::{RateWindow, UsageSnapshot};
+    ///
+    /// let previous = UsageSnapshot::new().with_primary(RateWindow::new(40.0));
+    /// let current = UsageSnapshot::new()
+    ///     .with_primary(RateWindow::new(47.0))
+    ///     .with_deltas_from(&previous);
+    ///
+    /// assert_eq!(current.primary.unwrap().delta_percent, Some(7.0));
+    /// ```
+    pub fn with_deltas_from(mut self, previous: &UsageSnapshot) -> Self {
+        fn delta(current: &mut Option<RateWindow>, previous: &Option<RateWindow>) {
+            if let (Some(current), Some(previous)) = (current.as_mut(), previous.as_ref()) {
+                current.delta_percent = Some(current.used_percent - previous.used_percent);
+            }
+        }
+
+        delta(&mut self.primary, &previous.primary);
+        delta(&mut self.secondary, &previous.secondary);
+        delta(&mut self.tertiary, &previous.tertiary);
+        self.delta_seconds = Some((self.updated_at - previous.updated_at).num_seconds());
+        self
+    }
+
+    /// Fills in each window's `pace` at `now`
+    pub fn with_pace_at(mut self, now: DateTime<Utc>) -> Self {
+        for window in [&mut self.primary, &mut self.secondary, &mut self.tertiary]
+            .into_iter()
+            .flatten()
+        {
+            window.pace = window.pace_at(now);
+        }
+        self
+    }
+
+    /// Fills in each window's `reset_text` in the user's locale and timezone
+    pub fn with_reset_text(mut self, style: ResetStyle) -> Self {
+        for window in [&mut self.primary, &mut self.secondary, &mut self.tertiary]
+            .into_iter()
+            .flatten()
+        {
+            window.reset_text = window.resets_at.map(|at| format_reset(at, style));
+        }
+        self
+    }
+
+    /// Fills in each window's `severity` from the provider's thresholds,
+    /// falling back to `global`
+    pub fn with_severity(mut self, thresholds: &ProviderThresholds, global: Thresholds) -> Self {
+        for (slot, window) in [
+            (WindowSlot::Primary, &mut self.primary),
+            (WindowSlot::Secondary, &mut self.secondary),
+            (WindowSlot::Tertiary, &mut self.tertiary),
+        ] {
+            if let Some(window) = window {
+                let levels = thresholds.resolve(slot, global);
+                window.severity = Some(levels.severity(window.used_percent));
+            }
+        }
+        self
+    }
+
     /// Returns the highest usage percentage across all windows
     pub fn max_usage(&self) -> f64 {
         [
@@ -165,18 +655,100 @@ impl UsageSnapshot {
         .fold(0.0, f64::max)
     }
 
-    /// Returns true if any window is at warning level
-    pub fn has_warning(&self) -> bool {
-        self.primary.as_ref().map_or(false, |w| w.is_warning())
-            || self.secondary.as_ref().map_or(false, |w| w.is_warning())
-            || self.tertiary.as_ref().map_or(false, |w| w.is_warning())
+    /// Returns the usage of the headline window
+    ///
+    /// `None`, or a window this snapshot doesn't have, falls back to the
+    /// highest usage.
+    pub fn headline_usage(&self, slot: Option<WindowSlot>) -> f64 {
+        let window = match slot {
+            Some(WindowSlot::Primary) => self.primary.as_ref(),
+            Some(WindowSlot::Secondary) => self.secondary.as_ref(),
+            Some(WindowSlot::Tertiary) => self.tertiary.as_ref(),
+            None => None,
+        };
+        window.map_or_else(|| self.max_usage(), |w| w.used_percent)
+    }
+
+    /// Returns the windows that are present, with their slots
+    pub fn windows(&self) -> impl Iterator<Item = (WindowSlot, &RateWindow)> {
+        [
+            (WindowSlot::Primary, self.primary.as_ref()),
+            (WindowSlot::Secondary, self.secondary.as_ref()),
+            (WindowSlot::Tertiary, self.tertiary.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(slot, window)| window.map(|w| (slot, w)))
+    }
+
+    /// Returns true if any window is at its warning level
+    pub fn has_warning(&self, thresholds: &ProviderThresholds, global: Thresholds) -> bool {
+        self.windows()
+            .any(|(slot, w)| w.is_warning(&thresholds.resolve(slot, global)))
+    }
+
+    /// Returns true if any window is at its critical level
+    pub fn has_critical(&self, thresholds: &ProviderThresholds, global: Thresholds) -> bool {
+        self.windows()
+            .any(|(slot, w)| w.is_critical(&thresholds.resolve(slot, global)))
+    }
+
+    /// Returns true if the snapshot is older than `max_age` at `now`
+    pub fn is_stale(&self, max_age: chrono::Duration, now: DateTime<Utc>) -> bool {
+        now - self.updated_at > max_age
     }
 
-    /// Returns true if any window is at critical level
-    pub fn has_critical(&self) -> bool {
-        self.primary.as_ref().map_or(false, |w| w.is_critical())
-            || self.secondary.as_ref().map_or(false, |w| w.is_critical())
-            || self.tertiary.as_ref().map_or(false, |w| w.is_critical())
+    /// Returns true if the snapshot moved beyond `tolerance` since `previous`
+    ///
+    /// Usage and reset times count once they move past the tolerance. Any
+    /// other change to the windows, identity, local usage, per-model
+    /// breakdown or data origin always counts. Fetch times and deltas are
+    /// ignored, since they differ on every fetch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gptbar_lib::providers::{ChangeTolerance, RateWindow, UsageSnapshot};
+    ///
+    /// let tolerance = ChangeTolerance { percent: 0.5, ..Default::default() };
+    /// let previous = UsageSnapshot::new().with_primary(RateWindow::new(40.0));
+    ///
+    /// let same = UsageSnapshot::new().with_primary(RateWindow::new(40.2));
+    /// assert!(!same.changed_since(&previous, &tolerance));
+    ///
+    /// let moved = UsageSnapshot::new().with_primary(RateWindow::new(41.0));
+    /// assert!(moved.changed_since(&previous, &tolerance));
+    /// ```
+    pub fn changed_since(&self, previous: &UsageSnapshot, tolerance: &ChangeTolerance) -> bool {
+        fn window_changed(
+            current: &Option<RateWindow>,
+            previous: &Option<RateWindow>,
+            tolerance: &ChangeTolerance,
+        ) -> bool {
+            let (current, previous) = match (current, previous) {
+                (Some(current), Some(previous)) => (current, previous),
+                (None, None) => return false,
+                _ => return true,
+            };
+            let reset_moved = match (current.resets_at, previous.resets_at) {
+                (Some(current), Some(previous)) => {
+                    (current - previous).abs() > tolerance.reset_shift
+                }
+                (current, previous) => current != previous,
+            };
+            (current.used_percent - previous.used_percent).abs() > tolerance.percent
+                || reset_moved
+                || current.window_minutes != previous.window_minutes
+                || current.reset_description != previous.reset_description
+        }
+
+        window_changed(&self.primary, &previous.primary, tolerance)
+            || window_changed(&self.secondary, &previous.secondary, tolerance)
+            || window_changed(&self.tertiary, &previous.tertiary, tolerance)
+            || self.identity != previous.identity
+            || self.local_usage != previous.local_usage
+            || self.by_model != previous.by_model
+            || self.source.as_ref().map(|s| (s.kind, &s.supplements))
+                != previous.source.as_ref().map(|s| (s.kind, &s.supplements))
     }
 }
 
@@ -186,6 +758,50 @@ impl Default for UsageSnapshot {
     }
 }
 
+/// Origin of usage data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageSource {
+    /// Provider API with an OAuth token
+    #[serde(rename = "oauth_api")]
+    OAuthApi,
+    /// Provider API with an API key
+    ApiKey,
+    /// Organization admin/reporting API
+    AdminApi,
+    /// Web dashboard scraped with browser cookies
+    Cookie,
+    /// Estimated from local CLI logs
+    LocalLogs,
+    /// A previously fetched snapshot
+    Cached,
+    /// Generated by the demo provider
+    Demo,
+}
+
+/// Provenance of a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotSource {
+    /// Origin of the main (primary window) data
+    pub kind: UsageSource,
+    /// When the data was fetched from that origin
+    pub fetched_at: DateTime<Utc>,
+    /// Other origins merged into the snapshot, e.g. local logs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supplements: Vec<UsageSource>,
+}
+
+impl SnapshotSource {
+    /// Creates a source fetched now
+    pub fn new(kind: UsageSource) -> Self {
+        Self {
+            kind,
+            fetched_at: Utc::now(),
+            supplements: Vec::new(),
+        }
+    }
+}
+
 /// Errors that can occur when fetching provider data
 #[derive(Debug, Error)]
 pub enum ProviderError {
@@ -220,6 +836,594 @@ pub enum ProviderError {
     /// Generic internal error
     #[error("Internal error: {0}")]
     Internal(String),
+
+    /// No provider is registered under the requested ID
+    #[error("Unknown provider: {0}")]
+    UnknownProvider(String),
+
+    /// Settings entered by the user were rejected
+    #[error("Invalid settings: {0}")]
+    InvalidSettings(String),
+
+    /// Credentials can't be changed or revealed in viewer mode
+    #[error("Not available in viewer mode")]
+    ViewerMode,
+
+    /// The provider is throttling our requests (HTTP 429)
+    #[error("Rate limited by the provider")]
+    RateLimited {
+        /// How long the provider asked us to wait, from `Retry-After`
+        retry_after: Option<std::time::Duration>,
+    },
+}
+
+/// Stable, machine-readable error codes sent to the frontend
+///
+/// These values are part of the IPC contract; rename with care.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderErrorCode {
+    AuthRequired,
+    AuthFailed,
+    Network,
+    Parse,
+    CookieExtraction,
+    Storage,
+    NotAvailable,
+    Internal,
+    UnknownProvider,
+    InvalidSettings,
+    ViewerMode,
+    RateLimited,
+}
+
+impl ProviderErrorCode {
+    /// Returns the serialized code string (e.g., "auth_failed")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AuthRequired => "auth_required",
+            Self::AuthFailed => "auth_failed",
+            Self::Network => "network",
+            Self::Parse => "parse",
+            Self::CookieExtraction => "cookie_extraction",
+            Self::Storage => "storage",
+            Self::NotAvailable => "not_available",
+            Self::Internal => "internal",
+            Self::UnknownProvider => "unknown_provider",
+            Self::InvalidSettings => "invalid_settings",
+            Self::ViewerMode => "viewer_mode",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+}
+
+/// Serializable form of a `ProviderError`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderErrorInfo {
+    /// Stable error code
+    pub code: ProviderErrorCode,
+    /// Human-readable message
+    pub message: String,
+    /// Seconds to wait before retrying, when the provider told us
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    /// Provider page that helps resolve the error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_url: Option<String>,
+    /// What the user can do to resolve the error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl ProviderError {
+    /// Returns the stable error code for this error
+    pub fn code(&self) -> ProviderErrorCode {
+        match self {
+            Self::AuthRequired => ProviderErrorCode::AuthRequired,
+            Self::AuthFailed(_) => ProviderErrorCode::AuthFailed,
+            Self::Network(_) => ProviderErrorCode::Network,
+            Self::Parse(_) => ProviderErrorCode::Parse,
+            Self::CookieExtraction(_) => ProviderErrorCode::CookieExtraction,
+            Self::Storage(_) => ProviderErrorCode::Storage,
+            Self::NotAvailable(_) => ProviderErrorCode::NotAvailable,
+            Self::Internal(_) => ProviderErrorCode::Internal,
+            Self::UnknownProvider(_) => ProviderErrorCode::UnknownProvider,
+            Self::InvalidSettings(_) => ProviderErrorCode::InvalidSettings,
+            Self::ViewerMode => ProviderErrorCode::ViewerMode,
+            Self::RateLimited { .. } => ProviderErrorCode::RateLimited,
+        }
+    }
+
+    /// Builds a [`ProviderError::RateLimited`] from the headers of a 429
+    /// response
+    pub fn rate_limited(headers: &reqwest::header::HeaderMap) -> Self {
+        let retry_after = headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, Utc::now()));
+        Self::RateLimited { retry_after }
+    }
+
+    /// Returns how long to wait before retrying, if known
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Returns what the user can do to resolve the error, in the active
+    /// language, if there is anything
+    pub fn remediation(&self) -> Option<String> {
+        let key = format!("remediation.{}", self.code().as_str());
+        crate::i18n::has(&key).then(|| crate::i18n::t(&key))
+    }
+
+    /// Like [`ProviderError::remediation`], preferring guidance specific to
+    /// a provider, e.g. which CLI to log in with or where to create a key
+    pub fn remediation_for(&self, provider_id: &str) -> Option<String> {
+        let key = format!("remediation.{}.{}", self.code().as_str(), provider_id);
+        if crate::i18n::has(&key) {
+            return Some(crate::i18n::t(&key));
+        }
+        self.remediation()
+    }
+
+    /// Returns the page of `provider` that helps resolve the error, if any
+    ///
+    /// Where to create a key, or the setup docs for other sign-ins, when
+    /// credentials are missing or refused; the rate limit docs when
+    /// throttled; the status page when the provider can't be reached.
+    pub fn doc_url(&self, provider: &dyn Provider) -> Option<&'static str> {
+        let pages: &[ProviderPage] = match self {
+            Self::AuthRequired | Self::AuthFailed(_) => match provider.auth_methods().first() {
+                Some(AuthMethod::ApiToken) => &[ProviderPage::ApiKeys, ProviderPage::Docs],
+                Some(AuthMethod::Cookie) => &[ProviderPage::Dashboard],
+                _ => &[ProviderPage::Docs],
+            },
+            Self::RateLimited { .. } => &[ProviderPage::Docs],
+            Self::Network(_) => &[ProviderPage::Status],
+            _ => &[],
+        };
+        pages.iter().find_map(|page| provider.page_url(*page))
+    }
+
+    /// Returns the HTTP status the provider answered with, if the error
+    /// came from an HTTP response
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            Self::Network(e) => e.status().map(|status| status.as_u16()),
+            Self::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+
+    /// Returns the inner detail message, if the variant carries one
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            Self::AuthRequired | Self::ViewerMode | Self::RateLimited { .. } => None,
+            Self::Network(e) => Some(e.to_string()),
+            Self::AuthFailed(d)
+            | Self::Parse(d)
+            | Self::CookieExtraction(d)
+            | Self::Storage(d)
+            | Self::NotAvailable(d)
+            | Self::Internal(d)
+            | Self::UnknownProvider(d)
+            | Self::InvalidSettings(d) => Some(d.clone()),
+        }
+    }
+
+    /// Returns the error message translated into the active language
+    pub fn localized_message(&self) -> String {
+        if let Some(wait) = self.retry_after() {
+            let retry_at =
+                chrono::Local::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+            let time = retry_at.format("%H:%M").to_string();
+            return crate::i18n::t_args("error.rate_limited_until", &[("time", &time)]);
+        }
+        let key = format!("error.{}", self.code().as_str());
+        let detail = self.detail().unwrap_or_default();
+        crate::i18n::t_args(&key, &[("detail", &detail)])
+    }
+
+    /// Converts the error into its serializable form
+    pub fn to_info(&self) -> ProviderErrorInfo {
+        ProviderErrorInfo {
+            code: self.code(),
+            message: crate::security::Sanitizer::sanitize_free_text(&self.localized_message()),
+            retry_after_secs: self.retry_after().map(|d| d.as_secs()),
+            doc_url: None,
+            remediation: self.remediation(),
+        }
+    }
+
+    /// Converts an error of a known provider into its serializable form,
+    /// with that provider's guidance and help page
+    pub fn to_info_for(&self, provider: &dyn Provider) -> ProviderErrorInfo {
+        ProviderErrorInfo {
+            doc_url: self.doc_url(provider).map(String::from),
+            remediation: self.remediation_for(provider.id()),
+            ..self.to_info()
+        }
+    }
+}
+
+impl From<ProviderError> for ProviderErrorInfo {
+    fn from(error: ProviderError) -> Self {
+        error.to_info()
+    }
+}
+
+/// Parses a `Retry-After` header value, either delay seconds or an HTTP date
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<std::time::Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    Some((at - now).to_std().unwrap_or_default())
+}
+
+/// API key entered for a provider in the settings
+///
+/// Read from the [`SecureStore`](crate::auth::SecureStore), or from the
+/// config file when the store has no copy, e.g. because the keychain was
+/// unavailable when it was saved.
+#[cfg_attr(not(any(feature = "desktop", feature = "claude")), allow(dead_code))]
+pub(crate) fn settings_api_key(provider_id: &str) -> Option<String> {
+    SettingsKeys::new().get(provider_id)
+}
+
+/// Where API keys entered in the settings are kept, and which providers
+/// were logged out
+///
+/// Keys go to the [`SecureStore`](crate::auth::SecureStore); the config
+/// file only holds one the store couldn't take, until the next start moves
+/// it over. Logouts are remembered in the config file, so they outlast a
+/// restart.
+pub(crate) struct SettingsKeys {
+    store: crate::auth::SecureStore,
+    config_dir: Option<std::path::PathBuf>,
+}
+
+impl SettingsKeys {
+    /// The app's secure store and config directory
+    pub fn new() -> Self {
+        Self {
+            store: crate::auth::SecureStore::new(),
+            config_dir: crate::config::AppConfig::config_dir(),
+        }
+    }
+
+    /// Keys and config kept in `dir`, away from the keychain and the app's
+    /// config
+    #[cfg(test)]
+    pub fn in_dir(dir: &std::path::Path) -> Self {
+        Self {
+            store: crate::auth::SecureStore::in_dir(dir),
+            config_dir: Some(dir.to_path_buf()),
+        }
+    }
+
+    fn load_config(&self) -> crate::config::AppConfig {
+        self.config_dir
+            .as_deref()
+            .map(crate::config::AppConfig::load_in)
+            .unwrap_or_default()
+    }
+
+    fn update_config(
+        &self,
+        provider_id: &str,
+        update: impl FnOnce(&mut crate::config::ProviderSettings) -> bool,
+    ) -> Result<(), ProviderError> {
+        let dir = self
+            .config_dir
+            .as_deref()
+            .ok_or_else(|| ProviderError::Storage("Could not determine config path".into()))?;
+        let mut config = crate::config::AppConfig::load_in(dir);
+        let settings = config
+            .provider_settings
+            .entry(provider_id.to_string())
+            .or_default();
+        if update(settings) {
+            config.save_in(dir).map_err(ProviderError::Storage)?;
+        }
+        Ok(())
+    }
+
+    /// The key entered for a provider
+    #[cfg_attr(
+        not(any(
+            feature = "desktop",
+            feature = "claude",
+            feature = "openai",
+            feature = "gemini",
+            feature = "codex",
+            feature = "together",
+            feature = "elevenlabs"
+        )),
+        allow(dead_code)
+    )]
+    pub fn get(&self, provider_id: &str) -> Option<String> {
+        use crate::auth::SecureStore;
+
+        self.store
+            .get_token(&SecureStore::api_key_entry(provider_id))
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to read the saved {} API key: {}", provider_id, e);
+                None
+            })
+            .or_else(|| self.load_config().get_provider_api_key(provider_id))
+    }
+
+    /// Whether a provider was logged out and no key was entered since
+    pub fn is_logged_out(&self, provider_id: &str) -> bool {
+        self.load_config()
+            .provider_settings
+            .get(provider_id)
+            .is_some_and(|s| s.logged_out)
+    }
+
+    /// Removes the key entered for a provider and remembers the logout
+    ///
+    /// Keys other apps keep in the keychain, such as an "openai" entry, are
+    /// left alone.
+    pub fn log_out(&self, provider_id: &str) -> Result<(), ProviderError> {
+        use crate::auth::SecureStore;
+
+        self.store
+            .delete_token(&SecureStore::api_key_entry(provider_id))
+            .map_err(|e| ProviderError::Storage(e.to_string()))?;
+
+        self.update_config(provider_id, |settings| {
+            settings.api_key = None;
+            settings.logged_out = true;
+            true
+        })
+    }
+
+    /// Forgets a provider's logout once a key is entered again
+    pub fn log_in(&self, provider_id: &str) -> Result<(), ProviderError> {
+        self.update_config(provider_id, |settings| {
+            std::mem::replace(&mut settings.logged_out, false)
+        })
+    }
+}
+
+/// The API key of a provider that takes one, cached once found
+///
+/// The key entered in the settings comes first, then wherever the provider
+/// looks for one. After a logout nothing is looked up, even after a
+/// restart, until a key is entered again.
+#[cfg_attr(
+    not(any(
+        feature = "openai",
+        feature = "gemini",
+        feature = "codex",
+        feature = "together",
+        feature = "elevenlabs"
+    )),
+    allow(dead_code)
+)]
+pub(crate) struct ApiKey {
+    provider_id: &'static str,
+    cached: tokio::sync::RwLock<Option<String>>,
+    keys: SettingsKeys,
+}
+
+#[cfg_attr(
+    not(any(
+        feature = "openai",
+        feature = "gemini",
+        feature = "codex",
+        feature = "together",
+        feature = "elevenlabs"
+    )),
+    allow(dead_code)
+)]
+impl ApiKey {
+    /// Creates the key of the provider with the given ID
+    pub fn new(provider_id: &'static str) -> Self {
+        Self {
+            provider_id,
+            cached: tokio::sync::RwLock::new(None),
+            keys: SettingsKeys::new(),
+        }
+    }
+
+    /// Reads entered keys and logouts from `keys`
+    #[cfg(test)]
+    pub fn with_keys(mut self, keys: SettingsKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// The key found last, if any
+    pub async fn cached(&self) -> Option<String> {
+        self.cached.read().await.clone()
+    }
+
+    /// Forgets the cached key, so the next load looks for one again
+    pub async fn clear(&self) {
+        *self.cached.write().await = None;
+    }
+
+    /// Uses `key` from now on, undoing a logout
+    pub async fn set(&self, key: &str) {
+        *self.cached.write().await = Some(key.to_string());
+        if let Err(e) = self.keys.log_in(self.provider_id) {
+            tracing::warn!("Failed to clear the {} logout: {}", self.provider_id, e);
+        }
+    }
+
+    /// The cached key, or else the first one found, in the settings or by
+    /// `discover`, which also names where it found it
+    pub async fn load(
+        &self,
+        discover: impl FnOnce() -> Option<(&'static str, String)>,
+    ) -> Option<String> {
+        if let Some(key) = self.cached().await {
+            return Some(key);
+        }
+
+        // After logout the key must be entered again explicitly
+        if self.keys.is_logged_out(self.provider_id) {
+            return None;
+        }
+
+        let (source, key) = match self.keys.get(self.provider_id) {
+            Some(key) => ("the settings", key),
+            None => discover()?,
+        };
+        tracing::info!("Found {} API key in {}", self.provider_id, source);
+        *self.cached.write().await = Some(key.clone());
+        Some(key)
+    }
+
+    /// Forgets the key and deletes the one entered in the settings, so a
+    /// key must be entered again
+    pub async fn log_out(&self) -> Result<(), ProviderError> {
+        *self.cached.write().await = None;
+        self.keys.log_out(self.provider_id)
+    }
+}
+
+/// Stores a freshly fetched snapshot as the provider's latest, filling in
+/// pace and deltas against the snapshot it replaces
+pub(crate) async fn record_snapshot(
+    last_snapshot: &tokio::sync::RwLock<Option<UsageSnapshot>>,
+    snapshot: UsageSnapshot,
+) -> UsageSnapshot {
+    let snapshot = snapshot.with_pace_at(Utc::now());
+    let mut last = last_snapshot.write().await;
+    let snapshot = match last.as_ref() {
+        Some(previous) => snapshot.with_deltas_from(previous),
+        None => snapshot,
+    };
+    *last = Some(snapshot.clone());
+    snapshot
+}
+
+/// Kind of value a provider setting holds, so the UI can pick an input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingKind {
+    /// Free-form text
+    Text,
+    /// API key; saved to the keychain and never sent back to the UI
+    Secret,
+    /// http(s) URL
+    Url,
+    /// Non-negative integer
+    Number,
+    /// One of the field's `options`
+    Choice,
+}
+
+/// Declarative description of a configurable provider field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SettingField {
+    /// Key used in the settings map (e.g., "api_base_url")
+    pub key: String,
+    /// Label shown in the settings UI
+    pub label: String,
+    /// Kind of value
+    pub kind: SettingKind,
+    /// Whether an empty value is rejected
+    pub required: bool,
+    /// Example value shown in an empty input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    /// Allowed values of a `Choice` field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+impl SettingField {
+    /// Creates an optional field
+    pub fn new(key: impl Into<String>, label: impl Into<String>, kind: SettingKind) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            kind,
+            required: false,
+            placeholder: None,
+            options: Vec::new(),
+        }
+    }
+
+    /// Marks the field as required
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Sets the placeholder
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Sets the allowed values of a `Choice` field
+    pub fn with_options(mut self, options: &[&str]) -> Self {
+        self.options = options.iter().map(|o| o.to_string()).collect();
+        self
+    }
+
+    /// Checks a non-empty value against the field's kind
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        match self.kind {
+            SettingKind::Text | SettingKind::Secret => Ok(()),
+            SettingKind::Url => match reqwest::Url::parse(value) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => Ok(()),
+                _ => Err(format!("{} must be an http(s) URL", self.label)),
+            },
+            SettingKind::Number => value
+                .parse::<u32>()
+                .map(|_| ())
+                .map_err(|_| format!("{} must be a whole number", self.label)),
+            SettingKind::Choice => {
+                if self.options.iter().any(|o| o == value) {
+                    Ok(())
+                } else {
+                    Err(format!("{} must be one of: {}", self.label, self.options.join(", ")))
+                }
+            }
+        }
+    }
+}
+
+/// Validates a settings map against a provider's schema
+///
+/// Unknown keys are rejected. An empty value clears the field, which is only
+/// allowed when it isn't required.
+pub fn validate_settings(
+    schema: &[SettingField],
+    values: &HashMap<String, String>,
+) -> Result<(), ProviderError> {
+    for (key, value) in values {
+        let field = schema
+            .iter()
+            .find(|f| &f.key == key)
+            .ok_or_else(|| ProviderError::InvalidSettings(format!("Unknown setting: {}", key)))?;
+
+        let value = value.trim();
+        if value.is_empty() {
+            if field.required {
+                return Err(ProviderError::InvalidSettings(format!(
+                    "{} is required",
+                    field.label
+                )));
+            }
+            continue;
+        }
+        field.validate(value).map_err(ProviderError::InvalidSettings)?;
+    }
+    Ok(())
 }
 
 /// Authentication method for a provider
@@ -237,6 +1441,35 @@ pub enum AuthMethod {
     None,
 }
 
+/// A provider web page the UI can open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProviderPage {
+    /// Usage overview
+    Dashboard,
+    /// Plans, invoices and payment settings
+    Billing,
+    /// Where API keys are created
+    ApiKeys,
+    /// Setup and API documentation
+    Docs,
+    /// Service status and incidents
+    Status,
+}
+
+impl ProviderPage {
+    /// Every page, in the order the UI lists them
+    pub fn all() -> &'static [ProviderPage] {
+        &[
+            Self::Dashboard,
+            Self::Billing,
+            Self::ApiKeys,
+            Self::Docs,
+            Self::Status,
+        ]
+    }
+}
+
 /// Result of a fetch operation
 #[derive(Debug, Clone)]
 pub struct FetchResult {
@@ -248,6 +1481,25 @@ pub struct FetchResult {
     pub is_cached: bool,
 }
 
+/// Brand color of providers that don't set their own
+pub const DEFAULT_BRAND_COLOR: &str = "#6b7280";
+
+/// Builds a square SVG icon showing `letter` on the brand color
+///
+/// Used for providers without artwork of their own.
+pub fn letter_icon(letter: char, brand_color: &str) -> String {
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 32 32">"#,
+            r#"<rect width="32" height="32" rx="8" fill="{}"/>"#,
+            r#"<text x="16" y="22" font-family="sans-serif" font-size="18" "#,
+            r#"font-weight="600" fill="white" text-anchor="middle">{}</text></svg>"#
+        ),
+        brand_color,
+        letter.to_uppercase()
+    )
+}
+
 /// Trait that all AI providers must implement
 ///
 /// This follows the Interface Segregation Principle - providers only need
@@ -263,11 +1515,47 @@ pub trait Provider: Send + Sync {
     /// Returns whether this provider is currently enabled
     fn is_enabled(&self) -> bool;
 
+    /// Brand color as a `#rrggbb` hex string, shared by the UI and tray icon
+    fn brand_color(&self) -> &'static str {
+        DEFAULT_BRAND_COLOR
+    }
+
+    /// Icon as SVG markup; defaults to the first letter of the name
+    fn icon_svg(&self) -> String {
+        letter_icon(
+            self.name().chars().next().unwrap_or('?'),
+            self.brand_color(),
+        )
+    }
+
     /// Returns whether this provider supports login flow
     fn supports_login(&self) -> bool {
         true
     }
 
+    /// URL of one of the provider's web pages, if it has one
+    fn page_url(&self, _page: ProviderPage) -> Option<&'static str> {
+        None
+    }
+
+    /// Pages [`Provider::page_url`] has a URL for
+    fn pages(&self) -> Vec<ProviderPage> {
+        ProviderPage::all()
+            .iter()
+            .copied()
+            .filter(|page| self.page_url(*page).is_some())
+            .collect()
+    }
+
+    /// Opens one of the provider's web pages in the default browser
+    fn open_page(&self, page: ProviderPage) -> Result<(), ProviderError> {
+        let url = self.page_url(page).ok_or_else(|| {
+            ProviderError::NotAvailable(format!("{} has no {:?} page", self.name(), page))
+        })?;
+        opener::open(url)
+            .map_err(|e| ProviderError::Internal(format!("Failed to open browser: {}", e)))
+    }
+
     /// Fetches the current usage data from the provider
     ///
     /// Implementations should try multiple auth methods in order of preference:
@@ -287,16 +1575,90 @@ pub trait Provider: Send + Sync {
     /// Checks if authentication is available for this provider
     async fn is_available(&self) -> bool;
 
+    /// Checks for credentials without using them, returning how the
+    /// provider would sign in
+    ///
+    /// Defaults to the first of [`Provider::auth_methods`] when
+    /// [`Provider::is_available`] finds credentials.
+    async fn check_credentials(&self) -> Option<AuthMethod> {
+        if !self.is_available().await {
+            return None;
+        }
+        Some(
+            self.auth_methods()
+                .first()
+                .copied()
+                .unwrap_or(AuthMethod::None),
+        )
+    }
+
+    /// When the credentials last used stop working, if the provider knows
+    async fn credentials_expire_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
     /// Returns the preferred authentication methods in order of preference
     fn auth_methods(&self) -> Vec<AuthMethod> {
         vec![AuthMethod::OAuth, AuthMethod::Cookie, AuthMethod::Cli]
     }
+
+    /// Describes the fields the settings UI renders for this provider
+    fn settings_schema(&self) -> Vec<SettingField> {
+        Vec::new()
+    }
+
+    /// Checks every place credentials are looked for, without using them
+    ///
+    /// Probes are listed in the order the provider tries them. May block on
+    /// file and keychain access.
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        Vec::new()
+    }
+
+    /// Validates and applies settings entered in the UI
+    ///
+    /// Only keys present in `values` change; an empty value restores the
+    /// field's default. Nothing is applied if any value is invalid.
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_letter_icon() {
+        let icon = letter_icon('c', "#d97706");
+        assert!(icon.starts_with("<svg"));
+        assert!(icon.contains(r##"fill="#d97706""##));
+        assert!(icon.contains(">C</text>"));
+    }
+
+    #[test]
+    fn test_snapshot_is_stale() {
+        let snapshot = UsageSnapshot::new();
+        let max_age = chrono::Duration::minutes(5);
+
+        assert!(!snapshot.is_stale(max_age, snapshot.updated_at + chrono::Duration::minutes(4)));
+        assert!(snapshot.is_stale(max_age, snapshot.updated_at + chrono::Duration::minutes(6)));
+    }
+
+    #[test]
+    fn test_provider_pages() {
+        let provider = crate::providers::DemoProvider::new();
+        assert!(provider.pages().is_empty());
+        assert!(matches!(
+            provider.open_page(ProviderPage::Docs),
+            Err(ProviderError::NotAvailable(_))
+        ));
+        assert_eq!(
+            serde_json::to_value(ProviderPage::ApiKeys).unwrap(),
+            "api-keys"
+        );
+    }
+
     #[test]
     fn test_rate_window_new() {
         let window = RateWindow::new(45.5);
@@ -321,13 +1683,70 @@ mod tests {
 
     #[test]
     fn test_rate_window_warning_levels() {
-        assert!(!RateWindow::new(79.9).is_warning());
-        assert!(RateWindow::new(80.0).is_warning());
-        assert!(RateWindow::new(85.0).is_warning());
+        let thresholds = Thresholds::default();
+        assert!(!RateWindow::new(79.9).is_warning(&thresholds));
+        assert!(RateWindow::new(80.0).is_warning(&thresholds));
+        assert!(RateWindow::new(85.0).is_warning(&thresholds));
+
+        assert!(!RateWindow::new(94.9).is_critical(&thresholds));
+        assert!(RateWindow::new(95.0).is_critical(&thresholds));
+        assert!(RateWindow::new(100.0).is_critical(&thresholds));
+    }
+
+    #[test]
+    fn test_rate_window_custom_thresholds() {
+        let thresholds = Thresholds::new(50.0, 75.0);
+        assert!(RateWindow::new(50.0).is_warning(&thresholds));
+        assert!(!RateWindow::new(74.9).is_critical(&thresholds));
+        assert!(RateWindow::new(75.0).is_critical(&thresholds));
+    }
+
+    #[test]
+    fn test_rate_window_pace() {
+        let now = Utc::now();
+        // 5h window with 3h left: 40% elapsed
+        let window = |percent| {
+            RateWindow::new(percent)
+                .with_window_minutes(300)
+                .with_resets_at(now + chrono::Duration::hours(3))
+        };
+        let status = |percent| window(percent).pace_at(now).unwrap().status;
+
+        assert_eq!(status(10.0), PaceStatus::Behind);
+        assert_eq!(status(45.0), PaceStatus::OnPace);
+        assert_eq!(status(70.0), PaceStatus::Ahead);
+
+        // Past the reset, the whole window has elapsed
+        let late = window(90.0)
+            .pace_at(now + chrono::Duration::hours(4))
+            .unwrap();
+        assert_eq!(late.elapsed_percent, 100.0);
+        assert_eq!(late.status, PaceStatus::OnPace);
+
+        let unknown_reset = RateWindow::new(70.0).with_window_minutes(300);
+        assert_eq!(unknown_reset.pace_at(now), None);
+    }
 
-        assert!(!RateWindow::new(94.9).is_critical());
-        assert!(RateWindow::new(95.0).is_critical());
-        assert!(RateWindow::new(100.0).is_critical());
+    #[test]
+    fn test_provider_thresholds_resolve() {
+        let global = Thresholds::default();
+        let mut thresholds = ProviderThresholds::default();
+        assert!(thresholds.is_empty());
+        assert_eq!(thresholds.resolve(WindowSlot::Primary, global), global);
+
+        thresholds.all = Some(Thresholds::new(70.0, 90.0));
+        thresholds
+            .windows
+            .insert(WindowSlot::Secondary, Thresholds::new(50.0, 80.0));
+
+        assert_eq!(
+            thresholds.resolve(WindowSlot::Primary, global),
+            Thresholds::new(70.0, 90.0)
+        );
+        assert_eq!(
+            thresholds.resolve(WindowSlot::Secondary, global),
+            Thresholds::new(50.0, 80.0)
+        );
     }
 
     #[test]
@@ -367,28 +1786,281 @@ mod tests {
         assert_eq!(snapshot.max_usage(), 80.0);
     }
 
+    #[test]
+    fn test_usage_snapshot_headline_usage() {
+        let snapshot = UsageSnapshot::new()
+            .with_primary(RateWindow::new(45.0))
+            .with_secondary(RateWindow::new(80.0));
+
+        assert_eq!(snapshot.headline_usage(None), 80.0);
+        assert_eq!(snapshot.headline_usage(Some(WindowSlot::Primary)), 45.0);
+        assert_eq!(snapshot.headline_usage(Some(WindowSlot::Tertiary)), 80.0);
+    }
+
     #[test]
     fn test_usage_snapshot_warning_detection() {
+        let none = ProviderThresholds::default();
+        let global = Thresholds::default();
+
         let normal = UsageSnapshot::new()
             .with_primary(RateWindow::new(50.0))
             .with_secondary(RateWindow::new(60.0));
 
-        assert!(!normal.has_warning());
-        assert!(!normal.has_critical());
+        assert!(!normal.has_warning(&none, global));
+        assert!(!normal.has_critical(&none, global));
 
         let warning = UsageSnapshot::new()
             .with_primary(RateWindow::new(85.0))
             .with_secondary(RateWindow::new(60.0));
 
-        assert!(warning.has_warning());
-        assert!(!warning.has_critical());
+        assert!(warning.has_warning(&none, global));
+        assert!(!warning.has_critical(&none, global));
 
         let critical = UsageSnapshot::new()
             .with_primary(RateWindow::new(50.0))
             .with_secondary(RateWindow::new(98.0));
 
-        assert!(critical.has_warning()); // 98% is also warning
-        assert!(critical.has_critical());
+        assert!(critical.has_warning(&none, global)); // 98% is also warning
+        assert!(critical.has_critical(&none, global));
+    }
+
+    #[test]
+    fn test_usage_snapshot_with_severity() {
+        let mut thresholds = ProviderThresholds::default();
+        thresholds
+            .windows
+            .insert(WindowSlot::Secondary, Thresholds::new(50.0, 90.0));
+        let snapshot = UsageSnapshot::new()
+            .with_primary(RateWindow::new(96.0))
+            .with_secondary(RateWindow::new(55.0))
+            .with_tertiary(RateWindow::new(10.0))
+            .with_severity(&thresholds, Thresholds::default());
+
+        let severities = snapshot.windows().map(|(_, w)| w.severity.unwrap());
+        assert_eq!(
+            severities.collect::<Vec<_>>(),
+            vec![Severity::Critical, Severity::Warning, Severity::Normal]
+        );
+        let tertiary = serde_json::to_value(snapshot.tertiary.unwrap()).unwrap();
+        assert_eq!(tertiary["severity"], "normal");
+    }
+
+    #[test]
+    fn test_usage_snapshot_window_thresholds() {
+        // Alert at 50% of the weekly window, the session keeps 80/95%
+        let mut thresholds = ProviderThresholds::default();
+        thresholds
+            .windows
+            .insert(WindowSlot::Secondary, Thresholds::new(50.0, 90.0));
+        let global = Thresholds::default();
+
+        let session_busy = UsageSnapshot::new()
+            .with_primary(RateWindow::new(70.0))
+            .with_secondary(RateWindow::new(20.0));
+        assert!(!session_busy.has_warning(&thresholds, global));
+
+        let weekly_half = UsageSnapshot::new()
+            .with_primary(RateWindow::new(10.0))
+            .with_secondary(RateWindow::new(55.0));
+        assert!(weekly_half.has_warning(&thresholds, global));
+        assert!(!weekly_half.has_critical(&thresholds, global));
+    }
+
+    #[test]
+    fn test_usage_snapshot_deltas() {
+        let mut previous = UsageSnapshot::new()
+            .with_primary(RateWindow::new(40.0))
+            .with_secondary(RateWindow::new(20.0));
+        previous.updated_at = Utc::now() - chrono::Duration::minutes(5);
+
+        let current = UsageSnapshot::new()
+            .with_primary(RateWindow::new(47.0))
+            .with_tertiary(RateWindow::new(10.0))
+            .with_deltas_from(&previous);
+
+        assert_eq!(current.primary.unwrap().delta_percent, Some(7.0));
+        assert_eq!(current.tertiary.unwrap().delta_percent, None);
+        assert!(current.secondary.is_none());
+        assert_eq!(current.delta_seconds, Some(300));
+    }
+
+    #[test]
+    fn test_usage_snapshot_changed_since() {
+        let resets_at = Utc::now() + chrono::Duration::hours(2);
+        let window = |percent: f64, resets_at: DateTime<Utc>| {
+            let mut window = RateWindow::new(percent);
+            window.resets_at = Some(resets_at);
+            window
+        };
+        let previous = UsageSnapshot::new().with_primary(window(40.0, resets_at));
+        let tolerance = ChangeTolerance::default();
+
+        // A later fetch with the same usage and a drifting reset time
+        let mut same = UsageSnapshot::new()
+            .with_primary(window(40.0, resets_at + chrono::Duration::seconds(20)))
+            .with_deltas_from(&previous);
+        same.updated_at = previous.updated_at + chrono::Duration::minutes(5);
+        assert!(!same.changed_since(&previous, &tolerance));
+
+        let used = UsageSnapshot::new().with_primary(window(40.1, resets_at));
+        assert!(used.changed_since(&previous, &tolerance));
+
+        let reset =
+            UsageSnapshot::new().with_primary(window(40.0, resets_at + chrono::Duration::hours(5)));
+        assert!(reset.changed_since(&previous, &tolerance));
+
+        let new_window = UsageSnapshot::new()
+            .with_primary(window(40.0, resets_at))
+            .with_secondary(RateWindow::new(5.0));
+        assert!(new_window.changed_since(&previous, &tolerance));
+    }
+
+    #[tokio::test]
+    async fn test_record_snapshot_tracks_previous() {
+        let last = tokio::sync::RwLock::new(None);
+
+        let first = record_snapshot(&last, UsageSnapshot::new().with_primary(RateWindow::new(10.0))).await;
+        assert_eq!(first.primary.unwrap().delta_percent, None);
+
+        let second = record_snapshot(&last, UsageSnapshot::new().with_primary(RateWindow::new(25.0))).await;
+        assert_eq!(second.primary.as_ref().unwrap().delta_percent, Some(15.0));
+        assert_eq!(last.read().await.as_ref(), Some(&second));
+    }
+
+    #[test]
+    fn test_provider_error_codes() {
+        assert_eq!(ProviderError::AuthRequired.code(), ProviderErrorCode::AuthRequired);
+        assert_eq!(
+            ProviderError::UnknownProvider("x".into()).code(),
+            ProviderErrorCode::UnknownProvider
+        );
+    }
+
+    #[test]
+    fn test_provider_error_serialization() {
+        let info = ProviderErrorInfo::from(ProviderError::AuthFailed("expired".into()));
+        let json = serde_json::to_value(info).unwrap();
+        assert_eq!(json["code"], "auth_failed");
+        assert!(json["message"].as_str().unwrap().contains("expired"));
+        assert!(json.get("retry_after_secs").is_none());
+    }
+
+    #[test]
+    fn test_provider_error_remediation() {
+        let error = ProviderError::AuthRequired;
+        let claude = error.remediation_for("claude").unwrap();
+        assert!(claude.contains("claude login"));
+        assert_eq!(error.remediation_for("demo"), error.remediation());
+        let provider = PagesProvider("claude", AuthMethod::OAuth);
+        assert_eq!(error.to_info_for(&provider).remediation, Some(claude));
+
+        assert!(ProviderError::Parse("bad".into()).remediation().is_some());
+        assert_eq!(ProviderError::Internal("bug".into()).remediation(), None);
+    }
+
+    /// Provider with every page, signing in with `.1` first
+    struct PagesProvider(&'static str, AuthMethod);
+
+    #[async_trait]
+    impl Provider for PagesProvider {
+        fn id(&self) -> &'static str {
+            self.0
+        }
+
+        fn name(&self) -> &'static str {
+            "Pages"
+        }
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn auth_methods(&self) -> Vec<AuthMethod> {
+            vec![self.1]
+        }
+
+        fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+            match page {
+                ProviderPage::ApiKeys => Some("https://example.com/keys"),
+                ProviderPage::Docs => Some("https://example.com/docs"),
+                ProviderPage::Status => Some("https://example.com/status"),
+                ProviderPage::Dashboard | ProviderPage::Billing => None,
+            }
+        }
+
+        async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+            Err(ProviderError::AuthRequired)
+        }
+
+        async fn login(&self) -> Result<bool, ProviderError> {
+            Ok(false)
+        }
+
+        async fn logout(&self) -> Result<(), ProviderError> {
+            Ok(())
+        }
+
+        async fn is_available(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_provider_error_doc_url() {
+        let keys = PagesProvider("openai", AuthMethod::ApiToken);
+        let oauth = PagesProvider("claude", AuthMethod::OAuth);
+        let cookie = PagesProvider("web", AuthMethod::Cookie);
+
+        let error = ProviderError::AuthFailed("revoked".into());
+        assert_eq!(error.doc_url(&keys), Some("https://example.com/keys"));
+        assert_eq!(error.doc_url(&oauth), Some("https://example.com/docs"));
+        // No dashboard page to sign in on
+        assert_eq!(error.doc_url(&cookie), None);
+
+        let throttled = ProviderError::RateLimited { retry_after: None };
+        assert_eq!(
+            throttled.to_info_for(&keys).doc_url.as_deref(),
+            Some("https://example.com/docs")
+        );
+        assert_eq!(ProviderError::Parse("bad".into()).doc_url(&keys), None);
+        assert_eq!(ProviderError::AuthRequired.to_info().doc_url, None);
+    }
+
+    #[test]
+    fn test_rate_limited_error() {
+        use std::time::Duration;
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, "120".parse().unwrap());
+        let error = ProviderError::rate_limited(&headers);
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(120)));
+        assert_eq!(error.http_status(), Some(429));
+
+        let json = serde_json::to_value(error.to_info()).unwrap();
+        assert_eq!(json["code"], "rate_limited");
+        assert_eq!(json["retry_after_secs"], 120);
+
+        let error = ProviderError::rate_limited(&reqwest::header::HeaderMap::new());
+        assert_eq!(error.retry_after(), None);
+        assert_eq!(error.code(), ProviderErrorCode::RateLimited);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        use std::time::Duration;
+
+        let now = DateTime::parse_from_rfc3339("2026-10-16T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let parse = |value| parse_retry_after(value, now);
+        let secs = |s| Some(Duration::from_secs(s));
+
+        assert_eq!(parse("30"), secs(30));
+        assert_eq!(parse(" 0 "), secs(0));
+        assert_eq!(parse("Fri, 16 Oct 2026 07:30:00 GMT"), secs(120));
+        // Dates already past mean "retry now"
+        assert_eq!(parse("Fri, 16 Oct 2026 07:00:00 GMT"), secs(0));
+        assert_eq!(parse("soon"), None);
     }
 
     #[test]
@@ -424,4 +2096,180 @@ mod tests {
         assert_eq!(snapshot.primary, deserialized.primary);
         assert_eq!(snapshot.identity, deserialized.identity);
     }
+
+    #[test]
+    fn test_local_usage_total_for_day() {
+        let day = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let usage = |input, cost| TokenUsage {
+            input_tokens: input,
+            cost_usd: cost,
+            ..Default::default()
+        };
+        let local = LocalUsage {
+            daily: vec![
+                DailyUsage {
+                    date: day,
+                    project: "api".to_string(),
+                    usage: usage(100, 0.5),
+                },
+                DailyUsage {
+                    date: day,
+                    project: "web".to_string(),
+                    usage: usage(50, 0.25),
+                },
+                DailyUsage {
+                    date: day.succ_opt().unwrap(),
+                    project: "api".to_string(),
+                    usage: usage(10, 1.0),
+                },
+            ],
+        };
+
+        let total = local.total_for(day);
+        assert_eq!(total.total_tokens(), 150);
+        assert_eq!(total.cost_usd, 0.75);
+
+        let json = serde_json::to_value(&local.daily[0]).unwrap();
+        assert_eq!(json["date"], "2025-06-01");
+        assert_eq!(json["input_tokens"], 100);
+    }
+
+    #[test]
+    fn test_local_usage_by_project() {
+        let day = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let entry = |date, project: &str, input, cost| DailyUsage {
+            date,
+            project: project.to_string(),
+            usage: TokenUsage {
+                input_tokens: input,
+                cost_usd: cost,
+                ..Default::default()
+            },
+        };
+        let local = LocalUsage {
+            daily: vec![
+                entry(day, "api", 100, 0.5),
+                entry(day, "docs", 500, 0.0),
+                entry(day, "web", 50, 0.75),
+                entry(day.succ_opt().unwrap(), "api", 10, 1.0),
+                entry(day.succ_opt().unwrap(), "tools", 900, 0.0),
+            ],
+        };
+
+        let projects = local.by_project("claude");
+        let order: Vec<_> = projects.iter().map(|p| p.project.as_str()).collect();
+        assert_eq!(order, vec!["api", "web", "tools", "docs"]);
+        assert_eq!(projects[0].usage.input_tokens, 110);
+        assert_eq!(projects[0].usage.cost_usd, 1.5);
+
+        let json = serde_json::to_value(&projects[0]).unwrap();
+        assert_eq!(json["provider_id"], "claude");
+        assert_eq!(json["input_tokens"], 110);
+    }
+
+    #[test]
+    fn test_model_usage_serialization() {
+        let snapshot = UsageSnapshot::new().with_model_usage(vec![
+            ModelUsage::new("gpt-4o").with_cost_usd(1.5).with_percent(15.0),
+            ModelUsage::new("gpt-4o-mini").with_tokens(1200),
+        ]);
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["by_model"][0]["model"], "gpt-4o");
+        assert_eq!(json["by_model"][0]["cost_usd"], 1.5);
+        assert!(json["by_model"][1].get("cost_usd").is_none());
+
+        // Snapshots without a breakdown omit the field
+        let json = serde_json::to_value(UsageSnapshot::new()).unwrap();
+        assert!(json.get("by_model").is_none());
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let schema = vec![
+            SettingField::new("api_key", "API key", SettingKind::Secret).required(),
+            SettingField::new("api_base_url", "Base URL", SettingKind::Url),
+            SettingField::new("limit", "Limit", SettingKind::Number),
+            SettingField::new("mode", "Mode", SettingKind::Choice).with_options(&["a", "b"]),
+        ];
+        let values = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert!(validate_settings(&schema, &values(&[("api_key", "sk-1")])).is_ok());
+        assert!(validate_settings(&schema, &values(&[("api_base_url", "")])).is_ok());
+        assert!(validate_settings(
+            &schema,
+            &values(&[("api_base_url", "http://localhost:8080"), ("limit", "500")])
+        )
+        .is_ok());
+
+        for invalid in [
+            values(&[("api_key", " ")]),
+            values(&[("api_base_url", "ftp://example.com")]),
+            values(&[("api_base_url", "not a url")]),
+            values(&[("limit", "-1")]),
+            values(&[("mode", "c")]),
+            values(&[("unknown", "x")]),
+        ] {
+            let err = validate_settings(&schema, &invalid).unwrap_err();
+            assert_eq!(err.code(), ProviderErrorCode::InvalidSettings);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_source() {
+        let snapshot = UsageSnapshot::new()
+            .with_source(UsageSource::OAuthApi)
+            .with_supplement(UsageSource::LocalLogs);
+        let source = snapshot.source.clone().unwrap();
+        assert_eq!(source.kind, UsageSource::OAuthApi);
+        assert_eq!(source.supplements, vec![UsageSource::LocalLogs]);
+
+        let cached = snapshot.into_cached().into_cached();
+        let cached_source = cached.source.unwrap();
+        assert_eq!(cached_source.kind, UsageSource::Cached);
+        assert_eq!(
+            cached_source.supplements,
+            vec![UsageSource::OAuthApi, UsageSource::LocalLogs]
+        );
+        assert_eq!(cached_source.fetched_at, source.fetched_at);
+
+        let json = serde_json::to_value(UsageSnapshot::new().with_supplement(UsageSource::LocalLogs))
+            .unwrap();
+        assert_eq!(json["source"]["kind"], "local_logs");
+        assert!(json["source"].get("supplements").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_api_key_prefers_settings_and_remembers_logout() {
+        let dir = tempfile::tempdir().unwrap();
+        let api_key = || ApiKey::new("openai").with_keys(SettingsKeys::in_dir(dir.path()));
+        let discovered = || Some(("OPENAI_API_KEY", "sk-env".to_string()));
+
+        let key = api_key();
+        assert_eq!(key.load(discovered).await.as_deref(), Some("sk-env"));
+
+        // A key entered in the settings wins over discovered ones
+        let entry = crate::auth::SecureStore::api_key_entry("openai");
+        let keys = SettingsKeys::in_dir(dir.path());
+        keys.store.set_token(&entry, "sk-entered").unwrap();
+        let key = api_key();
+        assert_eq!(key.load(discovered).await.as_deref(), Some("sk-entered"));
+
+        // Logging out deletes the entered key and ignores discovered ones,
+        // also after a restart
+        key.log_out().await.unwrap();
+        assert_eq!(key.load(discovered).await, None);
+        let restarted = api_key();
+        assert_eq!(restarted.load(discovered).await, None);
+        assert_eq!(keys.get("openai"), None);
+
+        // Until a key is entered again
+        restarted.set("sk-new").await;
+        assert_eq!(api_key().load(discovered).await.as_deref(), Some("sk-env"));
+    }
 }
@@ -4,12 +4,41 @@
 //! No browser cookie extraction - reads from Claude Code's stored credentials.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
 
-use super::base::{AuthMethod, Provider, ProviderError, RateWindow, UsageSnapshot};
+use super::base::{
+    record_snapshot, settings_api_key, validate_settings, AuthMethod, IdentitySnapshot, Provider,
+    ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, UsageSnapshot, UsageSource,
+};
+use super::claude_admin::fetch_cost_since;
+use super::claude_local::{default_config_dir, ClaudeLogCollector};
+use super::discovery::{probe_file, probe_keychain, probe_stored_api_key, CredentialProbe};
+use crate::auth::{keychain, SecureStore, TokenSync};
+use crate::budget::BudgetPeriod;
+use crate::format;
+use crate::i18n;
+use crate::security::Sanitizer;
+
+/// Days of local Claude Code usage attached to each snapshot
+const LOCAL_USAGE_DAYS: i64 = 7;
+
+/// How long before the OAuth token expires the credentials are read again
+///
+/// Claude Code refreshes the token when it runs and saves the new one; GPTBar
+/// doesn't exchange the refresh token itself, since that would invalidate
+/// the one Claude Code holds.
+const TOKEN_RELOAD_MARGIN: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Keychain service Claude Code keeps its credentials under
+const CLAUDE_CODE_KEYCHAIN: &str = "Claude Code-credentials";
+
+/// Secure store key of the copy of Claude Code's OAuth token
+const STORED_TOKEN_KEY: &str = "claude-oauth";
 
 /// Claude OAuth usage API response
 #[derive(Debug, Deserialize)]
@@ -68,6 +97,70 @@ struct ClaudeAiOAuthCredential {
     expires_at: Option<i64>,
 }
 
+impl ClaudeCodeCredentials {
+    /// Parses credentials stored by Claude Code, explaining what is missing
+    fn oauth_from_json(content: &str) -> Result<ClaudeAiOAuthCredential, String> {
+        serde_json::from_str::<Self>(content)
+            .map_err(|e| format!("Invalid JSON: {}", e))?
+            .claude_ai_oauth
+            .ok_or_else(|| "No claudeAiOauth entry".to_string())
+    }
+}
+
+/// Whether a token expiring at `expires_at` should be reloaded at `now`
+///
+/// Tokens without a known expiry are kept until a request is rejected.
+fn needs_reload(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    expires_at.is_some_and(|expires| expires - TOKEN_RELOAD_MARGIN <= now)
+}
+
+/// Describes an expired OAuth token, if `expires_at` (ms) has passed
+fn expiry_note(expires_at: i64) -> Option<String> {
+    let expires = chrono::DateTime::from_timestamp_millis(expires_at)?;
+    (expires < chrono::Utc::now()).then(|| {
+        format!(
+            "Expired at {}; run `claude` to refresh it",
+            expires.format("%Y-%m-%d %H:%M UTC")
+        )
+    })
+}
+
+/// Expands a leading `~` in a typed path to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").ok();
+
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").ok();
+
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            PathBuf::from(home).join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Which usage the Claude provider reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeUsageMode {
+    /// Claude Code subscription limits via the CLI's OAuth token
+    #[default]
+    Personal,
+    /// Organization or workspace spend via an Admin API key
+    Workspace,
+}
+
+impl ClaudeUsageMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Personal => "personal",
+            Self::Workspace => "workspace",
+        }
+    }
+}
+
 /// Configuration for Claude provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeConfig {
@@ -75,6 +168,29 @@ pub struct ClaudeConfig {
     pub enabled: bool,
     /// OAuth API base URL
     pub api_base_url: String,
+    /// Add token and cost usage parsed from local Claude Code logs
+    #[serde(default)]
+    pub local_usage: bool,
+    /// Personal limits or workspace spend
+    #[serde(default)]
+    pub usage_mode: ClaudeUsageMode,
+    /// Workspace to report in workspace mode; the whole organization if unset
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Budget the workspace spend in each budget period is measured against
+    #[serde(default)]
+    pub monthly_budget_usd: Option<f64>,
+    /// Period the workspace spend is summed over
+    #[serde(default)]
+    pub budget_period: BudgetPeriod,
+    /// Keep a copy of the OAuth token from Claude Code's credentials file in
+    /// GPTBar's secure store, used when the file is gone
+    #[serde(default)]
+    pub store_token: bool,
+    /// Claude Code's config directory, when not the default one; e.g. a
+    /// folder made visible to a Flatpak sandbox
+    #[serde(default)]
+    pub config_dir: Option<PathBuf>,
 }
 
 impl Default for ClaudeConfig {
@@ -82,6 +198,13 @@ impl Default for ClaudeConfig {
         Self {
             enabled: true,
             api_base_url: "https://api.anthropic.com".to_string(),
+            local_usage: false,
+            usage_mode: ClaudeUsageMode::Personal,
+            workspace_id: None,
+            monthly_budget_usd: None,
+            budget_period: BudgetPeriod::default(),
+            store_token: false,
+            config_dir: None,
         }
     }
 }
@@ -95,6 +218,9 @@ pub struct ClaudeProvider {
     config: RwLock<ClaudeConfig>,
     last_snapshot: RwLock<Option<UsageSnapshot>>,
     oauth_token: RwLock<Option<String>>,
+    /// When the cached OAuth token expires, if its credentials say
+    token_expires_at: RwLock<Option<DateTime<Utc>>>,
+    admin_key: RwLock<Option<String>>,
 }
 
 impl ClaudeProvider {
@@ -110,6 +236,8 @@ impl ClaudeProvider {
             config: RwLock::new(config),
             last_snapshot: RwLock::new(None),
             oauth_token: RwLock::new(None),
+            token_expires_at: RwLock::new(None),
+            admin_key: RwLock::new(None),
         }
     }
 
@@ -124,32 +252,59 @@ impl ClaudeProvider {
 
     /// Sets the OAuth token manually (for testing)
     pub async fn set_oauth_token(&self, token: &str) {
+        self.cache_token(token, None).await;
+    }
+
+    /// When the current OAuth token expires, if known
+    pub async fn token_expires_at(&self) -> Option<DateTime<Utc>> {
+        *self.token_expires_at.read().await
+    }
+
+    /// Keeps a token and its expiry (in ms, as Claude Code stores it)
+    async fn cache_token(&self, token: &str, expires_at: Option<i64>) {
         *self.oauth_token.write().await = Some(token.to_string());
+        *self.token_expires_at.write().await = expires_at.and_then(DateTime::from_timestamp_millis);
+    }
+
+    /// Forgets the cached token and its expiry
+    async fn clear_token(&self) {
+        *self.oauth_token.write().await = None;
+        *self.token_expires_at.write().await = None;
+    }
+
+    /// Claude Code's config directory: the configured one, otherwise
+    /// `CLAUDE_CONFIG_DIR` or `~/.claude`
+    fn config_dir(&self) -> Option<PathBuf> {
+        // Not waiting on settings being applied; the default is used meanwhile
+        self.config
+            .try_read()
+            .ok()
+            .and_then(|config| config.config_dir.clone())
+            .or_else(default_config_dir)
     }
 
     /// Gets the path to Claude Code credentials file (cross-platform)
-    fn get_credentials_path() -> Option<PathBuf> {
+    fn get_credentials_path(&self) -> Option<PathBuf> {
         // Windows: %USERPROFILE%\.claude\.credentials.json
         // macOS/Linux: ~/.claude/.credentials.json
-        #[cfg(target_os = "windows")]
-        let home = std::env::var("USERPROFILE").ok();
-
-        #[cfg(not(target_os = "windows"))]
-        let home = std::env::var("HOME").ok();
-
-        home.map(|h| PathBuf::from(h).join(".claude").join(".credentials.json"))
+        self.config_dir().map(|dir| dir.join(".credentials.json"))
     }
 
     /// Loads OAuth token from Claude Code CLI credentials
     async fn load_oauth_token(&self) -> Option<String> {
-        // First check in-memory cache
+        // First check in-memory cache, unless the token is about to expire
         if let Some(token) = self.oauth_token.read().await.clone() {
-            tracing::debug!("Using cached OAuth token");
-            return Some(token);
+            if !needs_reload(self.token_expires_at().await, Utc::now()) {
+                tracing::debug!("Using cached OAuth token");
+                return Some(token);
+            }
+            tracing::info!("OAuth token expires soon, reading Claude Code credentials again");
+            self.clear_token().await;
+            keychain::invalidate_service(CLAUDE_CODE_KEYCHAIN);
         }
 
         // Try to read from Claude Code credentials file
-        if let Some(path) = Self::get_credentials_path() {
+        if let Some(path) = self.get_credentials_path() {
             tracing::info!("Looking for credentials at: {:?}", path);
 
             if path.exists() {
@@ -162,8 +317,9 @@ impl ClaudeProvider {
                             Ok(creds) => {
                                 if let Some(oauth) = creds.claude_ai_oauth {
                                     if let Some(token) = oauth.access_token {
-                                        tracing::info!("Found Claude Code OAuth token ({}...)", &token[..20.min(token.len())]);
-                                        *self.oauth_token.write().await = Some(token.clone());
+                                        tracing::info!("Found Claude Code OAuth token ({})", Sanitizer::sanitize_token(&token));
+                                        self.cache_token(&token, oauth.expires_at).await;
+                                        self.store_discovered_token(&token).await;
                                         return Some(token);
                                     } else {
                                         tracing::warn!("No access_token in credentials");
@@ -189,25 +345,36 @@ impl ClaudeProvider {
         }
 
         // Try system keychain with Claude Code's service name
-        if let Ok(entry) = keyring::Entry::new("Claude Code-credentials", "default") {
-            if let Ok(token) = entry.get_password() {
-                // The credential might be JSON, try to parse it
-                if let Ok(creds) = serde_json::from_str::<ClaudeCodeCredentials>(&token) {
-                    if let Some(oauth) = creds.claude_ai_oauth {
-                        if let Some(access_token) = oauth.access_token {
-                            tracing::info!("Found Claude Code OAuth token from system keychain");
-                            *self.oauth_token.write().await = Some(access_token.clone());
-                            return Some(access_token);
-                        }
-                    }
-                } else {
-                    // Maybe it's just the token directly
-                    if token.starts_with("sk-ant-") {
+        if let Some(token) = keychain::get_password(CLAUDE_CODE_KEYCHAIN, "default") {
+            // The credential might be JSON, try to parse it
+            if let Ok(creds) = serde_json::from_str::<ClaudeCodeCredentials>(&token) {
+                if let Some(oauth) = creds.claude_ai_oauth {
+                    if let Some(access_token) = oauth.access_token {
                         tracing::info!("Found Claude Code OAuth token from system keychain");
-                        *self.oauth_token.write().await = Some(token.clone());
-                        return Some(token);
+                        self.cache_token(&access_token, oauth.expires_at).await;
+                        return Some(access_token);
                     }
                 }
+            } else {
+                // Maybe it's just the token directly
+                if token.starts_with("sk-ant-") {
+                    tracing::info!("Found Claude Code OAuth token from system keychain");
+                    self.cache_token(&token, None).await;
+                    return Some(token);
+                }
+            }
+        }
+
+        // Fall back to the copy saved while the credentials file existed
+        if self.config.read().await.store_token {
+            match SecureStore::new().get_token(STORED_TOKEN_KEY) {
+                Ok(Some(token)) => {
+                    tracing::info!("Using Claude Code OAuth token saved in the secure store");
+                    self.cache_token(&token, None).await;
+                    return Some(token);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read saved OAuth token: {}", e),
             }
         }
 
@@ -215,6 +382,109 @@ impl ClaudeProvider {
         None
     }
 
+    /// Copies a token read from Claude Code's credentials file into the
+    /// secure store, when enabled
+    async fn store_discovered_token(&self, token: &str) {
+        if !self.config.read().await.store_token {
+            return;
+        }
+        match SecureStore::new().sync_token(STORED_TOKEN_KEY, token) {
+            Ok(TokenSync::Stored) => {
+                tracing::info!("Saved Claude Code OAuth token to the secure store");
+            }
+            Ok(TokenSync::Rotated) => {
+                tracing::info!("Claude Code OAuth token rotated, updated the saved copy");
+            }
+            Ok(TokenSync::Unchanged) => {}
+            Err(e) => tracing::warn!("Failed to save OAuth token: {}", e),
+        }
+    }
+
+    /// Deletes the saved copy of Claude Code's OAuth token, if any
+    pub fn forget_stored_token(&self) {
+        if let Err(e) = SecureStore::new().delete_token(STORED_TOKEN_KEY) {
+            tracing::warn!("Failed to delete saved OAuth token: {}", e);
+        }
+    }
+
+    /// Loads the Admin API key saved from the provider settings
+    async fn load_admin_key(&self) -> Option<String> {
+        if let Some(key) = self.admin_key.read().await.clone() {
+            return Some(key);
+        }
+
+        let key = settings_api_key(self.id())?;
+        *self.admin_key.write().await = Some(key.clone());
+        Some(key)
+    }
+
+    /// Fetches this month's organization or workspace spend via the Admin API
+    async fn fetch_workspace_usage(&self, admin_key: &str) -> Result<UsageSnapshot, ProviderError> {
+        let config = self.config.read().await;
+        let (period_start, period_end) = config.budget_period.bounds(chrono::Utc::now());
+        let summary = fetch_cost_since(
+            &self.client,
+            &config.api_base_url,
+            admin_key,
+            config.workspace_id.as_deref(),
+            period_start,
+        )
+        .await?;
+
+        let spent = format::format_cost(summary.total_usd);
+        let (percent, spend) = match config.monthly_budget_usd {
+            Some(budget) if budget > 0.0 => (
+                (summary.total_usd / budget * 100.0).min(100.0),
+                format!("{} / {}", spent, format::format_cost(budget)),
+            ),
+            _ => (0.0, spent),
+        };
+
+        let window = RateWindow::new(percent)
+            .with_cost_usd(summary.total_usd)
+            .with_resets_at(period_end)
+            .with_reset_description(i18n::t_args("window.claude.workspace_spend", &[("spend", &spend)]));
+
+        let mut identity = IdentitySnapshot::new().with_plan(i18n::t("plan.workspace"));
+        if let Some(workspace) = &config.workspace_id {
+            identity = identity.with_organization(workspace.clone());
+        }
+
+        Ok(UsageSnapshot::new()
+            .with_source(UsageSource::AdminApi)
+            .with_primary(window)
+            .with_identity(identity)
+            .with_model_usage(summary.by_model))
+    }
+
+    /// Attaches the last week of local Claude Code usage, with its per-model
+    /// breakdown, when enabled
+    async fn attach_local_usage(&self, snapshot: UsageSnapshot) -> UsageSnapshot {
+        if !self.config.read().await.local_usage {
+            return snapshot;
+        }
+        let Some(config_dir) = self.config_dir() else {
+            return snapshot;
+        };
+        let collector = ClaudeLogCollector::in_config_dir(&config_dir);
+
+        let since = chrono::Local::now().date_naive() - chrono::Duration::days(LOCAL_USAGE_DAYS - 1);
+        match tokio::task::spawn_blocking(move || collector.collect_with_models(since)).await {
+            Ok(Ok((local_usage, by_model))) => snapshot
+                .with_supplement(UsageSource::LocalLogs)
+                .with_local_usage(local_usage)
+                .with_model_usage(by_model),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to read Claude Code logs: {}", e);
+                snapshot
+            }
+            Err(e) => {
+                tracing::warn!("Claude Code log collection panicked: {}", e);
+                snapshot
+            }
+        }
+    }
+
     /// Fetches usage via OAuth API
     async fn fetch_via_oauth(&self, token: &str) -> Result<UsageSnapshot, ProviderError> {
         let config = self.config.read().await;
@@ -244,8 +514,12 @@ impl ClaudeProvider {
             ));
         }
 
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+
         if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
+            let text = Sanitizer::sanitize_free_text(&response.text().await.unwrap_or_default());
             tracing::warn!("OAuth usage request failed: {} - {}", status, text);
             return Err(ProviderError::Parse(format!("HTTP {}: {}", status, text)));
         }
@@ -260,14 +534,14 @@ impl ClaudeProvider {
 
     /// Parses OAuth usage response into UsageSnapshot
     fn parse_oauth_usage(&self, data: ClaudeOAuthUsageResponse) -> Result<UsageSnapshot, ProviderError> {
-        let mut snapshot = UsageSnapshot::new();
+        let mut snapshot = UsageSnapshot::new().with_source(UsageSource::OAuthApi);
 
         // 5-hour session limit (primary)
         if let Some(five_hour) = data.five_hour {
             if let Some(pct) = five_hour.utilization {
                 let mut window = RateWindow::new(pct)
                     .with_window_minutes(300) // 5 hours
-                    .with_reset_description("5h session limit");
+                    .with_reset_description(i18n::t("window.claude.session"));
 
                 if let Some(resets_str) = five_hour.resets_at {
                     if let Ok(resets) = chrono::DateTime::parse_from_rfc3339(&resets_str) {
@@ -283,7 +557,7 @@ impl ClaudeProvider {
             if let Some(pct) = seven_day.utilization {
                 let mut window = RateWindow::new(pct)
                     .with_window_minutes(10080) // 7 days
-                    .with_reset_description("Weekly limit");
+                    .with_reset_description(i18n::t("window.claude.weekly"));
 
                 if let Some(resets_str) = seven_day.resets_at {
                     if let Ok(resets) = chrono::DateTime::parse_from_rfc3339(&resets_str) {
@@ -298,7 +572,8 @@ impl ClaudeProvider {
         if let Some(sonnet) = data.seven_day_sonnet {
             if let Some(pct) = sonnet.utilization {
                 let mut window = RateWindow::new(pct)
-                    .with_reset_description("Sonnet limit");
+                    .with_window_minutes(10080) // 7 days
+                    .with_reset_description(i18n::t("window.claude.sonnet"));
 
                 if let Some(resets_str) = sonnet.resets_at {
                     if let Ok(resets) = chrono::DateTime::parse_from_rfc3339(&resets_str) {
@@ -317,7 +592,8 @@ impl ClaudeProvider {
         tracing::info!("Reloading OAuth token from Claude Code...");
 
         // Clear cached token
-        *self.oauth_token.write().await = None;
+        self.clear_token().await;
+        keychain::invalidate_service(CLAUDE_CODE_KEYCHAIN);
 
         // Try to load again
         if self.load_oauth_token().await.is_some() {
@@ -344,6 +620,10 @@ impl Provider for ClaudeProvider {
         "Claude"
     }
 
+    fn brand_color(&self) -> &'static str {
+        "#d97706"
+    }
+
     fn is_enabled(&self) -> bool {
         true
     }
@@ -352,18 +632,37 @@ impl Provider for ClaudeProvider {
         true
     }
 
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://claude.ai/settings/usage"),
+            ProviderPage::Billing => Some("https://claude.ai/settings/billing"),
+            ProviderPage::ApiKeys => Some("https://console.anthropic.com/settings/keys"),
+            ProviderPage::Docs => Some("https://docs.anthropic.com/en/docs/claude-code/setup"),
+            ProviderPage::Status => Some("https://status.anthropic.com"),
+        }
+    }
+
     async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        if self.config.read().await.usage_mode == ClaudeUsageMode::Workspace {
+            let admin_key = self
+                .load_admin_key()
+                .await
+                .ok_or(ProviderError::AuthRequired)?;
+            let snapshot = self.fetch_workspace_usage(&admin_key).await?;
+            return Ok(record_snapshot(&self.last_snapshot, snapshot).await);
+        }
+
         // Try OAuth token from Claude Code
         if let Some(token) = self.load_oauth_token().await {
             match self.fetch_via_oauth(&token).await {
                 Ok(snapshot) => {
-                    *self.last_snapshot.write().await = Some(snapshot.clone());
-                    return Ok(snapshot);
+                    let snapshot = self.attach_local_usage(snapshot).await;
+                    return Ok(record_snapshot(&self.last_snapshot, snapshot).await);
                 }
                 Err(ProviderError::AuthFailed(msg)) => {
                     tracing::warn!("OAuth auth failed: {}", msg);
                     // Clear invalid token
-                    *self.oauth_token.write().await = None;
+                    self.clear_token().await;
                 }
                 Err(e) => {
                     tracing::warn!("OAuth fetch failed: {}", e);
@@ -378,10 +677,10 @@ impl Provider for ClaudeProvider {
     async fn login(&self) -> Result<bool, ProviderError> {
         tracing::info!("Claude login requested");
 
-        // Open Claude Code login page or instructions
+        // Open claude.ai, which asks to log in first if needed
         // The user needs to run `claude login` in their terminal
-        if let Err(e) = opener::open("https://claude.ai/login") {
-            tracing::warn!("Failed to open browser: {}", e);
+        if let Err(e) = self.open_page(ProviderPage::Dashboard) {
+            tracing::warn!("{}", e);
         }
 
         // Return false - user needs to login via Claude Code CLI
@@ -390,20 +689,139 @@ impl Provider for ClaudeProvider {
     }
 
     async fn logout(&self) -> Result<(), ProviderError> {
-        // Clear cached token
-        *self.oauth_token.write().await = None;
+        // Clear cached tokens
+        self.clear_token().await;
+        *self.admin_key.write().await = None;
         *self.last_snapshot.write().await = None;
+        self.forget_stored_token();
 
         tracing::info!("Cleared cached OAuth token. Note: This doesn't logout from Claude Code CLI.");
         Ok(())
     }
 
     async fn is_available(&self) -> bool {
+        if self.config.read().await.usage_mode == ClaudeUsageMode::Workspace {
+            return self.load_admin_key().await.is_some();
+        }
         self.load_oauth_token().await.is_some()
     }
 
+    async fn check_credentials(&self) -> Option<AuthMethod> {
+        if self.config.read().await.usage_mode == ClaudeUsageMode::Workspace {
+            return self.load_admin_key().await.map(|_| AuthMethod::ApiToken);
+        }
+        self.load_oauth_token().await.map(|_| AuthMethod::OAuth)
+    }
+
+    async fn credentials_expire_at(&self) -> Option<DateTime<Utc>> {
+        self.token_expires_at().await
+    }
+
     fn auth_methods(&self) -> Vec<AuthMethod> {
-        vec![AuthMethod::OAuth]
+        vec![AuthMethod::OAuth, AuthMethod::ApiToken]
+    }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        let mut expires_at = None;
+        let mut file = probe_file(self.get_credentials_path(), |content| {
+            let oauth = ClaudeCodeCredentials::oauth_from_json(content)?;
+            expires_at = oauth.expires_at;
+            oauth
+                .access_token
+                .ok_or_else(|| "No accessToken in claudeAiOauth".to_string())
+        });
+        if let Some(note) = expires_at.and_then(expiry_note) {
+            file = file.with_note(note);
+        }
+
+        let keychain = probe_keychain(CLAUDE_CODE_KEYCHAIN, "default", |value| {
+            if value.starts_with("sk-ant-") {
+                return Ok(value.to_string());
+            }
+            ClaudeCodeCredentials::oauth_from_json(value)?
+                .access_token
+                .ok_or_else(|| "No accessToken in claudeAiOauth".to_string())
+        });
+
+        let stored = probe_keychain(SecureStore::new().service(), STORED_TOKEN_KEY, |token| {
+            Ok(token.to_string())
+        })
+        .with_note("Copy saved from the credentials file");
+
+        // The Admin API key only matters in workspace mode
+        let admin_note = "Admin API key, used in workspace mode";
+        let admin_config = probe_file(crate::config::AppConfig::config_path(), |content| {
+            serde_json::from_str::<crate::config::AppConfig>(content)
+                .map_err(|e| format!("Invalid config: {}", e))?
+                .get_provider_api_key(self.id())
+                .ok_or_else(|| "No key saved".to_string())
+        });
+        let admin_keychain = probe_stored_api_key(self.id());
+
+        vec![
+            file,
+            keychain,
+            stored,
+            admin_config.with_note(admin_note),
+            admin_keychain.with_note(admin_note),
+        ]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        let modes = [ClaudeUsageMode::Personal, ClaudeUsageMode::Workspace].map(|m| m.as_str());
+        vec![
+            SettingField::new("usage_mode", "Usage", SettingKind::Choice)
+                .with_options(&modes)
+                .with_placeholder(ClaudeUsageMode::default().as_str()),
+            SettingField::new("api_key", "Admin API key (workspace usage)", SettingKind::Secret)
+                .with_placeholder("sk-ant-admin..."),
+            SettingField::new("workspace_id", "Workspace ID", SettingKind::Text)
+                .with_placeholder("wrkspc_..."),
+            SettingField::new("monthly_budget_usd", "Budget per period (USD)", SettingKind::Number),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(ClaudeConfig::default().api_base_url),
+            SettingField::new("config_dir", "Claude Code folder", SettingKind::Text)
+                .with_placeholder("~/.claude"),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        if let Some(key) = values.get("api_key").map(|v| v.trim()) {
+            *self.admin_key.write().await = (!key.is_empty()).then(|| key.to_string());
+        }
+
+        let mut config = self.config.write().await;
+        if let Some(mode) = values.get("usage_mode").map(|v| v.trim()) {
+            config.usage_mode = match mode {
+                "workspace" => ClaudeUsageMode::Workspace,
+                _ => ClaudeUsageMode::Personal,
+            };
+        }
+        if let Some(workspace) = values.get("workspace_id").map(|v| v.trim()) {
+            config.workspace_id = (!workspace.is_empty()).then(|| workspace.to_string());
+        }
+        if let Some(budget) = values.get("monthly_budget_usd").map(|v| v.trim()) {
+            config.monthly_budget_usd = budget.parse().ok();
+        }
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                ClaudeConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        if let Some(dir) = values.get("config_dir").map(|v| v.trim()) {
+            let dir = (!dir.is_empty()).then(|| expand_home(dir));
+            if config.config_dir != dir {
+                config.config_dir = dir;
+                drop(config);
+                // Credentials from the old folder no longer apply
+                self.clear_token().await;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -416,6 +834,8 @@ mod tests {
         let config = ClaudeConfig::default();
         assert!(config.enabled);
         assert!(config.api_base_url.contains("anthropic.com"));
+        assert!(!config.local_usage);
+        assert!(!config.store_token);
     }
 
     #[test]
@@ -445,13 +865,35 @@ mod tests {
 
     #[test]
     fn test_get_credentials_path() {
-        let path = ClaudeProvider::get_credentials_path();
+        let path = ClaudeProvider::new().get_credentials_path();
         assert!(path.is_some());
         let path = path.unwrap();
-        assert!(path.to_string_lossy().contains(".claude"));
         assert!(path.to_string_lossy().contains(".credentials.json"));
     }
 
+    #[test]
+    fn test_expand_home() {
+        let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+        assert_eq!(expand_home("~/.claude"), home.join(".claude"));
+        assert_eq!(expand_home("/opt/claude"), PathBuf::from("/opt/claude"));
+        assert_eq!(expand_home("~me/x"), PathBuf::from("~me/x"));
+    }
+
+    #[tokio::test]
+    async fn test_config_dir_setting() {
+        let provider = ClaudeProvider::new();
+        let values = HashMap::from([("config_dir".to_string(), "/host/claude".to_string())]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.get_credentials_path(),
+            Some(PathBuf::from("/host/claude/.credentials.json"))
+        );
+
+        let values = HashMap::from([("config_dir".to_string(), " ".to_string())]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(provider.config_dir(), default_config_dir());
+    }
+
     #[test]
     fn test_parse_oauth_usage() {
         let provider = ClaudeProvider::new();
@@ -474,6 +916,7 @@ mod tests {
 
         let snapshot = provider.parse_oauth_usage(data).unwrap();
 
+        assert_eq!(snapshot.source.as_ref().unwrap().kind, UsageSource::OAuthApi);
         assert!(snapshot.primary.is_some());
         assert_eq!(snapshot.primary.as_ref().unwrap().used_percent, 45.5);
 
@@ -520,7 +963,7 @@ mod tests {
 
     #[test]
     fn test_read_credentials_file() {
-        if let Some(path) = ClaudeProvider::get_credentials_path() {
+        if let Some(path) = ClaudeProvider::new().get_credentials_path() {
             println!("Credentials path: {:?}", path);
             println!("Path exists: {}", path.exists());
 
@@ -573,4 +1016,70 @@ mod tests {
             println!("No token found - this test passes if no Claude Code credentials exist");
         }
     }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let provider = ClaudeProvider::new();
+        let values = HashMap::from([(
+            "api_base_url".to_string(),
+            "https://proxy.example.com/".to_string(),
+        )]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "https://proxy.example.com"
+        );
+
+        let unknown = HashMap::from([("organization_id".to_string(), "org".to_string())]);
+        assert!(provider.apply_settings(&unknown).await.is_err());
+
+        let reset = HashMap::from([("api_base_url".to_string(), String::new())]);
+        provider.apply_settings(&reset).await.unwrap();
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "https://api.anthropic.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_workspace_settings() {
+        let provider = ClaudeProvider::new();
+        let values = HashMap::from([
+            ("usage_mode".to_string(), "workspace".to_string()),
+            ("api_key".to_string(), "sk-ant-admin01-test".to_string()),
+            ("workspace_id".to_string(), "wrkspc_123".to_string()),
+            ("monthly_budget_usd".to_string(), "500".to_string()),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+
+        {
+            let config = provider.config.read().await;
+            assert_eq!(config.usage_mode, ClaudeUsageMode::Workspace);
+            assert_eq!(config.workspace_id.as_deref(), Some("wrkspc_123"));
+            assert_eq!(config.monthly_budget_usd, Some(500.0));
+        }
+        assert!(provider.is_available().await);
+
+        let invalid = HashMap::from([("usage_mode".to_string(), "team".to_string())]);
+        assert!(provider.apply_settings(&invalid).await.is_err());
+    }
+
+    #[test]
+    fn test_credentials_expiry_note() {
+        let past = chrono::Utc::now().timestamp_millis() - 60_000;
+        let future = chrono::Utc::now().timestamp_millis() + 3_600_000;
+
+        assert!(expiry_note(past).unwrap().starts_with("Expired at"));
+        assert!(expiry_note(future).is_none());
+        assert!(ClaudeCodeCredentials::oauth_from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_needs_reload() {
+        let now = Utc::now();
+        assert!(!needs_reload(None, now));
+        assert!(!needs_reload(Some(now + chrono::Duration::hours(1)), now));
+        assert!(needs_reload(Some(now + chrono::Duration::minutes(2)), now));
+        assert!(needs_reload(Some(now - chrono::Duration::minutes(1)), now));
+    }
 }
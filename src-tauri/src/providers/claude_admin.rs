@@ -0,0 +1,188 @@
+//! Anthropic Admin API cost client
+//!
+//! Teams billed through the Anthropic API (rather than a Claude subscription)
+//! can read organization spend from the Usage & Cost Admin API with an Admin
+//! API key (`sk-ant-admin...`). The cost report returns daily buckets whose
+//! amounts are decimal strings in cents; this module sums the current budget
+//! period, optionally for a single workspace, with a per-model breakdown.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::base::{ModelUsage, ProviderError};
+
+/// Admin API version header value
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Upper bound on pages fetched per refresh
+const MAX_PAGES: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct CostReport {
+    #[serde(default)]
+    data: Vec<CostBucket>,
+    #[serde(default)]
+    has_more: bool,
+    next_page: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostBucket {
+    #[serde(default)]
+    results: Vec<CostResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostResult {
+    /// Cost in cents, as a decimal string
+    amount: String,
+    workspace_id: Option<String>,
+    model: Option<String>,
+}
+
+/// Spend for the current budget period
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostSummary {
+    /// Total spend in USD
+    pub total_usd: f64,
+    /// Spend per model, most expensive first
+    pub by_model: Vec<ModelUsage>,
+}
+
+/// Fetches the spend since `start` from the cost report
+///
+/// With `workspace_id`, only that workspace's costs are counted.
+pub async fn fetch_cost_since(
+    client: &Client,
+    base_url: &str,
+    admin_key: &str,
+    workspace_id: Option<&str>,
+    start: DateTime<Utc>,
+) -> Result<CostSummary, ProviderError> {
+    let url = format!("{}/v1/organizations/cost_report", base_url);
+
+    let mut reports = Vec::new();
+    let mut page: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+        let mut query = vec![
+            ("starting_at", start.to_rfc3339()),
+            ("group_by[]", "workspace_id".to_string()),
+            ("group_by[]", "description".to_string()),
+        ];
+        if let Some(page) = &page {
+            query.push(("page", page.clone()));
+        }
+
+        let response = client
+            .get(&url)
+            .query(&query)
+            .header("x-api-key", admin_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(ProviderError::AuthFailed(
+                "Admin API key is invalid or lacks access to usage reports".into(),
+            ));
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+
+        let report: CostReport = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::Parse(format!("Failed to parse cost report: {}", e)))?;
+        page = report.next_page.clone().filter(|_| report.has_more);
+        reports.push(report);
+
+        if page.is_none() {
+            break;
+        }
+    }
+
+    Ok(summarize(&reports, workspace_id))
+}
+
+/// Sums cost report pages, keeping only `workspace_id` when given
+fn summarize(reports: &[CostReport], workspace_id: Option<&str>) -> CostSummary {
+    let mut total_cents = 0.0;
+    let mut models: BTreeMap<&str, f64> = BTreeMap::new();
+
+    let results = reports
+        .iter()
+        .flat_map(|r| &r.data)
+        .flat_map(|b| &b.results)
+        .filter(|r| workspace_id.is_none() || r.workspace_id.as_deref() == workspace_id);
+
+    for result in results {
+        let Ok(cents) = result.amount.parse::<f64>() else {
+            continue;
+        };
+        total_cents += cents;
+        if let Some(model) = &result.model {
+            *models.entry(model.as_str()).or_default() += cents;
+        }
+    }
+
+    let mut by_model: Vec<ModelUsage> = models
+        .into_iter()
+        .map(|(model, cents)| ModelUsage::new(model).with_cost_usd(cents / 100.0))
+        .collect();
+    by_model.sort_by(|a, b| b.cost_usd.partial_cmp(&a.cost_usd).unwrap_or(Ordering::Equal));
+
+    CostSummary {
+        total_usd: total_cents / 100.0,
+        by_model,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "data": [
+            {
+                "starting_at": "2025-06-01T00:00:00Z",
+                "ending_at": "2025-06-02T00:00:00Z",
+                "results": [
+                    {"currency": "USD", "amount": "1250.5", "workspace_id": "wrkspc_a", "description": "Claude Sonnet 4 Usage - Input Tokens", "cost_type": "tokens", "model": "claude-sonnet-4-20250514"},
+                    {"currency": "USD", "amount": "300", "workspace_id": "wrkspc_b", "description": "Claude Opus 4 Usage - Output Tokens", "cost_type": "tokens", "model": "claude-opus-4-20250514"},
+                    {"currency": "USD", "amount": "49.5", "workspace_id": "wrkspc_a", "description": "Web Search", "cost_type": "web_search", "model": null}
+                ]
+            }
+        ],
+        "has_more": false,
+        "next_page": null
+    }"#;
+
+    #[test]
+    fn test_summarize_organization() {
+        let report: CostReport = serde_json::from_str(REPORT).unwrap();
+        let summary = summarize(&[report], None);
+
+        assert!((summary.total_usd - 16.0).abs() < 1e-9);
+        assert_eq!(summary.by_model.len(), 2);
+        assert_eq!(summary.by_model[0].model, "claude-sonnet-4-20250514");
+        assert_eq!(summary.by_model[0].cost_usd, Some(12.505));
+    }
+
+    #[test]
+    fn test_summarize_single_workspace() {
+        let report: CostReport = serde_json::from_str(REPORT).unwrap();
+        let summary = summarize(&[report], Some("wrkspc_b"));
+
+        assert_eq!(summary.total_usd, 3.0);
+        assert_eq!(summary.by_model.len(), 1);
+    }
+}
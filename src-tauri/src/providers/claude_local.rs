@@ -0,0 +1,433 @@
+//! Claude Code local log collector
+//!
+//! Claude Code writes one JSONL file per session under
+//! `~/.claude/projects/<project>/`. Each assistant message carries its model
+//! and token counts, which this module sums per project per day and prices
+//! with the public per-token rates (or the logged `costUSD` when present).
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Deserialize;
+
+use super::base::{DailyUsage, LocalUsage, ModelUsage, TokenUsage};
+use super::local_logs::find_logs;
+
+/// USD per million tokens: input, output, cache write, cache read
+struct Pricing {
+    input: f64,
+    output: f64,
+    cache_write: f64,
+    cache_read: f64,
+}
+
+/// Rates matched by model-name substring; more specific entries come first
+const PRICING: &[(&str, Pricing)] = &[
+    (
+        "opus-4-5",
+        Pricing {
+            input: 5.0,
+            output: 25.0,
+            cache_write: 6.25,
+            cache_read: 0.5,
+        },
+    ),
+    (
+        "opus",
+        Pricing {
+            input: 15.0,
+            output: 75.0,
+            cache_write: 18.75,
+            cache_read: 1.5,
+        },
+    ),
+    (
+        "sonnet",
+        Pricing {
+            input: 3.0,
+            output: 15.0,
+            cache_write: 3.75,
+            cache_read: 0.3,
+        },
+    ),
+    (
+        "haiku-4-5",
+        Pricing {
+            input: 1.0,
+            output: 5.0,
+            cache_write: 1.25,
+            cache_read: 0.1,
+        },
+    ),
+    (
+        "3-5-haiku",
+        Pricing {
+            input: 0.8,
+            output: 4.0,
+            cache_write: 1.0,
+            cache_read: 0.08,
+        },
+    ),
+    (
+        "haiku",
+        Pricing {
+            input: 0.25,
+            output: 1.25,
+            cache_write: 0.3,
+            cache_read: 0.03,
+        },
+    ),
+];
+
+fn pricing(model: &str) -> Option<&'static Pricing> {
+    PRICING
+        .iter()
+        .find(|(pattern, _)| model.contains(pattern))
+        .map(|(_, pricing)| pricing)
+}
+
+#[derive(Debug, Deserialize)]
+struct LogLine {
+    timestamp: Option<DateTime<Utc>>,
+    cwd: Option<String>,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
+    #[serde(rename = "costUSD")]
+    cost_usd: Option<f64>,
+    message: Option<LogMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogMessage {
+    id: Option<String>,
+    model: Option<String>,
+    usage: Option<LogUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+impl LogUsage {
+    fn to_token_usage(&self, model: Option<&str>, logged_cost: Option<f64>) -> TokenUsage {
+        let mut usage = TokenUsage {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_creation_tokens: self.cache_creation_input_tokens,
+            cache_read_tokens: self.cache_read_input_tokens,
+            cost_usd: 0.0,
+        };
+
+        usage.cost_usd = logged_cost.unwrap_or_else(|| {
+            model.and_then(pricing).map_or(0.0, |p| {
+                (usage.input_tokens as f64 * p.input
+                    + usage.output_tokens as f64 * p.output
+                    + usage.cache_creation_tokens as f64 * p.cache_write
+                    + usage.cache_read_tokens as f64 * p.cache_read)
+                    / 1_000_000.0
+            })
+        });
+        usage
+    }
+}
+
+/// Sums Claude Code session logs into per-project daily usage
+pub struct ClaudeLogCollector {
+    root: PathBuf,
+}
+
+/// Claude Code's config directory, holding its credentials and logs
+///
+/// Honors `CLAUDE_CONFIG_DIR`, otherwise uses `~/.claude`.
+pub fn default_config_dir() -> Option<PathBuf> {
+    std::env::var("CLAUDE_CONFIG_DIR")
+        .ok()
+        .map(PathBuf::from)
+        .or_else(|| {
+            #[cfg(target_os = "windows")]
+            let home = std::env::var("USERPROFILE").ok();
+
+            #[cfg(not(target_os = "windows"))]
+            let home = std::env::var("HOME").ok();
+
+            home.map(|h| PathBuf::from(h).join(".claude"))
+        })
+}
+
+impl ClaudeLogCollector {
+    /// Creates a collector for the default log directory
+    ///
+    /// See [`default_config_dir`].
+    pub fn new() -> Option<Self> {
+        default_config_dir().map(|dir| Self::in_config_dir(&dir))
+    }
+
+    /// Creates a collector for the logs in a Claude Code config directory
+    pub fn in_config_dir(config_dir: &Path) -> Self {
+        Self::with_root(config_dir.join("projects"))
+    }
+
+    /// Creates a collector reading logs below a custom `projects` directory
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Collects usage for every local day from `since` onwards
+    ///
+    /// Messages repeated across resumed sessions are counted once.
+    pub fn collect(&self, since: NaiveDate) -> std::io::Result<LocalUsage> {
+        self.collect_with_models(since).map(|(usage, _)| usage)
+    }
+
+    /// Like [`collect`](Self::collect), also returning per-model totals over
+    /// the same days, most expensive first
+    pub fn collect_with_models(
+        &self,
+        since: NaiveDate,
+    ) -> std::io::Result<(LocalUsage, Vec<ModelUsage>)> {
+        let mut totals: BTreeMap<(NaiveDate, String), TokenUsage> = BTreeMap::new();
+        let mut models: BTreeMap<String, TokenUsage> = BTreeMap::new();
+        let mut seen = HashSet::new();
+
+        let mut files = Vec::new();
+        find_logs(&self.root, &mut files)?;
+
+        let cutoff = since
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map(SystemTime::from);
+
+        for path in files {
+            // Skip sessions that haven't been written to since the cutoff
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if let (Some(modified), Some(cutoff)) = (modified, cutoff) {
+                if modified < cutoff {
+                    continue;
+                }
+            }
+
+            let fallback_project = path
+                .parent()
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let reader = BufReader::new(fs::File::open(&path)?);
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                let Ok(entry) = serde_json::from_str::<LogLine>(&line) else {
+                    continue;
+                };
+                let (Some(timestamp), Some(message)) = (entry.timestamp, entry.message) else {
+                    continue;
+                };
+                let Some(usage) = message.usage else {
+                    continue;
+                };
+
+                let date = timestamp.with_timezone(&Local).date_naive();
+                if date < since {
+                    continue;
+                }
+
+                if let (Some(id), Some(request_id)) = (&message.id, &entry.request_id) {
+                    if !seen.insert(format!("{}:{}", id, request_id)) {
+                        continue;
+                    }
+                }
+
+                let project = entry
+                    .cwd
+                    .as_deref()
+                    .and_then(|cwd| Path::new(cwd).file_name())
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| fallback_project.clone());
+
+                let usage = usage.to_token_usage(message.model.as_deref(), entry.cost_usd);
+                totals.entry((date, project)).or_default().add(&usage);
+                if let Some(model) = message.model {
+                    models.entry(model).or_default().add(&usage);
+                }
+            }
+        }
+
+        let daily = totals
+            .into_iter()
+            .map(|((date, project), usage)| DailyUsage {
+                date,
+                project,
+                usage,
+            })
+            .collect();
+
+        let mut by_model: Vec<ModelUsage> = models
+            .into_iter()
+            .map(|(model, usage)| {
+                ModelUsage::new(model)
+                    .with_tokens(usage.total_tokens())
+                    .with_cost_usd(usage.cost_usd)
+            })
+            .collect();
+        by_model.sort_by(|a, b| b.cost_usd.partial_cmp(&a.cost_usd).unwrap_or(Ordering::Equal));
+
+        Ok((LocalUsage { daily }, by_model))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assistant_line(id: &str, timestamp: &str, model: &str, input: u64, output: u64) -> String {
+        format!(
+            r#"{{"type":"assistant","timestamp":"{}","cwd":"/home/me/code/api","requestId":"req_{}","message":{{"id":"{}","model":"{}","usage":{{"input_tokens":{},"output_tokens":{},"cache_creation_input_tokens":0,"cache_read_input_tokens":0}}}}}}"#,
+            timestamp, id, id, model, input, output
+        )
+    }
+
+    fn write_session(root: &Path, project: &str, name: &str, lines: &[String]) {
+        let dir = root.join(project);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), lines.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn test_pricing_lookup() {
+        assert_eq!(pricing("claude-opus-4-5-20251101").unwrap().input, 5.0);
+        assert_eq!(pricing("claude-opus-4-1-20250805").unwrap().input, 15.0);
+        assert_eq!(pricing("claude-sonnet-4-20250514").unwrap().output, 15.0);
+        assert_eq!(pricing("claude-3-5-haiku-20241022").unwrap().input, 0.8);
+        assert!(pricing("<synthetic>").is_none());
+    }
+
+    #[test]
+    fn test_collect_sums_per_project_and_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let today = Local::now();
+        let timestamp = today.with_timezone(&Utc).to_rfc3339();
+
+        write_session(
+            dir.path(),
+            "-home-me-code-api",
+            "a.jsonl",
+            &[
+                assistant_line(
+                    "msg_1",
+                    &timestamp,
+                    "claude-sonnet-4-20250514",
+                    1_000_000,
+                    0,
+                ),
+                r#"{"type":"user","message":{"role":"user","content":"hi"}}"#.to_string(),
+                "not json".to_string(),
+                assistant_line(
+                    "msg_2",
+                    &timestamp,
+                    "claude-sonnet-4-20250514",
+                    0,
+                    1_000_000,
+                ),
+            ],
+        );
+        // A resumed session repeats msg_1; it must not be counted twice
+        write_session(
+            dir.path(),
+            "-home-me-code-api",
+            "b.jsonl",
+            &[assistant_line(
+                "msg_1",
+                &timestamp,
+                "claude-sonnet-4-20250514",
+                1_000_000,
+                0,
+            )],
+        );
+
+        let usage = ClaudeLogCollector::with_root(dir.path())
+            .collect(today.date_naive())
+            .unwrap();
+
+        assert_eq!(usage.daily.len(), 1);
+        let day = &usage.daily[0];
+        assert_eq!(day.project, "api");
+        assert_eq!(day.usage.input_tokens, 1_000_000);
+        assert_eq!(day.usage.output_tokens, 1_000_000);
+        assert!((day.usage.cost_usd - 18.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_collect_with_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now().to_rfc3339();
+        write_session(
+            dir.path(),
+            "proj",
+            "s.jsonl",
+            &[
+                assistant_line("msg_1", &now, "claude-sonnet-4-20250514", 1_000, 0),
+                assistant_line("msg_2", &now, "claude-opus-4-1-20250805", 1_000, 0),
+                assistant_line("msg_3", &now, "claude-opus-4-1-20250805", 1_000, 0),
+            ],
+        );
+
+        let (_, models) = ClaudeLogCollector::with_root(dir.path())
+            .collect_with_models(Local::now().date_naive())
+            .unwrap();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].model, "claude-opus-4-1-20250805");
+        assert_eq!(models[0].tokens, Some(2_000));
+        assert_eq!(models[1].model, "claude-sonnet-4-20250514");
+    }
+
+    #[test]
+    fn test_collect_prefers_logged_cost_and_skips_old_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let today = Local::now().date_naive();
+        let now = Utc::now().to_rfc3339();
+
+        let mut logged = assistant_line("msg_1", &now, "claude-opus-4-20250514", 10, 10);
+        logged.insert_str(1, r#""costUSD":0.42,"#);
+        write_session(
+            dir.path(),
+            "proj",
+            "s.jsonl",
+            &[
+                logged,
+                assistant_line(
+                    "msg_0",
+                    "2020-01-01T00:00:00Z",
+                    "claude-opus-4-20250514",
+                    10,
+                    10,
+                ),
+            ],
+        );
+
+        let usage = ClaudeLogCollector::with_root(dir.path())
+            .collect(today)
+            .unwrap();
+        assert_eq!(usage.daily.len(), 1);
+        assert_eq!(usage.total_for(today).cost_usd, 0.42);
+    }
+
+    #[test]
+    fn test_collect_missing_root() {
+        let usage = ClaudeLogCollector::with_root("/nonexistent/claude/projects")
+            .collect(Local::now().date_naive())
+            .unwrap();
+        assert!(usage.daily.is_empty());
+    }
+}
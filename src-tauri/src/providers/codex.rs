@@ -6,11 +6,20 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
 
+use crate::auth::keychain;
+use crate::i18n;
+
 use super::base::{
-    AuthMethod, IdentitySnapshot, Provider, ProviderError, RateWindow, UsageSnapshot,
+    letter_icon, record_snapshot, validate_settings, ApiKey, AuthMethod, IdentitySnapshot,
+    Provider, ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, UsageSnapshot,
+    UsageSource,
+};
+use super::discovery::{
+    probe_env, probe_file, probe_keychain, probe_stored_api_key, CredentialProbe,
 };
 
 /// Codex config response
@@ -23,6 +32,14 @@ struct CodexConfig {
     model: Option<String>,
 }
 
+/// Reads `OPENAI_API_KEY` from the Codex `.env` file
+fn parse_env_file(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        line.strip_prefix("OPENAI_API_KEY=")
+            .map(|key| key.trim().trim_matches('"').trim_matches('\'').to_string())
+    })
+}
+
 /// Configuration for Codex provider
 #[derive(Debug, Clone)]
 pub struct CodexProviderConfig {
@@ -48,7 +65,7 @@ impl Default for CodexProviderConfig {
 pub struct CodexProvider {
     client: Client,
     config: RwLock<CodexProviderConfig>,
-    api_key: RwLock<Option<String>>,
+    api_key: ApiKey,
     last_snapshot: RwLock<Option<UsageSnapshot>>,
 }
 
@@ -63,14 +80,14 @@ impl CodexProvider {
         Self {
             client: Client::new(),
             config: RwLock::new(config),
-            api_key: RwLock::new(None),
+            api_key: ApiKey::new("codex"),
             last_snapshot: RwLock::new(None),
         }
     }
 
     /// Sets the API key
     pub async fn set_api_key(&self, key: &str) {
-        *self.api_key.write().await = Some(key.to_string());
+        self.api_key.set(key).await;
     }
 
     /// Gets the path to Codex config directory
@@ -89,13 +106,10 @@ impl CodexProvider {
                 .map(|p| PathBuf::from(p).join("Library/Application Support/codex"))
         }
 
+        // The host's config, also from inside Flatpak
         #[cfg(target_os = "linux")]
         {
-            std::env::var("XDG_CONFIG_HOME")
-                .ok()
-                .map(PathBuf::from)
-                .or_else(|| std::env::var("HOME").ok().map(|p| PathBuf::from(p).join(".config")))
-                .map(|p| p.join("codex"))
+            crate::sandbox::host_config_dir().map(|p| p.join("codex"))
         }
 
         #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
@@ -104,18 +118,17 @@ impl CodexProvider {
         }
     }
 
-    /// Loads API key from Codex CLI config or environment
+    /// Loads the API key from the settings, Codex CLI config or environment
     async fn load_api_key(&self) -> Option<String> {
-        // Check cache first
-        if let Some(key) = self.api_key.read().await.clone() {
-            return Some(key);
-        }
+        self.api_key.load(Self::discover_api_key).await
+    }
 
+    /// Looks for an API key where Codex CLI keeps one, falling back to the
+    /// OpenAI key
+    fn discover_api_key() -> Option<(&'static str, String)> {
         // Try Codex-specific environment variable
         if let Ok(key) = std::env::var("CODEX_API_KEY") {
-            tracing::info!("Found Codex API key from CODEX_API_KEY");
-            *self.api_key.write().await = Some(key.clone());
-            return Some(key);
+            return Some(("CODEX_API_KEY", key));
         }
 
         // Try Codex config file
@@ -125,9 +138,7 @@ impl CodexProvider {
                 if let Ok(content) = std::fs::read_to_string(&config_path) {
                     if let Ok(config) = serde_json::from_str::<CodexConfig>(&content) {
                         if let Some(key) = config.api_key {
-                            tracing::info!("Found Codex API key from config file");
-                            *self.api_key.write().await = Some(key.clone());
-                            return Some(key);
+                            return Some(("the config file", key));
                         }
                     }
                 }
@@ -137,35 +148,22 @@ impl CodexProvider {
             let env_path = config_dir.join(".env");
             if env_path.exists() {
                 if let Ok(content) = std::fs::read_to_string(&env_path) {
-                    for line in content.lines() {
-                        if let Some(key) = line.strip_prefix("OPENAI_API_KEY=") {
-                            let key = key.trim().trim_matches('"').trim_matches('\'');
-                            tracing::info!("Found Codex API key from .env file");
-                            *self.api_key.write().await = Some(key.to_string());
-                            return Some(key.to_string());
-                        }
+                    if let Some(key) = parse_env_file(&content) {
+                        return Some(("the .env file", key));
                     }
                 }
             }
         }
 
         // Try system keychain
-        if let Ok(entry) = keyring::Entry::new("codex-cli", "api_key") {
-            if let Ok(key) = entry.get_password() {
-                tracing::info!("Found Codex API key from system keychain");
-                *self.api_key.write().await = Some(key.clone());
-                return Some(key);
-            }
+        if let Some(key) = keychain::get_password("codex-cli", "api_key") {
+            return Some(("the system keychain", key));
         }
 
         // Fall back to OpenAI key as Codex uses OpenAI API
-        if let Ok(key) = std::env::var("OPENAI_API_KEY") {
-            tracing::info!("Using OpenAI API key for Codex");
-            *self.api_key.write().await = Some(key.clone());
-            return Some(key);
-        }
-
-        None
+        std::env::var("OPENAI_API_KEY")
+            .ok()
+            .map(|key| ("OPENAI_API_KEY", key))
     }
 
     /// Fetches usage via OpenAI API (same endpoint as OpenAI provider)
@@ -186,19 +184,22 @@ impl CodexProvider {
         if status == reqwest::StatusCode::UNAUTHORIZED {
             return Err(ProviderError::AuthFailed("Invalid API key".into()));
         }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
 
         if !status.is_success() {
             return Err(ProviderError::Parse(format!("HTTP {}", status)));
         }
 
-        let mut snapshot = UsageSnapshot::new();
+        let mut snapshot = UsageSnapshot::new().with_source(UsageSource::ApiKey);
 
         // Codex uses OpenAI's API, so we show it's connected
-        let identity = IdentitySnapshot::new().with_plan("Connected");
+        let identity = IdentitySnapshot::new().with_plan(i18n::t("plan.connected"));
 
         snapshot = snapshot
             .with_primary(
-                RateWindow::new(0.0).with_reset_description("Uses OpenAI API"),
+                RateWindow::new(0.0).with_reset_description(i18n::t("window.codex.openai")),
             )
             .with_identity(identity);
 
@@ -222,6 +223,15 @@ impl Provider for CodexProvider {
         "Codex"
     }
 
+    fn brand_color(&self) -> &'static str {
+        "#6366f1"
+    }
+
+    fn icon_svg(&self) -> String {
+        // "C" is taken by Claude
+        letter_icon('X', self.brand_color())
+    }
+
     fn is_enabled(&self) -> bool {
         true
     }
@@ -230,6 +240,16 @@ impl Provider for CodexProvider {
         false // Uses API key
     }
 
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://platform.openai.com/usage"),
+            ProviderPage::Billing => Some("https://platform.openai.com/account/billing"),
+            ProviderPage::ApiKeys => Some("https://platform.openai.com/api-keys"),
+            ProviderPage::Docs => Some("https://github.com/openai/codex#readme"),
+            ProviderPage::Status => Some("https://status.openai.com"),
+        }
+    }
+
     async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
         let api_key = self
             .load_api_key()
@@ -237,22 +257,20 @@ impl Provider for CodexProvider {
             .ok_or(ProviderError::AuthRequired)?;
 
         let snapshot = self.fetch_usage(&api_key).await?;
-        *self.last_snapshot.write().await = Some(snapshot.clone());
-        Ok(snapshot)
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
     }
 
     async fn login(&self) -> Result<bool, ProviderError> {
-        // Open Codex CLI docs or OpenAI API keys page
-        if let Err(e) = opener::open("https://platform.openai.com/api-keys") {
-            tracing::warn!("Failed to open browser: {}", e);
+        // Open the OpenAI API keys page
+        if let Err(e) = self.open_page(ProviderPage::ApiKeys) {
+            tracing::warn!("{}", e);
         }
         Ok(false)
     }
 
     async fn logout(&self) -> Result<(), ProviderError> {
-        *self.api_key.write().await = None;
         *self.last_snapshot.write().await = None;
-        Ok(())
+        self.api_key.log_out().await
     }
 
     async fn is_available(&self) -> bool {
@@ -262,6 +280,56 @@ impl Provider for CodexProvider {
     fn auth_methods(&self) -> Vec<AuthMethod> {
         vec![AuthMethod::ApiToken]
     }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        let config_dir = Self::get_codex_config_dir();
+        vec![
+            probe_stored_api_key(self.id()),
+            probe_env("CODEX_API_KEY"),
+            probe_file(config_dir.as_ref().map(|d| d.join("config.json")), |content| {
+                serde_json::from_str::<CodexConfig>(content)
+                    .map_err(|e| format!("Invalid JSON: {}", e))?
+                    .api_key
+                    .ok_or_else(|| "No api_key in config".to_string())
+            }),
+            probe_file(config_dir.map(|d| d.join(".env")), |content| {
+                parse_env_file(content).ok_or_else(|| "No OPENAI_API_KEY line".to_string())
+            }),
+            probe_keychain("codex-cli", "api_key", |key| Ok(key.to_string())),
+            probe_env("OPENAI_API_KEY").with_note("Shared with the OpenAI provider"),
+        ]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("api_key", "API key", SettingKind::Secret)
+                .with_placeholder("sk-..."),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(CodexProviderConfig::default().api_base_url),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        if let Some(key) = values.get("api_key").map(|v| v.trim()) {
+            if key.is_empty() {
+                self.api_key.clear().await;
+            } else {
+                self.set_api_key(key).await;
+            }
+        }
+
+        let mut config = self.config.write().await;
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                CodexProviderConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -288,8 +356,8 @@ mod tests {
         let provider = CodexProvider::new();
         provider.set_api_key("sk-test-key").await;
 
-        let key = provider.api_key.read().await;
-        assert_eq!(key.as_ref().map(|s| s.as_str()), Some("sk-test-key"));
+        let key = provider.api_key.cached().await;
+        assert_eq!(key.as_deref(), Some("sk-test-key"));
     }
 
     #[test]
@@ -299,4 +367,36 @@ mod tests {
         #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
         assert!(dir.is_some());
     }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let provider = CodexProvider::new();
+        let values = HashMap::from([
+            ("api_key".to_string(), "sk-from-settings".to_string()),
+            ("api_base_url".to_string(), "http://localhost:8080/".to_string()),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.api_key.cached().await.as_deref(),
+            Some("sk-from-settings")
+        );
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+
+        let invalid = HashMap::from([("api_base_url".to_string(), "localhost".to_string())]);
+        assert!(provider.apply_settings(&invalid).await.is_err());
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let content = "# codex\nOPENAI_API_KEY=\"sk-codex-1234\"\nOTHER=1\n";
+        assert_eq!(parse_env_file(content), Some("sk-codex-1234".to_string()));
+        assert_eq!(parse_env_file("OTHER=1"), None);
+    }
 }
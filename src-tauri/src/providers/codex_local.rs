@@ -0,0 +1,371 @@
+//! Codex CLI local log collector
+//!
+//! Codex writes one JSONL rollout per session under
+//! `~/.codex/sessions/YYYY/MM/DD/`. The session's working directory and
+//! model are recorded up front, and `token_count` events carry the session's
+//! running token totals, which this module sums per project per day.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Deserialize;
+
+use super::base::{DailyUsage, LocalUsage, TokenUsage};
+use super::local_logs::find_logs;
+
+/// USD per million tokens: input, output, cached input
+struct Pricing {
+    input: f64,
+    output: f64,
+    cache_read: f64,
+}
+
+/// Rates matched by model-name substring; more specific entries come first
+const PRICING: &[(&str, Pricing)] = &[
+    (
+        "gpt-5-nano",
+        Pricing {
+            input: 0.05,
+            output: 0.4,
+            cache_read: 0.005,
+        },
+    ),
+    (
+        "gpt-5-mini",
+        Pricing {
+            input: 0.25,
+            output: 2.0,
+            cache_read: 0.025,
+        },
+    ),
+    (
+        "gpt-5",
+        Pricing {
+            input: 1.25,
+            output: 10.0,
+            cache_read: 0.125,
+        },
+    ),
+    (
+        "codex-mini",
+        Pricing {
+            input: 1.5,
+            output: 6.0,
+            cache_read: 0.375,
+        },
+    ),
+];
+
+fn pricing(model: &str) -> Option<&'static Pricing> {
+    PRICING
+        .iter()
+        .find(|(pattern, _)| model.contains(pattern))
+        .map(|(_, pricing)| pricing)
+}
+
+#[derive(Debug, Deserialize)]
+struct RolloutLine {
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    payload: Option<Payload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Payload {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    cwd: Option<String>,
+    model: Option<String>,
+    info: Option<TokenCountInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenCountInfo {
+    total_token_usage: Option<RolloutUsage>,
+}
+
+/// Token counts as Codex logs them; input includes cached input and
+/// output includes reasoning
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct RolloutUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    cached_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+impl RolloutUsage {
+    /// Tokens added since an earlier running total
+    fn since(&self, earlier: &RolloutUsage) -> RolloutUsage {
+        RolloutUsage {
+            input_tokens: self.input_tokens.saturating_sub(earlier.input_tokens),
+            cached_input_tokens: self
+                .cached_input_tokens
+                .saturating_sub(earlier.cached_input_tokens),
+            output_tokens: self.output_tokens.saturating_sub(earlier.output_tokens),
+        }
+    }
+
+    fn to_token_usage(self, model: Option<&str>) -> TokenUsage {
+        let mut usage = TokenUsage {
+            input_tokens: self.input_tokens.saturating_sub(self.cached_input_tokens),
+            output_tokens: self.output_tokens,
+            cache_creation_tokens: 0,
+            cache_read_tokens: self.cached_input_tokens,
+            cost_usd: 0.0,
+        };
+
+        usage.cost_usd = model.and_then(pricing).map_or(0.0, |p| {
+            (usage.input_tokens as f64 * p.input
+                + usage.output_tokens as f64 * p.output
+                + usage.cache_read_tokens as f64 * p.cache_read)
+                / 1_000_000.0
+        });
+        usage
+    }
+}
+
+/// Sums Codex CLI session rollouts into per-project daily usage
+pub struct CodexLogCollector {
+    root: PathBuf,
+}
+
+impl CodexLogCollector {
+    /// Creates a collector for the default sessions directory
+    ///
+    /// Honors `CODEX_HOME`, otherwise uses `~/.codex`.
+    pub fn new() -> Option<Self> {
+        let codex_home = std::env::var("CODEX_HOME")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                #[cfg(target_os = "windows")]
+                let home = std::env::var("USERPROFILE").ok();
+
+                #[cfg(not(target_os = "windows"))]
+                let home = std::env::var("HOME").ok();
+
+                home.map(|h| PathBuf::from(h).join(".codex"))
+            })?;
+
+        Some(Self::with_root(codex_home.join("sessions")))
+    }
+
+    /// Creates a collector reading rollouts below a custom `sessions` directory
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Collects usage for every local day from `since` onwards
+    pub fn collect(&self, since: NaiveDate) -> std::io::Result<LocalUsage> {
+        let mut totals: BTreeMap<(NaiveDate, String), TokenUsage> = BTreeMap::new();
+
+        let mut files = Vec::new();
+        find_logs(&self.root, &mut files)?;
+
+        let cutoff = since
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map(SystemTime::from);
+
+        for path in files {
+            // Skip sessions that haven't been written to since the cutoff
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if let (Some(modified), Some(cutoff)) = (modified, cutoff) {
+                if modified < cutoff {
+                    continue;
+                }
+            }
+
+            let mut project = String::new();
+            let mut model = None;
+            let mut previous = RolloutUsage::default();
+
+            let reader = BufReader::new(fs::File::open(&path)?);
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                let Ok(entry) = serde_json::from_str::<RolloutLine>(&line) else {
+                    continue;
+                };
+                let Some(payload) = entry.payload else {
+                    continue;
+                };
+
+                match (entry.kind.as_deref(), payload.kind.as_deref()) {
+                    (Some("session_meta" | "turn_context"), _) => {
+                        if let Some(name) = payload
+                            .cwd
+                            .as_deref()
+                            .and_then(|cwd| Path::new(cwd).file_name())
+                        {
+                            project = name.to_string_lossy().into_owned();
+                        }
+                        if payload.model.is_some() {
+                            model = payload.model;
+                        }
+                    }
+                    (Some("event_msg"), Some("token_count")) => {
+                        let Some(total) = payload.info.and_then(|i| i.total_token_usage) else {
+                            continue;
+                        };
+                        // Totals are repeated when nothing new was used
+                        let added = total.since(&previous);
+                        previous = total;
+
+                        let Some(timestamp) = entry.timestamp else {
+                            continue;
+                        };
+                        let date = timestamp.with_timezone(&Local).date_naive();
+                        if date < since {
+                            continue;
+                        }
+
+                        let usage = added.to_token_usage(model.as_deref());
+                        if usage.total_tokens() > 0 {
+                            totals
+                                .entry((date, project.clone()))
+                                .or_default()
+                                .add(&usage);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let daily = totals
+            .into_iter()
+            .map(|((date, project), usage)| DailyUsage {
+                date,
+                project,
+                usage,
+            })
+            .collect();
+
+        Ok(LocalUsage { daily })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta_line(cwd: &str) -> String {
+        format!(
+            r#"{{"timestamp":"2025-09-01T10:00:00Z","type":"session_meta","payload":{{"id":"s1","cwd":"{}","originator":"codex_cli_rs"}}}}"#,
+            cwd
+        )
+    }
+
+    fn context_line(model: &str) -> String {
+        format!(
+            r#"{{"timestamp":"2025-09-01T10:00:00Z","type":"turn_context","payload":{{"cwd":"/home/me/code/api","model":"{}"}}}}"#,
+            model
+        )
+    }
+
+    fn token_line(timestamp: &str, input: u64, cached: u64, output: u64) -> String {
+        format!(
+            r#"{{"timestamp":"{}","type":"event_msg","payload":{{"type":"token_count","info":{{"total_token_usage":{{"input_tokens":{},"cached_input_tokens":{},"output_tokens":{},"reasoning_output_tokens":0,"total_tokens":{}}}}}}}}}"#,
+            timestamp,
+            input,
+            cached,
+            output,
+            input + output
+        )
+    }
+
+    fn write_rollout(root: &Path, name: &str, lines: &[String]) {
+        let dir = root.join("2025").join("09").join("01");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), lines.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn test_pricing_lookup() {
+        assert_eq!(pricing("gpt-5-codex").unwrap().input, 1.25);
+        assert_eq!(pricing("gpt-5-mini").unwrap().output, 2.0);
+        assert_eq!(pricing("codex-mini-latest").unwrap().cache_read, 0.375);
+        assert!(pricing("o3").is_none());
+    }
+
+    #[test]
+    fn test_collect_sums_running_totals() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        write_rollout(
+            dir.path(),
+            "rollout-a.jsonl",
+            &[
+                meta_line("/home/me/code/api"),
+                context_line("gpt-5-codex"),
+                token_line(&now, 1_000_000, 0, 0),
+                // Repeated totals add nothing
+                token_line(&now, 1_000_000, 0, 0),
+                r#"{"timestamp":"2025-09-01T10:00:00Z","type":"event_msg","payload":{"type":"token_count","info":null}}"#.to_string(),
+                "not json".to_string(),
+                token_line(&now, 2_000_000, 1_000_000, 1_000_000),
+            ],
+        );
+        write_rollout(
+            dir.path(),
+            "rollout-b.jsonl",
+            &[meta_line("/home/me/code/web"), token_line(&now, 10, 0, 5)],
+        );
+
+        let usage = CodexLogCollector::with_root(dir.path())
+            .collect(Local::now().date_naive())
+            .unwrap();
+
+        assert_eq!(usage.daily.len(), 2);
+        let api = &usage.daily[0];
+        assert_eq!(api.project, "api");
+        assert_eq!(api.usage.input_tokens, 1_000_000);
+        assert_eq!(api.usage.cache_read_tokens, 1_000_000);
+        assert_eq!(api.usage.output_tokens, 1_000_000);
+        assert!((api.usage.cost_usd - 11.375).abs() < 1e-9);
+
+        // No model recorded, so unpriced
+        let web = &usage.daily[1];
+        assert_eq!(web.project, "web");
+        assert_eq!(web.usage.total_tokens(), 15);
+        assert_eq!(web.usage.cost_usd, 0.0);
+    }
+
+    #[test]
+    fn test_collect_skips_old_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now().to_rfc3339();
+        write_rollout(
+            dir.path(),
+            "rollout.jsonl",
+            &[
+                meta_line("/home/me/code/api"),
+                token_line("2020-01-01T00:00:00Z", 500, 0, 500),
+                token_line(&now, 600, 0, 700),
+            ],
+        );
+
+        let today = Local::now().date_naive();
+        let usage = CodexLogCollector::with_root(dir.path())
+            .collect(today)
+            .unwrap();
+        assert_eq!(usage.total_for(today).total_tokens(), 300);
+    }
+
+    #[test]
+    fn test_collect_missing_root() {
+        let usage = CodexLogCollector::with_root("/nonexistent/codex/sessions")
+            .collect(Local::now().date_naive())
+            .unwrap();
+        assert!(usage.daily.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+//! Demo provider
+//!
+//! Generates plausible usage without any credentials, for UI work,
+//! screenshots and tests. Enabled with `--demo` or `"demo_mode": true` in the
+//! config file; it is only registered in demo mode.
+//!
+//! Usage follows the shape of a real subscription: a 5-hour session window
+//! that climbs and resets, a weekly window that grows through the week, and
+//! a model-specific limit tracking a share of the weekly one. Peaks vary per
+//! window but are deterministic for a given seed and time.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use tokio::sync::RwLock;
+
+use super::base::{
+    record_snapshot, AuthMethod, IdentitySnapshot, ModelUsage, Provider, ProviderError,
+    RateWindow, UsageSnapshot, UsageSource,
+};
+
+/// Provider ID used in demo mode
+pub const DEMO_ID: &str = "demo";
+
+const SESSION_MINUTES: i64 = 300;
+const WEEK_MINUTES: i64 = 10_080;
+
+/// Provider that serves generated usage
+pub struct DemoProvider {
+    seed: u64,
+    last_snapshot: RwLock<Option<UsageSnapshot>>,
+}
+
+impl DemoProvider {
+    /// Creates a demo provider with a fixed seed
+    pub fn new() -> Self {
+        Self::with_seed(42)
+    }
+
+    /// Creates a demo provider whose curves depend on `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            last_snapshot: RwLock::new(None),
+        }
+    }
+}
+
+impl Default for DemoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps `(seed, bucket)` to a value in `[0, 1)` (splitmix64)
+fn noise(seed: u64, bucket: i64) -> f64 {
+    let mut z = seed.wrapping_add((bucket as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Generates the snapshot the demo provider reports at `now`
+pub fn demo_snapshot(now: DateTime<Utc>, seed: u64) -> UsageSnapshot {
+    // Session windows are aligned to the epoch, weeks to Monday 00:00 UTC
+    let minutes = now.timestamp() / 60;
+    let session = minutes / SESSION_MINUTES;
+    let session_start = DateTime::from_timestamp(session * SESSION_MINUTES * 60, 0).unwrap_or(now);
+    let session_progress = (minutes % SESSION_MINUTES) as f64 / SESSION_MINUTES as f64;
+
+    let week_start = (now.date_naive()
+        - Duration::days(now.weekday().num_days_from_monday() as i64))
+    .and_time(NaiveTime::MIN)
+    .and_utc();
+    let week_progress = (now - week_start).num_minutes() as f64 / WEEK_MINUTES as f64;
+    let week = week_start.timestamp() / (WEEK_MINUTES * 60);
+
+    let session_peak = 35.0 + 60.0 * noise(seed, session);
+    let weekly_peak = 50.0 + 45.0 * noise(seed ^ 0xABCD, week);
+
+    let session_percent = (session_peak * session_progress.powf(0.8)).min(100.0);
+    let weekly_percent = (weekly_peak * week_progress).min(100.0);
+    let model_percent = weekly_percent * 0.6;
+
+    let weekly_cost = weekly_percent * 2.0;
+    let by_model = vec![
+        ModelUsage::new("demo-large")
+            .with_percent(model_percent)
+            .with_cost_usd(weekly_cost * 0.7),
+        ModelUsage::new("demo-small")
+            .with_percent(weekly_percent - model_percent)
+            .with_cost_usd(weekly_cost * 0.3),
+    ];
+
+    UsageSnapshot::new()
+        .with_source(UsageSource::Demo)
+        .with_primary(
+            RateWindow::new(session_percent)
+                .with_window_minutes(SESSION_MINUTES)
+                .with_resets_at(session_start + Duration::minutes(SESSION_MINUTES))
+                .with_reset_description("Session"),
+        )
+        .with_secondary(
+            RateWindow::new(weekly_percent)
+                .with_window_minutes(WEEK_MINUTES)
+                .with_resets_at(week_start + Duration::minutes(WEEK_MINUTES))
+                .with_reset_description("Weekly"),
+        )
+        .with_tertiary(
+            RateWindow::new(model_percent)
+                .with_resets_at(week_start + Duration::minutes(WEEK_MINUTES))
+                .with_reset_description("demo-large"),
+        )
+        .with_identity(
+            IdentitySnapshot::new()
+                .with_email("demo@example.com")
+                .with_plan("Demo"),
+        )
+        .with_model_usage(by_model)
+}
+
+#[async_trait]
+impl Provider for DemoProvider {
+    fn id(&self) -> &'static str {
+        DEMO_ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Demo"
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn supports_login(&self) -> bool {
+        false
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        let snapshot = demo_snapshot(Utc::now(), self.seed);
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
+    }
+
+    async fn login(&self) -> Result<bool, ProviderError> {
+        Ok(true)
+    }
+
+    async fn logout(&self) -> Result<(), ProviderError> {
+        *self.last_snapshot.write().await = None;
+        Ok(())
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::None]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_noise_range_and_determinism() {
+        for bucket in 0..1000 {
+            let value = noise(7, bucket);
+            assert!((0.0..1.0).contains(&value));
+            assert_eq!(value, noise(7, bucket));
+        }
+        assert_ne!(noise(7, 1), noise(8, 1));
+    }
+
+    #[test]
+    fn test_session_climbs_then_resets() {
+        // Epoch-aligned sessions start at 00:00 and 05:00 UTC on 2025-06-11
+        let early = demo_snapshot(Utc.with_ymd_and_hms(2025, 6, 11, 0, 30, 0).unwrap(), 1);
+        let late = demo_snapshot(Utc.with_ymd_and_hms(2025, 6, 11, 4, 30, 0).unwrap(), 1);
+        let next = demo_snapshot(Utc.with_ymd_and_hms(2025, 6, 11, 5, 10, 0).unwrap(), 1);
+
+        let percent = |s: &UsageSnapshot| s.primary.as_ref().unwrap().used_percent;
+        assert!(percent(&early) < percent(&late));
+        assert!(percent(&next) < percent(&late));
+        assert_eq!(
+            late.primary.as_ref().unwrap().resets_at,
+            Some(Utc.with_ymd_and_hms(2025, 6, 11, 5, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_weekly_grows_and_stays_in_range() {
+        let monday = demo_snapshot(Utc.with_ymd_and_hms(2025, 6, 2, 12, 0, 0).unwrap(), 3);
+        let sunday = demo_snapshot(Utc.with_ymd_and_hms(2025, 6, 8, 12, 0, 0).unwrap(), 3);
+
+        let weekly = |s: &UsageSnapshot| s.secondary.as_ref().unwrap().used_percent;
+        assert!(weekly(&monday) < weekly(&sunday));
+        assert!(weekly(&sunday) <= 100.0);
+        assert_eq!(
+            sunday.secondary.as_ref().unwrap().resets_at,
+            Some(Utc.with_ymd_and_hms(2025, 6, 9, 0, 0, 0).unwrap())
+        );
+        assert_eq!(sunday.source.unwrap().kind, UsageSource::Demo);
+    }
+
+    #[tokio::test]
+    async fn test_demo_provider_fetch() {
+        let provider = DemoProvider::new();
+        assert!(provider.is_available().await);
+        let snapshot = provider.fetch().await.unwrap();
+        assert_eq!(snapshot.by_model.len(), 2);
+        assert_eq!(
+            snapshot.identity.unwrap().email.as_deref(),
+            Some("demo@example.com")
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Credential discovery
+//!
+//! Lists every place a provider looks for credentials and what is there,
+//! without using anything it finds. Helps explain why a provider reports
+//! itself as unavailable. Secrets only ever appear masked to their last four
+//! characters.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{BrowserType, CookieExtractor, SecureStore};
+use crate::sandbox;
+use crate::security::Sanitizer;
+
+use super::base::Provider;
+
+/// Kind of place a credential can come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialLocation {
+    /// Environment variable
+    EnvVar,
+    /// File on disk
+    File,
+    /// System keychain entry
+    Keychain,
+    /// Browser cookie store
+    Browser,
+}
+
+/// Result of checking one credential location
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CredentialProbe {
+    /// Kind of location
+    pub location: CredentialLocation,
+    /// Variable name, file path, keychain entry or browser
+    pub source: String,
+    /// Whether a usable credential is there
+    pub found: bool,
+    /// Masked credential (e.g., "***abcd")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    /// Why a present credential can't be used, or other details
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl CredentialProbe {
+    /// A location with nothing usable in it
+    pub fn missing(location: CredentialLocation, source: impl Into<String>) -> Self {
+        Self {
+            location,
+            source: source.into(),
+            found: false,
+            preview: None,
+            note: None,
+        }
+    }
+
+    /// A location holding a credential
+    pub fn found(location: CredentialLocation, source: impl Into<String>) -> Self {
+        Self {
+            found: true,
+            ..Self::missing(location, source)
+        }
+    }
+
+    /// Adds the masked form of `secret`
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        self.preview = Some(Sanitizer::sanitize_token(secret));
+        self
+    }
+
+    /// Adds a note, after any existing one
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        let note = note.into();
+        self.note = Some(match self.note.take() {
+            Some(existing) => format!("{}; {}", existing, note),
+            None => note,
+        });
+        self
+    }
+}
+
+/// Checks an environment variable
+pub fn probe_env(var: &str) -> CredentialProbe {
+    match std::env::var(var) {
+        Ok(value) if !value.trim().is_empty() => {
+            CredentialProbe::found(CredentialLocation::EnvVar, var).with_secret(&value)
+        }
+        Ok(_) => CredentialProbe::missing(CredentialLocation::EnvVar, var).with_note("Set but empty"),
+        Err(_) => CredentialProbe::missing(CredentialLocation::EnvVar, var),
+    }
+}
+
+/// Checks a system keychain entry
+///
+/// `extract` pulls the credential out of the stored value, or explains why
+/// it can't.
+pub fn probe_keychain(
+    service: &str,
+    account: &str,
+    extract: impl FnOnce(&str) -> Result<String, String>,
+) -> CredentialProbe {
+    let source = format!("{} / {}", service, account);
+    let password = keyring::Entry::new(service, account).and_then(|entry| entry.get_password());
+
+    match password {
+        Ok(value) => match extract(&value) {
+            Ok(secret) => CredentialProbe::found(CredentialLocation::Keychain, source).with_secret(&secret),
+            Err(note) => CredentialProbe::missing(CredentialLocation::Keychain, source).with_note(note),
+        },
+        Err(keyring::Error::NoEntry) => CredentialProbe::missing(CredentialLocation::Keychain, source),
+        Err(e) => {
+            let probe = CredentialProbe::missing(CredentialLocation::Keychain, source)
+                .with_note(format!("Keychain unavailable: {}", e));
+            if sandbox::is_flatpak() {
+                probe.with_note("inside Flatpak, allow `--talk-name=org.freedesktop.secrets`")
+            } else {
+                probe
+            }
+        }
+    }
+}
+
+/// Checks the secure store entry of an API key saved in the settings
+pub fn probe_stored_api_key(provider_id: &str) -> CredentialProbe {
+    probe_keychain(
+        SecureStore::new().service(),
+        &SecureStore::api_key_entry(provider_id),
+        |key| Ok(key.to_string()),
+    )
+    .with_note("Saved in the settings")
+}
+
+/// Checks a file, with `extract` pulling the credential out of its contents
+pub fn probe_file(
+    path: Option<PathBuf>,
+    extract: impl FnOnce(&str) -> Result<String, String>,
+) -> CredentialProbe {
+    let Some(path) = path else {
+        return CredentialProbe::missing(CredentialLocation::File, "(unknown home directory)");
+    };
+    let source = display_path(&path);
+
+    if !path.exists() {
+        let probe = CredentialProbe::missing(CredentialLocation::File, source);
+        return match sandbox::access_hint(&path) {
+            Some(hint) => probe.with_note(hint),
+            None => probe,
+        };
+    }
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => match extract(&content) {
+            Ok(secret) => CredentialProbe::found(CredentialLocation::File, source).with_secret(&secret),
+            Err(note) => CredentialProbe::missing(CredentialLocation::File, source).with_note(note),
+        },
+        Err(e) => CredentialProbe::missing(CredentialLocation::File, source)
+            .with_note(format!("Unreadable: {}", e)),
+    }
+}
+
+/// Shows `path` with the home directory abbreviated to `~`
+fn display_path(path: &Path) -> String {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").ok();
+
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").ok();
+
+    match home.and_then(|h| path.strip_prefix(h).ok().map(Path::to_path_buf)) {
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// Credential locations checked for one provider
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderCredentials {
+    /// Provider ID
+    pub provider_id: String,
+    /// Display name
+    pub name: String,
+    /// Locations in the order the provider tries them
+    pub probes: Vec<CredentialProbe>,
+    /// Index of the probe the provider would use (the first one found)
+    pub selected: Option<usize>,
+}
+
+impl ProviderCredentials {
+    /// Probes the provider's credential locations
+    pub fn discover(provider: &dyn Provider) -> Self {
+        let probes = provider.discover_credentials();
+        Self {
+            provider_id: provider.id().to_string(),
+            name: provider.name().to_string(),
+            selected: probes.iter().position(|p| p.found),
+            probes,
+        }
+    }
+}
+
+/// Credential discovery results for all providers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CredentialReport {
+    /// Per-provider results
+    pub providers: Vec<ProviderCredentials>,
+    /// Browser cookie stores found on this machine
+    pub browsers: Vec<CredentialProbe>,
+}
+
+impl CredentialReport {
+    /// Probes every provider and browser without using any credential
+    pub fn discover(providers: &[Arc<dyn Provider>]) -> Self {
+        Self {
+            providers: providers
+                .iter()
+                .map(|p| ProviderCredentials::discover(p.as_ref()))
+                .collect(),
+            browsers: BrowserType::all().iter().map(|b| probe_browser(*b)).collect(),
+        }
+    }
+
+    /// Drops the masked credentials, keeping where they were found
+    pub fn without_previews(mut self) -> Self {
+        let probes = self
+            .providers
+            .iter_mut()
+            .flat_map(|p| p.probes.iter_mut())
+            .chain(self.browsers.iter_mut());
+        for probe in probes {
+            probe.preview = None;
+        }
+        self
+    }
+}
+
+/// Checks whether a browser's cookie store exists (cookies are not read)
+fn probe_browser(browser: BrowserType) -> CredentialProbe {
+    match CookieExtractor::cookie_path(browser) {
+        Ok(path) if path.exists() => CredentialProbe::found(CredentialLocation::Browser, browser.name())
+            .with_note(display_path(&path)),
+        _ => CredentialProbe::missing(CredentialLocation::Browser, browser.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_probe_env_masks_value() {
+        std::env::set_var("GPTBAR_TEST_DISCOVERY_KEY", "sk-test-secret-1234");
+        let probe = probe_env("GPTBAR_TEST_DISCOVERY_KEY");
+        assert!(probe.found);
+        assert_eq!(probe.preview.as_deref(), Some("***1234"));
+
+        let missing = probe_env("GPTBAR_TEST_DISCOVERY_UNSET");
+        assert!(!missing.found);
+        assert!(missing.preview.is_none());
+    }
+
+    #[test]
+    fn test_probe_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "OPENAI_API_KEY=sk-abcdef9876").unwrap();
+
+        let probe = probe_file(Some(file.path().to_path_buf()), |content| {
+            content
+                .lines()
+                .find_map(|l| l.strip_prefix("OPENAI_API_KEY="))
+                .map(str::to_string)
+                .ok_or_else(|| "No key".to_string())
+        });
+        assert!(probe.found);
+        assert_eq!(probe.preview.as_deref(), Some("***9876"));
+
+        let unusable = probe_file(Some(file.path().to_path_buf()), |_| Err("No key".into()));
+        assert!(!unusable.found);
+        assert_eq!(unusable.note.as_deref(), Some("No key"));
+
+        let absent = probe_file(Some(PathBuf::from("/nonexistent/gptbar/credentials")), |c| {
+            Ok(c.to_string())
+        });
+        assert!(!absent.found);
+        assert!(absent.note.is_none());
+    }
+
+    #[test]
+    fn test_report_serializes_without_secrets() {
+        let probe = CredentialProbe::found(CredentialLocation::EnvVar, "OPENAI_API_KEY")
+            .with_secret("sk-proj-verysecretvalue");
+        let json = serde_json::to_string(&probe).unwrap();
+
+        assert!(json.contains("\"location\":\"env_var\""));
+        assert!(!json.contains("verysecret"));
+    }
+
+    #[test]
+    fn test_report_without_previews() {
+        let probe = CredentialProbe::found(CredentialLocation::EnvVar, "OPENAI_API_KEY")
+            .with_secret("sk-proj-verysecretvalue");
+        let report = CredentialReport {
+            providers: vec![ProviderCredentials {
+                provider_id: "openai".to_string(),
+                name: "OpenAI".to_string(),
+                probes: vec![probe],
+                selected: Some(0),
+            }],
+            browsers: Vec::new(),
+        }
+        .without_previews();
+
+        let probe = &report.providers[0].probes[0];
+        assert!(probe.found);
+        assert!(probe.preview.is_none());
+    }
+}
@@ -0,0 +1,338 @@
+//! ElevenLabs provider implementation
+//!
+//! Reports the subscription's character quota from `/v1/user/subscription`:
+//! characters used out of the plan's limit, and when the count resets.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::auth::keychain;
+use crate::i18n;
+
+use super::base::{
+    record_snapshot, validate_settings, ApiKey, AuthMethod, IdentitySnapshot, Provider,
+    ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, UsageSnapshot, UsageSource,
+};
+use super::discovery::{probe_env, probe_keychain, probe_stored_api_key, CredentialProbe};
+
+/// Environment variables an ElevenLabs API key is read from, in order
+const API_KEY_VARS: [&str; 2] = ["ELEVENLABS_API_KEY", "XI_API_KEY"];
+
+/// ElevenLabs subscription response
+#[derive(Debug, Deserialize)]
+struct ElevenLabsSubscription {
+    /// Plan, e.g. `free` or `creator`
+    tier: Option<String>,
+    /// Characters used in the current period
+    character_count: u64,
+    /// Characters the plan allows per period
+    character_limit: u64,
+    /// When the count resets, in seconds since the epoch
+    next_character_count_reset_unix: Option<i64>,
+}
+
+impl ElevenLabsSubscription {
+    /// Builds the character quota window
+    fn window(&self) -> RateWindow {
+        let percent = if self.character_limit > 0 {
+            (self.character_count as f64 / self.character_limit as f64 * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        let remaining = self.character_limit.saturating_sub(self.character_count);
+
+        let window = RateWindow::new(percent).with_reset_description(i18n::t_args(
+            "window.elevenlabs.characters",
+            &[
+                ("used", &self.character_count.to_string()),
+                ("limit", &self.character_limit.to_string()),
+                ("remaining", &remaining.to_string()),
+            ],
+        ));
+        match self
+            .next_character_count_reset_unix
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        {
+            Some(resets_at) => window.with_resets_at(resets_at),
+            None => window,
+        }
+    }
+}
+
+/// Configuration for ElevenLabs provider
+#[derive(Debug, Clone)]
+pub struct ElevenLabsConfig {
+    /// API base URL
+    pub api_base_url: String,
+}
+
+impl Default for ElevenLabsConfig {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://api.elevenlabs.io".to_string(),
+        }
+    }
+}
+
+/// ElevenLabs provider
+pub struct ElevenLabsProvider {
+    client: Client,
+    config: RwLock<ElevenLabsConfig>,
+    api_key: ApiKey,
+    last_snapshot: RwLock<Option<UsageSnapshot>>,
+}
+
+impl ElevenLabsProvider {
+    /// Creates a new ElevenLabs provider
+    pub fn new() -> Self {
+        Self::with_config(ElevenLabsConfig::default())
+    }
+
+    /// Creates a new ElevenLabs provider with custom configuration
+    pub fn with_config(config: ElevenLabsConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: RwLock::new(config),
+            api_key: ApiKey::new("elevenlabs"),
+            last_snapshot: RwLock::new(None),
+        }
+    }
+
+    /// Sets the API key
+    pub async fn set_api_key(&self, key: &str) {
+        self.api_key.set(key).await;
+    }
+
+    /// Loads the API key from the settings, environment or keychain
+    async fn load_api_key(&self) -> Option<String> {
+        self.api_key.load(Self::discover_api_key).await
+    }
+
+    /// Looks for an API key in the environment, then the keychain
+    fn discover_api_key() -> Option<(&'static str, String)> {
+        for var in API_KEY_VARS {
+            if let Ok(key) = std::env::var(var) {
+                return Some((var, key));
+            }
+        }
+
+        keychain::get_password("elevenlabs", "api_key").map(|key| ("the system keychain", key))
+    }
+
+    /// Fetches the character quota of the subscription
+    async fn fetch_usage(&self, api_key: &str) -> Result<UsageSnapshot, ProviderError> {
+        let url = format!(
+            "{}/v1/user/subscription",
+            self.config.read().await.api_base_url
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header("xi-api-key", api_key)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(ProviderError::AuthFailed("Invalid API key".into()));
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+
+        let subscription: ElevenLabsSubscription = response.json().await.map_err(|e| {
+            ProviderError::Parse(format!("Failed to parse subscription response: {}", e))
+        })?;
+
+        let identity = IdentitySnapshot::new().with_plan(
+            subscription
+                .tier
+                .clone()
+                .unwrap_or_else(|| i18n::t("plan.unknown")),
+        );
+        Ok(UsageSnapshot::new()
+            .with_source(UsageSource::ApiKey)
+            .with_primary(subscription.window())
+            .with_identity(identity))
+    }
+}
+
+impl Default for ElevenLabsProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for ElevenLabsProvider {
+    fn id(&self) -> &'static str {
+        "elevenlabs"
+    }
+
+    fn name(&self) -> &'static str {
+        "ElevenLabs"
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn supports_login(&self) -> bool {
+        false // Uses API key
+    }
+
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://elevenlabs.io/app/usage"),
+            ProviderPage::Billing => Some("https://elevenlabs.io/app/subscription"),
+            ProviderPage::ApiKeys => Some("https://elevenlabs.io/app/settings/api-keys"),
+            ProviderPage::Docs => {
+                Some("https://elevenlabs.io/docs/api-reference/user/subscription/get")
+            }
+            ProviderPage::Status => Some("https://status.elevenlabs.io"),
+        }
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        let api_key = self
+            .load_api_key()
+            .await
+            .ok_or(ProviderError::AuthRequired)?;
+
+        let snapshot = self.fetch_usage(&api_key).await?;
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
+    }
+
+    async fn login(&self) -> Result<bool, ProviderError> {
+        if let Err(e) = self.open_page(ProviderPage::ApiKeys) {
+            tracing::warn!("{}", e);
+        }
+        Ok(false)
+    }
+
+    async fn logout(&self) -> Result<(), ProviderError> {
+        *self.last_snapshot.write().await = None;
+        self.api_key.log_out().await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.load_api_key().await.is_some()
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::ApiToken]
+    }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        let mut probes = vec![probe_stored_api_key(self.id())];
+        probes.extend(API_KEY_VARS.into_iter().map(probe_env));
+        probes.push(probe_keychain("elevenlabs", "api_key", |key| {
+            Ok(key.to_string())
+        }));
+        probes
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("api_key", "API key", SettingKind::Secret).with_placeholder("sk_..."),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(ElevenLabsConfig::default().api_base_url),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        if let Some(key) = values.get("api_key").map(|v| v.trim()) {
+            if key.is_empty() {
+                self.api_key.clear().await;
+            } else {
+                self.set_api_key(key).await;
+            }
+        }
+
+        let mut config = self.config.write().await;
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                ElevenLabsConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_elevenlabs_provider_new() {
+        let provider = ElevenLabsProvider::new();
+        assert_eq!(provider.id(), "elevenlabs");
+        assert_eq!(provider.name(), "ElevenLabs");
+        assert!(!provider.supports_login());
+    }
+
+    #[test]
+    fn test_subscription_window() {
+        let subscription: ElevenLabsSubscription = serde_json::from_str(
+            r#"{"tier":"creator","character_count":25000,"character_limit":100000,
+                "can_extend_character_limit":true,"voice_limit":30,
+                "next_character_count_reset_unix":1751328000,"status":"active"}"#,
+        )
+        .unwrap();
+        assert_eq!(subscription.tier.as_deref(), Some("creator"));
+
+        let window = subscription.window();
+        assert_eq!(window.used_percent, 25.0);
+        assert_eq!(
+            window.resets_at,
+            Some(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap())
+        );
+        assert!(window.reset_description.unwrap().contains("75000"));
+    }
+
+    #[test]
+    fn test_subscription_window_over_limit() {
+        let subscription = ElevenLabsSubscription {
+            tier: None,
+            character_count: 12000,
+            character_limit: 10000,
+            next_character_count_reset_unix: None,
+        };
+        let window = subscription.window();
+        assert_eq!(window.used_percent, 100.0);
+        assert_eq!(window.resets_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let provider = ElevenLabsProvider::new();
+        let values = HashMap::from([
+            ("api_key".to_string(), "sk_from_settings".to_string()),
+            (
+                "api_base_url".to_string(),
+                "http://localhost:8080/".to_string(),
+            ),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.api_key.cached().await.as_deref(),
+            Some("sk_from_settings")
+        );
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+    }
+}
@@ -1,16 +1,26 @@
 //! Google Gemini provider implementation
 //!
-//! Fetches usage/quota data from Google AI API.
+//! Fetches usage/quota data from Google AI API, and counts today's Gemini CLI
+//! requests from its local logs against the free-tier daily limit.
 
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
 
+use crate::auth::keychain;
+use crate::i18n;
+
 use super::base::{
-    AuthMethod, IdentitySnapshot, Provider, ProviderError, RateWindow, UsageSnapshot,
+    record_snapshot, validate_settings, ApiKey, AuthMethod, IdentitySnapshot, Provider,
+    ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, UsageSnapshot, UsageSource,
+};
+use super::discovery::{
+    probe_env, probe_keychain, probe_stored_api_key, CredentialLocation, CredentialProbe,
 };
+use super::gemini_cli::{quota_day, GeminiCliCollector, DEFAULT_DAILY_LIMIT};
 
 /// Gemini models list response
 #[derive(Debug, Deserialize)]
@@ -49,6 +59,8 @@ pub struct GeminiConfig {
     pub enabled: bool,
     /// API base URL
     pub api_base_url: String,
+    /// Daily request limit used for the Gemini CLI gauge
+    pub cli_daily_limit: u32,
 }
 
 impl Default for GeminiConfig {
@@ -56,6 +68,7 @@ impl Default for GeminiConfig {
         Self {
             enabled: false,
             api_base_url: "https://generativelanguage.googleapis.com".to_string(),
+            cli_daily_limit: DEFAULT_DAILY_LIMIT,
         }
     }
 }
@@ -64,7 +77,7 @@ impl Default for GeminiConfig {
 pub struct GeminiProvider {
     client: Client,
     config: RwLock<GeminiConfig>,
-    api_key: RwLock<Option<String>>,
+    api_key: ApiKey,
     last_snapshot: RwLock<Option<UsageSnapshot>>,
 }
 
@@ -79,14 +92,14 @@ impl GeminiProvider {
         Self {
             client: Client::new(),
             config: RwLock::new(config),
-            api_key: RwLock::new(None),
+            api_key: ApiKey::new("gemini"),
             last_snapshot: RwLock::new(None),
         }
     }
 
     /// Sets the API key
     pub async fn set_api_key(&self, key: &str) {
-        *self.api_key.write().await = Some(key.to_string());
+        self.api_key.set(key).await;
     }
 
     /// Gets the path to Google credentials (reserved for future ADC support)
@@ -107,32 +120,22 @@ impl GeminiProvider {
         })
     }
 
-    /// Loads API key from environment or file
+    /// Loads the API key from the settings, environment or keychain
     async fn load_api_key(&self) -> Option<String> {
-        // Check cache first
-        if let Some(key) = self.api_key.read().await.clone() {
-            return Some(key);
-        }
+        self.api_key.load(Self::discover_api_key).await
+    }
 
+    /// Looks for an API key in the environment, then the keychain
+    fn discover_api_key() -> Option<(&'static str, String)> {
         // Try environment variables
         for var in ["GOOGLE_API_KEY", "GEMINI_API_KEY"] {
             if let Ok(key) = std::env::var(var) {
-                tracing::info!("Found Gemini API key from {}", var);
-                *self.api_key.write().await = Some(key.clone());
-                return Some(key);
+                return Some((var, key));
             }
         }
 
         // Try system keychain
-        if let Ok(entry) = keyring::Entry::new("google-gemini", "api_key") {
-            if let Ok(key) = entry.get_password() {
-                tracing::info!("Found Gemini API key from system keychain");
-                *self.api_key.write().await = Some(key.clone());
-                return Some(key);
-            }
-        }
-
-        None
+        keychain::get_password("google-gemini", "api_key").map(|key| ("the system keychain", key))
     }
 
     /// Fetches usage/availability via Gemini API
@@ -150,6 +153,9 @@ impl GeminiProvider {
         {
             return Err(ProviderError::AuthFailed("Invalid API key".into()));
         }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
 
         if !status.is_success() {
             return Err(ProviderError::Parse(format!("HTTP {}", status)));
@@ -159,21 +165,24 @@ impl GeminiProvider {
             ProviderError::Parse(format!("Failed to parse models response: {}", e))
         })?;
 
-        let mut snapshot = UsageSnapshot::new();
+        let mut snapshot = UsageSnapshot::new().with_source(UsageSource::ApiKey);
 
         // Gemini free tier has rate limits but no easy way to query current usage
         // We'll show availability status instead
         let model_count = models.models.as_ref().map(|m| m.len()).unwrap_or(0);
 
         let identity = IdentitySnapshot::new()
-            .with_plan(if model_count > 0 { "Active" } else { "Unknown" });
+            .with_plan(i18n::t(if model_count > 0 { "plan.active" } else { "plan.unknown" }));
 
         // Create a simple status indicator
         // Note: Gemini doesn't expose usage quotas via API like OpenAI does
         // We can only verify the key works
         snapshot = snapshot
             .with_primary(
-                RateWindow::new(0.0).with_reset_description(format!("{} models available", model_count)),
+                RateWindow::new(0.0).with_reset_description(i18n::t_args(
+                    "window.gemini.models",
+                    &[("count", &model_count.to_string())],
+                )),
             )
             .with_identity(identity);
 
@@ -181,6 +190,44 @@ impl GeminiProvider {
     }
 }
 
+/// Builds the daily request window for the Gemini CLI
+fn cli_window(count: u32, limit: u32, resets_at: chrono::DateTime<chrono::Utc>) -> RateWindow {
+    let percent = if limit > 0 {
+        (count as f64 / limit as f64 * 100.0).min(100.0)
+    } else {
+        0.0
+    };
+
+    RateWindow::new(percent)
+        .with_window_minutes(24 * 60)
+        .with_resets_at(resets_at)
+        .with_reset_description(i18n::t_args(
+            "window.gemini.cli_requests",
+            &[("count", &count.to_string()), ("limit", &limit.to_string())],
+        ))
+}
+
+impl GeminiProvider {
+    /// Counts today's Gemini CLI requests, if the CLI is installed
+    async fn fetch_cli_usage(&self) -> Option<RateWindow> {
+        let collector = GeminiCliCollector::new().filter(|c| c.is_installed())?;
+        let limit = self.config.read().await.cli_daily_limit;
+        let (start, resets_at) = quota_day(chrono::Utc::now());
+
+        match tokio::task::spawn_blocking(move || collector.count_requests(start)).await {
+            Ok(Ok(count)) => Some(cli_window(count, limit, resets_at)),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to read Gemini CLI logs: {}", e);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Gemini CLI log collection panicked: {}", e);
+                None
+            }
+        }
+    }
+}
+
 impl Default for GeminiProvider {
     fn default() -> Self {
         Self::new()
@@ -197,6 +244,10 @@ impl Provider for GeminiProvider {
         "Gemini"
     }
 
+    fn brand_color(&self) -> &'static str {
+        "#4285f4"
+    }
+
     fn is_enabled(&self) -> bool {
         true
     }
@@ -205,38 +256,114 @@ impl Provider for GeminiProvider {
         false // Uses API key
     }
 
-    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
-        let api_key = self
-            .load_api_key()
-            .await
-            .ok_or(ProviderError::AuthRequired)?;
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://aistudio.google.com"),
+            ProviderPage::Billing => Some("https://console.cloud.google.com/billing"),
+            ProviderPage::ApiKeys => Some("https://aistudio.google.com/app/apikey"),
+            ProviderPage::Docs => Some("https://ai.google.dev/gemini-api/docs/rate-limits"),
+            ProviderPage::Status => Some("https://status.cloud.google.com"),
+        }
+    }
 
-        let snapshot = self.fetch_usage(&api_key).await?;
-        *self.last_snapshot.write().await = Some(snapshot.clone());
-        Ok(snapshot)
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        let cli_window = self.fetch_cli_usage().await;
+
+        let snapshot = match (self.load_api_key().await, cli_window) {
+            // The CLI gauge is the meaningful number; API status moves down
+            (Some(api_key), Some(cli_window)) => {
+                let mut snapshot = self.fetch_usage(&api_key).await?;
+                snapshot.secondary = snapshot.primary.take();
+                snapshot
+                    .with_primary(cli_window)
+                    .with_source(UsageSource::LocalLogs)
+                    .with_supplement(UsageSource::ApiKey)
+            }
+            (Some(api_key), None) => self.fetch_usage(&api_key).await?,
+            (None, Some(cli_window)) => UsageSnapshot::new()
+                .with_source(UsageSource::LocalLogs)
+                .with_primary(cli_window),
+            (None, None) => return Err(ProviderError::AuthRequired),
+        };
+
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
     }
 
     async fn login(&self) -> Result<bool, ProviderError> {
         // Gemini uses API keys
-        if let Err(e) = opener::open("https://aistudio.google.com/app/apikey") {
-            tracing::warn!("Failed to open browser: {}", e);
+        if let Err(e) = self.open_page(ProviderPage::ApiKeys) {
+            tracing::warn!("{}", e);
         }
         Ok(false)
     }
 
     async fn logout(&self) -> Result<(), ProviderError> {
-        *self.api_key.write().await = None;
         *self.last_snapshot.write().await = None;
-        Ok(())
+        self.api_key.log_out().await
     }
 
     async fn is_available(&self) -> bool {
         self.load_api_key().await.is_some()
+            || GeminiCliCollector::new().is_some_and(|c| c.is_installed())
     }
 
     fn auth_methods(&self) -> Vec<AuthMethod> {
         vec![AuthMethod::ApiToken]
     }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        let cli = match GeminiCliCollector::new() {
+            Some(collector) if collector.is_installed() => {
+                CredentialProbe::found(CredentialLocation::File, "~/.gemini/tmp")
+                    .with_note("Gemini CLI logs; counted without an API key")
+            }
+            _ => CredentialProbe::missing(CredentialLocation::File, "~/.gemini/tmp"),
+        };
+
+        vec![
+            probe_stored_api_key(self.id()),
+            probe_env("GOOGLE_API_KEY"),
+            probe_env("GEMINI_API_KEY"),
+            probe_keychain("google-gemini", "api_key", |key| Ok(key.to_string())),
+            cli,
+        ]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("api_key", "API key", SettingKind::Secret)
+                .with_placeholder("AIza..."),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(GeminiConfig::default().api_base_url),
+            SettingField::new("cli_daily_limit", "Gemini CLI daily limit", SettingKind::Number)
+                .with_placeholder(DEFAULT_DAILY_LIMIT.to_string()),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        if let Some(key) = values.get("api_key").map(|v| v.trim()) {
+            if key.is_empty() {
+                self.api_key.clear().await;
+            } else {
+                self.set_api_key(key).await;
+            }
+        }
+
+        let mut config = self.config.write().await;
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                GeminiConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        if let Some(limit) = values.get("cli_daily_limit").map(|v| v.trim()) {
+            config.cli_daily_limit = limit.parse().unwrap_or(DEFAULT_DAILY_LIMIT);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -263,7 +390,48 @@ mod tests {
         let provider = GeminiProvider::new();
         provider.set_api_key("test-api-key").await;
 
-        let key = provider.api_key.read().await;
-        assert_eq!(key.as_ref().map(|s| s.as_str()), Some("test-api-key"));
+        let key = provider.api_key.cached().await;
+        assert_eq!(key.as_deref(), Some("test-api-key"));
+    }
+
+    #[test]
+    fn test_cli_window() {
+        let resets_at = chrono::Utc::now();
+        let window = cli_window(250, 1000, resets_at);
+        assert_eq!(window.used_percent, 25.0);
+        assert_eq!(window.resets_at, Some(resets_at));
+        assert!(window.reset_description.unwrap().contains("250"));
+
+        assert_eq!(cli_window(1500, 1000, resets_at).used_percent, 100.0);
+        assert_eq!(cli_window(5, 0, resets_at).used_percent, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let provider = GeminiProvider::new();
+        let values = HashMap::from([
+            ("api_key".to_string(), "sk-from-settings".to_string()),
+            ("api_base_url".to_string(), "http://localhost:8080/".to_string()),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.api_key.cached().await.as_deref(),
+            Some("sk-from-settings")
+        );
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+
+        let invalid = HashMap::from([("api_base_url".to_string(), "localhost".to_string())]);
+        assert!(provider.apply_settings(&invalid).await.is_err());
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+
+        let limit = HashMap::from([("cli_daily_limit".to_string(), "250".to_string())]);
+        provider.apply_settings(&limit).await.unwrap();
+        assert_eq!(provider.config.read().await.cli_daily_limit, 250);
     }
 }
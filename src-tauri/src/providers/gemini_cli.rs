@@ -0,0 +1,272 @@
+//! Gemini CLI local log collector
+//!
+//! The Gemini CLI keeps per-project state under `~/.gemini/tmp/<hash>/`:
+//! recorded chats in `chats/session-*.json` and a prompt log in `logs.json`.
+//! Counting today's model responses from these gives a usable gauge against
+//! the free tier's daily request limit, which resets at midnight Pacific time.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, TimeZone, Utc, Weekday};
+use serde::Deserialize;
+
+/// Default daily request limit of the free tier
+pub const DEFAULT_DAILY_LIMIT: u32 = 1000;
+
+#[derive(Debug, Deserialize)]
+struct ChatSession {
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptLogEntry {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+/// The Gemini CLI's directory, `~/.gemini`, if a home directory is known
+pub fn gemini_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").ok();
+
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").ok();
+
+    home.map(|h| PathBuf::from(h).join(".gemini"))
+}
+
+/// Counts Gemini CLI requests from its local logs
+pub struct GeminiCliCollector {
+    root: PathBuf,
+}
+
+impl GeminiCliCollector {
+    /// Creates a collector for `~/.gemini`, if a home directory is known
+    pub fn new() -> Option<Self> {
+        gemini_dir().map(Self::with_root)
+    }
+
+    /// Creates a collector for a custom Gemini CLI directory
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns true if the Gemini CLI has been used on this machine
+    pub fn is_installed(&self) -> bool {
+        self.root.join("tmp").is_dir()
+    }
+
+    /// Counts requests made since `since`
+    ///
+    /// Uses model responses from recorded chats where a project has them,
+    /// otherwise the prompts in its `logs.json`.
+    pub fn count_requests(&self, since: DateTime<Utc>) -> std::io::Result<u32> {
+        let projects = match fs::read_dir(self.root.join("tmp")) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        let mut count = 0;
+        for project in projects.flatten() {
+            let dir = project.path();
+            if !dir.is_dir() {
+                continue;
+            }
+
+            count += match count_chat_responses(&dir.join("chats"), since) {
+                Some(responses) => responses,
+                None => count_logged_prompts(&dir.join("logs.json"), since),
+            };
+        }
+        Ok(count)
+    }
+}
+
+/// Counts `gemini` messages in recorded chats; `None` if there are no chats
+fn count_chat_responses(dir: &Path, since: DateTime<Utc>) -> Option<u32> {
+    let entries = fs::read_dir(dir).ok()?;
+    let mut found = false;
+    let mut count = 0;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        found = true;
+
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(session) = serde_json::from_str::<ChatSession>(&content) else {
+            continue;
+        };
+        count += session
+            .messages
+            .iter()
+            .filter(|m| m.kind.as_deref() == Some("gemini"))
+            .filter(|m| m.timestamp.is_some_and(|t| t >= since))
+            .count() as u32;
+    }
+
+    found.then_some(count)
+}
+
+/// Counts user prompts in a project's `logs.json`
+fn count_logged_prompts(path: &Path, since: DateTime<Utc>) -> u32 {
+    let Ok(content) = fs::read_to_string(path) else {
+        return 0;
+    };
+    let Ok(entries) = serde_json::from_str::<Vec<PromptLogEntry>>(&content) else {
+        return 0;
+    };
+
+    entries
+        .iter()
+        .filter(|e| e.kind.as_deref() == Some("user"))
+        .filter(|e| e.timestamp.is_some_and(|t| t >= since))
+        .count() as u32
+}
+
+/// Returns the US Pacific offset in effect at `now`
+///
+/// DST runs from 2:00 on the second Sunday of March to 2:00 on the first
+/// Sunday of November.
+fn pacific_offset(now: DateTime<Utc>) -> FixedOffset {
+    let year = now.year();
+    let sunday = |month, week| {
+        NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, week)
+            .and_then(|d| d.and_hms_opt(2, 0, 0))
+    };
+
+    let standard = FixedOffset::west_opt(8 * 3600).unwrap();
+    let daylight = FixedOffset::west_opt(7 * 3600).unwrap();
+
+    match (sunday(3, 2), sunday(11, 1)) {
+        (Some(start), Some(end)) => {
+            let dst_start = start.and_utc() + Duration::hours(8);
+            let dst_end = end.and_utc() + Duration::hours(7);
+            if now >= dst_start && now < dst_end {
+                daylight
+            } else {
+                standard
+            }
+        }
+        _ => standard,
+    }
+}
+
+/// Returns the start and end of the quota day containing `now`
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use gptbar_lib::providers::quota_day;
+///
+/// // 07:30 UTC in January is 23:30 the previous day in Pacific time
+/// let (start, end) = quota_day(Utc.with_ymd_and_hms(2025, 1, 15, 7, 30, 0).unwrap());
+/// assert_eq!(start, Utc.with_ymd_and_hms(2025, 1, 14, 8, 0, 0).unwrap());
+/// assert_eq!(end, Utc.with_ymd_and_hms(2025, 1, 15, 8, 0, 0).unwrap());
+/// ```
+pub fn quota_day(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let offset = pacific_offset(now);
+    let local_date = now.with_timezone(&offset).date_naive();
+
+    let midnight = |date: NaiveDate| {
+        let naive = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let offset = pacific_offset(naive.and_utc() + Duration::hours(8));
+        offset
+            .from_local_datetime(&naive)
+            .single()
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|| naive.and_utc())
+    };
+
+    let next = local_date.succ_opt().unwrap_or(local_date);
+    (midnight(local_date), midnight(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_project(root: &Path, hash: &str) -> PathBuf {
+        let dir = root.join("tmp").join(hash);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_count_chat_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = write_project(dir.path(), "abc");
+        fs::create_dir_all(project.join("chats")).unwrap();
+        fs::write(
+            project.join("chats").join("session-1.json"),
+            r#"{"sessionId":"s1","messages":[
+                {"id":"1","type":"user","timestamp":"2025-06-01T10:00:00Z","content":"hi"},
+                {"id":"2","type":"gemini","timestamp":"2025-06-01T10:00:05Z","content":"hello"},
+                {"id":"3","type":"gemini","timestamp":"2025-05-31T10:00:05Z","content":"old"}
+            ]}"#,
+        )
+        .unwrap();
+        // Projects with chats ignore logs.json to avoid double counting
+        fs::write(
+            project.join("logs.json"),
+            r#"[{"type":"user","timestamp":"2025-06-01T10:00:00Z"}]"#,
+        )
+        .unwrap();
+
+        let collector = GeminiCliCollector::with_root(dir.path());
+        assert!(collector.is_installed());
+        let since = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        assert_eq!(collector.count_requests(since).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_count_logged_prompts() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = write_project(dir.path(), "def");
+        fs::write(
+            project.join("logs.json"),
+            r#"[
+                {"sessionId":"s","messageId":0,"type":"user","message":"a","timestamp":"2025-06-01T09:00:00Z"},
+                {"sessionId":"s","messageId":1,"type":"user","message":"b","timestamp":"2025-06-01T11:00:00Z"}
+            ]"#,
+        )
+        .unwrap();
+
+        let since = Utc.with_ymd_and_hms(2025, 6, 1, 10, 0, 0).unwrap();
+        let count = GeminiCliCollector::with_root(dir.path())
+            .count_requests(since)
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_not_installed() {
+        let collector = GeminiCliCollector::with_root("/nonexistent/.gemini");
+        assert!(!collector.is_installed());
+        assert_eq!(collector.count_requests(Utc::now()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_quota_day_during_dst() {
+        // 20:00 UTC in July is 13:00 PDT
+        let (start, end) = quota_day(Utc.with_ymd_and_hms(2025, 7, 10, 20, 0, 0).unwrap());
+        assert_eq!(start, Utc.with_ymd_and_hms(2025, 7, 10, 7, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2025, 7, 11, 7, 0, 0).unwrap());
+    }
+}
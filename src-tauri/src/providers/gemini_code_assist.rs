@@ -0,0 +1,565 @@
+//! Gemini Code Assist provider implementation
+//!
+//! Signing in to the Gemini CLI, Gemini Code Assist in an IDE or Antigravity
+//! with a Google account draws on Code Assist's daily request quota, a
+//! different pool from the `gemini` provider's API key. This provider reads
+//! the OAuth token the Gemini CLI saves in `~/.gemini/oauth_creds.json`,
+//! asks `loadCodeAssist` for the account's tier, and counts today's Gemini
+//! CLI requests against that tier's daily limit. A `countTokens` call, which
+//! costs no quota, tells whether the pool is already used up, which also
+//! covers requests made from an IDE.
+//!
+//! Like Claude, the token is never refreshed here: the Gemini CLI refreshes
+//! it when it runs, and a refresh from GPTBar would race with it.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+
+use crate::i18n;
+
+use super::base::{
+    letter_icon, record_snapshot, validate_settings, AuthMethod, IdentitySnapshot, Provider,
+    ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, SettingsKeys,
+    UsageSnapshot, UsageSource,
+};
+use super::discovery::{probe_file, CredentialProbe};
+use super::gemini_cli::{gemini_dir, quota_day, GeminiCliCollector, DEFAULT_DAILY_LIMIT};
+
+/// Model named in the `countTokens` check; any model of the pool works
+const CHECK_MODEL: &str = "models/gemini-2.5-flash";
+
+/// OAuth credentials saved by the Gemini CLI
+#[derive(Debug, Deserialize)]
+struct GeminiOAuthCredentials {
+    access_token: Option<String>,
+    /// Expiry in milliseconds since the epoch
+    expiry_date: Option<i64>,
+}
+
+impl GeminiOAuthCredentials {
+    /// Reads the access token, explaining why it can't be used
+    fn token_from_json(content: &str, now: DateTime<Utc>) -> Result<String, String> {
+        let credentials =
+            serde_json::from_str::<Self>(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let token = credentials
+            .access_token
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| "No access_token".to_string())?;
+
+        match credentials
+            .expiry_date
+            .and_then(DateTime::from_timestamp_millis)
+        {
+            Some(expires) if expires <= now => Err(format!(
+                "Expired at {}; run `gemini` to refresh it",
+                expires.format("%Y-%m-%d %H:%M UTC")
+            )),
+            _ => Ok(token),
+        }
+    }
+}
+
+/// `loadCodeAssist` response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LoadCodeAssistResponse {
+    current_tier: Option<CodeAssistTier>,
+    cloudaicompanion_project: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CodeAssistTier {
+    id: Option<String>,
+    name: Option<String>,
+}
+
+/// Daily request limit of a Code Assist tier
+fn daily_limit_for(tier_id: &str) -> u32 {
+    match tier_id {
+        "standard-tier" => 1500,
+        "enterprise-tier" => 2000,
+        _ => DEFAULT_DAILY_LIMIT,
+    }
+}
+
+/// Whether a 429 body is about the daily quota rather than the per-minute
+/// rate limit
+fn is_daily_quota_error(body: &str) -> bool {
+    let body = body.to_ascii_lowercase();
+    body.contains("perday") || body.contains("per day") || body.contains("daily")
+}
+
+/// Builds the daily request window
+///
+/// `count` is `None` when the Gemini CLI isn't installed, so only an
+/// exhausted pool can be shown.
+fn daily_window(
+    count: Option<u32>,
+    limit: u32,
+    exhausted: bool,
+    resets_at: DateTime<Utc>,
+) -> RateWindow {
+    let window = |percent: f64, description: String| {
+        RateWindow::new(percent)
+            .with_window_minutes(24 * 60)
+            .with_resets_at(resets_at)
+            .with_reset_description(description)
+    };
+    let limit_arg = limit.to_string();
+
+    if exhausted {
+        return window(100.0, i18n::t("window.gemini_code_assist.exhausted"));
+    }
+    match count {
+        Some(count) => {
+            let percent = if limit > 0 {
+                (count as f64 / limit as f64 * 100.0).min(100.0)
+            } else {
+                0.0
+            };
+            window(
+                percent,
+                i18n::t_args(
+                    "window.gemini_code_assist.requests",
+                    &[("count", &count.to_string()), ("limit", &limit_arg)],
+                ),
+            )
+        }
+        None => window(
+            0.0,
+            i18n::t_args("window.gemini_code_assist.limit", &[("limit", &limit_arg)]),
+        ),
+    }
+}
+
+/// Configuration for Gemini Code Assist provider
+#[derive(Debug, Clone)]
+pub struct GeminiCodeAssistConfig {
+    /// Code Assist API base URL
+    pub api_base_url: String,
+    /// Daily request limit; `None` uses the limit of the account's tier
+    pub daily_limit: Option<u32>,
+}
+
+impl Default for GeminiCodeAssistConfig {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://cloudcode-pa.googleapis.com".to_string(),
+            daily_limit: None,
+        }
+    }
+}
+
+/// Gemini Code Assist provider
+pub struct GeminiCodeAssistProvider {
+    client: Client,
+    config: RwLock<GeminiCodeAssistConfig>,
+    last_snapshot: RwLock<Option<UsageSnapshot>>,
+    /// Remembers a logout, after which the Gemini CLI's token is ignored
+    /// until Login, even across restarts
+    keys: SettingsKeys,
+}
+
+impl GeminiCodeAssistProvider {
+    /// Creates a new Gemini Code Assist provider
+    pub fn new() -> Self {
+        Self::with_config(GeminiCodeAssistConfig::default())
+    }
+
+    /// Creates a new Gemini Code Assist provider with custom configuration
+    pub fn with_config(config: GeminiCodeAssistConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: RwLock::new(config),
+            last_snapshot: RwLock::new(None),
+            keys: SettingsKeys::new(),
+        }
+    }
+
+    /// Path of the Gemini CLI's OAuth credentials
+    fn credentials_path() -> Option<PathBuf> {
+        gemini_dir().map(|dir| dir.join("oauth_creds.json"))
+    }
+
+    /// Reads the Gemini CLI's OAuth token
+    ///
+    /// Read on every fetch, so a token the CLI refreshed is picked up.
+    async fn load_token(&self) -> Result<String, ProviderError> {
+        if self.keys.is_logged_out(self.id()) {
+            return Err(ProviderError::AuthRequired);
+        }
+        let path = Self::credentials_path().ok_or(ProviderError::AuthRequired)?;
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ProviderError::AuthRequired)
+            }
+            Err(e) => return Err(ProviderError::Storage(e.to_string())),
+        };
+        GeminiOAuthCredentials::token_from_json(&content, Utc::now())
+            .map_err(ProviderError::AuthFailed)
+    }
+
+    /// POSTs `body` to a `v1internal` method
+    async fn call(
+        &self,
+        token: &str,
+        method: &str,
+        body: serde_json::Value,
+    ) -> Result<reqwest::Response, ProviderError> {
+        let url = format!(
+            "{}/v1internal:{}",
+            self.config.read().await.api_base_url,
+            method
+        );
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(ProviderError::AuthFailed(
+                "OAuth token rejected; run `gemini` to sign in again".into(),
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Asks for the account's tier and Code Assist project
+    async fn load_code_assist(&self, token: &str) -> Result<LoadCodeAssistResponse, ProviderError> {
+        let body = serde_json::json!({
+            "metadata": {
+                "ideType": "IDE_UNSPECIFIED",
+                "platform": "PLATFORM_UNSPECIFIED",
+                "pluginType": "GEMINI",
+            }
+        });
+        let response = self.call(token, "loadCodeAssist", body).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+        response.json().await.map_err(|e| {
+            ProviderError::Parse(format!("Failed to parse loadCodeAssist response: {}", e))
+        })
+    }
+
+    /// Whether today's quota is used up, from a `countTokens` call
+    async fn is_exhausted(
+        &self,
+        token: &str,
+        project: Option<&str>,
+    ) -> Result<bool, ProviderError> {
+        let mut body = serde_json::json!({
+            "request": {
+                "model": CHECK_MODEL,
+                "contents": [{ "role": "user", "parts": [{ "text": "ping" }] }],
+            }
+        });
+        if let Some(project) = project {
+            body["project"] = project.into();
+        }
+        let response = self.call(token, "countTokens", body).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            if is_daily_quota_error(&body) {
+                return Ok(true);
+            }
+            return Err(ProviderError::rate_limited(&headers));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+        Ok(false)
+    }
+
+    /// Counts today's Gemini CLI requests, if the CLI is installed
+    async fn count_cli_requests(&self, since: DateTime<Utc>) -> Option<u32> {
+        let collector = GeminiCliCollector::new().filter(|c| c.is_installed())?;
+        match tokio::task::spawn_blocking(move || collector.count_requests(since)).await {
+            Ok(Ok(count)) => Some(count),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to read Gemini CLI logs: {}", e);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Gemini CLI log collection panicked: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Default for GeminiCodeAssistProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for GeminiCodeAssistProvider {
+    fn id(&self) -> &'static str {
+        "gemini_code_assist"
+    }
+
+    fn name(&self) -> &'static str {
+        "Gemini Code Assist"
+    }
+
+    fn brand_color(&self) -> &'static str {
+        "#1a73e8"
+    }
+
+    fn icon_svg(&self) -> String {
+        // "G" is taken by Gemini
+        letter_icon('A', self.brand_color())
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn supports_login(&self) -> bool {
+        false // Signs in through the Gemini CLI
+    }
+
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://codeassist.google"),
+            ProviderPage::Billing => Some("https://console.cloud.google.com/billing"),
+            ProviderPage::ApiKeys => None,
+            ProviderPage::Docs => {
+                Some("https://developers.google.com/gemini-code-assist/resources/quotas")
+            }
+            ProviderPage::Status => Some("https://status.cloud.google.com"),
+        }
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        let token = self.load_token().await?;
+        let assist = self.load_code_assist(&token).await?;
+        let tier = assist.current_tier.ok_or_else(|| {
+            ProviderError::NotAvailable(
+                "Code Assist isn't set up for this account; run `gemini` to sign in".into(),
+            )
+        })?;
+
+        let project = assist.cloudaicompanion_project;
+        let exhausted = self.is_exhausted(&token, project.as_deref()).await?;
+        let (start, resets_at) = quota_day(Utc::now());
+        let count = self.count_cli_requests(start).await;
+        let limit = self
+            .config
+            .read()
+            .await
+            .daily_limit
+            .unwrap_or_else(|| daily_limit_for(tier.id.as_deref().unwrap_or_default()));
+
+        let mut identity = IdentitySnapshot::new().with_plan(
+            tier.name
+                .or(tier.id)
+                .unwrap_or_else(|| i18n::t("plan.unknown")),
+        );
+        if let Some(project) = project {
+            identity = identity.with_organization(project);
+        }
+
+        let mut snapshot = UsageSnapshot::new()
+            .with_source(UsageSource::OAuthApi)
+            .with_primary(daily_window(count, limit, exhausted, resets_at))
+            .with_identity(identity);
+        if count.is_some() {
+            snapshot = snapshot.with_supplement(UsageSource::LocalLogs);
+        }
+
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
+    }
+
+    async fn login(&self) -> Result<bool, ProviderError> {
+        // The token comes from the Gemini CLI; this only stops ignoring it
+        self.keys.log_in(self.id())?;
+        Ok(self.load_token().await.is_ok())
+    }
+
+    async fn logout(&self) -> Result<(), ProviderError> {
+        // The credentials belong to the Gemini CLI, so they stay on disk
+        *self.last_snapshot.write().await = None;
+        self.keys.log_out(self.id())
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.keys.is_logged_out(self.id())
+            && Self::credentials_path().is_some_and(|path| path.exists())
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::OAuth]
+    }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        vec![probe_file(Self::credentials_path(), |content| {
+            GeminiOAuthCredentials::token_from_json(content, Utc::now())
+        })]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("daily_limit", "Daily request limit", SettingKind::Number)
+                .with_placeholder("From your plan"),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(GeminiCodeAssistConfig::default().api_base_url),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        let mut config = self.config.write().await;
+        if let Some(limit) = values.get("daily_limit").map(|v| v.trim()) {
+            config.daily_limit = limit.parse().ok();
+        }
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                GeminiCodeAssistConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_provider_new() {
+        let provider = GeminiCodeAssistProvider::new();
+        assert_eq!(provider.id(), "gemini_code_assist");
+        assert_eq!(provider.name(), "Gemini Code Assist");
+        assert_eq!(provider.auth_methods(), vec![AuthMethod::OAuth]);
+    }
+
+    #[test]
+    fn test_token_from_json() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let valid =
+            r#"{"access_token":"ya29.token","refresh_token":"1//r","expiry_date":1748786400000}"#;
+        assert_eq!(
+            GeminiOAuthCredentials::token_from_json(valid, now),
+            Ok("ya29.token".to_string())
+        );
+
+        let expired = r#"{"access_token":"ya29.token","expiry_date":1748772000000}"#;
+        let note = GeminiOAuthCredentials::token_from_json(expired, now).unwrap_err();
+        assert!(note.starts_with("Expired at 2025-06-01 10:00 UTC"));
+
+        assert!(GeminiOAuthCredentials::token_from_json(r#"{"expiry_date":1}"#, now).is_err());
+        assert!(GeminiOAuthCredentials::token_from_json("not json", now).is_err());
+    }
+
+    #[test]
+    fn test_load_code_assist_response() {
+        let response: LoadCodeAssistResponse = serde_json::from_str(
+            r#"{"currentTier":{"id":"standard-tier","name":"Gemini Code Assist Standard"},
+                "allowedTiers":[],"cloudaicompanionProject":"my-project-123"}"#,
+        )
+        .unwrap();
+        let tier = response.current_tier.unwrap();
+        assert_eq!(tier.id.as_deref(), Some("standard-tier"));
+        assert_eq!(daily_limit_for("standard-tier"), 1500);
+        assert_eq!(daily_limit_for("free-tier"), DEFAULT_DAILY_LIMIT);
+        assert_eq!(
+            response.cloudaicompanion_project.as_deref(),
+            Some("my-project-123")
+        );
+    }
+
+    #[test]
+    fn test_is_daily_quota_error() {
+        assert!(is_daily_quota_error(
+            r#"{"error":{"code":429,"status":"RESOURCE_EXHAUSTED","details":[{"quotaId":"RequestsPerDayPerUser"}]}}"#
+        ));
+        assert!(!is_daily_quota_error(
+            r#"{"error":{"code":429,"details":[{"quotaId":"RequestsPerMinutePerUser"}]}}"#
+        ));
+    }
+
+    #[test]
+    fn test_daily_window() {
+        let resets_at = Utc::now();
+        let window = daily_window(Some(300), 1500, false, resets_at);
+        assert_eq!(window.used_percent, 20.0);
+        assert_eq!(window.resets_at, Some(resets_at));
+        assert!(window.reset_description.unwrap().contains("300"));
+
+        assert_eq!(
+            daily_window(Some(10), 1500, true, resets_at).used_percent,
+            100.0
+        );
+        assert_eq!(daily_window(None, 1500, false, resets_at).used_percent, 0.0);
+        assert_eq!(daily_window(Some(5), 0, false, resets_at).used_percent, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let provider = GeminiCodeAssistProvider::new();
+        let values = HashMap::from([
+            ("daily_limit".to_string(), "2000".to_string()),
+            (
+                "api_base_url".to_string(),
+                "http://localhost:8080/".to_string(),
+            ),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+        let config = provider.config.read().await.clone();
+        assert_eq!(config.daily_limit, Some(2000));
+        assert_eq!(config.api_base_url, "http://localhost:8080");
+
+        let cleared = HashMap::from([("daily_limit".to_string(), String::new())]);
+        provider.apply_settings(&cleared).await.unwrap();
+        assert_eq!(provider.config.read().await.daily_limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_logout_ignores_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = GeminiCodeAssistProvider {
+            keys: SettingsKeys::in_dir(dir.path()),
+            ..GeminiCodeAssistProvider::new()
+        };
+        provider.logout().await.unwrap();
+        assert!(!provider.is_available().await);
+        assert!(matches!(
+            provider.load_token().await,
+            Err(ProviderError::AuthRequired)
+        ));
+
+        // The logout outlasts a restart
+        let restarted = GeminiCodeAssistProvider {
+            keys: SettingsKeys::in_dir(dir.path()),
+            ..GeminiCodeAssistProvider::new()
+        };
+        assert!(!restarted.is_available().await);
+        restarted.login().await.unwrap();
+        assert!(!restarted.keys.is_logged_out(restarted.id()));
+    }
+}
@@ -0,0 +1,23 @@
+//! Helpers shared by the Claude Code and Codex local log collectors
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Recursively gathers `.jsonl` files; a missing root yields no files
+pub(super) fn find_logs(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_logs(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -6,38 +6,148 @@
 //! - Liskov Substitution: Any Provider implementation is interchangeable
 //! - Interface Segregation: Small, focused traits
 //! - Dependency Inversion: Depends on abstractions (Provider trait)
+//!
+//! Each service is behind a cargo feature of the same name (`claude`,
+//! `openai`, `gemini`, `gemini-code-assist`, `codex`, `together`,
+//! `elevenlabs`, `windsurf`), all on by default through `all-providers`.
+//! [`ProviderRegistry`] only holds the providers compiled in, and
+//! [`COMPILED_PROVIDERS`] lists their IDs.
+//!
+//! Services that only have a web dashboard can be described with a
+//! [`WebDashboardSpec`] instead of a provider of their own.
 
+mod aggregate;
+mod auth_state;
 mod base;
+#[cfg(feature = "claude")]
 mod claude;
+#[cfg(feature = "claude")]
+mod claude_admin;
+#[cfg(feature = "claude")]
+mod claude_local;
+#[cfg(feature = "codex")]
 mod codex;
+#[cfg(feature = "codex")]
+mod codex_local;
+mod demo;
+mod discovery;
+#[cfg(feature = "elevenlabs")]
+mod elevenlabs;
+#[cfg(feature = "gemini")]
 mod gemini;
+#[cfg(any(feature = "gemini", feature = "gemini-code-assist"))]
+mod gemini_cli;
+#[cfg(feature = "gemini-code-assist")]
+mod gemini_code_assist;
+#[cfg(any(feature = "claude", feature = "codex"))]
+mod local_logs;
+#[cfg(feature = "openai")]
 mod openai;
+#[cfg(feature = "together")]
+mod together;
+mod web_dashboard;
+#[cfg(feature = "windsurf")]
+mod windsurf;
 
+pub use aggregate::{AggregateMode, UsageAggregate, AGGREGATE_ID};
+pub use auth_state::{AuthObservation, AuthState, AuthStateTracker, AUTH_CHECK_TTL};
 pub use base::*;
-pub use claude::ClaudeProvider;
+#[cfg(feature = "claude")]
+pub use claude::{ClaudeConfig, ClaudeProvider, ClaudeUsageMode};
+#[cfg(feature = "claude")]
+pub use claude_local::ClaudeLogCollector;
+#[cfg(feature = "codex")]
 pub use codex::CodexProvider;
+#[cfg(feature = "codex")]
+pub use codex_local::CodexLogCollector;
+pub use demo::{demo_snapshot, DemoProvider, DEMO_ID};
+pub use discovery::{
+    probe_env, probe_file, probe_keychain, probe_stored_api_key, CredentialLocation,
+    CredentialProbe, CredentialReport, ProviderCredentials,
+};
+#[cfg(feature = "elevenlabs")]
+pub use elevenlabs::{ElevenLabsConfig, ElevenLabsProvider};
+#[cfg(feature = "gemini")]
 pub use gemini::GeminiProvider;
-pub use openai::OpenAIProvider;
+#[cfg(any(feature = "gemini", feature = "gemini-code-assist"))]
+pub use gemini_cli::{quota_day, GeminiCliCollector};
+#[cfg(feature = "gemini-code-assist")]
+pub use gemini_code_assist::{GeminiCodeAssistConfig, GeminiCodeAssistProvider};
+#[cfg(feature = "openai")]
+pub use openai::{OpenAIProvider, OrganizationInfo};
+#[cfg(feature = "together")]
+pub use together::{TogetherConfig, TogetherProvider};
+pub use web_dashboard::{WebDashboardProvider, WebDashboardSpec, WindowMapping};
+#[cfg(feature = "windsurf")]
+pub use windsurf::{WindsurfConfig, WindsurfProvider};
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// IDs of the providers compiled into this build
+pub const COMPILED_PROVIDERS: &[&str] = &[
+    #[cfg(feature = "claude")]
+    "claude",
+    #[cfg(feature = "openai")]
+    "openai",
+    #[cfg(feature = "gemini")]
+    "gemini",
+    #[cfg(feature = "gemini-code-assist")]
+    "gemini_code_assist",
+    #[cfg(feature = "codex")]
+    "codex",
+    #[cfg(feature = "together")]
+    "together",
+    #[cfg(feature = "elevenlabs")]
+    "elevenlabs",
+    #[cfg(feature = "windsurf")]
+    "windsurf",
+];
+
 /// Registry of all available providers
 pub struct ProviderRegistry {
     providers: HashMap<&'static str, Arc<dyn Provider>>,
 }
 
 impl ProviderRegistry {
-    /// Creates a new registry with all providers
+    /// Creates a new registry with every compiled-in provider
     pub fn new() -> Self {
-        let mut providers: HashMap<&'static str, Arc<dyn Provider>> = HashMap::new();
+        let providers: Vec<Arc<dyn Provider>> = vec![
+            #[cfg(feature = "claude")]
+            Arc::new(ClaudeProvider::new()),
+            #[cfg(feature = "openai")]
+            Arc::new(OpenAIProvider::new()),
+            #[cfg(feature = "gemini")]
+            Arc::new(GeminiProvider::new()),
+            #[cfg(feature = "gemini-code-assist")]
+            Arc::new(GeminiCodeAssistProvider::new()),
+            #[cfg(feature = "codex")]
+            Arc::new(CodexProvider::new()),
+            #[cfg(feature = "together")]
+            Arc::new(TogetherProvider::new()),
+            #[cfg(feature = "elevenlabs")]
+            Arc::new(ElevenLabsProvider::new()),
+            #[cfg(feature = "windsurf")]
+            Arc::new(WindsurfProvider::new()),
+        ];
 
-        providers.insert("claude", Arc::new(ClaudeProvider::new()));
-        providers.insert("openai", Arc::new(OpenAIProvider::new()));
-        providers.insert("gemini", Arc::new(GeminiProvider::new()));
-        providers.insert("codex", Arc::new(CodexProvider::new()));
+        let mut registry = Self::empty();
+        for provider in providers {
+            registry.register(provider);
+        }
+        registry
+    }
 
-        Self { providers }
+    /// Creates a registry without providers, to register configured ones
+    pub fn empty() -> Self {
+        Self {
+            providers: HashMap::new(),
+        }
+    }
+
+    /// Adds a provider, replacing any with the same ID
+    pub fn register(&mut self, provider: Arc<dyn Provider>) {
+        self.providers.insert(provider.id(), provider);
     }
 
     /// Gets a provider by ID
@@ -55,6 +165,18 @@ impl ProviderRegistry {
         self.providers.values().cloned().collect()
     }
 
+    /// Gets every provider but the demo one, ordered by ID
+    pub fn real_providers(&self) -> Vec<Arc<dyn Provider>> {
+        let mut providers: Vec<_> = self
+            .providers
+            .values()
+            .filter(|p| p.id() != DEMO_ID)
+            .cloned()
+            .collect();
+        providers.sort_by_key(|p| p.id());
+        providers
+    }
+
     /// Gets provider metadata for all providers
     pub fn metadata(&self) -> Vec<ProviderMetadata> {
         self.providers
@@ -64,6 +186,11 @@ impl ProviderRegistry {
                 name: p.name().to_string(),
                 supports_login: p.supports_login(),
                 auth_methods: p.auth_methods(),
+                settings: p.settings_schema(),
+                pinned: false,
+                brand_color: p.brand_color().to_string(),
+                icon: p.icon_svg(),
+                pages: p.pages(),
             })
             .collect()
     }
@@ -82,4 +209,39 @@ pub struct ProviderMetadata {
     pub name: String,
     pub supports_login: bool,
     pub auth_methods: Vec<AuthMethod>,
+    /// Fields the settings UI renders for this provider
+    #[serde(default)]
+    pub settings: Vec<SettingField>,
+    /// Whether the user pinned this provider to the top
+    #[serde(default)]
+    pub pinned: bool,
+    /// Brand color as a `#rrggbb` hex string
+    #[serde(default)]
+    pub brand_color: String,
+    /// Icon as SVG markup
+    #[serde(default)]
+    pub icon: String,
+    /// Web pages `open_provider_page` can open for this provider
+    #[serde(default)]
+    pub pages: Vec<ProviderPage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_holds_compiled_providers() {
+        let registry = ProviderRegistry::new();
+        let mut ids = registry.provider_ids();
+        ids.sort();
+        let mut compiled = COMPILED_PROVIDERS.to_vec();
+        compiled.sort();
+        assert_eq!(ids, compiled);
+
+        let mut registry = registry;
+        registry.register(Arc::new(DemoProvider::new()));
+        let real: Vec<_> = registry.real_providers().iter().map(|p| p.id()).collect();
+        assert_eq!(real, compiled);
+    }
 }
@@ -1,16 +1,36 @@
 //! OpenAI provider implementation
 //!
 //! Fetches usage data from OpenAI API using API key authentication.
+//!
+//! The API key is taken from the first of these that has one:
+//!
+//! 1. The key entered in the settings, from the secure store or, failing
+//!    that, the config file
+//! 2. The `OPENAI_API_KEY` environment variable
+//! 3. `~/.openai/credentials`
+//! 4. The "openai" keychain entry other tools keep
+//!
+//! A key entered in the settings thus replaces a stale environment variable
+//! right away, without restarting.
 
 use async_trait::async_trait;
 use chrono::Datelike;
-use reqwest::Client;
-use serde::Deserialize;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
 
+use crate::auth::keychain;
+use crate::format;
+use crate::i18n;
+
 use super::base::{
-    AuthMethod, IdentitySnapshot, Provider, ProviderError, RateWindow, UsageSnapshot,
+    record_snapshot, validate_settings, ApiKey, AuthMethod, IdentitySnapshot, ModelUsage, Provider,
+    ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, UsageSnapshot, UsageSource,
+};
+use super::discovery::{
+    probe_env, probe_file, probe_keychain, probe_stored_api_key, CredentialProbe,
 };
 
 /// OpenAI usage response (reserved for future detailed usage)
@@ -63,6 +83,144 @@ struct OpenAIPlan {
 struct OpenAIBillingUsage {
     /// Total usage in cents
     total_usage: Option<f64>,
+    /// Per-day costs broken down by model
+    #[serde(default)]
+    daily_costs: Vec<OpenAIDailyCost>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIDailyCost {
+    #[serde(default)]
+    line_items: Vec<OpenAILineItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAILineItem {
+    /// Model or product name
+    name: String,
+    /// Cost in cents
+    cost: f64,
+}
+
+impl OpenAIBillingUsage {
+    /// Sums line items per model, most expensive first
+    ///
+    /// Percentages are relative to `limit_usd` when one is known.
+    fn model_breakdown(&self, limit_usd: Option<f64>) -> Vec<ModelUsage> {
+        let mut costs: HashMap<&str, f64> = HashMap::new();
+        for item in self.daily_costs.iter().flat_map(|d| &d.line_items) {
+            *costs.entry(item.name.as_str()).or_default() += item.cost / 100.0;
+        }
+
+        let mut by_model: Vec<ModelUsage> = costs
+            .into_iter()
+            .filter(|(_, cost)| *cost > 0.0)
+            .map(|(name, cost)| {
+                let usage = ModelUsage::new(name).with_cost_usd(cost);
+                match limit_usd {
+                    Some(limit) if limit > 0.0 => usage.with_percent(cost / limit * 100.0),
+                    _ => usage,
+                }
+            })
+            .collect();
+        by_model.sort_by(|a, b| {
+            b.cost_usd
+                .partial_cmp(&a.cost_usd)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        by_model
+    }
+}
+
+/// `/v1/me` response, listing the organizations the key's user belongs to
+#[derive(Debug, Deserialize)]
+struct OpenAIMe {
+    #[serde(default)]
+    orgs: OpenAIOrgList,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenAIOrgList {
+    #[serde(default)]
+    data: Vec<OrganizationInfo>,
+}
+
+/// An OpenAI organization the account belongs to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrganizationInfo {
+    /// Organization ID (e.g., "org-...")
+    pub id: String,
+    /// Display name
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Whether requests without an organization header use this one
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Reads the key from `~/.openai/credentials`
+///
+/// Accepts an `OPENAI_API_KEY=...` line or just the key.
+fn parse_credentials_file(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find(|l| l.starts_with("OPENAI_API_KEY="))
+        .map(|l| l.trim_start_matches("OPENAI_API_KEY=").trim().to_string())
+        .or_else(|| {
+            let trimmed = content.trim();
+            if trimmed.starts_with("sk-") {
+                Some(trimmed.to_string())
+            } else {
+                None
+            }
+        })
+}
+
+/// Where the API key is looked for after the settings, in order of
+/// precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum KeySource {
+    /// The `OPENAI_API_KEY` environment variable
+    Environment,
+    /// `~/.openai/credentials`
+    CredentialsFile,
+    /// The "openai" keychain entry other tools keep
+    Keychain,
+}
+
+impl KeySource {
+    /// Every source, in order of precedence
+    const ALL: [Self; 3] = [Self::Environment, Self::CredentialsFile, Self::Keychain];
+
+    /// Reads the key kept in this source
+    fn read(self) -> Option<String> {
+        match self {
+            Self::Environment => std::env::var("OPENAI_API_KEY").ok(),
+            Self::CredentialsFile => {
+                let path = OpenAIProvider::get_credentials_path()?;
+                parse_credentials_file(&std::fs::read_to_string(path).ok()?)
+            }
+            Self::Keychain => keychain::get_password("openai", "api_key"),
+        }
+    }
+
+    /// Names the source for the logs
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Environment => "OPENAI_API_KEY",
+            Self::CredentialsFile => "the credentials file",
+            Self::Keychain => "the system keychain",
+        }
+    }
+}
+
+/// The first non-blank key `read` finds, trying sources in order of
+/// precedence
+fn resolve_api_key(read: impl Fn(KeySource) -> Option<String>) -> Option<(KeySource, String)> {
+    KeySource::ALL.into_iter().find_map(|source| {
+        let key = read(source)?.trim().to_string();
+        (!key.is_empty()).then_some((source, key))
+    })
 }
 
 /// Configuration for OpenAI provider
@@ -72,6 +230,10 @@ pub struct OpenAIConfig {
     pub enabled: bool,
     /// API base URL
     pub api_base_url: String,
+    /// Organization whose usage is shown; falls back to `OPENAI_ORG_ID`
+    pub organization_id: Option<String>,
+    /// Project whose usage is shown; falls back to `OPENAI_PROJECT_ID`
+    pub project_id: Option<String>,
 }
 
 impl Default for OpenAIConfig {
@@ -79,7 +241,39 @@ impl Default for OpenAIConfig {
         Self {
             enabled: false,
             api_base_url: "https://api.openai.com".to_string(),
+            organization_id: None,
+            project_id: None,
+        }
+    }
+}
+
+impl OpenAIConfig {
+    /// Returns the organization ID from config or environment
+    fn organization(&self) -> Option<String> {
+        self.organization_id
+            .clone()
+            .or_else(|| std::env::var("OPENAI_ORG_ID").ok())
+            .filter(|id| !id.is_empty())
+    }
+
+    /// Returns the project ID from config or environment
+    fn project(&self) -> Option<String> {
+        self.project_id
+            .clone()
+            .or_else(|| std::env::var("OPENAI_PROJECT_ID").ok())
+            .filter(|id| !id.is_empty())
+    }
+
+    /// Adds authorization and organization/project headers to a request
+    fn authorize(&self, request: RequestBuilder, api_key: &str) -> RequestBuilder {
+        let mut request = request.header("Authorization", format!("Bearer {}", api_key));
+        if let Some(org) = self.organization() {
+            request = request.header("OpenAI-Organization", org);
+        }
+        if let Some(project) = self.project() {
+            request = request.header("OpenAI-Project", project);
         }
+        request
     }
 }
 
@@ -87,7 +281,7 @@ impl Default for OpenAIConfig {
 pub struct OpenAIProvider {
     client: Client,
     config: RwLock<OpenAIConfig>,
-    api_key: RwLock<Option<String>>,
+    api_key: ApiKey,
     last_snapshot: RwLock<Option<UsageSnapshot>>,
 }
 
@@ -102,14 +296,14 @@ impl OpenAIProvider {
         Self {
             client: Client::new(),
             config: RwLock::new(config),
-            api_key: RwLock::new(None),
+            api_key: ApiKey::new("openai"),
             last_snapshot: RwLock::new(None),
         }
     }
 
     /// Sets the API key
     pub async fn set_api_key(&self, key: &str) {
-        *self.api_key.write().await = Some(key.to_string());
+        self.api_key.set(key).await;
     }
 
     /// Gets the path to OpenAI credentials
@@ -123,57 +317,49 @@ impl OpenAIProvider {
         home.map(|h| PathBuf::from(h).join(".openai").join("credentials"))
     }
 
-    /// Loads API key from environment or file
+    /// Loads the API key from the settings, then the first [`KeySource`]
+    /// that has one
     async fn load_api_key(&self) -> Option<String> {
-        // Check cache first
-        if let Some(key) = self.api_key.read().await.clone() {
-            return Some(key);
-        }
+        self.api_key
+            .load(|| {
+                let (source, key) = resolve_api_key(KeySource::read)?;
+                Some((source.describe(), key))
+            })
+            .await
+    }
 
-        // Try environment variable
-        if let Ok(key) = std::env::var("OPENAI_API_KEY") {
-            tracing::info!("Found OpenAI API key from environment");
-            *self.api_key.write().await = Some(key.clone());
-            return Some(key);
-        }
+    /// Lists the organizations the API key's user belongs to
+    ///
+    /// Used to pick which organization's usage to show when there are several.
+    pub async fn organizations(&self) -> Result<Vec<OrganizationInfo>, ProviderError> {
+        let api_key = self
+            .load_api_key()
+            .await
+            .ok_or(ProviderError::AuthRequired)?;
+        let config = self.config.read().await;
 
-        // Try credentials file
-        if let Some(path) = Self::get_credentials_path() {
-            if path.exists() {
-                if let Ok(content) = std::fs::read_to_string(&path) {
-                    // Simple key=value format or just the key
-                    let key = content
-                        .lines()
-                        .find(|l| l.starts_with("OPENAI_API_KEY="))
-                        .map(|l| l.trim_start_matches("OPENAI_API_KEY=").trim().to_string())
-                        .or_else(|| {
-                            let trimmed = content.trim();
-                            if trimmed.starts_with("sk-") {
-                                Some(trimmed.to_string())
-                            } else {
-                                None
-                            }
-                        });
-
-                    if let Some(k) = key {
-                        tracing::info!("Found OpenAI API key from credentials file");
-                        *self.api_key.write().await = Some(k.clone());
-                        return Some(k);
-                    }
-                }
-            }
-        }
+        let response = self
+            .client
+            .get(format!("{}/v1/me", config.api_base_url))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .send()
+            .await?;
 
-        // Try system keychain
-        if let Ok(entry) = keyring::Entry::new("openai", "api_key") {
-            if let Ok(key) = entry.get_password() {
-                tracing::info!("Found OpenAI API key from system keychain");
-                *self.api_key.write().await = Some(key.clone());
-                return Some(key);
-            }
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(ProviderError::AuthFailed("Invalid API key".into()));
+        }
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !response.status().is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", response.status())));
         }
 
-        None
+        let me: OpenAIMe = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::Parse(e.to_string()))?;
+        Ok(me.orgs.data)
     }
 
     /// Fetches usage via OpenAI API
@@ -183,20 +369,21 @@ impl OpenAIProvider {
         // Fetch subscription/billing info
         let subscription_url = format!("{}/v1/dashboard/billing/subscription", config.api_base_url);
 
-        let sub_response = self
-            .client
-            .get(&subscription_url)
-            .header("Authorization", format!("Bearer {}", api_key))
+        let sub_response = config
+            .authorize(self.client.get(&subscription_url), api_key)
             .send()
             .await?;
 
-        let mut snapshot = UsageSnapshot::new();
+        let mut snapshot = UsageSnapshot::new().with_source(UsageSource::ApiKey);
         let mut identity = IdentitySnapshot::new();
+        if let Some(org) = config.organization() {
+            identity = identity.with_organization(org);
+        }
 
         if sub_response.status().is_success() {
             if let Ok(sub) = sub_response.json::<OpenAISubscription>().await {
                 if let Some(plan) = sub.plan {
-                    identity = identity.with_plan(plan.title.unwrap_or_else(|| "Free".to_string()));
+                    identity = identity.with_plan(plan.title.unwrap_or_else(|| i18n::t("plan.free")));
                 }
 
                 // Get current month usage
@@ -214,14 +401,15 @@ impl OpenAIProvider {
                     config.api_base_url, start_date, end_date
                 );
 
-                if let Ok(usage_response) = self
-                    .client
-                    .get(&usage_url)
-                    .header("Authorization", format!("Bearer {}", api_key))
+                if let Ok(usage_response) = config
+                    .authorize(self.client.get(&usage_url), api_key)
                     .send()
                     .await
                 {
                     if let Ok(usage) = usage_response.json::<OpenAIBillingUsage>().await {
+                        snapshot =
+                            snapshot.with_model_usage(usage.model_breakdown(sub.hard_limit_usd));
+
                         if let (Some(used_cents), Some(limit)) =
                             (usage.total_usage, sub.hard_limit_usd)
                         {
@@ -234,9 +422,11 @@ impl OpenAIProvider {
 
                             snapshot = snapshot.with_primary(
                                 RateWindow::new(percent)
+                                    .with_cost_usd(used_usd)
                                     .with_reset_description(format!(
-                                        "${:.2} / ${:.2}",
-                                        used_usd, limit
+                                        "{} / {}",
+                                        format::format_cost(used_usd),
+                                        format::format_cost(limit)
                                     )),
                             );
                         }
@@ -248,6 +438,9 @@ impl OpenAIProvider {
             if status == reqwest::StatusCode::UNAUTHORIZED {
                 return Err(ProviderError::AuthFailed("Invalid API key".into()));
             }
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(ProviderError::rate_limited(sub_response.headers()));
+            }
             return Err(ProviderError::Parse(format!("HTTP {}", status)));
         }
 
@@ -272,6 +465,10 @@ impl Provider for OpenAIProvider {
         "OpenAI"
     }
 
+    fn brand_color(&self) -> &'static str {
+        "#10a37f"
+    }
+
     fn is_enabled(&self) -> bool {
         // Will be controlled by config
         true
@@ -281,6 +478,16 @@ impl Provider for OpenAIProvider {
         false // Uses API key, not OAuth
     }
 
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://platform.openai.com/usage"),
+            ProviderPage::Billing => Some("https://platform.openai.com/account/billing"),
+            ProviderPage::ApiKeys => Some("https://platform.openai.com/api-keys"),
+            ProviderPage::Docs => Some("https://platform.openai.com/docs/guides/rate-limits"),
+            ProviderPage::Status => Some("https://status.openai.com"),
+        }
+    }
+
     async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
         let api_key = self
             .load_api_key()
@@ -288,23 +495,21 @@ impl Provider for OpenAIProvider {
             .ok_or(ProviderError::AuthRequired)?;
 
         let snapshot = self.fetch_usage(&api_key).await?;
-        *self.last_snapshot.write().await = Some(snapshot.clone());
-        Ok(snapshot)
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
     }
 
     async fn login(&self) -> Result<bool, ProviderError> {
         // OpenAI uses API keys, not OAuth login
         // Open the API keys page
-        if let Err(e) = opener::open("https://platform.openai.com/api-keys") {
-            tracing::warn!("Failed to open browser: {}", e);
+        if let Err(e) = self.open_page(ProviderPage::ApiKeys) {
+            tracing::warn!("{}", e);
         }
         Ok(false)
     }
 
     async fn logout(&self) -> Result<(), ProviderError> {
-        *self.api_key.write().await = None;
         *self.last_snapshot.write().await = None;
-        Ok(())
+        self.api_key.log_out().await
     }
 
     async fn is_available(&self) -> bool {
@@ -314,11 +519,64 @@ impl Provider for OpenAIProvider {
     fn auth_methods(&self) -> Vec<AuthMethod> {
         vec![AuthMethod::ApiToken]
     }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        vec![
+            probe_stored_api_key(self.id()),
+            probe_env("OPENAI_API_KEY"),
+            probe_file(Self::get_credentials_path(), |content| {
+                parse_credentials_file(content).ok_or_else(|| "No API key in file".to_string())
+            }),
+            probe_keychain("openai", "api_key", |key| Ok(key.to_string())),
+        ]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("api_key", "API key", SettingKind::Secret)
+                .with_placeholder("sk-..."),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(OpenAIConfig::default().api_base_url),
+            SettingField::new("organization_id", "Organization ID", SettingKind::Text)
+                .with_placeholder("org-..."),
+            SettingField::new("project_id", "Project ID", SettingKind::Text)
+                .with_placeholder("proj_..."),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        if let Some(key) = values.get("api_key").map(|v| v.trim()) {
+            if key.is_empty() {
+                self.api_key.clear().await;
+            } else {
+                self.set_api_key(key).await;
+            }
+        }
+
+        let mut config = self.config.write().await;
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                OpenAIConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        if let Some(org) = values.get("organization_id").map(|v| v.trim()) {
+            config.organization_id = (!org.is_empty()).then(|| org.to_string());
+        }
+        if let Some(project) = values.get("project_id").map(|v| v.trim()) {
+            config.project_id = (!project.is_empty()).then(|| project.to_string());
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::base::SettingsKeys;
 
     #[test]
     fn test_openai_provider_new() {
@@ -340,7 +598,152 @@ mod tests {
         let provider = OpenAIProvider::new();
         provider.set_api_key("sk-test-key").await;
 
-        let key = provider.api_key.read().await;
-        assert_eq!(key.as_ref().map(|s| s.as_str()), Some("sk-test-key"));
+        let key = provider.api_key.cached().await;
+        assert_eq!(key.as_deref(), Some("sk-test-key"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_logout_requires_reentry() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = OpenAIProvider {
+            api_key: ApiKey::new("openai").with_keys(SettingsKeys::in_dir(dir.path())),
+            ..OpenAIProvider::new()
+        };
+        provider.set_api_key("sk-test-key").await;
+
+        provider.logout().await.unwrap();
+        assert!(provider.api_key.cached().await.is_none());
+        assert!(!provider.is_available().await);
+
+        provider.set_api_key("sk-new-key").await;
+        assert!(provider.is_available().await);
+    }
+
+    #[test]
+    fn test_resolve_api_key_precedence() {
+        let keys: HashMap<KeySource, String> = HashMap::from([
+            (KeySource::Environment, "sk-env".to_string()),
+            (KeySource::CredentialsFile, "sk-file".to_string()),
+            (KeySource::Keychain, "sk-keychain".to_string()),
+        ]);
+
+        assert_eq!(
+            resolve_api_key(|s| keys.get(&s).cloned()),
+            Some((KeySource::Environment, "sk-env".to_string()))
+        );
+
+        // Blank values are skipped
+        let mut blank = keys;
+        blank.insert(KeySource::Environment, "  ".to_string());
+        assert_eq!(
+            resolve_api_key(|s| blank.get(&s).cloned()),
+            Some((KeySource::CredentialsFile, "sk-file".to_string()))
+        );
+        assert_eq!(resolve_api_key(|_| None), None);
+    }
+
+    #[test]
+    fn test_billing_usage_model_breakdown() {
+        let usage: OpenAIBillingUsage = serde_json::from_str(
+            r#"{
+                "total_usage": 450.0,
+                "daily_costs": [
+                    {"timestamp": 1717200000, "line_items": [
+                        {"name": "GPT-4o", "cost": 200.0},
+                        {"name": "GPT-4o mini", "cost": 50.0}
+                    ]},
+                    {"timestamp": 1717286400, "line_items": [
+                        {"name": "GPT-4o", "cost": 200.0},
+                        {"name": "Embedding models", "cost": 0.0}
+                    ]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let by_model = usage.model_breakdown(Some(20.0));
+        assert_eq!(by_model.len(), 2);
+        assert_eq!(by_model[0].model, "GPT-4o");
+        assert_eq!(by_model[0].cost_usd, Some(4.0));
+        assert_eq!(by_model[0].percent, Some(20.0));
+        assert_eq!(by_model[1].cost_usd, Some(0.5));
+
+        assert_eq!(usage.model_breakdown(None)[0].percent, None);
+    }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let provider = OpenAIProvider::new();
+        let values = HashMap::from([
+            ("api_key".to_string(), "sk-from-settings".to_string()),
+            ("api_base_url".to_string(), "http://localhost:8080/".to_string()),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.api_key.cached().await.as_deref(),
+            Some("sk-from-settings")
+        );
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+
+        let invalid = HashMap::from([("api_base_url".to_string(), "localhost".to_string())]);
+        assert!(provider.apply_settings(&invalid).await.is_err());
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+    }
+
+    #[test]
+    fn test_authorize_adds_scope_headers() {
+        let config = OpenAIConfig {
+            organization_id: Some("org-abc".to_string()),
+            project_id: Some("proj_123".to_string()),
+            ..Default::default()
+        };
+        let request = config
+            .authorize(Client::new().get("https://api.openai.com/v1/me"), "sk-test")
+            .build()
+            .unwrap();
+
+        let headers = request.headers();
+        assert_eq!(headers["Authorization"], "Bearer sk-test");
+        assert_eq!(headers["OpenAI-Organization"], "org-abc");
+        assert_eq!(headers["OpenAI-Project"], "proj_123");
+    }
+
+    #[test]
+    fn test_parse_organizations() {
+        let me: OpenAIMe = serde_json::from_str(
+            r#"{
+                "object": "user",
+                "email": "me@example.com",
+                "orgs": {"object": "list", "data": [
+                    {"id": "org-personal", "title": "Personal", "is_default": true},
+                    {"id": "org-work", "title": "Acme", "is_default": false}
+                ]}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(me.orgs.data.len(), 2);
+        assert!(me.orgs.data[0].is_default);
+        assert_eq!(me.orgs.data[1].title.as_deref(), Some("Acme"));
+    }
+
+    #[tokio::test]
+    async fn test_apply_scope_settings() {
+        let provider = OpenAIProvider::new();
+        let values = HashMap::from([
+            ("organization_id".to_string(), " org-work ".to_string()),
+            ("project_id".to_string(), String::new()),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+
+        let config = provider.config.read().await;
+        assert_eq!(config.organization_id.as_deref(), Some("org-work"));
+        assert!(config.project_id.is_none());
     }
 }
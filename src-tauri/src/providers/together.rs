@@ -0,0 +1,352 @@
+//! Together AI provider implementation
+//!
+//! Checks the API key against the documented model list endpoint and shows
+//! how much of the API rate limit is in use, for open-model inference next
+//! to the other providers. The rate limit comes from the `x-ratelimit-*`
+//! headers described at <https://docs.together.ai/docs/rate-limits>; when
+//! the model list response doesn't carry them, only the connected key is
+//! shown, with no usage window. Together has no documented endpoint for the
+//! credit balance, so it isn't shown.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::auth::keychain;
+use crate::i18n;
+
+use super::base::{
+    letter_icon, record_snapshot, validate_settings, ApiKey, AuthMethod, IdentitySnapshot,
+    Provider, ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, UsageSnapshot,
+    UsageSource,
+};
+use super::discovery::{probe_env, probe_keychain, probe_stored_api_key, CredentialProbe};
+
+/// A model in Together's model list response
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct TogetherModel {
+    id: String,
+}
+
+/// Builds the rate limit window from a response's `x-ratelimit-*` headers
+///
+/// `x-ratelimit-reset` is the number of seconds until the limit resets; a
+/// reset too far off to represent is left out.
+fn rate_limit_window(headers: &HeaderMap, now: DateTime<Utc>) -> Option<RateWindow> {
+    let number = |name: &str| -> Option<f64> {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+    };
+
+    let limit = number("x-ratelimit-limit").filter(|limit| *limit > 0.0)?;
+    let remaining = number("x-ratelimit-remaining")?.clamp(0.0, limit);
+    let mut window =
+        RateWindow::new((limit - remaining) / limit * 100.0).with_reset_description(i18n::t_args(
+            "window.together.requests",
+            &[
+                ("remaining", &remaining.to_string()),
+                ("limit", &limit.to_string()),
+            ],
+        ));
+    let resets_at = number("x-ratelimit-reset").and_then(|reset| {
+        chrono::TimeDelta::try_milliseconds((reset * 1000.0) as i64)
+            .and_then(|delay| now.checked_add_signed(delay))
+    });
+    if let Some(resets_at) = resets_at {
+        window = window.with_resets_at(resets_at);
+    }
+    Some(window)
+}
+
+/// Configuration for Together AI provider
+#[derive(Debug, Clone)]
+pub struct TogetherConfig {
+    /// API base URL
+    pub api_base_url: String,
+}
+
+impl Default for TogetherConfig {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://api.together.xyz".to_string(),
+        }
+    }
+}
+
+/// Together AI provider
+pub struct TogetherProvider {
+    client: Client,
+    config: RwLock<TogetherConfig>,
+    api_key: ApiKey,
+    last_snapshot: RwLock<Option<UsageSnapshot>>,
+}
+
+impl TogetherProvider {
+    /// Creates a new Together AI provider
+    pub fn new() -> Self {
+        Self::with_config(TogetherConfig::default())
+    }
+
+    /// Creates a new Together AI provider with custom configuration
+    pub fn with_config(config: TogetherConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: RwLock::new(config),
+            api_key: ApiKey::new("together"),
+            last_snapshot: RwLock::new(None),
+        }
+    }
+
+    /// Sets the API key
+    pub async fn set_api_key(&self, key: &str) {
+        self.api_key.set(key).await;
+    }
+
+    /// Loads the API key from the settings, environment or keychain
+    async fn load_api_key(&self) -> Option<String> {
+        self.api_key.load(Self::discover_api_key).await
+    }
+
+    /// Looks for an API key in the environment, then the keychain
+    fn discover_api_key() -> Option<(&'static str, String)> {
+        if let Ok(key) = std::env::var("TOGETHER_API_KEY") {
+            return Some(("TOGETHER_API_KEY", key));
+        }
+
+        keychain::get_password("together", "api_key").map(|key| ("the system keychain", key))
+    }
+
+    /// Fetches the rate limit, when the response reports one
+    async fn fetch_usage(&self, api_key: &str) -> Result<UsageSnapshot, ProviderError> {
+        let config = self.config.read().await;
+
+        // Listing models checks the key without spending anything
+        let response = self
+            .client
+            .get(format!("{}/v1/models", config.api_base_url))
+            .bearer_auth(api_key)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(ProviderError::AuthFailed("Invalid API key".into()));
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+        let rate_limit = rate_limit_window(response.headers(), Utc::now());
+        let _models: Vec<TogetherModel> = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::Parse(e.to_string()))?;
+
+        let snapshot = UsageSnapshot::new()
+            .with_source(UsageSource::ApiKey)
+            .with_identity(IdentitySnapshot::new().with_plan(i18n::t("plan.connected")));
+        // Without the headers there is no usage to report, and a made-up
+        // 0% window would drag down aggregates and thresholds
+        Ok(match rate_limit {
+            Some(window) => snapshot.with_primary(window),
+            None => snapshot,
+        })
+    }
+}
+
+impl Default for TogetherProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for TogetherProvider {
+    fn id(&self) -> &'static str {
+        "together"
+    }
+
+    fn name(&self) -> &'static str {
+        "Together AI"
+    }
+
+    fn brand_color(&self) -> &'static str {
+        "#0f6fff"
+    }
+
+    fn icon_svg(&self) -> String {
+        letter_icon('T', self.brand_color())
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn supports_login(&self) -> bool {
+        false // Uses API key
+    }
+
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://api.together.ai"),
+            ProviderPage::Billing => Some("https://api.together.ai/settings/billing"),
+            ProviderPage::ApiKeys => Some("https://api.together.ai/settings/api-keys"),
+            ProviderPage::Docs => Some("https://docs.together.ai/docs/rate-limits"),
+            ProviderPage::Status => Some("https://status.together.ai"),
+        }
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        let api_key = self
+            .load_api_key()
+            .await
+            .ok_or(ProviderError::AuthRequired)?;
+
+        let snapshot = self.fetch_usage(&api_key).await?;
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
+    }
+
+    async fn login(&self) -> Result<bool, ProviderError> {
+        if let Err(e) = self.open_page(ProviderPage::ApiKeys) {
+            tracing::warn!("{}", e);
+        }
+        Ok(false)
+    }
+
+    async fn logout(&self) -> Result<(), ProviderError> {
+        *self.last_snapshot.write().await = None;
+        self.api_key.log_out().await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.load_api_key().await.is_some()
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::ApiToken]
+    }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        vec![
+            probe_stored_api_key(self.id()),
+            probe_env("TOGETHER_API_KEY"),
+            probe_keychain("together", "api_key", |key| Ok(key.to_string())),
+        ]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("api_key", "API key", SettingKind::Secret),
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(TogetherConfig::default().api_base_url),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        if let Some(key) = values.get("api_key").map(|v| v.trim()) {
+            if key.is_empty() {
+                self.api_key.clear().await;
+            } else {
+                self.set_api_key(key).await;
+            }
+        }
+
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            self.config.write().await.api_base_url = if url.is_empty() {
+                TogetherConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::SettingsKeys;
+    use reqwest::header::HeaderValue;
+
+    /// A provider whose keys and logouts stay in `dir`
+    fn provider_in(dir: &std::path::Path) -> TogetherProvider {
+        TogetherProvider {
+            api_key: ApiKey::new("together").with_keys(SettingsKeys::in_dir(dir)),
+            ..TogetherProvider::new()
+        }
+    }
+
+    #[test]
+    fn test_together_provider_new() {
+        let provider = TogetherProvider::new();
+        assert_eq!(provider.id(), "together");
+        assert_eq!(provider.name(), "Together AI");
+        assert!(!provider.supports_login());
+    }
+
+    #[tokio::test]
+    async fn test_together_logout_requires_reentry() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = provider_in(dir.path());
+        provider.set_api_key("tgp_v1_test").await;
+        provider.logout().await.unwrap();
+        assert!(!provider.is_available().await);
+
+        provider.set_api_key("tgp_v1_again").await;
+        assert!(provider.is_available().await);
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let now = Utc::now();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("10"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("4"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("2"));
+
+        let window = rate_limit_window(&headers, now).unwrap();
+        assert_eq!(window.used_percent, 60.0);
+        assert_eq!(window.resets_at, Some(now + chrono::Duration::seconds(2)));
+
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1e300"));
+        let window = rate_limit_window(&headers, now).unwrap();
+        assert_eq!(window.used_percent, 60.0);
+        assert!(window.resets_at.is_none());
+
+        headers.remove("x-ratelimit-limit");
+        assert!(rate_limit_window(&headers, now).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_apply_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = provider_in(dir.path());
+        let values = HashMap::from([
+            ("api_key".to_string(), "tgp_v1_settings".to_string()),
+            (
+                "api_base_url".to_string(),
+                "http://localhost:8080/".to_string(),
+            ),
+        ]);
+        provider.apply_settings(&values).await.unwrap();
+        assert_eq!(
+            provider.api_key.cached().await.as_deref(),
+            Some("tgp_v1_settings")
+        );
+        assert_eq!(
+            provider.config.read().await.api_base_url,
+            "http://localhost:8080"
+        );
+    }
+}
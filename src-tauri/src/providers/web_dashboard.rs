@@ -0,0 +1,595 @@
+//! Providers for services that only have a web dashboard
+//!
+//! Many services show usage on their website but have no API for it. When
+//! the dashboard loads its numbers from a JSON endpoint, a
+//! [`WebDashboardSpec`] is all it takes to show them: the domain whose
+//! browser cookies sign the request in, the endpoint, and a JSON pointer
+//! (RFC 6901) for each number. [`WebDashboardProvider`] does the rest.
+//!
+//! ```
+//! use gptbar_lib::providers::{Provider, WebDashboardProvider, WebDashboardSpec, WindowMapping};
+//!
+//! let spec = WebDashboardSpec::new("example", "Example", "example.com")
+//!     .with_endpoint("https://example.com/api/account/usage")
+//!     .with_primary(
+//!         WindowMapping::new("Searches")
+//!             .with_used("/usage/searches")
+//!             .with_limit("/plan/search_limit")
+//!             .with_resets_at("/usage/period_end"),
+//!     )
+//!     .with_plan("/plan/name");
+//! let provider = WebDashboardProvider::new(spec);
+//! assert_eq!(provider.id(), "example");
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use crate::auth::{BrowserType, Cookie, CookieError, CookieExtractor};
+use crate::i18n;
+
+use super::base::{
+    record_snapshot, AuthMethod, IdentitySnapshot, Provider, ProviderError, ProviderPage,
+    RateWindow, SettingsKeys, UsageSnapshot, UsageSource, DEFAULT_BRAND_COLOR,
+};
+use super::discovery::{CredentialLocation, CredentialProbe};
+
+/// Where one window's numbers are in the endpoint's JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowMapping {
+    /// Name shown with the numbers, e.g. "Searches"
+    pub label: &'static str,
+    /// Pointer to the amount used
+    pub used: Option<&'static str>,
+    /// Pointer to the amount allowed
+    pub limit: Option<&'static str>,
+    /// Pointer to the percentage used (0-100), when the endpoint has one
+    pub percent: Option<&'static str>,
+    /// Pointer to the reset time, as RFC 3339 or seconds since the epoch
+    pub resets_at: Option<&'static str>,
+    /// Length of the window, when it is fixed
+    pub window_minutes: Option<i64>,
+}
+
+impl WindowMapping {
+    /// Creates a mapping without any numbers yet
+    pub fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            used: None,
+            limit: None,
+            percent: None,
+            resets_at: None,
+            window_minutes: None,
+        }
+    }
+
+    /// Sets the pointer to the amount used
+    pub fn with_used(mut self, pointer: &'static str) -> Self {
+        self.used = Some(pointer);
+        self
+    }
+
+    /// Sets the pointer to the amount allowed
+    pub fn with_limit(mut self, pointer: &'static str) -> Self {
+        self.limit = Some(pointer);
+        self
+    }
+
+    /// Sets the pointer to the percentage used
+    pub fn with_percent(mut self, pointer: &'static str) -> Self {
+        self.percent = Some(pointer);
+        self
+    }
+
+    /// Sets the pointer to the reset time
+    pub fn with_resets_at(mut self, pointer: &'static str) -> Self {
+        self.resets_at = Some(pointer);
+        self
+    }
+
+    /// Sets the length of the window
+    pub fn with_window_minutes(mut self, minutes: i64) -> Self {
+        self.window_minutes = Some(minutes);
+        self
+    }
+
+    /// Reads the window from `json`
+    ///
+    /// The percentage is taken from `percent`, or else from `used` and
+    /// `limit`; without either there is no window.
+    fn read(&self, json: &Value) -> Option<RateWindow> {
+        let used = self.used.and_then(|p| number_at(json, p));
+        let limit = self.limit.and_then(|p| number_at(json, p));
+        let percent =
+            self.percent
+                .and_then(|p| number_at(json, p))
+                .or_else(|| match (used, limit) {
+                    (Some(used), Some(limit)) if limit > 0.0 => Some(used / limit * 100.0),
+                    _ => None,
+                })?;
+
+        let description = match (used, limit) {
+            (Some(used), Some(limit)) => i18n::t_args(
+                "window.web.used_of",
+                &[
+                    ("label", self.label),
+                    ("used", &used.to_string()),
+                    ("limit", &limit.to_string()),
+                ],
+            ),
+            _ => self.label.to_string(),
+        };
+        let mut window =
+            RateWindow::new(percent.clamp(0.0, 100.0)).with_reset_description(description);
+        if let Some(resets_at) = self.resets_at.and_then(|p| time_at(json, p)) {
+            window = window.with_resets_at(resets_at);
+        }
+        if let Some(minutes) = self.window_minutes {
+            window = window.with_window_minutes(minutes);
+        }
+        Some(window)
+    }
+}
+
+/// Reads a number, also from a numeric string
+fn number_at(json: &Value, pointer: &str) -> Option<f64> {
+    match json.pointer(pointer)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Reads a time given as RFC 3339 or as seconds since the epoch
+fn time_at(json: &Value, pointer: &str) -> Option<DateTime<Utc>> {
+    match json.pointer(pointer)? {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc)),
+        Value::Number(n) => DateTime::from_timestamp(n.as_i64()?, 0),
+        _ => None,
+    }
+}
+
+/// Reads a string, also from a number
+fn text_at(json: &Value, pointer: &str) -> Option<String> {
+    match json.pointer(pointer)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Describes a web-dashboard-only service
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebDashboardSpec {
+    /// Provider ID, e.g. "kagi"
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Domain whose browser cookies sign requests in, e.g. "kagi.com"
+    pub domain: &'static str,
+    /// The one cookie that signs requests in, when the others aren't needed
+    pub session_cookie: Option<&'static str>,
+    /// JSON endpoint the dashboard loads its numbers from
+    pub endpoint: &'static str,
+    /// Extra request headers some endpoints expect
+    pub headers: Vec<(&'static str, &'static str)>,
+    /// Brand color as a `#rrggbb` hex string
+    pub brand_color: &'static str,
+    /// Page with the usage, also opened to log in
+    pub dashboard_url: Option<&'static str>,
+    /// Page with the plan and invoices
+    pub billing_url: Option<&'static str>,
+    /// Main window
+    pub primary: Option<WindowMapping>,
+    /// Second window, e.g. a monthly limit next to a daily one
+    pub secondary: Option<WindowMapping>,
+    /// Pointer to the plan name
+    pub plan: Option<&'static str>,
+    /// Pointer to the account's email
+    pub email: Option<&'static str>,
+}
+
+impl WebDashboardSpec {
+    /// Creates a spec for a service signed in to on `domain`
+    pub fn new(id: &'static str, name: &'static str, domain: &'static str) -> Self {
+        Self {
+            id,
+            name,
+            domain,
+            session_cookie: None,
+            endpoint: "",
+            headers: Vec::new(),
+            brand_color: DEFAULT_BRAND_COLOR,
+            dashboard_url: None,
+            billing_url: None,
+            primary: None,
+            secondary: None,
+            plan: None,
+            email: None,
+        }
+    }
+
+    /// Sends only the named cookie instead of all of the domain's
+    pub fn with_session_cookie(mut self, name: &'static str) -> Self {
+        self.session_cookie = Some(name);
+        self
+    }
+
+    /// Sets the JSON endpoint
+    pub fn with_endpoint(mut self, url: &'static str) -> Self {
+        self.endpoint = url;
+        self
+    }
+
+    /// Adds a request header
+    pub fn with_header(mut self, name: &'static str, value: &'static str) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Sets the brand color
+    pub fn with_brand_color(mut self, color: &'static str) -> Self {
+        self.brand_color = color;
+        self
+    }
+
+    /// Sets the dashboard page
+    pub fn with_dashboard_url(mut self, url: &'static str) -> Self {
+        self.dashboard_url = Some(url);
+        self
+    }
+
+    /// Sets the billing page
+    pub fn with_billing_url(mut self, url: &'static str) -> Self {
+        self.billing_url = Some(url);
+        self
+    }
+
+    /// Sets the main window
+    pub fn with_primary(mut self, window: WindowMapping) -> Self {
+        self.primary = Some(window);
+        self
+    }
+
+    /// Sets the second window
+    pub fn with_secondary(mut self, window: WindowMapping) -> Self {
+        self.secondary = Some(window);
+        self
+    }
+
+    /// Sets the pointer to the plan name
+    pub fn with_plan(mut self, pointer: &'static str) -> Self {
+        self.plan = Some(pointer);
+        self
+    }
+
+    /// Sets the pointer to the account's email
+    pub fn with_email(mut self, pointer: &'static str) -> Self {
+        self.email = Some(pointer);
+        self
+    }
+
+    /// Builds a snapshot from the endpoint's JSON
+    ///
+    /// Fails when the main window can't be read, since that usually means
+    /// the endpoint changed or the session is signed out.
+    fn snapshot(&self, json: &Value) -> Result<UsageSnapshot, ProviderError> {
+        let primary = self
+            .primary
+            .as_ref()
+            .and_then(|mapping| mapping.read(json))
+            .ok_or_else(|| {
+                ProviderError::Parse(format!("No usage in the {} response", self.name))
+            })?;
+
+        let mut identity = IdentitySnapshot::new();
+        if let Some(plan) = self.plan.and_then(|p| text_at(json, p)) {
+            identity = identity.with_plan(plan);
+        }
+        if let Some(email) = self.email.and_then(|p| text_at(json, p)) {
+            identity = identity.with_email(email);
+        }
+
+        let mut snapshot = UsageSnapshot::new()
+            .with_source(UsageSource::Cookie)
+            .with_primary(primary)
+            .with_identity(identity);
+        if let Some(window) = self.secondary.as_ref().and_then(|m| m.read(json)) {
+            snapshot = snapshot.with_secondary(window);
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Browser profiles `cookies` came from, e.g. "Chrome (Default), Edge (Profile 1)"
+fn cookie_sources(cookies: &[Cookie]) -> String {
+    let mut sources: Vec<String> = cookies.iter().map(|c| c.source.to_string()).collect();
+    sources.sort();
+    sources.dedup();
+    sources.join(", ")
+}
+
+/// Provider reading a [`WebDashboardSpec`]'s endpoint with browser cookies
+pub struct WebDashboardProvider {
+    spec: WebDashboardSpec,
+    client: Client,
+    last_snapshot: RwLock<Option<UsageSnapshot>>,
+    /// Remembers a logout, after which browser cookies are ignored until
+    /// Login, even across restarts
+    keys: SettingsKeys,
+}
+
+impl WebDashboardProvider {
+    /// Creates a provider for `spec`
+    pub fn new(spec: WebDashboardSpec) -> Self {
+        Self {
+            spec,
+            client: Client::new(),
+            last_snapshot: RwLock::new(None),
+            keys: SettingsKeys::new(),
+        }
+    }
+
+    /// The service this provider reads
+    pub fn spec(&self) -> &WebDashboardSpec {
+        &self.spec
+    }
+
+    /// Builds the Cookie header from the browser profiles signed in to the
+    /// domain, the latest sign-in winning
+    async fn cookie_header(&self) -> Result<String, ProviderError> {
+        let domain = self.spec.domain;
+        let session_cookie = self.spec.session_cookie;
+        let cookies = tokio::task::spawn_blocking(move || {
+            let extractor = CookieExtractor::new();
+            match session_cookie {
+                Some(name) => extractor
+                    .extract_cookie_any_browser(domain, name)
+                    .map(|cookie| vec![cookie]),
+                None => extractor.extract_cookies_any_browser(domain),
+            }
+        })
+        .await
+        .map_err(|e| ProviderError::Internal(e.to_string()))?
+        .map_err(|e| match e {
+            CookieError::NoCookiesFound(_) | CookieError::CookieNotFound { .. } => {
+                ProviderError::AuthRequired
+            }
+            e => ProviderError::CookieExtraction(e.to_string()),
+        })?;
+        tracing::debug!("{} cookies from {}", self.spec.id, cookie_sources(&cookies));
+        Ok(CookieExtractor::format_cookie_header(&cookies))
+    }
+
+    /// Fetches the endpoint's JSON
+    async fn fetch_json(&self, cookie: &str) -> Result<Value, ProviderError> {
+        let mut request = self
+            .client
+            .get(self.spec.endpoint)
+            .header(reqwest::header::COOKIE, cookie)
+            .header(reqwest::header::ACCEPT, "application/json");
+        for (name, value) in &self.spec.headers {
+            request = request.header(*name, *value);
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(ProviderError::AuthFailed(format!(
+                "Session expired; log in to {} in your browser",
+                self.spec.domain
+            )));
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+        // A login page instead of JSON means the session is gone
+        response.json().await.map_err(|_| {
+            ProviderError::AuthFailed(format!(
+                "No JSON from {}; log in to {} in your browser",
+                self.spec.endpoint, self.spec.domain
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for WebDashboardProvider {
+    fn id(&self) -> &'static str {
+        self.spec.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.spec.name
+    }
+
+    fn brand_color(&self) -> &'static str {
+        self.spec.brand_color
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn supports_login(&self) -> bool {
+        false // Signs in through the browser
+    }
+
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => self.spec.dashboard_url,
+            ProviderPage::Billing => self.spec.billing_url,
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        if self.keys.is_logged_out(self.id()) {
+            return Err(ProviderError::AuthRequired);
+        }
+        let cookie = self.cookie_header().await?;
+        let json = self.fetch_json(&cookie).await?;
+        let snapshot = self.spec.snapshot(&json)?;
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
+    }
+
+    async fn login(&self) -> Result<bool, ProviderError> {
+        // Logging in happens in the browser; this only stops ignoring it
+        self.keys.log_in(self.id())?;
+        if let Err(e) = self.open_page(ProviderPage::Dashboard) {
+            tracing::warn!("{}", e);
+        }
+        Ok(false)
+    }
+
+    async fn logout(&self) -> Result<(), ProviderError> {
+        // The browser's session is left alone
+        *self.last_snapshot.write().await = None;
+        self.keys.log_out(self.id())
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.keys.is_logged_out(self.id()) && CookieExtractor::first_available_browser().is_some()
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::Cookie]
+    }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        let extractor = CookieExtractor::new();
+        let domain = self.spec.domain;
+        BrowserType::all()
+            .iter()
+            .map(|browser| {
+                let found = match self.spec.session_cookie {
+                    Some(name) => extractor
+                        .extract_cookie(*browser, domain, name)
+                        .map(|cookie| {
+                            format!("{} cookie for {} from {}", name, domain, cookie.source)
+                        }),
+                    None => extractor.extract_cookies(*browser, domain).map(|cookies| {
+                        format!(
+                            "{} cookies for {} from {}",
+                            cookies.len(),
+                            domain,
+                            cookie_sources(&cookies)
+                        )
+                    }),
+                };
+                match found {
+                    Ok(note) => CredentialProbe::found(CredentialLocation::Browser, browser.name())
+                        .with_note(note),
+                    Err(e) => CredentialProbe::missing(CredentialLocation::Browser, browser.name())
+                        .with_note(e.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn spec() -> WebDashboardSpec {
+        WebDashboardSpec::new("example", "Example", "example.com")
+            .with_endpoint("https://example.com/api/usage")
+            .with_primary(
+                WindowMapping::new("Searches")
+                    .with_used("/usage/searches")
+                    .with_limit("/plan/limit")
+                    .with_resets_at("/usage/period_end"),
+            )
+            .with_secondary(WindowMapping::new("Assistant").with_percent("/usage/assistant_pct"))
+            .with_plan("/plan/name")
+            .with_email("/account/email")
+    }
+
+    #[test]
+    fn test_snapshot_from_json() {
+        let json = serde_json::json!({
+            "account": {"email": "ada@example.com"},
+            "plan": {"name": "Professional", "limit": "300"},
+            "usage": {
+                "searches": 75,
+                "period_end": "2025-07-01T00:00:00Z",
+                "assistant_pct": 12.5
+            }
+        });
+        let snapshot = spec().snapshot(&json).unwrap();
+
+        let primary = snapshot.primary.unwrap();
+        assert_eq!(primary.used_percent, 25.0);
+        assert_eq!(
+            primary.resets_at,
+            Some(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap())
+        );
+        assert!(primary.reset_description.unwrap().contains("75"));
+        assert_eq!(snapshot.secondary.unwrap().used_percent, 12.5);
+
+        let identity = snapshot.identity.unwrap();
+        assert_eq!(identity.plan.as_deref(), Some("Professional"));
+        assert_eq!(identity.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(snapshot.source.unwrap().kind, UsageSource::Cookie);
+    }
+
+    #[test]
+    fn test_snapshot_needs_primary() {
+        let json = serde_json::json!({"usage": {"searches": 75}});
+        assert!(matches!(
+            spec().snapshot(&json),
+            Err(ProviderError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_time_at_unix_seconds() {
+        let json = serde_json::json!({"reset": 1751328000});
+        assert_eq!(
+            time_at(&json, "/reset"),
+            Some(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(time_at(&json, "/missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_provider_from_spec() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = WebDashboardProvider {
+            keys: SettingsKeys::in_dir(dir.path()),
+            ..WebDashboardProvider::new(spec().with_dashboard_url("https://example.com"))
+        };
+        assert_eq!(provider.id(), "example");
+        assert_eq!(provider.auth_methods(), vec![AuthMethod::Cookie]);
+        assert_eq!(
+            provider.page_url(ProviderPage::Dashboard),
+            Some("https://example.com")
+        );
+        assert_eq!(provider.page_url(ProviderPage::ApiKeys), None);
+
+        provider.logout().await.unwrap();
+        assert!(!provider.is_available().await);
+        assert!(matches!(
+            provider.fetch().await,
+            Err(ProviderError::AuthRequired)
+        ));
+
+        // The logout outlasts a restart
+        let restarted = WebDashboardProvider {
+            keys: SettingsKeys::in_dir(dir.path()),
+            ..WebDashboardProvider::new(spec())
+        };
+        assert!(!restarted.is_available().await);
+    }
+}
@@ -0,0 +1,510 @@
+//! Windsurf (Codeium) provider implementation
+//!
+//! Windsurf keeps the signed-in account's API key in its editor state
+//! database, `User/globalStorage/state.vscdb`, under `windsurfAuthStatus`.
+//! With it, Codeium's `GetUserStatus` reports the plan's prompt credits: how
+//! many the period grants, how many are used, and when the period ends.
+//! Credits come in hundredths, so `50000` is 500 prompt credits.
+//!
+//! The database is only read; signing out of GPTBar leaves Windsurf signed in.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+use crate::i18n;
+
+use super::base::{
+    letter_icon, record_snapshot, validate_settings, AuthMethod, IdentitySnapshot, Provider,
+    ProviderError, ProviderPage, RateWindow, SettingField, SettingKind, SettingsKeys,
+    UsageSnapshot, UsageSource,
+};
+use super::discovery::{CredentialLocation, CredentialProbe};
+
+/// Key of the auth status in Windsurf's state database
+const AUTH_STATUS_KEY: &str = "windsurfAuthStatus";
+
+/// Reads a number that proto3 JSON may send as a string (int64) or a number
+fn de_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::Number(n)) => n.as_f64(),
+            Some(serde_json::Value::String(s)) => s.parse().ok(),
+            _ => None,
+        },
+    )
+}
+
+/// `windsurfAuthStatus` entry
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WindsurfAuthStatus {
+    api_key: Option<String>,
+}
+
+impl WindsurfAuthStatus {
+    /// Reads the API key, explaining why it can't be used
+    fn api_key_from_json(content: &str) -> Result<String, String> {
+        serde_json::from_str::<Self>(content)
+            .map_err(|e| format!("Invalid JSON: {}", e))?
+            .api_key
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| "Not signed in to Windsurf".to_string())
+    }
+}
+
+/// `GetUserStatus` response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetUserStatusResponse {
+    user_status: Option<UserStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserStatus {
+    email: Option<String>,
+    plan_status: Option<PlanStatus>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlanStatus {
+    plan_info: Option<PlanInfo>,
+    plan_start: Option<DateTime<Utc>>,
+    plan_end: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "de_number")]
+    available_prompt_credits: Option<f64>,
+    #[serde(default, deserialize_with = "de_number")]
+    used_prompt_credits: Option<f64>,
+    #[serde(default, deserialize_with = "de_number")]
+    available_flex_credits: Option<f64>,
+    #[serde(default, deserialize_with = "de_number")]
+    used_flex_credits: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlanInfo {
+    plan_name: Option<String>,
+}
+
+impl PlanStatus {
+    /// Builds the window of one kind of credit, if the plan grants any
+    ///
+    /// `available` and `used` are in hundredths of a credit.
+    fn credit_window(
+        &self,
+        key: &str,
+        available: Option<f64>,
+        used: Option<f64>,
+    ) -> Option<RateWindow> {
+        let available = available.filter(|a| *a > 0.0)?;
+        let used = used.unwrap_or(0.0).max(0.0);
+
+        let mut window = RateWindow::new((used / available * 100.0).min(100.0))
+            .with_reset_description(i18n::t_args(
+                key,
+                &[
+                    ("used", &format!("{:.0}", used / 100.0)),
+                    ("limit", &format!("{:.0}", available / 100.0)),
+                ],
+            ));
+        if let Some(end) = self.plan_end {
+            window = window.with_resets_at(end);
+            if let Some(start) = self.plan_start.filter(|start| *start < end) {
+                window = window.with_window_minutes((end - start).num_minutes());
+            }
+        }
+        Some(window)
+    }
+
+    /// Monthly prompt credits
+    fn prompt_window(&self) -> Option<RateWindow> {
+        self.credit_window(
+            "window.windsurf.prompt_credits",
+            self.available_prompt_credits,
+            self.used_prompt_credits,
+        )
+    }
+
+    /// Flex credits bought on top of the plan
+    fn flex_window(&self) -> Option<RateWindow> {
+        self.credit_window(
+            "window.windsurf.flex_credits",
+            self.available_flex_credits,
+            self.used_flex_credits,
+        )
+    }
+}
+
+/// Reads the `windsurfAuthStatus` JSON from a state database
+fn read_auth_status(db_path: &Path) -> rusqlite::Result<Option<String>> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.query_row(
+        "SELECT CAST(value AS TEXT) FROM ItemTable WHERE key = ?1",
+        [AUTH_STATUS_KEY],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Configuration for Windsurf provider
+#[derive(Debug, Clone)]
+pub struct WindsurfConfig {
+    /// Codeium API base URL
+    pub api_base_url: String,
+}
+
+impl Default for WindsurfConfig {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://server.codeium.com".to_string(),
+        }
+    }
+}
+
+/// Windsurf provider
+pub struct WindsurfProvider {
+    client: Client,
+    config: RwLock<WindsurfConfig>,
+    last_snapshot: RwLock<Option<UsageSnapshot>>,
+    /// Remembers a logout, after which Windsurf's key is ignored until
+    /// Login, even across restarts
+    keys: SettingsKeys,
+}
+
+impl WindsurfProvider {
+    /// Creates a new Windsurf provider
+    pub fn new() -> Self {
+        Self::with_config(WindsurfConfig::default())
+    }
+
+    /// Creates a new Windsurf provider with custom configuration
+    pub fn with_config(config: WindsurfConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config: RwLock::new(config),
+            last_snapshot: RwLock::new(None),
+            keys: SettingsKeys::new(),
+        }
+    }
+
+    /// Gets the path to Windsurf's state database
+    fn state_db_path() -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        let config_dir = std::env::var("APPDATA").ok().map(PathBuf::from);
+
+        #[cfg(target_os = "macos")]
+        let config_dir = std::env::var("HOME")
+            .ok()
+            .map(|h| PathBuf::from(h).join("Library/Application Support"));
+
+        // The host's config, also from inside Flatpak
+        #[cfg(target_os = "linux")]
+        let config_dir = crate::sandbox::host_config_dir();
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        let config_dir: Option<PathBuf> = None;
+
+        config_dir.map(|dir| dir.join("Windsurf/User/globalStorage/state.vscdb"))
+    }
+
+    /// Reads Windsurf's API key from its state database
+    ///
+    /// Read on every fetch, so signing in to another account in Windsurf is
+    /// picked up.
+    async fn load_api_key(&self) -> Result<String, ProviderError> {
+        if self.keys.is_logged_out(self.id()) {
+            return Err(ProviderError::AuthRequired);
+        }
+        let path = Self::state_db_path()
+            .filter(|path| path.exists())
+            .ok_or(ProviderError::AuthRequired)?;
+
+        let status = tokio::task::spawn_blocking(move || read_auth_status(&path))
+            .await
+            .map_err(|e| ProviderError::Internal(e.to_string()))?
+            .map_err(|e| ProviderError::Storage(e.to_string()))?
+            .ok_or(ProviderError::AuthRequired)?;
+        WindsurfAuthStatus::api_key_from_json(&status).map_err(ProviderError::AuthFailed)
+    }
+
+    /// Fetches the plan's credits via `GetUserStatus`
+    async fn fetch_usage(&self, api_key: &str) -> Result<UsageSnapshot, ProviderError> {
+        let url = format!(
+            "{}/exa.seat_management_pb.SeatManagementService/GetUserStatus",
+            self.config.read().await.api_base_url
+        );
+        let body = serde_json::json!({
+            "metadata": {
+                "apiKey": api_key,
+                "ideName": "windsurf",
+                "extensionName": "gptbar",
+                "extensionVersion": env!("CARGO_PKG_VERSION"),
+                "locale": "en",
+            }
+        });
+        let response = self.client.post(&url).json(&body).send().await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(ProviderError::AuthFailed(
+                "Windsurf's sign-in was rejected; sign in to Windsurf again".into(),
+            ));
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(response.headers()));
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Parse(format!("HTTP {}", status)));
+        }
+
+        let user = response
+            .json::<GetUserStatusResponse>()
+            .await
+            .map_err(|e| ProviderError::Parse(format!("Failed to parse user status: {}", e)))?
+            .user_status
+            .ok_or_else(|| ProviderError::Parse("No userStatus in response".into()))?;
+        let plan = user.plan_status.unwrap_or_default();
+
+        let mut identity = IdentitySnapshot::new().with_plan(
+            plan.plan_info
+                .as_ref()
+                .and_then(|info| info.plan_name.clone())
+                .unwrap_or_else(|| i18n::t("plan.unknown")),
+        );
+        if let Some(email) = user.email {
+            identity = identity.with_email(email);
+        }
+
+        let mut snapshot = UsageSnapshot::new()
+            .with_source(UsageSource::OAuthApi)
+            .with_identity(identity);
+        if let Some(window) = plan.prompt_window() {
+            snapshot = snapshot.with_primary(window);
+        }
+        if let Some(window) = plan.flex_window() {
+            snapshot = snapshot.with_secondary(window);
+        }
+        Ok(snapshot)
+    }
+}
+
+impl Default for WindsurfProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for WindsurfProvider {
+    fn id(&self) -> &'static str {
+        "windsurf"
+    }
+
+    fn name(&self) -> &'static str {
+        "Windsurf"
+    }
+
+    fn brand_color(&self) -> &'static str {
+        "#09b6a2"
+    }
+
+    fn icon_svg(&self) -> String {
+        letter_icon('W', self.brand_color())
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn supports_login(&self) -> bool {
+        false // Signs in through Windsurf
+    }
+
+    fn page_url(&self, page: ProviderPage) -> Option<&'static str> {
+        match page {
+            ProviderPage::Dashboard => Some("https://windsurf.com/subscription/usage"),
+            ProviderPage::Billing => Some("https://windsurf.com/subscription/manage-plan"),
+            ProviderPage::ApiKeys => None,
+            ProviderPage::Docs => Some("https://docs.windsurf.com/windsurf/accounts/usage"),
+            ProviderPage::Status => Some("https://status.windsurf.com"),
+        }
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, ProviderError> {
+        let api_key = self.load_api_key().await?;
+        let snapshot = self.fetch_usage(&api_key).await?;
+        Ok(record_snapshot(&self.last_snapshot, snapshot).await)
+    }
+
+    async fn login(&self) -> Result<bool, ProviderError> {
+        // The key comes from Windsurf; this only stops ignoring it
+        self.keys.log_in(self.id())?;
+        Ok(self.load_api_key().await.is_ok())
+    }
+
+    async fn logout(&self) -> Result<(), ProviderError> {
+        *self.last_snapshot.write().await = None;
+        self.keys.log_out(self.id())
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.keys.is_logged_out(self.id())
+            && Self::state_db_path().is_some_and(|path| path.exists())
+    }
+
+    fn auth_methods(&self) -> Vec<AuthMethod> {
+        vec![AuthMethod::OAuth]
+    }
+
+    fn discover_credentials(&self) -> Vec<CredentialProbe> {
+        // The database isn't text, so it's read with SQLite rather than
+        // through `probe_file`
+        let Some(path) = Self::state_db_path() else {
+            return vec![CredentialProbe::missing(
+                CredentialLocation::File,
+                "(unknown home directory)",
+            )];
+        };
+        let source = path.display().to_string();
+        if !path.exists() {
+            return vec![CredentialProbe::missing(CredentialLocation::File, source)];
+        }
+
+        let probe = match read_auth_status(&path) {
+            Ok(Some(status)) => match WindsurfAuthStatus::api_key_from_json(&status) {
+                Ok(key) => {
+                    CredentialProbe::found(CredentialLocation::File, source).with_secret(&key)
+                }
+                Err(note) => {
+                    CredentialProbe::missing(CredentialLocation::File, source).with_note(note)
+                }
+            },
+            Ok(None) => CredentialProbe::missing(CredentialLocation::File, source)
+                .with_note("Not signed in to Windsurf"),
+            Err(e) => CredentialProbe::missing(CredentialLocation::File, source)
+                .with_note(format!("Unreadable: {}", e)),
+        };
+        vec![probe]
+    }
+
+    fn settings_schema(&self) -> Vec<SettingField> {
+        vec![
+            SettingField::new("api_base_url", "API base URL", SettingKind::Url)
+                .with_placeholder(WindsurfConfig::default().api_base_url),
+        ]
+    }
+
+    async fn apply_settings(&self, values: &HashMap<String, String>) -> Result<(), ProviderError> {
+        validate_settings(&self.settings_schema(), values)?;
+
+        let mut config = self.config.write().await;
+        if let Some(url) = values.get("api_base_url").map(|v| v.trim()) {
+            config.api_base_url = if url.is_empty() {
+                WindsurfConfig::default().api_base_url
+            } else {
+                url.trim_end_matches('/').to_string()
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_windsurf_provider_new() {
+        let provider = WindsurfProvider::new();
+        assert_eq!(provider.id(), "windsurf");
+        assert_eq!(provider.name(), "Windsurf");
+        assert!(!provider.supports_login());
+    }
+
+    #[test]
+    fn test_read_auth_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.vscdb");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)",
+        )
+        .unwrap();
+        assert_eq!(read_auth_status(&path).unwrap(), None);
+
+        conn.execute(
+            "INSERT INTO ItemTable (key, value) VALUES (?1, ?2)",
+            rusqlite::params![
+                AUTH_STATUS_KEY,
+                br#"{"name":"Ada","apiKey":"sk-ws-01-abc"}"#.to_vec()
+            ],
+        )
+        .unwrap();
+        let status = read_auth_status(&path).unwrap().unwrap();
+        assert_eq!(
+            WindsurfAuthStatus::api_key_from_json(&status),
+            Ok("sk-ws-01-abc".to_string())
+        );
+        assert!(WindsurfAuthStatus::api_key_from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_plan_status_windows() {
+        let response: GetUserStatusResponse = serde_json::from_str(
+            r#"{"userStatus":{"email":"ada@example.com","planStatus":{
+                "planInfo":{"planName":"Pro"},
+                "planStart":"2025-06-01T00:00:00Z","planEnd":"2025-07-01T00:00:00Z",
+                "availablePromptCredits":50000,"usedPromptCredits":"12500",
+                "availableFlexCredits":0}}}"#,
+        )
+        .unwrap();
+        let user = response.user_status.unwrap();
+        let plan = user.plan_status.unwrap();
+
+        let window = plan.prompt_window().unwrap();
+        assert_eq!(window.used_percent, 25.0);
+        assert_eq!(
+            window.resets_at,
+            Some(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(window.window_minutes, Some(30 * 24 * 60));
+        let description = window.reset_description.unwrap();
+        assert!(description.contains("125") && description.contains("500"));
+
+        // Plans without flex credits get no second window
+        assert!(plan.flex_window().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_logout_ignores_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = WindsurfProvider {
+            keys: SettingsKeys::in_dir(dir.path()),
+            ..WindsurfProvider::new()
+        };
+        provider.logout().await.unwrap();
+        assert!(!provider.is_available().await);
+        assert!(matches!(
+            provider.load_api_key().await,
+            Err(ProviderError::AuthRequired)
+        ));
+
+        // The logout outlasts a restart
+        let restarted = WindsurfProvider {
+            keys: SettingsKeys::in_dir(dir.path()),
+            ..WindsurfProvider::new()
+        };
+        assert!(!restarted.is_available().await);
+        restarted.login().await.unwrap();
+        assert!(!restarted.keys.is_logged_out("windsurf"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Flatpak sandbox awareness
+//!
+//! Inside Flatpak `$HOME` is still the user's home, but only the folders the
+//! app was granted are visible in it, and `XDG_CONFIG_HOME` points into
+//! `~/.var/app/<id>` instead of the host's config. CLIs installed on the host
+//! keep their credentials in host folders, so providers look for them through
+//! [`host_config_dir`], and missing files come with an [`access_hint`] on how
+//! to grant access. The keychain needs D-Bus access to the Secret Service
+//! (`--talk-name=org.freedesktop.secrets`).
+
+use std::path::{Path, PathBuf};
+
+/// Whether GPTBar runs inside a Flatpak sandbox
+pub fn is_flatpak() -> bool {
+    cfg!(target_os = "linux")
+        && (std::env::var_os("FLATPAK_ID").is_some() || Path::new("/.flatpak-info").exists())
+}
+
+/// The Flatpak application ID, when sandboxed
+pub fn app_id() -> Option<String> {
+    std::env::var("FLATPAK_ID").ok().filter(|_| is_flatpak())
+}
+
+/// The host's XDG config directory, where CLIs keep their settings
+///
+/// Flatpak passes the host's own `XDG_CONFIG_HOME` as `HOST_XDG_CONFIG_HOME`.
+pub fn host_config_dir() -> Option<PathBuf> {
+    let var = if is_flatpak() {
+        "HOST_XDG_CONFIG_HOME"
+    } else {
+        "XDG_CONFIG_HOME"
+    };
+    std::env::var(var)
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|h| PathBuf::from(h).join(".config"))
+        })
+}
+
+/// How to let the sandbox see a missing file, or `None` outside Flatpak
+pub fn access_hint(path: &Path) -> Option<String> {
+    app_id().map(|id| access_hint_for(&id, path))
+}
+
+fn access_hint_for(app_id: &str, path: &Path) -> String {
+    let dir = path.parent().unwrap_or(path);
+    format!(
+        "Not visible inside Flatpak; run `flatpak override --user --filesystem={}:ro {}`",
+        dir.display(),
+        app_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_hint_names_the_folder() {
+        let hint = access_hint_for(
+            "io.github.episuarez.GPTBar",
+            Path::new("/home/me/.claude/.credentials.json"),
+        );
+        assert_eq!(
+            hint,
+            "Not visible inside Flatpak; run `flatpak override --user \
+             --filesystem=/home/me/.claude:ro io.github.episuarez.GPTBar`"
+        );
+    }
+
+    #[test]
+    fn test_host_config_dir() {
+        // The test process is not sandboxed
+        if !is_flatpak() {
+            assert!(app_id().is_none());
+            assert!(host_config_dir().is_some());
+        }
+    }
+}
@@ -4,11 +4,14 @@
 //! - Sanitizing sensitive data for logs
 //! - Secure string handling with zeroization
 //! - DPAPI-based encryption on Windows
+//! - OS re-authentication before revealing secrets
 //! - Certificate pinning for HTTPS clients
 
+mod os_auth;
 mod sanitizer;
 mod secure_string;
 
+pub use os_auth::{OsAuth, OsAuthError};
 pub use sanitizer::Sanitizer;
 pub use secure_string::SecureString;
 
@@ -0,0 +1,248 @@
+//! Operating system re-authentication gate
+//!
+//! Asks the user to confirm their identity (Windows Hello, Touch ID / login
+//! password on macOS, their own password through polkit on Linux) before
+//! secrets are revealed or exported.
+
+use thiserror::Error;
+
+/// Errors that can occur during OS re-authentication
+#[derive(Debug, Error, PartialEq)]
+pub enum OsAuthError {
+    /// No re-authentication mechanism is available on this system
+    #[error("OS authentication unavailable: {0}")]
+    Unavailable(String),
+
+    /// The user cancelled or failed verification
+    #[error("OS authentication was denied")]
+    Denied,
+
+    /// The platform API failed unexpectedly
+    #[error("OS authentication failed: {0}")]
+    Failed(String),
+}
+
+/// polkit action the Linux gate asks for, defined in
+/// `polkit/com.episuarez.gptbar.policy`
+#[cfg(target_os = "linux")]
+const POLKIT_ACTION: &str = "com.episuarez.gptbar.reveal-secret";
+
+/// OS-level user verification
+///
+/// All methods block until the user answers the system prompt, so callers on
+/// an async runtime should use `spawn_blocking`.
+pub struct OsAuth;
+
+impl OsAuth {
+    /// Runs verification only when the gate is enabled
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gptbar_lib::security::OsAuth;
+    ///
+    /// // A disabled gate never prompts
+    /// assert!(OsAuth::require(false, "Reveal API key").is_ok());
+    /// ```
+    pub fn require(enabled: bool, reason: &str) -> Result<(), OsAuthError> {
+        if enabled {
+            Self::verify(reason)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Prompts the user to confirm their identity with Windows Hello
+    #[cfg(target_os = "windows")]
+    pub fn verify(reason: &str) -> Result<(), OsAuthError> {
+        use windows::core::HSTRING;
+        use windows::Security::Credentials::UI::{
+            UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+        };
+
+        let availability = UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|op| op.get())
+            .map_err(|e| OsAuthError::Failed(e.to_string()))?;
+
+        if availability != UserConsentVerifierAvailability::Available {
+            return Err(OsAuthError::Unavailable(
+                "Windows Hello is not configured".into(),
+            ));
+        }
+
+        let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|op| op.get())
+            .map_err(|e| OsAuthError::Failed(e.to_string()))?;
+
+        if result == UserConsentVerificationResult::Verified {
+            Ok(())
+        } else {
+            Err(OsAuthError::Denied)
+        }
+    }
+
+    /// Prompts the user to confirm their identity with Touch ID or their
+    /// login password through LocalAuthentication
+    #[cfg(target_os = "macos")]
+    pub fn verify(reason: &str) -> Result<(), OsAuthError> {
+        use std::sync::mpsc;
+
+        use block2::RcBlock;
+        use objc2::rc::Retained;
+        use objc2::runtime::{AnyObject, Bool};
+        use objc2::{class, msg_send};
+        use objc2_foundation::{NSError, NSString};
+
+        #[link(name = "LocalAuthentication", kind = "framework")]
+        extern "C" {}
+
+        /// `LAPolicyDeviceOwnerAuthentication`: biometrics, a watch or the
+        /// login password
+        const DEVICE_OWNER_AUTHENTICATION: isize = 2;
+        /// `LAErrorAuthenticationFailed`, `LAErrorUserCancel` and
+        /// `LAErrorSystemCancel`
+        const DENIED_CODES: [isize; 3] = [-1, -2, -4];
+
+        let context: Retained<AnyObject> = unsafe { msg_send![class!(LAContext), new] };
+
+        let available: Result<(), Retained<NSError>> = unsafe {
+            msg_send![&context, canEvaluatePolicy: DEVICE_OWNER_AUTHENTICATION, error: _]
+        };
+        if let Err(e) = available {
+            return Err(OsAuthError::Unavailable(
+                e.localizedDescription().to_string(),
+            ));
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+            let result = match unsafe { error.as_ref() } {
+                _ if success.as_bool() => Ok(()),
+                Some(e) if DENIED_CODES.contains(&e.code()) => Err(OsAuthError::Denied),
+                Some(e) => Err(OsAuthError::Failed(e.localizedDescription().to_string())),
+                None => Err(OsAuthError::Denied),
+            };
+            let _ = tx.send(result);
+        });
+
+        let reason = NSString::from_str(reason);
+        let _: () = unsafe {
+            msg_send![
+                &context,
+                evaluatePolicy: DEVICE_OWNER_AUTHENTICATION,
+                localizedReason: &*reason,
+                reply: &*reply
+            ]
+        };
+
+        // The reply arrives on a private queue once the prompt is answered
+        rx.recv().unwrap_or_else(|_| {
+            Err(OsAuthError::Failed(
+                "LocalAuthentication gave no reply".into(),
+            ))
+        })
+    }
+
+    /// Prompts the user for their own password through polkit
+    ///
+    /// Asks for `POLKIT_ACTION`, which the packaged policy file grants
+    /// with `auth_self`; without that file installed the gate is
+    /// unavailable.
+    #[cfg(target_os = "linux")]
+    pub fn verify(_reason: &str) -> Result<(), OsAuthError> {
+        use std::collections::HashMap;
+
+        use zbus::blocking::Connection;
+        use zbus::zvariant::Value;
+
+        /// `AllowUserInteraction`, so polkit shows its agent dialog
+        const ALLOW_USER_INTERACTION: u32 = 1;
+
+        let connection = Connection::system()
+            .map_err(|e| OsAuthError::Unavailable(format!("system bus: {}", e)))?;
+
+        let subject = (
+            "unix-process",
+            HashMap::from([
+                ("pid", Value::from(std::process::id())),
+                ("start-time", Value::from(process_start_time()?)),
+            ]),
+        );
+        let details: HashMap<&str, &str> = HashMap::new();
+
+        let reply = connection
+            .call_method(
+                Some("org.freedesktop.PolicyKit1"),
+                "/org/freedesktop/PolicyKit1/Authority",
+                Some("org.freedesktop.PolicyKit1.Authority"),
+                "CheckAuthorization",
+                &(subject, POLKIT_ACTION, details, ALLOW_USER_INTERACTION, ""),
+            )
+            .map_err(|e| OsAuthError::Unavailable(format!("polkit: {}", e)))?;
+
+        let ((authorized, _challenge, _details),): ((bool, bool, HashMap<String, String>),) = reply
+            .body()
+            .deserialize()
+            .map_err(|e| OsAuthError::Failed(format!("polkit reply: {}", e)))?;
+
+        if authorized {
+            Ok(())
+        } else {
+            Err(OsAuthError::Denied)
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    pub fn verify(_reason: &str) -> Result<(), OsAuthError> {
+        Err(OsAuthError::Unavailable(
+            "No OS authentication on this platform".into(),
+        ))
+    }
+}
+
+/// When this process started, in clock ticks since boot, which polkit uses
+/// to tell it apart from a later process with the same PID
+#[cfg(target_os = "linux")]
+fn process_start_time() -> Result<u64, OsAuthError> {
+    let stat = std::fs::read_to_string("/proc/self/stat")
+        .map_err(|e| OsAuthError::Failed(format!("/proc/self/stat: {}", e)))?;
+    parse_start_time(&stat)
+        .ok_or_else(|| OsAuthError::Failed("Unexpected /proc/self/stat format".into()))
+}
+
+/// The `starttime` field (the 22nd) of a `/proc/<pid>/stat` line
+///
+/// The command name in the second field may hold spaces and parentheses, so
+/// fields are counted from its closing parenthesis.
+#[cfg(any(target_os = "linux", test))]
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_require_disabled_skips_prompt() {
+        assert_eq!(OsAuth::require(false, "test"), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        let stat = "4242 (gpt bar) S 1 4242 4242 0 -1 4194560 1200 0 0 0 15 3 0 0 20 0 \
+                    9 0 873421 102400000 2048 18446744073709551615";
+        assert_eq!(parse_start_time(stat), Some(873421));
+        assert_eq!(parse_start_time("4242 (gptbar) S 1"), None);
+        assert_eq!(parse_start_time("garbage"), None);
+    }
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(OsAuthError::Denied.to_string(), "OS authentication was denied");
+        assert!(OsAuthError::Unavailable("x".into())
+            .to_string()
+            .contains("unavailable"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Startup phase timing
+//!
+//! A cold start on a slow disk is dominated by a few phases, such as opening
+//! the history database or creating the tray icon. A [`StartupTimer`] logs
+//! how long each one took, so a slow start can be narrowed down from the log
+//! in a diagnostic bundle.
+
+use std::time::{Duration, Instant};
+
+/// Measures the consecutive phases of one startup step
+pub struct StartupTimer {
+    name: &'static str,
+    started: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StartupTimer {
+    /// Starts timing the step called `name`
+    pub fn new(name: &'static str) -> Self {
+        let now = Instant::now();
+        Self {
+            name,
+            started: now,
+            last: now,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Ends the phase running since the previous one
+    pub fn phase(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        tracing::debug!("{}: {} took {:?}", self.name, phase, now - self.last);
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    /// Phases ended so far, with how long each took
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    /// One line with the total and each phase, in milliseconds
+    pub fn summary(&self) -> String {
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(phase, took)| format!("{} {} ms", phase, took.as_millis()))
+            .collect();
+        format!(
+            "{} took {} ms ({})",
+            self.name,
+            self.started.elapsed().as_millis(),
+            phases.join(", ")
+        )
+    }
+
+    /// Logs the summary
+    pub fn finish(self) {
+        tracing::info!("{}", self.summary());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_in_order() {
+        let mut timer = StartupTimer::new("App state");
+        timer.phase("config");
+        std::thread::sleep(Duration::from_millis(5));
+        timer.phase("history");
+
+        let names: Vec<&str> = timer.phases().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["config", "history"]);
+        assert!(timer.phases()[1].1 >= Duration::from_millis(5));
+
+        let summary = timer.summary();
+        assert!(summary.starts_with("App state took "));
+        assert!(summary.contains("(config 0 ms, history "));
+    }
+}
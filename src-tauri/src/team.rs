@@ -0,0 +1,339 @@
+//! Team usage sync
+//!
+//! Off by default. With `team_sync` on, the
+//! [`TeamSyncAgent`](crate::agents::TeamSyncAgent) publishes this install's
+//! window usage to a shared, self-hosted endpoint, and [`TeamClient::fetch`]
+//! reads back what every member published, so a team sharing a plan can see
+//! the combined pressure on its limits.
+//!
+//! The endpoint has two routes, both called with the team token as a bearer
+//! token:
+//!
+//! - `PUT <url>/members/<id>` stores one [`MemberUsage`]
+//! - `GET <url>/members` returns every stored [`MemberUsage`]
+//!
+//! Only window percentages, lengths and reset times are shared; never costs,
+//! account names or keys. Members without a `member_name` are listed as
+//! anonymous, under a random ID.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::SecureStore;
+use crate::providers::{UsageSnapshot, WindowSlot};
+
+/// SecureStore key for the team token
+pub const TEAM_TOKEN_KEY: &str = "team-sync-token";
+
+/// Where and as whom usage is shared
+///
+/// The team token is kept in the `SecureStore`, not here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TeamSyncSettings {
+    /// Whether usage is published and the team view is available
+    pub enabled: bool,
+    /// Base URL of the sync endpoint
+    pub url: String,
+    /// Name shown to teammates; anonymous when unset
+    pub member_name: Option<String>,
+    /// Random ID usage is published under, generated on first start
+    pub member_id: Option<String>,
+    /// Provider IDs shared; all refreshed providers when empty
+    pub providers: Vec<String>,
+}
+
+impl TeamSyncSettings {
+    /// Generates the member ID if there is none yet, returning whether it did
+    pub fn ensure_member_id(&mut self) -> bool {
+        if self.member_id.is_some() {
+            return false;
+        }
+        let id: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        self.member_id = Some(id.to_lowercase());
+        true
+    }
+
+    /// Whether a provider's usage is shared
+    pub fn shares(&self, provider_id: &str) -> bool {
+        self.providers.is_empty() || self.providers.iter().any(|p| p == provider_id)
+    }
+}
+
+/// One window's usage as shared with the team
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedWindow {
+    pub used_percent: f64,
+    pub window_minutes: Option<i64>,
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+/// What one member publishes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberUsage {
+    pub id: String,
+    /// Name, or `None` for anonymous members
+    pub name: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    /// Window usage by provider ID
+    pub providers: BTreeMap<String, HashMap<WindowSlot, SharedWindow>>,
+}
+
+impl MemberUsage {
+    /// Takes the shared providers' windows from `snapshots`
+    pub fn new(settings: &TeamSyncSettings, snapshots: &HashMap<String, UsageSnapshot>) -> Self {
+        let providers = snapshots
+            .iter()
+            .filter(|(id, _)| settings.shares(id))
+            .map(|(id, snapshot)| {
+                let windows = snapshot
+                    .windows()
+                    .map(|(slot, window)| {
+                        (
+                            slot,
+                            SharedWindow {
+                                used_percent: window.used_percent,
+                                window_minutes: window.window_minutes,
+                                resets_at: window.resets_at,
+                            },
+                        )
+                    })
+                    .collect();
+                (id.clone(), windows)
+            })
+            .collect();
+        Self {
+            id: settings.member_id.clone().unwrap_or_default(),
+            name: settings.member_name.clone().filter(|n| !n.is_empty()),
+            updated_at: Utc::now(),
+            providers,
+        }
+    }
+}
+
+/// Usage of one provider window across the team
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CombinedWindow {
+    pub provider_id: String,
+    pub window: WindowSlot,
+    /// Members with usage in this window
+    pub members: usize,
+    pub average_percent: f64,
+    pub max_percent: f64,
+    /// Sum of the members' usage, for limits the team shares as one pool
+    pub total_percent: f64,
+}
+
+/// Every member's usage, and the combined usage of each window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TeamView {
+    pub members: Vec<MemberUsage>,
+    pub combined: Vec<CombinedWindow>,
+}
+
+impl TeamView {
+    /// Combines the members' windows
+    ///
+    /// Windows that already reset are left out of the combined usage, so a
+    /// member who stopped publishing doesn't keep adding to it.
+    pub fn new(mut members: Vec<MemberUsage>, now: DateTime<Utc>) -> Self {
+        members.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+
+        let mut windows: BTreeMap<(String, usize), Vec<f64>> = BTreeMap::new();
+        for member in &members {
+            for (provider_id, shared) in &member.providers {
+                for (slot, window) in shared {
+                    if window.resets_at.is_some_and(|at| at <= now) {
+                        continue;
+                    }
+                    let order = WindowSlot::ALL.iter().position(|s| s == slot).unwrap_or(0);
+                    windows
+                        .entry((provider_id.clone(), order))
+                        .or_default()
+                        .push(window.used_percent);
+                }
+            }
+        }
+
+        let combined = windows
+            .into_iter()
+            .map(|((provider_id, order), percents)| {
+                let total: f64 = percents.iter().sum();
+                CombinedWindow {
+                    provider_id,
+                    window: WindowSlot::ALL[order],
+                    members: percents.len(),
+                    average_percent: total / percents.len() as f64,
+                    max_percent: percents.iter().copied().fold(0.0, f64::max),
+                    total_percent: total,
+                }
+            })
+            .collect();
+
+        Self { members, combined }
+    }
+}
+
+/// Talks to the sync endpoint
+pub struct TeamClient {
+    client: Client,
+    url: String,
+    token: String,
+}
+
+impl TeamClient {
+    /// Creates a client for `url`, authenticating with `token`
+    pub fn new(url: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+        }
+    }
+
+    /// Creates a client from the settings and the stored team token
+    pub fn from_settings(settings: &TeamSyncSettings, store: &SecureStore) -> Result<Self, String> {
+        if settings.url.is_empty() {
+            return Err("No team sync URL configured".to_string());
+        }
+        let token = store
+            .get_token(TEAM_TOKEN_KEY)
+            .map_err(|e| e.to_string())?
+            .ok_or("No team token saved")?;
+        Ok(Self::new(settings.url.clone(), token))
+    }
+
+    fn members_url(&self) -> String {
+        format!("{}/members", self.url)
+    }
+
+    /// Stores this member's usage
+    pub async fn publish(&self, usage: &MemberUsage) -> Result<(), String> {
+        let response = self
+            .client
+            .put(format!("{}/{}", self.members_url(), usage.id))
+            .bearer_auth(&self.token)
+            .json(usage)
+            .send()
+            .await
+            .map_err(|e| format!("Team sync failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Team sync failed: HTTP {}", response.status()));
+        }
+        Ok(())
+    }
+
+    /// Fetches every member's usage
+    pub async fn fetch(&self) -> Result<Vec<MemberUsage>, String> {
+        let response = self
+            .client
+            .get(self.members_url())
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| format!("Team sync failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Team sync failed: HTTP {}", response.status()));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid team usage: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{IdentitySnapshot, RateWindow};
+
+    fn member(name: Option<&str>, percent: f64, resets_at: DateTime<Utc>) -> MemberUsage {
+        let window = SharedWindow {
+            used_percent: percent,
+            window_minutes: Some(7 * 24 * 60),
+            resets_at: Some(resets_at),
+        };
+        MemberUsage {
+            id: name.unwrap_or("x7k2").to_string(),
+            name: name.map(str::to_string),
+            updated_at: Utc::now(),
+            providers: BTreeMap::from([(
+                "claude".to_string(),
+                HashMap::from([(WindowSlot::Secondary, window)]),
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_member_usage_shares_only_windows() {
+        let mut settings = TeamSyncSettings {
+            providers: vec!["claude".to_string()],
+            ..Default::default()
+        };
+        assert!(settings.ensure_member_id());
+        assert!(!settings.ensure_member_id());
+
+        let snapshots = HashMap::from([
+            (
+                "claude".to_string(),
+                UsageSnapshot::new()
+                    .with_primary(RateWindow::new(40.0).with_cost_usd(12.5))
+                    .with_identity(IdentitySnapshot::new().with_email("me@example.com")),
+            ),
+            (
+                "openai".to_string(),
+                UsageSnapshot::new().with_primary(RateWindow::new(10.0)),
+            ),
+        ]);
+
+        let usage = MemberUsage::new(&settings, &snapshots);
+        assert_eq!(usage.id.len(), 16);
+        assert_eq!(usage.name, None);
+        assert_eq!(usage.providers.keys().collect::<Vec<_>>(), vec!["claude"]);
+        assert_eq!(
+            usage.providers["claude"][&WindowSlot::Primary].used_percent,
+            40.0
+        );
+        let json = serde_json::to_string(&usage).unwrap();
+        assert!(!json.contains("me@example.com"));
+        assert!(!json.contains("12.5"));
+    }
+
+    #[test]
+    fn test_team_view_combines_current_windows() {
+        let now = Utc::now();
+        let later = now + chrono::Duration::days(3);
+        let view = TeamView::new(
+            vec![
+                member(Some("bea"), 60.0, later),
+                member(None, 20.0, later),
+                // Reset since it last published
+                member(Some("al"), 90.0, now - chrono::Duration::hours(1)),
+            ],
+            now,
+        );
+
+        assert_eq!(view.members.len(), 3);
+        assert_eq!(view.members[0].name, None);
+        assert_eq!(
+            view.combined,
+            vec![CombinedWindow {
+                provider_id: "claude".to_string(),
+                window: WindowSlot::Secondary,
+                members: 2,
+                average_percent: 40.0,
+                max_percent: 60.0,
+                total_percent: 80.0,
+            }]
+        );
+    }
+}
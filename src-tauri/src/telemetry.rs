@@ -0,0 +1,246 @@
+//! Opt-in anonymous telemetry
+//!
+//! Off by default. Once turned on in the settings, counters are kept in
+//! memory and the [`TelemetryAgent`](crate::agents::TelemetryAgent) sends
+//! them as one [`TelemetryBatch`] a day:
+//!
+//! - how often features were used, and which optional ones are turned on
+//! - how often each provider was fetched
+//! - failed fetches by error category, such as `network` or `auth_failed`
+//!
+//! Batches never hold usage numbers, costs, messages, account names, keys
+//! or any ID of the install. Counters recorded while telemetry is off are
+//! dropped.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::providers::ProviderErrorCode;
+
+/// Where batches are sent, set when building releases
+pub const TELEMETRY_URL: Option<&str> = option_env!("GPTBAR_TELEMETRY_URL");
+
+/// Version of the batch format
+const SCHEMA_VERSION: u32 = 1;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Counters since the last sent batch
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Counters {
+    /// Uses of each feature
+    pub features: BTreeMap<String, u64>,
+    /// Fetches of each provider
+    pub providers: BTreeMap<String, u64>,
+    /// Failed fetches by error category
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl Counters {
+    /// Whether nothing was counted
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty() && self.providers.is_empty() && self.errors.is_empty()
+    }
+
+    /// Adds the counts of `other`
+    pub fn merge(&mut self, other: Counters) {
+        for (mine, theirs) in [
+            (&mut self.features, other.features),
+            (&mut self.providers, other.providers),
+            (&mut self.errors, other.errors),
+        ] {
+            for (key, count) in theirs {
+                *mine.entry(key).or_default() += count;
+            }
+        }
+    }
+}
+
+/// What is sent: the counters plus coarse facts about the build
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TelemetryBatch {
+    pub schema: u32,
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    /// Day the batch was made, without a time
+    pub date: NaiveDate,
+    /// Optional features turned on in the settings
+    pub settings: Vec<&'static str>,
+    #[serde(flatten)]
+    pub counters: Counters,
+}
+
+impl TelemetryBatch {
+    /// Wraps `counters` with facts about the build and `config`
+    pub fn new(counters: Counters, config: &AppConfig) -> Self {
+        Self {
+            schema: SCHEMA_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            date: Utc::now().date_naive(),
+            settings: enabled_settings(config),
+            counters,
+        }
+    }
+}
+
+/// Names of the optional features `config` turns on
+pub fn enabled_settings(config: &AppConfig) -> Vec<&'static str> {
+    [
+        ("start_on_login", config.start_on_login),
+        ("require_os_auth", config.require_os_auth),
+        ("store_discovered_tokens", config.store_discovered_tokens),
+        ("ipc", config.ipc_enabled),
+        ("mqtt", config.mqtt.enabled),
+        ("api_server", config.api_server.enabled),
+        ("claude_local_usage", config.claude_local_usage),
+        (
+            "currency",
+            !config.currency.code.eq_ignore_ascii_case("USD"),
+        ),
+        ("check_for_updates", config.check_for_updates),
+        ("usage_freeze", config.usage_freeze.is_enabled()),
+        ("hooks", config.hooks.enabled),
+        ("team_sync", config.team_sync.enabled),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect()
+}
+
+fn counters() -> &'static Mutex<Counters> {
+    static COUNTERS: OnceLock<Mutex<Counters>> = OnceLock::new();
+    COUNTERS.get_or_init(|| Mutex::new(Counters::default()))
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Counters> {
+    counters().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Turns counting on or off; turning it off drops what was counted
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        *lock() = Counters::default();
+    }
+}
+
+/// Whether counting is on
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn count(select: impl FnOnce(&mut Counters) -> &mut BTreeMap<String, u64>, key: &str) {
+    if is_enabled() {
+        *select(&mut lock()).entry(key.to_string()).or_default() += 1;
+    }
+}
+
+/// Counts a use of a feature
+pub fn record_feature(feature: &'static str) {
+    count(|c| &mut c.features, feature);
+}
+
+/// Counts a fetch of a provider, and its error category if it failed
+pub fn record_fetch(provider_id: &str, error: Option<ProviderErrorCode>) {
+    count(|c| &mut c.providers, provider_id);
+    if let Some(code) = error {
+        count(|c| &mut c.errors, code.as_str());
+    }
+}
+
+/// The counters so far, without resetting them
+pub fn peek() -> Counters {
+    lock().clone()
+}
+
+/// Takes the counters so far, leaving them empty
+pub fn take() -> Counters {
+    std::mem::take(&mut *lock())
+}
+
+/// Puts back counters that could not be sent
+pub fn restore(counters: Counters) {
+    if is_enabled() {
+        lock().merge(counters);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut counters = Counters::default();
+        counters.features.insert("export".to_string(), 1);
+        let mut other = Counters::default();
+        other.features.insert("export".to_string(), 2);
+        other.errors.insert("network".to_string(), 1);
+
+        counters.merge(other);
+        assert_eq!(counters.features["export"], 3);
+        assert_eq!(counters.errors["network"], 1);
+        assert!(!counters.is_empty());
+        assert!(Counters::default().is_empty());
+    }
+
+    #[test]
+    fn test_counting_only_while_enabled() {
+        set_enabled(true);
+        record_feature("test_feature");
+        record_fetch("test_provider", Some(ProviderErrorCode::Network));
+        let counted = peek();
+        assert_eq!(counted.features["test_feature"], 1);
+        assert_eq!(counted.providers["test_provider"], 1);
+        assert!(counted.errors["network"] >= 1);
+
+        set_enabled(false);
+        assert!(peek().is_empty());
+        record_feature("test_feature");
+        assert!(peek().is_empty());
+    }
+
+    #[test]
+    fn test_batch_holds_no_usage() {
+        let mut counters = Counters::default();
+        counters.providers.insert("claude".to_string(), 4);
+        let mut config = AppConfig::default();
+        config.mqtt.enabled = true;
+        config.mqtt.host = "broker.home.lan".to_string();
+
+        let json = serde_json::to_value(TelemetryBatch::new(counters, &config)).unwrap();
+        assert_eq!(json["schema"], 1);
+        assert_eq!(json["providers"]["claude"], 4);
+        assert_eq!(json["settings"], serde_json::json!(["ipc", "mqtt"]));
+        assert!(!json.to_string().contains("broker.home.lan"));
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "arch",
+                "date",
+                "errors",
+                "features",
+                "os",
+                "providers",
+                "schema",
+                "settings",
+                "version"
+            ]
+        );
+    }
+}
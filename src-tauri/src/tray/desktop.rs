@@ -0,0 +1,480 @@
+//! Tray icon and its Tauri wiring
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tauri::{
+    image::Image,
+    menu::{Menu, MenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, Manager, Theme,
+};
+
+use super::{
+    native_icon_size, parse_hex_color, title_text, usage_bucket, AlertBlink, Bitmap, IconRenderer,
+    IconTheme, TrayIconStyle, TrayTitleSource, ALERT_BLINK_INTERVAL,
+};
+use crate::config::{AppConfig, WindowPosition};
+use crate::i18n;
+use crate::memory::MemoryUsage;
+use crate::providers::{ProviderMetadata, UsageSnapshot};
+use crate::window::{self, PopupState};
+use crate::AppState;
+
+/// ID of the application's tray icon
+pub const TRAY_ID: &str = "main";
+
+// Popup window dimensions before it is resized
+const DEFAULT_WIDTH: i32 = 300;
+const DEFAULT_HEIGHT: i32 = 520;
+const MARGIN: i32 = 10;
+
+/// Clicks closer together than this are treated as one
+const CLICK_DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// Loads the base tray icon
+fn load_icon() -> Image<'static> {
+    Image::from_path("icons/icon.png")
+        .or_else(|_| Image::from_path("icons/32x32.png"))
+        .unwrap_or_else(|_| {
+            Image::from_bytes(include_bytes!("../../icons/32x32.png"))
+                .expect("Failed to load embedded icon")
+        })
+}
+
+// Tray menu item IDs
+const MENU_DASHBOARD: &str = "dashboard";
+const MENU_QUIT: &str = "quit";
+
+/// Creates the system tray icon and wires the click and menu handlers
+pub fn create(app: &App) -> tauri::Result<()> {
+    let dashboard = MenuItem::with_id(
+        app,
+        MENU_DASHBOARD,
+        i18n::t("tray.menu.dashboard"),
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(
+        app,
+        MENU_QUIT,
+        i18n::t("tray.menu.quit"),
+        true,
+        None::<&str>,
+    )?;
+    let menu = Menu::with_items(app, &[&dashboard, &quit])?;
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(load_icon())
+        .tooltip(i18n::t("tray.tooltip"))
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            MENU_DASHBOARD => {
+                if let Err(e) = window::open_dashboard(app) {
+                    tracing::error!("Failed to open dashboard: {}", e);
+                }
+            }
+            MENU_QUIT => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                let app = tray.app_handle();
+                let tray_state = app.state::<Arc<TrayState>>();
+                if !tray_state.accept_click() {
+                    return;
+                }
+                tray_state.acknowledge_alert(app);
+
+                // Get or create the popup window
+                if let Some(window) = app.get_webview_window("main") {
+                    if window.is_visible().unwrap_or(false) {
+                        let _ = window.hide();
+                    } else {
+                        prewarm(app);
+
+                        // A pinned popup reopens where it was left,
+                        // otherwise it is positioned near the tray icon
+                        let pinned = app.state::<Arc<PopupState>>().pinned_position();
+                        if let Some(position) = pinned {
+                            window::place(&window, position);
+                        } else if let Some(rect) = tray.rect().ok().flatten() {
+                            let (tray_x, tray_y) = match rect.position {
+                                tauri::Position::Physical(p) => (p.x, p.y),
+                                tauri::Position::Logical(l) => (l.x as i32, l.y as i32),
+                            };
+                            let (tray_w, _tray_h) = match rect.size {
+                                tauri::Size::Physical(s) => (s.width as i32, s.height as i32),
+                                tauri::Size::Logical(s) => (s.width as i32, s.height as i32),
+                            };
+                            let (width, height) = window
+                                .outer_size()
+                                .map(|s| (s.width as i32, s.height as i32))
+                                .unwrap_or((DEFAULT_WIDTH, DEFAULT_HEIGHT));
+
+                            // Position: horizontally centered on tray icon, above
+                            // the taskbar, kept on the tray's monitor
+                            window::place(
+                                &window,
+                                WindowPosition {
+                                    x: tray_x + (tray_w / 2) - (width / 2),
+                                    y: tray_y - height - MARGIN,
+                                },
+                            );
+                        }
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
+
+/// Refreshes providers older than one refresh interval in the background
+///
+/// New snapshots reach the popup as `usage-updated` events while it opens.
+fn prewarm(app: &AppHandle) {
+    let refresh = app.state::<Arc<AppState>>().refresh.clone();
+    tauri::async_runtime::spawn(async move {
+        let refreshed = refresh
+            .refresh_stale(AppConfig::load().refresh_period())
+            .await;
+        if !refreshed.is_empty() {
+            tracing::debug!("Pre-warmed {:?} for the popup", refreshed);
+        }
+    });
+}
+
+/// Keeps the tray indicators in sync with the latest snapshots
+pub struct TrayState {
+    snapshots: Mutex<HashMap<String, UsageSnapshot>>,
+    theme: Mutex<IconTheme>,
+    scale_factor: Mutex<f64>,
+    icons: Mutex<IconRenderer>,
+    last_click: Mutex<Option<Instant>>,
+    brand_colors: Mutex<HashMap<String, [u8; 3]>>,
+    /// Last rendered usage icon and whether it is a template image
+    icon: Mutex<Option<(Bitmap, bool)>>,
+    alert: Mutex<AlertBlink>,
+    /// How many things need attention
+    attention: Mutex<usize>,
+}
+
+impl TrayState {
+    /// Creates the tray state with the base icon
+    pub fn new() -> Self {
+        let icon = load_icon();
+        Self {
+            snapshots: Mutex::new(HashMap::new()),
+            theme: Mutex::new(IconTheme::default()),
+            scale_factor: Mutex::new(1.0),
+            icons: Mutex::new(IconRenderer::new(Bitmap::new(
+                icon.rgba().to_vec(),
+                icon.width(),
+                icon.height(),
+            ))),
+            last_click: Mutex::new(None),
+            brand_colors: Mutex::new(HashMap::new()),
+            icon: Mutex::new(None),
+            alert: Mutex::new(AlertBlink::default()),
+            attention: Mutex::new(0),
+        }
+    }
+
+    /// Rendered icon variants held in the cache
+    pub fn icon_memory(&self) -> MemoryUsage {
+        self.icons
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .memory()
+    }
+
+    /// Stores provider brand colors, used for the arc of a single-provider
+    /// tray title
+    pub fn set_brand_colors(&self, providers: &[ProviderMetadata]) {
+        let colors = providers
+            .iter()
+            .filter_map(|p| Some((p.id.clone(), parse_hex_color(&p.brand_color)?)))
+            .collect();
+        *self.brand_colors.lock().unwrap_or_else(|e| e.into_inner()) = colors;
+    }
+
+    /// Returns false for a click that follows the previous one too closely,
+    /// so a double click doesn't show and immediately hide the popup
+    pub fn accept_click(&self) -> bool {
+        let mut last_click = self.last_click.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if last_click.is_some_and(|last| now.duration_since(last) < CLICK_DEBOUNCE) {
+            return false;
+        }
+        *last_click = Some(now);
+        true
+    }
+
+    /// Records a new snapshot and re-renders the indicators
+    pub fn update(&self, app: &AppHandle, provider_id: &str, snapshot: &UsageSnapshot) {
+        self.snapshots
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(provider_id.to_string(), snapshot.clone());
+        self.refresh(app);
+    }
+
+    /// Records how many things need attention, re-rendering the indicators
+    /// when it changed
+    pub fn set_attention(&self, app: &AppHandle, count: usize) {
+        let previous = std::mem::replace(
+            &mut *self.attention.lock().unwrap_or_else(|e| e.into_inner()),
+            count,
+        );
+        if previous != count {
+            self.refresh(app);
+        }
+    }
+
+    /// Selects the icon variant for the current system theme
+    pub fn set_theme(&self, app: &AppHandle, window_theme: Option<Theme>) {
+        let theme = detect_theme(window_theme);
+        tracing::debug!("Tray icon theme: {:?}", theme);
+        *self.theme.lock().unwrap_or_else(|e| e.into_inner()) = theme;
+        self.refresh(app);
+    }
+
+    /// Re-renders the arc icon at the resolution of the current display
+    pub fn set_scale_factor(&self, app: &AppHandle, scale_factor: f64) {
+        *self.scale_factor.lock().unwrap_or_else(|e| e.into_inner()) = scale_factor;
+        self.refresh(app);
+    }
+
+    /// Re-renders the indicators from the cached snapshots and current config
+    pub fn refresh(&self, app: &AppHandle) {
+        let config = AppConfig::load();
+        let snapshots = self.snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        let aggregate = config
+            .usage_aggregate()
+            .snapshot(&snapshots, &config.active_providers());
+
+        apply_title(
+            app,
+            title_text(
+                &config.tray_title,
+                &snapshots,
+                aggregate.as_ref(),
+                &config.headline_windows(),
+            ),
+        );
+        let attention = *self.attention.lock().unwrap_or_else(|e| e.into_inner());
+        apply_tooltip(app, aggregate.as_ref(), attention);
+
+        let critical = config.any_critical(&snapshots);
+        let aggregate = aggregate.map(|s| s.max_usage());
+        let theme = *self.theme.lock().unwrap_or_else(|e| e.into_inner());
+        let mut icons = self.icons.lock().unwrap_or_else(|e| e.into_inner());
+
+        let icon = match (config.tray_icon_style, aggregate) {
+            (TrayIconStyle::Arc, Some(percent)) => {
+                let size = self.icon_size();
+                let track = match &config.tray_title {
+                    TrayTitleSource::Provider(id) => self
+                        .brand_colors
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get(id)
+                        .copied(),
+                    _ => None,
+                };
+                (icons.render_arc(theme, size, percent, track).clone(), false)
+            }
+            _ => {
+                // Only Windows gets a badge; macOS shows the title instead
+                let bucket = if cfg!(target_os = "windows") {
+                    usage_bucket(aggregate.unwrap_or(0.0))
+                } else {
+                    0
+                };
+                // The plain logo is a template image so macOS tints it itself
+                (icons.render(theme, bucket).clone(), bucket == 0)
+            }
+        };
+        drop(icons);
+        drop(snapshots);
+        // The dot is red, so the icon is no longer a template image
+        let icon = match icon {
+            (bitmap, _) if attention > 0 => (bitmap.with_attention_dot(), false),
+            icon => icon,
+        };
+        *self.icon.lock().unwrap_or_else(|e| e.into_inner()) = Some(icon);
+
+        let start_blinking = self
+            .alert
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .set_critical(critical);
+        self.show_frame(app);
+        if start_blinking {
+            spawn_blink_timer(app);
+        }
+    }
+
+    /// Stops the critical alert blink until usage drops and turns critical
+    /// again
+    pub fn acknowledge_alert(&self, app: &AppHandle) {
+        self.alert
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .acknowledge();
+        self.show_frame(app);
+    }
+
+    /// Shows the next blink frame; returns false once blinking has stopped
+    fn blink(&self, app: &AppHandle) -> bool {
+        let frame = self.alert.lock().unwrap_or_else(|e| e.into_inner()).tick();
+        self.show_frame(app);
+        frame.is_some()
+    }
+
+    /// Applies the alert glyph or the usage icon, whichever the blink shows
+    fn show_frame(&self, app: &AppHandle) {
+        let shows_alert = self
+            .alert
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .shows_alert();
+        if shows_alert {
+            let size = self.icon_size();
+            let mut icons = self.icons.lock().unwrap_or_else(|e| e.into_inner());
+            apply_icon(app, icons.render_alert(size), false);
+        } else if let Some((bitmap, template)) =
+            &*self.icon.lock().unwrap_or_else(|e| e.into_inner())
+        {
+            apply_icon(app, bitmap, *template);
+        }
+    }
+
+    fn icon_size(&self) -> u32 {
+        native_icon_size(*self.scale_factor.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Alternates the alert glyph with the usage icon until blinking stops
+fn spawn_blink_timer(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(ALERT_BLINK_INTERVAL);
+        // The first tick completes immediately, on the frame already shown
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !app.state::<Arc<TrayState>>().blink(&app) {
+                break;
+            }
+        }
+    });
+}
+
+impl Default for TrayState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn apply_tooltip(app: &AppHandle, aggregate: Option<&UsageSnapshot>, attention: usize) {
+    let mut tooltip = match aggregate {
+        Some(snapshot) => i18n::t_args(
+            "tray.tooltip.usage",
+            &[("percent", &format!("{:.0}", snapshot.max_usage()))],
+        ),
+        None => i18n::t("tray.tooltip"),
+    };
+    if attention > 0 {
+        tooltip.push('\n');
+        tooltip.push_str(&i18n::t_args(
+            "tray.tooltip.attention",
+            &[("count", &attention.to_string())],
+        ));
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn apply_title(app: &AppHandle, title: Option<String>) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        if let Err(e) = tray.set_title(title) {
+            tracing::warn!("Failed to set tray title: {}", e);
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn apply_title(_app: &AppHandle, _title: Option<String>) {
+    // Tray titles are only rendered in the macOS menu bar
+}
+
+fn apply_icon(app: &AppHandle, bitmap: &Bitmap, template: bool) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let image = Image::new_owned(bitmap.rgba.clone(), bitmap.width, bitmap.height);
+        if let Err(e) = tray.set_icon(Some(image)) {
+            tracing::warn!("Failed to set tray icon: {}", e);
+        }
+        let _ = tray.set_icon_as_template(template);
+    }
+}
+
+/// Picks the icon theme, preferring the taskbar color on Windows since it can
+/// differ from the app theme
+fn detect_theme(window_theme: Option<Theme>) -> IconTheme {
+    #[cfg(target_os = "windows")]
+    if let Some(theme) = taskbar_theme() {
+        return theme;
+    }
+
+    match window_theme {
+        Some(Theme::Light) => IconTheme::Light,
+        _ => IconTheme::Dark,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn taskbar_theme() -> Option<IconTheme> {
+    use windows::core::w;
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+
+    // SAFETY: value and size point to a valid, correctly sized DWORD buffer
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("SystemUsesLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut _),
+            Some(&mut size),
+        )
+    };
+
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+
+    Some(if value == 0 {
+        IconTheme::Dark
+    } else {
+        IconTheme::Light
+    })
+}
@@ -0,0 +1,721 @@
+//! Tray icon rendering
+//!
+//! Draws usage indicators into the tray icon bitmap: a badge on top of the
+//! static logo, or a circular usage arc drawn at the platform's native icon
+//! resolution. The arc's track can take a provider's brand color. Rendered
+//! variants are cached, so repeated updates that land in the same theme and
+//! bucket don't redraw. Each cache holds at most [`ICON_CACHE_CAPACITY`]
+//! variants and starts over when full.
+//!
+//! While usage is critical, [`AlertBlink`] alternates the icon with an alert
+//! glyph until the alert is acknowledged or usage drops. A red dot in the
+//! top-right corner shows that something needs attention.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::time::Duration;
+use tiny_skia::{FillRule, LineCap, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::agents::NotificationThresholds;
+use crate::memory::MemoryUsage;
+
+/// Variants each icon cache keeps before it is cleared
+///
+/// Arcs are cached per percent, display size and track color, so switching
+/// displays and providers would otherwise keep adding bitmaps.
+pub const ICON_CACHE_CAPACITY: usize = 256;
+
+/// Clears `cache` when it is full and lacks `key`, so inserting it stays
+/// within [`ICON_CACHE_CAPACITY`]
+fn make_room<K: Eq + std::hash::Hash>(cache: &mut HashMap<K, Bitmap>, key: &K) {
+    if cache.len() >= ICON_CACHE_CAPACITY && !cache.contains_key(key) {
+        cache.clear();
+    }
+}
+
+/// Straight (non-premultiplied) RGBA bitmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    /// Pixel data, 4 bytes per pixel, row-major
+    pub rgba: Vec<u8>,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl Bitmap {
+    /// Creates a bitmap from raw RGBA data
+    pub fn new(rgba: Vec<u8>, width: u32, height: u32) -> Self {
+        debug_assert_eq!(rgba.len(), (width * height * 4) as usize);
+        Self {
+            rgba,
+            width,
+            height,
+        }
+    }
+
+    /// Returns a copy with every pixel recolored, keeping the alpha channel
+    pub fn tinted(&self, rgb: [u8; 3]) -> Self {
+        let mut rgba = self.rgba.clone();
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel[..3].copy_from_slice(&rgb);
+        }
+        Self::new(rgba, self.width, self.height)
+    }
+
+    /// Returns a copy with a red dot in the top-right corner, shown while
+    /// something needs attention
+    pub fn with_attention_dot(&self) -> Self {
+        let mut bitmap = self.clone();
+        draw_attention_dot(&mut bitmap);
+        bitmap
+    }
+
+    fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        let i = ((y * self.width + x) * 4) as usize;
+        self.rgba[i..i + 4].copy_from_slice(&color);
+    }
+}
+
+/// Brightness of the surface the tray icon is drawn on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IconTheme {
+    /// Light menu bar or taskbar; the icon is drawn dark
+    Light,
+    /// Dark menu bar or taskbar; the icon is drawn light
+    #[default]
+    Dark,
+}
+
+impl IconTheme {
+    /// Glyph color that stays visible on this theme
+    fn glyph_color(self) -> [u8; 3] {
+        match self {
+            Self::Light => [0x1f, 0x1f, 0x1f],
+            Self::Dark => [0xff, 0xff, 0xff],
+        }
+    }
+}
+
+/// Parses a `#rrggbb` color, such as a provider's brand color
+pub fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// How the tray icon is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayIconStyle {
+    /// Static application logo (with a usage badge on Windows)
+    #[default]
+    Logo,
+    /// Circular arc showing the highest usage
+    Arc,
+}
+
+/// Tray icon edge length in pixels for the given display scale factor
+///
+/// Based on the logical size each platform draws tray icons at: 18pt in the
+/// macOS menu bar, 16px in the Windows notification area and 22px on Linux
+/// panels.
+pub fn native_icon_size(scale_factor: f64) -> u32 {
+    let logical = if cfg!(target_os = "macos") {
+        18.0
+    } else if cfg!(target_os = "windows") {
+        16.0
+    } else {
+        22.0
+    };
+    (logical * scale_factor.max(1.0)).round() as u32
+}
+
+/// Rounds a usage percentage down to its badge bucket (0, 25, 50, 75 or 100)
+///
+/// # Examples
+///
+/// ```
+/// use gptbar_lib::tray::usage_bucket;
+///
+/// assert_eq!(usage_bucket(12.0), 0);
+/// assert_eq!(usage_bucket(74.9), 50);
+/// assert_eq!(usage_bucket(100.0), 100);
+/// ```
+pub fn usage_bucket(percent: f64) -> u8 {
+    (percent.clamp(0.0, 100.0) / 25.0).floor() as u8 * 25
+}
+
+/// How long each frame of the critical alert blink is shown
+pub const ALERT_BLINK_INTERVAL: Duration = Duration::from_millis(600);
+
+/// Blink state of the tray icon while usage is critical
+///
+/// Blinking starts when usage turns critical and stops when the alert is
+/// acknowledged or usage drops. An acknowledged alert stays quiet until
+/// usage drops, so only the next crossing blinks again.
+#[derive(Debug, Default)]
+pub struct AlertBlink {
+    critical: bool,
+    acknowledged: bool,
+    /// Whether the current frame is the alert glyph
+    alert_frame: bool,
+    /// Whether a timer is calling [`AlertBlink::tick`]
+    running: bool,
+}
+
+impl AlertBlink {
+    /// Records whether usage is critical
+    ///
+    /// Returns true when a timer should be started to call
+    /// [`AlertBlink::tick`].
+    pub fn set_critical(&mut self, critical: bool) -> bool {
+        if !critical {
+            self.acknowledged = false;
+        }
+        self.critical = critical;
+        if self.is_active() && !self.running {
+            self.running = true;
+            self.alert_frame = true;
+            return true;
+        }
+        false
+    }
+
+    /// Stops blinking until usage drops below critical and crosses it again
+    pub fn acknowledge(&mut self) {
+        self.acknowledged = true;
+    }
+
+    /// Whether the icon should be blinking
+    pub fn is_active(&self) -> bool {
+        self.critical && !self.acknowledged
+    }
+
+    /// Whether the alert glyph is shown instead of the usual icon
+    pub fn shows_alert(&self) -> bool {
+        self.is_active() && self.alert_frame
+    }
+
+    /// Advances to the next frame, returning whether it shows the alert
+    /// glyph, or `None` once blinking has stopped and the timer should end
+    pub fn tick(&mut self) -> Option<bool> {
+        if !self.is_active() {
+            self.running = false;
+            self.alert_frame = false;
+            return None;
+        }
+        self.alert_frame = !self.alert_frame;
+        Some(self.alert_frame)
+    }
+}
+
+/// Theme, display size, percent and track color of a cached usage arc
+type ArcKey = (IconTheme, u32, u8, Option<[u8; 3]>);
+
+/// Renders themed tray icons, caching each variant
+pub struct IconRenderer {
+    base: Bitmap,
+    thresholds: NotificationThresholds,
+    cache: HashMap<(IconTheme, u8), Bitmap>,
+    arc_cache: HashMap<ArcKey, Bitmap>,
+    alert_cache: HashMap<u32, Bitmap>,
+}
+
+impl IconRenderer {
+    /// Creates a renderer for the given base icon
+    pub fn new(base: Bitmap) -> Self {
+        Self {
+            base,
+            thresholds: NotificationThresholds::default(),
+            cache: HashMap::new(),
+            arc_cache: HashMap::new(),
+            alert_cache: HashMap::new(),
+        }
+    }
+
+    /// Sets the thresholds at which the usage arc turns yellow and red
+    pub fn with_thresholds(mut self, thresholds: NotificationThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Returns a `size`×`size` icon with a usage arc for the given percentage
+    ///
+    /// The track behind the arc is drawn in `track`, or in the theme's glyph
+    /// color when `None`.
+    pub fn render_arc(
+        &mut self,
+        theme: IconTheme,
+        size: u32,
+        percent: f64,
+        track: Option<[u8; 3]>,
+    ) -> &Bitmap {
+        let percent = percent.clamp(0.0, 100.0).round() as u8;
+        let thresholds = &self.thresholds;
+        let key = (theme, size, percent, track);
+        make_room(&mut self.arc_cache, &key);
+        self.arc_cache.entry(key).or_insert_with(|| {
+            let track = track.unwrap_or(theme.glyph_color());
+            draw_arc(size, percent as f32, track, thresholds)
+        })
+    }
+
+    /// Returns a `size`×`size` alert glyph, shown while usage is critical
+    pub fn render_alert(&mut self, size: u32) -> &Bitmap {
+        make_room(&mut self.alert_cache, &size);
+        self.alert_cache
+            .entry(size)
+            .or_insert_with(|| draw_alert(size))
+    }
+
+    /// Returns the icon tinted for the theme, with a badge for the given
+    /// bucket (bucket 0 draws no badge)
+    pub fn render(&mut self, theme: IconTheme, bucket: u8) -> &Bitmap {
+        let base = &self.base;
+        make_room(&mut self.cache, &(theme, bucket));
+        self.cache.entry((theme, bucket)).or_insert_with(|| {
+            let mut bitmap = base.tinted(theme.glyph_color());
+            if bucket > 0 {
+                draw_badge(&mut bitmap, bucket);
+            }
+            bitmap
+        })
+    }
+
+    /// Cached variants and their pixel data
+    pub fn memory(&self) -> MemoryUsage {
+        let bitmaps = self
+            .cache
+            .values()
+            .chain(self.arc_cache.values())
+            .chain(self.alert_cache.values());
+        MemoryUsage {
+            entries: self.cache.len() + self.arc_cache.len() + self.alert_cache.len(),
+            bytes: bitmaps.map(|bitmap| bitmap.rgba.len()).sum(),
+        }
+    }
+}
+
+const ARC_GREEN: [u8; 3] = [0x22, 0xc5, 0x5e];
+const ARC_YELLOW: [u8; 3] = [0xea, 0xb3, 0x08];
+const ARC_RED: [u8; 3] = [0xef, 0x44, 0x44];
+
+fn arc_color(percent: f32, thresholds: &NotificationThresholds) -> [u8; 3] {
+    let percent = percent as f64;
+    if percent >= thresholds.critical_percent {
+        ARC_RED
+    } else if percent >= thresholds.warning_percent {
+        ARC_YELLOW
+    } else {
+        ARC_GREEN
+    }
+}
+
+/// Draws a faint ring in the track color with the used fraction stroked on
+/// top, clockwise from twelve o'clock
+fn draw_arc(
+    size: u32,
+    percent: f32,
+    track: [u8; 3],
+    thresholds: &NotificationThresholds,
+) -> Bitmap {
+    let size = size.max(1);
+    let mut pixmap = Pixmap::new(size, size).expect("icon size is non-zero");
+
+    let edge = size as f32;
+    let stroke = Stroke {
+        width: (edge * 0.16).max(1.5),
+        line_cap: LineCap::Round,
+        ..Stroke::default()
+    };
+    let center = edge / 2.0;
+    let radius = (edge - stroke.width) / 2.0 - 0.5;
+
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Paint::default()
+    };
+
+    let [r, g, b] = track;
+    paint.set_color_rgba8(r, g, b, 0x60);
+    if let Some(track) = PathBuilder::from_circle(center, center, radius) {
+        pixmap.stroke_path(&track, &paint, &stroke, Transform::identity(), None);
+    }
+
+    let sweep = TAU * (percent / 100.0).clamp(0.0, 1.0);
+    if sweep > 0.0 {
+        let [r, g, b] = arc_color(percent, thresholds);
+        paint.set_color_rgba8(r, g, b, 0xff);
+        if let Some(arc) = arc_path(center, center, radius, sweep) {
+            pixmap.stroke_path(&arc, &paint, &stroke, Transform::identity(), None);
+        }
+    }
+
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    Bitmap::new(rgba, size, size)
+}
+
+/// Draws a red disc with a white exclamation mark, visible on light and
+/// dark surfaces alike
+fn draw_alert(size: u32) -> Bitmap {
+    let size = size.max(1);
+    let mut pixmap = Pixmap::new(size, size).expect("icon size is non-zero");
+
+    let edge = size as f32;
+    let center = edge / 2.0;
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Paint::default()
+    };
+
+    let [r, g, b] = ARC_RED;
+    paint.set_color_rgba8(r, g, b, 0xff);
+    if let Some(disc) = PathBuilder::from_circle(center, center, edge / 2.0 - 0.5) {
+        pixmap.fill_path(
+            &disc,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+
+    paint.set_color_rgba8(0xff, 0xff, 0xff, 0xff);
+    let stroke = Stroke {
+        width: (edge * 0.14).max(1.5),
+        line_cap: LineCap::Round,
+        ..Stroke::default()
+    };
+    let mut bar = PathBuilder::new();
+    bar.move_to(center, edge * 0.24);
+    bar.line_to(center, edge * 0.56);
+    if let Some(bar) = bar.finish() {
+        pixmap.stroke_path(&bar, &paint, &stroke, Transform::identity(), None);
+    }
+    if let Some(dot) = PathBuilder::from_circle(center, edge * 0.75, stroke.width * 0.6) {
+        pixmap.fill_path(&dot, &paint, FillRule::Winding, Transform::identity(), None);
+    }
+
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    Bitmap::new(rgba, size, size)
+}
+
+/// Builds a clockwise arc starting at twelve o'clock out of cubic segments
+/// of at most a quarter turn each
+fn arc_path(cx: f32, cy: f32, radius: f32, sweep: f32) -> Option<tiny_skia::Path> {
+    let point = |angle: f32| (cx + radius * angle.cos(), cy + radius * angle.sin());
+
+    let segments = (sweep / FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = sweep / segments as f32;
+    let k = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+
+    let mut angle = -FRAC_PI_2;
+    let mut pb = PathBuilder::new();
+    let (x, y) = point(angle);
+    pb.move_to(x, y);
+
+    for _ in 0..segments {
+        let next = angle + step;
+        let (x0, y0) = point(angle);
+        let (x1, y1) = point(next);
+        pb.cubic_to(
+            x0 - k * angle.sin(),
+            y0 + k * angle.cos(),
+            x1 + k * next.sin(),
+            y1 - k * next.cos(),
+            x1,
+            y1,
+        );
+        angle = next;
+    }
+
+    pb.finish()
+}
+
+const BADGE_RING: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const BADGE_BACKGROUND: [u8; 4] = [0x30, 0x30, 0x30, 0xff];
+
+fn badge_color(bucket: u8) -> [u8; 4] {
+    match bucket {
+        0..=49 => [0x22, 0xc5, 0x5e, 0xff],  // green
+        50..=74 => [0xea, 0xb3, 0x08, 0xff], // yellow
+        75..=99 => [0xf9, 0x73, 0x16, 0xff], // orange
+        _ => [0xef, 0x44, 0x44, 0xff],       // red
+    }
+}
+
+/// Draws a pie badge in the bottom-right corner, filled clockwise from
+/// twelve o'clock in proportion to the bucket
+fn draw_badge(bitmap: &mut Bitmap, bucket: u8) {
+    let size = bitmap.width.min(bitmap.height) as f64;
+    let radius = (size * 0.22).max(3.0);
+    let cx = bitmap.width as f64 - radius - 1.0;
+    let cy = bitmap.height as f64 - radius - 1.0;
+    let fraction = bucket as f64 / 100.0;
+    let color = badge_color(bucket);
+
+    let min_x = (cx - radius - 1.0).floor().max(0.0) as u32;
+    let min_y = (cy - radius - 1.0).floor().max(0.0) as u32;
+
+    for y in min_y..bitmap.height {
+        for x in min_x..bitmap.width {
+            let dx = x as f64 + 0.5 - cx;
+            let dy = y as f64 + 0.5 - cy;
+            let distance = (dx * dx + dy * dy).sqrt();
+
+            if distance <= radius {
+                // Angle measured clockwise from twelve o'clock, in [0, 1)
+                let angle = dx.atan2(-dy).rem_euclid(std::f64::consts::TAU)
+                    / std::f64::consts::TAU;
+                let fill = if angle < fraction {
+                    color
+                } else {
+                    BADGE_BACKGROUND
+                };
+                bitmap.set_pixel(x, y, fill);
+            } else if distance <= radius + 1.0 {
+                bitmap.set_pixel(x, y, BADGE_RING);
+            }
+        }
+    }
+}
+
+/// Draws a red dot with a white ring in the top-right corner
+fn draw_attention_dot(bitmap: &mut Bitmap) {
+    let size = bitmap.width.min(bitmap.height) as f64;
+    let radius = (size * 0.16).max(2.0);
+    let cx = bitmap.width as f64 - radius - 1.0;
+    let cy = radius + 1.0;
+    let [r, g, b] = ARC_RED;
+
+    let min_x = (cx - radius - 1.0).floor().max(0.0) as u32;
+    let max_y = ((cy + radius + 1.0).ceil() as u32).min(bitmap.height);
+
+    for y in 0..max_y {
+        for x in min_x..bitmap.width {
+            let dx = x as f64 + 0.5 - cx;
+            let dy = y as f64 + 0.5 - cy;
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance <= radius {
+                bitmap.set_pixel(x, y, [r, g, b, 0xff]);
+            } else if distance <= radius + 1.0 {
+                bitmap.set_pixel(x, y, BADGE_RING);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Bitmap {
+        Bitmap::new(vec![0; 32 * 32 * 4], 32, 32)
+    }
+
+    fn pixel(bitmap: &Bitmap, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * bitmap.width + x) * 4) as usize;
+        bitmap.rgba[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_usage_bucket() {
+        assert_eq!(usage_bucket(-5.0), 0);
+        assert_eq!(usage_bucket(25.0), 25);
+        assert_eq!(usage_bucket(99.9), 75);
+        assert_eq!(usage_bucket(150.0), 100);
+    }
+
+    #[test]
+    fn test_theme_tints_glyph() {
+        let opaque = Bitmap::new([0x80, 0x40, 0x20, 0xff].repeat(32 * 32), 32, 32);
+        let mut renderer = IconRenderer::new(opaque);
+
+        assert_eq!(pixel(renderer.render(IconTheme::Dark, 0), 0, 0), [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(pixel(renderer.render(IconTheme::Light, 0), 0, 0), [0x1f, 0x1f, 0x1f, 0xff]);
+    }
+
+    #[test]
+    fn test_tint_keeps_alpha() {
+        let tinted = base().tinted([0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&tinted, 0, 0), [0xff, 0xff, 0xff, 0]);
+    }
+
+    #[test]
+    fn test_badge_draws_bottom_right() {
+        let mut renderer = IconRenderer::new(base());
+        let full = renderer.render(IconTheme::Dark, 100).clone();
+
+        // Top-left untouched, badge centre filled red
+        assert_eq!(pixel(&full, 0, 0), [0xff, 0xff, 0xff, 0]);
+        assert_eq!(pixel(&full, 24, 24), badge_color(100));
+    }
+
+    #[test]
+    fn test_badge_fill_follows_bucket() {
+        let mut renderer = IconRenderer::new(base());
+        let quarter = renderer.render(IconTheme::Dark, 25).clone();
+
+        // Upper-right quadrant of the badge is filled, lower-left is not
+        assert_eq!(pixel(&quarter, 26, 22), badge_color(25));
+        assert_eq!(pixel(&quarter, 22, 26), BADGE_BACKGROUND);
+    }
+
+    #[test]
+    fn test_attention_dot_draws_top_right() {
+        let dotted = base().with_attention_dot();
+        assert_eq!(
+            pixel(&dotted, 26, 5),
+            [ARC_RED[0], ARC_RED[1], ARC_RED[2], 0xff]
+        );
+        assert_eq!(pixel(&dotted, 5, 26), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_native_icon_size_scales() {
+        assert_eq!(native_icon_size(2.0), native_icon_size(1.0) * 2);
+        assert_eq!(native_icon_size(0.5), native_icon_size(1.0));
+    }
+
+    #[test]
+    fn test_arc_color_follows_thresholds() {
+        let thresholds = NotificationThresholds::default();
+        assert_eq!(arc_color(50.0, &thresholds), ARC_GREEN);
+        assert_eq!(arc_color(85.0, &thresholds), ARC_YELLOW);
+        assert_eq!(arc_color(95.0, &thresholds), ARC_RED);
+    }
+
+    #[test]
+    fn test_render_arc() {
+        let mut renderer = IconRenderer::new(base());
+        let quarter = renderer.render_arc(IconTheme::Dark, 32, 25.0, None).clone();
+        assert_eq!((quarter.width, quarter.height), (32, 32));
+
+        // Right side of the ring is in the used arc, left side only in the track
+        let right = pixel(&quarter, 29, 16);
+        let left = pixel(&quarter, 2, 16);
+        assert_eq!(right, [ARC_GREEN[0], ARC_GREEN[1], ARC_GREEN[2], 0xff]);
+        assert_eq!(&left[..3], &[0xff, 0xff, 0xff]);
+        assert!(left[3] > 0 && left[3] < 0xff);
+
+        // Centre stays transparent
+        assert_eq!(pixel(&quarter, 16, 16)[3], 0);
+    }
+
+    #[test]
+    fn test_render_arc_brand_track() {
+        let mut renderer = IconRenderer::new(base());
+        let arc = renderer.render_arc(IconTheme::Dark, 32, 25.0, Some([0xd9, 0x77, 0x06]));
+
+        // The track is translucent, so unpremultiplying may round each channel
+        let left = pixel(arc, 2, 16);
+        for (channel, expected) in left[..3].iter().zip([0xd9u8, 0x77, 0x06]) {
+            assert!(channel.abs_diff(expected) <= 2, "{left:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#d97706"), Some([0xd9, 0x77, 0x06]));
+        assert_eq!(parse_hex_color("d97706"), None);
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#zz7706"), None);
+    }
+
+    #[test]
+    fn test_render_arc_uses_custom_thresholds() {
+        let mut renderer =
+            IconRenderer::new(base()).with_thresholds(NotificationThresholds::new(20.0, 30.0));
+        let arc = renderer.render_arc(IconTheme::Dark, 32, 25.0, None);
+        assert_eq!(&pixel(arc, 29, 16)[..3], &ARC_YELLOW);
+    }
+
+    #[test]
+    fn test_render_alert() {
+        let mut renderer = IconRenderer::new(base());
+        let alert = renderer.render_alert(32).clone();
+        assert_eq!((alert.width, alert.height), (32, 32));
+
+        // Red disc, white mark in the middle, transparent corners
+        assert_eq!(
+            pixel(&alert, 8, 16),
+            [ARC_RED[0], ARC_RED[1], ARC_RED[2], 0xff]
+        );
+        assert_eq!(pixel(&alert, 16, 12), [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&alert, 0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_alert_blink() {
+        let mut blink = AlertBlink::default();
+        assert!(!blink.set_critical(false));
+        assert_eq!(blink.tick(), None);
+
+        // Turning critical starts the timer once, on the alert frame
+        assert!(blink.set_critical(true));
+        assert!(blink.shows_alert());
+        assert!(!blink.set_critical(true));
+        assert_eq!(blink.tick(), Some(false));
+        assert_eq!(blink.tick(), Some(true));
+
+        // Acknowledged until usage drops
+        blink.acknowledge();
+        assert!(!blink.shows_alert());
+        assert_eq!(blink.tick(), None);
+        assert!(!blink.set_critical(true));
+
+        blink.set_critical(false);
+        assert!(blink.set_critical(true));
+    }
+
+    #[test]
+    fn test_renderer_caches_variants() {
+        let mut renderer = IconRenderer::new(base());
+        renderer.render(IconTheme::Dark, 50);
+        renderer.render(IconTheme::Dark, 50);
+        renderer.render(IconTheme::Light, 50);
+        renderer.render(IconTheme::Dark, 75);
+        assert_eq!(renderer.cache.len(), 3);
+
+        renderer.render_arc(IconTheme::Dark, 32, 40.2, None);
+        renderer.render_arc(IconTheme::Dark, 32, 39.8, None);
+        assert_eq!(renderer.arc_cache.len(), 1);
+        renderer.render_arc(IconTheme::Dark, 32, 40.0, Some([0xd9, 0x77, 0x06]));
+        assert_eq!(renderer.arc_cache.len(), 2);
+        assert_eq!(renderer.memory().entries, 5);
+    }
+
+    #[test]
+    fn test_renderer_cache_is_bounded() {
+        let mut renderer = IconRenderer::new(base());
+        for size in 1..=ICON_CACHE_CAPACITY as u32 {
+            renderer.render_alert(size);
+        }
+        assert_eq!(renderer.alert_cache.len(), ICON_CACHE_CAPACITY);
+
+        renderer.render_alert(1);
+        assert_eq!(renderer.alert_cache.len(), ICON_CACHE_CAPACITY);
+        renderer.render_alert(ICON_CACHE_CAPACITY as u32 + 1);
+        assert_eq!(renderer.alert_cache.len(), 1);
+    }
+}
@@ -0,0 +1,27 @@
+//! System tray integration
+//!
+//! Builds the tray icon, positions the popup window next to it, and keeps
+//! the usage indicators in sync with refreshes: the menu-bar title on macOS
+//! and a usage badge drawn into the icon on Windows. The icon is recolored
+//! to match the light or dark theme of the menu bar or taskbar, or replaced
+//! by a rendered usage arc when that style is selected. While any provider
+//! is critical the icon blinks until it is clicked or usage drops, and a
+//! red dot marks the icon while anything needs attention.
+//! Right-clicking opens a menu with the dashboard window.
+//!
+//! Icon rendering and title text don't depend on Tauri; the tray itself is
+//! only built with the `desktop` feature.
+
+#[cfg(feature = "desktop")]
+mod desktop;
+mod icon;
+mod title;
+
+#[cfg(feature = "desktop")]
+pub use desktop::{create, TrayState, TRAY_ID};
+
+pub use icon::{
+    native_icon_size, parse_hex_color, usage_bucket, AlertBlink, Bitmap, IconRenderer, IconTheme,
+    TrayIconStyle, ALERT_BLINK_INTERVAL, ICON_CACHE_CAPACITY,
+};
+pub use title::{title_text, TrayTitleSource};
@@ -0,0 +1,140 @@
+//! Menu-bar title text
+//!
+//! Computes the percentage shown next to the tray icon. Only macOS renders
+//! tray titles, but the computation is platform-independent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::providers::{UsageSnapshot, WindowSlot};
+
+/// What the tray title displays
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayTitleSource {
+    /// No title, icon only
+    #[default]
+    Off,
+    /// The aggregate usage view
+    Aggregate,
+    /// Usage of a single provider
+    Provider(String),
+}
+
+/// Returns the tray title for the given source, or `None` to hide it
+///
+/// A single provider shows its headline window from `headlines`, or its
+/// highest one.
+pub fn title_text(
+    source: &TrayTitleSource,
+    snapshots: &HashMap<String, UsageSnapshot>,
+    aggregate: Option<&UsageSnapshot>,
+    headlines: &HashMap<String, WindowSlot>,
+) -> Option<String> {
+    let percent = match source {
+        TrayTitleSource::Off => return None,
+        TrayTitleSource::Aggregate => aggregate?.max_usage(),
+        TrayTitleSource::Provider(id) => snapshots
+            .get(id)?
+            .headline_usage(headlines.get(id).copied()),
+    };
+
+    Some(format!("{:.0}%", percent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::RateWindow;
+
+    fn snapshots() -> HashMap<String, UsageSnapshot> {
+        let mut map = HashMap::new();
+        map.insert(
+            "claude".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(42.4)),
+        );
+        map.insert(
+            "openai".to_string(),
+            UsageSnapshot::new().with_primary(RateWindow::new(71.6)),
+        );
+        map
+    }
+
+    #[test]
+    fn test_title_off() {
+        assert_eq!(
+            title_text(&TrayTitleSource::Off, &snapshots(), None, &HashMap::new()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_title_aggregate() {
+        let aggregate = UsageSnapshot::new().with_primary(RateWindow::new(71.6));
+        assert_eq!(
+            title_text(
+                &TrayTitleSource::Aggregate,
+                &snapshots(),
+                Some(&aggregate),
+                &HashMap::new()
+            ),
+            Some("72%".to_string())
+        );
+        assert_eq!(
+            title_text(
+                &TrayTitleSource::Aggregate,
+                &snapshots(),
+                None,
+                &HashMap::new()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_title_provider() {
+        let none = HashMap::new();
+        let source = TrayTitleSource::Provider("claude".into());
+        assert_eq!(
+            title_text(&source, &snapshots(), None, &none),
+            Some("42%".to_string())
+        );
+
+        let missing = TrayTitleSource::Provider("gemini".into());
+        assert_eq!(title_text(&missing, &snapshots(), None, &none), None);
+    }
+
+    #[test]
+    fn test_title_provider_headline_window() {
+        let mut snapshots = snapshots();
+        snapshots.insert(
+            "claude".to_string(),
+            UsageSnapshot::new()
+                .with_primary(RateWindow::new(42.4))
+                .with_secondary(RateWindow::new(12.0)),
+        );
+        let source = TrayTitleSource::Provider("claude".into());
+        assert_eq!(
+            title_text(&source, &snapshots, None, &HashMap::new()),
+            Some("42%".to_string())
+        );
+
+        let headlines = HashMap::from([("claude".to_string(), WindowSlot::Secondary)]);
+        assert_eq!(
+            title_text(&source, &snapshots, None, &headlines),
+            Some("12%".to_string())
+        );
+    }
+
+    #[test]
+    fn test_title_source_serialization() {
+        assert_eq!(
+            serde_json::to_string(&TrayTitleSource::Aggregate).unwrap(),
+            "\"aggregate\""
+        );
+        assert_eq!(
+            serde_json::to_string(&TrayTitleSource::Provider("claude".into())).unwrap(),
+            "{\"provider\":\"claude\"}"
+        );
+    }
+}
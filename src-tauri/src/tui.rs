@@ -0,0 +1,335 @@
+//! Terminal UI
+//!
+//! `gptbar tui` shows usage in the terminal for users without a desktop,
+//! e.g. over SSH: a gauge per rate window with its reset countdown, and a
+//! sparkline of the last day of history for each provider.
+//!
+//! It builds the same [`AppState`] from the same config as the tray app but
+//! only starts the refresh agent, so it can run next to a tray instance
+//! without competing for the IPC socket or REST API port.
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ratatui::backend::Backend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::agents::{Agent, NotificationThresholds};
+use crate::config::AppConfig;
+use crate::events::{self, AppEvent};
+use crate::format::{self, ResetStyle};
+use crate::history::HistoryEntry;
+use crate::i18n;
+use crate::providers::{RateWindow, UsageSnapshot, WindowSlot};
+use crate::AppState;
+
+/// How long to wait for a key before redrawing the countdowns
+const TICK: Duration = Duration::from_secs(1);
+
+/// Hours of history shown in each sparkline
+const SPARKLINE_HOURS: i64 = 24;
+
+/// One provider's row
+struct ProviderView {
+    name: String,
+    snapshot: Option<UsageSnapshot>,
+    /// Highest window usage of each recorded snapshot, oldest first
+    history: Vec<u64>,
+}
+
+/// Everything drawn in one frame
+struct Screen {
+    providers: Vec<ProviderView>,
+    next_refresh: Option<DateTime<Utc>>,
+    thresholds: NotificationThresholds,
+    reset_style: ResetStyle,
+}
+
+/// Runs the terminal UI until the user quits
+pub fn run() -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+
+    runtime.block_on(async {
+        let state = AppState::new().await;
+        state
+            .agent_manager
+            .start_agent("refresh")
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let result = match ratatui::try_init() {
+            Ok(mut terminal) => {
+                let result = event_loop(&mut terminal, &state).await;
+                ratatui::restore();
+                result
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = state.agent_manager.stop_all().await {
+            tracing::warn!("Failed to stop agents: {}", e);
+        }
+        result.map_err(|e| e.to_string())
+    })
+}
+
+/// Redraws every tick and on new snapshots; `q` or Esc quits and `r`
+/// refreshes now
+async fn event_loop<B: Backend>(terminal: &mut Terminal<B>, state: &AppState) -> io::Result<()> {
+    let config = AppConfig::load();
+    let names: HashMap<String, String> = state
+        .registry
+        .metadata()
+        .into_iter()
+        .map(|m| (m.id, m.name))
+        .collect();
+    let mut updates = events::subscribe();
+    let mut histories: HashMap<String, Vec<u64>> = HashMap::new();
+    let mut stale = true;
+
+    loop {
+        loop {
+            match updates.try_recv() {
+                Ok(AppEvent::SnapshotUpdated(_)) | Err(TryRecvError::Lagged(_)) => stale = true,
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+
+        let mut snapshots = state.refresh.get_all_snapshots().await;
+        // Enabled providers in display order, then any others being
+        // refreshed, such as the generated one in demo mode
+        let mut ids = config.active_providers();
+        let mut others: Vec<String> = snapshots
+            .keys()
+            .filter(|id| !ids.contains(id))
+            .cloned()
+            .collect();
+        others.sort();
+        ids.extend(others);
+
+        if stale {
+            histories = ids
+                .iter()
+                .map(|id| {
+                    let entries = state
+                        .history
+                        .recent(Some(id), SPARKLINE_HOURS)
+                        .unwrap_or_default();
+                    (id.clone(), sparkline_data(&entries))
+                })
+                .collect();
+            stale = false;
+        }
+
+        let screen = Screen {
+            providers: ids
+                .iter()
+                .map(|id| ProviderView {
+                    name: names.get(id).cloned().unwrap_or_else(|| id.clone()),
+                    snapshot: snapshots.remove(id),
+                    history: histories.get(id).cloned().unwrap_or_default(),
+                })
+                .collect(),
+            next_refresh: state.refresh.next_run(),
+            thresholds: state.notification.thresholds(),
+            reset_style: config.reset_style,
+        };
+        terminal.draw(|frame| draw(frame, &screen))?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('r') => {
+                        let refresh = state.refresh.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = refresh.trigger().await {
+                                tracing::warn!("Refresh failed: {}", e);
+                            }
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, screen: &Screen) {
+    let heights = screen.providers.iter().map(|p| {
+        let windows = p
+            .snapshot
+            .as_ref()
+            .map_or(1, |s| s.windows().count().max(1));
+        // Borders, one line per window and the sparkline
+        Constraint::Length(windows as u16 + 4)
+    });
+    let areas = Layout::vertical(
+        std::iter::once(Constraint::Length(1))
+            .chain(heights)
+            .chain(std::iter::once(Constraint::Min(0))),
+    )
+    .split(frame.area());
+
+    let next = screen.next_refresh.map_or_else(
+        || "-".to_string(),
+        |at| format::format_reset(at, ResetStyle::Relative),
+    );
+    frame.render_widget(
+        Paragraph::new(i18n::t_args("tui.header", &[("next", &next)])),
+        areas[0],
+    );
+
+    for (provider, area) in screen.providers.iter().zip(&areas[1..]) {
+        draw_provider(frame, provider, screen, *area);
+    }
+}
+
+fn draw_provider(frame: &mut Frame, provider: &ProviderView, screen: &Screen, area: Rect) {
+    let block = Block::bordered().title(provider.name.as_str());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let Some(snapshot) = &provider.snapshot else {
+        frame.render_widget(Paragraph::new(i18n::t("tui.no_data")), inner);
+        return;
+    };
+
+    let windows: Vec<_> = snapshot.windows().collect();
+    let rows = Layout::vertical(
+        windows
+            .iter()
+            .map(|_| Constraint::Length(1))
+            .chain([Constraint::Length(2)]),
+    )
+    .split(inner);
+
+    for ((slot, window), row) in windows.iter().zip(rows.iter()) {
+        let color = usage_color(window.used_percent, &screen.thresholds);
+        frame.render_widget(
+            Gauge::default()
+                .gauge_style(Style::default().fg(color))
+                .ratio((window.used_percent / 100.0).clamp(0.0, 1.0))
+                .label(window_label(*slot, window, screen.reset_style)),
+            *row,
+        );
+    }
+
+    frame.render_widget(
+        Sparkline::default()
+            .data(&provider.history)
+            .max(100)
+            .style(Style::default().fg(Color::Blue)),
+        rows[windows.len()],
+    );
+}
+
+/// Gauge text, e.g. "5h session limit 42% · in 2h 5m"
+fn window_label(slot: WindowSlot, window: &RateWindow, style: ResetStyle) -> String {
+    let name = window
+        .reset_description
+        .clone()
+        .unwrap_or_else(|| format!("{:?}", slot));
+    let mut label = format!("{} {:.0}%", name, window.used_percent);
+    if let Some(resets_at) = window.resets_at {
+        label.push_str(" · ");
+        label.push_str(&format::format_reset(resets_at, style));
+    }
+    label
+}
+
+/// Green below the warning threshold, yellow below critical, red above
+fn usage_color(percent: f64, thresholds: &NotificationThresholds) -> Color {
+    if percent >= thresholds.critical_percent {
+        Color::Red
+    } else if percent >= thresholds.warning_percent {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
+
+/// Highest window usage of each entry, as sparkline bars out of 100
+fn sparkline_data(entries: &[HistoryEntry]) -> Vec<u64> {
+    entries
+        .iter()
+        .map(|e| e.snapshot.max_usage().clamp(0.0, 100.0).round() as u64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn snapshot(percent: f64) -> UsageSnapshot {
+        UsageSnapshot::new().with_primary(RateWindow::new(percent))
+    }
+
+    #[test]
+    fn test_usage_color() {
+        let thresholds = NotificationThresholds::new(50.0, 80.0);
+        assert_eq!(usage_color(10.0, &thresholds), Color::Green);
+        assert_eq!(usage_color(50.0, &thresholds), Color::Yellow);
+        assert_eq!(usage_color(95.0, &thresholds), Color::Red);
+    }
+
+    #[test]
+    fn test_sparkline_data_clamps() {
+        let entries: Vec<_> = [12.4, 150.0, -3.0]
+            .into_iter()
+            .map(|percent| HistoryEntry {
+                provider_id: "claude".to_string(),
+                snapshot: snapshot(percent),
+            })
+            .collect();
+        assert_eq!(sparkline_data(&entries), vec![12, 100, 0]);
+    }
+
+    #[test]
+    fn test_draw_shows_providers() {
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        let screen = Screen {
+            providers: vec![
+                ProviderView {
+                    name: "Claude".to_string(),
+                    snapshot: Some(snapshot(42.0)),
+                    history: vec![10, 20, 42],
+                },
+                ProviderView {
+                    name: "OpenAI".to_string(),
+                    snapshot: None,
+                    history: Vec::new(),
+                },
+            ],
+            next_refresh: None,
+            thresholds: NotificationThresholds::default(),
+            reset_style: ResetStyle::Relative,
+        };
+
+        terminal.draw(|frame| draw(frame, &screen)).unwrap();
+
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("Claude"));
+        assert!(text.contains("Primary 42%"));
+        assert!(text.contains("OpenAI"));
+        assert!(text.contains(&i18n::t("tui.no_data")));
+    }
+}
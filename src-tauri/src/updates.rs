@@ -0,0 +1,252 @@
+//! Version info and update checks
+//!
+//! With `check_for_updates` on, [`AppVersionInfo::current`] asks GitHub for
+//! the latest release at most every few hours, so the settings pane can show
+//! "Update available" and the changelog without the frontend calling GitHub
+//! itself. Nothing is downloaded or installed.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// The latest published release of GPTBar
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/episuarez/gptBar/releases/latest";
+
+/// How long a fetched release is reused
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The running build
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// When this binary was built, set by `build.rs`
+pub fn build_date() -> Option<DateTime<Utc>> {
+    option_env!("GPTBAR_BUILD_TIMESTAMP")
+        .and_then(|secs| secs.parse().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+/// A published release
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Release {
+    /// Version without the tag's `v` prefix
+    pub version: String,
+    pub name: Option<String>,
+    /// Release notes, in Markdown
+    pub changelog: String,
+    /// Release page
+    pub url: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// GitHub's release JSON, the fields used
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    name: Option<String>,
+    body: Option<String>,
+    html_url: String,
+    published_at: Option<DateTime<Utc>>,
+}
+
+impl From<GitHubRelease> for Release {
+    fn from(release: GitHubRelease) -> Self {
+        Self {
+            version: release.tag_name.trim_start_matches('v').to_string(),
+            name: release.name.filter(|name| !name.is_empty()),
+            changelog: release.body.unwrap_or_default(),
+            url: release.html_url,
+            published_at: release.published_at,
+        }
+    }
+}
+
+/// Whether `latest` is a newer version than `current`
+///
+/// Versions that don't parse as semver are never newer.
+///
+/// # Examples
+///
+/// ```
+/// use gptbar_lib::updates::is_newer;
+///
+/// assert!(is_newer("0.2.0", "0.1.9"));
+/// assert!(is_newer("1.0.0", "1.0.0-beta.2"));
+/// assert!(!is_newer("0.1.0", "0.1.0"));
+/// assert!(!is_newer("nightly", "0.1.0"));
+/// ```
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (
+        semver::Version::parse(latest),
+        semver::Version::parse(current),
+    ) {
+        (Ok(latest), Ok(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Fetches the latest release, reusing it for [`CHECK_INTERVAL`]
+pub struct UpdateChecker {
+    client: Client,
+    cached: Mutex<Option<(Instant, Release)>>,
+}
+
+impl UpdateChecker {
+    /// Creates a checker with nothing fetched yet
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The latest release, fetched when the cached one is too old
+    pub async fn latest(&self) -> Result<Release, String> {
+        if let Some((fetched_at, release)) = &*self.lock() {
+            if fetched_at.elapsed() < CHECK_INTERVAL {
+                return Ok(release.clone());
+            }
+        }
+
+        let release = self.fetch().await?;
+        *self.lock() = Some((Instant::now(), release.clone()));
+        Ok(release)
+    }
+
+    async fn fetch(&self) -> Result<Release, String> {
+        let response = self
+            .client
+            .get(LATEST_RELEASE_URL)
+            // Required by the GitHub API
+            .header(reqwest::header::USER_AGENT, format!("GPTBar/{}", VERSION))
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .map_err(|e| format!("Update check failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Update check failed: HTTP {}", response.status()));
+        }
+        let release: GitHubRelease = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid release data: {}", e))?;
+        Ok(release.into())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<(Instant, Release)>> {
+        self.cached.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for UpdateChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn checker() -> &'static UpdateChecker {
+    static CHECKER: OnceLock<UpdateChecker> = OnceLock::new();
+    CHECKER.get_or_init(UpdateChecker::new)
+}
+
+/// The running version and, when checks are on, the latest release
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppVersionInfo {
+    pub version: String,
+    pub build_date: Option<DateTime<Utc>>,
+    /// Whether update checks are on
+    pub check_for_updates: bool,
+    /// Latest release, when checked successfully
+    pub latest: Option<Release>,
+    /// Whether `latest` is newer than the running version
+    pub update_available: bool,
+    /// Why the check failed, if it did
+    pub error: Option<String>,
+}
+
+impl AppVersionInfo {
+    /// Describes the running build, checking for updates if `check` is set
+    pub async fn current(check: bool) -> Self {
+        let latest = if check {
+            Some(checker().latest().await)
+        } else {
+            None
+        };
+        Self::new(VERSION, check, latest)
+    }
+
+    fn new(version: &str, check: bool, latest: Option<Result<Release, String>>) -> Self {
+        let (latest, error) = match latest {
+            Some(Ok(release)) => (Some(release), None),
+            Some(Err(e)) => (None, Some(e)),
+            None => (None, None),
+        };
+        Self {
+            version: version.to_string(),
+            build_date: build_date(),
+            check_for_updates: check,
+            update_available: latest
+                .as_ref()
+                .is_some_and(|release| is_newer(&release.version, version)),
+            latest,
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str) -> Release {
+        Release {
+            version: version.to_string(),
+            name: None,
+            changelog: "- Fixes".to_string(),
+            url: "https://github.com/episuarez/gptBar/releases/tag/v0.2.0".to_string(),
+            published_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_github_release() {
+        let json = r###"{
+            "tag_name": "v0.2.0",
+            "name": "",
+            "body": "## Changes\n- Fixes",
+            "html_url": "https://github.com/episuarez/gptBar/releases/tag/v0.2.0",
+            "published_at": "2026-03-01T10:00:00Z",
+            "assets": []
+        }"###;
+        let release: Release = serde_json::from_str::<GitHubRelease>(json).unwrap().into();
+        assert_eq!(release.version, "0.2.0");
+        assert_eq!(release.name, None);
+        assert_eq!(release.changelog, "## Changes\n- Fixes");
+        assert!(release.published_at.is_some());
+    }
+
+    #[test]
+    fn test_update_available() {
+        let info = AppVersionInfo::new("0.1.0", true, Some(Ok(release("0.2.0"))));
+        assert!(info.update_available);
+        assert!(info.error.is_none());
+
+        let info = AppVersionInfo::new("0.2.0", true, Some(Ok(release("0.2.0"))));
+        assert!(!info.update_available);
+
+        let info = AppVersionInfo::new("0.1.0", true, Some(Err("offline".to_string())));
+        assert!(!info.update_available);
+        assert_eq!(info.error.as_deref(), Some("offline"));
+
+        let info = AppVersionInfo::new("0.1.0", false, None);
+        assert!(!info.check_for_updates);
+        assert!(info.latest.is_none());
+    }
+
+    #[test]
+    fn test_build_date_is_set() {
+        assert!(build_date().is_some());
+    }
+}
@@ -0,0 +1,360 @@
+//! Popup window behavior
+//!
+//! Tracks whether the popup is pinned open, which turns off hiding it when
+//! it loses focus, and remembers the size it was resized to and where a
+//! pinned popup was moved to. Restored geometry is clamped to the monitor
+//! the popup opens on.
+//!
+//! Also opens the larger dashboard window with the full usage history.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tauri::window::{Effect, EffectState, EffectsBuilder};
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder,
+};
+
+use crate::config::{
+    AppConfig, AppearanceSettings, WindowEffect, WindowPosition, WindowSettings, WindowSize,
+};
+use crate::i18n;
+
+/// Label of the dashboard window
+pub const DASHBOARD_LABEL: &str = "dashboard";
+
+/// Runtime state of the popup window
+pub struct PopupState {
+    pinned: AtomicBool,
+    position: Mutex<Option<WindowPosition>>,
+    size: Mutex<Option<WindowSize>>,
+}
+
+impl PopupState {
+    /// Creates the state from the saved window settings
+    pub fn new(settings: &WindowSettings) -> Self {
+        Self {
+            pinned: AtomicBool::new(settings.pinned),
+            position: Mutex::new(settings.position),
+            size: Mutex::new(settings.size),
+        }
+    }
+
+    /// Whether the popup stays open when it loses focus
+    pub fn is_pinned(&self) -> bool {
+        self.pinned.load(Ordering::SeqCst)
+    }
+
+    /// Pins or unpins the popup
+    pub fn set_pinned(&self, pinned: bool) {
+        self.pinned.store(pinned, Ordering::SeqCst);
+    }
+
+    /// Records where the popup was moved to; ignored while unpinned, since
+    /// an unpinned popup is always placed next to the tray icon
+    pub fn moved(&self, x: i32, y: i32) {
+        if self.is_pinned() {
+            *self.position.lock().unwrap_or_else(|e| e.into_inner()) =
+                Some(WindowPosition { x, y });
+        }
+    }
+
+    /// Records the size the popup was resized to; minimizing reports a zero
+    /// size, which is ignored
+    pub fn resized(&self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        *self.size.lock().unwrap_or_else(|e| e.into_inner()) = Some(WindowSize { width, height });
+    }
+
+    /// The remembered popup size, if it was ever resized
+    pub fn size(&self) -> Option<WindowSize> {
+        *self.size.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Where to show the popup, if it is pinned and has been moved
+    pub fn pinned_position(&self) -> Option<WindowPosition> {
+        if !self.is_pinned() {
+            return None;
+        }
+        *self.position.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current settings, for saving
+    pub fn settings(&self) -> WindowSettings {
+        WindowSettings {
+            pinned: self.is_pinned(),
+            position: *self.position.lock().unwrap_or_else(|e| e.into_inner()),
+            size: self.size(),
+        }
+    }
+
+    /// Saves the pinned flag and geometry to the config file
+    pub fn save(&self) -> Result<(), String> {
+        let mut config = AppConfig::load();
+        let settings = self.settings();
+        if config.window == settings {
+            return Ok(());
+        }
+        config.window = settings;
+        config.save()
+    }
+}
+
+impl Default for PopupState {
+    fn default() -> Self {
+        Self::new(&WindowSettings::default())
+    }
+}
+
+/// Moves and shrinks a window so it lies within an area, such as a
+/// monitor's work area
+pub fn clamp_to_area(
+    position: WindowPosition,
+    size: WindowSize,
+    area_position: WindowPosition,
+    area_size: WindowSize,
+) -> (WindowPosition, WindowSize) {
+    let size = WindowSize {
+        width: size.width.min(area_size.width),
+        height: size.height.min(area_size.height),
+    };
+    let max_x = area_position.x + (area_size.width - size.width) as i32;
+    let max_y = area_position.y + (area_size.height - size.height) as i32;
+    let position = WindowPosition {
+        x: position.x.clamp(area_position.x, max_x),
+        y: position.y.clamp(area_position.y, max_y),
+    };
+    (position, size)
+}
+
+/// Shows the window at `position`, clamped to the monitor containing that
+/// point (or the window's current monitor if none does)
+pub fn place(window: &WebviewWindow, position: WindowPosition) {
+    let Ok(size) = window.outer_size() else {
+        return;
+    };
+    let size = WindowSize {
+        width: size.width,
+        height: size.height,
+    };
+
+    let monitor = window
+        .available_monitors()
+        .ok()
+        .and_then(|monitors| {
+            monitors.into_iter().find(|m| {
+                let area = m.work_area();
+                position.x >= area.position.x
+                    && position.y >= area.position.y
+                    && position.x < area.position.x + area.size.width as i32
+                    && position.y < area.position.y + area.size.height as i32
+            })
+        })
+        .or_else(|| window.current_monitor().ok().flatten());
+
+    let (position, clamped) = match monitor {
+        Some(monitor) => {
+            let area = monitor.work_area();
+            clamp_to_area(
+                position,
+                size,
+                WindowPosition {
+                    x: area.position.x,
+                    y: area.position.y,
+                },
+                WindowSize {
+                    width: area.size.width,
+                    height: area.size.height,
+                },
+            )
+        }
+        None => (position, size),
+    };
+
+    if clamped != size {
+        let _ = window.set_size(PhysicalSize::new(clamped.width, clamped.height));
+    }
+    let _ = window.set_position(PhysicalPosition::new(position.x, position.y));
+}
+
+/// Shows and focuses the popup away from the tray, e.g. on launch or for a
+/// deep link
+///
+/// A pinned popup reopens where it was left, otherwise it is centered.
+pub fn show_popup(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    match app.state::<std::sync::Arc<PopupState>>().pinned_position() {
+        Some(position) => place(&window, position),
+        None => {
+            let _ = window.center();
+        }
+    }
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+/// Applies the background effect of `appearance` to the popup
+///
+/// The opacity is applied by the frontend, which draws the background.
+pub fn apply_appearance(
+    window: &WebviewWindow,
+    appearance: &AppearanceSettings,
+) -> tauri::Result<()> {
+    let effect = match appearance.effect {
+        WindowEffect::None | WindowEffect::Transparent => None,
+        WindowEffect::Acrylic => Some(Effect::Acrylic),
+        WindowEffect::Mica => Some(Effect::Mica),
+        WindowEffect::Vibrancy => Some(Effect::Popover),
+    };
+    // Keep the effect while the pinned popup is unfocused
+    window.set_effects(effect.map(|effect| {
+        EffectsBuilder::new()
+            .effect(effect)
+            .state(EffectState::Active)
+            .build()
+    }))
+}
+
+/// Shows the dashboard window, creating it on first use
+pub fn open_dashboard(app: &AppHandle) -> tauri::Result<()> {
+    let window = match app.get_webview_window(DASHBOARD_LABEL) {
+        Some(window) => window,
+        None => {
+            WebviewWindowBuilder::new(app, DASHBOARD_LABEL, WebviewUrl::App("dashboard".into()))
+                .title(i18n::t("dashboard.title"))
+                .inner_size(900.0, 640.0)
+                .min_inner_size(600.0, 400.0)
+                .center()
+                .build()?
+        }
+    };
+    window.unminimize()?;
+    window.show()?;
+    window.set_focus()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpinned_popup_ignores_moves() {
+        let popup = PopupState::default();
+        popup.moved(100, 200);
+
+        assert!(!popup.is_pinned());
+        assert_eq!(popup.pinned_position(), None);
+        assert_eq!(popup.settings().position, None);
+    }
+
+    #[test]
+    fn test_pinned_popup_remembers_position() {
+        let popup = PopupState::default();
+        popup.set_pinned(true);
+        popup.moved(100, 200);
+
+        assert_eq!(
+            popup.pinned_position(),
+            Some(WindowPosition { x: 100, y: 200 })
+        );
+
+        // The position is kept for the next time it is pinned
+        popup.set_pinned(false);
+        assert_eq!(popup.pinned_position(), None);
+        assert_eq!(
+            popup.settings().position,
+            Some(WindowPosition { x: 100, y: 200 })
+        );
+    }
+
+    #[test]
+    fn test_restores_saved_settings() {
+        let popup = PopupState::new(&WindowSettings {
+            pinned: true,
+            position: Some(WindowPosition { x: 5, y: 6 }),
+            size: Some(WindowSize {
+                width: 400,
+                height: 600,
+            }),
+        });
+
+        assert!(popup.is_pinned());
+        assert_eq!(popup.pinned_position(), Some(WindowPosition { x: 5, y: 6 }));
+        assert_eq!(
+            popup.size(),
+            Some(WindowSize {
+                width: 400,
+                height: 600
+            })
+        );
+    }
+
+    #[test]
+    fn test_resized_is_remembered_when_unpinned() {
+        let popup = PopupState::default();
+        popup.resized(350, 640);
+
+        assert_eq!(
+            popup.settings().size,
+            Some(WindowSize {
+                width: 350,
+                height: 640
+            })
+        );
+    }
+
+    const AREA_POSITION: WindowPosition = WindowPosition { x: 0, y: 0 };
+    const AREA_SIZE: WindowSize = WindowSize {
+        width: 1920,
+        height: 1040,
+    };
+    const POPUP: WindowSize = WindowSize {
+        width: 300,
+        height: 520,
+    };
+
+    #[test]
+    fn test_clamp_keeps_window_inside_area() {
+        let (position, size) = clamp_to_area(
+            WindowPosition { x: 100, y: 100 },
+            POPUP,
+            AREA_POSITION,
+            AREA_SIZE,
+        );
+        assert_eq!(position, WindowPosition { x: 100, y: 100 });
+        assert_eq!(size, POPUP);
+    }
+
+    #[test]
+    fn test_clamp_moves_offscreen_window_back() {
+        // Saved on a monitor that has since been disconnected
+        let (position, _) = clamp_to_area(
+            WindowPosition { x: 3000, y: -50 },
+            POPUP,
+            AREA_POSITION,
+            AREA_SIZE,
+        );
+        assert_eq!(position, WindowPosition { x: 1620, y: 0 });
+    }
+
+    #[test]
+    fn test_clamp_shrinks_oversized_window() {
+        let (position, size) = clamp_to_area(
+            WindowPosition { x: 10, y: 10 },
+            WindowSize {
+                width: 2500,
+                height: 520,
+            },
+            AREA_POSITION,
+            AREA_SIZE,
+        );
+        assert_eq!(position, WindowPosition { x: 0, y: 10 });
+        assert_eq!(size.width, 1920);
+    }
+}
@@ -0,0 +1,78 @@
+//! Claude provider against a mocked OAuth usage endpoint
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use gptbar_lib::providers::{ClaudeProvider, Provider, ProviderError, UsageSource};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer};
+
+const USAGE_PATH: &str = "/api/oauth/usage";
+
+async fn provider_for(server: &MockServer) -> ClaudeProvider {
+    let provider = ClaudeProvider::new_with_base_url(&server.uri());
+    provider.set_oauth_token("sk-ant-oat01-test").await;
+    provider
+}
+
+#[tokio::test]
+async fn parses_oauth_usage() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(USAGE_PATH))
+        .and(header("Authorization", "Bearer sk-ant-oat01-test"))
+        .and(header("anthropic-beta", "oauth-2025-04-20"))
+        .respond_with(common::fixture_response("claude_oauth_usage.json"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let snapshot = provider_for(&server).await.fetch().await.unwrap();
+
+    let session = snapshot.primary.unwrap();
+    assert_eq!(session.used_percent, 42.0);
+    assert_eq!(session.window_minutes, Some(300));
+    assert_eq!(
+        session.resets_at,
+        Some(Utc.with_ymd_and_hms(2025, 6, 11, 17, 0, 0).unwrap())
+    );
+
+    let weekly = snapshot.secondary.unwrap();
+    assert_eq!(weekly.used_percent, 63.5);
+    assert_eq!(weekly.window_minutes, Some(10080));
+
+    assert_eq!(snapshot.tertiary.unwrap().used_percent, 18.0);
+    assert_eq!(snapshot.source.unwrap().kind, UsageSource::OAuthApi);
+}
+
+#[tokio::test]
+async fn missing_windows_are_left_empty() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(USAGE_PATH))
+        .respond_with(
+            wiremock::ResponseTemplate::new(200)
+                .set_body_raw(r#"{"five_hour": {"utilization": 5.0}}"#, "application/json"),
+        )
+        .mount(&server)
+        .await;
+
+    let snapshot = provider_for(&server).await.fetch().await.unwrap();
+
+    assert_eq!(snapshot.primary.unwrap().used_percent, 5.0);
+    assert!(snapshot.secondary.is_none());
+    assert!(snapshot.tertiary.is_none());
+}
+
+#[tokio::test]
+async fn rejected_token_requires_login() {
+    let server = MockServer::start().await;
+    common::mount_status(&server, USAGE_PATH, 401).await;
+
+    let provider = provider_for(&server).await;
+
+    assert!(matches!(
+        provider.fetch().await,
+        Err(ProviderError::AuthRequired)
+    ));
+}
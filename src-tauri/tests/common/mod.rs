@@ -0,0 +1,44 @@
+//! Helpers shared by the provider integration tests
+//!
+//! Each test starts a wiremock server, mounts recorded API responses from
+//! `tests/fixtures` on it and points a provider at the server's URL.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Reads a recorded response body from `tests/fixtures`
+pub fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("missing fixture {}: {}", path.display(), e))
+}
+
+/// Builds a 200 response with a fixture as its JSON body
+pub fn fixture_response(name: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(fixture(name), "application/json")
+}
+
+/// Serves a fixture for `GET <route>` on the server
+pub async fn mount_fixture(server: &MockServer, route: &str, name: &str) {
+    Mock::given(method("GET"))
+        .and(path(route))
+        .respond_with(fixture_response(name))
+        .mount(server)
+        .await;
+}
+
+/// Answers `GET <route>` with an empty response of the given status
+pub async fn mount_status(server: &MockServer, route: &str, status: u16) {
+    Mock::given(method("GET"))
+        .and(path(route))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(server)
+        .await;
+}
@@ -0,0 +1,79 @@
+//! Gemini provider against a mocked models endpoint
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use gptbar_lib::i18n::{self, Language};
+use gptbar_lib::providers::{GeminiProvider, Provider, ProviderError, UsageSource};
+use tempfile::TempDir;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer};
+
+const MODELS_PATH: &str = "/v1beta/models";
+
+/// Points `HOME` at an empty directory so local Gemini CLI logs are ignored
+fn isolate_home() {
+    static HOME: OnceLock<TempDir> = OnceLock::new();
+    let home = HOME.get_or_init(|| TempDir::new().unwrap());
+    std::env::set_var("HOME", home.path());
+    std::env::set_var("USERPROFILE", home.path());
+}
+
+async fn provider_for(server: &MockServer) -> GeminiProvider {
+    isolate_home();
+    let provider = GeminiProvider::new();
+    let values = HashMap::from([("api_base_url".to_string(), server.uri())]);
+    provider.apply_settings(&values).await.unwrap();
+    provider.set_api_key("test-key").await;
+    provider
+}
+
+#[tokio::test]
+async fn reports_available_models() {
+    i18n::set_language(Language::En);
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(MODELS_PATH))
+        .and(query_param("key", "test-key"))
+        .respond_with(common::fixture_response("gemini_models.json"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let snapshot = provider_for(&server).await.fetch().await.unwrap();
+
+    let primary = snapshot.primary.unwrap();
+    assert_eq!(primary.used_percent, 0.0);
+    assert_eq!(
+        primary.reset_description.as_deref(),
+        Some("3 models available")
+    );
+    assert_eq!(snapshot.identity.unwrap().plan.as_deref(), Some("Active"));
+    assert_eq!(snapshot.source.unwrap().kind, UsageSource::ApiKey);
+}
+
+#[tokio::test]
+async fn rejected_key_fails_auth() {
+    let server = MockServer::start().await;
+    common::mount_status(&server, MODELS_PATH, 403).await;
+
+    let result = provider_for(&server).await.fetch().await;
+
+    assert!(matches!(result, Err(ProviderError::AuthFailed(_))));
+}
+
+#[tokio::test]
+async fn malformed_response_is_a_parse_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(MODELS_PATH))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw("<html>", "text/html"))
+        .mount(&server)
+        .await;
+
+    let result = provider_for(&server).await.fetch().await;
+
+    assert!(matches!(result, Err(ProviderError::Parse(_))));
+}
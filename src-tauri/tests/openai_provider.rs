@@ -0,0 +1,91 @@
+//! OpenAI provider against mocked billing endpoints
+
+mod common;
+
+use std::collections::HashMap;
+
+use gptbar_lib::providers::{OpenAIProvider, Provider, ProviderError, UsageSource};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer};
+
+const SUBSCRIPTION_PATH: &str = "/v1/dashboard/billing/subscription";
+const USAGE_PATH: &str = "/v1/dashboard/billing/usage";
+
+async fn provider_for(server: &MockServer, extra: &[(&str, &str)]) -> OpenAIProvider {
+    let provider = OpenAIProvider::new();
+    let mut values = HashMap::from([("api_base_url".to_string(), server.uri())]);
+    values.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+    provider.apply_settings(&values).await.unwrap();
+    provider.set_api_key("sk-test").await;
+    provider
+}
+
+#[tokio::test]
+async fn parses_billing_usage() {
+    let server = MockServer::start().await;
+    common::mount_fixture(&server, SUBSCRIPTION_PATH, "openai_subscription.json").await;
+    common::mount_fixture(&server, USAGE_PATH, "openai_billing_usage.json").await;
+
+    let snapshot = provider_for(&server, &[]).await.fetch().await.unwrap();
+
+    // $24.00 of a $120.00 hard limit
+    let primary = snapshot.primary.unwrap();
+    assert!((primary.used_percent - 20.0).abs() < 1e-9);
+    assert_eq!(
+        primary.reset_description.as_deref(),
+        Some("$24.00 / $120.00")
+    );
+
+    // Zero-cost line items are dropped; the rest are summed across days
+    assert_eq!(snapshot.by_model.len(), 2);
+    assert_eq!(snapshot.by_model[0].model, "GPT-4o");
+    assert_eq!(snapshot.by_model[0].cost_usd, Some(21.0));
+    assert_eq!(snapshot.by_model[1].model, "GPT-4o mini");
+
+    assert_eq!(
+        snapshot.identity.unwrap().plan.as_deref(),
+        Some("Pay-as-you-go")
+    );
+    assert_eq!(snapshot.source.unwrap().kind, UsageSource::ApiKey);
+}
+
+#[tokio::test]
+async fn scopes_requests_to_organization_and_project() {
+    let server = MockServer::start().await;
+    for (route, name) in [
+        (SUBSCRIPTION_PATH, "openai_subscription.json"),
+        (USAGE_PATH, "openai_billing_usage.json"),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .and(header("Authorization", "Bearer sk-test"))
+            .and(header("OpenAI-Organization", "org-test"))
+            .and(header("OpenAI-Project", "proj_test"))
+            .respond_with(common::fixture_response(name))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let provider = provider_for(
+        &server,
+        &[("organization_id", "org-test"), ("project_id", "proj_test")],
+    )
+    .await;
+    let snapshot = provider.fetch().await.unwrap();
+
+    assert_eq!(
+        snapshot.identity.unwrap().organization.as_deref(),
+        Some("org-test")
+    );
+}
+
+#[tokio::test]
+async fn invalid_key_fails_auth() {
+    let server = MockServer::start().await;
+    common::mount_status(&server, SUBSCRIPTION_PATH, 401).await;
+
+    let result = provider_for(&server, &[]).await.fetch().await;
+
+    assert!(matches!(result, Err(ProviderError::AuthFailed(_))));
+}
@@ -0,0 +1,89 @@
+//! Together AI provider against a mocked models endpoint
+
+mod common;
+
+use std::collections::HashMap;
+
+use gptbar_lib::i18n::{self, Language};
+use gptbar_lib::providers::{Provider, ProviderError, TogetherProvider, UsageSource};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer};
+
+const MODELS_PATH: &str = "/v1/models";
+
+async fn provider_for(server: &MockServer) -> TogetherProvider {
+    let provider = TogetherProvider::new();
+    let values = HashMap::from([("api_base_url".to_string(), server.uri())]);
+    provider.apply_settings(&values).await.unwrap();
+    provider.set_api_key("tgp_v1_test").await;
+    provider
+}
+
+#[tokio::test]
+async fn reports_rate_limit_headers() {
+    i18n::set_language(Language::En);
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(MODELS_PATH))
+        .and(header("Authorization", "Bearer tgp_v1_test"))
+        .respond_with(
+            common::fixture_response("together_models.json")
+                .insert_header("x-ratelimit-limit", "10")
+                .insert_header("x-ratelimit-remaining", "9")
+                .insert_header("x-ratelimit-reset", "1"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let snapshot = provider_for(&server).await.fetch().await.unwrap();
+
+    let primary = snapshot.primary.unwrap();
+    assert!((primary.used_percent - 10.0).abs() < 1e-9);
+    assert_eq!(
+        primary.reset_description.as_deref(),
+        Some("9/10 requests left in the rate limit")
+    );
+    assert!(primary.resets_at.is_some());
+    assert!(snapshot.secondary.is_none());
+    assert_eq!(snapshot.source.unwrap().kind, UsageSource::ApiKey);
+}
+
+#[tokio::test]
+async fn reports_no_window_without_rate_limit_headers() {
+    i18n::set_language(Language::En);
+    let server = MockServer::start().await;
+    common::mount_fixture(&server, MODELS_PATH, "together_models.json").await;
+
+    let snapshot = provider_for(&server).await.fetch().await.unwrap();
+
+    assert!(snapshot.primary.is_none());
+    assert_eq!(
+        snapshot.identity.unwrap().plan.as_deref(),
+        Some("Connected")
+    );
+}
+
+#[tokio::test]
+async fn rejected_key_fails_auth() {
+    let server = MockServer::start().await;
+    common::mount_status(&server, MODELS_PATH, 401).await;
+
+    let result = provider_for(&server).await.fetch().await;
+
+    assert!(matches!(result, Err(ProviderError::AuthFailed(_))));
+}
+
+#[tokio::test]
+async fn malformed_response_is_a_parse_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(MODELS_PATH))
+        .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw("<html>", "text/html"))
+        .mount(&server)
+        .await;
+
+    let result = provider_for(&server).await.fetch().await;
+
+    assert!(matches!(result, Err(ProviderError::Parse(_))));
+}
@@ -0,0 +1,8 @@
+import type { AppearanceSettings } from '$lib/types';
+
+// Sets how see-through the popup background is; the effect behind it is
+// applied by the backend
+export function applyAppearance(appearance: AppearanceSettings) {
+  const alpha = appearance.effect === 'none' ? 1 : appearance.opacity;
+  document.documentElement.style.setProperty('--background-alpha', String(alpha));
+}
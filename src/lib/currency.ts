@@ -0,0 +1,13 @@
+import type { Currency } from '$lib/types';
+
+// Formats an amount in USD in the display currency from `get_currency`
+export function formatCost(usd: number, currency: Currency | null): string {
+  const code = currency?.code ?? 'USD';
+  const amount = usd * (currency?.per_usd ?? 1);
+  try {
+    return new Intl.NumberFormat(undefined, { style: 'currency', currency: code }).format(amount);
+  } catch {
+    // Not an ISO 4217 code the browser knows
+    return `${amount.toFixed(2)} ${code}`;
+  }
+}
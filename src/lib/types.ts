@@ -5,6 +5,47 @@ export interface RateWindow {
   window_minutes: number | null;
   resets_at: string | null;
   reset_description: string | null;
+  delta_percent?: number;
+  /** Spend counted so far, for windows measuring spend */
+  cost_usd?: number;
+  /** Usage compared to the time elapsed in the window */
+  pace?: Pace;
+  /** `resets_at` formatted in the user's locale, timezone and reset style */
+  reset_text?: string;
+  /** Level against the provider's thresholds, named so it isn't told by color alone */
+  severity?: Severity;
+}
+
+export type Severity = 'normal' | 'warning' | 'critical';
+
+/** Accessibility settings read from the OS */
+export interface UiPreferences {
+  reduce_motion: boolean;
+  high_contrast: boolean;
+  /** Text size relative to the default */
+  font_scale: number;
+}
+
+/** What one subsystem holds in memory */
+export interface MemoryUsage {
+  entries: number;
+  /** Approximate size, not counting allocator overhead */
+  bytes: number;
+}
+
+export interface MemoryReport {
+  /** Resident memory of the whole process, when the OS reports it */
+  resident_bytes: number | null;
+  subsystems: Record<string, MemoryUsage>;
+  tracked_bytes: number;
+}
+
+export type PaceStatus = 'behind' | 'on_pace' | 'ahead';
+
+export interface Pace {
+  /** Share of the window's time elapsed (0-100) */
+  elapsed_percent: number;
+  status: PaceStatus;
 }
 
 export interface IdentitySnapshot {
@@ -13,40 +54,579 @@ export interface IdentitySnapshot {
   organization: string | null;
 }
 
+export interface TokenUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_tokens: number;
+  cache_read_tokens: number;
+  cost_usd: number;
+}
+
+export interface DailyUsage extends TokenUsage {
+  date: string;
+  project: string;
+}
+
+export interface LocalUsage {
+  daily: DailyUsage[];
+}
+
+/** Local CLI usage of one project over a range, from `get_usage_by_project` */
+export interface ProjectUsage extends TokenUsage {
+  provider_id: string;
+  project: string;
+}
+
+export interface ModelUsage {
+  model: string;
+  percent?: number;
+  tokens?: number;
+  cost_usd?: number;
+}
+
+export type UsageSource =
+  | 'oauth_api'
+  | 'api_key'
+  | 'admin_api'
+  | 'cookie'
+  | 'local_logs'
+  | 'cached'
+  | 'demo';
+
+export interface SnapshotSource {
+  kind: UsageSource;
+  fetched_at: string;
+  supplements?: UsageSource[];
+}
+
 export interface UsageSnapshot {
   primary: RateWindow | null;
   secondary: RateWindow | null;
   tertiary: RateWindow | null;
   updated_at: string;
   identity: IdentitySnapshot | null;
+  delta_seconds?: number;
+  local_usage?: LocalUsage;
+  by_model?: ModelUsage[];
+  source?: SnapshotSource;
+}
+
+// Payload of the 'usage-updated' event emitted after each refresh
+export interface UsageUpdate {
+  provider_id: string;
+  snapshot: UsageSnapshot;
+}
+
+export type ProviderErrorCode =
+  | 'auth_required'
+  | 'auth_failed'
+  | 'network'
+  | 'parse'
+  | 'cookie_extraction'
+  | 'storage'
+  | 'not_available'
+  | 'internal'
+  | 'unknown_provider'
+  | 'invalid_settings'
+  | 'viewer_mode'
+  | 'rate_limited';
+
+// Error payload returned by provider commands
+export interface ProviderErrorInfo {
+  code: ProviderErrorCode;
+  message: string;
+  retry_after_secs?: number;
+  // Provider page that helps resolve it
+  doc_url?: string;
+  // What the user can do about it
+  remediation?: string;
 }
 
 export type AuthMethod = 'OAuth' | 'Cookie' | 'Cli' | 'ApiToken' | 'None';
 
+export type SettingKind = 'text' | 'secret' | 'url' | 'number' | 'choice';
+
+// One configurable provider field, rendered generically by the settings UI
+export interface SettingField {
+  key: string;
+  label: string;
+  kind: SettingKind;
+  required: boolean;
+  placeholder?: string;
+  options?: string[];
+}
+
+export interface OrganizationInfo {
+  id: string;
+  title?: string;
+  is_default: boolean;
+}
+
+export type BrowserType = 'chrome' | 'edge' | 'firefox';
+
+export type CredentialLocation = 'env_var' | 'file' | 'keychain' | 'browser';
+
+export interface CredentialProbe {
+  location: CredentialLocation;
+  source: string;
+  found: boolean;
+  /** Masked credential, e.g. "***abcd" */
+  preview?: string;
+  note?: string;
+}
+
+export interface ProviderCredentials {
+  provider_id: string;
+  name: string;
+  probes: CredentialProbe[];
+  /** Index of the probe the provider would use */
+  selected: number | null;
+}
+
+export interface CredentialReport {
+  providers: ProviderCredentials[];
+  browsers: CredentialProbe[];
+}
+
 export interface ProviderMetadata {
   id: string;
   name: string;
   supports_login: boolean;
   auth_methods: AuthMethod[];
+  settings: SettingField[];
+  /** Pinned providers are listed and refreshed first */
+  pinned: boolean;
+  /** Brand color as `#rrggbb` */
+  brand_color: string;
+  /** Icon as SVG markup */
+  icon: string;
+  /** Web pages `open_provider_page` can open */
+  pages: ProviderPage[];
 }
 
+export type ProviderPage = 'dashboard' | 'billing' | 'api-keys' | 'docs' | 'status';
+
+/** What is known about a provider's credentials, from get_provider_states */
+export type AuthState =
+  | { status: 'unknown' }
+  | { status: 'discovered'; source: AuthMethod }
+  | { status: 'valid'; expires_at: string | null }
+  | { status: 'expired' }
+  | { status: 'missing' }
+  | { status: 'revoked' };
+
 export interface ProviderState {
   id: string;
   name: string;
   snapshot: UsageSnapshot | null;
   loading: boolean;
   error: string | null;
+  remediation?: string | null;
+  // Provider page that helps resolve the error
+  docUrl?: string | null;
   isAvailable: boolean;
 }
 
 export interface ProviderSettings {
   enabled: boolean;
   api_key?: string;
+  thresholds?: ProviderThresholds;
+  /** Windows that never trigger notifications */
+  muted_windows?: WindowSlot[];
+  /** Period spend is summed over; calendar months if unset */
+  budget_period?: BudgetPeriod;
+  /** Window shown for the provider in the tray and aggregate; its highest if unset */
+  headline_window?: WindowSlot;
+  /** Logged out; keys found outside GPTBar are ignored until one is entered */
+  logged_out?: boolean;
+}
+
+/** How a provider's spend is grouped into periods, in UTC */
+export type BudgetPeriod =
+  | { kind: 'calendar_month' }
+  | { kind: 'billing_anchor'; day: number }
+  | { kind: 'weekly' };
+
+export interface Thresholds {
+  warning_percent: number;
+  critical_percent: number;
+}
+
+export type WindowSlot = 'primary' | 'secondary' | 'tertiary';
+
+/** Overrides of the global 80/95% levels; a window's own entry wins */
+export interface ProviderThresholds {
+  all?: Thresholds;
+  windows?: Partial<Record<WindowSlot, Thresholds>>;
+}
+
+export interface MqttSettings {
+  enabled: boolean;
+  host: string;
+  port: number;
+  topic_prefix: string;
+  use_tls: boolean;
+}
+
+export interface ApiServerSettings {
+  enabled: boolean;
+  bind_address: string;
+  port: number;
+}
+
+export interface HistoryEntry {
+  provider_id: string;
+  snapshot: UsageSnapshot;
+}
+
+/** Usage aggregates of one provider over a period */
+export interface PeriodStats {
+  max_percent: number | null;
+  /** Weighted by how long each snapshot held */
+  average_percent: number | null;
+  minutes_above: number;
+  samples: number;
+}
+
+/** Today's and this week's aggregates from `get_usage_stats` */
+export interface UsageStats {
+  provider_id: string;
+  /** Usage percentage counted in `minutes_above` */
+  threshold_percent: number;
+  today: PeriodStats;
+  week: PeriodStats;
+}
+
+// Everything the dashboard window charts, returned by get_dashboard
+export interface DashboardData {
+  providers: ProviderMetadata[];
+  snapshots: Record<string, UsageSnapshot>;
+  history: HistoryEntry[];
+}
+
+export type HistoryRange = 'day' | 'week' | 'month' | 'all';
+
+export type ExportFormat = 'csv' | 'json';
+
+/** Display currency; without `rate`, it is fetched daily from the ECB */
+export interface CurrencySettings {
+  code: string;
+  rate: number | null;
+}
+
+/** Currency costs are shown in, from `get_currency` */
+export interface Currency {
+  code: string;
+  /** Units of `code` per US dollar */
+  per_usd: number;
+}
+
+/** A named group of providers, from the config file */
+export interface Profile {
+  name: string;
+  providers: string[];
 }
 
 export interface AppConfig {
   refresh_interval: number;
   start_on_login: boolean;
   enabled_providers: string[];
+  profiles?: Profile[];
+  /** Profile limiting the providers used; all enabled ones when null */
+  active_profile: string | null;
+  /** Popup order; pinned providers stay first either way */
+  provider_sort?: ProviderSort;
   provider_settings: Record<string, ProviderSettings>;
+  require_os_auth: boolean;
+  /** Read-only mode for shared machines; only set in the config file */
+  viewer_mode: boolean;
+  /** Save tokens from CLI credential files to the secure store */
+  store_discovered_tokens: boolean;
+  /** Profile folders of browsers not installed in the default place */
+  browser_data_dirs?: Partial<Record<BrowserType, string>>;
+  language: Language | null;
+  tray_title: TrayTitleSource;
+  tray_icon_style: TrayIconStyle;
+  aggregate_mode: AggregateMode;
+  aggregate_weights: Record<string, number>;
+  ipc_enabled: boolean;
+  mqtt: MqttSettings;
+  api_server: ApiServerSettings;
+  claude_local_usage: boolean;
+  demo_mode: boolean;
+  window: WindowSettings;
+  appearance: AppearanceSettings;
+  /** Open the popup at startup; `--hidden` overrides it */
+  show_window_on_launch: boolean;
+  reset_style: ResetStyle;
+  currency: CurrencySettings;
+  /** Look up the latest GitHub release when the settings are opened */
+  check_for_updates: boolean;
+  /** Send anonymous feature and error counts; off by default */
+  telemetry_enabled: boolean;
+  /** Marker file and commands for providers whose usage runs out */
+  usage_freeze: FreezeSettings;
+  /** Commands run on events; only edited in the config file */
+  hooks: HooksSettings;
+  team_sync: TeamSyncSettings;
+  /** Saved settings per agent ID, applied at startup */
+  agents?: Record<string, Record<string, unknown>>;
+}
+
+/** Usage freeze actions; only edited in the config file */
+export interface FreezeSettings {
+  /** Usage percentage at which a provider is frozen */
+  percent: number;
+  marker_file: string | null;
+  command: string | null;
+  unfreeze_command: string | null;
+}
+
+/** Events hooks can run on */
+export type HookEvent =
+  | 'threshold_crossed'
+  | 'window_reset'
+  | 'auth_lost'
+  | 'budget_period_started'
+  | 'usage_frozen'
+  | 'usage_unfrozen';
+
+/** A command run on an event, with the event as JSON on stdin */
+export interface Hook {
+  on: HookEvent;
+  command: string;
+  /** Only run for this provider */
+  provider?: string;
+}
+
+export interface HooksSettings {
+  enabled: boolean;
+  /** Seconds a command may run before it is killed */
+  timeout_secs: number;
+  hooks: Hook[];
+}
+
+/** Team sync; edited in the config file, the token with `set_team_token` */
+export interface TeamSyncSettings {
+  enabled: boolean;
+  url: string;
+  /** Name shown to teammates; anonymous when null */
+  member_name: string | null;
+  member_id: string | null;
+  /** Provider IDs shared; all when empty */
+  providers: string[];
+}
+
+export interface SharedWindow {
+  used_percent: number;
+  window_minutes: number | null;
+  resets_at: string | null;
+}
+
+/** One member's published usage */
+export interface MemberUsage {
+  id: string;
+  name: string | null;
+  updated_at: string;
+  providers: Record<string, Partial<Record<WindowSlot, SharedWindow>>>;
+}
+
+export interface CombinedWindow {
+  provider_id: string;
+  window: WindowSlot;
+  members: number;
+  average_percent: number;
+  max_percent: number;
+  total_percent: number;
+}
+
+/** Team usage from `get_team_usage` */
+export interface TeamView {
+  members: MemberUsage[];
+  combined: CombinedWindow[];
+}
+
+/** Relative ("in 2h 5m") or absolute ("today at 15:45") reset times */
+export type ResetStyle = 'relative' | 'absolute';
+
+export interface WindowPosition {
+  x: number;
+  y: number;
+}
+
+export interface WindowSize {
+  width: number;
+  height: number;
+}
+
+// Popup window behavior and remembered geometry, in physical pixels
+export interface WindowSettings {
+  /** Keep the popup open when it loses focus */
+  pinned: boolean;
+  position: WindowPosition | null;
+  size: WindowSize | null;
+}
+
+// Background effect behind the popup; unsupported ones only make it translucent
+export type WindowEffect = 'none' | 'transparent' | 'acrylic' | 'mica' | 'vibrancy';
+
+export interface AppearanceSettings {
+  effect: WindowEffect;
+  /** Background opacity while an effect is on, from 0.2 to 1 */
+  opacity: number;
+}
+
+// Everything the popup renders, returned by get_app_state
+export interface AppStateSnapshot {
+  providers: ProviderMetadata[];
+  enabled_providers: string[];
+  available: Record<string, boolean>;
+  auth_states: Record<string, AuthState>;
+  snapshots: Record<string, UsageSnapshot>;
+  /** Providers whose snapshot is older than two refresh intervals */
+  stale: string[];
+  agents: AgentInfo[];
+  config: AppConfig;
+  demo_mode: boolean;
+  /** Why credentials are kept in an encrypted file instead of the keychain */
+  credential_store_fallback: string | null;
+}
+
+export type Language = 'en' | 'es';
+
+/** Text shown next to the tray icon (macOS menu bar only) */
+export type TrayTitleSource = 'off' | 'aggregate' | { provider: string };
+
+/** How the tray icon is drawn */
+export type TrayIconStyle = 'logo' | 'arc';
+
+/** How provider usages are combined into the aggregate view */
+export type AggregateMode = 'max' | 'average';
+
+/** Saved order, or most urgent first (critical, warning, then by usage) */
+export type ProviderSort = 'configured' | 'urgency';
+
+export type NotificationLevel = 'warning' | 'critical' | 'ahead_of_pace';
+
+/** A sent usage notification from `get_notification_history` */
+export interface NotificationRecord {
+  provider_id: string;
+  level: NotificationLevel;
+  percent: number;
+  window: WindowSlot | null;
+  sent_at: string;
 }
+
+/** Per-provider fetch statistics from `get_refresh_metrics` */
+export interface FetchMetrics {
+  successes: number;
+  errors: number;
+  /** Fetches that failed in a row since the last success */
+  consecutive_errors: number;
+  last_duration_ms: number | null;
+  total_duration_ms: number;
+  last_http_status: number | null;
+  last_error: string | null;
+  last_fetch_at: string | null;
+}
+
+/** Status of a background agent from `get_agent_status` */
+export interface AgentInfo {
+  id: string;
+  name: string;
+  status: string;
+  next_refresh_at: string | null;
+  /** Current settings, in the form `set_agent_config` takes */
+  settings: Record<string, unknown> | null;
+}
+
+/** A usage notification as sent, inside an `AppEvent` */
+export interface NotificationEvent {
+  provider_id: string;
+  title: string;
+  message: string;
+  level: NotificationLevel;
+  window: WindowSlot;
+}
+
+/** A provider entered a new budget period, inside an `AppEvent` */
+export interface BudgetRollover {
+  provider_id: string;
+  start: string;
+  end: string;
+}
+
+/** A published GPTBar release */
+export interface Release {
+  version: string;
+  name: string | null;
+  /** Release notes, in Markdown */
+  changelog: string;
+  url: string;
+  published_at: string | null;
+}
+
+/** Running version and latest release, from `get_app_version_info` */
+export interface AppVersionInfo {
+  version: string;
+  build_date: string | null;
+  check_for_updates: boolean;
+  latest: Release | null;
+  update_available: boolean;
+  error: string | null;
+}
+
+/** Anonymous counters telemetry would send, from `get_telemetry_preview` */
+export interface TelemetryBatch {
+  schema: number;
+  version: string;
+  os: string;
+  arch: string;
+  date: string;
+  /** Optional features turned on in the settings */
+  settings: string[];
+  features: Record<string, number>;
+  providers: Record<string, number>;
+  /** Failed fetches by error category */
+  errors: Record<string, number>;
+}
+
+/** Panic report left by an earlier run, from `get_crash_report` */
+export interface CrashReport {
+  version: string;
+  os: string;
+  arch: string;
+  thread: string | null;
+  message: string;
+  location: string | null;
+  backtrace: string;
+  config: Record<string, unknown>;
+  crashed_at: string;
+}
+
+/** Something the user should act on, from `get_attention_items` */
+export interface AttentionItem {
+  provider_id: string;
+  reason: 'auth_expired' | 'critical_usage' | 'fetch_failing';
+  /** Last fetch error, for failing fetches */
+  detail: string | null;
+}
+
+/** Payload of the `app-event` event */
+export type AppEvent =
+  | ({ type: 'snapshot_updated' } & UsageUpdate)
+  | { type: 'fetch_failed'; provider_id: string; code: ProviderErrorCode }
+  | {
+      type: 'auth_state_changed';
+      provider_id: string;
+      state: AuthState;
+      previous: AuthState;
+      available: boolean;
+    }
+  | { type: 'agent_state_changed'; agent_id: string; running: boolean }
+  | { type: 'config_changed' }
+  | ({ type: 'notification_sent' } & NotificationEvent)
+  | ({ type: 'budget_period_started' } & BudgetRollover)
+  | { type: 'deep_link_opened'; action: 'show' | 'refresh' }
+  | { type: 'deep_link_opened'; action: 'provider'; provider_id: string }
+  | { type: 'credential_store_fallback'; reason: string };